@@ -5,9 +5,11 @@ use core_types::config::{default_config_path, load_or_create_config};
 #[cfg(not(windows))]
 use ipc::MetricsSnapshot;
 use ipc::{
-    QueryExpr, ReloadConfigRequest, RescanRequest, SearchMode, SearchRequest, SearchResponse,
-    StatusRequest, StatusResponse, TermExpr, TermModifier,
+    DiagnosticsRequest, QueryExpr, ReloadConfigRequest, RescanRequest, ResetIndexRequest,
+    SchedulerTraceRequest, SearchMode, SearchRequest, SearchResponse, StatusRequest,
+    StatusResponse, TermExpr, TermModifier, VerifyRequest,
 };
+use std::path::PathBuf;
 use uuid::Uuid;
 
 #[cfg(windows)]
@@ -73,6 +75,53 @@ enum Commands {
         json: bool,
     },
 
+    /// Write a diagnostics bundle (redacted config, index stats, log tail) for support.
+    Diagnose {
+        /// Output file for the JSON bundle.
+        #[arg(long, default_value = "ultrasearch-diagnostics.json")]
+        out: PathBuf,
+        /// Number of recent warning/error log lines to include.
+        #[arg(long, default_value_t = DiagnosticsRequest::DEFAULT_LOG_TAIL_LINES)]
+        log_lines: u32,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Wipe all indexes. Run once without --confirm to have the service log a token,
+    /// then again with --confirm <token>.
+    ResetIndex {
+        /// Confirmation token from the service log.
+        #[arg(long, default_value = "")]
+        confirm: String,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check the indexes for corruption and report whether a rebuild is recommended.
+    Verify {
+        /// DocKeys sampled from the name FST and looked up in the meta index.
+        #[arg(long, default_value_t = 1000)]
+        sample: u32,
+        /// Also verify segment file checksums (reads every index file).
+        #[arg(long)]
+        checksums: bool,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the scheduler's recent decisions: inputs, whether content ran, and why not.
+    SchedulerTrace {
+        /// Number of most recent decisions to show.
+        #[arg(long, default_value_t = 20)]
+        limit: u16,
+        /// Output as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Show or edit the config on disk (ProgramData).
     Config {
         #[command(subcommand)]
@@ -162,6 +211,97 @@ async fn main() -> Result<()> {
                 Ok(())
             })?;
         }
+        Commands::Diagnose {
+            ref out,
+            log_lines,
+            json,
+        } => {
+            let req = DiagnosticsRequest {
+                id: Uuid::new_v4(),
+                log_tail_lines: log_lines,
+            };
+            let resp = pipe(&cli).diagnostics(req).await?;
+            if resp.success {
+                std::fs::write(out, &resp.bundle_json)?;
+            }
+            output(resp, json, |r| {
+                if r.success {
+                    println!(
+                        "{} {}",
+                        style("Diagnostics written:").green(),
+                        out.display()
+                    );
+                } else {
+                    println!("{} failed", style("Diagnostics:").red());
+                }
+                if let Some(msg) = &r.message {
+                    println!("  {}", msg);
+                }
+                Ok(())
+            })?;
+        }
+        Commands::ResetIndex { ref confirm, json } => {
+            let req = ResetIndexRequest {
+                id: Uuid::new_v4(),
+                confirm_token: confirm.clone(),
+            };
+            let resp = pipe(&cli).reset_index(req).await?;
+            output(resp, json, |r| {
+                if r.success {
+                    println!("{} ok", style("Reset:").green());
+                } else {
+                    println!("{} not performed", style("Reset:").red());
+                }
+                if let Some(msg) = &r.message {
+                    println!("  {}", msg);
+                }
+                Ok(())
+            })?;
+        }
+        Commands::Verify {
+            sample,
+            checksums,
+            json,
+        } => {
+            let req = VerifyRequest {
+                id: Uuid::new_v4(),
+                sample_keys: sample,
+                checksums,
+            };
+            let resp = pipe(&cli).verify(req).await?;
+            output(resp, json, |r| {
+                if r.healthy {
+                    println!("{} ok", style("Verify:").green());
+                } else {
+                    println!("{} problems found", style("Verify:").red());
+                }
+                println!("{}", r.report_json);
+                if r.rebuild_recommended {
+                    println!(
+                        "  {}",
+                        style("Rebuild recommended: run reset-index, then rescan.").yellow()
+                    );
+                }
+                Ok(())
+            })?;
+        }
+        Commands::SchedulerTrace { limit, json } => {
+            let req = SchedulerTraceRequest {
+                id: Uuid::new_v4(),
+                limit,
+            };
+            let resp = pipe(&cli).scheduler_trace(req).await?;
+            output(resp, json, |r| {
+                if !r.enabled {
+                    println!(
+                        "{}",
+                        style("Decision trace is off (scheduler.decision_trace_len = 0).").yellow()
+                    );
+                }
+                println!("{}", r.decisions_json);
+                Ok(())
+            })?;
+        }
         Commands::Config { sub } => match sub {
             ConfigCmd::Show { json } => {
                 let path = default_config_path();
@@ -268,6 +408,23 @@ fn build_search_request(
             ModeArg::Hybrid => SearchMode::Hybrid,
         },
         timeout: timeout_ms.map(std::time::Duration::from_millis),
+        group_by: None,
+        fields: None,
+        refine: None,
+        sort: None,
+        snippets_per_hit: 1,
+        cursor: None,
+        total_bytes: false,
+        context_path: None,
+        extract_status: false,
+        anytime: false,
+        explain: false,
+        deterministic_order: false,
+        max_hits_per_dir: None,
+        result_set: false,
+        refine_set: None,
+        cancel_token: None,
+        query_text: Some(query.to_string()),
     }
 }
 
@@ -278,6 +435,14 @@ fn print_status_response(resp: &StatusResponse) -> Result<()> {
         "  Served By: {}",
         resp.served_by.as_deref().unwrap_or("unknown")
     );
+    if let Some(eta) = resp.eta_seconds {
+        println!(
+            "  Indexing ETA: {}h {:02}m {:02}s",
+            eta / 3600,
+            eta / 60 % 60,
+            eta % 60
+        );
+    }
 
     if let Some(metrics) = &resp.metrics {
         println!("{}", style("Metrics:").yellow());
@@ -292,6 +457,19 @@ fn print_status_response(resp: &StatusResponse) -> Result<()> {
         if let Some(drop) = metrics.content_dropped {
             println!("    Content Jobs Dropped: {}", drop);
         }
+        if let Some(failures) = metrics.pipe_accept_failures.filter(|f| *f > 0) {
+            println!("    IPC Accept Failures: {}", failures);
+        }
+        let trimmed = (
+            metrics.trimmed_content.unwrap_or(0),
+            metrics.trimmed_meta.unwrap_or(0),
+        );
+        if trimmed != (0, 0) {
+            println!(
+                "    Trimmed (size budget): {} content, {} metadata",
+                trimmed.0, trimmed.1
+            );
+        }
     }
 
     println!(
@@ -308,6 +486,9 @@ fn print_status_response(resp: &StatusResponse) -> Result<()> {
 }
 
 fn print_search_response(resp: &SearchResponse) -> Result<()> {
+    if let Some(err) = &resp.error {
+        println!("{}", style(format!("Query error: {err}")).red());
+    }
     println!("{}", style("Hits:").green());
     for (i, hit) in resp.hits.iter().enumerate() {
         println!(
@@ -371,6 +552,39 @@ impl StubClient {
             message: Some("stub".into()),
         })
     }
+    async fn diagnostics(&self, req: DiagnosticsRequest) -> Result<ipc::DiagnosticsResponse> {
+        Ok(ipc::DiagnosticsResponse {
+            id: req.id,
+            success: true,
+            bundle_json: serde_json::json!({ "stub": true }).to_string(),
+            message: Some("stub".into()),
+        })
+    }
+    async fn reset_index(&self, req: ResetIndexRequest) -> Result<ipc::ResetIndexResponse> {
+        Ok(ipc::ResetIndexResponse {
+            id: req.id,
+            success: false,
+            message: Some("stub".into()),
+        })
+    }
+    async fn scheduler_trace(
+        &self,
+        req: SchedulerTraceRequest,
+    ) -> Result<ipc::SchedulerTraceResponse> {
+        Ok(ipc::SchedulerTraceResponse {
+            id: req.id,
+            enabled: false,
+            decisions_json: "[]".into(),
+        })
+    }
+    async fn verify(&self, req: VerifyRequest) -> Result<ipc::VerifyResponse> {
+        Ok(ipc::VerifyResponse {
+            id: req.id,
+            healthy: true,
+            rebuild_recommended: false,
+            report_json: serde_json::json!({ "stub": true }).to_string(),
+        })
+    }
 }
 
 #[cfg(not(windows))]
@@ -386,6 +600,16 @@ async fn stub_search(req: SearchRequest) -> Result<SearchResponse> {
         truncated: false,
         took_ms: 0,
         served_by: Some("cli-linux-stub".into()),
+        grouped: None,
+        degraded_to: None,
+        next_cursor: None,
+        approximate: false,
+        total_bytes_matched: None,
+        refining: false,
+        executed_query: None,
+        error: None,
+        result_set_id: None,
+        cancelled: false,
     })
 }
 
@@ -400,6 +624,11 @@ async fn stub_status(req: StatusRequest) -> Result<StatusResponse> {
         volumes: vec![],
         last_index_commit_ts: None,
         scheduler_state: "stubbed".into(),
+        content_jobs_total: None,
+        content_jobs_remaining: None,
+        content_bytes_total: None,
+        content_bytes_remaining: None,
+        eta_seconds: None,
         metrics: Some(MetricsSnapshot {
             search_latency_ms_p50: None,
             search_latency_ms_p95: None,
@@ -409,6 +638,9 @@ async fn stub_status(req: StatusRequest) -> Result<StatusResponse> {
             active_workers: Some(0),
             content_enqueued: Some(0),
             content_dropped: Some(0),
+            pipe_accept_failures: Some(0),
+            trimmed_content: None,
+            trimmed_meta: None,
         }),
         served_by: Some("cli-linux-stub".into()),
     })
@@ -0,0 +1,135 @@
+//! Archive listing (`extract.index_archives`).
+//!
+//! Files inside a `.zip` have no MFT record, so enumeration never sees them. [`ArchiveExtractor`]
+//! reads an archive's central directory and returns its file entries, which the scanner
+//! indexes as virtual children of the archive. Only names, sizes and timestamps are read;
+//! nothing is decompressed. Archives over the size limit are skipped, and listing stops at
+//! the entry limit.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::fs::File;
+use std::path::Path;
+
+/// One file inside an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// Path within the archive, `/`-separated, e.g. `docs/report.pdf`.
+    pub path: String,
+    /// Uncompressed size.
+    pub size: u64,
+    /// Last modification as Unix seconds, when the archive records a valid one.
+    pub modified: Option<i64>,
+}
+
+impl ArchiveEntry {
+    /// Final path component.
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// Lists the entries of `.zip` archives within size and entry-count limits.
+#[derive(Debug, Clone, Copy)]
+pub struct ArchiveExtractor {
+    max_bytes: u64,
+    max_entries: usize,
+}
+
+impl ArchiveExtractor {
+    pub fn new(max_bytes: u64, max_entries: usize) -> Self {
+        Self {
+            max_bytes,
+            max_entries,
+        }
+    }
+
+    /// Whether `path` names an archive format this extractor reads.
+    pub fn supports(path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+    }
+
+    /// File entries of the archive at `path`, in archive order and at most `max_entries` of
+    /// them. Directories are left out. Empty when the archive is larger than `max_bytes`.
+    pub fn entries(&self, path: &Path) -> Result<Vec<ArchiveEntry>> {
+        let file = File::open(path)?;
+        if file.metadata()?.len() > self.max_bytes {
+            return Ok(Vec::new());
+        }
+        let mut archive = zip::ZipArchive::new(file)?;
+        let mut entries = Vec::new();
+        for i in 0..archive.len() {
+            if entries.len() >= self.max_entries {
+                break;
+            }
+            let entry = archive.by_index_raw(i)?;
+            let name = entry.name().replace('\\', "/");
+            let name = name.trim_matches('/');
+            if entry.is_dir() || name.is_empty() {
+                continue;
+            }
+            entries.push(ArchiveEntry {
+                path: name.to_string(),
+                size: entry.size(),
+                modified: entry.last_modified().and_then(|t| {
+                    NaiveDate::from_ymd_opt(t.year().into(), t.month().into(), t.day().into())?
+                        .and_hms_opt(t.hour().into(), t.minute().into(), t.second().into())
+                        .map(|dt| dt.and_utc().timestamp())
+                }),
+            });
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    #[test]
+    fn zip_entries_are_listed_within_limits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("backup.ZIP");
+        let mut zip = zip::ZipWriter::new(File::create(&path).unwrap());
+        let opts = SimpleFileOptions::default()
+            .last_modified_time(zip::DateTime::from_date_and_time(2024, 3, 1, 12, 0, 0).unwrap());
+        zip.add_directory("docs/", opts).unwrap();
+        zip.start_file("docs/report.pdf", opts).unwrap();
+        zip.write_all(b"%PDF-1.7").unwrap();
+        zip.start_file("notes.txt", opts).unwrap();
+        zip.write_all(b"hello").unwrap();
+        zip.finish().unwrap();
+
+        assert!(ArchiveExtractor::supports(&path));
+        let entries = ArchiveExtractor::new(u64::MAX, 100).entries(&path).unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                ArchiveEntry {
+                    path: "docs/report.pdf".into(),
+                    size: 8,
+                    modified: Some(1_709_294_400),
+                },
+                ArchiveEntry {
+                    path: "notes.txt".into(),
+                    size: 5,
+                    modified: Some(1_709_294_400),
+                },
+            ]
+        );
+        assert_eq!(entries[0].name(), "report.pdf");
+
+        let capped = ArchiveExtractor::new(u64::MAX, 1).entries(&path).unwrap();
+        assert_eq!(capped.len(), 1);
+        assert!(
+            ArchiveExtractor::new(10, 100)
+                .entries(&path)
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
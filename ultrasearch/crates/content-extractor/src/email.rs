@@ -0,0 +1,547 @@
+//! Email extraction (`extract.index_email`).
+//!
+//! An `.eml` file holds one RFC 5322 message; an `.mbox` holds many, each introduced by a
+//! `From ` separator line. [`EmailExtractor`] splits and parses them, emitting every
+//! message's subject and body as the document's text and its subject, senders, recipients
+//! and date as [`EmailHeaders`], which the content index keeps as separate fields. Bodies are
+//! decoded from quoted-printable or base64. Of a multipart message the `text/plain` parts are
+//! used, or `text/html` with the tags removed when there are none; attachments are skipped.
+//! Encoded-word headers (`=?UTF-8?B?...?=`) are decoded. UTF-8 and Latin-1 charsets are
+//! honoured, and anything else is read as UTF-8.
+
+use crate::{
+    ExtractContext, ExtractError, ExtractedContent, Extractor, enforce_limits_str, read_file,
+};
+use chrono::DateTime;
+use core_types::{DocKey, EmailHeaders};
+use std::fs;
+use std::path::Path;
+
+/// One parsed message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EmailMessage {
+    pub subject: Option<String>,
+    pub from: Vec<String>,
+    /// `To` and `Cc` recipients.
+    pub to: Vec<String>,
+    /// `Date` as Unix seconds.
+    pub sent: Option<i64>,
+    pub body: String,
+}
+
+impl EmailMessage {
+    /// Subject, then body.
+    pub fn to_text(&self) -> String {
+        match &self.subject {
+            Some(subject) => format!("{subject}\n\n{}", self.body),
+            None => self.body.clone(),
+        }
+    }
+}
+
+/// Indexes `.eml` and `.mbox` files as email. Enabled with
+/// [`ExtractorStack::with_email`](crate::ExtractorStack::with_email).
+pub struct EmailExtractor;
+
+impl Extractor for EmailExtractor {
+    fn name(&self) -> &'static str {
+        "email"
+    }
+
+    fn supports(&self, ctx: &ExtractContext) -> bool {
+        matches!(crate::resolve_ext(ctx).as_deref(), Some("eml" | "mbox"))
+    }
+
+    fn extract(&self, ctx: &ExtractContext, key: DocKey) -> Result<ExtractedContent, ExtractError> {
+        let path = Path::new(ctx.path);
+        let len = fs::metadata(path)
+            .map_err(|e| ExtractError::Failed(e.to_string()))?
+            .len();
+        let max_bytes = ctx.max_bytes as u64;
+        if len > max_bytes {
+            return Err(ExtractError::FileTooLarge {
+                bytes: len,
+                max_bytes,
+            });
+        }
+        let data = read_file(path)?;
+        let messages: Vec<EmailMessage> = if crate::resolve_ext(ctx).as_deref() == Some("mbox") {
+            split_mbox(&data).iter().map(|m| parse_message(m)).collect()
+        } else {
+            vec![parse_message(&data)]
+        };
+
+        let mut headers = EmailHeaders::default();
+        let mut texts = Vec::with_capacity(messages.len());
+        for message in messages {
+            texts.push(message.to_text());
+            headers.subject.extend(message.subject);
+            headers.from.extend(message.from);
+            headers.to.extend(message.to);
+            headers.sent.extend(message.sent);
+        }
+        let (text, truncated, _) = enforce_limits_str(&texts.join("\n\n"), ctx);
+        Ok(ExtractedContent {
+            key,
+            text,
+            lang: None,
+            truncated,
+            content_lang: None,
+            bytes_processed: data.len(),
+            mime: None,
+            email: Some(headers),
+            title: None,
+        })
+    }
+}
+
+/// The messages of an mbox, without their `From ` separator lines. A separator starts the
+/// file or follows an empty line; `>From ` quoting in bodies (mboxrd) is undone.
+pub fn split_mbox(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut messages = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+    let mut previous_blank = true;
+    for line in data.split_inclusive(|&b| b == b'\n') {
+        if previous_blank && line.starts_with(b"From ") {
+            messages.extend(current.take());
+            current = Some(Vec::new());
+            previous_blank = false;
+            continue;
+        }
+        previous_blank = trim_eol(line).is_empty();
+        let Some(message) = current.as_mut() else {
+            continue;
+        };
+        let quoted = line.iter().take_while(|&&b| b == b'>').count();
+        if quoted > 0 && line[quoted..].starts_with(b"From ") {
+            message.extend_from_slice(&line[1..]);
+        } else {
+            message.extend_from_slice(line);
+        }
+    }
+    messages.extend(current);
+    messages
+}
+
+/// Parse one RFC 5322 message.
+pub fn parse_message(raw: &[u8]) -> EmailMessage {
+    let part = Part::parse(raw);
+    let mut plain = Vec::new();
+    let mut html = Vec::new();
+    part.collect_text(&mut plain, &mut html);
+    let body = if plain.is_empty() {
+        html.iter().map(|h| strip_html(h)).collect::<Vec<_>>()
+    } else {
+        plain
+    };
+    let addresses = |name: &str| -> Vec<String> {
+        part.headers_named(name)
+            .flat_map(|v| split_addresses(&decode_words(v)))
+            .collect()
+    };
+    EmailMessage {
+        subject: part
+            .header("subject")
+            .map(|s| decode_words(s).trim().to_string())
+            .filter(|s| !s.is_empty()),
+        from: addresses("from"),
+        to: addresses("to").into_iter().chain(addresses("cc")).collect(),
+        sent: part.header("date").and_then(parse_date),
+        body: body.join("\n\n").trim().to_string(),
+    }
+}
+
+/// A message or MIME part: unfolded headers and the raw body.
+struct Part<'a> {
+    headers: Vec<(String, String)>,
+    body: &'a [u8],
+}
+
+impl<'a> Part<'a> {
+    fn parse(raw: &'a [u8]) -> Self {
+        let mut headers: Vec<(String, String)> = Vec::new();
+        let mut pos = 0;
+        while pos < raw.len() {
+            let end = raw[pos..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(raw.len(), |i| pos + i + 1);
+            let line = trim_eol(&raw[pos..end]);
+            pos = end;
+            if line.is_empty() {
+                break;
+            }
+            let line = String::from_utf8_lossy(line);
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            } else if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+        Self {
+            headers,
+            body: &raw[pos..],
+        }
+    }
+
+    fn headers_named<'s>(&'s self, name: &'s str) -> impl Iterator<Item = &'s str> {
+        self.headers
+            .iter()
+            .filter(move |(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Lowercased MIME type (default `text/plain`) and the header's parameters.
+    fn content_type(&self) -> (String, &str) {
+        let value = self.header("content-type").unwrap_or("text/plain");
+        let (mime, params) = value.split_once(';').unwrap_or((value, ""));
+        (mime.trim().to_ascii_lowercase(), params)
+    }
+
+    fn collect_text(&self, plain: &mut Vec<String>, html: &mut Vec<String>) {
+        let is_attachment = self.header("content-disposition").is_some_and(|d| {
+            d.trim_start()
+                .to_ascii_lowercase()
+                .starts_with("attachment")
+        });
+        if is_attachment {
+            return;
+        }
+        let (mime, params) = self.content_type();
+        if mime.starts_with("multipart/") {
+            if let Some(boundary) = param(params, "boundary") {
+                for raw in split_multipart(self.body, &boundary) {
+                    Part::parse(raw).collect_text(plain, html);
+                }
+            }
+            return;
+        }
+        let out = match mime.as_str() {
+            "text/plain" => plain,
+            "text/html" => html,
+            _ => return,
+        };
+        let bytes = self.decoded_body();
+        let charset = param(params, "charset").unwrap_or_default();
+        out.push(decode_charset(&bytes, &charset));
+    }
+
+    fn decoded_body(&self) -> Vec<u8> {
+        let encoding = self
+            .header("content-transfer-encoding")
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match encoding.as_str() {
+            "base64" => decode_base64(self.body),
+            "quoted-printable" => decode_quoted_printable(self.body, false),
+            _ => self.body.to_vec(),
+        }
+    }
+}
+
+/// The parts between `--boundary` delimiter lines, up to the closing `--boundary--`.
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{boundary}");
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut pos = 0;
+    while pos < body.len() {
+        let end = body[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(body.len(), |i| pos + i + 1);
+        let line = trim_eol(&body[pos..end]);
+        if let Some(rest) = line.strip_prefix(delimiter.as_bytes()) {
+            if let Some(s) = start {
+                parts.push(&body[s..pos]);
+            }
+            if rest.starts_with(b"--") {
+                return parts;
+            }
+            start = Some(end);
+        }
+        pos = end;
+    }
+    parts.extend(start.map(|s| &body[s..]));
+    parts
+}
+
+/// A `name=value` parameter of a structured header, unquoted.
+fn param(params: &str, name: &str) -> Option<String> {
+    params.split(';').find_map(|p| {
+        let (key, value) = p.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// Addresses of an address-list header, split on commas outside quotes and angle brackets.
+fn split_addresses(value: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut current = String::new();
+    let (mut quoted, mut angle) = (false, false);
+    for c in value.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            '<' if !quoted => angle = true,
+            '>' if !quoted => angle = false,
+            ',' if !quoted && !angle => {
+                out.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    out.push(current);
+    out.into_iter()
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty())
+        .collect()
+}
+
+/// RFC 2822 date, ignoring a trailing comment such as `(PST)`.
+fn parse_date(value: &str) -> Option<i64> {
+    let value = match value.find('(') {
+        Some(i) => &value[..i],
+        None => value,
+    };
+    DateTime::parse_from_rfc2822(value.trim())
+        .ok()
+        .map(|d| d.timestamp())
+}
+
+/// Decode RFC 2047 encoded words; whitespace between two encoded words is dropped.
+fn decode_words(value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = value;
+    let mut after_word = false;
+    while let Some(start) = rest.find("=?") {
+        let Some((decoded, len)) = encoded_word(&rest[start..]) else {
+            out.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            after_word = false;
+            continue;
+        };
+        let gap = &rest[..start];
+        if !(after_word && gap.trim().is_empty()) {
+            out.push_str(gap);
+        }
+        out.push_str(&decoded);
+        rest = &rest[start + len..];
+        after_word = true;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// `=?charset?B|Q?text?=` at the start of `s`: the decoded text and the word's length.
+fn encoded_word(s: &str) -> Option<(String, usize)> {
+    let inner = s.strip_prefix("=?")?;
+    let (charset, inner) = inner.split_once('?')?;
+    let (encoding, inner) = inner.split_once('?')?;
+    let end = inner.find("?=")?;
+    let text = &inner[..end];
+    let bytes = match encoding {
+        "B" | "b" => decode_base64(text.as_bytes()),
+        "Q" | "q" => decode_quoted_printable(text.as_bytes(), true),
+        _ => return None,
+    };
+    let len = 2 + charset.len() + 1 + encoding.len() + 1 + end + 2;
+    Some((decode_charset(&bytes, charset), len))
+}
+
+fn decode_charset(bytes: &[u8], charset: &str) -> String {
+    let charset = charset.to_ascii_lowercase();
+    if matches!(
+        charset.as_str(),
+        "iso-8859-1" | "latin1" | "latin-1" | "windows-1252" | "cp1252"
+    ) {
+        bytes.iter().map(|&b| b as char).collect()
+    } else {
+        String::from_utf8_lossy(bytes).into_owned()
+    }
+}
+
+/// Quoted-printable; `header` also maps `_` to a space (the Q encoding of encoded words).
+fn decode_quoted_printable(data: &[u8], header: bool) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut i = 0;
+    while i < data.len() {
+        match data[i] {
+            b'=' => {
+                let rest = &data[i + 1..];
+                if rest.starts_with(b"\r\n") {
+                    i += 3;
+                } else if rest.starts_with(b"\n") {
+                    i += 2;
+                } else if let Some(byte) = rest
+                    .get(..2)
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                {
+                    out.push(byte);
+                    i += 3;
+                } else {
+                    out.push(b'=');
+                    i += 1;
+                }
+            }
+            b'_' if header => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// Base64, skipping whitespace and stopping at padding or an invalid character.
+fn decode_base64(data: &[u8]) -> Vec<u8> {
+    let value = |c: u8| match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let (mut acc, mut bits) = (0u32, 0);
+    for &c in data.iter().filter(|c| !c.is_ascii_whitespace()) {
+        let Some(v) = value(c) else {
+            break;
+        };
+        acc = (acc << 6) | v as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    out
+}
+
+/// Text of an HTML body: tags removed, common entities decoded, whitespace collapsed.
+fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                text.push(' ');
+            }
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn trim_eol(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVOICE_EML: &str = "From: \"Alice Example\" <alice@example.com>\r\n\
+To: bob@example.com,\r\n \"Carol, Accounts\" <carol@example.com>\r\n\
+Subject: =?UTF-8?Q?Invoice_=E2=84=96?= 4711\r\n\
+Date: Thu, 1 Jun 2023 09:30:00 +0200 (CEST)\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"outer\"\r\n\
+\r\n\
+--outer\r\n\
+Content-Type: multipart/alternative; boundary=\"inner\"\r\n\
+\r\n\
+--inner\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+Please find the quarterly invoice attached. Total: 1=2C200 =E2=82=AC, due=\r\n in 30 days.\r\n\
+--inner\r\n\
+Content-Type: text/html\r\n\
+\r\n\
+<p>Please find the <b>quarterly</b> invoice attached.</p>\r\n\
+--inner--\r\n\
+--outer\r\n\
+Content-Type: application/pdf\r\n\
+Content-Disposition: attachment; filename=\"invoice.pdf\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+JVBERi0xLjQK\r\n\
+--outer--\r\n";
+
+    #[test]
+    fn eml_headers_and_body_are_extracted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("invoice.eml");
+        std::fs::write(&path, INVOICE_EML).unwrap();
+        let ctx = ExtractContext {
+            path: path.to_str().unwrap(),
+            max_bytes: 64 * 1024,
+            max_chars: 64 * 1024,
+            ext_hint: None,
+            mime_hint: None,
+            kind_hint: None,
+        };
+        assert!(EmailExtractor.supports(&ctx));
+        let out = EmailExtractor
+            .extract(&ctx, DocKey::from_parts(1, 1))
+            .unwrap();
+
+        let headers = out.email.unwrap();
+        assert_eq!(headers.subject, vec!["Invoice № 4711"]);
+        assert_eq!(headers.from, vec!["\"Alice Example\" <alice@example.com>"]);
+        assert_eq!(
+            headers.to,
+            vec!["bob@example.com", "\"Carol, Accounts\" <carol@example.com>"]
+        );
+        assert_eq!(headers.sent, vec![1_685_604_600]);
+        assert!(out.text.starts_with("Invoice № 4711\n\n"));
+        assert!(out.text.contains("Total: 1,200 €, due in 30 days."));
+        assert!(!out.text.contains("<p>"), "plain part preferred over html");
+        assert!(!out.text.contains("JVBER"), "attachments skipped");
+
+        let mbox = format!(
+            "From alice@example.com Thu Jun  1 09:30:00 2023\n{}\nFrom bob@example.com Fri Jun  2 10:00:00 2023\nSubject: Re: invoice\nFrom: bob@example.com\n\nPaid.\n>From the bank: confirmed.\n",
+            INVOICE_EML.replace("\r\n", "\n")
+        );
+        let messages: Vec<_> = split_mbox(mbox.as_bytes())
+            .iter()
+            .map(|m| parse_message(m))
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].from, headers.from);
+        assert_eq!(messages[1].subject.as_deref(), Some("Re: invoice"));
+        assert_eq!(messages[1].body, "Paid.\nFrom the bank: confirmed.");
+    }
+}
@@ -1,5 +1,7 @@
 #![cfg(windows)]
 
+pub mod sta_pool;
+
 use crate::{ExtractContext, ExtractError, ExtractedContent, Extractor, enforce_limits_str};
 use anyhow::Result;
 use core_types::DocKey;
@@ -9,18 +11,27 @@ use windows::Win32::Foundation::S_OK;
 use windows::Win32::Storage::IndexServer::{
     CHUNK_TEXT, FILTER_E_END_OF_CHUNKS, FILTER_E_NO_MORE_TEXT, IFilter, LoadIFilter, STAT_CHUNK,
 };
-use windows::Win32::System::Com::{CoInitialize, CoUninitialize};
 use windows::core::{HSTRING, Interface, PCWSTR, PWSTR};
 
-// TODO: Properly manage COM initialization. CoInitialize is thread-local.
-// A robust solution might use a dedicated STA thread pool for IFilters.
-// For this shim, we attempt to init and ignore if already inited (RPC_E_CHANGED_MODE).
+use sta_pool::StaPool;
 
-pub struct IFilterExtractor;
+/// Extracts text through the system's registered IFilters. Filters run on a bounded pool of
+/// STA threads ([`StaPool`]), never on the caller's thread.
+pub struct IFilterExtractor {
+    pool: StaPool,
+}
 
 impl IFilterExtractor {
+    /// One STA thread.
     pub fn new() -> Self {
-        Self
+        Self::with_threads(1)
+    }
+
+    /// At most `threads` concurrent IFilter extractions (`extract.max_ifilter_threads`).
+    pub fn with_threads(threads: usize) -> Self {
+        Self {
+            pool: StaPool::new(threads),
+        }
     }
 }
 
@@ -45,132 +56,131 @@ impl Extractor for IFilterExtractor {
     }
 
     fn extract(&self, ctx: &ExtractContext, key: DocKey) -> Result<ExtractedContent, ExtractError> {
-        let path = Path::new(ctx.path);
-        let path_hstring = HSTRING::from(path.as_os_str());
-
-        unsafe {
-            // Attempt init; track whether we should uninitialize.
-            let coinit_hr = CoInitialize(None);
-            let should_uninit = coinit_hr.is_ok();
-            // Defer uninit? In a thread pool, we might init once per thread.
-            // Here we are likely in a rayon thread.
-            // Ideally we should use a scope guard or just assume the thread is initialized by the runtime wrapper.
-            // But rayon threads are generic.
-            // Let's defer uninit for correctness in this scope.
-            // Actually, excessive init/uninit is slow.
-
-            // Scope guard for CoUninitialize
-            struct CoGuard(bool);
-            impl Drop for CoGuard {
-                fn drop(&mut self) {
-                    if self.0 {
-                        unsafe {
-                            CoUninitialize();
-                        }
-                    }
-                }
-            }
-            let _guard = CoGuard(should_uninit);
-
-            let mut raw_filter: *mut c_void = std::ptr::null_mut();
-            LoadIFilter(
-                PCWSTR(path_hstring.as_ptr()),
-                None,
-                &mut raw_filter as *mut *mut _,
-            )
-            .map_err(|e| ExtractError::Failed(format!("LoadIFilter failed: {e}")))?;
-
-            if raw_filter.is_null() {
-                return Err(ExtractError::Failed(
-                    "LoadIFilter returned null filter".into(),
-                ));
-            }
+        let (path, max_bytes, max_chars) = (ctx.path.to_string(), ctx.max_bytes, ctx.max_chars);
+        self.pool.run(move || {
+            let ctx = ExtractContext {
+                path: &path,
+                max_bytes,
+                max_chars,
+                ext_hint: None,
+                mime_hint: None,
+                kind_hint: None,
+            };
+            extract_with_filter(&ctx, key)
+        })
+    }
+}
 
-            // SAFETY: LoadIFilter populated raw_filter on success.
-            let filter: IFilter = IFilter::from_raw(raw_filter.cast());
-
-            // Initialize filter (canonicalize whitespace and paragraphs, index attributes).
-            let init_flags: u32 =
-                (windows::Win32::Storage::IndexServer::IFILTER_INIT_CANON_PARAGRAPHS.0
-                    | windows::Win32::Storage::IndexServer::IFILTER_INIT_CANON_SPACES.0
-                    | windows::Win32::Storage::IndexServer::IFILTER_INIT_APPLY_INDEX_ATTRIBUTES.0
-                    | windows::Win32::Storage::IndexServer::IFILTER_INIT_INDEXING_ONLY.0
-                    | windows::Win32::Storage::IndexServer::IFILTER_INIT_SEARCH_LINKS.0)
-                    as u32;
-            let mut init_flags_out: u32 = 0;
-            let init_hr = filter.Init(init_flags, &[], &mut init_flags_out);
-            if init_hr != S_OK.0 {
-                return Err(ExtractError::Failed(format!(
-                    "IFilter::Init failed with 0x{init_hr:08x}"
-                )));
-            }
+/// Run the file's IFilter on the current thread, which must be in an STA.
+fn extract_with_filter(
+    ctx: &ExtractContext,
+    key: DocKey,
+) -> Result<ExtractedContent, ExtractError> {
+    let path = Path::new(ctx.path);
+    let path_hstring = HSTRING::from(path.as_os_str());
+
+    unsafe {
+        let mut raw_filter: *mut c_void = std::ptr::null_mut();
+        LoadIFilter(
+            PCWSTR(path_hstring.as_ptr()),
+            None,
+            &mut raw_filter as *mut *mut _,
+        )
+        .map_err(|e| ExtractError::Failed(format!("LoadIFilter failed: {e}")))?;
+
+        if raw_filter.is_null() {
+            return Err(ExtractError::Failed(
+                "LoadIFilter returned null filter".into(),
+            ));
+        }
 
-            // Extract text chunks
-            let mut text = String::new();
-            let mut truncated = false;
-            let mut bytes_processed = 0;
+        // SAFETY: LoadIFilter populated raw_filter on success.
+        let filter: IFilter = IFilter::from_raw(raw_filter.cast());
+
+        // Initialize filter (canonicalize whitespace and paragraphs, index attributes).
+        let init_flags: u32 = (windows::Win32::Storage::IndexServer::IFILTER_INIT_CANON_PARAGRAPHS
+            .0
+            | windows::Win32::Storage::IndexServer::IFILTER_INIT_CANON_SPACES.0
+            | windows::Win32::Storage::IndexServer::IFILTER_INIT_APPLY_INDEX_ATTRIBUTES.0
+            | windows::Win32::Storage::IndexServer::IFILTER_INIT_INDEXING_ONLY.0
+            | windows::Win32::Storage::IndexServer::IFILTER_INIT_SEARCH_LINKS.0)
+            as u32;
+        let mut init_flags_out: u32 = 0;
+        let init_hr = filter.Init(init_flags, &[], &mut init_flags_out);
+        if init_hr != S_OK.0 {
+            return Err(ExtractError::Failed(format!(
+                "IFilter::Init failed with 0x{init_hr:08x}"
+            )));
+        }
 
-            // Stat chunk
-            // STAT_CHUNK struct.
-            // IFilter::GetChunk(&mut stat)
+        // Extract text chunks
+        let mut text = String::new();
+        let mut truncated = false;
+        let mut bytes_processed = 0;
 
-            // Loop chunks
-            // Reading text: IFilter::GetText(&mut buffer)
-            // We need a buffer.
+        // Stat chunk
+        // STAT_CHUNK struct.
+        // IFilter::GetChunk(&mut stat)
 
-            loop {
-                let mut stat = STAT_CHUNK::default();
-                let hr = filter.GetChunk(&mut stat);
-                if hr == FILTER_E_END_OF_CHUNKS.0 {
-                    break;
-                }
-                if hr != S_OK.0 {
-                    return Err(ExtractError::Failed(format!(
-                        "GetChunk failed with 0x{hr:08x}"
-                    )));
-                }
+        // Loop chunks
+        // Reading text: IFilter::GetText(&mut buffer)
+        // We need a buffer.
+
+        loop {
+            let mut stat = STAT_CHUNK::default();
+            let hr = filter.GetChunk(&mut stat);
+            if hr == FILTER_E_END_OF_CHUNKS.0 {
+                break;
+            }
+            if hr != S_OK.0 {
+                return Err(ExtractError::Failed(format!(
+                    "GetChunk failed with 0x{hr:08x}"
+                )));
+            }
 
-                if stat.flags.0 & CHUNK_TEXT.0 == CHUNK_TEXT.0 {
-                    // Read text
-                    loop {
-                        let mut buf = [0u16; 4096];
-                        let mut count = buf.len() as u32;
-                        let hr = filter.GetText(&mut count, PWSTR(buf.as_mut_ptr()));
-
-                        if hr == FILTER_E_NO_MORE_TEXT.0 {
-                            break;
-                        }
-                        if hr != S_OK.0 {
-                            break;
-                        }
-                        if count == 0 {
-                            break;
-                        }
-
-                        let chunk = String::from_utf16_lossy(&buf[..count as usize]);
-                        let (trimmed, was_trunc, used) = enforce_limits_str(&chunk, ctx);
-                        text.push_str(&trimmed);
-                        bytes_processed += used; // approximate bytes from the UTF-16 slice
-                        if was_trunc || text.len() >= ctx.max_chars {
-                            truncated = true;
-                            break;
-                        }
+            if stat.flags.0 & CHUNK_TEXT.0 == CHUNK_TEXT.0 {
+                // Read text
+                loop {
+                    let mut buf = [0u16; 4096];
+                    let mut count = buf.len() as u32;
+                    let hr = filter.GetText(&mut count, PWSTR(buf.as_mut_ptr()));
+
+                    if hr == FILTER_E_NO_MORE_TEXT.0 {
+                        break;
+                    }
+                    if hr != S_OK.0 {
+                        break;
+                    }
+                    if count == 0 {
+                        break;
                     }
-                }
 
-                if truncated {
-                    break;
+                    let chunk = String::from_utf16_lossy(&buf[..count as usize]);
+                    let (trimmed, was_trunc, used) = enforce_limits_str(&chunk, ctx);
+                    text.push_str(&trimmed);
+                    bytes_processed += used; // approximate bytes from the UTF-16 slice
+                    if was_trunc || text.len() >= ctx.max_chars {
+                        truncated = true;
+                        break;
+                    }
                 }
             }
 
-            Ok(ExtractedContent {
-                key,
-                text,
-                lang: None,
-                truncated,
-                content_lang: None,
-                bytes_processed,
-            })
+            if truncated {
+                break;
+            }
         }
+
+        Ok(ExtractedContent {
+            key,
+            text,
+            lang: None,
+            truncated,
+            content_lang: None,
+            bytes_processed,
+            mime: None,
+            email: None,
+            title: None,
+        })
     }
 }
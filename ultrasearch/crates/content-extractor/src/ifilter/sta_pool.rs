@@ -0,0 +1,158 @@
+//! Bounded pool of single-threaded-apartment (STA) threads for IFilter calls
+//! (`extract.max_ifilter_threads`).
+//!
+//! COM initialization is per thread, and some filters only work in an STA. Each pool thread
+//! enters an STA once when it starts and leaves it when the pool is dropped, so extractions
+//! neither pay for `CoInitializeEx` per call nor run on whatever apartment a caller's thread
+//! happens to be in.
+
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use windows::Win32::System::Com::{COINIT_APARTMENTTHREADED, CoInitializeEx, CoUninitialize};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Prefix of the pool threads' names.
+pub const THREAD_NAME_PREFIX: &str = "ifilter-sta-";
+
+pub struct StaPool {
+    jobs: Option<Sender<Job>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl StaPool {
+    /// Start `size` STA threads (at least one).
+    pub fn new(size: usize) -> Self {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        let threads = (0..size.max(1))
+            .map(|i| {
+                let rx = rx.clone();
+                thread::Builder::new()
+                    .name(format!("{THREAD_NAME_PREFIX}{i}"))
+                    .spawn(move || sta_thread(&rx))
+                    .expect("spawn IFilter STA thread")
+            })
+            .collect();
+        Self {
+            jobs: Some(tx),
+            threads,
+        }
+    }
+
+    /// Number of pool threads.
+    pub fn size(&self) -> usize {
+        self.threads.len()
+    }
+
+    /// Run `job` on the next free pool thread and wait for its result. A panic in `job` is
+    /// resumed on the calling thread; the pool thread survives it, as long as the build unwinds
+    /// (the release profile's `panic = "unwind"`).
+    pub fn run<T, F>(&self, job: F) -> T
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (tx, rx) = mpsc::sync_channel(1);
+        let job: Job = Box::new(move || {
+            let _ = tx.send(catch_unwind(AssertUnwindSafe(job)));
+        });
+        self.jobs
+            .as_ref()
+            .expect("pool is running")
+            .send(job)
+            .expect("IFilter STA threads exited");
+        match rx.recv().expect("IFilter STA thread dropped the job") {
+            Ok(out) => out,
+            Err(payload) => resume_unwind(payload),
+        }
+    }
+}
+
+impl Drop for StaPool {
+    fn drop(&mut self) {
+        // Closing the queue ends every thread's loop.
+        self.jobs.take();
+        for handle in self.threads.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn sta_thread(jobs: &Mutex<Receiver<Job>>) {
+    // SAFETY: called once on a thread this pool owns, balanced by `CoUninitialize` below.
+    let entered = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED) }.is_ok();
+    if !entered {
+        tracing::warn!("IFilter thread could not enter a single-threaded apartment");
+    }
+    loop {
+        let job = match jobs.lock() {
+            Ok(rx) => rx.recv(),
+            Err(poisoned) => poisoned.into_inner().recv(),
+        };
+        match job {
+            Ok(job) => job(),
+            Err(_) => break,
+        }
+    }
+    if entered {
+        // SAFETY: balances the successful `CoInitializeEx` above on this thread.
+        unsafe { CoUninitialize() };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use windows::Win32::System::Com::{APTTYPE, APTTYPE_STA, APTTYPEQUALIFIER, CoGetApartmentType};
+
+    #[test]
+    fn jobs_run_on_at_most_size_sta_threads() {
+        let pool = Arc::new(StaPool::new(2));
+        assert_eq!(pool.size(), 2);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+
+        let callers: Vec<_> = (0..8)
+            .map(|_| {
+                let (pool, running, peak) = (pool.clone(), running.clone(), peak.clone());
+                thread::spawn(move || {
+                    pool.run(move || {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        thread::sleep(Duration::from_millis(20));
+                        running.fetch_sub(1, Ordering::SeqCst);
+
+                        let (mut apt, mut qualifier) =
+                            (APTTYPE::default(), APTTYPEQUALIFIER::default());
+                        // SAFETY: both out-pointers are valid locals.
+                        unsafe { CoGetApartmentType(&mut apt, &mut qualifier) }.unwrap();
+                        (thread::current().name().map(str::to_string), apt)
+                    })
+                })
+            })
+            .collect();
+
+        let mut names = HashSet::new();
+        for caller in callers {
+            let (name, apt) = caller.join().unwrap();
+            let name = name.unwrap();
+            assert!(name.starts_with(THREAD_NAME_PREFIX), "{name}");
+            assert_eq!(apt, APTTYPE_STA);
+            names.insert(name);
+        }
+        assert!(names.len() <= 2);
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+
+        // A panicking job reaches the caller and leaves the pool usable.
+        let caught = catch_unwind(AssertUnwindSafe(|| pool.run(|| panic!("bad filter"))));
+        assert!(caught.is_err());
+        assert_eq!(pool.run(|| 7), 7);
+    }
+}
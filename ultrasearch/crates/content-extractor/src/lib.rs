@@ -5,15 +5,22 @@
 //! c00.5 we provide compile-ready scaffolding with minimal logic.
 
 use anyhow::Result;
-use core_types::DocKey;
+use core_types::filenames::{ContentKind, classify_known_filename};
+use core_types::{DocKey, EmailHeaders};
 use std::fs;
+use std::panic::{AssertUnwindSafe, catch_unwind};
 use std::path::Path;
-use tracing::instrument;
+use tracing::{instrument, warn};
 
+pub mod archive;
 pub mod component_manager;
+pub mod email;
 pub mod ifilter;
+pub mod lnk;
+pub mod mime;
 pub mod ocr;
 pub mod plugins;
+pub mod title;
 
 /// Unified extraction output.
 #[derive(Debug, Clone)]
@@ -24,6 +31,12 @@ pub struct ExtractedContent {
     pub truncated: bool,
     pub content_lang: Option<String>,
     pub bytes_processed: usize,
+    /// MIME type detected from the file's magic bytes (see [`ExtractorStack::with_mime_detection`]).
+    pub mime: Option<String>,
+    /// Header fields, when the file was parsed as email (see [`email::EmailExtractor`]).
+    pub email: Option<EmailHeaders>,
+    /// The document's own title, when it has one (see [`ExtractorStack::with_titles`]).
+    pub title: Option<String>,
 }
 
 /// Context passed to extractors (paths, limits, hints).
@@ -34,6 +47,8 @@ pub struct ExtractContext<'a> {
     pub max_chars: usize,
     pub ext_hint: Option<&'a str>,
     pub mime_hint: Option<&'a str>,
+    /// Kind of a recognized extensionless file (e.g. `Makefile`), if the caller classified it.
+    pub kind_hint: Option<ContentKind>,
 }
 
 /// Extraction error categories.
@@ -45,6 +60,17 @@ pub enum ExtractError {
     Failed(String),
     #[error("file too large (bytes={bytes}, max={max_bytes})")]
     FileTooLarge { bytes: u64, max_bytes: u64 },
+    /// Another process holds the file without sharing it; worth retrying once released.
+    #[error("file is locked by another process: {0}")]
+    Locked(String),
+    #[error("access denied: {0}")]
+    AccessDenied(String),
+    #[error("extractor {backend} panicked on {path}: {message}")]
+    Panicked {
+        backend: &'static str,
+        path: String,
+        message: String,
+    },
 }
 
 /// Trait implemented by concrete extractor backends.
@@ -54,9 +80,25 @@ pub trait Extractor {
     fn extract(&self, ctx: &ExtractContext, key: DocKey) -> Result<ExtractedContent, ExtractError>;
 }
 
+/// [`Extractor::name`] of every built-in backend, for validating configured names.
+pub const EXTRACTOR_NAMES: &[&str] = &[
+    "simple-text",
+    "extractous",
+    "lnk",
+    "email",
+    "ifilter",
+    "ocr-tesseract",
+    "noop",
+];
+
 /// Ordered stack of extractors with first-win semantics.
 pub struct ExtractorStack {
     backends: Vec<Box<dyn Extractor + Send + Sync>>,
+    isolate_panics: bool,
+    detect_mime: bool,
+    extract_titles: bool,
+    /// Backend names switched off by configuration; they never claim a file.
+    disabled: Vec<String>,
 }
 
 impl ExtractorStack {
@@ -86,7 +128,83 @@ impl ExtractorStack {
     }
 
     pub fn new(backends: Vec<Box<dyn Extractor + Send + Sync>>) -> Self {
-        Self { backends }
+        Self {
+            backends,
+            isolate_panics: true,
+            detect_mime: false,
+            extract_titles: false,
+            disabled: Vec::new(),
+        }
+    }
+
+    /// Switch off the backends named in `names` (e.g. `ifilter`), whenever they were added:
+    /// their files go to the next backend that supports them.
+    pub fn with_disabled(mut self, names: &[String]) -> Self {
+        self.disabled = names.to_vec();
+        self
+    }
+
+    /// Whether `backend` is enabled and claims the file in `ctx`.
+    fn supports(&self, backend: &(dyn Extractor + Send + Sync), ctx: &ExtractContext) -> bool {
+        !self.disabled.iter().any(|n| n == backend.name()) && backend.supports(ctx)
+    }
+
+    /// Catch backend panics and report them as `ExtractError::Panicked` (default: on). Only
+    /// works in a build that unwinds, which is why the release profile sets `panic = "unwind"`.
+    pub fn with_panic_isolation(mut self, enabled: bool) -> Self {
+        self.isolate_panics = enabled;
+        self
+    }
+
+    /// Fill [`ExtractedContent::mime`] from the file's leading bytes (default: off).
+    pub fn with_mime_detection(mut self, enabled: bool) -> Self {
+        self.detect_mime = enabled;
+        self
+    }
+
+    /// Fill [`ExtractedContent::title`] from document metadata via [`title::document_title`]
+    /// (default: off).
+    pub fn with_titles(mut self, enabled: bool) -> Self {
+        self.extract_titles = enabled;
+        self
+    }
+
+    /// Index `.lnk` shortcuts by their target path and arguments via [`lnk::LnkExtractor`],
+    /// ahead of the other backends (default: off).
+    pub fn with_shortcut_targets(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.backends.insert(0, Box::new(lnk::LnkExtractor));
+        }
+        self
+    }
+
+    /// Parse `.eml` and `.mbox` files via [`email::EmailExtractor`], ahead of the other
+    /// backends (default: off).
+    pub fn with_email(mut self, enabled: bool) -> Self {
+        if enabled {
+            self.backends.insert(0, Box::new(email::EmailExtractor));
+        }
+        self
+    }
+
+    /// Extract RTF, ODT and MSG files through the system's IFilters
+    /// ([`ifilter::IFilterExtractor`]) on at most `max_threads` STA threads, ahead of the
+    /// trailing Noop. 0 leaves them out; no-op off Windows.
+    #[cfg_attr(not(windows), allow(unused_mut, unused_variables))]
+    pub fn with_ifilter(mut self, max_threads: usize) -> Self {
+        #[cfg(windows)]
+        if max_threads > 0 {
+            let at = self
+                .backends
+                .iter()
+                .position(|b| b.name() == "noop")
+                .unwrap_or(self.backends.len());
+            self.backends.insert(
+                at,
+                Box::new(ifilter::IFilterExtractor::with_threads(max_threads)),
+            );
+        }
+        self
     }
 
     /// Run the first extractor that claims support.
@@ -98,13 +216,56 @@ impl ExtractorStack {
         }
 
         for backend in &self.backends {
-            if backend.supports(ctx) {
-                return backend.extract(ctx, key).map_err(|e| e.into());
+            if self.supports(backend.as_ref(), ctx) {
+                if !self.isolate_panics {
+                    return self.annotate(ctx, backend.extract(ctx, key)?);
+                }
+                return match catch_unwind(AssertUnwindSafe(|| backend.extract(ctx, key))) {
+                    Ok(res) => self.annotate(ctx, res?),
+                    Err(payload) => {
+                        let message = panic_message(payload.as_ref());
+                        warn!(backend = backend.name(), path = ctx.path, %message, "extractor panicked");
+                        Err(anyhow::anyhow!(ExtractError::Panicked {
+                            backend: backend.name(),
+                            path: ctx.path.to_string(),
+                            message,
+                        }))
+                    }
+                };
             }
         }
         let ext = resolve_ext(ctx).unwrap_or_else(|| "unknown".to_string());
         Err(anyhow::anyhow!(ExtractError::Unsupported(ext)))
     }
+
+    /// Add the MIME type and title a backend didn't supply, when enabled.
+    fn annotate(
+        &self,
+        ctx: &ExtractContext,
+        mut out: ExtractedContent,
+    ) -> Result<ExtractedContent> {
+        if self.detect_mime && out.mime.is_none() {
+            match mime::detect_mime_file(Path::new(ctx.path)) {
+                Ok(mime) => out.mime = mime.map(str::to_string),
+                Err(e) => warn!(path = ctx.path, error = %e, "mime detection failed"),
+            }
+        }
+        if self.extract_titles
+            && out.title.is_none()
+            && let Some(ext) = resolve_ext(ctx)
+        {
+            out.title = title::document_title(Path::new(ctx.path), &ext, ctx.max_bytes);
+        }
+        Ok(out)
+    }
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
 }
 
 /// Minimal placeholder extractor that returns empty text; used until real
@@ -129,6 +290,9 @@ impl Extractor for NoopExtractor {
             truncated,
             content_lang: None,
             bytes_processed: used,
+            mime: None,
+            email: None,
+            title: None,
         })
     }
 }
@@ -148,7 +312,13 @@ impl Extractor for SimpleTextExtractor {
                 "txt" | "log" | "md" | "json" | "jsonl" | "toml" | "rs" | "ts" | "tsx" | "csv"
             )
         } else {
-            false
+            // Extensionless: only well-known names such as `Makefile` or `LICENSE`.
+            ctx.kind_hint.is_some()
+                || Path::new(ctx.path)
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(classify_known_filename)
+                    .is_some()
         }
     }
 
@@ -163,7 +333,7 @@ impl Extractor for SimpleTextExtractor {
             });
         }
 
-        let data = fs::read(path).map_err(|e| ExtractError::Failed(e.to_string()))?;
+        let data = read_file(path)?;
         if is_probably_binary(&data) {
             return Err(ExtractError::Unsupported("binary".into()));
         }
@@ -178,10 +348,54 @@ impl Extractor for SimpleTextExtractor {
             truncated,
             content_lang: None,
             bytes_processed: used_bytes,
+            mime: None,
+            email: None,
+            title: None,
         })
     }
 }
 
+/// Read a whole file for extraction. On Windows it is opened sharing read, write and delete
+/// access, so files other processes have open (logs, databases) stay readable; a file that
+/// still can't be opened because of a lock fails with [`ExtractError::Locked`].
+pub fn read_file(path: &Path) -> Result<Vec<u8>, ExtractError> {
+    let mut options = fs::OpenOptions::new();
+    options.read(true);
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        use windows::Win32::Storage::FileSystem::{
+            FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE,
+        };
+        options.share_mode((FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE).0);
+    }
+    let mut data = Vec::new();
+    options
+        .open(path)
+        .and_then(|mut file| std::io::Read::read_to_end(&mut file, &mut data))
+        .map_err(read_error)?;
+    Ok(data)
+}
+
+/// Map a file I/O error to the extraction error that says whether retrying can help:
+/// sharing and lock violations are [`ExtractError::Locked`], missing permissions
+/// [`ExtractError::AccessDenied`].
+pub fn read_error(err: std::io::Error) -> ExtractError {
+    #[cfg(windows)]
+    {
+        use windows::Win32::Foundation::{ERROR_LOCK_VIOLATION, ERROR_SHARING_VIOLATION};
+        let code = err.raw_os_error().map(|c| c as u32);
+        if code == Some(ERROR_SHARING_VIOLATION.0) || code == Some(ERROR_LOCK_VIOLATION.0) {
+            return ExtractError::Locked(err.to_string());
+        }
+    }
+    if err.kind() == std::io::ErrorKind::PermissionDenied {
+        ExtractError::AccessDenied(err.to_string())
+    } else {
+        ExtractError::Failed(err.to_string())
+    }
+}
+
 /// Enforce both byte and char limits on an in-memory string.
 pub fn enforce_limits_str(text: &str, ctx: &ExtractContext) -> (String, bool, usize) {
     let mut bytes = 0usize;
@@ -272,7 +486,7 @@ impl Extractor for ExtractousExtractor {
         }
 
         let engine = self.build_engine(ctx);
-        let (text, _metadata) = engine
+        let (text, metadata) = engine
             .extract_file_to_string(ctx.path)
             .map_err(|e| ExtractError::Failed(e.to_string()))?;
 
@@ -287,6 +501,13 @@ impl Extractor for ExtractousExtractor {
             truncated,
             bytes_processed: byte_len.min(ctx.max_bytes),
             text,
+            mime: None,
+            email: None,
+            title: metadata
+                .get("dc:title")
+                .and_then(|v| v.first())
+                .filter(|t| !t.trim().is_empty())
+                .cloned(),
         })
     }
 }
@@ -303,6 +524,7 @@ mod tests {
             max_chars: 1024,
             ext_hint: Some("txt"),
             mime_hint: None,
+            kind_hint: None,
         };
         let stack = ExtractorStack::new(vec![Box::new(NoopExtractor)]);
         let out = stack.extract(DocKey::from_parts(1, 42), &ctx).unwrap();
@@ -320,6 +542,7 @@ mod tests {
             max_chars: 3,
             ext_hint: None,
             mime_hint: None,
+            kind_hint: None,
         };
         let (trimmed, was_truncated, used) = enforce_limits_str(s, &ctx);
         assert_eq!(trimmed, "abc");
@@ -336,6 +559,7 @@ mod tests {
             max_chars: 10,
             ext_hint: None,
             mime_hint: None,
+            kind_hint: None,
         };
         let (trimmed, truncated, used) = enforce_limits_str(s, &ctx);
         assert_eq!(trimmed, "é");
@@ -352,6 +576,7 @@ mod tests {
             max_chars: 10,
             ext_hint: None,
             mime_hint: None,
+            kind_hint: None,
         };
         let (trimmed, truncated, used) = enforce_limits_str(s, &ctx);
         assert_eq!(trimmed, "01234");
@@ -382,6 +607,7 @@ mod tests {
             max_chars: 10,
             ext_hint: Some("txt"),
             mime_hint: None,
+            kind_hint: None,
         };
         let simple = SimpleTextExtractor;
         let out = simple.extract(&ctx, DocKey::from_parts(1, 1)).unwrap();
@@ -404,6 +630,7 @@ mod tests {
             max_chars: 20,
             ext_hint: Some("txt"),
             mime_hint: None,
+            kind_hint: None,
         };
         let simple = SimpleTextExtractor;
         let err = simple.extract(&ctx, DocKey::from_parts(1, 1)).unwrap_err();
@@ -424,9 +651,40 @@ mod tests {
             max_chars: 1024,
             ext_hint: None,
             mime_hint: None,
+            kind_hint: None,
+        };
+        let simple = SimpleTextExtractor;
+        assert!(simple.supports(&ctx));
+    }
+
+    #[test]
+    fn known_extensionless_names_are_extracted_as_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Dockerfile");
+        std::fs::write(&path, b"FROM rust:latest\n").unwrap();
+        let ctx = ExtractContext {
+            path: path.to_str().unwrap(),
+            max_bytes: 1024,
+            max_chars: 1024,
+            ext_hint: None,
+            mime_hint: None,
+            kind_hint: None,
         };
         let simple = SimpleTextExtractor;
         assert!(simple.supports(&ctx));
+        let out = simple.extract(&ctx, DocKey::from_parts(1, 7)).unwrap();
+        assert!(out.text.contains("FROM rust"));
+
+        let unknown = ExtractContext {
+            path: "/tmp/notes",
+            ..ctx.clone()
+        };
+        assert!(!simple.supports(&unknown));
+        let hinted = ExtractContext {
+            kind_hint: Some(ContentKind::Code),
+            ..unknown
+        };
+        assert!(simple.supports(&hinted));
     }
 
     #[test]
@@ -437,6 +695,7 @@ mod tests {
             max_chars: 10,
             ext_hint: Some("txt"),
             mime_hint: None,
+            kind_hint: None,
         };
         assert_eq!(resolve_ext(&ctx).as_deref(), Some("txt"));
     }
@@ -449,12 +708,187 @@ mod tests {
             max_chars: 10,
             ext_hint: None,
             mime_hint: None,
+            kind_hint: None,
         };
         let stack = ExtractorStack::new(vec![]);
         let err = stack.extract(DocKey::from_parts(1, 1), &ctx).unwrap_err();
         assert!(err.to_string().contains("unsupported"));
     }
 
+    #[test]
+    fn shortcut_targets_become_content_when_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Editor.lnk");
+        let lnk = lnk::tests::fixture_lnk(
+            r"C:\Program Files\Contoso",
+            "contoso.exe",
+            "--profile work",
+            "Contoso Editor",
+        );
+        std::fs::write(&path, lnk).unwrap();
+        let ctx = ExtractContext {
+            path: path.to_str().unwrap(),
+            max_bytes: 1024,
+            max_chars: 1024,
+            ext_hint: Some("lnk"),
+            mime_hint: None,
+            kind_hint: None,
+        };
+        let key = DocKey::from_parts(1, 1);
+
+        let off = ExtractorStack::simple_only().extract(key, &ctx).unwrap();
+        assert!(off.text.is_empty());
+        let on = ExtractorStack::simple_only()
+            .with_shortcut_targets(true)
+            .extract(key, &ctx)
+            .unwrap();
+        let mut lines = on.text.lines();
+        assert_eq!(lines.next(), Some(r"C:\Program Files\Contoso\contoso.exe"));
+        assert_eq!(lines.next(), Some("--profile work"));
+        assert!(on.text.contains("Contoso Editor"));
+    }
+
+    #[test]
+    fn mime_detection_ignores_the_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.dat");
+        std::fs::write(&path, b"%PDF-1.4\n1 0 obj\n<< /Type /Catalog >>\nendobj\n").unwrap();
+        let ctx = ExtractContext {
+            path: path.to_str().unwrap(),
+            max_bytes: 1024,
+            max_chars: 1024,
+            ext_hint: Some("dat"),
+            mime_hint: None,
+            kind_hint: None,
+        };
+        let key = DocKey::from_parts(1, 1);
+
+        let off = ExtractorStack::simple_only().extract(key, &ctx).unwrap();
+        assert_eq!(off.mime, None);
+        let on = ExtractorStack::simple_only()
+            .with_mime_detection(true)
+            .extract(key, &ctx)
+            .unwrap();
+        assert_eq!(on.mime.as_deref(), Some("application/pdf"));
+    }
+
+    struct PanickyExtractor;
+
+    impl Extractor for PanickyExtractor {
+        fn name(&self) -> &'static str {
+            "panicky"
+        }
+
+        fn supports(&self, _ctx: &ExtractContext) -> bool {
+            true
+        }
+
+        fn extract(
+            &self,
+            ctx: &ExtractContext,
+            key: DocKey,
+        ) -> Result<ExtractedContent, ExtractError> {
+            if ctx.path.contains("malformed") {
+                panic!("bad xref table");
+            }
+            NoopExtractor.extract(ctx, key)
+        }
+    }
+
+    /// Stands in for the Windows-only IFilter backend.
+    struct FakeIFilter;
+
+    impl Extractor for FakeIFilter {
+        fn name(&self) -> &'static str {
+            "ifilter"
+        }
+
+        fn supports(&self, ctx: &ExtractContext) -> bool {
+            resolve_ext(ctx).as_deref() == Some("rtf")
+        }
+
+        fn extract(
+            &self,
+            ctx: &ExtractContext,
+            key: DocKey,
+        ) -> Result<ExtractedContent, ExtractError> {
+            let mut out = NoopExtractor.extract(ctx, key)?;
+            out.text = "from ifilter".into();
+            Ok(out)
+        }
+    }
+
+    #[test]
+    fn disabled_extractor_passes_its_files_to_the_next_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let notes = dir.path().join("notes.txt");
+        std::fs::write(&notes, b"plain notes").unwrap();
+        let ctx_for = |path| ExtractContext {
+            path,
+            max_bytes: 1024,
+            max_chars: 1024,
+            ext_hint: None,
+            mime_hint: None,
+            kind_hint: None,
+        };
+        let stack = || {
+            ExtractorStack::new(vec![
+                Box::new(SimpleTextExtractor),
+                Box::new(FakeIFilter),
+                Box::new(NoopExtractor),
+            ])
+        };
+        let rtf = ctx_for("memo.rtf");
+        let txt = ctx_for(notes.to_str().unwrap());
+        let key = DocKey::from_parts(1, 1);
+
+        assert_eq!(stack().extract(key, &rtf).unwrap().text, "from ifilter");
+        let stack = stack().with_disabled(&["ifilter".to_string()]);
+        assert_eq!(stack.extract(key, &rtf).unwrap().text, "", "noop takes it");
+        assert_eq!(stack.extract(key, &txt).unwrap().text, "plain notes");
+
+        let skipped = stack.with_disabled(&["ifilter".to_string(), "noop".to_string()]);
+        let err = skipped.extract(key, &rtf).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ExtractError>(),
+            Some(ExtractError::Unsupported(ext)) if ext == "rtf"
+        ));
+    }
+
+    #[test]
+    fn panicking_extractor_is_isolated_per_file() {
+        let stack = ExtractorStack::new(vec![Box::new(PanickyExtractor)]);
+        let ctx_for = |path| ExtractContext {
+            path,
+            max_bytes: 10,
+            max_chars: 10,
+            ext_hint: Some("pdf"),
+            mime_hint: None,
+            kind_hint: None,
+        };
+
+        let results: Vec<_> = ["malformed.pdf", "fine.pdf"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, path)| stack.extract(DocKey::from_parts(1, i as u64), &ctx_for(path)))
+            .collect();
+
+        let err = results[0].as_ref().unwrap_err();
+        match err.downcast_ref::<ExtractError>() {
+            Some(ExtractError::Panicked {
+                backend,
+                path,
+                message,
+            }) => {
+                assert_eq!(*backend, "panicky");
+                assert_eq!(path, "malformed.pdf");
+                assert!(message.contains("bad xref table"));
+            }
+            other => panic!("expected Panicked, got {other:?}"),
+        }
+        assert!(results[1].is_ok());
+    }
+
     #[test]
     fn with_extractous_disabled_uses_simple_only() {
         let stack = ExtractorStack::with_extractous_enabled(false);
@@ -487,6 +921,7 @@ mod tests {
             max_chars: 1024,
             ext_hint: Some("docx"),
             mime_hint: None,
+            kind_hint: None,
         };
 
         let extractor = ExtractousExtractor::new();
@@ -522,6 +957,7 @@ mod tests {
             max_chars: 2048,
             ext_hint: Some("txt"),
             mime_hint: None,
+            kind_hint: None,
         };
 
         let extractor = ExtractousExtractor::new();
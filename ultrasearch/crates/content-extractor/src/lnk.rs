@@ -0,0 +1,334 @@
+//! Windows shortcut (`.lnk`) extraction (`extract.index_shortcuts`).
+//!
+//! A shortcut's name is already searchable; what it points to is not. [`LnkExtractor`] parses
+//! the Shell Link binary format ([MS-SHLLINK]) and emits the target path, arguments, working
+//! directory, and description as the document's text, so "the shortcut to app.exe" can be
+//! found by searching for `app.exe`.
+//!
+//! [MS-SHLLINK]: https://learn.microsoft.com/openspecs/windows_protocols/ms-shllink
+
+use crate::{
+    ExtractContext, ExtractError, ExtractedContent, Extractor, enforce_limits_str, read_file,
+};
+use core_types::DocKey;
+use std::fs;
+use std::path::Path;
+
+const HEADER_SIZE: usize = 0x4C;
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+const HAS_TARGET_ID_LIST: u32 = 0x01;
+const HAS_LINK_INFO: u32 = 0x02;
+const HAS_NAME: u32 = 0x04;
+const HAS_RELATIVE_PATH: u32 = 0x08;
+const HAS_WORKING_DIR: u32 = 0x10;
+const HAS_ARGUMENTS: u32 = 0x20;
+const HAS_ICON_LOCATION: u32 = 0x40;
+const IS_UNICODE: u32 = 0x80;
+
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x01;
+const COMMON_NETWORK_RELATIVE_LINK: u32 = 0x02;
+
+/// The searchable parts of a shell link.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellLink {
+    /// Absolute target from the link info, e.g. `C:\Program Files\App\app.exe`.
+    pub target: Option<String>,
+    pub relative_path: Option<String>,
+    pub working_dir: Option<String>,
+    pub arguments: Option<String>,
+    pub description: Option<String>,
+}
+
+impl ShellLink {
+    /// Target first, then arguments, working directory, relative path, and description; one
+    /// per line.
+    pub fn to_text(&self) -> String {
+        [
+            &self.target,
+            &self.arguments,
+            &self.working_dir,
+            &self.relative_path,
+            &self.description,
+        ]
+        .into_iter()
+        .flatten()
+        .filter(|s| !s.is_empty())
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+}
+
+/// Parse a `.lnk` file's bytes.
+pub fn parse_shell_link(data: &[u8]) -> Result<ShellLink, ExtractError> {
+    let bad = |what: &str| ExtractError::Failed(format!("malformed shell link: {what}"));
+    if data.len() < HEADER_SIZE
+        || read_u32(data, 0) != Some(HEADER_SIZE as u32)
+        || data[4..20] != LINK_CLSID
+    {
+        return Err(ExtractError::Unsupported("not a shell link".into()));
+    }
+    let flags = read_u32(data, 0x14).ok_or_else(|| bad("header"))?;
+    let mut pos = HEADER_SIZE;
+    let mut link = ShellLink::default();
+
+    if flags & HAS_TARGET_ID_LIST != 0 {
+        let len = read_u16(data, pos).ok_or_else(|| bad("id list"))?;
+        pos += 2 + len as usize;
+    }
+    if flags & HAS_LINK_INFO != 0 {
+        let info_len = read_u32(data, pos).ok_or_else(|| bad("link info"))? as usize;
+        let info = data
+            .get(pos..pos + info_len)
+            .ok_or_else(|| bad("link info"))?;
+        link.target = link_info_target(info);
+        pos += info_len;
+    }
+
+    let unicode = flags & IS_UNICODE != 0;
+    let mut next_string = |present: bool| -> Result<Option<String>, ExtractError> {
+        if !present {
+            return Ok(None);
+        }
+        let (s, used) = read_string_data(data, pos, unicode).ok_or_else(|| bad("string data"))?;
+        pos += used;
+        Ok(Some(s))
+    };
+    link.description = next_string(flags & HAS_NAME != 0)?;
+    link.relative_path = next_string(flags & HAS_RELATIVE_PATH != 0)?;
+    link.working_dir = next_string(flags & HAS_WORKING_DIR != 0)?;
+    link.arguments = next_string(flags & HAS_ARGUMENTS != 0)?;
+    // Icon location is parsed only to validate the structure; it isn't useful to search.
+    next_string(flags & HAS_ICON_LOCATION != 0)?;
+    Ok(link)
+}
+
+/// Local base path (or network share) joined with the common path suffix.
+fn link_info_target(info: &[u8]) -> Option<String> {
+    let header_len = read_u32(info, 4)? as usize;
+    let flags = read_u32(info, 8)?;
+    let suffix_at = |unicode_field: usize, ansi_field: usize| -> Option<String> {
+        if header_len >= 0x24
+            && let Some(off) = read_u32(info, unicode_field).filter(|&o| o != 0)
+        {
+            return read_utf16z(info, off as usize);
+        }
+        read_ansiz(info, read_u32(info, ansi_field)? as usize)
+    };
+    let suffix = suffix_at(0x20, 0x18).unwrap_or_default();
+
+    let base = if flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+        suffix_at(0x1C, 0x10)?
+    } else if flags & COMMON_NETWORK_RELATIVE_LINK != 0 {
+        let net_at = read_u32(info, 0x14)? as usize;
+        let net = info.get(net_at..)?;
+        let name_off = read_u32(net, 8)? as usize;
+        // The Unicode name offset only exists when the structure extends past 0x14.
+        let unicode = (name_off > 0x14)
+            .then(|| read_u32(net, 0x14))
+            .flatten()
+            .filter(|&o| o != 0)
+            .and_then(|o| read_utf16z(net, o as usize));
+        unicode.or_else(|| read_ansiz(net, name_off))?
+    } else {
+        return None;
+    };
+
+    Some(match (base.is_empty(), suffix.is_empty()) {
+        (_, true) => base,
+        (true, false) => suffix,
+        (false, false) if base.ends_with('\\') => format!("{base}{suffix}"),
+        (false, false) => format!("{base}\\{suffix}"),
+    })
+}
+
+/// A counted StringData entry; returns it and the bytes consumed.
+fn read_string_data(data: &[u8], pos: usize, unicode: bool) -> Option<(String, usize)> {
+    let count = read_u16(data, pos)? as usize;
+    let start = pos + 2;
+    if unicode {
+        let bytes = data.get(start..start + count * 2)?;
+        Some((decode_utf16(bytes), 2 + count * 2))
+    } else {
+        let bytes = data.get(start..start + count)?;
+        Some((decode_ansi(bytes), 2 + count))
+    }
+}
+
+fn read_ansiz(data: &[u8], at: usize) -> Option<String> {
+    let rest = data.get(at..)?;
+    let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+    Some(decode_ansi(&rest[..end]))
+}
+
+fn read_utf16z(data: &[u8], at: usize) -> Option<String> {
+    let (units, _) = data.get(at..)?.as_chunks::<2>();
+    let end = units
+        .iter()
+        .position(|c| *c == [0, 0])
+        .unwrap_or(units.len());
+    Some(decode_utf16(units[..end].as_flattened()))
+}
+
+fn decode_utf16(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .as_chunks::<2>()
+        .0
+        .iter()
+        .map(|&c| u16::from_le_bytes(c))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// ANSI strings use the system code page; Latin-1 is exact for ASCII and close for the rest.
+fn decode_ansi(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| char::from(b)).collect()
+}
+
+fn read_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Indexes `.lnk` shortcuts by what they point to. Enabled with
+/// [`ExtractorStack::with_shortcut_targets`](crate::ExtractorStack::with_shortcut_targets).
+pub struct LnkExtractor;
+
+impl Extractor for LnkExtractor {
+    fn name(&self) -> &'static str {
+        "lnk"
+    }
+
+    fn supports(&self, ctx: &ExtractContext) -> bool {
+        crate::resolve_ext(ctx).as_deref() == Some("lnk")
+    }
+
+    fn extract(&self, ctx: &ExtractContext, key: DocKey) -> Result<ExtractedContent, ExtractError> {
+        let path = Path::new(ctx.path);
+        let len = fs::metadata(path)
+            .map_err(|e| ExtractError::Failed(e.to_string()))?
+            .len();
+        let max_bytes = ctx.max_bytes as u64;
+        if len > max_bytes {
+            return Err(ExtractError::FileTooLarge {
+                bytes: len,
+                max_bytes,
+            });
+        }
+        let data = read_file(path)?;
+        let link = parse_shell_link(&data)?;
+        let (text, truncated, _) = enforce_limits_str(&link.to_text(), ctx);
+        Ok(ExtractedContent {
+            key,
+            text,
+            lang: None,
+            truncated,
+            content_lang: None,
+            bytes_processed: data.len(),
+            mime: None,
+            email: None,
+            title: None,
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn string_data(s: &str) -> Vec<u8> {
+        let units: Vec<u16> = s.encode_utf16().collect();
+        let mut out = (units.len() as u16).to_le_bytes().to_vec();
+        out.extend(units.iter().flat_map(|u| u.to_le_bytes()));
+        out
+    }
+
+    /// A shortcut as Explorer writes one: local target in the link info, Unicode strings.
+    pub(crate) fn fixture_lnk(base: &str, suffix: &str, args: &str, desc: &str) -> Vec<u8> {
+        let flags = HAS_TARGET_ID_LIST
+            | HAS_LINK_INFO
+            | HAS_NAME
+            | HAS_WORKING_DIR
+            | HAS_ARGUMENTS
+            | IS_UNICODE;
+        let mut out = vec![0u8; HEADER_SIZE];
+        out[0..4].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        out[4..20].copy_from_slice(&LINK_CLSID);
+        out[0x14..0x18].copy_from_slice(&flags.to_le_bytes());
+
+        // An ID list we don't interpret.
+        out.extend(4u16.to_le_bytes());
+        out.extend([2, 0, 0, 0]);
+
+        let volume_id = [0x10u8, 0, 0, 0, 3, 0, 0, 0, 0, 0, 0, 0, 0x10, 0, 0, 0];
+        let header_len = 0x1Cu32;
+        let volume_at = header_len;
+        let base_at = volume_at + volume_id.len() as u32;
+        let suffix_at = base_at + base.len() as u32 + 1;
+        let info_len = suffix_at + suffix.len() as u32 + 1;
+        let mut info = Vec::new();
+        for field in [
+            info_len,
+            header_len,
+            VOLUME_ID_AND_LOCAL_BASE_PATH,
+            volume_at,
+            base_at,
+            0,
+            suffix_at,
+        ] {
+            info.extend(field.to_le_bytes());
+        }
+        info.extend(volume_id);
+        info.extend(base.as_bytes());
+        info.push(0);
+        info.extend(suffix.as_bytes());
+        info.push(0);
+        out.extend(info);
+
+        out.extend(string_data(desc));
+        out.extend(string_data(r"C:\Program Files\Contoso"));
+        out.extend(string_data(args));
+        out.extend(0u32.to_le_bytes()); // terminal extra data block
+        out
+    }
+
+    #[test]
+    fn parses_target_arguments_and_description() {
+        let data = fixture_lnk(
+            r"C:\Program Files\Contoso\",
+            "contoso.exe",
+            "--profile work",
+            "Contoso Editor",
+        );
+        let link = parse_shell_link(&data).unwrap();
+        assert_eq!(
+            link.target.as_deref(),
+            Some(r"C:\Program Files\Contoso\contoso.exe")
+        );
+        assert_eq!(link.arguments.as_deref(), Some("--profile work"));
+        assert_eq!(
+            link.working_dir.as_deref(),
+            Some(r"C:\Program Files\Contoso")
+        );
+        assert_eq!(link.description.as_deref(), Some("Contoso Editor"));
+        assert!(
+            link.to_text()
+                .starts_with(r"C:\Program Files\Contoso\contoso.exe")
+        );
+
+        assert!(matches!(
+            parse_shell_link(b"not a shortcut at all"),
+            Err(ExtractError::Unsupported(_))
+        ));
+        assert!(
+            parse_shell_link(&data[..data.len() - 12]).is_err(),
+            "truncated"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+//! MIME type detection from leading magic bytes (`extract.detect_mime`).
+//!
+//! Extensions lie (`report.dat` may well be a PDF), so the content index also records what the
+//! bytes say. Only signatures that are unambiguous at a fixed offset are recognized; plain text
+//! has no signature and yields `None`.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Bytes read from the start of a file for detection. Covers the tar header at offset 257
+/// and the first few ZIP entry names used to tell OOXML/EPUB apart from plain ZIP.
+pub const MIME_PROBE_BYTES: usize = 8 * 1024;
+
+const ZIP: &str = "application/zip";
+
+/// `(offset, signature, mime)`, checked in order.
+const SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (0, b"II*\x00", "image/tiff"),
+    (0, b"MM\x00*", "image/tiff"),
+    (0, b"PK\x03\x04", ZIP),
+    (0, b"\x1f\x8b", "application/gzip"),
+    (0, b"BZh", "application/x-bzip2"),
+    (0, b"\xfd7zXZ\x00", "application/x-xz"),
+    (0, b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (0, b"7z\xbc\xaf\x27\x1c", "application/x-7z-compressed"),
+    (0, b"Rar!\x1a\x07", "application/vnd.rar"),
+    (257, b"ustar", "application/x-tar"),
+    (
+        0,
+        b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1",
+        "application/x-ole-storage",
+    ),
+    (0, b"{\\rtf", "application/rtf"),
+    (0, b"SQLite format 3\x00", "application/vnd.sqlite3"),
+    (0, b"\x7fELF", "application/x-executable"),
+    (0, b"MZ", "application/vnd.microsoft.portable-executable"),
+    (0, b"ID3", "audio/mpeg"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"\x1a\x45\xdf\xa3", "video/x-matroska"),
+    (4, b"ftypqt", "video/quicktime"),
+    (4, b"ftypheic", "image/heic"),
+    (4, b"ftyp", "video/mp4"),
+    (0, b"wOFF", "font/woff"),
+    (0, b"wOF2", "font/woff2"),
+    (
+        0,
+        b"L\x00\x00\x00\x01\x14\x02\x00",
+        "application/x-ms-shortcut",
+    ),
+];
+
+/// RIFF containers share a header; the form type at offset 8 says what's inside.
+const RIFF_FORMS: &[(&[u8], &str)] = &[
+    (b"WEBP", "image/webp"),
+    (b"WAVE", "audio/wav"),
+    (b"AVI ", "video/x-msvideo"),
+];
+
+/// ZIP-based formats, recognized by an entry name near the start of the archive.
+const ZIP_MARKERS: &[(&[u8], &str)] = &[
+    (b"mimetypeapplication/epub+zip", "application/epub+zip"),
+    (
+        b"word/",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    (
+        b"xl/",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    (
+        b"ppt/",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+];
+
+/// MIME type of `head` (the leading bytes of a file), if its signature is known.
+pub fn detect_mime(head: &[u8]) -> Option<&'static str> {
+    if head.starts_with(b"RIFF") {
+        let form = head.get(8..12)?;
+        return RIFF_FORMS
+            .iter()
+            .find(|(tag, _)| *tag == form)
+            .map(|(_, mime)| *mime);
+    }
+    let mime = SIGNATURES
+        .iter()
+        .find(|(offset, sig, _)| {
+            head.get(*offset..offset + sig.len())
+                .is_some_and(|bytes| bytes == *sig)
+        })
+        .map(|(_, _, mime)| *mime)?;
+    if mime == ZIP {
+        let marker = ZIP_MARKERS
+            .iter()
+            .find(|(name, _)| head.windows(name.len()).any(|w| w == *name));
+        return Some(marker.map_or(ZIP, |(_, mime)| *mime));
+    }
+    Some(mime)
+}
+
+/// Read the first [`MIME_PROBE_BYTES`] of `path` and detect its MIME type.
+pub fn detect_mime_file(path: &Path) -> std::io::Result<Option<&'static str>> {
+    let mut head = Vec::with_capacity(MIME_PROBE_BYTES);
+    File::open(path)?
+        .take(MIME_PROBE_BYTES as u64)
+        .read_to_end(&mut head)?;
+    Ok(detect_mime(&head))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signatures_win_over_extensions() {
+        assert_eq!(detect_mime(b"%PDF-1.7\n%..."), Some("application/pdf"));
+        assert_eq!(detect_mime(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(detect_mime(b"RIFF\0\0\0\0XXXX"), None);
+        assert_eq!(
+            detect_mime(b"\0\0\0\x18ftypisom"),
+            Some("video/mp4"),
+            "generic ftyp brand"
+        );
+
+        let mut tar = vec![0u8; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(detect_mime(&tar), Some("application/x-tar"));
+
+        let docx = b"PK\x03\x04\x14\0\0\0\0\0[Content_Types].xmlPK\x03\x04word/document.xml";
+        assert_eq!(
+            detect_mime(docx),
+            Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document")
+        );
+        assert_eq!(detect_mime(b"PK\x03\x04notes.txt"), Some("application/zip"));
+
+        assert_eq!(detect_mime(b"just some text"), None);
+        assert_eq!(detect_mime(b""), None);
+    }
+}
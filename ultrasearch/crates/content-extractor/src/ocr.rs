@@ -96,6 +96,9 @@ impl Extractor for OcrExtractor {
             truncated,
             content_lang: None,
             bytes_processed: used_bytes,
+            mime: None,
+            email: None,
+            title: None,
         })
     }
 }
@@ -0,0 +1,298 @@
+//! Document titles (`extract.index_titles`).
+//!
+//! A document's own title often says more than its filename (`doc1.pdf` titled "Annual
+//! Report"). [`document_title`] reads it from the HTML `<title>`, the `dc:title` of an
+//! Office Open XML (`docProps/core.xml`) or OpenDocument (`meta.xml`) package, or the
+//! `/Title` of a PDF's document information dictionary. Only that metadata is read; the
+//! body still goes through the regular extractors.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Longest title kept, in characters.
+const MAX_TITLE_CHARS: usize = 512;
+
+/// Bytes of an HTML file searched for `<title>`.
+const HTML_HEAD_BYTES: u64 = 64 * 1024;
+
+/// Title of the document at `path`, by its (lowercase) extension. `None` for other formats,
+/// unreadable files, and documents without a title. PDFs larger than `max_bytes` are not
+/// read.
+pub fn document_title(path: &Path, ext: &str, max_bytes: usize) -> Option<String> {
+    let title = match ext {
+        "html" | "htm" | "xhtml" => {
+            let mut head = Vec::new();
+            File::open(path)
+                .ok()?
+                .take(HTML_HEAD_BYTES)
+                .read_to_end(&mut head)
+                .ok()?;
+            html_title(&String::from_utf8_lossy(&head))
+        }
+        "docx" | "docm" | "xlsx" | "xlsm" | "pptx" | "pptm" => {
+            package_title(path, "docProps/core.xml")
+        }
+        "odt" | "ods" | "odp" => package_title(path, "meta.xml"),
+        "pdf" => {
+            if std::fs::metadata(path).ok()?.len() > max_bytes as u64 {
+                return None;
+            }
+            pdf_title(&std::fs::read(path).ok()?)
+        }
+        _ => None,
+    }?;
+    let title: String = title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(MAX_TITLE_CHARS)
+        .collect();
+    (!title.is_empty()).then_some(title)
+}
+
+/// Text between `open` (a tag name, matched up to its `>`) and `close`, ASCII
+/// case-insensitively.
+fn element_text<'a>(doc: &'a str, open: &str, close: &str) -> Option<&'a str> {
+    let lower = doc.to_ascii_lowercase();
+    let start = lower.find(open)?;
+    let body = start + lower[start..].find('>')? + 1;
+    let end = body + lower[body..].find(close)?;
+    Some(&doc[body..end])
+}
+
+/// Replace the predefined XML/HTML entities and numeric character references.
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';').filter(|&i| i <= 10) else {
+            out.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let decoded = match &rest[1..semi] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            num => num
+                .strip_prefix("#x")
+                .or_else(|| num.strip_prefix("#X"))
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| num.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn html_title(html: &str) -> Option<String> {
+    element_text(html, "<title", "</title").map(unescape)
+}
+
+/// `dc:title` from the XML part `entry` of the zip package at `path`.
+fn package_title(path: &Path, entry: &str) -> Option<String> {
+    let mut archive = zip::ZipArchive::new(File::open(path).ok()?).ok()?;
+    let mut xml = String::new();
+    archive
+        .by_name(entry)
+        .ok()?
+        .take(1024 * 1024)
+        .read_to_string(&mut xml)
+        .ok()?;
+    element_text(&xml, "<dc:title", "</dc:title").map(unescape)
+}
+
+/// `/Title` of the object the trailer's `/Info n g R` points to. Titles inside compressed
+/// object streams are not found.
+fn pdf_title(pdf: &[u8]) -> Option<String> {
+    let find =
+        |haystack: &[u8], needle: &[u8]| haystack.windows(needle.len()).position(|w| w == needle);
+    let rfind =
+        |haystack: &[u8], needle: &[u8]| haystack.windows(needle.len()).rposition(|w| w == needle);
+
+    let info = rfind(pdf, b"/Info")? + 5;
+    let mut refs = pdf[info..]
+        .split(|b| b.is_ascii_whitespace())
+        .filter(|t| !t.is_empty());
+    let (num, generation) = (refs.next()?, refs.next()?);
+    if !num.iter().chain(generation).all(u8::is_ascii_digit) {
+        return None;
+    }
+    let header = [num, b" ", generation, b" obj"].concat();
+    let mut at = 0;
+    let object = loop {
+        let found = at + find(&pdf[at..], &header)?;
+        // Skip matches such as `12 0 obj` when looking for `2 0 obj`.
+        if found == 0 || !pdf[found - 1].is_ascii_digit() {
+            break &pdf[found + header.len()..];
+        }
+        at = found + 1;
+    };
+    let object = &object[..find(object, b"endobj").unwrap_or(object.len())];
+    let value = &object[find(object, b"/Title")? + 6..];
+    let value = &value[value.iter().position(|b| !b.is_ascii_whitespace())?..];
+    let bytes = match value.first()? {
+        b'(' => pdf_literal(&value[1..]),
+        b'<' => pdf_hex(&value[1..]),
+        _ => return None,
+    };
+    Some(pdf_text(&bytes))
+}
+
+/// Bytes of a literal string after its `(`, up to the balancing `)`.
+fn pdf_literal(s: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+    while i < s.len() {
+        match s[i] {
+            b'\\' if i + 1 < s.len() => {
+                i += 1;
+                match s[i] {
+                    b'n' => out.push(b'\n'),
+                    b'r' => out.push(b'\r'),
+                    b't' => out.push(b'\t'),
+                    b'b' => out.push(0x08),
+                    b'f' => out.push(0x0C),
+                    b'0'..=b'7' => {
+                        let digits = s[i..]
+                            .iter()
+                            .take(3)
+                            .take_while(|b| (b'0'..=b'7').contains(b));
+                        let len = digits.clone().count();
+                        let code = digits.fold(0u32, |acc, d| acc * 8 + u32::from(d - b'0'));
+                        out.push(code as u8);
+                        i += len - 1;
+                    }
+                    // A backslash before a line break continues the string.
+                    b'\r' | b'\n' => {}
+                    other => out.push(other),
+                }
+            }
+            b'(' => {
+                depth += 1;
+                out.push(b'(');
+            }
+            b')' if depth == 0 => break,
+            b')' => {
+                depth -= 1;
+                out.push(b')');
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Bytes of a hex string after its `<`, up to the `>`.
+fn pdf_hex(s: &[u8]) -> Vec<u8> {
+    let digits: Vec<u8> = s
+        .iter()
+        .take_while(|&&b| b != b'>')
+        .filter_map(|&b| (b as char).to_digit(16).map(|d| d as u8))
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair.get(1).copied().unwrap_or(0))
+        .collect()
+}
+
+/// Decode a PDF text string: UTF-16BE after a byte order mark, otherwise PDFDocEncoding,
+/// read as Latin-1.
+fn pdf_text(bytes: &[u8]) -> String {
+    match bytes.strip_prefix(&[0xFE, 0xFF]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16
+                .as_chunks::<2>()
+                .0
+                .iter()
+                .map(|&pair| u16::from_be_bytes(pair))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn titles_come_from_html_office_and_pdf_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let html = dir.path().join("page1.html");
+        std::fs::write(
+            &html,
+            "<html><head><TITLE lang=en>Annual\n  Report &amp; Accounts</TITLE></head></html>",
+        )
+        .unwrap();
+        assert_eq!(
+            document_title(&html, "html", 1 << 20).as_deref(),
+            Some("Annual Report & Accounts")
+        );
+
+        let docx = dir.path().join("doc1.docx");
+        let mut zip = zip::ZipWriter::new(File::create(&docx).unwrap());
+        zip.start_file(
+            "docProps/core.xml",
+            zip::write::SimpleFileOptions::default(),
+        )
+        .unwrap();
+        zip.write_all(
+            br#"<cp:coreProperties xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>Annual Report 2024</dc:title></cp:coreProperties>"#,
+        )
+        .unwrap();
+        zip.finish().unwrap();
+        assert_eq!(
+            document_title(&docx, "docx", 1 << 20).as_deref(),
+            Some("Annual Report 2024")
+        );
+
+        // An outline entry's /Title comes first; only the Info dictionary's counts.
+        let pdf = dir.path().join("scan.pdf");
+        std::fs::write(
+            &pdf,
+            b"%PDF-1.4\n1 0 obj\n<< /Title (Chapter 1) >>\nendobj\n\
+              12 0 obj\n<< /Author (x) >>\nendobj\n\
+              2 0 obj\n<< /Title (Annual \\(Draft\\) Report) /Producer (y) >>\nendobj\n\
+              trailer\n<< /Root 3 0 R /Info 2 0 R >>\n%%EOF\n",
+        )
+        .unwrap();
+        assert_eq!(
+            document_title(&pdf, "pdf", 1 << 20).as_deref(),
+            Some("Annual (Draft) Report")
+        );
+        assert_eq!(document_title(&pdf, "pdf", 16), None, "over max_bytes");
+
+        // UTF-16BE hex string with a byte order mark.
+        assert_eq!(
+            pdf_title(b"2 0 obj << /Title <FEFF00C4006E006E00750061006C> >> endobj trailer << /Info 2 0 R >>")
+                .as_deref(),
+            Some("\u{c4}nnual")
+        );
+        assert_eq!(document_title(&html, "txt", 1 << 20), None);
+    }
+}
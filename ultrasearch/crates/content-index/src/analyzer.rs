@@ -0,0 +1,120 @@
+//! Analyzer for the `content` field, with an optional stop-word list.
+//!
+//! Stop words change which postings exist, so index time and query time must agree. The
+//! options an index was built with are persisted next to it (`analyzer.json`) and win over
+//! the caller's options on open; new options are only adopted while the index is empty.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use tantivy::tokenizer::{
+    Language, LowerCaser, RemoveLongFilter, SimpleTokenizer, StopWordFilter, TextAnalyzer,
+    TokenizerManager,
+};
+
+/// Tokenizer name used by the `content` field.
+pub const CONTENT_ANALYZER: &str = "content_text";
+
+/// Sidecar file recording the options an index was built with.
+pub const ANALYZER_FILE: &str = "analyzer.json";
+
+/// How `content` text is tokenized.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AnalyzerOptions {
+    /// Drop stop words at index and query time. Disable for code/log corpora.
+    pub stop_words_enabled: bool,
+    /// Language whose built-in list is used when `stop_words` is empty (e.g. `english`).
+    pub language: String,
+    /// Custom list; replaces the language list when non-empty.
+    pub stop_words: Vec<String>,
+}
+
+impl Default for AnalyzerOptions {
+    fn default() -> Self {
+        Self {
+            stop_words_enabled: true,
+            language: "english".into(),
+            stop_words: Vec::new(),
+        }
+    }
+}
+
+impl AnalyzerOptions {
+    /// Plain lowercasing analyzer without stop words.
+    pub fn without_stop_words() -> Self {
+        Self {
+            stop_words_enabled: false,
+            ..Self::default()
+        }
+    }
+
+    fn stop_word_filter(&self) -> Result<Option<StopWordFilter>> {
+        if !self.stop_words_enabled {
+            return Ok(None);
+        }
+        if !self.stop_words.is_empty() {
+            let words = self.stop_words.iter().map(|w| w.trim().to_lowercase());
+            return Ok(Some(StopWordFilter::remove(
+                words.filter(|w| !w.is_empty()),
+            )));
+        }
+        let Some(language) = parse_language(&self.language) else {
+            bail!("unknown stop-word language `{}`", self.language);
+        };
+        Ok(StopWordFilter::new(language))
+    }
+}
+
+fn parse_language(name: &str) -> Option<Language> {
+    Some(match name.trim().to_ascii_lowercase().as_str() {
+        "danish" | "da" => Language::Danish,
+        "dutch" | "nl" => Language::Dutch,
+        "english" | "en" => Language::English,
+        "finnish" | "fi" => Language::Finnish,
+        "french" | "fr" => Language::French,
+        "german" | "de" => Language::German,
+        "hungarian" | "hu" => Language::Hungarian,
+        "italian" | "it" => Language::Italian,
+        "norwegian" | "no" => Language::Norwegian,
+        "portuguese" | "pt" => Language::Portuguese,
+        "russian" | "ru" => Language::Russian,
+        "spanish" | "es" => Language::Spanish,
+        "swedish" | "sv" => Language::Swedish,
+        _ => return None,
+    })
+}
+
+/// Register [`CONTENT_ANALYZER`]: the default tokenizer chain plus the stop-word filter.
+pub fn register_content_analyzer(manager: &TokenizerManager, opts: &AnalyzerOptions) -> Result<()> {
+    let builder = TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(RemoveLongFilter::limit(40))
+        .filter(LowerCaser)
+        .dynamic();
+    let analyzer = match opts.stop_word_filter()? {
+        Some(filter) => builder.filter_dynamic(filter).build(),
+        None => builder.build(),
+    };
+    manager.register(CONTENT_ANALYZER, analyzer);
+    Ok(())
+}
+
+pub(crate) fn load_options(index_dir: &Path) -> Result<Option<AnalyzerOptions>> {
+    let path = index_dir.join(ANALYZER_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let raw = fs::read_to_string(&path)?;
+    let opts = serde_json::from_str(&raw)
+        .with_context(|| format!("invalid analyzer settings in {}", path.display()))?;
+    Ok(Some(opts))
+}
+
+pub(crate) fn save_options(index_dir: &Path, opts: &AnalyzerOptions) -> Result<()> {
+    fs::write(
+        index_dir.join(ANALYZER_FILE),
+        serde_json::to_string_pretty(opts)?,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,245 @@
+//! Shared bodies for files with identical text (`extract.dedup_content`).
+//!
+//! License headers, templates and copied files often extract to the same text. With dedup on,
+//! the first such file is indexed as usual and later ones are written by
+//! [`add_content_doc_dedup`] as metadata-only documents whose `body_of` names the stored
+//! body's `body_hash`, so the postings and preview exist once. Searches match a reference's
+//! text through [`BodyScores`] of its body, and its other fields directly. Bodies are compared
+//! after [`normalized_hash`], so files differing only in case or whitespace share one; this is
+//! not byte-identical file dedup.
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Result;
+use tantivy::collector::{Collector, SegmentCollector, TopDocs};
+use tantivy::columnar::Column;
+use tantivy::query::TermQuery;
+use tantivy::schema::{Field, IndexRecordOption, TantivyDocument, Value};
+use tantivy::{DocId, IndexWriter, Score, Searcher, SegmentOrdinal, SegmentReader, Term};
+
+use crate::{ContentDoc, ContentFields, ContentIndex, to_document, to_reference_document};
+
+/// FNV-1a of `text` lowercased, with whitespace runs collapsed to one space and trimmed.
+/// Stable across builds, since it is persisted in the index.
+pub fn normalized_hash(text: &str) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let mut hash = OFFSET;
+    let mut feed = |bytes: &[u8]| {
+        for &b in bytes {
+            hash = (hash ^ b as u64).wrapping_mul(PRIME);
+        }
+    };
+    for (i, word) in text.split_whitespace().enumerate() {
+        if i > 0 {
+            feed(b" ");
+        }
+        let mut buf = [0u8; 4];
+        for c in word.chars().flat_map(char::to_lowercase) {
+            feed(c.encode_utf8(&mut buf).as_bytes());
+        }
+    }
+    hash
+}
+
+/// Bodies already stored: those committed when the writer session began plus those added since.
+pub struct BodyDedup {
+    searcher: Searcher,
+    body_hash: Field,
+    added: HashSet<u64>,
+}
+
+impl BodyDedup {
+    pub fn new(idx: &ContentIndex) -> Result<Self> {
+        Ok(Self {
+            searcher: idx.index.reader()?.searcher(),
+            body_hash: idx.fields.body_hash,
+            added: HashSet::new(),
+        })
+    }
+
+    /// Whether a body with `hash` is stored; if not, records that it is about to be.
+    fn claim(&mut self, hash: u64) -> Result<bool> {
+        if self.added.contains(&hash) {
+            return Ok(true);
+        }
+        let term = Term::from_field_u64(self.body_hash, hash);
+        if self.searcher.doc_freq(&term)? > 0 {
+            return Ok(true);
+        }
+        self.added.insert(hash);
+        Ok(false)
+    }
+}
+
+/// Add `doc`, storing its body only if no identical body is stored yet. Returns whether the
+/// body was stored (`false` = written as a reference).
+pub fn add_content_doc_dedup(
+    writer: &mut IndexWriter,
+    fields: &ContentFields,
+    doc: &ContentDoc,
+    dedup: &mut BodyDedup,
+) -> Result<bool> {
+    let hash = normalized_hash(&doc.content);
+    if dedup.claim(hash)? {
+        writer.add_document(to_reference_document(doc, fields, hash))?;
+        Ok(false)
+    } else {
+        writer.add_document(to_document(doc, fields))?;
+        Ok(true)
+    }
+}
+
+/// Whether any document references a shared body, i.e. whether searches need to expand hits.
+pub fn has_references(searcher: &Searcher, fields: &ContentFields) -> bool {
+    searcher.segment_readers().iter().any(|segment| {
+        segment
+            .inverted_index(fields.body_of)
+            .is_ok_and(|index| index.terms().num_terms() > 0)
+    })
+}
+
+/// Give a reference document (one with `body_of`) the preview of the body it shares, so it
+/// renders snippets like the document storing the body. Other documents are left as is.
+pub fn attach_shared_preview(
+    searcher: &Searcher,
+    fields: &ContentFields,
+    doc: &mut TantivyDocument,
+) -> tantivy::Result<()> {
+    let Some(hash) = doc.get_first(fields.body_of).and_then(|v| v.as_u64()) else {
+        return Ok(());
+    };
+    let owner = TermQuery::new(
+        Term::from_field_u64(fields.body_hash, hash),
+        IndexRecordOption::Basic,
+    );
+    let top = searcher.search(&owner, &TopDocs::with_limit(1))?;
+    if let Some((_, addr)) = top.first() {
+        let body: TantivyDocument = searcher.doc(*addr)?;
+        if let Some(preview) = body
+            .get_first(fields.content_preview)
+            .and_then(|v| v.as_str())
+        {
+            doc.add_text(fields.content_preview, preview);
+        }
+    }
+    Ok(())
+}
+
+/// Collects the `body_hash` of every matched document that stores a body, with its score.
+pub struct BodyScores;
+
+pub struct BodyScoresSegment {
+    hashes: Column<u64>,
+    out: HashMap<u64, Score>,
+}
+
+impl SegmentCollector for BodyScoresSegment {
+    type Fruit = HashMap<u64, Score>;
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        if let Some(hash) = self.hashes.first(doc) {
+            let best = self.out.entry(hash).or_insert(score);
+            *best = best.max(score);
+        }
+    }
+
+    fn harvest(self) -> HashMap<u64, Score> {
+        self.out
+    }
+}
+
+impl Collector for BodyScores {
+    type Fruit = HashMap<u64, Score>;
+    type Child = BodyScoresSegment;
+
+    fn for_segment(
+        &self,
+        _segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tantivy::Result<BodyScoresSegment> {
+        Ok(BodyScoresSegment {
+            hashes: segment.fast_fields().u64("body_hash")?,
+            out: HashMap::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        true
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<HashMap<u64, Score>>,
+    ) -> tantivy::Result<HashMap<u64, Score>> {
+        let mut merged = HashMap::new();
+        for (hash, score) in segment_fruits.into_iter().flatten() {
+            let best = merged.entry(hash).or_insert(score);
+            *best = f32::max(*best, score);
+        }
+        Ok(merged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{WriterConfig, create_in_ram, create_writer, open_reader};
+    use core_types::DocKey;
+    use tantivy::collector::Count;
+
+    fn doc(file_id: u64, content: &str) -> ContentDoc {
+        ContentDoc {
+            key: DocKey::from_parts(1, file_id),
+            volume: 1,
+            name: Some(format!("LICENSE{file_id}")),
+            path: None,
+            ext: None,
+            size: content.len() as u64,
+            modified: 0,
+            created: 0,
+            content_lang: None,
+            content: content.into(),
+            mime: None,
+            email: None,
+            title: None,
+        }
+    }
+
+    #[test]
+    fn identical_normalized_bodies_are_stored_once() {
+        assert_eq!(
+            normalized_hash("MIT License\n\n  Permission is granted"),
+            normalized_hash("mit license permission is granted ")
+        );
+        assert_ne!(normalized_hash("a b"), normalized_hash("ab"));
+
+        let idx = create_in_ram().unwrap();
+        let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+        let mut dedup = BodyDedup::new(&idx).unwrap();
+        let stored: Vec<bool> = [
+            doc(1, "MIT License\nPermission is granted"),
+            doc(2, "mit license   permission is GRANTED"),
+            doc(3, "Apache License"),
+        ]
+        .iter()
+        .map(|d| add_content_doc_dedup(&mut writer, &idx.fields, d, &mut dedup).unwrap())
+        .collect();
+        assert_eq!(stored, vec![true, false, true]);
+        writer.commit().unwrap();
+
+        let searcher = open_reader(&idx).unwrap().searcher();
+        assert_eq!(searcher.num_docs(), 3);
+        let term = Term::from_field_text(idx.fields.content, "permission");
+        let matches = searcher
+            .search(&TermQuery::new(term, IndexRecordOption::Basic), &Count)
+            .unwrap();
+        assert_eq!(matches, 1, "one stored body");
+        assert!(has_references(&searcher, &idx.fields));
+
+        // A later session sees the committed body.
+        let mut dedup = BodyDedup::new(&idx).unwrap();
+        let again = doc(4, "MIT license permission is granted");
+        assert!(!add_content_doc_dedup(&mut writer, &idx.fields, &again, &mut dedup).unwrap());
+    }
+}
@@ -2,15 +2,33 @@
 //!
 //! Schema matches the plan: doc_key, volume, name/path/ext metadata, size,
 //! modified, optional content_lang, and the main `content` text field.
+//! `size`, `modified` and `created` are indexed + fast so range filters run
+//! natively inside content queries without a meta-store join. A capped,
+//! stored `content_preview` carries the leading text used for snippets.
+//! `mime` holds the type detected from magic bytes, when extraction detected one.
+//! `content` is tokenized by [`analyzer::CONTENT_ANALYZER`], which can drop stop words.
+//! `body_hash`/`body_of` let files with identical text share one stored body (see [`dedup`]).
+//! `email_*` hold the header fields of email files (`extract.index_email`).
+//! `title` holds the document's own title from its metadata (`extract.index_titles`).
 
 use std::path::Path;
 
-use anyhow::Result;
-use core_types::DocKey;
+use anyhow::{Context, Result};
+use core_types::config::ContentMergeSection;
+use core_types::{DocKey, EmailHeaders, FileMeta, dir_components, set_aside_dir};
 pub use tantivy::IndexWriter;
+use tantivy::indexer::LogMergePolicy;
 use tantivy::{Index, IndexSettings, ReloadPolicy, schema::document::TantivyDocument, schema::*};
 
+pub mod analyzer;
+pub mod dedup;
 pub mod log_analysis;
+pub mod snippet;
+
+pub use analyzer::AnalyzerOptions;
+
+/// Upper bound (bytes) on the stored preview text used for snippet generation.
+pub const PREVIEW_MAX_BYTES: usize = 64 * 1024;
 
 /// Field handles for the content index schema.
 #[derive(Debug, Clone)]
@@ -24,6 +42,57 @@ pub struct ContentFields {
     pub modified: Field,
     pub content_lang: Field,
     pub content: Field,
+    pub created: Field,
+    pub content_preview: Field,
+    /// Raw, lowercased ancestor directory names (multi-valued, not stored).
+    pub dir_component: Field,
+    /// Lowercased MIME type from magic-byte detection (e.g. `application/pdf`).
+    pub mime: Field,
+    /// [`dedup::normalized_hash`] of a document that stores its body.
+    pub body_hash: Field,
+    /// On a document without a body of its own: the `body_hash` of the document storing it.
+    pub body_of: Field,
+    /// Email `Subject`, `From`, `To`/`Cc` (tokenized) and `Date` (Unix seconds); multi-valued
+    /// for an `.mbox`.
+    pub email_subject: Field,
+    pub email_from: Field,
+    pub email_to: Field,
+    pub email_sent: Field,
+    /// Title from the document's metadata (HTML `<title>`, Office `dc:title`, PDF `/Title`).
+    pub title: Field,
+}
+
+impl ContentFields {
+    /// Resolve field handles by name from an existing index schema.
+    pub fn from_schema(schema: &Schema) -> Result<Self> {
+        let get = |name: &str| {
+            schema
+                .get_field(name)
+                .with_context(|| format!("content index schema missing field `{name}`"))
+        };
+        Ok(Self {
+            doc_key: get("doc_key")?,
+            volume: get("volume")?,
+            name: get("name")?,
+            path: get("path")?,
+            ext: get("ext")?,
+            size: get("size")?,
+            modified: get("modified")?,
+            content_lang: get("content_lang")?,
+            content: get("content")?,
+            created: get("created")?,
+            content_preview: get("content_preview")?,
+            dir_component: get("dir_component")?,
+            mime: get("mime")?,
+            body_hash: get("body_hash")?,
+            body_of: get("body_of")?,
+            email_subject: get("email_subject")?,
+            email_from: get("email_from")?,
+            email_to: get("email_to")?,
+            email_sent: get("email_sent")?,
+            title: get("title")?,
+        })
+    }
 }
 
 pub fn build_schema() -> (Schema, ContentFields) {
@@ -34,12 +103,32 @@ pub fn build_schema() -> (Schema, ContentFields) {
     let name = builder.add_text_field("name", TEXT | STORED);
     let path = builder.add_text_field("path", TEXT | STORED);
     let ext = builder.add_text_field("ext", STRING | FAST);
-    let size = builder.add_u64_field("size", FAST | STORED);
-    let modified = builder.add_i64_field("modified", FAST | STORED);
+    let size = builder.add_u64_field("size", INDEXED | FAST | STORED);
+    let modified = builder.add_i64_field("modified", INDEXED | FAST | STORED);
     let content_lang = builder.add_text_field("content_lang", STRING | STORED);
 
-    // Use default tokenizer for content, but allow overrides via per-field options later if needed.
-    let content = builder.add_text_field("content", TEXT);
+    // Default tokenizer chain plus an optional stop-word filter (see `analyzer`).
+    let content_indexing = TextFieldIndexing::default()
+        .set_tokenizer(analyzer::CONTENT_ANALYZER)
+        .set_index_option(IndexRecordOption::WithFreqsAndPositions);
+    let content = builder.add_text_field(
+        "content",
+        TextOptions::default().set_indexing_options(content_indexing),
+    );
+
+    // Added after the first release. Tantivy can't add fields to an existing index, so one
+    // created without them is set aside and recreated on open.
+    let created = builder.add_i64_field("created", INDEXED | FAST | STORED);
+    let content_preview = builder.add_text_field("content_preview", STORED);
+    let dir_component = builder.add_text_field("dir_component", STRING);
+    let mime = builder.add_text_field("mime", STRING);
+    let body_hash = builder.add_u64_field("body_hash", INDEXED | FAST | STORED);
+    let body_of = builder.add_u64_field("body_of", INDEXED | FAST | STORED);
+    let email_subject = builder.add_text_field("email_subject", TEXT);
+    let email_from = builder.add_text_field("email_from", TEXT);
+    let email_to = builder.add_text_field("email_to", TEXT);
+    let email_sent = builder.add_i64_field("email_sent", INDEXED | FAST);
+    let title = builder.add_text_field("title", TEXT);
 
     let fields = ContentFields {
         doc_key,
@@ -51,6 +140,17 @@ pub fn build_schema() -> (Schema, ContentFields) {
         modified,
         content_lang,
         content,
+        created,
+        content_preview,
+        dir_component,
+        mime,
+        body_hash,
+        body_of,
+        email_subject,
+        email_from,
+        email_to,
+        email_sent,
+        title,
     };
 
     (builder.build(), fields)
@@ -62,27 +162,101 @@ pub struct ContentIndex {
     pub fields: ContentFields,
 }
 
-fn setup_index(index: &Index) {
+fn setup_index(index: &Index, analyzer: &AnalyzerOptions) -> Result<()> {
     log_analysis::register_log_analyzers(index.tokenizers());
+    analyzer::register_content_analyzer(index.tokenizers(), analyzer)
 }
 
+/// Open with the analyzer options the index was built with (defaults for a new index).
 pub fn open_or_create(path: &Path) -> Result<ContentIndex> {
+    open_index(path, None)
+}
+
+/// Open or create the index at `path`. An existing index keeps the analyzer options it was
+/// built with unless it is still empty, in which case `analyzer` replaces them.
+///
+/// An existing index whose schema differs from [`build_schema`], e.g. one created before a
+/// field was added, is moved aside to `<dir>.outdated` and replaced by an empty one; files are
+/// extracted into it again as the scheduler reaches them.
+pub fn open_or_create_with(path: &Path, analyzer: &AnalyzerOptions) -> Result<ContentIndex> {
+    open_index(path, Some(analyzer))
+}
+
+fn open_index(path: &Path, requested: Option<&AnalyzerOptions>) -> Result<ContentIndex> {
     let (schema, fields) = build_schema();
-    let index = if path.join("meta.json").exists() {
-        Index::open_in_dir(path)?
+    let existing = if path.join("meta.json").exists() {
+        let index = Index::open_in_dir(path)?;
+        if schema_matches(&index.schema(), &schema) {
+            Some(index)
+        } else {
+            drop(index);
+            let aside =
+                set_aside_dir(path, "outdated").context("set aside outdated content index")?;
+            tracing::warn!(?aside, "content index schema is outdated; recreating it");
+            std::fs::create_dir_all(path)?;
+            None
+        }
     } else {
-        Index::create_in_dir(path, schema)?
+        None
+    };
+    let (index, fields) = match existing {
+        Some(index) => {
+            let fields = ContentFields::from_schema(&index.schema())?;
+            (index, fields)
+        }
+        None => (Index::create_in_dir(path, schema)?, fields),
     };
-    setup_index(&index);
+
+    let effective = match (analyzer::load_options(path)?, requested) {
+        (Some(stored), Some(requested)) if stored != *requested => {
+            let num_docs: u32 = index
+                .searchable_segment_metas()?
+                .iter()
+                .map(|s| s.num_docs())
+                .sum();
+            if num_docs == 0 {
+                analyzer::save_options(path, requested)?;
+                requested.clone()
+            } else {
+                tracing::warn!(
+                    ?stored,
+                    ?requested,
+                    "content index was built with different analyzer settings; reset the index to apply them"
+                );
+                stored
+            }
+        }
+        (Some(stored), _) => stored,
+        (None, requested) => {
+            let opts = requested.cloned().unwrap_or_default();
+            analyzer::save_options(path, &opts)?;
+            opts
+        }
+    };
+    setup_index(&index, &effective)?;
     Ok(ContentIndex { index, fields })
 }
 
+/// Whether `existing` has every field of `current`, with the same type and options.
+fn schema_matches(existing: &Schema, current: &Schema) -> bool {
+    current.fields().all(|(_, entry)| {
+        existing
+            .get_field(entry.name())
+            .is_ok_and(|field| existing.get_field_entry(field) == entry)
+    })
+}
+
 /// Create an in-memory index for tests and benchmarks.
 pub fn create_in_ram() -> Result<ContentIndex> {
+    create_in_ram_with(&AnalyzerOptions::default())
+}
+
+/// In-memory index with explicit analyzer options.
+pub fn create_in_ram_with(analyzer: &AnalyzerOptions) -> Result<ContentIndex> {
     let (schema, fields) = build_schema();
     let dir = tantivy::directory::RamDirectory::create();
     let index = Index::create(dir, schema, IndexSettings::default())?;
-    setup_index(&index);
+    setup_index(&index, analyzer)?;
     Ok(ContentIndex { index, fields })
 }
 
@@ -90,6 +264,8 @@ pub fn create_in_ram() -> Result<ContentIndex> {
 pub struct WriterConfig {
     pub heap_size_bytes: usize,
     pub num_threads: usize,
+    /// Background merge policy (`[content_merge]`).
+    pub merge: ContentMergeSection,
 }
 
 impl Default for WriterConfig {
@@ -97,6 +273,7 @@ impl Default for WriterConfig {
         Self {
             heap_size_bytes: 256 * 1024 * 1024, // conservative; content writer often heavier
             num_threads: 4,
+            merge: ContentMergeSection::default(),
         }
     }
 }
@@ -105,9 +282,54 @@ pub fn create_writer(idx: &ContentIndex, cfg: &WriterConfig) -> Result<IndexWrit
     let writer = idx
         .index
         .writer_with_num_threads(cfg.num_threads, cfg.heap_size_bytes)?;
+    writer.set_merge_policy(Box::new(log_merge_policy(&cfg.merge)));
     Ok(writer)
 }
 
+/// Tantivy log merge policy built from `[content_merge]`.
+pub fn log_merge_policy(cfg: &ContentMergeSection) -> LogMergePolicy {
+    let mut policy = LogMergePolicy::default();
+    policy.set_min_num_segments(cfg.min_num_segments.max(2));
+    policy.set_max_docs_before_merge(cfg.max_docs_before_merge);
+    policy.set_min_layer_size(cfg.min_layer_size);
+    policy.set_level_log_size(cfg.level_log_size);
+    policy
+        .set_del_docs_ratio_before_merge(cfg.del_docs_ratio_before_merge.clamp(f32::EPSILON, 1.0));
+    policy
+}
+
+/// Number of committed, searchable segments.
+pub fn segment_count(idx: &ContentIndex) -> Result<usize> {
+    Ok(idx.index.searchable_segment_ids()?.len())
+}
+
+/// Merge the smallest segments together until at most `max_segments` remain, wait for the
+/// merge to finish and drop the files it made obsolete. Returns the resulting segment count.
+///
+/// Takes the index writer lock, so it must not run while another writer is open.
+pub fn force_merge(idx: &ContentIndex, max_segments: usize) -> Result<usize> {
+    let max_segments = max_segments.max(1);
+    let mut metas = idx.index.searchable_segment_metas()?;
+    if metas.len() <= max_segments {
+        return Ok(metas.len());
+    }
+    metas.sort_by_key(|m| m.num_docs());
+    let ids: Vec<_> = metas[..metas.len() - max_segments + 1]
+        .iter()
+        .map(|m| m.id())
+        .collect();
+
+    let mut writer: IndexWriter = idx.index.writer_with_num_threads(1, 64 * 1024 * 1024)?;
+    writer.set_merge_policy(Box::new(tantivy::indexer::NoMergePolicy));
+    writer
+        .merge(&ids)
+        .wait()
+        .context("merge content segments")?;
+    writer.garbage_collect_files().wait()?;
+    writer.wait_merging_threads()?;
+    segment_count(idx)
+}
+
 pub fn open_reader(idx: &ContentIndex) -> Result<tantivy::IndexReader> {
     let reader = idx
         .index
@@ -126,11 +348,56 @@ pub struct ContentDoc {
     pub ext: Option<String>,
     pub size: u64,
     pub modified: i64,
+    pub created: i64,
     pub content_lang: Option<String>,
     pub content: String,
+    pub mime: Option<String>,
+    pub email: Option<EmailHeaders>,
+    pub title: Option<String>,
+}
+
+impl ContentDoc {
+    /// Build a content document whose metadata (incl. range-filterable size/created/modified)
+    /// comes from the file's `FileMeta`.
+    pub fn from_meta(meta: &FileMeta, content: String, content_lang: Option<String>) -> Self {
+        Self {
+            key: meta.key,
+            volume: meta.volume,
+            name: Some(meta.name.clone()),
+            path: meta.path.clone(),
+            ext: meta.ext.clone(),
+            size: meta.size,
+            modified: meta.modified,
+            created: meta.created,
+            content_lang,
+            content,
+            mime: None,
+            email: None,
+            title: None,
+        }
+    }
 }
 
 pub fn to_document(doc: &ContentDoc, fields: &ContentFields) -> TantivyDocument {
+    let mut d = metadata_document(doc, fields);
+    d.add_text(fields.content, &doc.content);
+    d.add_text(fields.content_preview, preview_text(&doc.content));
+    d.add_u64(fields.body_hash, dedup::normalized_hash(&doc.content));
+    d
+}
+
+/// `doc` without a body: its metadata plus a reference to the document storing `body_hash`.
+pub fn to_reference_document(
+    doc: &ContentDoc,
+    fields: &ContentFields,
+    body_hash: u64,
+) -> TantivyDocument {
+    let mut d = metadata_document(doc, fields);
+    d.add_u64(fields.body_of, body_hash);
+    d
+}
+
+fn metadata_document(doc: &ContentDoc, fields: &ContentFields) -> TantivyDocument {
     let mut d = TantivyDocument::default();
     d.add_u64(fields.doc_key, doc.key.0);
     d.add_u64(fields.volume, doc.volume as u64);
@@ -139,19 +406,54 @@ pub fn to_document(doc: &ContentDoc, fields: &ContentFields) -> TantivyDocument
     }
     if let Some(path) = &doc.path {
         d.add_text(fields.path, path);
+        for component in dir_components(path) {
+            d.add_text(fields.dir_component, component);
+        }
     }
     if let Some(ext) = &doc.ext {
         d.add_text(fields.ext, ext);
     }
     d.add_u64(fields.size, doc.size);
     d.add_i64(fields.modified, doc.modified);
+    d.add_i64(fields.created, doc.created);
     if let Some(lang) = &doc.content_lang {
         d.add_text(fields.content_lang, lang);
     }
-    d.add_text(fields.content, &doc.content);
+    if let Some(mime) = &doc.mime {
+        d.add_text(fields.mime, mime.to_ascii_lowercase());
+    }
+    if let Some(email) = &doc.email {
+        for (field, values) in [
+            (fields.email_subject, &email.subject),
+            (fields.email_from, &email.from),
+            (fields.email_to, &email.to),
+        ] {
+            for value in values {
+                d.add_text(field, value);
+            }
+        }
+        for &sent in &email.sent {
+            d.add_i64(fields.email_sent, sent);
+        }
+    }
+    if let Some(title) = &doc.title {
+        d.add_text(fields.title, title);
+    }
     d
 }
 
+/// Leading slice of `content` kept for snippets, cut on a char boundary.
+pub fn preview_text(content: &str) -> &str {
+    if content.len() <= PREVIEW_MAX_BYTES {
+        return content;
+    }
+    let mut end = PREVIEW_MAX_BYTES;
+    while !content.is_char_boundary(end) {
+        end -= 1;
+    }
+    &content[..end]
+}
+
 /// Add a single content document to the index writer.
 pub fn add_content_doc(
     writer: &mut IndexWriter,
@@ -166,6 +468,7 @@ pub fn add_content_doc(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tantivy::doc;
     use tantivy::schema::OwnedValue;
 
     #[test]
@@ -181,6 +484,17 @@ mod tests {
             fields.modified,
             fields.content_lang,
             fields.content,
+            fields.created,
+            fields.content_preview,
+            fields.dir_component,
+            fields.mime,
+            fields.body_hash,
+            fields.body_of,
+            fields.email_subject,
+            fields.email_from,
+            fields.email_to,
+            fields.email_sent,
+            fields.title,
         ] {
             assert!(!schema.get_field_entry(f).name().is_empty());
         }
@@ -197,8 +511,12 @@ mod tests {
             ext: Some("txt".into()),
             size: 10,
             modified: 123,
+            created: 100,
             content_lang: Some("en".into()),
             content: "hello world".into(),
+            mime: None,
+            email: None,
+            title: None,
         };
         let tantivy_doc = to_document(&doc, &fields);
         let mut vals = tantivy_doc.get_all(fields.doc_key);
@@ -208,6 +526,170 @@ mod tests {
         assert!(vals.next().is_none());
     }
 
+    #[test]
+    fn preview_is_capped_on_char_boundary() {
+        let long = "é".repeat(PREVIEW_MAX_BYTES);
+        let preview = preview_text(&long);
+        assert!(preview.len() <= PREVIEW_MAX_BYTES);
+        assert!(preview.chars().all(|c| c == 'é'));
+        assert_eq!(preview_text("short"), "short");
+    }
+
+    fn content_hits(idx: &ContentIndex, query: &str) -> usize {
+        let reader = open_reader(idx).unwrap();
+        let parser = tantivy::query::QueryParser::for_index(&idx.index, vec![idx.fields.content]);
+        let q = parser.parse_query(query).unwrap();
+        reader
+            .searcher()
+            .search(&q, &tantivy::collector::Count)
+            .unwrap()
+    }
+
+    fn index_text(analyzer: &AnalyzerOptions, text: &str) -> ContentIndex {
+        let idx = create_in_ram_with(analyzer).unwrap();
+        let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+        let meta = FileMeta::new(
+            DocKey::from_parts(1, 1),
+            1,
+            None,
+            "notes.txt".into(),
+            Some(r"C:\notes.txt".into()),
+            10,
+            0,
+            0,
+            core_types::FileFlags::empty(),
+        );
+        add_content_doc(
+            &mut writer,
+            &idx.fields,
+            &ContentDoc::from_meta(&meta, text.into(), None),
+        )
+        .unwrap();
+        writer.commit().unwrap();
+        idx
+    }
+
+    #[test]
+    fn stop_words_are_dropped_unless_disabled() {
+        let text = "the migration plan and the rollback";
+
+        let idx = index_text(&AnalyzerOptions::default(), text);
+        assert_eq!(content_hits(&idx, "the"), 0);
+        assert_eq!(content_hits(&idx, "rollback"), 1);
+
+        let idx = index_text(&AnalyzerOptions::without_stop_words(), text);
+        assert_eq!(content_hits(&idx, "the"), 1);
+        assert_eq!(content_hits(&idx, "and"), 1);
+
+        let custom = AnalyzerOptions {
+            stop_words: vec!["rollback".into()],
+            ..AnalyzerOptions::default()
+        };
+        let idx = index_text(&custom, text);
+        assert_eq!(content_hits(&idx, "rollback"), 0);
+        assert_eq!(content_hits(&idx, "the"), 1);
+    }
+
+    #[test]
+    fn analyzer_settings_persist_with_the_index() {
+        let dir = tempfile::tempdir().unwrap();
+        open_or_create_with(dir.path(), &AnalyzerOptions::without_stop_words()).unwrap();
+        // Still empty: new settings are adopted.
+        open_or_create_with(dir.path(), &AnalyzerOptions::default()).unwrap();
+        assert_eq!(
+            analyzer::load_options(dir.path()).unwrap(),
+            Some(AnalyzerOptions::default())
+        );
+
+        let idx = open_or_create(dir.path()).unwrap();
+        let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+        writer
+            .add_document(doc!(idx.fields.doc_key => 1u64, idx.fields.content => "the end"))
+            .unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+
+        // Populated: the stored settings win so queries match what was indexed.
+        let idx = open_or_create_with(dir.path(), &AnalyzerOptions::without_stop_words()).unwrap();
+        assert_eq!(
+            analyzer::load_options(dir.path()).unwrap(),
+            Some(AnalyzerOptions::default())
+        );
+        assert_eq!(content_hits(&idx, "the"), 0);
+        assert!(
+            analyzer::register_content_analyzer(
+                idx.index.tokenizers(),
+                &AnalyzerOptions {
+                    language: "klingon".into(),
+                    ..AnalyzerOptions::default()
+                }
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn index_with_an_outdated_schema_is_set_aside_and_recreated() {
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("content");
+        std::fs::create_dir_all(&path).unwrap();
+        // A schema from before `created` and later fields existed.
+        let mut old = Schema::builder();
+        old.add_u64_field("doc_key", FAST | STORED);
+        old.add_text_field("content", TEXT);
+        Index::create_in_dir(&path, old.build()).unwrap();
+
+        let idx = open_or_create(&path).unwrap();
+        assert!(idx.index.schema().get_field("title").is_ok());
+        let aside = root.path().join("content.outdated");
+        assert!(aside.join("meta.json").exists());
+        open_or_create(&path).unwrap();
+        assert!(!root.path().join("content.outdated-1").exists());
+    }
+
+    #[test]
+    fn force_merge_reduces_segments_after_small_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        let idx = open_or_create(dir.path()).unwrap();
+        // Keep the background policy from merging so each commit stays its own segment.
+        let cfg = WriterConfig {
+            heap_size_bytes: 16 * 1024 * 1024,
+            num_threads: 1,
+            merge: ContentMergeSection {
+                min_num_segments: 100,
+                ..Default::default()
+            },
+        };
+        let mut writer = create_writer(&idx, &cfg).unwrap();
+        for i in 0..12u64 {
+            let doc = ContentDoc {
+                key: DocKey::from_parts(1, i),
+                volume: 1,
+                name: Some(format!("f{i}.txt")),
+                path: None,
+                ext: Some("txt".into()),
+                size: 1,
+                modified: 0,
+                created: 0,
+                content_lang: None,
+                content: format!("segment body {i}"),
+                mime: None,
+                email: None,
+                title: None,
+            };
+            add_content_doc(&mut writer, &idx.fields, &doc).unwrap();
+            writer.commit().unwrap();
+        }
+        writer.wait_merging_threads().unwrap();
+        assert_eq!(segment_count(&idx).unwrap(), 12);
+
+        assert_eq!(force_merge(&idx, 3).unwrap(), 3);
+        assert_eq!(content_hits(&idx, "segment"), 12);
+        // Already within the limit: nothing to do.
+        assert_eq!(force_merge(&idx, 3).unwrap(), 3);
+        assert_eq!(force_merge(&idx, 1).unwrap(), 1);
+    }
+
     #[test]
     fn create_ram_index_works() {
         let idx = create_in_ram().unwrap();
@@ -228,8 +710,12 @@ fn add_content_doc_appends() {
         ext: Some("txt".into()),
         size: 10,
         modified: 123,
+        created: 100,
         content_lang: Some("en".into()),
         content: "hello world".into(),
+        mime: None,
+        email: None,
+        title: None,
     };
     add_content_doc(&mut writer, &idx.fields, &doc).unwrap();
     writer.commit().unwrap();
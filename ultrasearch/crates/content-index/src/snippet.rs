@@ -0,0 +1,486 @@
+//! Snippet generation for content hits.
+//!
+//! Fragments are centred on term matches and then snapped to clean edges: a sentence
+//! boundary when one falls inside the window, otherwise the nearest word boundary (UAX #29).
+//! Runs without word breaks (e.g. Thai, long identifiers) fall back to grapheme boundaries so
+//! a fragment never splits a user-perceived character. Near-duplicate fragments (e.g. the
+//! same log line matched three times) are collapsed so multiple snippets stay diverse.
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+pub use core_types::config::HighlightStyle;
+
+/// Opens a match in [`HighlightStyle::Markers`] output.
+pub const MARK_START: char = '\u{2}';
+/// Closes a match in [`HighlightStyle::Markers`] output.
+pub const MARK_END: char = '\u{3}';
+
+/// Knobs for fragment size and boundary handling.
+#[derive(Debug, Clone)]
+pub struct SnippetOptions {
+    /// Target fragment length in characters (boundary snapping may shift it slightly).
+    pub max_chars: usize,
+    /// Maximum number of fragments returned per document.
+    pub max_fragments: usize,
+    /// Prefer sentence boundaries over word boundaries when they fall inside the window.
+    pub sentence_boundaries: bool,
+    /// Markup applied by [`best_snippet`].
+    pub highlight: HighlightStyle,
+    /// Skip a fragment whose word overlap with a better, already kept fragment reaches this
+    /// ratio (1.0 = only fragments identical after normalization; 0 = keep all).
+    pub dedup_similarity: f32,
+}
+
+impl Default for SnippetOptions {
+    fn default() -> Self {
+        Self {
+            max_chars: 160,
+            max_fragments: 1,
+            sentence_boundaries: true,
+            highlight: HighlightStyle::Plain,
+            dedup_similarity: DEFAULT_DEDUP_SIMILARITY,
+        }
+    }
+}
+
+/// Default for [`SnippetOptions::dedup_similarity`].
+pub const DEFAULT_DEDUP_SIMILARITY: f32 = 0.9;
+
+/// One snippet fragment cut from the source text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment {
+    pub text: String,
+    /// Byte range of `text` within the source.
+    pub range: Range<usize>,
+    /// Byte ranges of term matches, relative to `text`.
+    pub highlights: Vec<Range<usize>>,
+    /// Number of term matches inside the fragment.
+    pub score: f32,
+}
+
+impl Fragment {
+    /// `text` with its highlights marked up. Source text is escaped (`Html`) or stripped of
+    /// marker characters (`Markers`) so only the tags inserted here are interpreted.
+    pub fn render(&self, style: HighlightStyle) -> String {
+        let (open, close) = match style {
+            HighlightStyle::Plain => return self.text.clone(),
+            HighlightStyle::Html => ("<b>", "</b>"),
+            HighlightStyle::Markers => ("\u{2}", "\u{3}"),
+        };
+        let mut out = String::with_capacity(self.text.len() + 16);
+        let mut pos = 0;
+        for h in &self.highlights {
+            if h.start < pos {
+                continue;
+            }
+            push_source(&mut out, &self.text[pos..h.start], style);
+            out.push_str(open);
+            push_source(&mut out, &self.text[h.clone()], style);
+            out.push_str(close);
+            pos = h.end;
+        }
+        push_source(&mut out, &self.text[pos..], style);
+        out
+    }
+}
+
+fn push_source(out: &mut String, s: &str, style: HighlightStyle) {
+    match style {
+        HighlightStyle::Plain => out.push_str(s),
+        HighlightStyle::Html => {
+            for c in s.chars() {
+                match c {
+                    '&' => out.push_str("&amp;"),
+                    '<' => out.push_str("&lt;"),
+                    '>' => out.push_str("&gt;"),
+                    '"' => out.push_str("&quot;"),
+                    '\'' => out.push_str("&#39;"),
+                    c => out.push(c),
+                }
+            }
+        }
+        HighlightStyle::Markers => {
+            out.extend(s.chars().filter(|&c| c != MARK_START && c != MARK_END));
+        }
+    }
+}
+
+/// Best fragment for `terms`, if any term occurs in `text`, rendered with `opts.highlight`.
+pub fn best_snippet(text: &str, terms: &[String], opts: &SnippetOptions) -> Option<String> {
+    let opts = SnippetOptions {
+        max_fragments: 1,
+        ..opts.clone()
+    };
+    generate_fragments(text, terms, &opts)
+        .into_iter()
+        .next()
+        .map(|f| f.render(opts.highlight))
+}
+
+/// Up to `opts.max_fragments` rendered fragments, best first.
+pub fn best_snippets(text: &str, terms: &[String], opts: &SnippetOptions) -> Vec<String> {
+    generate_fragments(text, terms, opts)
+        .into_iter()
+        .map(|f| f.render(opts.highlight))
+        .collect()
+}
+
+/// Up to `opts.max_fragments` fragments, best (most matches) first, without near-duplicates.
+pub fn generate_fragments(text: &str, terms: &[String], opts: &SnippetOptions) -> Vec<Fragment> {
+    if text.is_empty() || terms.is_empty() || opts.max_fragments == 0 || opts.max_chars == 0 {
+        return Vec::new();
+    }
+    let matches = find_matches(text, terms);
+    if matches.is_empty() {
+        return Vec::new();
+    }
+
+    let bounds = Boundaries::new(text, opts.sentence_boundaries);
+    let mut candidates: Vec<Fragment> = Vec::new();
+    let mut covered_until = 0usize;
+    for m in &matches {
+        if m.start < covered_until {
+            continue;
+        }
+        let range = fragment_range(text, m.clone(), opts.max_chars, &bounds);
+        covered_until = range.end;
+        candidates.push(to_fragment(text, range, &matches));
+    }
+
+    candidates.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.range.start.cmp(&b.range.start))
+    });
+    if opts.dedup_similarity > 0.0 {
+        candidates = dedup_similar(candidates, opts);
+    }
+    candidates.truncate(opts.max_fragments);
+    candidates
+}
+
+/// Keep fragments in order, skipping any too similar to one already kept.
+fn dedup_similar(candidates: Vec<Fragment>, opts: &SnippetOptions) -> Vec<Fragment> {
+    let mut kept: Vec<(Fragment, HashSet<String>)> = Vec::new();
+    for fragment in candidates {
+        if kept.len() == opts.max_fragments {
+            break;
+        }
+        let words = normalized_words(&fragment.text);
+        if kept
+            .iter()
+            .all(|(_, seen)| similarity(seen, &words) < opts.dedup_similarity)
+        {
+            kept.push((fragment, words));
+        }
+    }
+    kept.into_iter().map(|(fragment, _)| fragment).collect()
+}
+
+/// Lowercased words with digit runs folded, so lines differing only in timestamps or
+/// counters compare equal.
+fn normalized_words(text: &str) -> HashSet<String> {
+    text.unicode_words()
+        .map(|w| {
+            let mut out = String::with_capacity(w.len());
+            for c in w.chars().flat_map(char::to_lowercase) {
+                let c = if c.is_ascii_digit() { '0' } else { c };
+                if !(c == '0' && out.ends_with('0')) {
+                    out.push(c);
+                }
+            }
+            out
+        })
+        .collect()
+}
+
+/// Jaccard overlap of two word sets (1.0 when both are empty).
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 1.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+/// Byte ranges of whole-word, case-insensitive term matches. Falls back to substring search
+/// for scripts where terms do not align with word segments (e.g. CJK compounds).
+fn find_matches(text: &str, terms: &[String]) -> Vec<Range<usize>> {
+    let terms: Vec<String> = terms
+        .iter()
+        .map(|t| t.trim().to_lowercase())
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let mut out: Vec<Range<usize>> = text
+        .unicode_word_indices()
+        .filter(|(_, w)| terms.iter().any(|t| w.to_lowercase() == *t))
+        .map(|(i, w)| i..i + w.len())
+        .collect();
+
+    if out.is_empty() {
+        // Lowercasing can change byte lengths; only search the folded text when it is safe.
+        let lowered = text.to_lowercase();
+        let haystack = if lowered.len() == text.len() {
+            lowered.as_str()
+        } else {
+            text
+        };
+        for t in &terms {
+            out.extend(
+                haystack
+                    .match_indices(t.as_str())
+                    .map(|(i, s)| i..i + s.len()),
+            );
+        }
+        out.sort_by_key(|r| r.start);
+        out.dedup_by(|b, a| b.start < a.end);
+    }
+    out
+}
+
+/// Sorted boundary offsets (each includes `0` and `text.len()`).
+struct Boundaries {
+    words: Vec<usize>,
+    sentences: Vec<usize>,
+    graphemes: Vec<usize>,
+}
+
+impl Boundaries {
+    fn new(text: &str, sentences: bool) -> Self {
+        let with_end = |mut v: Vec<usize>| {
+            v.push(text.len());
+            v
+        };
+        Self {
+            words: with_end(text.split_word_bound_indices().map(|(i, _)| i).collect()),
+            sentences: if sentences {
+                with_end(
+                    text.split_sentence_bound_indices()
+                        .map(|(i, _)| i)
+                        .collect(),
+                )
+            } else {
+                Vec::new()
+            },
+            graphemes: with_end(text.grapheme_indices(true).map(|(i, _)| i).collect()),
+        }
+    }
+}
+
+/// Largest boundary in `[lo, hi]`.
+fn last_in(bounds: &[usize], lo: usize, hi: usize) -> Option<usize> {
+    let idx = bounds.partition_point(|&b| b <= hi);
+    bounds[..idx].last().copied().filter(|&b| b >= lo)
+}
+
+/// Smallest boundary in `[lo, hi]`.
+fn first_in(bounds: &[usize], lo: usize, hi: usize) -> Option<usize> {
+    let idx = bounds.partition_point(|&b| b < lo);
+    bounds.get(idx).copied().filter(|&b| b <= hi)
+}
+
+/// Boundary nearest to `pos` within `slack` bytes, clamped to `[lo, hi]`.
+fn nearest(bounds: &[usize], pos: usize, slack: usize, lo: usize, hi: usize) -> Option<usize> {
+    let before = last_in(bounds, pos.saturating_sub(slack).max(lo), pos);
+    let after = first_in(bounds, pos, (pos + slack).min(hi));
+    match (before, after) {
+        (Some(b), Some(a)) => Some(if pos - b <= a - pos { b } else { a }),
+        (b, a) => b.or(a),
+    }
+}
+
+fn char_back(text: &str, pos: usize, n: usize) -> usize {
+    if n == 0 {
+        return pos;
+    }
+    text[..pos]
+        .char_indices()
+        .rev()
+        .nth(n - 1)
+        .map_or(0, |(i, _)| i)
+}
+
+fn char_fwd(text: &str, pos: usize, n: usize) -> usize {
+    text[pos..]
+        .char_indices()
+        .nth(n)
+        .map_or(text.len(), |(i, _)| pos + i)
+}
+
+/// Window of roughly `max_chars` around `hit`, snapped so it never splits a word.
+fn fragment_range(
+    text: &str,
+    hit: Range<usize>,
+    max_chars: usize,
+    bounds: &Boundaries,
+) -> Range<usize> {
+    let hit_chars = text[hit.clone()].chars().count();
+    let lead = max_chars.saturating_sub(hit_chars) / 2;
+    let raw_start = char_back(text, hit.start, lead);
+    let raw_end = char_fwd(text, raw_start, max_chars).max(hit.end);
+    // How far (in bytes) a word edge may move the window before we settle for a grapheme edge.
+    let slack = (raw_end - raw_start) / 4;
+
+    let start = last_in(&bounds.sentences, raw_start, hit.start)
+        .or_else(|| nearest(&bounds.words, raw_start, slack, 0, hit.start))
+        .or_else(|| nearest(&bounds.graphemes, raw_start, slack, 0, hit.start))
+        .unwrap_or(hit.start);
+    let end = last_in(&bounds.sentences, hit.end, raw_end)
+        .filter(|&e| e > hit.end)
+        .or_else(|| nearest(&bounds.words, raw_end, slack, hit.end, text.len()))
+        .or_else(|| nearest(&bounds.graphemes, raw_end, slack, hit.end, text.len()))
+        .unwrap_or(hit.end);
+
+    // Trim whitespace introduced by sentence/word edges.
+    let slice = &text[start..end];
+    let lead_ws = slice.len() - slice.trim_start().len();
+    let trail_ws = slice.len() - slice.trim_end().len();
+    start + lead_ws..end - trail_ws
+}
+
+fn to_fragment(text: &str, range: Range<usize>, matches: &[Range<usize>]) -> Fragment {
+    let highlights: Vec<Range<usize>> = matches
+        .iter()
+        .filter(|m| m.start >= range.start && m.end <= range.end)
+        .map(|m| m.start - range.start..m.end - range.start)
+        .collect();
+    Fragment {
+        text: text[range.clone()].to_string(),
+        score: highlights.len() as f32,
+        range,
+        highlights,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn window_snaps_to_word_boundaries() {
+        let text = "alphabetical bravado charlatan delta echoing foxtrotting golfing hotelier";
+        let opts = SnippetOptions {
+            max_chars: 24,
+            sentence_boundaries: false,
+            ..SnippetOptions::default()
+        };
+        let snippet = best_snippet(text, &terms(&["delta"]), &opts).unwrap();
+        assert!(snippet.contains("delta"));
+        let words: Vec<&str> = text.split(' ').collect();
+        for w in snippet.split(' ') {
+            assert!(words.contains(&w), "fragment split a word: {snippet:?}");
+        }
+    }
+
+    #[test]
+    fn prefers_sentence_boundaries_inside_window() {
+        let text = "First sentence is here. The needle sits in this one. Trailing words follow.";
+        let opts = SnippetOptions {
+            max_chars: 40,
+            ..SnippetOptions::default()
+        };
+        let snippet = best_snippet(text, &terms(&["needle"]), &opts).unwrap();
+        assert!(snippet.starts_with("The needle"), "{snippet:?}");
+    }
+
+    #[test]
+    fn cjk_text_keeps_characters_whole() {
+        let text = "東京は日本の首都です。大阪は大きな都市です。";
+        let opts = SnippetOptions {
+            max_chars: 8,
+            ..SnippetOptions::default()
+        };
+        let snippet = best_snippet(text, &terms(&["首都"]), &opts).unwrap();
+        assert!(snippet.contains("首都"));
+        assert!(text.contains(&snippet));
+    }
+
+    #[test]
+    fn multiple_fragments_ranked_by_matches() {
+        let text = "cat. dog dog here. cat again and dog.";
+        let opts = SnippetOptions {
+            max_chars: 12,
+            max_fragments: 3,
+            sentence_boundaries: false,
+            highlight: HighlightStyle::Plain,
+            dedup_similarity: 0.0,
+        };
+        let frags = generate_fragments(text, &terms(&["dog"]), &opts);
+        assert!(!frags.is_empty());
+        assert!(frags.windows(2).all(|w| w[0].score >= w[1].score));
+        for f in &frags {
+            for h in &f.highlights {
+                assert_eq!(&f.text[h.clone()], "dog");
+            }
+        }
+    }
+
+    #[test]
+    fn identical_matching_lines_yield_one_snippet() {
+        let line = "10:00:01 error: connection refused by upstream server";
+        let text = [line, line, line].join("\n");
+        let opts = |dedup_similarity| SnippetOptions {
+            max_chars: 50,
+            max_fragments: 3,
+            dedup_similarity,
+            ..SnippetOptions::default()
+        };
+        let snippets = best_snippets(&text, &terms(&["refused"]), &opts(0.0));
+        assert_eq!(snippets.len(), 3);
+
+        let snippets = best_snippets(&text, &terms(&["refused"]), &opts(1.0));
+        assert_eq!(snippets.len(), 1);
+        assert!(snippets[0].contains("connection refused"));
+
+        // Lines differing only in their timestamp are near-identical too.
+        let text = "10:00:01 error: connection refused\n10:00:02 error: connection refused";
+        assert_eq!(
+            best_snippets(text, &terms(&["refused"]), &opts(0.9)).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn html_style_escapes_source_but_keeps_highlight_tags() {
+        let text = r#"Run <script>alert("x")</script> & find the needle here."#;
+        let opts = |highlight| SnippetOptions {
+            max_chars: 200,
+            highlight,
+            ..SnippetOptions::default()
+        };
+        let needle = terms(&["needle"]);
+
+        let html = best_snippet(text, &needle, &opts(HighlightStyle::Html)).unwrap();
+        assert_eq!(
+            html,
+            "Run &lt;script&gt;alert(&quot;x&quot;)&lt;/script&gt; &amp; find the <b>needle</b> here."
+        );
+
+        let plain = best_snippet(text, &needle, &opts(HighlightStyle::Plain)).unwrap();
+        assert_eq!(plain, text);
+
+        let spoofed = "fake \u{2}mark\u{3} then needle";
+        let markers = best_snippet(spoofed, &needle, &opts(HighlightStyle::Markers)).unwrap();
+        assert_eq!(markers, "fake mark then \u{2}needle\u{3}");
+    }
+
+    #[test]
+    fn no_match_yields_nothing() {
+        assert!(
+            best_snippet(
+                "hello world",
+                &terms(&["absent"]),
+                &SnippetOptions::default()
+            )
+            .is_none()
+        );
+    }
+}
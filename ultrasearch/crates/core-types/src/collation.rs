@@ -0,0 +1,191 @@
+//! Locale-aware ordering of file names for name-sorted results.
+//!
+//! A compact multi-level collation in the spirit of the Unicode Collation Algorithm, without
+//! its tables: strings compare first by base letters (case and diacritics ignored), then by
+//! diacritics (unaccented first), then by case (lowercase first). Punctuation and spaces sort
+//! before digits, digits before letters. Folding covers Latin-1 and Latin Extended-A; other
+//! scripts compare by code point within their level. A few locales reorder letters that
+//! their alphabets treat as distinct (Swedish `å ä ö`, Danish/Norwegian `æ ø å` after `z`).
+
+use std::cmp::Ordering;
+
+/// Primary weights for punctuation/symbols are their code points (all below this).
+const DIGIT_BASE: u32 = 0x1000_0000;
+const LETTER_BASE: u32 = 0x2000_0000;
+
+/// Letters sorted after `z`, grouped by primary weight; later entries in a group differ
+/// from the first only at the diacritic level.
+type Tailoring = &'static [&'static [char]];
+
+const SWEDISH: Tailoring = &[&['å'], &['ä', 'æ'], &['ö', 'ø']];
+const DANISH_NORWEGIAN: Tailoring = &[&['æ', 'ä'], &['ø', 'ö'], &['å']];
+
+/// Compares strings under one locale's rules.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Collator {
+    tailoring: Tailoring,
+}
+
+/// Precomputed comparison key; ordering keys equals ordering the strings with
+/// [`Collator::compare`]. Useful with `sort_by_cached_key`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SortKey {
+    primary: Vec<u32>,
+    secondary: Vec<u32>,
+    tertiary: Vec<bool>,
+    /// Final tie-break so distinct strings never compare equal.
+    raw: String,
+}
+
+impl Collator {
+    /// Collator for a BCP 47 tag or POSIX-style locale (`sv`, `sv-SE`, `nb_NO.UTF-8`).
+    /// Unknown or empty locales use the root order.
+    pub fn new(locale: &str) -> Self {
+        let lang = locale
+            .split(['-', '_', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let tailoring = match lang.as_str() {
+            "sv" | "fi" => SWEDISH,
+            "da" | "nb" | "nn" | "no" => DANISH_NORWEGIAN,
+            _ => &[],
+        };
+        Self { tailoring }
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        self.sort_key(a).cmp(&self.sort_key(b))
+    }
+
+    pub fn sort_key(&self, s: &str) -> SortKey {
+        let mut key = SortKey {
+            primary: Vec::with_capacity(s.len()),
+            secondary: Vec::with_capacity(s.len()),
+            tertiary: Vec::with_capacity(s.len()),
+            raw: s.to_string(),
+        };
+        for c in s.chars() {
+            let upper = c.is_uppercase();
+            for lc in c.to_lowercase() {
+                if let Some((group, pos)) = self.tailored(lc) {
+                    key.push(LETTER_BASE + (('z' as u32) << 2) + 1 + group, pos, upper);
+                    continue;
+                }
+                let (base, accented) = fold(lc);
+                let secondary = if accented { lc as u32 } else { 0 };
+                for b in base.chars() {
+                    key.push(primary_weight(b), secondary, upper);
+                }
+            }
+        }
+        key
+    }
+
+    fn tailored(&self, c: char) -> Option<(u32, u32)> {
+        self.tailoring
+            .iter()
+            .enumerate()
+            .find_map(|(group, letters)| {
+                let pos = letters.iter().position(|&l| l == c)?;
+                Some((group as u32, pos as u32))
+            })
+    }
+}
+
+impl SortKey {
+    fn push(&mut self, primary: u32, secondary: u32, upper: bool) {
+        self.primary.push(primary);
+        self.secondary.push(secondary);
+        self.tertiary.push(upper);
+    }
+}
+
+fn primary_weight(c: char) -> u32 {
+    if c.is_ascii_digit() {
+        DIGIT_BASE + c as u32
+    } else if c.is_alphabetic() {
+        LETTER_BASE + ((c as u32) << 2)
+    } else {
+        c as u32
+    }
+}
+
+/// Base letters of a lowercase character and whether it carried a diacritic.
+fn fold(c: char) -> (std::borrow::Cow<'static, str>, bool) {
+    let base = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' | 'đ' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => "s",
+        'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        'ß' => "ss",
+        'æ' => "ae",
+        'œ' => "oe",
+        'þ' => "th",
+        _ => return (c.to_string().into(), false),
+    };
+    (base.into(), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(collator: &Collator, names: &[&str]) -> Vec<String> {
+        let mut out: Vec<String> = names.iter().map(|s| s.to_string()).collect();
+        out.sort_by_cached_key(|s| collator.sort_key(s));
+        out
+    }
+
+    #[test]
+    fn root_order_ignores_case_and_accents_at_first_level() {
+        let names = ["Zebra", "apple", "Äpfel"];
+        let mut bytes = names.to_vec();
+        bytes.sort();
+        assert_eq!(bytes, vec!["Zebra", "apple", "Äpfel"]);
+
+        assert_eq!(
+            sorted(&Collator::default(), &names),
+            vec!["Äpfel", "apple", "Zebra"]
+        );
+    }
+
+    #[test]
+    fn accents_then_case_break_ties() {
+        let c = Collator::default();
+        assert_eq!(c.compare("resume", "résumé"), Ordering::Less);
+        assert_eq!(c.compare("résumé", "Resume"), Ordering::Greater);
+        assert_eq!(c.compare("apple", "Apple"), Ordering::Less);
+        assert_eq!(c.compare("straße", "strasse"), Ordering::Greater);
+        assert_eq!(c.compare("strasse", "strassen"), Ordering::Less);
+        assert_eq!(c.compare("_notes", "1 notes"), Ordering::Less);
+    }
+
+    #[test]
+    fn swedish_sorts_a_ring_and_umlauts_after_z() {
+        let names = ["öl", "apple", "Äpfel", "zebra", "år"];
+        assert_eq!(
+            sorted(&Collator::new("sv-SE"), &names),
+            vec!["apple", "zebra", "år", "Äpfel", "öl"]
+        );
+        assert_eq!(
+            sorted(&Collator::new("de"), &names),
+            vec!["Äpfel", "apple", "år", "öl", "zebra"]
+        );
+    }
+}
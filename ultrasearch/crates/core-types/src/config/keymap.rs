@@ -0,0 +1,236 @@
+//! Keyboard bindings for the desktop UI.
+//!
+//! The UI binds keys and renders its help panel from the same resolved keymap: the built-in
+//! defaults below with any `[keymap.overrides]` from config applied on top.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// User overrides, keyed by action name (e.g. `FocusSearch = ["ctrl-f"]`).
+///
+/// An override replaces every default keystroke of that action; unknown actions and
+/// malformed keystrokes are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeymapConfig {
+    #[serde(default)]
+    pub overrides: BTreeMap<String, Vec<String>>,
+}
+
+/// One action with its keystrokes in GPUI syntax (`ctrl-shift-c`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindingEntry {
+    pub action: &'static str,
+    pub group: &'static str,
+    pub description: &'static str,
+    pub keys: Vec<String>,
+}
+
+impl KeyBindingEntry {
+    /// Keystrokes formatted for display, e.g. `Ctrl/Cmd+K` or `F1 / Ctrl+/`.
+    pub fn display_keys(&self) -> String {
+        display_keys(&self.keys)
+    }
+}
+
+/// Help panel sections, in display order.
+pub const KEYMAP_GROUPS: &[&str] = &["Navigation", "Actions", "System"];
+
+const DEFAULT_BINDINGS: &[(&str, &str, &str, &[&str])] = &[
+    (
+        "FocusSearch",
+        "Navigation",
+        "Focus search",
+        &["ctrl-k", "cmd-k"],
+    ),
+    ("SubmitSearch", "Navigation", "Run search", &["enter"]),
+    ("ClearSearch", "Navigation", "Clear search", &["escape"]),
+    ("SelectNext", "Navigation", "Next result", &["down"]),
+    ("SelectPrev", "Navigation", "Previous result", &["up"]),
+    (
+        "ModeMetadata",
+        "Navigation",
+        "Name search mode",
+        &["ctrl-1", "cmd-1"],
+    ),
+    (
+        "ModeMixed",
+        "Navigation",
+        "Mixed search mode",
+        &["ctrl-2", "cmd-2"],
+    ),
+    (
+        "ModeContent",
+        "Navigation",
+        "Content search mode",
+        &["ctrl-3", "cmd-3"],
+    ),
+    (
+        "OpenSelected",
+        "Actions",
+        "Open selected",
+        &["ctrl-o", "cmd-o"],
+    ),
+    (
+        "CopySelectedPath",
+        "Actions",
+        "Copy path",
+        &["ctrl-c", "cmd-c"],
+    ),
+    (
+        "CopySelectedFile",
+        "Actions",
+        "Copy file",
+        &["ctrl-shift-c"],
+    ),
+    (
+        "OpenContainingFolder",
+        "Actions",
+        "Open folder",
+        &["ctrl-shift-o"],
+    ),
+    ("ShowProperties", "Actions", "Properties", &["alt-enter"]),
+    (
+        "ToggleShortcuts",
+        "System",
+        "Toggle help",
+        &["f1", "ctrl-/", "cmd-/"],
+    ),
+    ("MinimizeToTray", "System", "Minimize to tray", &["ctrl-m"]),
+    ("QuitApp", "System", "Quit", &["ctrl-q", "cmd-q"]),
+];
+
+const MODIFIERS: &[&str] = &[
+    "ctrl", "cmd", "alt", "shift", "fn", "super", "win", "platform",
+];
+
+impl KeymapConfig {
+    /// Default bindings with overrides applied, in display order.
+    pub fn resolve(&self) -> Vec<KeyBindingEntry> {
+        DEFAULT_BINDINGS
+            .iter()
+            .map(|&(action, group, description, keys)| {
+                let keys = match self.overrides.get(action) {
+                    Some(custom) => custom
+                        .iter()
+                        .map(|k| k.trim().to_ascii_lowercase())
+                        .filter(|k| is_valid_keystroke(k))
+                        .collect(),
+                    None => keys.iter().map(|k| k.to_string()).collect(),
+                };
+                KeyBindingEntry {
+                    action,
+                    group,
+                    description,
+                    keys,
+                }
+            })
+            .collect()
+    }
+}
+
+/// `true` for `key` or `mod-...-key` with known modifiers, e.g. `ctrl-shift-c` or `ctrl--`.
+pub fn is_valid_keystroke(keystroke: &str) -> bool {
+    let (mods, key) = split_keystroke(keystroke);
+    !key.is_empty() && mods.iter().all(|m| MODIFIERS.contains(m))
+}
+
+fn split_keystroke(keystroke: &str) -> (Vec<&str>, &str) {
+    let mut mods = Vec::new();
+    let mut rest = keystroke;
+    while let Some((head, tail)) = rest.split_once('-') {
+        if head.is_empty() || tail.is_empty() {
+            break;
+        }
+        mods.push(head);
+        rest = tail;
+    }
+    (mods, rest)
+}
+
+/// Render one keystroke, e.g. `ctrl-shift-c` -> `Ctrl+Shift+C`.
+pub fn display_keystroke(keystroke: &str) -> String {
+    let (mods, key) = split_keystroke(keystroke);
+    mods.into_iter()
+        .chain(std::iter::once(key))
+        .map(capitalize)
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// Render an action's keystrokes; a `ctrl-x`/`cmd-x` pair collapses to `Ctrl/Cmd+X`.
+pub fn display_keys(keys: &[String]) -> String {
+    let mut out = Vec::new();
+    for key in keys {
+        if let Some(rest) = key.strip_prefix("cmd-")
+            && keys.iter().any(|k| k.strip_prefix("ctrl-") == Some(rest))
+        {
+            continue;
+        }
+        match key.strip_prefix("ctrl-") {
+            Some(rest) if keys.iter().any(|k| k.strip_prefix("cmd-") == Some(rest)) => {
+                out.push(format!("Ctrl/Cmd+{}", display_keystroke(rest)));
+            }
+            _ => out.push(display_keystroke(key)),
+        }
+    }
+    out.join(" / ")
+}
+
+fn capitalize(part: &str) -> String {
+    let mut chars = part.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry<'a>(entries: &'a [KeyBindingEntry], action: &str) -> &'a KeyBindingEntry {
+        entries.iter().find(|e| e.action == action).unwrap()
+    }
+
+    #[test]
+    fn defaults_render_like_the_help_panel() {
+        let entries = KeymapConfig::default().resolve();
+        assert_eq!(entry(&entries, "FocusSearch").display_keys(), "Ctrl/Cmd+K");
+        assert_eq!(
+            entry(&entries, "ToggleShortcuts").display_keys(),
+            "F1 / Ctrl/Cmd+/"
+        );
+        assert_eq!(
+            entry(&entries, "CopySelectedFile").display_keys(),
+            "Ctrl+Shift+C"
+        );
+        assert!(entries.iter().all(|e| KEYMAP_GROUPS.contains(&e.group)));
+    }
+
+    #[test]
+    fn override_changes_binding_and_unknown_actions_are_ignored() {
+        let mut cfg = KeymapConfig::default();
+        cfg.overrides.insert(
+            "FocusSearch".into(),
+            vec!["Ctrl-F".into(), "bogus-x".into()],
+        );
+        cfg.overrides
+            .insert("LaunchRockets".into(), vec!["ctrl-r".into()]);
+
+        let entries = cfg.resolve();
+        let focus = entry(&entries, "FocusSearch");
+        assert_eq!(focus.keys, vec!["ctrl-f".to_string()]);
+        assert_eq!(focus.display_keys(), "Ctrl+F");
+        assert_eq!(entries.len(), DEFAULT_BINDINGS.len());
+        assert!(entries.iter().all(|e| e.action != "LaunchRockets"));
+        assert_eq!(entry(&entries, "QuitApp").display_keys(), "Ctrl/Cmd+Q");
+    }
+
+    #[test]
+    fn keystroke_parsing_handles_minus_key() {
+        assert!(is_valid_keystroke("ctrl--"));
+        assert_eq!(display_keystroke("ctrl--"), "Ctrl+-");
+        assert!(!is_valid_keystroke(""));
+        assert!(!is_valid_keystroke("hyper-k"));
+    }
+}
@@ -8,6 +8,9 @@ use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use std::sync::RwLock;
 
+pub mod keymap;
+pub use keymap::KeymapConfig;
+
 /// Global configuration root loaded from `.env` + `config/config.toml`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -31,6 +34,24 @@ pub struct AppConfig {
     pub extract: ExtractSection,
     #[serde(default)]
     pub semantic: SemanticSection,
+    #[serde(default)]
+    pub indexing: IndexingSection,
+    #[serde(default)]
+    pub search: SearchSection,
+    #[serde(default)]
+    pub content_analysis: ContentAnalysisSection,
+    #[serde(default)]
+    pub keymap: KeymapConfig,
+    #[serde(default)]
+    pub ipc: IpcSection,
+    #[serde(default)]
+    pub audit: AuditSection,
+    #[serde(default)]
+    pub content_merge: ContentMergeSection,
+    #[serde(default)]
+    pub integrity: IntegritySection,
+    #[serde(default)]
+    pub meta_store: MetaStoreSection,
 }
 
 /// Load config, creating a default config file if none exists at the target path.
@@ -64,6 +85,15 @@ impl Default for AppConfig {
             paths: PathsSection::default(),
             extract: ExtractSection::default(),
             semantic: SemanticSection::default(),
+            indexing: IndexingSection::default(),
+            search: SearchSection::default(),
+            content_analysis: ContentAnalysisSection::default(),
+            keymap: KeymapConfig::default(),
+            ipc: IpcSection::default(),
+            audit: AuditSection::default(),
+            content_merge: ContentMergeSection::default(),
+            integrity: IntegritySection::default(),
+            meta_store: MetaStoreSection::default(),
             volumes: Vec::new(),
             content_index_volumes: Vec::new(),
         }
@@ -116,6 +146,13 @@ pub struct LoggingSection {
     pub max_size_mb: u64,
     #[serde(default = "default_log_retain")]
     pub retain: u32,
+    /// OTLP/HTTP collector traces are exported to, e.g. `http://localhost:4318/v1/traces`.
+    /// Needs a service built with the `otel` feature; `None` exports nothing.
+    #[serde(default)]
+    pub otlp_endpoint: Option<String>,
+    /// Fraction of traces exported, from 0.0 to 1.0.
+    #[serde(default = "default_otlp_sample_ratio")]
+    pub otlp_sample_ratio: f64,
 }
 
 impl Default for LoggingSection {
@@ -127,6 +164,8 @@ impl Default for LoggingSection {
             roll: default_log_roll(),
             max_size_mb: default_log_max_size(),
             retain: default_log_retain(),
+            otlp_endpoint: None,
+            otlp_sample_ratio: default_otlp_sample_ratio(),
         }
     }
 }
@@ -149,6 +188,9 @@ fn default_log_max_size() -> u64 {
 fn default_log_retain() -> u32 {
     7
 }
+fn default_otlp_sample_ratio() -> f64 {
+    1.0
+}
 
 /// Metrics configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -257,6 +299,52 @@ pub struct SchedulerSection {
     pub content_batch_size: u64,
     #[serde(default)]
     pub power_save_mode: bool,
+    /// How background work behaves on battery power.
+    #[serde(default)]
+    pub power_profile: PowerProfile,
+    /// Under `Balanced` (with `power_save_mode`), metadata indexing continues on battery
+    /// while the charge is at least this percentage.
+    #[serde(default = "default_battery_floor_pct")]
+    pub battery_floor_pct: u8,
+    /// Local-time windows in which metadata/content indexing may run; empty means always.
+    #[serde(default)]
+    pub quiet_hours: Vec<IndexWindow>,
+    /// Recent scheduler decisions kept for `SchedulerTraceRequest` (0 = don't keep any).
+    #[serde(default = "default_decision_trace_len")]
+    pub decision_trace_len: usize,
+    /// Index files under the foreground application's working directory ahead of the rest
+    /// of the content queue.
+    #[serde(default)]
+    pub follow_foreground: bool,
+    /// Seconds over which the content-job completion rate is smoothed for the status ETA;
+    /// 0 turns the ETA off.
+    #[serde(default = "default_eta_window_secs")]
+    pub eta_window_secs: u64,
+}
+
+/// Battery policy for non-critical indexing work.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerProfile {
+    /// Follow `power_save_mode`: on battery only metadata runs, and only above the floor.
+    #[default]
+    Balanced,
+    /// No metadata or content indexing at all while on battery.
+    AcOnly,
+    /// Ignore battery state.
+    Always,
+}
+
+/// A local-time window during which background indexing is allowed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexWindow {
+    /// Days the window starts on (`mon`..`sun`); empty means every day.
+    #[serde(default)]
+    pub days: Vec<String>,
+    /// Inclusive start, `HH:MM`.
+    pub start: String,
+    /// Exclusive end, `HH:MM`; earlier than `start` means the window spans midnight.
+    pub end: String,
 }
 
 impl Default for SchedulerSection {
@@ -271,10 +359,25 @@ impl Default for SchedulerSection {
             disk_busy_bytes_per_s: default_disk_busy(),
             content_batch_size: default_content_batch(),
             power_save_mode: true, // Default to enabled
+            power_profile: PowerProfile::default(),
+            battery_floor_pct: default_battery_floor_pct(),
+            quiet_hours: Vec::new(),
+            decision_trace_len: default_decision_trace_len(),
+            follow_foreground: false,
+            eta_window_secs: default_eta_window_secs(),
         }
     }
 }
 
+fn default_battery_floor_pct() -> u8 {
+    50
+}
+fn default_decision_trace_len() -> usize {
+    256
+}
+fn default_eta_window_secs() -> u64 {
+    300
+}
 fn default_idle_warm() -> u64 {
     15
 }
@@ -311,6 +414,9 @@ pub struct PathsSection {
     pub state_dir: String,
     #[serde(default = "default_jobs_dir")]
     pub jobs_dir: String,
+    /// User-assigned tags per document; kept outside the index dirs so resets keep them.
+    #[serde(default = "default_tags_file")]
+    pub tags_file: String,
 }
 
 impl Default for PathsSection {
@@ -320,6 +426,7 @@ impl Default for PathsSection {
             content_index: default_content_index_path(),
             state_dir: default_state_dir(),
             jobs_dir: default_jobs_dir(),
+            tags_file: default_tags_file(),
         }
     }
 }
@@ -333,6 +440,9 @@ fn default_content_index_path() -> String {
 fn default_state_dir() -> String {
     "{data_dir}/volumes".into()
 }
+fn default_tags_file() -> String {
+    "{data_dir}/tags.rkyv".into()
+}
 fn default_jobs_dir() -> String {
     "{data_dir}/jobs".into()
 }
@@ -348,6 +458,59 @@ pub struct ExtractSection {
     pub ocr_enabled: bool,
     #[serde(default = "default_ocr_max_pages")]
     pub ocr_max_pages: u64,
+    /// Extensionless file names (e.g. `Tiltfile`) to index as code, on top of the built-ins.
+    #[serde(default)]
+    pub extra_code_filenames: Vec<String>,
+    /// Extensionless file names to index as plain text, on top of the built-ins.
+    #[serde(default)]
+    pub extra_text_filenames: Vec<String>,
+    /// Detect each file's MIME type from its leading bytes during extraction, for `mime:`
+    /// queries that don't trust the extension.
+    #[serde(default)]
+    pub detect_mime: bool,
+    /// Index `.lnk` shortcuts by their target path, arguments, and working directory, so a
+    /// shortcut can be found by what it points to.
+    #[serde(default)]
+    pub index_shortcuts: bool,
+    /// Store identical extracted text (compared case- and whitespace-insensitively) once in
+    /// the content index; later files with that text reference the stored body.
+    #[serde(default)]
+    pub dedup_content: bool,
+    /// Parse `.eml` and `.mbox` files as email: subject and body become content, and sender,
+    /// recipients, subject and date become fields for `from:`/`to:`/`subject:`/`sent` queries.
+    #[serde(default)]
+    pub index_email: bool,
+    /// Read each document's own title (HTML `<title>`, Office `dc:title`, PDF `/Title`) into
+    /// a separate field for `title:` queries, so `doc1.pdf` titled "Annual Report" is found
+    /// by its title.
+    #[serde(default)]
+    pub index_titles: bool,
+    /// List the files inside `.zip` archives during scans and index them by name as virtual
+    /// children of the archive, e.g. `report.pdf` inside `backup.zip`.
+    #[serde(default)]
+    pub index_archives: bool,
+    /// Archives larger than this are not listed.
+    #[serde(default = "default_archive_max_bytes")]
+    pub archive_max_bytes: u64,
+    /// Most entries indexed per archive.
+    #[serde(default = "default_archive_max_entries")]
+    pub archive_max_entries: usize,
+    /// Windows only: extract formats without a built-in extractor (RTF, ODT, MSG) through the
+    /// system's IFilters, on at most this many STA threads at once. 0 disables IFilters.
+    #[serde(default)]
+    pub max_ifilter_threads: usize,
+    /// Extractors switched off by name (`simple-text`, `extractous`, `lnk`, `email`,
+    /// `ifilter`, `noop`), e.g. `["ifilter"]` on a server. Their files go to the next
+    /// extractor that handles them, or are skipped when none does.
+    #[serde(default)]
+    pub disabled_extractors: Vec<String>,
+    /// Retries of a file another process holds locked before it is dead-lettered as failed;
+    /// 0 dead-letters locked files at once. Access-denied files are never retried.
+    #[serde(default = "default_locked_retry_attempts")]
+    pub locked_retry_attempts: u32,
+    /// Delay before the first retry of a locked file; doubles per attempt, up to an hour.
+    #[serde(default = "default_locked_retry_backoff_secs")]
+    pub locked_retry_backoff_secs: u64,
 }
 
 impl Default for ExtractSection {
@@ -357,10 +520,38 @@ impl Default for ExtractSection {
             max_chars_per_file: default_max_chars(),
             ocr_enabled: false,
             ocr_max_pages: default_ocr_max_pages(),
+            extra_code_filenames: Vec::new(),
+            extra_text_filenames: Vec::new(),
+            detect_mime: false,
+            index_shortcuts: false,
+            dedup_content: false,
+            index_email: false,
+            index_titles: false,
+            index_archives: false,
+            archive_max_bytes: default_archive_max_bytes(),
+            archive_max_entries: default_archive_max_entries(),
+            max_ifilter_threads: 0,
+            disabled_extractors: Vec::new(),
+            locked_retry_attempts: default_locked_retry_attempts(),
+            locked_retry_backoff_secs: default_locked_retry_backoff_secs(),
         }
     }
 }
 
+fn default_locked_retry_attempts() -> u32 {
+    5
+}
+fn default_locked_retry_backoff_secs() -> u64 {
+    60
+}
+
+fn default_archive_max_bytes() -> u64 {
+    1024 * 1024 * 1024
+}
+fn default_archive_max_entries() -> usize {
+    10_000
+}
+
 fn default_max_bytes() -> u64 {
     16 * 1024 * 1024
 }
@@ -380,6 +571,21 @@ pub struct SemanticSection {
     pub model: String,
     #[serde(default = "default_semantic_index_dir")]
     pub index_dir: String,
+    /// How keyword and semantic result lists are combined into one ranking.
+    #[serde(default)]
+    pub fusion: FusionMethod,
+    /// RRF damping constant: a document at rank `r` (1-based) contributes `1 / (k + r)`.
+    #[serde(default = "default_rrf_k")]
+    pub rrf_k: u32,
+    /// Share of the weighted sum given to the (min-max normalized) semantic score.
+    #[serde(default = "default_semantic_weight")]
+    pub semantic_weight: f32,
+    /// Embedding batches computed at once, independent of extraction concurrency.
+    #[serde(default = "default_max_concurrent_embeddings")]
+    pub max_concurrent_embeddings: usize,
+    /// Extracted chunks handed to the model per `embed` call.
+    #[serde(default = "default_embedding_batch_size")]
+    pub embedding_batch_size: usize,
 }
 
 impl Default for SemanticSection {
@@ -388,16 +594,549 @@ impl Default for SemanticSection {
             enabled: false,
             model: default_semantic_model(),
             index_dir: default_semantic_index_dir(),
+            fusion: FusionMethod::default(),
+            rrf_k: default_rrf_k(),
+            semantic_weight: default_semantic_weight(),
+            max_concurrent_embeddings: default_max_concurrent_embeddings(),
+            embedding_batch_size: default_embedding_batch_size(),
         }
     }
 }
 
+/// Strategy for merging keyword and semantic hits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FusionMethod {
+    /// Normalize each list's scores to [0, 1] and blend them by `semantic_weight`.
+    #[default]
+    WeightedSum,
+    /// Reciprocal Rank Fusion: sum `1 / (rrf_k + rank)` over the lists; ignores raw scores.
+    Rrf,
+}
+
 fn default_semantic_model() -> String {
     "all-minilm-l12-v2".into()
 }
 fn default_semantic_index_dir() -> String {
     "{data_dir}/index/semantic".into()
 }
+fn default_rrf_k() -> u32 {
+    60
+}
+fn default_semantic_weight() -> f32 {
+    0.5
+}
+fn default_max_concurrent_embeddings() -> usize {
+    1
+}
+fn default_embedding_batch_size() -> usize {
+    32
+}
+
+/// Which files enumeration and ingest should consider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexingSection {
+    /// Index `$Recycle.Bin` and `System Volume Information` (excluded by default).
+    #[serde(default)]
+    pub include_recycle_bin: bool,
+    /// Safety cap on entries MFT enumeration emits per volume (0 = unlimited).
+    #[serde(default)]
+    pub max_entries_per_volume: u64,
+    /// Index mount-point directories instead of skipping them. Each volume is enumerated on
+    /// its own, so skipping (the default) avoids indexing a nested volume twice.
+    #[serde(default)]
+    pub follow_mount_points: bool,
+    /// Directories re-scanned by the polling watcher when the USN journal is unavailable
+    /// (disabled, non-NTFS, or no privilege). Empty = no fallback.
+    #[serde(default)]
+    pub poll_dirs: Vec<String>,
+    /// Seconds between polling scans.
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// Memory cap for the FST name overlay buffered between commits; exceeding it flushes
+    /// the overlay to a new on-disk segment early.
+    #[serde(default = "default_overlay_max_bytes")]
+    pub overlay_max_bytes: u64,
+    /// Read each file's owner SID during enumeration so queries can filter by owner. Costs a
+    /// security-descriptor lookup per file, so off by default.
+    #[serde(default)]
+    pub index_owner: bool,
+    /// Read each file's NTFS object ID during enumeration and remember it per file, so a
+    /// record number NTFS reused for a new file is indexed as that new file rather than as
+    /// a change to the old one. Costs an open and FSCTL per file, so off by default. Kept in
+    /// `object_ids.rkyv` under `paths.state_dir`.
+    #[serde(default)]
+    pub index_object_ids: bool,
+    /// Volumes enumerated at once per physical disk; partitions of one drive beyond this wait
+    /// their turn while separate drives run in parallel (0 = no limit).
+    #[serde(default = "default_max_jobs_per_physical_disk")]
+    pub max_jobs_per_physical_disk: usize,
+    /// Only act on structural changes from the change watcher: creates, deletes, renames, and
+    /// changes to directories. File content/attribute modifications are dropped, so modified
+    /// files are not re-extracted.
+    #[serde(default)]
+    pub watch_structure_only: bool,
+    /// Skip entries more than this many components below the volume root, e.g. files deep
+    /// inside `node_modules` (0 = unlimited). Directories up to the limit are still indexed.
+    #[serde(default)]
+    pub max_path_depth: usize,
+    /// Baseline for incremental catch-up: enumeration skips files last modified before this
+    /// Unix timestamp (seconds). Directories are always kept. Unset = index everything.
+    #[serde(default)]
+    pub only_modified_since: Option<i64>,
+    /// Folder-watch mode: index only these root folders (and everything below them) instead
+    /// of enumerating whole volumes; `volumes` is then ignored. Empty = volume-wide indexing.
+    #[serde(default)]
+    pub watch_folders: Vec<String>,
+    /// Collapse a file renamed several times within one change batch (A→B→C, e.g. during
+    /// `git checkout`) into a single rename to its final name.
+    #[serde(default = "default_true")]
+    pub coalesce_renames: bool,
+    /// Folder-watch mode only: skip files matched by `.gitignore` / `.ignore` files found
+    /// under `watch_folders`, on top of the other indexing filters.
+    #[serde(default)]
+    pub respect_gitignore: bool,
+    /// Disk budget for the metadata and content indexes together (0 = unlimited). When a
+    /// commit leaves them over it, the least-valuable entries are trimmed: content bodies
+    /// accessed longest ago first, then metadata for files modified longest ago.
+    #[serde(default)]
+    pub max_index_bytes: u64,
+    /// Previous names remembered per file when the change watcher sees it renamed, newest
+    /// first (0 = don't record renames). Kept in `former_names.rkyv` under `paths.state_dir`.
+    #[serde(default)]
+    pub former_names: usize,
+}
+
+impl Default for IndexingSection {
+    fn default() -> Self {
+        Self {
+            include_recycle_bin: false,
+            max_entries_per_volume: 0,
+            follow_mount_points: false,
+            poll_dirs: Vec::new(),
+            poll_interval_secs: default_poll_interval_secs(),
+            overlay_max_bytes: default_overlay_max_bytes(),
+            index_owner: false,
+            index_object_ids: false,
+            max_jobs_per_physical_disk: default_max_jobs_per_physical_disk(),
+            watch_structure_only: false,
+            max_path_depth: 0,
+            only_modified_since: None,
+            watch_folders: Vec::new(),
+            coalesce_renames: true,
+            respect_gitignore: false,
+            max_index_bytes: 0,
+            former_names: 0,
+        }
+    }
+}
+
+fn default_poll_interval_secs() -> u64 {
+    30
+}
+fn default_max_jobs_per_physical_disk() -> usize {
+    1
+}
+fn default_overlay_max_bytes() -> u64 {
+    32 * 1024 * 1024
+}
+
+/// Named-pipe server behavior.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpcSection {
+    /// First retry delay after the pipe server fails to create an instance; doubles per
+    /// consecutive failure.
+    #[serde(default = "default_accept_backoff_initial_ms")]
+    pub accept_backoff_initial_ms: u64,
+    /// Upper bound on the retry delay.
+    #[serde(default = "default_accept_backoff_max_ms")]
+    pub accept_backoff_max_ms: u64,
+    /// Consecutive failures after which the server is reported as down at error level.
+    #[serde(default = "default_accept_failure_threshold")]
+    pub accept_failure_threshold: u32,
+    /// Address for the gRPC server (`grpc` feature), e.g. `127.0.0.1:50051`; empty = off. The
+    /// named pipe is served either way.
+    #[serde(default)]
+    pub grpc_addr: String,
+}
+
+impl Default for IpcSection {
+    fn default() -> Self {
+        Self {
+            accept_backoff_initial_ms: default_accept_backoff_initial_ms(),
+            accept_backoff_max_ms: default_accept_backoff_max_ms(),
+            accept_failure_threshold: default_accept_failure_threshold(),
+            grpc_addr: String::new(),
+        }
+    }
+}
+
+fn default_accept_backoff_initial_ms() -> u64 {
+    250
+}
+fn default_accept_backoff_max_ms() -> u64 {
+    30_000
+}
+fn default_accept_failure_threshold() -> u32 {
+    10
+}
+
+/// Search audit log: one JSON line per search request, separate from the tracing log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditSection {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_audit_file")]
+    pub file: String,
+    /// Size at which the active file is rotated (timestamp-suffixed, never deleted).
+    #[serde(default = "default_audit_max_size")]
+    pub max_size_mb: u64,
+}
+
+impl Default for AuditSection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file: default_audit_file(),
+            max_size_mb: default_audit_max_size(),
+        }
+    }
+}
+
+fn default_audit_file() -> String {
+    "{data_dir}/log/audit.jsonl".into()
+}
+fn default_audit_max_size() -> u64 {
+    16
+}
+
+/// Segment merging for the content index: the log merge policy applied by every content
+/// writer, plus an explicit compaction run while the machine is deeply idle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentMergeSection {
+    /// Segments of similar size needed before a background merge of that level starts.
+    #[serde(default = "default_merge_min_num_segments")]
+    pub min_num_segments: usize,
+    /// Segments holding at least this many docs are never merged again.
+    #[serde(default = "default_merge_max_docs_before_merge")]
+    pub max_docs_before_merge: usize,
+    /// Segments below this doc count are all treated as one level.
+    #[serde(default = "default_merge_min_layer_size")]
+    pub min_layer_size: u32,
+    /// Log-size ratio between consecutive levels.
+    #[serde(default = "default_merge_level_log_size")]
+    pub level_log_size: f64,
+    /// Deleted-doc ratio at which a segment is merged even if its level isn't full.
+    #[serde(default = "default_merge_del_docs_ratio")]
+    pub del_docs_ratio_before_merge: f32,
+    /// Segment count that deep-idle compaction merges down to (0 = no idle compaction).
+    #[serde(default = "default_compact_max_segments")]
+    pub compact_max_segments: usize,
+    /// Minimum time between two idle compaction runs.
+    #[serde(default = "default_compact_interval_secs")]
+    pub compact_interval_secs: u64,
+}
+
+impl Default for ContentMergeSection {
+    fn default() -> Self {
+        Self {
+            min_num_segments: default_merge_min_num_segments(),
+            max_docs_before_merge: default_merge_max_docs_before_merge(),
+            min_layer_size: default_merge_min_layer_size(),
+            level_log_size: default_merge_level_log_size(),
+            del_docs_ratio_before_merge: default_merge_del_docs_ratio(),
+            compact_max_segments: default_compact_max_segments(),
+            compact_interval_secs: default_compact_interval_secs(),
+        }
+    }
+}
+
+/// Index self-check run before the service opens its indexes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegritySection {
+    /// Verify the indexes at startup and log a failing component (and a rebuild
+    /// recommendation) before serving.
+    #[serde(default)]
+    pub verify_on_startup: bool,
+    /// DocKeys sampled from the name FST and looked up in the meta index.
+    #[serde(default = "default_integrity_sample_keys")]
+    pub sample_keys: usize,
+    /// Also verify segment file checksums; reads every index file, so slow on large indexes.
+    #[serde(default)]
+    pub verify_checksums: bool,
+}
+
+impl Default for IntegritySection {
+    fn default() -> Self {
+        Self {
+            verify_on_startup: false,
+            sample_keys: default_integrity_sample_keys(),
+            verify_checksums: false,
+        }
+    }
+}
+
+fn default_integrity_sample_keys() -> usize {
+    1000
+}
+
+/// On-disk layout of the meta index's stored fields (names, paths, sizes, timestamps,
+/// flags). Fixed when the index is created.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetaStoreSection {
+    /// Compress stored fields with zstd instead of LZ4: smaller on disk, a little more CPU
+    /// per lookup.
+    #[serde(default)]
+    pub compress: bool,
+    /// Bytes of stored documents per compressed block; a lookup decompresses one block.
+    #[serde(default = "default_meta_store_block_size")]
+    pub block_size: usize,
+    /// zstd level, 1 (fastest) to 22 (smallest).
+    #[serde(default = "default_meta_store_compression_level")]
+    pub compression_level: i32,
+}
+
+impl Default for MetaStoreSection {
+    fn default() -> Self {
+        Self {
+            compress: false,
+            block_size: default_meta_store_block_size(),
+            compression_level: default_meta_store_compression_level(),
+        }
+    }
+}
+
+fn default_meta_store_block_size() -> usize {
+    64 * 1024
+}
+fn default_meta_store_compression_level() -> i32 {
+    3
+}
+
+fn default_merge_min_num_segments() -> usize {
+    8
+}
+fn default_merge_max_docs_before_merge() -> usize {
+    10_000_000
+}
+fn default_merge_min_layer_size() -> u32 {
+    10_000
+}
+fn default_merge_level_log_size() -> f64 {
+    0.75
+}
+fn default_merge_del_docs_ratio() -> f32 {
+    1.0
+}
+fn default_compact_max_segments() -> usize {
+    4
+}
+fn default_compact_interval_secs() -> u64 {
+    6 * 60 * 60
+}
+
+/// Query-time behavior of the search handler.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchSection {
+    /// Count an adjacent transposition ("teh" -> "the") as one edit in fuzzy queries
+    /// (Damerau-Levenshtein). More permissive, so off by default.
+    #[serde(default)]
+    pub fuzzy_transpositions: bool,
+    /// How long a rendered snippet is reused for the same document and query terms (0 = off).
+    #[serde(default = "default_snippet_cache_ttl_secs")]
+    pub snippet_cache_ttl_secs: u64,
+    /// Maximum cached snippets.
+    #[serde(default = "default_snippet_cache_entries")]
+    pub snippet_cache_entries: usize,
+    /// When a hybrid search returns a hit whose body the index budget trimmed from the content
+    /// index, enqueue a re-extraction so later searches can match its content. Off by default
+    /// to avoid unexpected I/O.
+    #[serde(default)]
+    pub rehydrate_on_miss: bool,
+    /// How term matches are marked in snippets.
+    #[serde(default)]
+    pub snippet_highlight: HighlightStyle,
+    /// Locale used to collate names when a request sorts by name or path (e.g. `sv-SE`).
+    /// Empty uses the root order: case- and accent-insensitive first, then accents, then case.
+    #[serde(default)]
+    pub sort_locale: String,
+    /// While the content index is missing or empty (e.g. right after install), answer
+    /// content queries with an empty result and hybrid/auto queries from names only, flagging
+    /// the response with `degraded_to`.
+    #[serde(default = "default_true")]
+    pub degrade_without_content: bool,
+    /// Responses with more hits than this are paged: the first `stream_page_size` hits are
+    /// returned with a `next_cursor` for the rest (0 = always return everything at once).
+    #[serde(default = "default_stream_threshold")]
+    pub stream_threshold: u32,
+    /// Hits per page once a response is paged.
+    #[serde(default = "default_stream_page_size")]
+    pub stream_page_size: u32,
+    /// Drop a snippet fragment whose words overlap a better fragment of the same hit by at
+    /// least this ratio (1.0 = only identical ones, 0 = keep all).
+    #[serde(default = "default_snippet_dedup_similarity")]
+    pub snippet_dedup_similarity: f32,
+    /// Order name-only results by match quality (exact name, then match at a word start,
+    /// then inside a word; shorter names first) instead of BM25, which barely separates
+    /// short names.
+    #[serde(default)]
+    pub name_match_ranking: bool,
+    /// Most documents a content query scores before returning the best of those; results
+    /// cut short this way are flagged `approximate` (0 = score every match).
+    #[serde(default = "default_content_max_docs_scored")]
+    pub content_max_docs_scored: usize,
+    /// How matched directories count toward `total_bytes_matched` when a request asks for it.
+    #[serde(default)]
+    pub total_bytes_directories: DirectoryBytes,
+    /// How strongly a request's `context_path` favours nearby hits: a hit in that folder
+    /// scores up to `1 + weight` times its relevance (0 = ignore `context_path`).
+    #[serde(default = "default_context_path_weight")]
+    pub context_path_weight: f32,
+    /// Zone in which bare dates in queries (`modified:2023-06-01`) are read: `local` for the
+    /// system zone, `UTC`, or a fixed offset such as `+10:00`.
+    #[serde(default = "default_date_time_zone")]
+    pub date_time_zone: String,
+    /// Run the last plain name term of a query as a `Prefix` term, for search-as-you-type
+    /// clients that send the query while the user is still typing its final word.
+    #[serde(default)]
+    pub prefix_trailing_term: bool,
+    /// Let name terms also match the previous names recorded under `indexing.former_names`;
+    /// such hits are flagged `matched_former_name`.
+    #[serde(default)]
+    pub match_former_names: bool,
+    /// Track the most frequent content queries and, during deep idle, read their terms'
+    /// posting lists so those searches find them cached. How many queries to prefetch
+    /// (0 = off).
+    #[serde(default)]
+    pub prefetch_queries: usize,
+    /// How long the key set behind a `result_set_id` stays available for export and refine.
+    #[serde(default = "default_result_set_ttl_secs")]
+    pub result_set_ttl_secs: u64,
+    /// Most document keys held across all cached result sets; the oldest sets are dropped to
+    /// make room, and a larger match set gets no id (0 = result sets off).
+    #[serde(default = "default_result_set_max_keys")]
+    pub result_set_max_keys: usize,
+    /// Distinct queries kept in the local query history behind `SuggestRequest`, least
+    /// recently used dropped first (0 = off, nothing recorded).
+    #[serde(default)]
+    pub query_history_len: usize,
+}
+
+impl Default for SearchSection {
+    fn default() -> Self {
+        Self {
+            fuzzy_transpositions: false,
+            snippet_cache_ttl_secs: default_snippet_cache_ttl_secs(),
+            snippet_cache_entries: default_snippet_cache_entries(),
+            rehydrate_on_miss: false,
+            snippet_highlight: HighlightStyle::default(),
+            sort_locale: String::new(),
+            degrade_without_content: true,
+            stream_threshold: default_stream_threshold(),
+            stream_page_size: default_stream_page_size(),
+            snippet_dedup_similarity: default_snippet_dedup_similarity(),
+            name_match_ranking: false,
+            content_max_docs_scored: default_content_max_docs_scored(),
+            total_bytes_directories: DirectoryBytes::default(),
+            context_path_weight: default_context_path_weight(),
+            date_time_zone: default_date_time_zone(),
+            prefix_trailing_term: false,
+            match_former_names: false,
+            prefetch_queries: 0,
+            result_set_ttl_secs: default_result_set_ttl_secs(),
+            result_set_max_keys: default_result_set_max_keys(),
+            query_history_len: 0,
+        }
+    }
+}
+
+/// Highlight markup applied to snippet term matches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HighlightStyle {
+    /// Source text as-is, no markup.
+    #[default]
+    Plain,
+    /// Matches wrapped in `<b>`/`</b>`; the source text is HTML-escaped first.
+    Html,
+    /// Matches wrapped in STX (`\u{2}`) / ETX (`\u{3}`); those characters are removed from
+    /// the source text.
+    Markers,
+}
+
+/// Treatment of matched directories when summing a result set's bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DirectoryBytes {
+    /// Directories add nothing; only matched files are summed.
+    #[default]
+    Exclude,
+    /// A matched directory adds the files beneath it (each file counted once).
+    Contents,
+}
+
+fn default_snippet_cache_ttl_secs() -> u64 {
+    60
+}
+fn default_snippet_cache_entries() -> usize {
+    4096
+}
+fn default_stream_threshold() -> u32 {
+    1000
+}
+fn default_stream_page_size() -> u32 {
+    250
+}
+fn default_content_max_docs_scored() -> usize {
+    1_000_000
+}
+fn default_context_path_weight() -> f32 {
+    1.0
+}
+fn default_date_time_zone() -> String {
+    "local".into()
+}
+fn default_snippet_dedup_similarity() -> f32 {
+    0.9
+}
+fn default_result_set_ttl_secs() -> u64 {
+    600
+}
+fn default_result_set_max_keys() -> usize {
+    1_000_000
+}
+
+/// Tokenization of indexed file content. Applied when the content index is created (or while
+/// it is still empty); changing it later requires a reset.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContentAnalysisSection {
+    /// Drop very common words ("the", "and") from content postings and queries.
+    #[serde(default = "default_true")]
+    pub stop_words_enabled: bool,
+    /// Language of the built-in stop-word list.
+    #[serde(default = "default_stop_words_language")]
+    pub stop_words_language: String,
+    /// Custom stop words; replaces the language list when non-empty.
+    #[serde(default)]
+    pub stop_words: Vec<String>,
+}
+
+impl Default for ContentAnalysisSection {
+    fn default() -> Self {
+        Self {
+            stop_words_enabled: true,
+            stop_words_language: default_stop_words_language(),
+            stop_words: Vec::new(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+fn default_stop_words_language() -> String {
+    "english".into()
+}
 
 static CONFIG: Lazy<RwLock<AppConfig>> = Lazy::new(|| RwLock::new(AppConfig::default()));
 
@@ -456,6 +1195,23 @@ impl AppConfig {
         }
         Ok(())
     }
+
+    /// Move `app.data_dir` to `dir` along with every path laid out beneath it by default
+    /// (indexes, volume state, jobs, tag store, logs, audit log, semantic index). Paths the
+    /// config file set elsewhere are replaced too, so nothing outside `dir` is touched.
+    pub fn override_data_dir(&mut self, dir: &str) {
+        let defaults = AppConfig::default();
+        self.app.data_dir = dir.to_string();
+        self.logging.file = defaults.logging.file;
+        self.audit.file = defaults.audit.file;
+        self.paths.meta_index = defaults.paths.meta_index;
+        self.paths.content_index = defaults.paths.content_index;
+        self.paths.state_dir = defaults.paths.state_dir;
+        self.paths.jobs_dir = defaults.paths.jobs_dir;
+        self.paths.tags_file = defaults.paths.tags_file;
+        self.semantic.index_dir = defaults.semantic.index_dir;
+        apply_placeholders(self);
+    }
 }
 
 /// Replace `{data_dir}` placeholder tokens with the configured data_dir,
@@ -467,18 +1223,22 @@ fn apply_placeholders(cfg: &mut AppConfig) {
     // 2. Replace {data_dir} in other paths.
     let dd = cfg.app.data_dir.clone();
     cfg.logging.file = cfg.logging.file.replace("{data_dir}", &dd);
+    cfg.audit.file = cfg.audit.file.replace("{data_dir}", &dd);
     cfg.paths.meta_index = cfg.paths.meta_index.replace("{data_dir}", &dd);
     cfg.paths.content_index = cfg.paths.content_index.replace("{data_dir}", &dd);
     cfg.paths.state_dir = cfg.paths.state_dir.replace("{data_dir}", &dd);
     cfg.paths.jobs_dir = cfg.paths.jobs_dir.replace("{data_dir}", &dd);
+    cfg.paths.tags_file = cfg.paths.tags_file.replace("{data_dir}", &dd);
     cfg.semantic.index_dir = cfg.semantic.index_dir.replace("{data_dir}", &dd);
 
     // 3. Expand env vars in all paths (in case user hardcoded %TEMP% in logging.file, etc.)
     cfg.logging.file = expand_env_vars(&cfg.logging.file);
+    cfg.audit.file = expand_env_vars(&cfg.audit.file);
     cfg.paths.meta_index = expand_env_vars(&cfg.paths.meta_index);
     cfg.paths.content_index = expand_env_vars(&cfg.paths.content_index);
     cfg.paths.state_dir = expand_env_vars(&cfg.paths.state_dir);
     cfg.paths.jobs_dir = expand_env_vars(&cfg.paths.jobs_dir);
+    cfg.paths.tags_file = expand_env_vars(&cfg.paths.tags_file);
     cfg.semantic.index_dir = expand_env_vars(&cfg.semantic.index_dir);
 }
 
@@ -580,6 +1340,14 @@ mod tests {
         base.paths = override_cfg.paths;
         base.extract = override_cfg.extract;
         base.semantic = override_cfg.semantic;
+        base.indexing = override_cfg.indexing;
+        base.search = override_cfg.search;
+        base.content_analysis = override_cfg.content_analysis;
+        base.keymap = override_cfg.keymap;
+        base.ipc = override_cfg.ipc;
+        base.audit = override_cfg.audit;
+        base.content_merge = override_cfg.content_merge;
+        base.integrity = override_cfg.integrity;
         base.volumes = override_cfg.volumes;
         base.content_index_volumes = override_cfg.content_index_volumes;
         base
@@ -646,6 +1414,13 @@ mod tests {
         assert_eq!(cfg.scheduler.cpu_hard_limit_pct, 80);
     }
 
+    #[test]
+    fn indexing_section_defaults_and_parses() {
+        assert!(!AppConfig::default().indexing.include_recycle_bin);
+        let cfg: AppConfig = toml::from_str("[indexing]\ninclude_recycle_bin = true\n").unwrap();
+        assert!(cfg.indexing.include_recycle_bin);
+    }
+
     #[test]
     fn extract_section_alias_for_max_chars() {
         // Ensure legacy "max_chars" still deserializes via alias.
@@ -0,0 +1,201 @@
+//! Local calendar dates to UTC ranges, for date queries such as `modified:2023-06-01`.
+//!
+//! Timestamps are indexed as Unix seconds (UTC), but a date someone types means a day on
+//! their own clock. [`day_range`] turns it into `[local midnight, next local midnight)` in
+//! the configured [`DateZone`] (`search.date_time_zone`). Relative ages (`today`, `<7d`,
+//! `>1y`) are resolved against the current time by [`parse_relative_time`].
+
+use chrono::{Days, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone};
+
+/// Time zone in which bare dates are read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateZone {
+    /// The system's local zone, daylight saving included.
+    #[default]
+    Local,
+    /// A fixed offset from UTC (`UTC`, `+10:00`, `-05:30`).
+    Fixed(FixedOffset),
+}
+
+impl DateZone {
+    /// `local` (or empty), `UTC`/`Z`, or an offset as `+HH:MM`, `+HHMM` or `+HH`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if s.is_empty() || s.eq_ignore_ascii_case("local") {
+            return Some(Self::Local);
+        }
+        if s.eq_ignore_ascii_case("utc") || s == "Z" {
+            return FixedOffset::east_opt(0).map(Self::Fixed);
+        }
+        let sign = match s.as_bytes()[0] {
+            b'+' => 1,
+            b'-' => -1,
+            _ => return None,
+        };
+        let digits: String = s[1..].chars().filter(|c| *c != ':').collect();
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        let (hours, minutes) = match digits.len() {
+            1 | 2 => (digits.parse::<i32>().ok()?, 0),
+            4 => (digits[..2].parse().ok()?, digits[2..].parse().ok()?),
+            _ => return None,
+        };
+        if minutes >= 60 {
+            return None;
+        }
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).map(Self::Fixed)
+    }
+
+    /// Unix seconds of `local` in this zone. A time skipped by a daylight-saving change
+    /// moves forward by whole hours until it exists.
+    fn to_unix(self, local: NaiveDateTime) -> i64 {
+        match self {
+            Self::Fixed(offset) => offset.from_local_datetime(&local).earliest(),
+            Self::Local => (0..=4).find_map(|hours| {
+                Local
+                    .from_local_datetime(&(local + TimeDelta::hours(hours)))
+                    .earliest()
+                    .map(|t| t.fixed_offset())
+            }),
+        }
+        .map_or_else(|| local.and_utc().timestamp(), |t| t.timestamp())
+    }
+
+    /// Calendar date of the Unix time `secs` in this zone.
+    fn date_of(self, secs: i64) -> Option<NaiveDate> {
+        match self {
+            Self::Fixed(offset) => offset
+                .timestamp_opt(secs, 0)
+                .single()
+                .map(|t| t.date_naive()),
+            Self::Local => Local
+                .timestamp_opt(secs, 0)
+                .single()
+                .map(|t| t.date_naive()),
+        }
+    }
+}
+
+/// A bare `YYYY-MM-DD` date.
+pub fn parse_date(s: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d").ok()
+}
+
+/// `[start, end)` in Unix seconds covering `date` on the clock of `zone`.
+pub fn day_range(date: NaiveDate, zone: DateZone) -> (i64, i64) {
+    let midnight = |d: NaiveDate| zone.to_unix(d.and_time(NaiveTime::MIN));
+    let next = date.checked_add_days(Days::new(1)).unwrap_or(date);
+    (midnight(date), midnight(next))
+}
+
+/// Inclusive `(lo, hi)` Unix-second bounds of a relative age, `hi: None` meaning open-ended:
+///
+/// - `today` / `yesterday`: that whole day on the clock of `zone`;
+/// - `<N<unit>`: within the last N units (e.g. `<7d`);
+/// - `>N<unit>`: more than N units before `now` (e.g. `>1y`).
+///
+/// Units are `h`, `d`, `w` and `y` (365 days). The result fits `RangeValue::I64` with
+/// `RangeOp::Between` for any timestamp field.
+pub fn parse_relative_time(token: &str, now: i64, zone: DateZone) -> Option<(i64, Option<i64>)> {
+    let token = token.trim();
+    let day = |back: u64| {
+        let date = zone.date_of(now)?.checked_sub_days(Days::new(back))?;
+        let (start, end) = day_range(date, zone);
+        Some((start, Some(end - 1)))
+    };
+    if token.eq_ignore_ascii_case("today") {
+        return day(0);
+    }
+    if token.eq_ignore_ascii_case("yesterday") {
+        return day(1);
+    }
+
+    let (newer, age) = match token.as_bytes().first()? {
+        b'<' => (true, &token[1..]),
+        b'>' => (false, &token[1..]),
+        _ => return None,
+    };
+    let unit = age.chars().last()?;
+    let count: i64 = age[..age.len() - unit.len_utf8()].trim().parse().ok()?;
+    let unit_secs: i64 = match unit.to_ascii_lowercase() {
+        'h' => 3600,
+        'd' => 86_400,
+        'w' => 7 * 86_400,
+        'y' => 365 * 86_400,
+        _ => return None,
+    };
+    let cutoff = now.checked_sub(count.checked_mul(unit_secs)?)?;
+    Some(if newer {
+        (cutoff, None)
+    } else {
+        (i64::MIN, Some(cutoff - 1))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dates_in_an_eastern_zone_start_before_utc_midnight() {
+        let date = parse_date("2023-06-01").unwrap();
+        let utc = DateZone::parse("UTC").unwrap();
+        let sydney = DateZone::parse("+10:00").unwrap();
+
+        assert_eq!(day_range(date, utc), (1_685_577_600, 1_685_664_000));
+        // 2023-06-01T00:00+10:00 is 2023-05-31T14:00Z.
+        assert_eq!(day_range(date, sydney), (1_685_541_600, 1_685_628_000));
+        assert_ne!(day_range(date, sydney), day_range(date, utc));
+
+        assert_eq!(DateZone::parse("+1000"), Some(sydney));
+        assert_eq!(
+            DateZone::parse("-05:30"),
+            FixedOffset::west_opt(5 * 3600 + 1800).map(DateZone::Fixed)
+        );
+        assert_eq!(DateZone::parse("local"), Some(DateZone::Local));
+        assert_eq!(DateZone::parse("Mars/Olympus"), None);
+        assert_eq!(parse_date("June 1"), None);
+    }
+
+    #[test]
+    fn relative_ages_resolve_against_now() {
+        // 2023-06-15T10:00:00Z.
+        let now = 1_686_823_200;
+        let utc = DateZone::parse("UTC").unwrap();
+        let midnight = 1_686_787_200;
+
+        assert_eq!(
+            parse_relative_time("today", now, utc),
+            Some((midnight, Some(midnight + 86_399)))
+        );
+        assert_eq!(
+            parse_relative_time("Yesterday", now, utc),
+            Some((midnight - 86_400, Some(midnight - 1)))
+        );
+        assert_eq!(
+            parse_relative_time(">30d", now, utc),
+            Some((i64::MIN, Some(now - 30 * 86_400 - 1)))
+        );
+        assert_eq!(
+            parse_relative_time("<7d", now, utc),
+            Some((now - 7 * 86_400, None))
+        );
+        assert_eq!(
+            parse_relative_time(">1y", now, utc),
+            Some((i64::MIN, Some(now - 365 * 86_400 - 1)))
+        );
+        // 10:00Z is already the 16th in +14:00.
+        let kiribati = DateZone::parse("+14:00").unwrap();
+        assert_eq!(
+            parse_relative_time("today", now, kiribati),
+            Some((
+                midnight + 86_400 - 14 * 3600,
+                Some(midnight + 2 * 86_400 - 14 * 3600 - 1)
+            ))
+        );
+        for bad in ["<7", "7d", ">xd", "<3q", "", "tomorrow"] {
+            assert_eq!(parse_relative_time(bad, now, utc), None, "{bad}");
+        }
+    }
+}
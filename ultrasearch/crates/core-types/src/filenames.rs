@@ -0,0 +1,81 @@
+//! Name-based classification for well-known extensionless files.
+//!
+//! Extension-driven extractors miss files like `Makefile` or `LICENSE`; this maps such names
+//! to the kind of content they hold so they can still be routed to a text extractor.
+
+use serde::{Deserialize, Serialize};
+
+/// Broad content kind of a recognized file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    /// Build scripts and other source-like files.
+    Code,
+    /// Prose such as licenses and readmes.
+    Text,
+}
+
+/// Classify a bare file name (no directory) against the built-in list, ignoring ASCII case.
+pub fn classify_known_filename(name: &str) -> Option<ContentKind> {
+    match name.to_ascii_lowercase().as_str() {
+        "makefile" | "gnumakefile" | "dockerfile" | "containerfile" | "jenkinsfile"
+        | "vagrantfile" | "rakefile" | "gemfile" | "procfile" | "justfile" | "brewfile"
+        | "podfile" | "build" | "workspace" => Some(ContentKind::Code),
+        "license" | "licence" | "readme" | "copying" | "authors" | "contributors" | "changelog"
+        | "changes" | "notice" | "todo" | "install" | "news" | "history" | "codeowners" => {
+            Some(ContentKind::Text)
+        }
+        _ => None,
+    }
+}
+
+/// Like [`classify_known_filename`], but configured names are checked first.
+pub fn classify_filename_with(
+    name: &str,
+    extra_code: &[String],
+    extra_text: &[String],
+) -> Option<ContentKind> {
+    let listed = |names: &[String]| names.iter().any(|n| n.trim().eq_ignore_ascii_case(name));
+    if listed(extra_code) {
+        Some(ContentKind::Code)
+    } else if listed(extra_text) {
+        Some(ContentKind::Text)
+    } else {
+        classify_known_filename(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_names_classify_case_insensitively() {
+        assert_eq!(
+            classify_known_filename("Dockerfile"),
+            Some(ContentKind::Code)
+        );
+        assert_eq!(classify_known_filename("LICENSE"), Some(ContentKind::Text));
+        assert_eq!(classify_known_filename("makefile"), Some(ContentKind::Code));
+        assert_eq!(classify_known_filename("notes"), None);
+        assert_eq!(classify_known_filename("Dockerfile.dev"), None);
+    }
+
+    #[test]
+    fn configured_names_extend_and_override_builtins() {
+        let code = vec!["Tiltfile".to_string()];
+        let text = vec!["Dockerfile".to_string()];
+        assert_eq!(
+            classify_filename_with("tiltfile", &code, &text),
+            Some(ContentKind::Code)
+        );
+        assert_eq!(
+            classify_filename_with("Dockerfile", &code, &text),
+            Some(ContentKind::Text)
+        );
+        assert_eq!(
+            classify_filename_with("README", &code, &text),
+            Some(ContentKind::Text)
+        );
+    }
+}
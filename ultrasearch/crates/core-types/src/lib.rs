@@ -4,6 +4,7 @@
 //! serialization-friendly for rkyv/bincode and IPC payloads.
 
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 pub type VolumeId = u16;
@@ -22,6 +23,15 @@ impl DocKey {
         DocKey(packed)
     }
 
+    /// Like [`from_parts`](Self::from_parts), but fails instead of masking when `file` does
+    /// not fit in 48 bits, so distinct FRNs can't alias to the same key.
+    pub const fn try_from_parts(volume: VolumeId, file: FileId) -> Result<Self, DocKeyError> {
+        if file > MAX_FILE_ID {
+            return Err(DocKeyError::FileIdOverflow(file));
+        }
+        Ok(Self::from_parts(volume, file))
+    }
+
     /// Split the packed id back into `(VolumeId, FileId)`.
     pub const fn into_parts(self) -> (VolumeId, FileId) {
         let volume = (self.0 >> 48) as VolumeId;
@@ -38,31 +48,109 @@ impl DocKey {
     pub const fn file_id(self) -> FileId {
         self.0 & 0x0000_FFFF_FFFF_FFFF
     }
+
+    /// Synthetic key for the entry at `entry_path` inside the archive `archive`. Entries have
+    /// no FRN, so the file id is an FNV-1a hash of the archive key and entry path with
+    /// [`ARCHIVE_ENTRY_BIT`] set, on the archive's volume; it is stable across scans.
+    pub fn archive_entry(archive: DocKey, entry_path: &str) -> Self {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in archive.0.to_le_bytes().iter().chain(entry_path.as_bytes()) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        DocKey::from_parts(
+            archive.volume(),
+            ARCHIVE_ENTRY_BIT | (hash & (ARCHIVE_ENTRY_BIT - 1)),
+        )
+    }
 }
 
+/// Set in the file id of archive entry keys, above any NTFS record number. Entries are
+/// recognized by [`FileFlags::ARCHIVE_ENTRY`], not by this bit: folder-watch path hashes
+/// can set it too.
+pub const ARCHIVE_ENTRY_BIT: FileId = 1 << 47;
+
+/// Largest file id a `DocKey` can hold (48 bits).
+pub const MAX_FILE_ID: FileId = 0x0000_FFFF_FFFF_FFFF;
+
+/// Why a `(volume, file)` pair can't be packed into a `DocKey`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocKeyError {
+    /// The file id has bits set above [`MAX_FILE_ID`].
+    FileIdOverflow(FileId),
+}
+
+impl core::fmt::Display for DocKeyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::FileIdOverflow(id) => {
+                write!(f, "file id {id:#x} does not fit in 48 bits")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DocKeyError {}
+
+/// Textual form for logs and config files: `v<volume>:<file>` in decimal, e.g. `v1:1193046`.
 impl core::fmt::Display for DocKey {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         let (v, id) = self.into_parts();
-        write!(f, "{}:{:#014x}", v, id)
+        write!(f, "v{v}:{id}")
+    }
+}
+
+/// Why a string is not a `v<volume>:<file>` doc key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DocKeyParseError {
+    /// Not of the form `v<volume>:<file>`.
+    Format(String),
+    /// The volume is not a decimal number that fits in 16 bits.
+    Volume(String),
+    /// The file id is not a decimal number that fits in 48 bits.
+    File(String),
+}
+
+impl core::fmt::Display for DocKeyParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Format(s) => write!(f, "doc key `{s}` is not of the form v<volume>:<file>"),
+            Self::Volume(v) => write!(f, "doc key volume `{v}` is not a number in 0..=65535"),
+            Self::File(id) => {
+                write!(
+                    f,
+                    "doc key file id `{id}` is not a number in 0..={MAX_FILE_ID}"
+                )
+            }
+        }
     }
 }
 
+impl std::error::Error for DocKeyParseError {}
+
 impl FromStr for DocKey {
-    type Err = &'static str;
+    type Err = DocKeyParseError;
 
-    /// Parses the Display form: `<volume>:0x<frn_hex>`.
+    /// Parses the Display form, `v<volume>:<file>`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (vol_part, frn_part) = s.split_once(':').ok_or("missing ':'")?;
-        let volume: VolumeId = vol_part.parse().map_err(|_| "invalid volume id")?;
-        let frn_hex = frn_part.strip_prefix("0x").ok_or("missing 0x prefix")?;
-        let file = u64::from_str_radix(frn_hex, 16).map_err(|_| "invalid frn hex")?
-            & 0x0000_FFFF_FFFF_FFFF;
+        let (volume, file) = s
+            .strip_prefix('v')
+            .and_then(|rest| rest.split_once(':'))
+            .ok_or_else(|| DocKeyParseError::Format(s.to_string()))?;
+        let volume: VolumeId = volume
+            .parse()
+            .map_err(|_| DocKeyParseError::Volume(volume.to_string()))?;
+        let file = file
+            .parse::<FileId>()
+            .ok()
+            .filter(|id| *id <= MAX_FILE_ID)
+            .ok_or_else(|| DocKeyParseError::File(file.to_string()))?;
         Ok(DocKey::from_parts(volume, file))
     }
 }
 
 bitflags::bitflags! {
-    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub struct FileFlags: u32 {
         const IS_DIR   = 0b0000_0001;
         const HIDDEN   = 0b0000_0010;
@@ -71,6 +159,76 @@ bitflags::bitflags! {
         const REPARSE  = 0b0001_0000;
         const OFFLINE  = 0b0010_0000;
         const TEMPORARY= 0b0100_0000;
+        /// Directory whose reparse tag is `IO_REPARSE_TAG_MOUNT_POINT` (volume mount or junction).
+        const MOUNT_POINT = 0b1000_0000;
+        /// NTFS-compressed (`FILE_ATTRIBUTE_COMPRESSED`).
+        const COMPRESSED = 0b0001_0000_0000;
+        /// EFS-encrypted (`FILE_ATTRIBUTE_ENCRYPTED`).
+        const ENCRYPTED = 0b0010_0000_0000;
+        /// Sparse file (`FILE_ATTRIBUTE_SPARSE_FILE`).
+        const SPARSE = 0b0100_0000_0000;
+        /// Entry listed from inside an archive (`extract.index_archives`); not a file on disk.
+        const ARCHIVE_ENTRY = 0b1000_0000_0000_0000;
+    }
+}
+
+/// Human-readable formats (JSON, TOML) get the flag names, e.g. `["IS_DIR", "HIDDEN"]`;
+/// compact ones (bincode) get the bits.
+impl Serialize for FileFlags {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(self.iter_names().map(|(name, _)| name))
+        } else {
+            serializer.serialize_u32(self.bits())
+        }
+    }
+}
+
+/// Accepts a list of names, the bits, or bitflags' `"IS_DIR | HIDDEN"` text.
+impl<'de> Deserialize<'de> for FileFlags {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::{Error, SeqAccess, Unexpected, Visitor};
+
+        struct FlagsVisitor;
+
+        impl<'de> Visitor<'de> for FlagsVisitor {
+            type Value = FileFlags;
+
+            fn expecting(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                f.write_str("a list of file flag names or the flag bits")
+            }
+
+            fn visit_u64<E: Error>(self, bits: u64) -> Result<FileFlags, E> {
+                u32::try_from(bits)
+                    .map(FileFlags::from_bits_retain)
+                    .map_err(|_| E::invalid_value(Unexpected::Unsigned(bits), &self))
+            }
+
+            fn visit_i64<E: Error>(self, bits: i64) -> Result<FileFlags, E> {
+                u64::try_from(bits)
+                    .map_err(|_| E::invalid_value(Unexpected::Signed(bits), &self))
+                    .and_then(|bits| self.visit_u64(bits))
+            }
+
+            fn visit_str<E: Error>(self, text: &str) -> Result<FileFlags, E> {
+                bitflags::parser::from_str(text).map_err(E::custom)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<FileFlags, A::Error> {
+                let mut flags = FileFlags::empty();
+                while let Some(name) = seq.next_element::<std::borrow::Cow<'de, str>>()? {
+                    flags |= FileFlags::from_name(&name)
+                        .ok_or_else(|| A::Error::custom(format!("unknown file flag `{name}`")))?;
+                }
+                Ok(flags)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(FlagsVisitor)
+        } else {
+            deserializer.deserialize_u32(FlagsVisitor)
+        }
     }
 }
 
@@ -87,6 +245,9 @@ pub struct FileMeta {
     pub created: Timestamp,
     pub modified: Timestamp,
     pub flags: FileFlags,
+    /// Owner SID (`S-1-5-21-...`), read only when `indexing.index_owner` is enabled.
+    #[serde(default)]
+    pub owner: Option<String>,
 }
 
 impl FileMeta {
@@ -103,9 +264,7 @@ impl FileMeta {
         modified: Timestamp,
         flags: FileFlags,
     ) -> Self {
-        let ext = name
-            .rsplit_once('.')
-            .map(|(_, ext)| ext.to_ascii_lowercase());
+        let ext = extension_of(&name).map(str::to_ascii_lowercase);
         Self {
             key,
             volume,
@@ -117,8 +276,67 @@ impl FileMeta {
             created,
             modified,
             flags,
+            owner: None,
         }
     }
+
+    /// Extension of `name` as written: the text after the last `.`. `None` without a dot,
+    /// for a trailing dot, and for dotfiles such as `.gitignore`.
+    pub fn extension(&self) -> Option<&str> {
+        extension_of(&self.name)
+    }
+
+    /// [`extension`](Self::extension), ASCII-lowercased.
+    pub fn extension_lower(&self) -> Option<String> {
+        self.extension().map(str::to_ascii_lowercase)
+    }
+}
+
+fn extension_of(name: &str) -> Option<&str> {
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.is_empty() => Some(ext),
+        _ => None,
+    }
+}
+
+/// Header fields of an email file, one entry per message (an `.mbox` holds many). Indexed
+/// beside the file's text for `from:`, `to:` and `subject:` queries and `sent` ranges.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EmailHeaders {
+    pub subject: Vec<String>,
+    /// Senders as written, e.g. `Alice Example <alice@example.com>`.
+    pub from: Vec<String>,
+    /// `To` and `Cc` recipients.
+    pub to: Vec<String>,
+    /// `Date` headers as Unix seconds.
+    pub sent: Vec<Timestamp>,
+}
+
+/// Lowercased ancestor directory names of `path`, excluding the file name and any drive or
+/// `\\?\` prefix. Indexed so queries can match a folder name at any depth.
+pub fn dir_components(path: &str) -> Vec<String> {
+    let mut parts: Vec<&str> = path.split(['\\', '/']).filter(|p| !p.is_empty()).collect();
+    parts.pop();
+    parts
+        .into_iter()
+        .filter(|p| !p.ends_with(':') && !matches!(*p, "?" | "." | ".."))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Rename the directory at `path` to the first unused sibling `<name>.<suffix>`,
+/// `<name>.<suffix>-1`, … and return where it went. Used to keep an index that can no longer
+/// be opened around for inspection instead of deleting it.
+pub fn set_aside_dir(path: &Path, suffix: &str) -> std::io::Result<PathBuf> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let mut target = path.with_file_name(format!("{name}.{suffix}"));
+    let mut n = 0;
+    while target.exists() {
+        n += 1;
+        target = path.with_file_name(format!("{name}.{suffix}-{n}"));
+    }
+    std::fs::rename(path, &target)?;
+    Ok(target)
 }
 
 /// Per-volume configuration snapshot (kept simple for now).
@@ -140,7 +358,11 @@ pub struct VolumeDescriptor {
     pub drive_letters: Vec<String>,
 }
 
+pub mod collation;
 pub mod config;
+pub mod dates;
+pub mod filenames;
+pub mod phonetic;
 
 impl FileFlags {
     pub fn is_dir(self) -> bool {
@@ -160,6 +382,25 @@ mod tests {
         assert_eq!(f, 0x1234_5678_9abc);
     }
 
+    #[test]
+    fn try_from_parts_rejects_truncated_frns() {
+        assert_eq!(
+            DocKey::try_from_parts(7, MAX_FILE_ID),
+            Ok(DocKey::from_parts(7, MAX_FILE_ID))
+        );
+        for frn in [MAX_FILE_ID + 1, 0x0001_0000_0000_0005, u64::MAX] {
+            assert_eq!(
+                DocKey::try_from_parts(7, frn),
+                Err(DocKeyError::FileIdOverflow(frn))
+            );
+        }
+        // The lossy form still masks, aliasing to the low 48 bits.
+        assert_eq!(
+            DocKey::from_parts(7, 0x0001_0000_0000_0005),
+            DocKey::from_parts(7, 5)
+        );
+    }
+
     #[test]
     fn file_meta_ext_derives_lowercase() {
         let key = DocKey::from_parts(1, 2);
@@ -177,17 +418,110 @@ mod tests {
         assert_eq!(fm.ext.as_deref(), Some("pdf"));
     }
 
+    #[test]
+    fn extension_borrows_the_last_segment() {
+        let meta = |name: &str| {
+            FileMeta::new(
+                DocKey::from_parts(1, 2),
+                1,
+                None,
+                name.to_string(),
+                None,
+                0,
+                0,
+                0,
+                FileFlags::empty(),
+            )
+        };
+        let cases = [
+            ("Report.PDF", Some("PDF")),
+            ("archive.tar.gz", Some("gz")),
+            ("README", None),
+            ("trailing.", None),
+            (".gitignore", None),
+            (".config.toml", Some("toml")),
+        ];
+        for (name, ext) in cases {
+            let fm = meta(name);
+            assert_eq!(fm.extension(), ext, "{name}");
+            let lower = ext.map(str::to_ascii_lowercase);
+            assert_eq!(fm.extension_lower(), lower, "{name}");
+            assert_eq!(fm.ext, lower, "{name}");
+        }
+    }
+
+    #[test]
+    fn file_flags_round_trip_through_serde() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Wrapper {
+            flags: FileFlags,
+        }
+        let original = Wrapper {
+            flags: FileFlags::COMPRESSED | FileFlags::ENCRYPTED | FileFlags::SPARSE,
+        };
+        let text = toml::to_string(&original).unwrap();
+        assert_eq!(toml::from_str::<Wrapper>(&text).unwrap(), original);
+        assert_eq!(original.flags.bits(), 0b0111_0000_0000);
+    }
+
+    #[test]
+    fn file_flags_are_names_in_json_and_bits_in_bincode() {
+        let flags = FileFlags::IS_DIR | FileFlags::HIDDEN;
+
+        let json = serde_json::to_string(&flags).unwrap();
+        assert_eq!(json, r#"["IS_DIR","HIDDEN"]"#);
+        assert_eq!(serde_json::from_str::<FileFlags>(&json).unwrap(), flags);
+        assert_eq!(serde_json::to_string(&FileFlags::empty()).unwrap(), "[]");
+        // Bits and bitflags' own text form are read too.
+        assert_eq!(serde_json::from_str::<FileFlags>("3").unwrap(), flags);
+        assert_eq!(
+            serde_json::from_str::<FileFlags>(r#""IS_DIR | HIDDEN""#).unwrap(),
+            flags
+        );
+        assert!(serde_json::from_str::<FileFlags>(r#"["IS_DIR","SHINY"]"#).is_err());
+
+        let bytes = bincode::serialize(&flags).unwrap();
+        assert_eq!(bytes, 3u32.to_le_bytes());
+        assert_eq!(bincode::deserialize::<FileFlags>(&bytes).unwrap(), flags);
+    }
+
+    #[test]
+    fn dir_components_skip_drive_and_file_name() {
+        assert_eq!(
+            dir_components(r"C:\Users\Me\Invoices\2024\march.pdf"),
+            vec!["users", "me", "invoices", "2024"]
+        );
+        assert_eq!(dir_components(r"\\?\D:\a.txt"), Vec::<String>::new());
+        assert_eq!(dir_components("/srv/invoices/x"), vec!["srv", "invoices"]);
+    }
+
     #[test]
     fn doc_key_display_is_stable() {
-        let dk = DocKey::from_parts(7, 0xabc);
-        assert_eq!(dk.to_string(), "7:0x000000000abc");
+        let dk = DocKey::from_parts(1, 1_193_046);
+        assert_eq!(dk.to_string(), "v1:1193046");
     }
 
     #[test]
     fn doc_key_parse_round_trip() {
-        let original = DocKey::from_parts(9, 0xfeed_beef);
-        let parsed: DocKey = original.to_string().parse().unwrap();
-        assert_eq!(parsed, original);
+        for original in [
+            DocKey::from_parts(9, 0xfeed_beef),
+            DocKey::archive_entry(DocKey::from_parts(3, 77), "docs/report.pdf"),
+            DocKey::from_parts(0, 0),
+            DocKey::from_parts(VolumeId::MAX, MAX_FILE_ID),
+        ] {
+            let parsed: DocKey = original.to_string().parse().unwrap();
+            assert_eq!(parsed, original);
+        }
+
+        let err = |s: &str| s.parse::<DocKey>().unwrap_err();
+        assert_eq!(err("1:5"), DocKeyParseError::Format("1:5".into()));
+        assert_eq!(err("v65536:5"), DocKeyParseError::Volume("65536".into()));
+        let too_big = (MAX_FILE_ID + 1).to_string();
+        assert_eq!(
+            err(&format!("v1:{too_big}")),
+            DocKeyParseError::File(too_big)
+        );
+        assert!(err("v1:x").to_string().contains("file id `x`"));
     }
 
     #[test]
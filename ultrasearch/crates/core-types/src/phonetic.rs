@@ -0,0 +1,309 @@
+//! Phonetic codes for sound-alike name matching ("Stephen" / "Steven").
+//!
+//! A compact Double Metaphone: each word gets a primary and an alternate code of at most
+//! [`MAX_CODE_LEN`] characters. It covers the common English rules (silent letters, `PH`,
+//! `TH`, soft `C`/`G`, `SH`/`CH`/`TION`, ...) rather than the full set of language-specific
+//! exceptions, which is enough to pair up names that differ only in spelling.
+
+/// Codes are truncated to this many characters, as in the original algorithm.
+pub const MAX_CODE_LEN: usize = 4;
+
+/// Primary and alternate code for one word; both are empty when it has no ASCII letters.
+pub fn double_metaphone(word: &str) -> (String, String) {
+    let w: Vec<u8> = word
+        .bytes()
+        .filter(u8::is_ascii_alphabetic)
+        .map(|b| b.to_ascii_uppercase())
+        .collect();
+    let mut enc = Encoder::default();
+    if w.is_empty() {
+        return (enc.primary, enc.alternate);
+    }
+
+    let at = |i: usize| w.get(i).copied().unwrap_or(0);
+    let is_vowel = |i: usize| matches!(at(i), b'A' | b'E' | b'I' | b'O' | b'U' | b'Y');
+    let starts = |i: usize, s: &[u8]| w.get(i..i + s.len()) == Some(s);
+
+    let mut i = 0;
+    // Silent first letter.
+    if [&b"GN"[..], b"KN", b"PN", b"WR", b"PS"]
+        .iter()
+        .any(|p| starts(0, p))
+    {
+        i = 1;
+    }
+    if at(0) == b'X' {
+        enc.add(b'S');
+        i = 1;
+    }
+
+    while i < w.len() && !enc.full() {
+        let c = at(i);
+        // Doubled letters (other than C, see below) sound once.
+        let skip_double = |n: usize| if at(i + 1) == c { n + 1 } else { n };
+        i += match c {
+            b'A' | b'E' | b'I' | b'O' | b'U' | b'Y' => {
+                if i == 0 {
+                    enc.add(b'A');
+                }
+                1
+            }
+            b'B' => {
+                enc.add(b'P');
+                skip_double(1)
+            }
+            b'C' => {
+                if starts(i, b"CIA") || starts(i, b"CH") {
+                    // CHR-/CHL- are hard ("Christine", "Chloe").
+                    if starts(i, b"CHR") || starts(i, b"CHL") {
+                        enc.add(b'K');
+                    } else if starts(i, b"CIA") {
+                        enc.add(b'X');
+                    } else {
+                        enc.add2(b'X', b'K');
+                    }
+                    2
+                } else if starts(i, b"CC") && matches!(at(i + 2), b'E' | b'I' | b'Y') {
+                    enc.add_str(b"KS");
+                    3
+                } else if matches!(at(i + 1), b'E' | b'I' | b'Y') {
+                    enc.add(b'S');
+                    2
+                } else {
+                    enc.add(b'K');
+                    if matches!(at(i + 1), b'C' | b'K' | b'Q' | b'G') {
+                        2
+                    } else {
+                        1
+                    }
+                }
+            }
+            b'D' => {
+                if starts(i, b"DG") && matches!(at(i + 2), b'E' | b'I' | b'Y') {
+                    enc.add(b'J');
+                    3
+                } else {
+                    enc.add(b'T');
+                    if matches!(at(i + 1), b'T' | b'D') {
+                        2
+                    } else {
+                        1
+                    }
+                }
+            }
+            b'F' | b'V' => {
+                enc.add(b'F');
+                if matches!(at(i + 1), b'F' | b'V') {
+                    2
+                } else {
+                    1
+                }
+            }
+            b'G' => {
+                if at(i + 1) == b'H' {
+                    // Initial GH is hard ("Ghent"); otherwise silent ("Hugh", "Knight").
+                    if i == 0 || !is_vowel(i - 1) {
+                        enc.add(b'K');
+                    }
+                    2
+                } else if at(i + 1) == b'N' {
+                    // Silent before N ("Sign"); the N is coded next.
+                    1
+                } else if matches!(at(i + 1), b'E' | b'I' | b'Y') {
+                    enc.add2(b'J', b'K');
+                    2
+                } else {
+                    enc.add(b'K');
+                    skip_double(1)
+                }
+            }
+            b'H' => {
+                // Only sounded between a vowel (or the start) and a vowel.
+                if (i == 0 || is_vowel(i - 1)) && is_vowel(i + 1) {
+                    enc.add(b'H');
+                }
+                1
+            }
+            b'J' => {
+                if i == 0 {
+                    enc.add2(b'J', b'A');
+                } else {
+                    enc.add(b'J');
+                }
+                skip_double(1)
+            }
+            b'K' | b'Q' => {
+                enc.add(b'K');
+                if matches!(at(i + 1), b'K' | b'Q') {
+                    2
+                } else {
+                    1
+                }
+            }
+            b'L' | b'M' | b'N' | b'R' => {
+                enc.add(c);
+                // Final MB: the B is silent ("Plumb").
+                if c == b'M' && at(i + 1) == b'B' && i + 2 == w.len() {
+                    2
+                } else {
+                    skip_double(1)
+                }
+            }
+            b'P' => {
+                if at(i + 1) == b'H' {
+                    enc.add(b'F');
+                    2
+                } else {
+                    enc.add(b'P');
+                    if matches!(at(i + 1), b'P' | b'B') {
+                        2
+                    } else {
+                        1
+                    }
+                }
+            }
+            b'S' => {
+                if starts(i, b"SCH") {
+                    enc.add_str(b"SK");
+                    3
+                } else if at(i + 1) == b'H' {
+                    enc.add(b'X');
+                    2
+                } else if starts(i, b"SIO") || starts(i, b"SIA") {
+                    enc.add2(b'X', b'S');
+                    3
+                } else if at(i + 1) == b'C' && matches!(at(i + 2), b'E' | b'I' | b'Y') {
+                    enc.add(b'S');
+                    3
+                } else {
+                    enc.add(b'S');
+                    if matches!(at(i + 1), b'S' | b'Z') {
+                        2
+                    } else {
+                        1
+                    }
+                }
+            }
+            b'T' => {
+                if starts(i, b"TIO") || starts(i, b"TIA") || starts(i, b"TCH") {
+                    enc.add(b'X');
+                    3
+                } else if at(i + 1) == b'H' {
+                    enc.add2(b'0', b'T');
+                    2
+                } else {
+                    enc.add(b'T');
+                    if matches!(at(i + 1), b'T' | b'D') {
+                        2
+                    } else {
+                        1
+                    }
+                }
+            }
+            b'W' => {
+                // Initial W before a vowel sounds like a vowel or a V ("Walter" / "Vater").
+                if i == 0 && is_vowel(1) {
+                    enc.add2(b'A', b'F');
+                }
+                1
+            }
+            b'X' => {
+                enc.add_str(b"KS");
+                if matches!(at(i + 1), b'C' | b'X') {
+                    2
+                } else {
+                    1
+                }
+            }
+            b'Z' => {
+                enc.add(b'S');
+                skip_double(1)
+            }
+            _ => 1,
+        };
+    }
+
+    enc.primary.truncate(MAX_CODE_LEN);
+    enc.alternate.truncate(MAX_CODE_LEN);
+    (enc.primary, enc.alternate)
+}
+
+/// Distinct phonetic codes (primary and alternate) for the words of a file name. The
+/// extension is dropped and words split at anything that isn't an ASCII letter.
+pub fn name_phonetic_codes(name: &str) -> Vec<String> {
+    let stem = match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => name,
+    };
+    let mut codes = Vec::new();
+    for word in stem.split(|c: char| !c.is_ascii_alphabetic()) {
+        if word.len() < 2 {
+            continue;
+        }
+        let (primary, alternate) = double_metaphone(word);
+        for code in [primary, alternate] {
+            if !code.is_empty() && !codes.contains(&code) {
+                codes.push(code);
+            }
+        }
+    }
+    codes
+}
+
+#[derive(Default)]
+struct Encoder {
+    primary: String,
+    alternate: String,
+}
+
+impl Encoder {
+    fn full(&self) -> bool {
+        self.primary.len() >= MAX_CODE_LEN && self.alternate.len() >= MAX_CODE_LEN
+    }
+
+    fn add(&mut self, c: u8) {
+        self.add2(c, c);
+    }
+
+    fn add2(&mut self, primary: u8, alternate: u8) {
+        self.primary.push(primary as char);
+        self.alternate.push(alternate as char);
+    }
+
+    fn add_str(&mut self, s: &[u8]) {
+        s.iter().for_each(|&c| self.add(c));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn primary(word: &str) -> String {
+        double_metaphone(word).0
+    }
+
+    #[test]
+    fn spelling_variants_share_a_code() {
+        assert_eq!(primary("Stefan"), "STFN");
+        assert_eq!(primary("Stephan"), "STFN");
+        assert_eq!(primary("Steven"), primary("Stephen"));
+        assert_eq!(primary("Catherine"), primary("Katherine"));
+        assert_eq!(primary("Philip"), primary("Filip"));
+        assert_eq!(primary("Knight"), primary("Night"));
+        assert_ne!(primary("Stefan"), primary("Simon"));
+    }
+
+    #[test]
+    fn alternate_code_covers_ambiguous_sounds() {
+        assert_eq!(double_metaphone("Thomas"), ("0MS".into(), "TMS".into()));
+        assert_eq!(double_metaphone("Schmidt"), ("SKMT".into(), "SKMT".into()));
+        assert_eq!(double_metaphone("42"), (String::new(), String::new()));
+    }
+
+    #[test]
+    fn name_codes_skip_extension_and_digits() {
+        let codes = name_phonetic_codes("Stephan_CV-2024.pdf");
+        assert_eq!(codes, vec!["STFN".to_string(), "KF".to_string()]);
+    }
+}
@@ -6,15 +6,23 @@
 //! - Optional Extractous backend toggle via flag or ULTRASEARCH_ENABLE_EXTRACTOUS
 //! - Preview or JSON output for debugging
 //! - Writes extracted docs into the content index (creates if missing)
+//! - Extractor panics are isolated per file; extraction failures are dead-lettered next to
+//!   the job file, so searches can report why a file has no content
+//! - Files locked by another process are deferred to `locked_retry.jsonl` with backoff until
+//!   `--locked-retry-attempts` run out; the service re-queues them when due
 
 use anyhow::{Context, Result};
 use clap::Parser;
-use content_extractor::{ExtractContext, ExtractorStack};
+use content_extractor::{EXTRACTOR_NAMES, ExtractContext, ExtractError, ExtractorStack};
+use content_index::dedup::{BodyDedup, add_content_doc_dedup};
 use content_index::{ContentIndex, IndexWriter, WriterConfig};
 use core_types::DocKey;
+use core_types::config::ContentMergeSection;
+use core_types::filenames::{ContentKind, classify_known_filename};
 use dotenvy::dotenv;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::{env, fs};
 use tracing::{info, warn};
 
@@ -57,6 +65,39 @@ struct Args {
     /// Commit after at most N docs (0 = commit once at end).
     #[arg(long, default_value = "0")]
     commit_every: usize,
+    /// `[content_merge]` section as JSON; tantivy's default log merge policy when absent.
+    #[arg(long)]
+    merge_policy: Option<String>,
+    /// Record each file's MIME type detected from its magic bytes (`extract.detect_mime`).
+    #[arg(long, default_value = "false")]
+    detect_mime: bool,
+    /// Extract `.lnk` shortcut targets as content (`extract.index_shortcuts`).
+    #[arg(long, default_value = "false")]
+    index_shortcuts: bool,
+    /// Store identical normalized text once; later files reference it (`extract.dedup_content`).
+    #[arg(long, default_value = "false")]
+    dedup_content: bool,
+    /// Parse `.eml`/`.mbox` files as email with header fields (`extract.index_email`).
+    #[arg(long, default_value = "false")]
+    index_email: bool,
+    /// Read document titles from file metadata into the `title` field (`extract.index_titles`).
+    #[arg(long, default_value = "false")]
+    index_titles: bool,
+    /// Extract RTF/ODT/MSG through IFilters on at most N STA threads; 0 = off
+    /// (`extract.max_ifilter_threads`, Windows only).
+    #[arg(long, default_value = "0")]
+    max_ifilter_threads: usize,
+    /// Extractors switched off by name, comma-separated (`extract.disabled_extractors`).
+    #[arg(long, value_delimiter = ',')]
+    disabled_extractors: Vec<String>,
+    /// Retries of a file locked by another process before it is dead-lettered
+    /// (`extract.locked_retry_attempts`).
+    #[arg(long, default_value = "5")]
+    locked_retry_attempts: u32,
+    /// Delay before the first retry of a locked file, doubling per attempt
+    /// (`extract.locked_retry_backoff_secs`).
+    #[arg(long, default_value = "60")]
+    locked_retry_backoff_secs: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -70,6 +111,48 @@ struct JobSpec {
     max_chars: Option<usize>,
     #[serde(default)]
     file_size: u64,
+    #[serde(default)]
+    content_kind: Option<ContentKind>,
+    /// Retries already made because the file was locked.
+    #[serde(default)]
+    attempt: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct DeadLetter<'a> {
+    volume_id: u16,
+    file_id: u64,
+    path: &'a Path,
+    reason: &'a str,
+    ts: i64,
+    /// Not extracted by design (unsupported format or over the size limit) rather than failed.
+    skipped: bool,
+}
+
+/// Longest delay between retries of a locked file.
+const MAX_LOCKED_BACKOFF_SECS: u64 = 3600;
+
+/// A job deferred because its file was locked; the service re-queues it after `not_before`.
+#[derive(Debug, Serialize)]
+struct LockedRetry {
+    job: JobSpec,
+    not_before: i64,
+}
+
+/// `--locked-retry-attempts` and `--locked-retry-backoff-secs`.
+#[derive(Debug, Clone, Copy)]
+struct LockedRetryPolicy {
+    attempts: u32,
+    backoff_secs: u64,
+}
+
+impl LockedRetryPolicy {
+    /// Delay before retry number `attempt + 1`.
+    fn delay_secs(&self, attempt: u32) -> u64 {
+        self.backoff_secs
+            .saturating_mul(1u64 << attempt.min(32))
+            .min(MAX_LOCKED_BACKOFF_SECS)
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -132,18 +215,54 @@ fn main() -> Result<()> {
         args.enable_extractous = false;
     }
 
-    let stack = ExtractorStack::with_extractous_enabled(args.enable_extractous);
+    let stack = ExtractorStack::with_extractous_enabled(args.enable_extractous)
+        .with_mime_detection(args.detect_mime)
+        .with_shortcut_targets(args.index_shortcuts)
+        .with_email(args.index_email)
+        .with_titles(args.index_titles)
+        .with_ifilter(args.max_ifilter_threads)
+        .with_disabled(&args.disabled_extractors);
+    for name in &args.disabled_extractors {
+        if !EXTRACTOR_NAMES.contains(&name.as_str()) {
+            warn!(extractor = %name, "unknown extractor in extract.disabled_extractors");
+        }
+    }
 
     // Open index writer once for the run.
     let index: ContentIndex = content_index::open_or_create(&args.index_dir)?;
-    let mut writer: IndexWriter = content_index::create_writer(&index, &WriterConfig::default())?;
+    let merge: ContentMergeSection = match &args.merge_policy {
+        Some(json) => serde_json::from_str(json).context("invalid --merge-policy")?,
+        None => ContentMergeSection::default(),
+    };
+    let mut writer: IndexWriter = content_index::create_writer(
+        &index,
+        &WriterConfig {
+            merge,
+            ..WriterConfig::default()
+        },
+    )?;
+    let mut dedup = if args.dedup_content {
+        Some(BodyDedup::new(&index)?)
+    } else {
+        None
+    };
+    let retry = LockedRetryPolicy {
+        attempts: args.locked_retry_attempts,
+        backoff_secs: args.locked_retry_backoff_secs,
+    };
     let mut pending = 0usize;
 
     if let Some(job_file) = args.job_file.clone() {
         let jobs = load_jobs(&job_file)?;
         for job in jobs {
-            if let Err(err) = process_job(&stack, &index, &mut writer, job, &args) {
+            if let Err(err) = process_job(&stack, &index, &mut writer, dedup.as_mut(), &job, &args)
+            {
                 warn!("job failed: {err}");
+                if let Some(extract_err) = err.downcast_ref::<ExtractError>()
+                    && let Err(e) = record_failure(&job_file, &job, extract_err, retry, unix_now())
+                {
+                    warn!("failed to record failure of {:?}: {e}", job.path);
+                }
             }
             pending += 1;
             if args.commit_every > 0 && pending >= args.commit_every {
@@ -170,9 +289,11 @@ fn main() -> Result<()> {
             max_bytes: Some(args.max_bytes),
             max_chars: Some(args.max_chars),
             file_size: fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            content_kind: None,
+            attempt: 0,
         };
 
-        process_job(&stack, &index, &mut writer, single, &args)?;
+        process_job(&stack, &index, &mut writer, dedup.as_mut(), &single, &args)?;
         pending += 1;
     }
 
@@ -240,11 +361,69 @@ fn load_jobs(job_file: &PathBuf) -> Result<Vec<JobSpec>> {
     }
 }
 
+fn unix_now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Record a job whose extraction failed at `now`: a locked file with retries left is deferred
+/// to `locked_retry.jsonl` beside the job file, anything else goes to `dead_letter.jsonl`.
+fn record_failure(
+    job_file: &Path,
+    job: &JobSpec,
+    err: &ExtractError,
+    retry: LockedRetryPolicy,
+    now: i64,
+) -> Result<()> {
+    if matches!(err, ExtractError::Locked(_)) && job.attempt < retry.attempts {
+        let record = LockedRetry {
+            job: JobSpec {
+                attempt: job.attempt + 1,
+                ..job.clone()
+            },
+            not_before: now.saturating_add(retry.delay_secs(job.attempt) as i64),
+        };
+        info!(
+            "{:?} is locked; retry {} in {}s",
+            job.path,
+            record.job.attempt,
+            record.not_before - now
+        );
+        return append_record(&job_file.with_file_name("locked_retry.jsonl"), &record);
+    }
+    let reason = err.to_string();
+    let record = DeadLetter {
+        volume_id: job.volume_id,
+        file_id: job.file_id,
+        path: &job.path,
+        reason: &reason,
+        skipped: matches!(
+            err,
+            ExtractError::Unsupported(_) | ExtractError::FileTooLarge { .. }
+        ),
+        ts: now,
+    };
+    append_record(&job_file.with_file_name("dead_letter.jsonl"), &record)
+}
+
+fn append_record(path: &Path, record: &impl Serialize) -> Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("cannot open {}", path.display()))?;
+    writeln!(file, "{}", serde_json::to_string(record)?)?;
+    Ok(())
+}
+
 fn process_job(
     stack: &ExtractorStack,
     index: &content_index::ContentIndex,
     writer: &mut IndexWriter,
-    job: JobSpec,
+    dedup: Option<&mut BodyDedup>,
+    job: &JobSpec,
     args: &Args,
 ) -> Result<()> {
     let doc_key = DocKey::from_parts(job.volume_id, job.file_id);
@@ -258,6 +437,16 @@ fn process_job(
         .extension()
         .and_then(|s| s.to_str())
         .map(|s| s.to_ascii_lowercase());
+    // Older job files carry no kind; classify extensionless names locally.
+    let kind_hint = match (job.content_kind, &ext_owned) {
+        (Some(kind), _) => Some(kind),
+        (None, Some(_)) => None,
+        (None, None) => job
+            .path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(classify_known_filename),
+    };
 
     let ctx = ExtractContext {
         path: job
@@ -268,6 +457,7 @@ fn process_job(
         max_chars,
         ext_hint: ext_owned.as_deref(),
         mime_hint: None,
+        kind_hint,
     };
 
     let meta = fs::metadata(&job.path)
@@ -291,9 +481,19 @@ fn process_job(
             );
 
             // Index the document.
-            let content_doc = to_content_doc(&job, &meta, out)?;
-            let tdoc = content_index::to_document(&content_doc, &index.fields);
-            writer.add_document(tdoc)?;
+            let content_doc = to_content_doc(job, &meta, out)?;
+            match dedup {
+                Some(dedup) => {
+                    let stored = add_content_doc_dedup(writer, &index.fields, &content_doc, dedup)?;
+                    if !stored {
+                        info!("body already stored; indexed {:?} as a reference", job.path);
+                    }
+                }
+                None => {
+                    let tdoc = content_index::to_document(&content_doc, &index.fields);
+                    writer.add_document(tdoc)?;
+                }
+            }
 
             // Output for debugging.
             if args.json {
@@ -326,12 +526,14 @@ fn to_content_doc(
     meta: &std::fs::Metadata,
     out: content_extractor::ExtractedContent,
 ) -> Result<content_index::ContentDoc> {
-    let modified = meta
-        .modified()
-        .ok()
-        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-        .map(|d| d.as_secs() as i64)
-        .unwrap_or_default();
+    let to_unix = |t: std::io::Result<std::time::SystemTime>| {
+        t.ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default()
+    };
+    let modified = to_unix(meta.modified());
+    let created = to_unix(meta.created());
 
     let name = job
         .path
@@ -353,7 +555,80 @@ fn to_content_doc(
         ext,
         size: meta.len(),
         modified,
+        created,
         content_lang: out.content_lang.clone(),
         content: out.text,
+        mime: out.mime,
+        email: out.email,
+        title: out.title,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(path: &Path) -> Vec<serde_json::Value> {
+        fs::read_to_string(path)
+            .unwrap_or_default()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn locked_files_are_deferred_and_access_denied_is_dead_lettered() {
+        let dir = tempfile::tempdir().unwrap();
+        let job_file = dir.path().join("job_1.json");
+        let (retries, dead) = (
+            dir.path().join("locked_retry.jsonl"),
+            dir.path().join("dead_letter.jsonl"),
+        );
+        let retry = LockedRetryPolicy {
+            attempts: 2,
+            backoff_secs: 60,
+        };
+        let mut job = JobSpec {
+            volume_id: 1,
+            file_id: 7,
+            path: PathBuf::from(r"C:\data\app.db"),
+            max_bytes: None,
+            max_chars: None,
+            file_size: 0,
+            content_kind: None,
+            attempt: 0,
+        };
+        let locked = ExtractError::Locked("sharing violation".into());
+
+        // Deferred with a doubling delay while retries remain.
+        record_failure(&job_file, &job, &locked, retry, 1000).unwrap();
+        job.attempt = 1;
+        record_failure(&job_file, &job, &locked, retry, 2000).unwrap();
+        let deferred = lines(&retries);
+        assert_eq!(deferred.len(), 2);
+        assert_eq!(deferred[0]["job"]["attempt"], 1);
+        assert_eq!(deferred[0]["not_before"], 1060);
+        assert_eq!(deferred[1]["job"]["attempt"], 2);
+        assert_eq!(deferred[1]["not_before"], 2120);
+        assert!(lines(&dead).is_empty());
+
+        // Out of retries: dead-lettered as failed.
+        job.attempt = 2;
+        record_failure(&job_file, &job, &locked, retry, 3000).unwrap();
+        // Access denied is permanent and never deferred.
+        job.attempt = 0;
+        let denied = ExtractError::AccessDenied("permission denied".into());
+        record_failure(&job_file, &job, &denied, retry, 4000).unwrap();
+
+        assert_eq!(lines(&retries).len(), 2);
+        let dead = lines(&dead);
+        assert_eq!(dead.len(), 2);
+        assert!(dead.iter().all(|d| d["skipped"] == false));
+        assert!(
+            dead[1]["reason"]
+                .as_str()
+                .unwrap()
+                .contains("access denied")
+        );
+    }
+}
@@ -1,20 +1,26 @@
 #![cfg(target_os = "windows")]
 
 use crate::{
-    ReloadConfigRequest, ReloadConfigResponse, RescanRequest, RescanResponse, SearchRequest,
-    SearchResponse, StatusRequest, StatusResponse, framing,
+    CancelRequest, CancelResponse, ClearQueryHistoryRequest, ClearQueryHistoryResponse,
+    DiagnosticsRequest, DiagnosticsResponse, ReloadConfigRequest, ReloadConfigResponse,
+    RescanRequest, RescanResponse, ResetIndexRequest, ResetIndexResponse, SchedulerTraceRequest,
+    SchedulerTraceResponse, SearchHit, SearchRequest, SearchResponse, SearchStreamFrame,
+    SearchStreamRequest, StatusRequest, StatusResponse, SuggestRequest, SuggestResponse,
+    VerifyRequest, VerifyResponse, framing,
 };
 use anyhow::{Result, bail};
 use serde::{Serialize, de::DeserializeOwned};
 use std::sync::OnceLock;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::windows::named_pipe::ClientOptions;
+use tokio::net::windows::named_pipe::{ClientOptions, NamedPipeClient};
+use tokio::sync::mpsc;
 use tokio::time::{Duration, sleep};
+use tokio_stream::Stream;
+use tokio_stream::wrappers::ReceiverStream;
 use tracing::warn;
 
 const DEFAULT_PIPE_NAME: &str = r#"\\.\pipe\ultrasearch"#;
-const MAX_MESSAGE_BYTES: usize = 256 * 1024;
 const DEFAULT_TIMEOUT_MS: u64 = 750;
 const DEFAULT_RETRIES: u32 = 5;
 const DEFAULT_BACKOFF_MS: u64 = 100;
@@ -38,6 +44,7 @@ pub struct PipeClient {
     request_timeout: Duration,
     retries: u32,
     backoff: Duration,
+    max_response_bytes: usize,
 }
 
 impl Default for PipeClient {
@@ -47,6 +54,7 @@ impl Default for PipeClient {
             request_timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
             retries: DEFAULT_RETRIES,
             backoff: Duration::from_millis(DEFAULT_BACKOFF_MS),
+            max_response_bytes: framing::DEFAULT_MAX_MESSAGE,
         }
     }
 }
@@ -58,6 +66,7 @@ impl PipeClient {
             request_timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
             retries: DEFAULT_RETRIES,
             backoff: Duration::from_millis(DEFAULT_BACKOFF_MS),
+            max_response_bytes: framing::DEFAULT_MAX_MESSAGE,
         }
     }
 
@@ -76,14 +85,64 @@ impl PipeClient {
         self
     }
 
+    /// Cap on a reassembled (possibly chunked) response.
+    pub fn with_max_response_bytes(mut self, max: usize) -> Self {
+        self.max_response_bytes = max;
+        self
+    }
+
     pub async fn status(&self, req: StatusRequest) -> Result<StatusResponse> {
         self.request(&req).await
     }
 
+    /// One response for `req`. `anytime` requests are answered in several responses and go
+    /// through [`Self::search_anytime`] instead.
     pub async fn search(&self, req: SearchRequest) -> Result<SearchResponse> {
+        if req.anytime {
+            bail!("anytime searches are answered in stages; use search_anytime");
+        }
         self.request(&req).await
     }
 
+    /// Each stage of an `anytime` request as the service sends it: provisional responses
+    /// flagged `refining`, then the final one, after which the stream ends. Ends with an error
+    /// when the connection fails. Dropping the stream closes the connection, which stops the
+    /// search.
+    pub fn search_anytime(
+        &self,
+        mut req: SearchRequest,
+    ) -> impl Stream<Item = Result<SearchResponse>> + use<> {
+        req.anytime = true;
+        // Room for one response, so reading runs at most a stage ahead of the consumer.
+        let (tx, rx) = mpsc::channel(1);
+        let client = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = client.read_stages(&req, &tx).await {
+                let _ = tx.send(Err(e)).await;
+            }
+        });
+        ReceiverStream::new(rx)
+    }
+
+    /// Hits of `req` as the service sends them, for requests over
+    /// [`crate::SEARCH_STREAM_MIN_LIMIT`] hits. Ends after the last hit, or with an error when
+    /// the connection fails or the service rejects the query (`SearchResponse::error`).
+    /// Dropping the stream closes the connection, which stops the search.
+    pub fn search_stream(
+        &self,
+        req: SearchStreamRequest,
+    ) -> impl Stream<Item = Result<SearchHit>> + use<> {
+        // Room for one batch, so reading runs at most a batch ahead of the consumer.
+        let (tx, rx) = mpsc::channel(req.batch_size.max(1) as usize);
+        let client = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = client.read_stream(&req, &tx).await {
+                let _ = tx.send(Err(e)).await;
+            }
+        });
+        ReceiverStream::new(rx)
+    }
+
     pub async fn reload_config(&self, req: ReloadConfigRequest) -> Result<ReloadConfigResponse> {
         self.request(&req).await
     }
@@ -92,6 +151,130 @@ impl PipeClient {
         self.request(&req).await
     }
 
+    pub async fn diagnostics(&self, req: DiagnosticsRequest) -> Result<DiagnosticsResponse> {
+        self.request(&req).await
+    }
+
+    pub async fn reset_index(&self, req: ResetIndexRequest) -> Result<ResetIndexResponse> {
+        self.request(&req).await
+    }
+
+    pub async fn verify(&self, req: VerifyRequest) -> Result<VerifyResponse> {
+        self.request(&req).await
+    }
+
+    pub async fn scheduler_trace(
+        &self,
+        req: SchedulerTraceRequest,
+    ) -> Result<SchedulerTraceResponse> {
+        self.request(&req).await
+    }
+
+    /// Stop a search sent with a `cancel_token`. Goes over its own connection, since the
+    /// search's connection is busy until it answers.
+    pub async fn cancel(&self, req: CancelRequest) -> Result<CancelResponse> {
+        self.request(&req).await
+    }
+
+    pub async fn suggest(&self, req: SuggestRequest) -> Result<SuggestResponse> {
+        self.request(&req).await
+    }
+
+    pub async fn clear_query_history(
+        &self,
+        req: ClearQueryHistoryRequest,
+    ) -> Result<ClearQueryHistoryResponse> {
+        self.request(&req).await
+    }
+
+    async fn read_stream(
+        &self,
+        req: &SearchStreamRequest,
+        tx: &mpsc::Sender<Result<SearchHit>>,
+    ) -> Result<()> {
+        let frame = framing::encode_frame(&bincode::serialize(req)?)?;
+        let mut conn = self.connect().await?;
+        conn.write_all(&frame).await?;
+        loop {
+            let message =
+                tokio::time::timeout(self.request_timeout, self.read_message(&mut conn)).await??;
+            match bincode::deserialize(&message)? {
+                SearchStreamFrame::Hits(batch) => {
+                    for hit in batch.hits {
+                        if tx.send(Ok(hit)).await.is_err() {
+                            // The consumer dropped the stream.
+                            return Ok(());
+                        }
+                    }
+                }
+                SearchStreamFrame::End(end) => match end.error {
+                    Some(error) => bail!("search failed: {error}"),
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+
+    async fn read_stages(
+        &self,
+        req: &SearchRequest,
+        tx: &mpsc::Sender<Result<SearchResponse>>,
+    ) -> Result<()> {
+        let frame = framing::encode_frame(&bincode::serialize(req)?)?;
+        let mut conn = self.connect().await?;
+        conn.write_all(&frame).await?;
+        loop {
+            let message =
+                tokio::time::timeout(self.request_timeout, self.read_message(&mut conn)).await??;
+            let resp: SearchResponse = bincode::deserialize(&message)?;
+            let last = !resp.refining;
+            if tx.send(Ok(resp)).await.is_err() || last {
+                // The consumer dropped the stream, or the search is done.
+                return Ok(());
+            }
+        }
+    }
+
+    /// Open the pipe, retrying while the service is starting or busy.
+    async fn connect(&self) -> Result<NamedPipeClient> {
+        let mut attempt = 0;
+        loop {
+            match ClientOptions::new().open(&self.pipe_name) {
+                Ok(conn) => {
+                    if attempt > 0 {
+                        reconnect_counter().fetch_add(1, Ordering::Relaxed);
+                    }
+                    return Ok(conn);
+                }
+                // 2 = ERROR_FILE_NOT_FOUND, 231 = ERROR_PIPE_BUSY
+                Err(e) if matches!(e.raw_os_error(), Some(2 | 231)) && attempt < self.retries => {
+                    attempt += 1;
+                    warn!("pipe connect attempt {attempt}: service unavailable/busy ({e})");
+                    sleep(self.backoff * attempt.min(10)).await;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Read frames until one message is complete; large messages arrive as sequenced chunks.
+    async fn read_message(&self, conn: &mut NamedPipeClient) -> Result<Vec<u8>> {
+        let mut assembler = framing::ChunkAssembler::new(self.max_response_bytes);
+        loop {
+            let mut len_buf = [0u8; 4];
+            conn.read_exact(&mut len_buf).await?;
+            let header = framing::parse_header(len_buf)?;
+            if header.len == 0 {
+                bail!("invalid response length 0");
+            }
+            let mut body = vec![0u8; header.len];
+            conn.read_exact(&mut body).await?;
+            if let Some(message) = assembler.push(header, &body)? {
+                return Ok(message);
+            }
+        }
+    }
+
     async fn request<Req, Resp>(&self, req: &Req) -> Result<Resp>
     where
         Req: Serialize,
@@ -119,23 +302,8 @@ impl PipeClient {
                 // Write the framed request
                 conn.write_all(&frame).await?;
 
-                // Read response header
-                let mut len_buf = [0u8; 4];
-                conn.read_exact(&mut len_buf).await?;
-                let resp_len = u32::from_le_bytes(len_buf) as usize;
-
-                if resp_len == 0 || resp_len > MAX_MESSAGE_BYTES {
-                    bail!("invalid response length {}", resp_len);
-                }
-
-                // Read response body
-                let mut buf = vec![0u8; resp_len];
-                conn.read_exact(&mut buf).await?;
+                let buf = self.read_message(&mut conn).await?;
 
-                // Deserialize directly from the body buffer
-                // (framing::decode_frame expects [header + body], but we already consumed header.
-                // Since we trust the stream logic here, we can skip using decode_frame logic for the buffer check
-                // and just deserialize the body.)
                 let resp: Resp = bincode::deserialize(&buf)?;
                 Ok(resp)
             };
@@ -1,7 +1,17 @@
 //! Simple length-prefixed framing helpers for pipe/stream transport.
+//!
+//! Messages that fit in one frame are sent as a bare frame. Larger messages are split into
+//! chunk frames: the high bit of the length word is set and the body starts with a
+//! `seq: u32` / `more: u8` header, so old single-frame messages decode unchanged.
 use anyhow::{Result, bail};
 
 pub const MAX_FRAME: usize = 256 * 1024;
+/// Set in the length word of a chunk frame.
+pub const CHUNK_FLAG: u32 = 0x8000_0000;
+/// `seq` (u32 LE) + `more` (u8) at the start of a chunk frame body.
+pub const CHUNK_HEADER_LEN: usize = 5;
+/// Default cap on a reassembled message.
+pub const DEFAULT_MAX_MESSAGE: usize = 64 * 1024 * 1024;
 
 /// Encode a payload with a little-endian u32 length prefix.
 pub fn encode_frame(payload: &[u8]) -> Result<Vec<u8>> {
@@ -34,6 +44,146 @@ pub fn decode_frame(buf: &[u8]) -> Result<(Vec<u8>, &[u8])> {
     Ok((payload, &buf[4 + len..]))
 }
 
+/// Decoded frame length word.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameHeader {
+    /// Body length in bytes (chunk header included).
+    pub len: usize,
+    pub chunked: bool,
+}
+
+/// Parse and validate the 4-byte length word of a frame.
+pub fn parse_header(header: [u8; 4]) -> Result<FrameHeader> {
+    let word = u32::from_le_bytes(header);
+    let chunked = word & CHUNK_FLAG != 0;
+    let len = (word & !CHUNK_FLAG) as usize;
+    if len > MAX_FRAME {
+        bail!("frame too large: {} bytes", len);
+    }
+    if chunked && len < CHUNK_HEADER_LEN {
+        bail!("chunk frame shorter than its header: {} bytes", len);
+    }
+    Ok(FrameHeader { len, chunked })
+}
+
+/// Encode a message of any size. Payloads up to `chunk_size` become a single plain frame;
+/// larger ones are split into chunk frames whose bodies are at most `chunk_size` bytes.
+pub fn encode_chunked(payload: &[u8], chunk_size: usize) -> Result<Vec<u8>> {
+    if chunk_size <= CHUNK_HEADER_LEN || chunk_size > MAX_FRAME {
+        bail!("invalid chunk size {}", chunk_size);
+    }
+    if payload.len() <= chunk_size {
+        return encode_frame(payload);
+    }
+    let data_len = chunk_size - CHUNK_HEADER_LEN;
+    let count = payload.len().div_ceil(data_len);
+    if count > u32::MAX as usize {
+        bail!("message needs too many chunks: {}", count);
+    }
+    let mut buf = Vec::with_capacity(payload.len() + count * (4 + CHUNK_HEADER_LEN));
+    for (seq, data) in payload.chunks(data_len).enumerate() {
+        let more = seq + 1 < count;
+        let len = (CHUNK_HEADER_LEN + data.len()) as u32;
+        buf.extend_from_slice(&(len | CHUNK_FLAG).to_le_bytes());
+        buf.extend_from_slice(&(seq as u32).to_le_bytes());
+        buf.push(more as u8);
+        buf.extend_from_slice(data);
+    }
+    Ok(buf)
+}
+
+/// Reassembles a message from the frames produced by [`encode_chunked`].
+#[derive(Debug)]
+pub struct ChunkAssembler {
+    buf: Vec<u8>,
+    next_seq: u32,
+    max_message: usize,
+}
+
+impl Default for ChunkAssembler {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_MESSAGE)
+    }
+}
+
+impl ChunkAssembler {
+    pub fn new(max_message: usize) -> Self {
+        Self {
+            buf: Vec::new(),
+            next_seq: 0,
+            max_message,
+        }
+    }
+
+    /// Feed one frame body. Returns the full message once the last chunk (or a plain frame)
+    /// arrives, and errors on out-of-order chunks or when the message exceeds the cap.
+    pub fn push(&mut self, header: FrameHeader, body: &[u8]) -> Result<Option<Vec<u8>>> {
+        if body.len() != header.len {
+            bail!(
+                "frame body is {} bytes, header says {}",
+                body.len(),
+                header.len
+            );
+        }
+        if !header.chunked {
+            if self.next_seq != 0 {
+                bail!(
+                    "plain frame inside chunked message at seq {}",
+                    self.next_seq
+                );
+            }
+            return Ok(Some(body.to_vec()));
+        }
+        let seq = u32::from_le_bytes(body[0..4].try_into().unwrap());
+        let more = body[4] != 0;
+        if seq != self.next_seq {
+            bail!(
+                "chunk out of order: expected {}, got {}",
+                self.next_seq,
+                seq
+            );
+        }
+        let data = &body[CHUNK_HEADER_LEN..];
+        if self.buf.len() + data.len() > self.max_message {
+            bail!(
+                "message exceeds {} bytes after {} chunks",
+                self.max_message,
+                seq + 1
+            );
+        }
+        self.buf.extend_from_slice(data);
+        if more {
+            self.next_seq = seq
+                .checked_add(1)
+                .ok_or_else(|| anyhow::anyhow!("chunk sequence overflow"))?;
+            return Ok(None);
+        }
+        self.next_seq = 0;
+        Ok(Some(std::mem::take(&mut self.buf)))
+    }
+}
+
+/// Decode one (possibly chunked) message from the provided buffer.
+/// Returns (message, remaining).
+pub fn decode_message(buf: &[u8], max_message: usize) -> Result<(Vec<u8>, &[u8])> {
+    let mut assembler = ChunkAssembler::new(max_message);
+    let mut rest = buf;
+    loop {
+        if rest.len() < 4 {
+            bail!("incomplete frame header");
+        }
+        let header = parse_header(rest[0..4].try_into().unwrap())?;
+        if rest.len() < 4 + header.len {
+            bail!("incomplete frame body");
+        }
+        let body = &rest[4..4 + header.len];
+        rest = &rest[4 + header.len..];
+        if let Some(message) = assembler.push(header, body)? {
+            return Ok((message, rest));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,4 +241,56 @@ mod tests {
         let res = decode_frame(&[0, 0, 0]);
         assert!(res.is_err());
     }
+
+    #[test]
+    fn large_message_is_split_and_reassembled() {
+        let payload: Vec<u8> = (0..MAX_FRAME * 2 + 1234).map(|i| (i % 251) as u8).collect();
+        let encoded = encode_chunked(&payload, MAX_FRAME).unwrap();
+
+        // Every frame respects MAX_FRAME and only the last clears `more`.
+        let mut rest = encoded.as_slice();
+        let mut more_flags = Vec::new();
+        while !rest.is_empty() {
+            let header = parse_header(rest[0..4].try_into().unwrap()).unwrap();
+            assert!(header.chunked);
+            assert!(header.len <= MAX_FRAME);
+            more_flags.push(rest[4 + 4]);
+            rest = &rest[4 + header.len..];
+        }
+        assert_eq!(more_flags, vec![1, 1, 0]);
+
+        let (out, rem) = decode_message(&encoded, DEFAULT_MAX_MESSAGE).unwrap();
+        assert_eq!(out, payload);
+        assert!(rem.is_empty());
+    }
+
+    #[test]
+    fn small_message_stays_a_plain_frame() {
+        let encoded = encode_chunked(b"hello", MAX_FRAME).unwrap();
+        assert_eq!(encoded, encode_frame(b"hello").unwrap());
+        let (out, _) = decode_message(&encoded, DEFAULT_MAX_MESSAGE).unwrap();
+        assert_eq!(out, b"hello");
+    }
+
+    #[test]
+    fn assembler_rejects_out_of_order_and_oversized_messages() {
+        let payload = vec![7u8; 100];
+        let encoded = encode_chunked(&payload, 32).unwrap();
+        let first = parse_header(encoded[0..4].try_into().unwrap()).unwrap();
+        let first_body = &encoded[4..4 + first.len];
+        let second_start = 4 + first.len;
+        let second =
+            parse_header(encoded[second_start..second_start + 4].try_into().unwrap()).unwrap();
+        let second_body = &encoded[second_start + 4..second_start + 4 + second.len];
+
+        let mut skipped = ChunkAssembler::default();
+        assert!(skipped.push(second, second_body).is_err());
+
+        let mut capped = ChunkAssembler::new(40);
+        assert!(capped.push(first, first_body).unwrap().is_none());
+        assert!(capped.push(second, second_body).is_err());
+
+        assert!(decode_message(&encoded, 99).is_err());
+        assert_eq!(decode_message(&encoded, 100).unwrap().0, payload);
+    }
 }
@@ -79,6 +79,22 @@ pub enum FieldKind {
     Flags,
     Volume,
     Kind,
+    /// Owner SID (`S-1-5-21-...`); only matches when owners are indexed.
+    Owner,
+    /// User-assigned tag (`tag:important`), set with [`TagRequest`].
+    Tag,
+    /// MIME type detected from content (`mime:application/pdf`); content index only, and only
+    /// populated when `extract.detect_mime` is on.
+    Mime,
+    /// Email header fields of `.eml`/`.mbox` files; content index only, and only populated
+    /// when `extract.index_email` is on. `Sent` is the `Date` header, for range queries.
+    Subject,
+    From,
+    To,
+    Sent,
+    /// Title from the document's own metadata (`title:"Annual Report"`); content index only,
+    /// and only populated when `extract.index_titles` is on.
+    Title,
 }
 
 /// How a term should be interpreted.
@@ -88,6 +104,20 @@ pub enum TermModifier {
     Phrase,
     Prefix,
     Fuzzy(u8), // max edit distance
+    /// Whole directory name matched against any ancestor folder (`path` field only).
+    Component,
+    /// Sound-alike match on name words by phonetic code ("Stefan" finds "Stephan"; `name`
+    /// field only).
+    Phonetic,
+    /// Shell-style pattern matched against the whole file name, case-insensitively (`name`
+    /// field only): `*` matches any run of characters, `?` one character, and `[a-z]` one
+    /// character of a class (`[!a-z]` or `[^a-z]` negates it). A backslash makes the next
+    /// character literal, so `\*` matches a `*` in the name.
+    Glob,
+    /// Regular expression (`regex` crate syntax) searched for anywhere in the file name,
+    /// case-insensitively (`name` field only); anchor it with `^`/`$`. An invalid pattern
+    /// fails the request with `SearchResponse::error`.
+    Regex,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -126,6 +156,13 @@ pub enum QueryExpr {
     Not(Box<QueryExpr>),
     And(Vec<QueryExpr>),
     Or(Vec<QueryExpr>),
+    /// Content words within `slop` positions of each other: two terms in either order, more
+    /// in the order given. Content only, so a `NameOnly` request containing it is rejected
+    /// with `SearchResponse::error`.
+    Near {
+        terms: Vec<String>,
+        slop: u32,
+    },
 }
 
 impl Default for QueryExpr {
@@ -143,6 +180,57 @@ pub enum SearchMode {
     Hybrid,   // meta + content merge
 }
 
+/// Optional grouping applied to the returned page of hits.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum GroupBy {
+    /// Group by the hit's containing folder.
+    Folder,
+}
+
+/// Hit field a request can order results by instead of relevance.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum SortField {
+    /// File name, collated for the service's `search.sort_locale`.
+    Name,
+    /// Full path, collated like `Name`.
+    Path,
+    Size,
+    Modified,
+}
+
+/// Result ordering; hits missing the sort field go last in either direction.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SortSpec {
+    pub field: SortField,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// Optional `SearchHit` field a request can project; `key` and `score` are always filled.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HitField {
+    Name,
+    Path,
+    Ext,
+    Size,
+    Modified,
+    Snippet,
+    Tags,
+}
+
+/// Whether a hit's content made it into the content index (`SearchHit::extract_status`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ExtractStatus {
+    /// Content is indexed.
+    Ok,
+    /// Deliberately not extracted: a directory, an unsupported format, or over the size limit.
+    Skipped,
+    /// Extraction failed, with the worker's reason.
+    Failed(String),
+    /// Not extracted yet, e.g. still queued or awaiting re-extraction.
+    Pending,
+}
+
 #[cfg(windows)]
 pub mod client;
 pub mod framing;
@@ -158,14 +246,87 @@ pub struct SearchRequest {
     pub mode: SearchMode,
     #[serde(default, with = "duration_ms::option")]
     pub timeout: Option<Duration>,
+    /// Hits to skip. Results are ordered by score, then `DocKey` ascending, before `offset`
+    /// and `limit` apply, so consecutive pages over an unchanged index neither repeat nor
+    /// skip a hit.
     #[serde(default)]
     pub offset: u32,
+    #[serde(default)]
+    pub group_by: Option<GroupBy>,
+    /// Fields to populate on each hit (`None` = all). List views can skip snippets and sizes.
+    #[serde(default)]
+    pub fields: Option<Vec<HitField>>,
+    /// Search within results: only documents in this set (typically the keys of an earlier
+    /// response) can match. `None` searches the whole corpus.
+    #[serde(default)]
+    pub refine: Option<Vec<DocKey>>,
+    /// Order by a hit field instead of score (`None` = relevance).
+    #[serde(default)]
+    pub sort: Option<SortSpec>,
+    /// Fragments to return per content hit in `SearchHit::snippets`, best first.
+    #[serde(default = "default_snippets_per_hit")]
+    pub snippets_per_hit: u8,
+    /// Continue a paged response: the `next_cursor` of the previous page. Overrides `offset`
+    /// and `limit`; the rest of the request should be unchanged.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Report `SearchResponse::total_bytes_matched`. Costs a pass over every match, so it is
+    /// off unless asked for.
+    #[serde(default)]
+    pub total_bytes: bool,
+    /// Directory the search is made from (e.g. an IDE's project folder). Hits in or near it
+    /// rank higher, closer ones more so; `None` ranks by relevance alone.
+    #[serde(default)]
+    pub context_path: Option<String>,
+    /// Fill `SearchHit::extract_status`, so a client can tell why a name hit has no content.
+    #[serde(default)]
+    pub extract_status: bool,
+    /// Answer in stages: a quick best-effort response first, then refined ones as more
+    /// candidates are scored, each flagged `refining` except the last. Needs a client that
+    /// reads responses until one arrives with `refining == false`, such as
+    /// `PipeClient::search_anytime`.
+    #[serde(default)]
+    pub anytime: bool,
+    /// Report the query actually executed in `SearchResponse::executed_query`.
+    #[serde(default)]
+    pub explain: bool,
+    /// Reproducible results for snapshot tests and bug reports: content scoring is never
+    /// capped, so the same hits come back whatever the index layout. Equal scores are broken
+    /// by `DocKey` with or without it.
+    #[serde(default)]
+    pub deterministic_order: bool,
+    /// Most hits from any one folder (`None` = no cap), so a single huge directory can't
+    /// crowd out matches elsewhere. Lower-ranked hits past the cap are left out and the next
+    /// best hits from other folders take their places.
+    #[serde(default)]
+    pub max_hits_per_dir: Option<u32>,
+    /// Cache every matched `DocKey` on the server and return the set's id in
+    /// `SearchResponse::result_set_id`, for [`ExportResultsRequest`] or `refine_set`.
+    #[serde(default)]
+    pub result_set: bool,
+    /// Search within a cached result set: like `refine` with that set's keys. An unknown or
+    /// expired id fails the request with `SearchResponse::error`.
+    #[serde(default)]
+    pub refine_set: Option<Uuid>,
+    /// Let a [`CancelRequest`] carrying this token stop the search while it runs, e.g. once a
+    /// newer keystroke has superseded it. Usually the request's own `id`.
+    #[serde(default)]
+    pub cancel_token: Option<Uuid>,
+    /// The query as the user typed it, recorded in the service's query history for
+    /// [`SuggestRequest`] when `search.query_history_len` > 0. Send it for searches the user
+    /// settled on (Enter, a CLI run), not for every keystroke of a typeahead.
+    #[serde(default)]
+    pub query_text: Option<String>,
 }
 
 fn default_limit() -> u32 {
     50
 }
 
+fn default_snippets_per_hit() -> u8 {
+    1
+}
+
 impl Default for SearchRequest {
     fn default() -> Self {
         SearchRequest {
@@ -175,6 +336,23 @@ impl Default for SearchRequest {
             mode: SearchMode::Auto,
             timeout: None,
             offset: 0,
+            group_by: None,
+            fields: None,
+            refine: None,
+            sort: None,
+            snippets_per_hit: 1,
+            cursor: None,
+            total_bytes: false,
+            context_path: None,
+            extract_status: false,
+            anytime: false,
+            explain: false,
+            deterministic_order: false,
+            max_hits_per_dir: None,
+            result_set: false,
+            refine_set: None,
+            cancel_token: None,
+            query_text: None,
         }
     }
 }
@@ -211,6 +389,113 @@ impl SearchRequest {
         self.mode = mode;
         self
     }
+
+    /// Request grouped results.
+    pub fn with_group_by(mut self, group_by: GroupBy) -> Self {
+        self.group_by = Some(group_by);
+        self
+    }
+
+    /// Only populate the given hit fields.
+    pub fn with_fields(mut self, fields: impl Into<Vec<HitField>>) -> Self {
+        self.fields = Some(fields.into());
+        self
+    }
+
+    /// Restrict matches to `keys`, e.g. the hits of a previous search.
+    pub fn within(mut self, keys: impl Into<Vec<DocKey>>) -> Self {
+        self.refine = Some(keys.into());
+        self
+    }
+
+    /// Order results by `field` instead of relevance.
+    pub fn with_sort(mut self, field: SortField, descending: bool) -> Self {
+        self.sort = Some(SortSpec { field, descending });
+        self
+    }
+
+    /// Continue from a previous response's `next_cursor`.
+    pub fn with_cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    /// Ask for the summed size of the whole result set.
+    pub fn with_total_bytes(mut self) -> Self {
+        self.total_bytes = true;
+        self
+    }
+
+    /// Report each hit's content extraction state.
+    pub fn with_extract_status(mut self) -> Self {
+        self.extract_status = true;
+        self
+    }
+
+    /// Stream a fast first response followed by refined ones.
+    pub fn with_anytime(mut self) -> Self {
+        self.anytime = true;
+        self
+    }
+
+    /// Report the executed query alongside the results.
+    pub fn with_explain(mut self) -> Self {
+        self.explain = true;
+        self
+    }
+
+    /// Order results reproducibly across runs and machines.
+    pub fn with_deterministic_order(mut self) -> Self {
+        self.deterministic_order = true;
+        self
+    }
+
+    /// Cache the matched keys and report the set's id.
+    pub fn with_result_set(mut self) -> Self {
+        self.result_set = true;
+        self
+    }
+
+    /// Search only the documents of a cached result set.
+    pub fn with_refine_set(mut self, id: Uuid) -> Self {
+        self.refine_set = Some(id);
+        self
+    }
+
+    /// Make the search cancellable under its own id (see [`CancelRequest`]).
+    pub fn with_cancel_token(mut self) -> Self {
+        self.cancel_token = Some(self.id);
+        self
+    }
+
+    /// Record `text` in the query history (see [`SearchRequest::query_text`]).
+    pub fn with_query_text(mut self, text: impl Into<String>) -> Self {
+        self.query_text = Some(text.into());
+        self
+    }
+
+    /// Return at most `n` hits from any one folder.
+    pub fn with_max_hits_per_dir(mut self, n: u32) -> Self {
+        self.max_hits_per_dir = Some(n);
+        self
+    }
+
+    /// Favour hits near `dir`.
+    pub fn with_context_path(mut self, dir: impl Into<String>) -> Self {
+        self.context_path = Some(dir.into());
+        self
+    }
+
+    /// Return up to `n` snippet fragments per content hit.
+    pub fn with_snippets_per_hit(mut self, n: u8) -> Self {
+        self.snippets_per_hit = n;
+        self
+    }
+
+    /// Whether hits should carry `field` under this request's projection.
+    pub fn wants_field(&self, field: HitField) -> bool {
+        self.fields.as_ref().is_none_or(|f| f.contains(&field))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -222,18 +507,185 @@ pub struct SearchHit {
     pub ext: Option<String>,
     pub size: Option<u64>,
     pub modified: Option<i64>,
+    /// Best fragment; the same as `snippets[0]`, kept for older clients.
     pub snippet: Option<String>,
+    /// Up to `SearchRequest::snippets_per_hit` fragments, best first.
+    #[serde(default)]
+    pub snippets: Vec<String>,
+    /// User-assigned tags, sorted.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Content extraction state, when the request set `extract_status`.
+    #[serde(default)]
+    pub extract_status: Option<ExtractStatus>,
+    /// For a file listed from inside an archive (`extract.index_archives`), the archive's key;
+    /// `path` then runs through the archive, e.g. `C:\backup.zip\docs\report.pdf`.
+    #[serde(default)]
+    pub archive: Option<DocKey>,
+    /// The hit matched a name the file had before it was renamed (`search.match_former_names`),
+    /// not its current name.
+    #[serde(default)]
+    pub matched_former_name: bool,
+}
+
+impl SearchHit {
+    /// Clear every optional field not listed in `fields`.
+    pub fn project(&mut self, fields: &[HitField]) {
+        let keep = |f: HitField| fields.contains(&f);
+        if !keep(HitField::Name) {
+            self.name = None;
+        }
+        if !keep(HitField::Path) {
+            self.path = None;
+        }
+        if !keep(HitField::Ext) {
+            self.ext = None;
+        }
+        if !keep(HitField::Size) {
+            self.size = None;
+        }
+        if !keep(HitField::Modified) {
+            self.modified = None;
+        }
+        if !keep(HitField::Snippet) {
+            self.snippet = None;
+            self.snippets.clear();
+        }
+        if !keep(HitField::Tags) {
+            self.tags.clear();
+        }
+    }
+}
+
+/// Hits sharing a containing folder, best first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderGroup {
+    pub folder_path: String,
+    pub hits: Vec<SearchHit>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResponse {
     pub id: Uuid,
     pub hits: Vec<SearchHit>,
+    /// Every match of the query, whatever `offset` and `limit` (see `approximate` for the
+    /// exception).
     pub total: u64,
     pub truncated: bool,
     pub took_ms: u32,
     #[serde(default)]
     pub served_by: Option<String>,
+    /// Present when the request set `group_by`; groups are ordered by their top hit's score.
+    #[serde(default)]
+    pub grouped: Option<Vec<FolderGroup>>,
+    /// Set when the requested mode couldn't be served as asked and this mode answered
+    /// instead, e.g. `NameOnly` for a hybrid query while the content index is still empty.
+    #[serde(default)]
+    pub degraded_to: Option<SearchMode>,
+    /// Set when the server paged a large result: `hits` is the first page and this cursor
+    /// fetches the next one (via `SearchRequest::cursor`). `None` means nothing follows.
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+    /// Set when the content query matched more documents than the server scores per query
+    /// (`search.content_max_docs_scored`): `hits` are the best of those scored, not
+    /// necessarily of every match, and `total` counts only the scored ones.
+    #[serde(default)]
+    pub approximate: bool,
+    /// Summed size of every file in the result set (not just this page), when the request
+    /// set `total_bytes`. Directories count per `search.total_bytes_directories`.
+    #[serde(default)]
+    pub total_bytes_matched: Option<u64>,
+    /// An `anytime` request's provisional answer: a better-ranked response for the same
+    /// request follows on the connection.
+    #[serde(default)]
+    pub refining: bool,
+    /// The query as run, after planning and any trailing-term prefix upgrade; set when the
+    /// request asked for `explain`.
+    #[serde(default)]
+    pub executed_query: Option<QueryExpr>,
+    /// Set when the request couldn't be run as written, e.g. a `Regex` term whose pattern
+    /// doesn't compile; `hits` is then empty. Tells a bad query apart from one with no matches.
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Id of the cached set of every matched key, when the request set `result_set` and the
+    /// server cached it.
+    #[serde(default)]
+    pub result_set_id: Option<Uuid>,
+    /// A [`CancelRequest`] stopped the search before it finished; `hits` is then empty and
+    /// `truncated` is set.
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+/// Position within a paged result: the next hit to return and the end of the originally
+/// requested window. Sent to clients as an opaque string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageCursor {
+    pub offset: u32,
+    pub end: u32,
+}
+
+impl PageCursor {
+    pub fn encode(self) -> String {
+        format!("{}-{}", self.offset, self.end)
+    }
+
+    /// `None` for a malformed cursor or one past its end.
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let (offset, end) = cursor.split_once('-')?;
+        let cursor = Self {
+            offset: offset.parse().ok()?,
+            end: end.parse().ok()?,
+        };
+        (cursor.offset < cursor.end).then_some(cursor)
+    }
+}
+
+/// Requests for more hits than this should be sent as a [`SearchStreamRequest`]. A one-shot
+/// response has to be built, encoded and read whole before the client shows anything, and
+/// past `search.stream_threshold` (1000 by default) the server pages it behind
+/// `next_cursor` anyway; a stream delivers every hit of the window in batches as they are
+/// ready.
+pub const SEARCH_STREAM_MIN_LIMIT: u32 = 1000;
+
+/// Run `request` and answer with a sequence of [`SearchStreamFrame`]s on the connection: its
+/// hits in [`SearchHitBatch`]es of up to `batch_size`, in rank order, then one `End` frame.
+/// The server follows its own paging, so the whole `offset`/`limit` window is sent.
+/// `anytime` and `group_by` are ignored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchStreamRequest {
+    pub request: SearchRequest,
+    /// Hits per batch (0 = [`Self::DEFAULT_BATCH_SIZE`]).
+    pub batch_size: u32,
+}
+
+impl SearchStreamRequest {
+    pub const DEFAULT_BATCH_SIZE: u32 = 256;
+
+    pub fn new(request: SearchRequest) -> Self {
+        Self {
+            request,
+            batch_size: Self::DEFAULT_BATCH_SIZE,
+        }
+    }
+}
+
+/// Consecutive hits of a streamed search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHitBatch {
+    /// The streamed request's id.
+    pub id: Uuid,
+    /// 0 for the first batch, counting up.
+    pub seq: u32,
+    pub hits: Vec<SearchHit>,
+}
+
+/// One message of a streamed search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SearchStreamFrame {
+    Hits(SearchHitBatch),
+    /// Last frame: the response without its hits (`total`, `approximate`, `error`, ...).
+    End(SearchResponse),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -265,6 +717,186 @@ pub struct RescanResponse {
     pub message: Option<String>,
 }
 
+/// Request a diagnostics bundle (config, index stats, log tail, scheduler, volumes, version).
+///
+/// Carries `log_tail_lines` so its wire shape differs from the id-only requests.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsRequest {
+    pub id: Uuid,
+    /// Maximum number of recent warning/error log lines to include.
+    pub log_tail_lines: u32,
+}
+
+impl DiagnosticsRequest {
+    pub const DEFAULT_LOG_TAIL_LINES: u32 = 200;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticsResponse {
+    pub id: Uuid,
+    pub success: bool,
+    /// Pretty-printed JSON bundle; empty when `success` is false.
+    pub bundle_json: String,
+    pub message: Option<String>,
+}
+
+/// Wipe all indexes. The service only proceeds when `confirm_token` matches the token it
+/// wrote to its log; an empty or wrong token is rejected (and makes sure a token is logged).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResetIndexRequest {
+    pub id: Uuid,
+    pub confirm_token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResetIndexResponse {
+    pub id: Uuid,
+    pub success: bool,
+    pub message: Option<String>,
+}
+
+/// Run the index integrity self-check. Its `u32` + `bool` tail keeps the wire shape distinct
+/// from the id-only requests and [`DiagnosticsRequest`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyRequest {
+    pub id: Uuid,
+    /// DocKeys sampled from the name FST and looked up in the meta index.
+    pub sample_keys: u32,
+    /// Also verify segment file checksums (slow: reads every index file).
+    pub checksums: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyResponse {
+    pub id: Uuid,
+    /// Every component passed or was skipped.
+    pub healthy: bool,
+    pub rebuild_recommended: bool,
+    /// Pretty-printed JSON report with per-component status and detail.
+    pub report_json: String,
+}
+
+/// Fetch the scheduler's most recent tick decisions. The `u16` limit keeps the wire shape
+/// distinct from the id-only requests and [`DiagnosticsRequest`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerTraceRequest {
+    pub id: Uuid,
+    pub limit: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerTraceResponse {
+    pub id: Uuid,
+    /// `scheduler.decision_trace_len` > 0; when false `decisions_json` is an empty list.
+    pub enabled: bool,
+    /// JSON array of decisions, oldest first: tick inputs (idle, CPU, disk, battery), whether
+    /// content work was allowed, the reasons it was blocked, and what was dispatched.
+    pub decisions_json: String,
+}
+
+/// Add and remove user tags on one document. Tags are trimmed and lowercased; removals
+/// apply after additions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagRequest {
+    pub id: Uuid,
+    pub key: DocKey,
+    pub add: Vec<String>,
+    pub remove: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagResponse {
+    pub id: Uuid,
+    pub success: bool,
+    /// The document's tags after the update, sorted.
+    pub tags: Vec<String>,
+    pub message: Option<String>,
+}
+
+/// Read back a cached result set (`SearchResponse::result_set_id`), `limit` keys from
+/// `offset` on. The two `u32`s keep the wire shape distinct from [`ResetIndexRequest`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportResultsRequest {
+    pub id: Uuid,
+    pub result_set_id: Uuid,
+    pub offset: u32,
+    pub limit: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportResultsResponse {
+    pub id: Uuid,
+    /// The set is still cached; when false it expired or never existed and `keys` is empty.
+    pub found: bool,
+    /// Keys of the set in ascending order.
+    pub keys: Vec<DocKey>,
+    /// Size of the whole set.
+    pub total: u64,
+}
+
+/// Stop the in-flight search whose `SearchRequest::cancel_token` is `token`; it answers
+/// with `SearchResponse::cancelled` set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelRequest {
+    pub id: Uuid,
+    pub token: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CancelResponse {
+    pub id: Uuid,
+    /// A search was running under the token; false if it had already finished or never
+    /// started.
+    pub cancelled: bool,
+}
+
+/// Typeahead suggestions for `prefix`: past queries from the service's query history and
+/// file names starting with it. The `u16` tail keeps the wire shape distinct from
+/// [`ResetIndexRequest`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestRequest {
+    pub id: Uuid,
+    pub prefix: String,
+    /// Most entries of each kind to return.
+    pub limit: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestResponse {
+    pub id: Uuid,
+    /// Recorded queries starting with the prefix (case-insensitively), most recently used
+    /// first; empty when `search.query_history_len` is 0.
+    pub queries: Vec<QuerySuggestion>,
+    /// Distinct names of indexed files starting with the prefix.
+    pub names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct QuerySuggestion {
+    pub query: String,
+    /// Unix seconds of the last search with this query.
+    pub last_used: i64,
+    pub uses: u32,
+}
+
+/// Forget recorded queries starting with `prefix` that were not used in the last
+/// `older_than_secs` seconds; 0 and an empty prefix clear the whole history. The `prefix` tail
+/// keeps the wire shape distinct from [`ResetIndexRequest`], including its empty-token form.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClearQueryHistoryRequest {
+    pub id: Uuid,
+    pub older_than_secs: u64,
+    /// Only queries starting with this (case-insensitively); empty for all.
+    pub prefix: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClearQueryHistoryResponse {
+    pub id: Uuid,
+    /// Entries removed.
+    pub removed: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VolumeStatus {
     pub volume: u16,
@@ -286,6 +918,9 @@ pub struct StatusResponse {
     pub content_jobs_remaining: Option<u64>,
     pub content_bytes_total: Option<u64>,
     pub content_bytes_remaining: Option<u64>,
+    /// Estimated seconds until pending content jobs finish, from the recent completion rate
+    /// (`scheduler.eta_window_secs`); `None` while throughput is unknown or zero.
+    pub eta_seconds: Option<u64>,
     pub metrics: Option<MetricsSnapshot>,
     pub served_by: Option<String>,
 }
@@ -302,6 +937,12 @@ pub struct MetricsSnapshot {
     pub content_enqueued: Option<u64>,
     /// Total content jobs dropped due to backpressure or missing scheduler (best-effort).
     pub content_dropped: Option<u64>,
+    /// Consecutive named-pipe create failures in the server's accept loop (0 = healthy).
+    pub pipe_accept_failures: Option<u64>,
+    /// Content bodies trimmed by `indexing.max_index_bytes` since startup.
+    pub trimmed_content: Option<u64>,
+    /// Metadata documents trimmed by `indexing.max_index_bytes` since startup.
+    pub trimmed_meta: Option<u64>,
 }
 
 #[cfg(test)]
@@ -344,19 +985,51 @@ mod tests {
                     hi: None,
                 },
             }),
+            QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: "report_??.pdf".into(),
+                modifier: TermModifier::Glob,
+            }),
+            QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: r"^report_\d+\.pdf$".into(),
+                modifier: TermModifier::Regex,
+            }),
+            QueryExpr::Near {
+                terms: vec!["quarterly".into(), "revenue".into()],
+                slop: 3,
+            },
         ]);
 
         let req = SearchRequest {
             id: Uuid::new_v4(),
-            query: q,
+            query: q.clone(),
             limit: 20,
             mode: SearchMode::Hybrid,
             timeout: None,
             offset: 0,
+            group_by: None,
+            fields: None,
+            refine: None,
+            sort: None,
+            snippets_per_hit: 1,
+            cursor: None,
+            total_bytes: false,
+            context_path: None,
+            extract_status: false,
+            anytime: false,
+            explain: false,
+            deterministic_order: false,
+            max_hits_per_dir: None,
+            result_set: false,
+            refine_set: None,
+            cancel_token: None,
+            query_text: None,
         };
 
         let bytes = ser(&req);
         let back: SearchRequest = de(&bytes);
+        assert_eq!(back.query, q);
         assert_eq!(back.limit, 20);
         assert!(matches!(back.mode, SearchMode::Hybrid));
         assert_eq!(back.timeout, None);
@@ -376,11 +1049,30 @@ mod tests {
             mode: SearchMode::Auto,
             timeout: Some(Duration::from_millis(250)),
             offset: 7,
+            group_by: None,
+            fields: Some(vec![HitField::Name, HitField::Path]),
+            refine: None,
+            sort: None,
+            snippets_per_hit: 1,
+            cursor: None,
+            total_bytes: false,
+            context_path: None,
+            extract_status: false,
+            anytime: false,
+            explain: false,
+            deterministic_order: false,
+            max_hits_per_dir: None,
+            result_set: false,
+            refine_set: None,
+            cancel_token: None,
+            query_text: None,
         };
         let bytes = ser(&req);
         let back: SearchRequest = de(&bytes);
         assert_eq!(back.timeout, Some(Duration::from_millis(250)));
         assert_eq!(back.offset, 7);
+        assert!(back.wants_field(HitField::Path));
+        assert!(!back.wants_field(HitField::Snippet));
     }
 
     #[test]
@@ -413,6 +1105,9 @@ mod tests {
             active_workers: Some(2),
             content_enqueued: Some(9),
             content_dropped: Some(1),
+            pipe_accept_failures: Some(3),
+            trimmed_content: Some(4),
+            trimmed_meta: Some(0),
         };
         let bytes = ser(&m);
         let back: MetricsSnapshot = de(&bytes);
@@ -420,6 +1115,217 @@ mod tests {
         assert_eq!(back.active_workers, Some(2));
         assert_eq!(back.content_enqueued, Some(9));
         assert_eq!(back.content_dropped, Some(1));
+        assert_eq!(back.pipe_accept_failures, Some(3));
+        assert_eq!(back.trimmed_content, Some(4));
+    }
+
+    #[test]
+    fn reset_request_does_not_alias_other_requests() {
+        let id = Uuid::new_v4();
+        let reset = ser(&ResetIndexRequest {
+            id,
+            confirm_token: String::new(),
+        });
+        assert_ne!(reset.len(), ser(&StatusRequest { id }).len());
+        assert_ne!(
+            reset.len(),
+            ser(&DiagnosticsRequest {
+                id,
+                log_tail_lines: 0
+            })
+            .len()
+        );
+        let back: ResetIndexRequest = de(&reset);
+        assert_eq!(back.id, id);
+        assert!(back.confirm_token.is_empty());
+    }
+
+    #[test]
+    fn diagnostics_request_does_not_alias_status() {
+        let id = Uuid::new_v4();
+        let diag = ser(&DiagnosticsRequest {
+            id,
+            log_tail_lines: DiagnosticsRequest::DEFAULT_LOG_TAIL_LINES,
+        });
+        let status = ser(&StatusRequest { id });
+        assert_ne!(diag.len(), status.len());
+        let back: DiagnosticsRequest = de(&diag);
+        assert_eq!(back.id, id);
+        assert_eq!(back.log_tail_lines, 200);
+    }
+
+    #[test]
+    fn tag_request_round_trips_and_does_not_alias_other_requests() {
+        let id = Uuid::new_v4();
+        let bytes = ser(&TagRequest {
+            id,
+            key: DocKey::from_parts(3, 77),
+            add: vec!["important".into()],
+            remove: Vec::new(),
+        });
+        let back: TagRequest = de(&bytes);
+        assert_eq!(back.key, DocKey::from_parts(3, 77));
+        assert_eq!(back.add, vec!["important".to_string()]);
+
+        assert!(bincode::deserialize::<ResetIndexRequest>(&bytes).is_err());
+        assert_ne!(bytes.len(), ser(&StatusRequest { id }).len());
+    }
+
+    #[test]
+    fn verify_request_does_not_alias_other_requests() {
+        let id = Uuid::new_v4();
+        let verify = ser(&VerifyRequest {
+            id,
+            sample_keys: 0,
+            checksums: false,
+        });
+        assert_ne!(verify.len(), ser(&StatusRequest { id }).len());
+        assert_ne!(
+            verify.len(),
+            ser(&DiagnosticsRequest {
+                id,
+                log_tail_lines: 0
+            })
+            .len()
+        );
+        assert!(bincode::deserialize::<ResetIndexRequest>(&verify).is_err());
+        let back: VerifyRequest = de(&verify);
+        assert_eq!(back.id, id);
+    }
+
+    #[test]
+    fn scheduler_trace_request_does_not_alias_other_requests() {
+        let id = Uuid::new_v4();
+        let trace = ser(&SchedulerTraceRequest { id, limit: 5 });
+        assert_ne!(trace.len(), ser(&StatusRequest { id }).len());
+        assert_ne!(
+            trace.len(),
+            ser(&DiagnosticsRequest {
+                id,
+                log_tail_lines: 5
+            })
+            .len()
+        );
+        assert_ne!(
+            trace.len(),
+            ser(&VerifyRequest {
+                id,
+                sample_keys: 5,
+                checksums: false
+            })
+            .len()
+        );
+        assert!(bincode::deserialize::<ResetIndexRequest>(&trace).is_err());
+        let back: SchedulerTraceRequest = de(&trace);
+        assert_eq!((back.id, back.limit), (id, 5));
+    }
+
+    #[test]
+    fn export_results_request_does_not_alias_other_requests() {
+        let id = Uuid::new_v4();
+        // 0xff bytes are never valid UTF-8, so this can't read as a reset token either.
+        let result_set_id = Uuid::from_u128(u128::MAX);
+        let export = ser(&ExportResultsRequest {
+            id,
+            result_set_id,
+            offset: 0,
+            limit: 100,
+        });
+        assert_ne!(export.len(), ser(&StatusRequest { id }).len());
+        assert_ne!(
+            export.len(),
+            ser(&VerifyRequest {
+                id,
+                sample_keys: 0,
+                checksums: false
+            })
+            .len()
+        );
+        assert!(bincode::deserialize::<ResetIndexRequest>(&export).is_err());
+        assert!(bincode::deserialize::<TagRequest>(&export).is_err());
+        let back: ExportResultsRequest = de(&export);
+        assert_eq!((back.result_set_id, back.limit), (result_set_id, 100));
+    }
+
+    #[test]
+    fn cancel_request_does_not_alias_other_requests() {
+        let id = Uuid::new_v4();
+        let token = Uuid::new_v4();
+        let cancel = ser(&CancelRequest { id, token });
+        assert_ne!(cancel.len(), ser(&StatusRequest { id }).len());
+        let reset = ser(&ResetIndexRequest {
+            id,
+            confirm_token: "0123456789ab".into(),
+        });
+        assert_ne!(cancel.len(), reset.len());
+        assert!(bincode::deserialize::<CancelRequest>(&reset).is_err());
+        assert!(bincode::deserialize::<SearchRequest>(&cancel).is_err());
+        assert!(bincode::deserialize::<TagRequest>(&cancel).is_err());
+        let back: CancelRequest = de(&cancel);
+        assert_eq!((back.id, back.token), (id, token));
+    }
+
+    #[test]
+    fn suggest_and_clear_history_do_not_alias_other_requests() {
+        let id = Uuid::new_v4();
+        let suggest = ser(&SuggestRequest {
+            id,
+            prefix: "report".into(),
+            limit: 10,
+        });
+        let reset = ser(&ResetIndexRequest {
+            id,
+            confirm_token: "report".into(),
+        });
+        assert!(bincode::deserialize::<SuggestRequest>(&reset).is_err());
+        assert!(bincode::deserialize::<SearchRequest>(&suggest).is_err());
+        let clear = ser(&ClearQueryHistoryRequest {
+            id,
+            older_than_secs: 0,
+            prefix: String::new(),
+        });
+        assert!(bincode::deserialize::<ClearQueryHistoryRequest>(&suggest).is_err());
+        assert_ne!(clear.len(), ser(&CancelRequest { id, token: id }).len());
+        // The first step of a reset sends no token at all.
+        for token in ["", "report", "0123456789ab"] {
+            let reset = ser(&ResetIndexRequest {
+                id,
+                confirm_token: token.into(),
+            });
+            assert!(bincode::deserialize::<ClearQueryHistoryRequest>(&reset).is_err());
+            assert_ne!(clear.len(), reset.len());
+        }
+        // A cancel's second uuid leaves no room for the suggestion's u16, and a 16-byte
+        // prefix leaves it unread as a cancel.
+        let cancel = ser(&CancelRequest { id, token: id });
+        assert!(bincode::deserialize::<SuggestRequest>(&cancel).is_err());
+        let sixteen = ser(&SuggestRequest {
+            id,
+            prefix: "0123456789abcdef".into(),
+            limit: 10,
+        });
+        assert_ne!(sixteen.len(), cancel.len());
+        let back: SuggestRequest = de(&suggest);
+        assert_eq!((back.prefix.as_str(), back.limit), ("report", 10));
+    }
+
+    #[test]
+    fn search_stream_request_does_not_alias_search_request() {
+        let request = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: None,
+            value: "report".into(),
+            modifier: TermModifier::Term,
+        }));
+        let plain = ser(&request);
+        let stream = ser(&SearchStreamRequest::new(request));
+        // The service matches requests by exact length: the stream request's SearchRequest
+        // prefix leaves its batch size unread.
+        let mut cursor = std::io::Cursor::new(&stream);
+        let prefix = bincode::deserialize_from::<_, SearchRequest>(&mut cursor);
+        assert!(prefix.is_err() || cursor.position() as usize != stream.len());
+        assert!(bincode::deserialize::<SearchStreamRequest>(&plain).is_err());
+        let back: SearchStreamRequest = de(&stream);
+        assert_eq!(back.batch_size, SearchStreamRequest::DEFAULT_BATCH_SIZE);
     }
 
     #[test]
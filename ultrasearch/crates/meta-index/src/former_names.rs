@@ -0,0 +1,196 @@
+//! Previous file names, persisted per `DocKey` outside the Tantivy index
+//! (`indexing.former_names`).
+//!
+//! When a file is renamed, its old name is remembered so a search for the name the user
+//! still has in mind can find it. Each file keeps at most `max` names, newest first. Names
+//! are matched word by word, like the tokenized `name` field: `draft` matches `draft.txt`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use anyhow::{Context, Result};
+use core_serialization::{from_rkyv_bytes, to_rkyv_bytes};
+use core_types::DocKey;
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[derive(Debug, Default, Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct FormerNameFile {
+    entries: Vec<FormerNameEntry>,
+}
+
+#[derive(Debug, Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct FormerNameEntry {
+    key: u64,
+    names: Vec<String>,
+}
+
+/// Former names per document, written through to `path` on every rename.
+#[derive(Debug)]
+pub struct FormerNameStore {
+    path: PathBuf,
+    max: usize,
+    names: RwLock<HashMap<DocKey, Vec<String>>>,
+}
+
+impl FormerNameStore {
+    /// Load the store at `path`, keeping at most `max` names per file; a missing file is an
+    /// empty store.
+    pub fn open(path: &Path, max: usize) -> Result<Self> {
+        let mut names = HashMap::new();
+        if path.exists() {
+            let bytes = fs::read(path).context("read former-name store")?;
+            let file = from_rkyv_bytes::<FormerNameFile>(&bytes)
+                .context("deserialize former-name store")?;
+            for mut entry in file.entries {
+                entry.names.truncate(max);
+                if !entry.names.is_empty() {
+                    names.insert(DocKey(entry.key), entry.names);
+                }
+            }
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            max,
+            names: RwLock::new(names),
+        })
+    }
+
+    /// Record that the file at `from` named `old_name` is now `to` named `new_name`, and
+    /// persist. The history moves with the file when its key changes. Renaming back to a
+    /// former name drops that name from the history.
+    pub fn record_rename(
+        &self,
+        from: DocKey,
+        to: DocKey,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<()> {
+        if self.max == 0 || old_name.is_empty() || old_name.eq_ignore_ascii_case(new_name) {
+            return Ok(());
+        }
+        let mut names = self.names.write().unwrap_or_else(|e| e.into_inner());
+        let mut history = names.remove(&from).unwrap_or_default();
+        if from != to
+            && let Some(existing) = names.remove(&to)
+        {
+            history.extend(existing);
+        }
+        history.retain(|n| !n.eq_ignore_ascii_case(old_name) && !n.eq_ignore_ascii_case(new_name));
+        history.insert(0, old_name.to_string());
+        history.truncate(self.max);
+        names.insert(to, history);
+        self.save(&names)
+    }
+
+    /// Drop the history of `keys`, e.g. once their record numbers name other files, and
+    /// persist if anything was dropped.
+    pub fn forget(&self, keys: &[DocKey]) -> Result<()> {
+        let mut names = self.names.write().unwrap_or_else(|e| e.into_inner());
+        let before = names.len();
+        for key in keys {
+            names.remove(key);
+        }
+        if names.len() == before {
+            return Ok(());
+        }
+        self.save(&names)
+    }
+
+    /// Former names of `key`, newest first.
+    pub fn names(&self, key: DocKey) -> Vec<String> {
+        let names = self.names.read().unwrap_or_else(|e| e.into_inner());
+        names.get(&key).cloned().unwrap_or_default()
+    }
+
+    /// Documents with a former name matching `query` (see [`name_matches`]).
+    pub fn keys_matching(&self, query: &str) -> Vec<DocKey> {
+        let names = self.names.read().unwrap_or_else(|e| e.into_inner());
+        names
+            .iter()
+            .filter(|(_, history)| history.iter().any(|n| name_matches(n, query)))
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    fn save(&self, names: &HashMap<DocKey, Vec<String>>) -> Result<()> {
+        let file = FormerNameFile {
+            entries: names
+                .iter()
+                .map(|(key, names)| FormerNameEntry {
+                    key: key.0,
+                    names: names.clone(),
+                })
+                .collect(),
+        };
+        let bytes = to_rkyv_bytes(&file).context("serialize former-name store")?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("create former-name store dir")?;
+        }
+
+        // Atomic write: write to tmp, rename.
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, &bytes).context("write tmp former-name store")?;
+        fs::rename(&tmp_path, &self.path).context("rename former-name store")?;
+        Ok(())
+    }
+}
+
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Whether every word of `query` is a word of `name`, case-insensitively. A query without
+/// words matches nothing.
+pub fn name_matches(name: &str, query: &str) -> bool {
+    let name: Vec<String> = words(name).collect();
+    let mut query = words(query).peekable();
+    query.peek().is_some() && query.all(|w| name.contains(&w))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn renames_build_a_bounded_history_that_survives_reopening() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("former_names.rkyv");
+        let key = DocKey::from_parts(1, 42);
+
+        let store = FormerNameStore::open(&path, 2).unwrap();
+        store
+            .record_rename(key, key, "draft.txt", "review.txt")
+            .unwrap();
+        store
+            .record_rename(key, key, "review.txt", "final.txt")
+            .unwrap();
+        store
+            .record_rename(key, key, "final.txt", "final v2.txt")
+            .unwrap();
+        assert_eq!(store.names(key), vec!["final.txt", "review.txt"]);
+
+        // A new key (path-keyed watchers) takes the history along.
+        let moved = DocKey::from_parts(1, 43);
+        store
+            .record_rename(key, moved, "final v2.txt", "review.txt")
+            .unwrap();
+        assert!(store.names(key).is_empty());
+        assert_eq!(store.names(moved), vec!["final v2.txt", "final.txt"]);
+
+        let reopened = FormerNameStore::open(&path, 2).unwrap();
+        assert_eq!(reopened.keys_matching("Final"), vec![moved]);
+        assert!(reopened.keys_matching("draft").is_empty());
+        assert!(reopened.keys_matching("fin").is_empty());
+
+        let off = FormerNameStore::open(&dir.path().join("off.rkyv"), 0).unwrap();
+        off.record_rename(key, key, "a.txt", "b.txt").unwrap();
+        assert!(off.names(key).is_empty());
+    }
+}
@@ -1,6 +1,7 @@
-use std::fs::File;
+use std::collections::{BTreeSet, HashSet};
+use std::fs::{self, File};
 use std::io::BufWriter;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use core_types::DocKey;
@@ -12,18 +13,31 @@ use memmap2::Mmap;
 /// Keys are encoded as `normalized_name + \0 + doc_key_be_bytes` to handle duplicates.
 /// The value associated with the FST key is unused (always 0) because the DocKey
 /// is embedded in the key itself to allow multiple files with the same name.
+///
+/// Entries written by [`FstBuilder::insert_cased_batch`] whose original name differs from the
+/// normalized one carry it too, as `normalized_name + \0 + original_name + \0 + doc_key`, so
+/// [`search_exact_case`](Self::search_exact_case) can tell `Foo` from `foo`. A u64 value has
+/// no room for the name, and keeping it in the key leaves prefix order intact.
+///
+/// An index built with [`FstBuilder::with_suffix_index`] has a second FST beside it (see
+/// [`suffix_index_path`]) over the reversed normalized names, so suffix queries like `*.log`
+/// become prefix queries there instead of a scan of every name.
 pub struct FstIndex {
     map: Map<Mmap>,
+    reversed: Option<Map<Mmap>>,
 }
 
 impl FstIndex {
-    /// Open an FST index from a path.
+    /// Open an FST index from a path, along with its suffix index if one was built.
     pub fn open(path: &Path) -> Result<Self> {
-        let file = File::open(path)?;
-        // SAFETY: We assume the file is immutable and safe to map.
-        let mmap = unsafe { Mmap::map(&file)? };
-        let map = Map::new(mmap)?;
-        Ok(Self { map })
+        let map = map_file(path)?;
+        let reversed_path = suffix_index_path(path);
+        let reversed = if reversed_path.exists() {
+            Some(map_file(&reversed_path)?)
+        } else {
+            None
+        };
+        Ok(Self { map, reversed })
     }
 
     /// Search for keys starting with the given prefix.
@@ -31,69 +45,314 @@ impl FstIndex {
     /// `prefix` should be normalized (lowercased) if the index was built with normalized names.
     /// `limit` caps the number of results returned to prevent excessive memory usage.
     pub fn search<'a>(&'a self, prefix: &str, limit: usize) -> impl Iterator<Item = DocKey> + 'a {
-        let start = prefix.as_bytes().to_vec();
-        let mut builder = self.map.range().ge(start);
-
-        // Calculate end bound for prefix range
-        let mut end = prefix.as_bytes().to_vec();
-        let mut has_end = false;
-        while let Some(last) = end.last_mut() {
-            if *last < 255 {
-                *last += 1;
-                has_end = true;
-                break;
+        collect_prefix(&self.map, prefix.as_bytes(), limit, |_| true).into_iter()
+    }
+
+    /// Search for names ending with `suffix` (normalized like the names), by querying the
+    /// reversed-name FST for the reversed suffix. Finds nothing if the index was built
+    /// without [`FstBuilder::with_suffix_index`].
+    pub fn search_suffix(&self, suffix: &str, limit: usize) -> Vec<DocKey> {
+        match &self.reversed {
+            Some(reversed) => {
+                collect_prefix(reversed, reverse_name(suffix).as_bytes(), limit, |_| true)
             }
-            end.pop();
+            None => Vec::new(),
         }
+    }
+
+    /// Whether the index was built with a suffix index.
+    pub fn has_suffix_index(&self) -> bool {
+        self.reversed.is_some()
+    }
 
-        if has_end {
-            builder = builder.lt(end);
+    /// Case-sensitive prefix search: the hits of `search(prefix, limit)` whose original name
+    /// starts with `original`, where `prefix` is `original` normalized.
+    ///
+    /// Only works if the original name is recoverable: entries written by
+    /// [`FstBuilder::insert_batch`] count their normalized name as the original.
+    pub fn search_exact_case<'a>(
+        &'a self,
+        prefix: &str,
+        original: &str,
+        limit: usize,
+    ) -> impl Iterator<Item = DocKey> + 'a {
+        collect_prefix(&self.map, prefix.as_bytes(), limit, |k| {
+            decode_original(k).is_some_and(|name| name.starts_with(original.as_bytes()))
+        })
+        .into_iter()
+    }
+}
+
+fn map_file(path: &Path) -> Result<Map<Mmap>> {
+    let file = File::open(path)?;
+    // SAFETY: We assume the file is immutable and safe to map.
+    let mmap = unsafe { Mmap::map(&file)? };
+    Ok(Map::new(mmap)?)
+}
+
+/// Where the suffix index of the FST at `path` lives: `name.fst` gets `name.rev.fst`.
+pub fn suffix_index_path(path: &Path) -> PathBuf {
+    path.with_extension("rev.fst")
+}
+
+/// `name` with its characters in reverse order, as the suffix index stores it.
+fn reverse_name(name: &str) -> String {
+    name.chars().rev().collect()
+}
+
+fn collect_prefix(
+    map: &Map<Mmap>,
+    prefix: &[u8],
+    limit: usize,
+    keep: impl Fn(&[u8]) -> bool,
+) -> Vec<DocKey> {
+    let start = prefix.to_vec();
+    let mut builder = map.range().ge(start);
+
+    // Calculate end bound for prefix range
+    let mut end = prefix.to_vec();
+    let mut has_end = false;
+    while let Some(last) = end.last_mut() {
+        if *last < 255 {
+            *last += 1;
+            has_end = true;
+            break;
         }
+        end.pop();
+    }
 
-        let mut stream = builder.into_stream();
-        let mut hits = Vec::new();
+    if has_end {
+        builder = builder.lt(end);
+    }
 
-        while let Some((k, _)) = stream.next() {
-            if hits.len() >= limit {
-                break;
-            }
+    let mut stream = builder.into_stream();
+    let mut hits = Vec::new();
 
-            // Double check prefix (range should handle it, but being safe against edge cases)
-            if !k.starts_with(prefix.as_bytes()) {
-                continue;
-            }
+    while let Some((k, _)) = stream.next() {
+        if hits.len() >= limit {
+            break;
+        }
 
-            // Key format: name_bytes + \0 + 8 bytes DocKey (BE).
-            if k.len() < 9 {
-                continue;
-            }
+        // Double check prefix (range should handle it, but being safe against edge cases)
+        if !k.starts_with(prefix) || !keep(k) {
+            continue;
+        }
 
-            let (rest, dk_bytes) = k.split_at(k.len() - 8);
-            if rest.last() != Some(&0) {
-                continue;
-            }
+        if let Some(key) = decode_key(k) {
+            hits.push(key);
+        }
+    }
 
-            if let Ok(bytes) = dk_bytes.try_into() {
-                let val = u64::from_be_bytes(bytes);
-                hits.push(DocKey(val));
+    hits
+}
+
+/// Key format: name_bytes + \0 + 8 bytes DocKey (BE).
+fn encode_key(name: &str, dk: DocKey) -> Vec<u8> {
+    let mut k = name.as_bytes().to_vec();
+    encode_key_tail(&mut k, dk);
+    k
+}
+
+/// Key format with the original name kept: name_bytes + \0 + original_bytes + \0 + DocKey (BE).
+/// Falls back to [`encode_key`] when the original equals the normalized name.
+fn encode_cased_key(name: &str, original: &str, dk: DocKey) -> Vec<u8> {
+    if name == original {
+        return encode_key(name, dk);
+    }
+    let mut k = name.as_bytes().to_vec();
+    k.push(0);
+    k.extend_from_slice(original.as_bytes());
+    encode_key_tail(&mut k, dk);
+    k
+}
+
+fn encode_key_tail(k: &mut Vec<u8>, dk: DocKey) {
+    k.push(0);
+    k.extend_from_slice(&dk.0.to_be_bytes());
+}
+
+/// Original-case name of an encoded key; the normalized name if none was stored.
+fn decode_original(k: &[u8]) -> Option<&[u8]> {
+    let names = k.len().checked_sub(9).map(|n| &k[..n])?;
+    Some(match names.iter().position(|&b| b == 0) {
+        Some(sep) => &names[sep + 1..],
+        None => names,
+    })
+}
+
+fn decode_key(k: &[u8]) -> Option<DocKey> {
+    if k.len() < 9 {
+        return None;
+    }
+    let (rest, dk_bytes) = k.split_at(k.len() - 8);
+    if rest.last() != Some(&0) {
+        return None;
+    }
+    dk_bytes
+        .try_into()
+        .ok()
+        .map(|b| DocKey(u64::from_be_bytes(b)))
+}
+
+/// Directory under the meta index dir holding the name FST segments.
+pub const FST_DIR: &str = "fst";
+
+/// Approximate per-entry cost of the overlay's `BTreeSet<Vec<u8>>` beyond the key bytes.
+const OVERLAY_ENTRY_OVERHEAD: usize = 32;
+
+/// FST name index made of immutable on-disk segments plus an in-memory write overlay.
+///
+/// New names are buffered in the overlay until [`commit`](Self::commit) writes them out as a
+/// new `seg-NNNNNN.fst` segment. When the overlay grows past `overlay_max_bytes` it is
+/// flushed early, so memory stays bounded during bursts at the cost of extra segments.
+/// Searches consult the overlay and every segment.
+pub struct LayeredFstIndex {
+    dir: PathBuf,
+    segments: Vec<FstIndex>,
+    overlay: BTreeSet<Vec<u8>>,
+    overlay_bytes: usize,
+    overlay_max_bytes: usize,
+    next_segment: u64,
+}
+
+impl LayeredFstIndex {
+    /// Open the segments in `dir` (created if missing).
+    pub fn open(dir: &Path, overlay_max_bytes: u64) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let mut numbered = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            if let Some(n) = name
+                .to_str()
+                .and_then(|n| n.strip_prefix("seg-")?.strip_suffix(".fst"))
+                .and_then(|n| n.parse::<u64>().ok())
+            {
+                numbered.push(n);
             }
         }
+        numbered.sort_unstable();
+        let segments = numbered
+            .iter()
+            .map(|n| FstIndex::open(&segment_path(dir, *n)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            segments,
+            overlay: BTreeSet::new(),
+            overlay_bytes: 0,
+            overlay_max_bytes: usize::try_from(overlay_max_bytes).unwrap_or(usize::MAX),
+            next_segment: numbered.last().map_or(0, |n| n + 1),
+        })
+    }
+
+    /// Buffer `(normalized_name, doc_key)`. Returns `true` if this insert pushed the overlay
+    /// over its cap and it was flushed to a new segment.
+    pub fn insert(&mut self, name: &str, dk: DocKey) -> Result<bool> {
+        let key = encode_key(name, dk);
+        let cost = key.len() + OVERLAY_ENTRY_OVERHEAD;
+        if self.overlay.insert(key) {
+            self.overlay_bytes += cost;
+        }
+        if self.overlay_bytes > self.overlay_max_bytes {
+            self.commit()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Write the overlay out as a new segment and clear it. No-op when the overlay is empty.
+    pub fn commit(&mut self) -> Result<()> {
+        if self.overlay.is_empty() {
+            return Ok(());
+        }
+        let n = self.next_segment;
+        let path = segment_path(&self.dir, n);
+        let tmp = path.with_extension("fst.tmp");
+        let mut writer = MapBuilder::new(BufWriter::new(File::create(&tmp)?))?;
+        // BTreeSet iterates in sorted order, as MapBuilder requires.
+        for key in &self.overlay {
+            writer.insert(key, 0)?;
+        }
+        writer.finish()?;
+        fs::rename(&tmp, &path)?;
+
+        self.segments.push(FstIndex::open(&path)?);
+        self.next_segment = n + 1;
+        self.overlay.clear();
+        self.overlay_bytes = 0;
+        Ok(())
+    }
+
+    /// Estimated memory held by the overlay.
+    pub fn overlay_bytes(&self) -> usize {
+        self.overlay_bytes
+    }
 
-        hits.into_iter()
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Prefix search across the overlay and all segments, newest first, without duplicates.
+    pub fn search(&self, prefix: &str, limit: usize) -> Vec<DocKey> {
+        let mut seen = HashSet::new();
+        let mut hits = Vec::new();
+        let overlay = self
+            .overlay
+            .range(prefix.as_bytes().to_vec()..)
+            .take_while(|k| k.starts_with(prefix.as_bytes()))
+            .filter_map(|k| decode_key(k));
+        let segments = self
+            .segments
+            .iter()
+            .rev()
+            .flat_map(|seg| seg.search(prefix, limit));
+        for key in overlay.chain(segments) {
+            if hits.len() >= limit {
+                break;
+            }
+            if seen.insert(key) {
+                hits.push(key);
+            }
+        }
+        hits
     }
 }
 
+fn segment_path(dir: &Path, n: u64) -> PathBuf {
+    dir.join(format!("seg-{n:06}.fst"))
+}
+
 /// Builder for FST index.
 pub struct FstBuilder {
+    path: PathBuf,
     writer: MapBuilder<BufWriter<File>>,
+    /// Reversed-name FST, when built [`with_suffix_index`](Self::with_suffix_index).
+    reversed: Option<MapBuilder<BufWriter<File>>>,
 }
 
 impl FstBuilder {
-    /// Create a new builder writing to the specified path.
+    /// Create a new builder writing to the specified path. A suffix index left there by an
+    /// earlier build is removed, so it can't go stale.
     pub fn new(path: &Path) -> Result<Self> {
         let file = File::create(path)?;
         let writer = MapBuilder::new(BufWriter::new(file))?;
-        Ok(Self { writer })
+        match fs::remove_file(suffix_index_path(path)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer,
+            reversed: None,
+        })
+    }
+
+    /// Also build the reversed-name FST behind [`FstIndex::search_suffix`]. Roughly doubles
+    /// the size on disk, so indexes without suffix queries leave it off.
+    pub fn with_suffix_index(mut self) -> Result<Self> {
+        let file = File::create(suffix_index_path(&self.path))?;
+        self.reversed = Some(MapBuilder::new(BufWriter::new(file))?);
+        Ok(self)
     }
 
     /// Insert a batch of entries.
@@ -101,33 +360,59 @@ impl FstBuilder {
     /// `entries` is a list of `(normalized_name, doc_key)`.
     /// This function sorts them internally to satisfy FST insertion requirements.
     pub fn insert_batch(&mut self, entries: Vec<(String, DocKey)>) -> Result<()> {
+        self.insert_reversed(entries.iter().map(|(name, dk)| (name.as_str(), *dk)))?;
         // Transform to encoded keys: name + \0 + doc_key(BE)
-        let mut keys: Vec<Vec<u8>> = entries
+        let keys = entries
             .into_iter()
-            .map(|(name, dk)| {
-                let mut k = name.into_bytes();
-                k.push(0);
-                k.extend_from_slice(&dk.0.to_be_bytes());
-                k
-            })
+            .map(|(name, dk)| encode_key(&name, dk))
             .collect();
+        insert_keys(&mut self.writer, keys)
+    }
 
-        keys.sort();
-        keys.dedup(); // Dedup exact matches just in case
+    /// Insert a batch of `(normalized_name, original_name, doc_key)`, keeping the original
+    /// name for [`FstIndex::search_exact_case`].
+    pub fn insert_cased_batch(&mut self, entries: Vec<(String, String, DocKey)>) -> Result<()> {
+        self.insert_reversed(entries.iter().map(|(name, _, dk)| (name.as_str(), *dk)))?;
+        let keys = entries
+            .into_iter()
+            .map(|(name, original, dk)| encode_cased_key(&name, &original, dk))
+            .collect();
+        insert_keys(&mut self.writer, keys)
+    }
 
-        for k in keys {
-            self.writer.insert(&k, 0)?;
-        }
-        Ok(())
+    fn insert_reversed<'a>(
+        &mut self,
+        entries: impl Iterator<Item = (&'a str, DocKey)>,
+    ) -> Result<()> {
+        let Some(reversed) = &mut self.reversed else {
+            return Ok(());
+        };
+        let keys = entries
+            .map(|(name, dk)| encode_key(&reverse_name(name), dk))
+            .collect();
+        insert_keys(reversed, keys)
     }
 
     /// Finish writing the index.
     pub fn finish(self) -> Result<()> {
         self.writer.finish()?;
+        if let Some(reversed) = self.reversed {
+            reversed.finish()?;
+        }
         Ok(())
     }
 }
 
+fn insert_keys(writer: &mut MapBuilder<BufWriter<File>>, mut keys: Vec<Vec<u8>>) -> Result<()> {
+    keys.sort();
+    keys.dedup(); // Dedup exact matches just in case
+
+    for k in keys {
+        writer.insert(&k, 0)?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -179,4 +464,109 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn case_sensitive_search_tells_foo_from_foo() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("cased.fst");
+
+        let mut builder = FstBuilder::new(&path)?;
+        builder.insert_cased_batch(vec![
+            ("foo".to_string(), "Foo".to_string(), DocKey(1)),
+            ("foo".to_string(), "foo".to_string(), DocKey(2)),
+            ("foobar".to_string(), "FooBar".to_string(), DocKey(3)),
+        ])?;
+        builder.finish()?;
+        let index = FstIndex::open(&path)?;
+
+        let sorted = |hits: &mut dyn Iterator<Item = DocKey>| {
+            let mut hits: Vec<u64> = hits.map(|k| k.0).collect();
+            hits.sort();
+            hits
+        };
+        assert_eq!(sorted(&mut index.search("foo", 10)), vec![1, 2, 3]);
+        let upper = &mut index.search_exact_case("foo", "Foo", 10);
+        assert_eq!(sorted(upper), vec![1, 3]);
+        let lower = &mut index.search_exact_case("foo", "foo", 10);
+        assert_eq!(sorted(lower), vec![2]);
+        let bar = &mut index.search_exact_case("foob", "FooB", 10);
+        assert_eq!(sorted(bar), vec![3]);
+        Ok(())
+    }
+
+    #[test]
+    fn suffix_index_turns_suffix_queries_into_prefix_lookups() -> Result<()> {
+        let dir = tempdir()?;
+        let path = dir.path().join("names.fst");
+        let entries = vec![
+            ("build.log".to_string(), DocKey(1)),
+            ("error.log".to_string(), DocKey(2)),
+            ("notes.txt".to_string(), DocKey(3)),
+            ("catalog".to_string(), DocKey(4)),
+            ("résumé.log".to_string(), DocKey(5)),
+        ];
+        let mut builder = FstBuilder::new(&path)?.with_suffix_index()?;
+        builder.insert_batch(entries.clone())?;
+        builder.finish()?;
+
+        let index = FstIndex::open(&path)?;
+        let mut hits: Vec<u64> = index
+            .search_suffix(".log", 10)
+            .iter()
+            .map(|k| k.0)
+            .collect();
+        hits.sort();
+        assert_eq!(hits, vec![1, 2, 5]);
+        assert_eq!(index.search_suffix("log", 10).len(), 4);
+        assert_eq!(index.search_suffix("é.log", 10), vec![DocKey(5)]);
+        assert!(index.search_suffix(".pdf", 10).is_empty());
+        assert_eq!(index.search_suffix(".log", 2).len(), 2);
+
+        // Rebuilt without the option: no stale suffix index is picked up.
+        let mut builder = FstBuilder::new(&path)?;
+        builder.insert_batch(entries)?;
+        builder.finish()?;
+        let index = FstIndex::open(&path)?;
+        assert!(!index.has_suffix_index());
+        assert!(index.search_suffix(".log", 10).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn overlay_over_cap_flushes_to_segment_and_stays_searchable() -> Result<()> {
+        let dir = tempdir()?;
+        // Room for a handful of entries only.
+        let mut index = LayeredFstIndex::open(dir.path(), 256)?;
+
+        let mut flushed_at = None;
+        let mut before_flush = 0;
+        for i in 0..10u64 {
+            before_flush = index.overlay_bytes();
+            if index.insert(&format!("report-{i}"), DocKey(i))? {
+                flushed_at = Some(i);
+                break;
+            }
+        }
+        let flushed_at = flushed_at.expect("cap should force a flush");
+        assert!(before_flush > 0);
+        assert_eq!(index.overlay_bytes(), 0);
+        assert_eq!(index.segment_count(), 1);
+
+        // Later inserts buffer again on top of the flushed segment.
+        index.insert("report-x", DocKey(99))?;
+        assert!(index.overlay_bytes() < before_flush);
+
+        let mut hits: Vec<u64> = index.search("report", 100).iter().map(|k| k.0).collect();
+        hits.sort();
+        let mut expected: Vec<u64> = (0..=flushed_at).collect();
+        expected.push(99);
+        assert_eq!(hits, expected);
+
+        // Segments, including the one from the explicit commit, survive a reopen.
+        index.commit()?;
+        let reopened = LayeredFstIndex::open(dir.path(), 256)?;
+        assert_eq!(reopened.segment_count(), 2);
+        assert_eq!(reopened.search("report-x", 10), vec![DocKey(99)]);
+        Ok(())
+    }
 }
@@ -7,16 +7,26 @@
 
 use std::path::Path;
 
-use anyhow::Result;
-use core_types::{DocKey, FileMeta as CoreFileMeta};
-use tantivy::{Index, IndexWriter, schema::document::TantivyDocument, schema::*};
+use anyhow::{Context, Result};
+use core_types::config::MetaStoreSection;
+use core_types::phonetic::name_phonetic_codes;
+use core_types::{DocKey, FileFlags, FileMeta as CoreFileMeta, dir_components, set_aside_dir};
+use tantivy::collector::TopDocs;
+use tantivy::query::RangeQuery;
+use tantivy::store::{Compressor, ZstdCompressor};
+use tantivy::{
+    Index, IndexSettings, IndexWriter, Searcher, Term, schema::document::TantivyDocument, schema::*,
+};
 
 #[cfg(test)]
-use tantivy::{IndexSettings, ReloadPolicy};
+use tantivy::ReloadPolicy;
 
 pub mod cache;
+pub mod former_names;
 pub mod fst;
+pub mod object_ids;
 pub mod state;
+pub mod tags;
 pub mod tiers;
 
 /// Fields used in the metadata index.
@@ -31,6 +41,43 @@ pub struct MetaFields {
     pub created: Field,
     pub modified: Field,
     pub flags: Field,
+    /// Raw, lowercased ancestor directory names (multi-valued, not stored).
+    pub dir_component: Field,
+    /// Double Metaphone codes of the name's words (multi-valued, not stored).
+    pub name_phonetic: Field,
+    /// Owner SID, uppercase; absent unless owners are indexed.
+    pub owner: Field,
+    /// Key of the archive holding a virtual archive entry; absent for files on disk.
+    pub archive: Field,
+    /// The whole name lowercased, as one term (not stored), for `Glob` terms.
+    pub name_lower: Field,
+}
+
+impl MetaFields {
+    /// Resolve field handles by name from an existing index schema.
+    pub fn from_schema(schema: &Schema) -> Result<Self> {
+        let get = |name: &str| {
+            schema
+                .get_field(name)
+                .with_context(|| format!("meta index schema missing field `{name}`"))
+        };
+        Ok(Self {
+            doc_key: get("doc_key")?,
+            volume: get("volume")?,
+            name: get("name")?,
+            path: get("path")?,
+            ext: get("ext")?,
+            size: get("size")?,
+            created: get("created")?,
+            modified: get("modified")?,
+            flags: get("flags")?,
+            dir_component: get("dir_component")?,
+            name_phonetic: get("name_phonetic")?,
+            owner: get("owner")?,
+            archive: get("archive")?,
+            name_lower: get("name_lower")?,
+        })
+    }
 }
 
 /// Build the Tantivy schema and return both `Schema` and typed field handles.
@@ -46,6 +93,13 @@ pub fn build_schema() -> (Schema, MetaFields) {
     let created = builder.add_i64_field("created", FAST | STORED);
     let modified = builder.add_i64_field("modified", FAST | STORED);
     let flags = builder.add_u64_field("flags", FAST | STORED);
+    // Added after the first release. Tantivy can't add fields to an existing index, so one
+    // created without them is set aside and recreated on open.
+    let dir_component = builder.add_text_field("dir_component", STRING);
+    let name_phonetic = builder.add_text_field("name_phonetic", STRING);
+    let owner = builder.add_text_field("owner", STRING | STORED);
+    let archive = builder.add_u64_field("archive", FAST | STORED);
+    let name_lower = builder.add_text_field("name_lower", STRING);
 
     let fields = MetaFields {
         doc_key,
@@ -57,6 +111,11 @@ pub fn build_schema() -> (Schema, MetaFields) {
         created,
         modified,
         flags,
+        dir_component,
+        name_phonetic,
+        owner,
+        archive,
+        name_lower,
     };
 
     (builder.build(), fields)
@@ -74,6 +133,9 @@ pub struct MetaDoc {
     pub created: i64,
     pub modified: i64,
     pub flags: u64,
+    pub owner: Option<String>,
+    /// The archive an archive entry was listed from.
+    pub archive: Option<DocKey>,
 }
 
 impl From<&CoreFileMeta> for MetaDoc {
@@ -88,6 +150,10 @@ impl From<&CoreFileMeta> for MetaDoc {
             created: f.created,
             modified: f.modified,
             flags: f.flags.bits() as u64,
+            owner: f.owner.clone(),
+            archive: f
+                .parent
+                .filter(|_| f.flags.contains(FileFlags::ARCHIVE_ENTRY)),
         }
     }
 }
@@ -128,15 +194,98 @@ pub struct MetaIndex {
 /// “open or create” ergonomics without forcing the caller to probe the
 /// directory manually.
 pub fn open_or_create_index(path: &Path) -> Result<MetaIndex> {
+    open_or_create_index_with(path, &MetaStoreSection::default())
+}
+
+/// Like [`open_or_create_index`], laying out a newly created index's stored fields per
+/// `store`. An existing index keeps the layout it was created with.
+///
+/// An existing index whose schema differs from [`build_schema`], e.g. one created before a
+/// field was added, is moved aside to `<dir>.outdated` and replaced by an empty one; the next
+/// volume scan refills it.
+pub fn open_or_create_index_with(path: &Path, store: &MetaStoreSection) -> Result<MetaIndex> {
     let (schema, fields) = build_schema();
-    let index = if path.join("meta.json").exists() {
-        Index::open_in_dir(path)?
-    } else {
-        Index::create_in_dir(path, schema)?
-    };
+    if path.join("meta.json").exists() {
+        let index = Index::open_in_dir(path)?;
+        if schema_matches(&index.schema(), &schema) {
+            let fields = MetaFields::from_schema(&index.schema())?;
+            return Ok(MetaIndex { index, fields });
+        }
+        drop(index);
+        let aside = set_aside_dir(path, "outdated").context("set aside outdated meta index")?;
+        tracing::warn!(?aside, "meta index schema is outdated; recreating it");
+        std::fs::create_dir_all(path)?;
+    }
+    let dir = tantivy::directory::MmapDirectory::open(path)?;
+    let index = Index::create(dir, schema, index_settings(store))?;
     Ok(MetaIndex { index, fields })
 }
 
+/// Whether `existing` has every field of `current`, with the same type and options.
+fn schema_matches(existing: &Schema, current: &Schema) -> bool {
+    current.fields().all(|(_, entry)| {
+        existing
+            .get_field(entry.name())
+            .is_ok_and(|field| existing.get_field_entry(field) == entry)
+    })
+}
+
+/// zstd blocks of `block_size` when `store.compress` is set, Tantivy's LZ4 default otherwise.
+fn index_settings(store: &MetaStoreSection) -> IndexSettings {
+    if !store.compress {
+        return IndexSettings::default();
+    }
+    IndexSettings {
+        docstore_compression: Compressor::Zstd(ZstdCompressor {
+            compression_level: Some(store.compression_level.clamp(1, 22)),
+        }),
+        docstore_blocksize: store.block_size.max(1024),
+        ..IndexSettings::default()
+    }
+}
+
+/// The stored entry for `key`. `parent` is only stored for archive entries (their archive),
+/// so it otherwise comes back `None`, and `ext` is derived from the name as at ingest.
+pub fn lookup_file_meta(
+    searcher: &Searcher,
+    fields: &MetaFields,
+    key: DocKey,
+) -> Result<Option<CoreFileMeta>> {
+    let term = Term::from_field_u64(fields.doc_key, key.0);
+    let query = RangeQuery::new(
+        std::ops::Bound::Included(term.clone()),
+        std::ops::Bound::Included(term),
+    );
+    let Some((_, addr)) = searcher.search(&query, &TopDocs::with_limit(1))?.pop() else {
+        return Ok(None);
+    };
+    let doc: TantivyDocument = searcher.doc(addr)?;
+    let u64_of = |field| doc.get_first(field).and_then(|v| v.as_u64()).unwrap_or(0);
+    let i64_of = |field| doc.get_first(field).and_then(|v| v.as_i64()).unwrap_or(0);
+    let str_of = |field| {
+        doc.get_first(field)
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+    };
+    let archive = doc
+        .get_first(fields.archive)
+        .and_then(|v| v.as_u64())
+        .map(DocKey);
+    let mut meta = CoreFileMeta::new(
+        key,
+        u64_of(fields.volume) as u16,
+        archive,
+        str_of(fields.name).unwrap_or_default(),
+        str_of(fields.path),
+        u64_of(fields.size),
+        i64_of(fields.created),
+        i64_of(fields.modified),
+        FileFlags::from_bits_truncate(u64_of(fields.flags) as u32),
+    );
+    meta.owner = str_of(fields.owner);
+    Ok(Some(meta))
+}
+
 /// Writer configuration used during initial builds and batch updates.
 #[derive(Debug, Clone)]
 pub struct WriterConfig {
@@ -174,8 +323,15 @@ pub fn to_document(doc: &MetaDoc, fields: &MetaFields) -> TantivyDocument {
     d.add_u64(fields.doc_key, doc.key.0);
     d.add_u64(fields.volume, doc.volume as u64);
     d.add_text(fields.name, &doc.name);
+    d.add_text(fields.name_lower, doc.name.to_lowercase());
+    for code in name_phonetic_codes(&doc.name) {
+        d.add_text(fields.name_phonetic, code);
+    }
     if let Some(path) = &doc.path {
         d.add_text(fields.path, path);
+        for component in dir_components(path) {
+            d.add_text(fields.dir_component, component);
+        }
     }
     if let Some(ext) = &doc.ext {
         d.add_text(fields.ext, ext);
@@ -184,6 +340,12 @@ pub fn to_document(doc: &MetaDoc, fields: &MetaFields) -> TantivyDocument {
     d.add_i64(fields.created, doc.created);
     d.add_i64(fields.modified, doc.modified);
     d.add_u64(fields.flags, doc.flags);
+    if let Some(owner) = &doc.owner {
+        d.add_text(fields.owner, owner.to_ascii_uppercase());
+    }
+    if let Some(archive) = doc.archive {
+        d.add_u64(fields.archive, archive.0);
+    }
     d
 }
 
@@ -205,6 +367,8 @@ mod tests {
             created: 100,
             modified: 200,
             flags: 0b1010,
+            owner: Some("s-1-5-18".into()),
+            archive: None,
         };
 
         let tdoc = to_document(&doc, &fields);
@@ -215,6 +379,12 @@ mod tests {
         assert_eq!(get(fields.created).as_i64().unwrap(), doc.created);
         assert_eq!(get(fields.modified).as_i64().unwrap(), doc.modified);
         assert_eq!(get(fields.flags).as_u64().unwrap(), doc.flags);
+        assert!(tdoc.get_first(fields.dir_component).is_none());
+        assert_eq!(get(fields.owner).as_str(), Some("S-1-5-18"));
+        assert_eq!(
+            tdoc.get_first(fields.name_phonetic).unwrap().as_str(),
+            Some("SMPL")
+        );
     }
 
     #[test]
@@ -235,6 +405,8 @@ mod tests {
                 created: 1_700_000_000,
                 modified: 1_700_000_100,
                 flags: 0,
+                owner: None,
+                archive: None,
             },
             MetaDoc {
                 key: DocKey::from_parts(2, 20),
@@ -246,6 +418,8 @@ mod tests {
                 created: 1_700_000_200,
                 modified: 1_700_000_300,
                 flags: 0,
+                owner: None,
+                archive: None,
             },
         ];
 
@@ -267,4 +441,107 @@ mod tests {
         assert!(doc_key == docs[0].key.0 || doc_key == docs[1].key.0);
         Ok(())
     }
+
+    #[test]
+    fn compressed_store_returns_same_entries_in_less_space() -> Result<()> {
+        let metas: Vec<CoreFileMeta> = (0..5_000u64)
+            .map(|i| {
+                let name = format!("invoice-{:05}.pdf", i);
+                let mut meta = CoreFileMeta::new(
+                    DocKey::from_parts(3, i),
+                    3,
+                    None,
+                    name.clone(),
+                    Some(format!(r"D:\Accounting\2024\Invoices\Customers\{name}")),
+                    10_000 + i,
+                    1_700_000_000 + i as i64,
+                    1_700_100_000 + i as i64,
+                    FileFlags::ARCHIVE,
+                );
+                meta.owner = Some("S-1-5-21-1000".into());
+                meta
+            })
+            .collect();
+        let build = |store: &MetaStoreSection| -> Result<(tempfile::TempDir, MetaIndex)> {
+            let dir = tempfile::tempdir()?;
+            let meta = open_or_create_index_with(dir.path(), store)?;
+            let mut writer = create_writer(
+                &meta,
+                &WriterConfig {
+                    heap_size_bytes: 50_000_000,
+                    num_threads: 1,
+                },
+            )?;
+            add_file_meta_batch(&mut writer, &meta.fields, metas.clone())?;
+            writer.commit()?;
+            writer.wait_merging_threads()?;
+            Ok((dir, meta))
+        };
+        let store_bytes = |dir: &Path| -> u64 {
+            std::fs::read_dir(dir)
+                .unwrap()
+                .flatten()
+                .filter(|e| e.path().extension().is_some_and(|ext| ext == "store"))
+                .map(|e| e.metadata().unwrap().len())
+                .sum()
+        };
+
+        let (plain_dir, plain) = build(&MetaStoreSection::default())?;
+        let compressed_store = MetaStoreSection {
+            compress: true,
+            block_size: 256 * 1024,
+            compression_level: 9,
+        };
+        let (packed_dir, packed) = build(&compressed_store)?;
+
+        let plain_searcher = open_reader(&plain)?.searcher();
+        let packed_searcher = open_reader(&packed)?.searcher();
+        for meta in metas.iter().step_by(499) {
+            let expected = lookup_file_meta(&plain_searcher, &plain.fields, meta.key)?;
+            assert_eq!(expected.as_ref(), Some(meta));
+            assert_eq!(
+                lookup_file_meta(&packed_searcher, &packed.fields, meta.key)?,
+                expected
+            );
+        }
+        let missing = DocKey::from_parts(3, 99_999);
+        assert_eq!(
+            lookup_file_meta(&packed_searcher, &packed.fields, missing)?,
+            None
+        );
+
+        let (plain_size, packed_size) = (
+            store_bytes(plain_dir.path()),
+            store_bytes(packed_dir.path()),
+        );
+        assert!(packed_size < plain_size, "{packed_size} >= {plain_size}");
+
+        // Reopening keeps the layout the index was created with.
+        let reopened = open_or_create_index(packed_dir.path())?;
+        assert!(matches!(
+            reopened.index.settings().docstore_compression,
+            Compressor::Zstd(_)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn index_with_an_outdated_schema_is_set_aside_and_recreated() -> Result<()> {
+        let root = tempfile::tempdir()?;
+        let path = root.path().join("meta");
+        std::fs::create_dir_all(&path)?;
+        // A schema from before `dir_component` and later fields existed.
+        let mut old = Schema::builder();
+        old.add_u64_field("doc_key", FAST | STORED);
+        old.add_text_field("name", TEXT | STORED);
+        Index::create_in_dir(&path, old.build())?;
+
+        let meta = open_or_create_index(&path)?;
+        assert!(meta.index.schema().get_field("name_lower").is_ok());
+        assert!(root.path().join("meta.outdated").join("meta.json").exists());
+        // A current index opens as is.
+        open_or_create_index(&path)?;
+        assert!(!root.path().join("meta.outdated-1").exists());
+        Ok(())
+    }
 }
@@ -0,0 +1,151 @@
+//! NTFS object IDs, persisted per `DocKey` outside the Tantivy index
+//! (`indexing.index_object_ids`).
+//!
+//! NTFS hands a deleted file's record number to the next file it creates, so the same
+//! `DocKey` can name two unrelated files across sessions. An object ID stays with its file,
+//! so a key whose recorded ID no longer matches was reused: the entry is a new file, not an
+//! update of the old one.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use anyhow::{Context, Result};
+use core_serialization::{from_rkyv_bytes, to_rkyv_bytes};
+use core_types::DocKey;
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[derive(Debug, Default, Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct ObjectIdFile {
+    entries: Vec<ObjectIdEntry>,
+}
+
+#[derive(Debug, Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct ObjectIdEntry {
+    key: u64,
+    object_id: [u8; 16],
+}
+
+/// Object IDs per document, written through to `path` when they change.
+#[derive(Debug)]
+pub struct ObjectIdStore {
+    path: PathBuf,
+    ids: RwLock<HashMap<DocKey, [u8; 16]>>,
+}
+
+impl ObjectIdStore {
+    /// Load the store at `path`; a missing file is an empty store.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut ids = HashMap::new();
+        if path.exists() {
+            let bytes = fs::read(path).context("read object-id store")?;
+            let file =
+                from_rkyv_bytes::<ObjectIdFile>(&bytes).context("deserialize object-id store")?;
+            for entry in file.entries {
+                ids.insert(DocKey(entry.key), entry.object_id);
+            }
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            ids: RwLock::new(ids),
+        })
+    }
+
+    /// Object ID recorded for `key`.
+    pub fn get(&self, key: DocKey) -> Option<[u8; 16]> {
+        let ids = self.ids.read().unwrap_or_else(|e| e.into_inner());
+        ids.get(&key).copied()
+    }
+
+    /// Record the object IDs just read for `observed` (`None` = the file has none), persist,
+    /// and return the keys that now name a different file than before. A key with no
+    /// recorded ID can't be told apart from its predecessor and is not reported.
+    pub fn observe(&self, observed: &[(DocKey, Option<[u8; 16]>)]) -> Result<Vec<DocKey>> {
+        let mut ids = self.ids.write().unwrap_or_else(|e| e.into_inner());
+        let mut reused = Vec::new();
+        let mut changed = false;
+        for &(key, current) in observed {
+            let previous = match current {
+                Some(id) => ids.insert(key, id),
+                None => ids.remove(&key),
+            };
+            if previous != current {
+                changed = true;
+                if previous.is_some() {
+                    reused.push(key);
+                }
+            }
+        }
+        if changed {
+            self.save(&ids)?;
+        }
+        Ok(reused)
+    }
+
+    fn save(&self, ids: &HashMap<DocKey, [u8; 16]>) -> Result<()> {
+        let file = ObjectIdFile {
+            entries: ids
+                .iter()
+                .map(|(key, object_id)| ObjectIdEntry {
+                    key: key.0,
+                    object_id: *object_id,
+                })
+                .collect(),
+        };
+        let bytes = to_rkyv_bytes(&file).context("serialize object-id store")?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("create object-id store dir")?;
+        }
+
+        // Atomic write: write to tmp, rename.
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, &bytes).context("write tmp object-id store")?;
+        fs::rename(&tmp_path, &self.path).context("rename object-id store")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reused_frn_with_a_changed_object_id_is_a_new_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("object_ids.rkyv");
+        let (reused, kept, fresh) = (
+            DocKey::from_parts(1, 10),
+            DocKey::from_parts(1, 11),
+            DocKey::from_parts(1, 12),
+        );
+
+        let store = ObjectIdStore::open(&path).unwrap();
+        let first = [
+            (reused, Some([1; 16])),
+            (kept, Some([2; 16])),
+            (fresh, None),
+        ];
+        assert!(store.observe(&first).unwrap().is_empty());
+
+        // Same record numbers next session: one now holds a file with another ID (or none
+        // yet), the other is the same file, and the key that had no ID can't be judged.
+        let store = ObjectIdStore::open(&path).unwrap();
+        assert_eq!(store.get(kept), Some([2; 16]));
+        let second = [
+            (reused, Some([9; 16])),
+            (kept, Some([2; 16])),
+            (fresh, Some([3; 16])),
+        ];
+        assert_eq!(store.observe(&second).unwrap(), vec![reused]);
+        assert_eq!(store.observe(&[(kept, None)]).unwrap(), vec![kept]);
+        assert!(
+            store
+                .observe(&[(reused, Some([9; 16]))])
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
@@ -0,0 +1,152 @@
+//! User-assigned tags, persisted per `DocKey` outside the Tantivy index.
+//!
+//! Tags are user data rather than file system metadata, so they live in their own file and
+//! survive the meta index being rebuilt or reset. Tags are trimmed and lowercased; a document
+//! with no tags left is dropped from the store.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use anyhow::{Context, Result};
+use core_serialization::{from_rkyv_bytes, to_rkyv_bytes};
+use core_types::DocKey;
+use rkyv::{Archive, Deserialize, Serialize};
+
+#[derive(Debug, Default, Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct TagFile {
+    entries: Vec<TagEntry>,
+}
+
+#[derive(Debug, Archive, Serialize, Deserialize)]
+#[archive(check_bytes)]
+struct TagEntry {
+    key: u64,
+    tags: Vec<String>,
+}
+
+/// Tags per document, written through to `path` on every change.
+#[derive(Debug)]
+pub struct TagStore {
+    path: PathBuf,
+    tags: RwLock<HashMap<DocKey, BTreeSet<String>>>,
+}
+
+impl TagStore {
+    /// Load the store at `path`; a missing file is an empty store.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut tags = HashMap::new();
+        if path.exists() {
+            let bytes = fs::read(path).context("read tag store")?;
+            let file = from_rkyv_bytes::<TagFile>(&bytes).context("deserialize tag store")?;
+            for entry in file.entries {
+                tags.insert(DocKey(entry.key), entry.tags.into_iter().collect());
+            }
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            tags: RwLock::new(tags),
+        })
+    }
+
+    /// Add then remove tags on `key`, persist, and return its resulting tags (sorted).
+    pub fn update(&self, key: DocKey, add: &[String], remove: &[String]) -> Result<Vec<String>> {
+        let mut tags = self.tags.write().unwrap_or_else(|e| e.into_inner());
+        let set = tags.entry(key).or_default();
+        set.extend(add.iter().filter_map(|t| normalize(t)));
+        for tag in remove.iter().filter_map(|t| normalize(t)) {
+            set.remove(&tag);
+        }
+        let current: Vec<String> = set.iter().cloned().collect();
+        if current.is_empty() {
+            tags.remove(&key);
+        }
+        self.save(&tags)?;
+        Ok(current)
+    }
+
+    /// Tags on `key`, sorted.
+    pub fn tags(&self, key: DocKey) -> Vec<String> {
+        let tags = self.tags.read().unwrap_or_else(|e| e.into_inner());
+        tags.get(&key)
+            .map(|set| set.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Documents carrying `tag` (matched case-insensitively).
+    pub fn keys_with(&self, tag: &str) -> Vec<DocKey> {
+        let Some(tag) = normalize(tag) else {
+            return Vec::new();
+        };
+        let tags = self.tags.read().unwrap_or_else(|e| e.into_inner());
+        tags.iter()
+            .filter(|(_, set)| set.contains(&tag))
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    fn save(&self, tags: &HashMap<DocKey, BTreeSet<String>>) -> Result<()> {
+        let file = TagFile {
+            entries: tags
+                .iter()
+                .map(|(key, set)| TagEntry {
+                    key: key.0,
+                    tags: set.iter().cloned().collect(),
+                })
+                .collect(),
+        };
+        let bytes = to_rkyv_bytes(&file).context("serialize tag store")?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("create tag store dir")?;
+        }
+
+        // Atomic write: write to tmp, rename.
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, &bytes).context("write tmp tag store")?;
+        fs::rename(&tmp_path, &self.path).context("rename tag store")?;
+        Ok(())
+    }
+}
+
+fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.trim();
+    (!tag.is_empty()).then(|| tag.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    fn strings(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn add_remove_round_trips_through_disk() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("tags.rkyv");
+        let key = DocKey::from_parts(1, 42);
+        let other = DocKey::from_parts(1, 43);
+
+        let store = TagStore::open(&path).unwrap();
+        let now = store
+            .update(key, &strings(&["Important", " work ", ""]), &[])
+            .unwrap();
+        assert_eq!(now, strings(&["important", "work"]));
+        store.update(other, &strings(&["work"]), &[]).unwrap();
+        let now = store.update(key, &[], &strings(&["WORK"])).unwrap();
+        assert_eq!(now, strings(&["important"]));
+
+        let reopened = TagStore::open(&path).unwrap();
+        assert_eq!(reopened.tags(key), strings(&["important"]));
+        assert_eq!(reopened.keys_with("Important"), vec![key]);
+        assert_eq!(reopened.keys_with("work"), vec![other]);
+
+        reopened.update(key, &[], &strings(&["important"])).unwrap();
+        assert!(TagStore::open(&path).unwrap().tags(key).is_empty());
+        assert!(reopened.keys_with("important").is_empty());
+    }
+}
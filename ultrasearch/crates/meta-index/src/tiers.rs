@@ -140,6 +140,8 @@ pub fn doc_to_meta(doc: &TantivyDocument, fields: &MetaFields) -> Option<MetaDoc
     let mut modified = None;
     let mut flags = None;
     let mut volume = None;
+    let mut owner = None;
+    let mut archive = None;
 
     for (field, value) in doc.iter_fields_and_values() {
         match field {
@@ -152,6 +154,8 @@ pub fn doc_to_meta(doc: &TantivyDocument, fields: &MetaFields) -> Option<MetaDoc
             f if f == fields.created => created = value.as_i64(),
             f if f == fields.modified => modified = value.as_i64(),
             f if f == fields.flags => flags = value.as_u64(),
+            f if f == fields.owner => owner = value.as_str().map(|s| s.to_string()),
+            f if f == fields.archive => archive = value.as_u64().map(core_types::DocKey),
             _ => {}
         }
     }
@@ -169,6 +173,8 @@ pub fn doc_to_meta(doc: &TantivyDocument, fields: &MetaFields) -> Option<MetaDoc
             created: c,
             modified: m,
             flags: f,
+            owner,
+            archive,
         })
     } else {
         None
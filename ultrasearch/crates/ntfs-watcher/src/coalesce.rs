@@ -0,0 +1,109 @@
+//! Collapse rename chains within one batch of events (`indexing.coalesce_renames`).
+//!
+//! Operations such as `git checkout` can rename a file A→B→C inside a single USN batch. The
+//! FRN (and so the `DocKey`) survives each step, so the chain is really a series of name
+//! changes to one document; only the last one matters to the index.
+
+use crate::FileEvent;
+use core_types::DocKey;
+use std::collections::HashMap;
+
+/// Merge each run of renames of one document into a single `Renamed` carrying the first
+/// `from` and the final metadata. The merged event takes the place of the last rename in the
+/// chain, so it still follows any events that came between the steps. A `Created` or
+/// `Deleted` for the key ends its chain.
+pub fn coalesce_renames(events: Vec<FileEvent>) -> Vec<FileEvent> {
+    let mut out: Vec<Option<FileEvent>> = Vec::with_capacity(events.len());
+    // Current key of a rename already emitted -> its slot in `out`.
+    let mut pending: HashMap<DocKey, usize> = HashMap::new();
+    for event in events {
+        match event {
+            FileEvent::Renamed { from, to } => {
+                let from = match pending.remove(&from).and_then(|slot| out[slot].take()) {
+                    Some(FileEvent::Renamed { from: first, .. }) => first,
+                    _ => from,
+                };
+                pending.insert(to.key, out.len());
+                out.push(Some(FileEvent::Renamed { from, to }));
+            }
+            FileEvent::Created(meta) => {
+                pending.remove(&meta.key);
+                out.push(Some(FileEvent::Created(meta)));
+            }
+            FileEvent::Deleted(doc) => {
+                pending.remove(&doc);
+                out.push(Some(FileEvent::Deleted(doc)));
+            }
+            other => out.push(Some(other)),
+        }
+    }
+    out.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_types::{FileFlags, FileMeta};
+
+    fn named(frn: u64, name: &str) -> FileMeta {
+        FileMeta::new(
+            DocKey::from_parts(1, frn),
+            1,
+            None,
+            name.into(),
+            Some(format!(r"C:\repo\{name}")),
+            0,
+            0,
+            0,
+            FileFlags::empty(),
+        )
+    }
+
+    #[test]
+    fn rename_chain_collapses_to_final_name() {
+        let key = DocKey::from_parts(1, 7);
+        let other = named(8, "other.rs");
+        let events = vec![
+            FileEvent::Renamed {
+                from: key,
+                to: named(7, "b.rs"),
+            },
+            FileEvent::Modified { doc: key },
+            FileEvent::Renamed {
+                from: key,
+                to: named(7, "c.rs"),
+            },
+            FileEvent::Created(other.clone()),
+            FileEvent::Renamed {
+                from: key,
+                to: named(7, "d.rs"),
+            },
+        ];
+
+        assert_eq!(
+            coalesce_renames(events),
+            vec![
+                FileEvent::Modified { doc: key },
+                FileEvent::Created(other),
+                FileEvent::Renamed {
+                    from: key,
+                    to: named(7, "d.rs"),
+                },
+            ]
+        );
+
+        // A delete ends the chain: the rename before it and the one after stay separate.
+        let split = vec![
+            FileEvent::Renamed {
+                from: key,
+                to: named(7, "b.rs"),
+            },
+            FileEvent::Deleted(key),
+            FileEvent::Renamed {
+                from: key,
+                to: named(7, "c.rs"),
+            },
+        ];
+        assert_eq!(coalesce_renames(split.clone()), split);
+    }
+}
@@ -0,0 +1,60 @@
+//! Physical disk lookup, so volumes sharing one drive can be indexed one at a time.
+
+use crate::VolumeInfo;
+
+/// Number of the physical disk backing `volume` (`IOCTL_STORAGE_GET_DEVICE_NUMBER`), or
+/// `None` if it can't be determined, e.g. for volumes spanning several disks.
+#[cfg(windows)]
+pub fn physical_disk_number(volume: &VolumeInfo) -> Option<u32> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, FILE_FLAGS_AND_ATTRIBUTES, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+    };
+    use windows::Win32::System::IO::DeviceIoControl;
+    use windows::Win32::System::Ioctl::{IOCTL_STORAGE_GET_DEVICE_NUMBER, STORAGE_DEVICE_NUMBER};
+    use windows::core::PCWSTR;
+
+    // The device itself, not its root directory: no trailing backslash.
+    let device = volume.guid_path.trim_end_matches('\\');
+    let wide: Vec<u16> = OsStr::new(device).encode_wide().chain(Some(0)).collect();
+    // SAFETY: `wide` is NUL-terminated; zero access rights suffice for this IOCTL.
+    let handle = unsafe {
+        CreateFileW(
+            PCWSTR(wide.as_ptr()),
+            0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            None,
+        )
+    }
+    .ok()?;
+
+    let mut number = STORAGE_DEVICE_NUMBER::default();
+    let mut returned = 0u32;
+    // SAFETY: the output buffer is a properly sized STORAGE_DEVICE_NUMBER.
+    let result = unsafe {
+        DeviceIoControl(
+            handle,
+            IOCTL_STORAGE_GET_DEVICE_NUMBER,
+            None,
+            0,
+            Some(&mut number as *mut _ as *mut _),
+            std::mem::size_of::<STORAGE_DEVICE_NUMBER>() as u32,
+            Some(&mut returned),
+            None,
+        )
+    };
+    unsafe {
+        let _ = CloseHandle(handle);
+    }
+    result.ok().map(|_| number.DeviceNumber)
+}
+
+#[cfg(not(windows))]
+pub fn physical_disk_number(_volume: &VolumeInfo) -> Option<u32> {
+    None
+}
@@ -0,0 +1,134 @@
+//! Optional reduction of the event stream to directory-structure changes.
+//!
+//! Structure-focused consumers (e.g. a folder-sync tool) only need creates, deletes and
+//! renames, plus changes to directories themselves. `Modified`/`AttributesChanged` carry only
+//! a key, so the filter remembers which keys are directories from enumeration snapshots and
+//! from the metadata on `Created`/`Renamed` events. The filter can also collapse rename
+//! chains first (see [`coalesce_renames`]).
+
+use crate::{FileEvent, coalesce_renames};
+use core_types::{DocKey, FileMeta};
+use std::collections::HashSet;
+
+#[derive(Debug, Default)]
+pub struct EventFilter {
+    structure_only: bool,
+    coalesce_renames: bool,
+    dirs: HashSet<DocKey>,
+}
+
+impl EventFilter {
+    /// `structure_only = false` passes every event and tracks nothing.
+    pub fn new(structure_only: bool) -> Self {
+        Self {
+            structure_only,
+            coalesce_renames: false,
+            dirs: HashSet::new(),
+        }
+    }
+
+    /// Collapse A→B→C rename chains within each batch into one rename (default: off).
+    pub fn with_rename_coalescing(mut self, enabled: bool) -> Self {
+        self.coalesce_renames = enabled;
+        self
+    }
+
+    /// Learn directory keys from an enumeration snapshot.
+    pub fn observe(&mut self, metas: &[FileMeta]) {
+        if self.structure_only {
+            self.dirs
+                .extend(metas.iter().filter(|m| m.flags.is_dir()).map(|m| m.key));
+        }
+    }
+
+    /// Keep the events this filter lets through, in order.
+    pub fn apply(&mut self, events: Vec<FileEvent>) -> Vec<FileEvent> {
+        let events = if self.coalesce_renames {
+            coalesce_renames(events)
+        } else {
+            events
+        };
+        if !self.structure_only {
+            return events;
+        }
+        events.into_iter().filter(|ev| self.allows(ev)).collect()
+    }
+
+    fn allows(&mut self, ev: &FileEvent) -> bool {
+        match ev {
+            FileEvent::Created(meta) => {
+                self.track(meta);
+                true
+            }
+            FileEvent::Renamed { from, to } => {
+                self.dirs.remove(from);
+                self.track(to);
+                true
+            }
+            FileEvent::Deleted(doc) => {
+                self.dirs.remove(doc);
+                true
+            }
+            FileEvent::Modified { doc } | FileEvent::AttributesChanged { doc } => {
+                self.dirs.contains(doc)
+            }
+        }
+    }
+
+    fn track(&mut self, meta: &FileMeta) {
+        if meta.flags.is_dir() {
+            self.dirs.insert(meta.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_types::FileFlags;
+
+    fn meta(frn: u64, name: &str, flags: FileFlags) -> FileMeta {
+        FileMeta::new(
+            DocKey::from_parts(1, frn),
+            1,
+            None,
+            name.into(),
+            None,
+            0,
+            0,
+            0,
+            flags,
+        )
+    }
+
+    #[test]
+    fn structure_only_drops_file_modifications_but_keeps_directory_changes() {
+        let dir = meta(1, "Projects", FileFlags::IS_DIR);
+        let file = meta(2, "notes.txt", FileFlags::empty());
+        let known_dir = meta(3, "Archive", FileFlags::IS_DIR);
+        let events = vec![
+            FileEvent::Created(dir.clone()),
+            FileEvent::Modified { doc: file.key },
+            FileEvent::AttributesChanged { doc: file.key },
+            FileEvent::Modified { doc: dir.key },
+            FileEvent::AttributesChanged { doc: known_dir.key },
+            FileEvent::Deleted(file.key),
+        ];
+
+        let mut filter = EventFilter::new(true);
+        filter.observe(&[known_dir.clone(), file.clone()]);
+        let kept = filter.apply(events.clone());
+        assert_eq!(
+            kept,
+            vec![
+                FileEvent::Created(dir.clone()),
+                FileEvent::Modified { doc: dir.key },
+                FileEvent::AttributesChanged { doc: known_dir.key },
+                FileEvent::Deleted(file.key),
+            ]
+        );
+
+        // Off: everything passes.
+        assert_eq!(EventFilter::new(false).apply(events.clone()), events);
+    }
+}
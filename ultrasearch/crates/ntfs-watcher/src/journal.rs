@@ -0,0 +1,287 @@
+//! USN change journal decoding.
+//!
+//! `FSCTL_READ_USN_JOURNAL` fills a buffer with the next USN to read followed by
+//! variable-length `USN_RECORD_V2` / `USN_RECORD_V3` records. [`decode_records`] parses that
+//! buffer (V3 carries 128-bit file IDs; NTFS keeps the 64-bit FRN in the low half), and
+//! [`events_from_records`] turns the records' reason flags into [`FileEvent`]s.
+
+use crate::record::filetime_to_unix;
+use crate::{FileEvent, NtfsError, Usn, attribute_flags};
+use core_types::{DocKey, FileFlags, FileMeta, Timestamp, VolumeId};
+
+pub const USN_REASON_DATA_OVERWRITE: u32 = 0x0000_0001;
+pub const USN_REASON_DATA_EXTEND: u32 = 0x0000_0002;
+pub const USN_REASON_DATA_TRUNCATION: u32 = 0x0000_0004;
+pub const USN_REASON_NAMED_DATA_OVERWRITE: u32 = 0x0000_0010;
+pub const USN_REASON_NAMED_DATA_EXTEND: u32 = 0x0000_0020;
+pub const USN_REASON_NAMED_DATA_TRUNCATION: u32 = 0x0000_0040;
+pub const USN_REASON_FILE_CREATE: u32 = 0x0000_0100;
+pub const USN_REASON_FILE_DELETE: u32 = 0x0000_0200;
+pub const USN_REASON_EA_CHANGE: u32 = 0x0000_0400;
+pub const USN_REASON_SECURITY_CHANGE: u32 = 0x0000_0800;
+pub const USN_REASON_RENAME_OLD_NAME: u32 = 0x0000_1000;
+pub const USN_REASON_RENAME_NEW_NAME: u32 = 0x0000_2000;
+pub const USN_REASON_INDEXABLE_CHANGE: u32 = 0x0000_4000;
+pub const USN_REASON_BASIC_INFO_CHANGE: u32 = 0x0000_8000;
+pub const USN_REASON_HARD_LINK_CHANGE: u32 = 0x0001_0000;
+pub const USN_REASON_COMPRESSION_CHANGE: u32 = 0x0002_0000;
+pub const USN_REASON_ENCRYPTION_CHANGE: u32 = 0x0004_0000;
+pub const USN_REASON_OBJECT_ID_CHANGE: u32 = 0x0008_0000;
+pub const USN_REASON_REPARSE_POINT_CHANGE: u32 = 0x0010_0000;
+pub const USN_REASON_STREAM_CHANGE: u32 = 0x0020_0000;
+pub const USN_REASON_CLOSE: u32 = 0x8000_0000;
+
+const DATA_REASONS: u32 = USN_REASON_DATA_OVERWRITE
+    | USN_REASON_DATA_EXTEND
+    | USN_REASON_DATA_TRUNCATION
+    | USN_REASON_NAMED_DATA_OVERWRITE
+    | USN_REASON_NAMED_DATA_EXTEND
+    | USN_REASON_NAMED_DATA_TRUNCATION
+    | USN_REASON_STREAM_CHANGE;
+
+const ATTRIBUTE_REASONS: u32 = USN_REASON_EA_CHANGE
+    | USN_REASON_SECURITY_CHANGE
+    | USN_REASON_INDEXABLE_CHANGE
+    | USN_REASON_BASIC_INFO_CHANGE
+    | USN_REASON_HARD_LINK_CHANGE
+    | USN_REASON_COMPRESSION_CHANGE
+    | USN_REASON_ENCRYPTION_CHANGE
+    | USN_REASON_OBJECT_ID_CHANGE
+    | USN_REASON_REPARSE_POINT_CHANGE;
+
+const FILE_ATTRIBUTE_HIDDEN: u32 = 0x0002;
+const FILE_ATTRIBUTE_SYSTEM: u32 = 0x0004;
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x0010;
+
+/// One decoded `USN_RECORD_V2`/`V3`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsnRecord {
+    pub usn: Usn,
+    /// File reference number (record number plus sequence).
+    pub frn: u64,
+    pub parent_frn: u64,
+    /// Unix seconds.
+    pub timestamp: Timestamp,
+    /// `USN_REASON_*` bits accumulated for this record.
+    pub reason: u32,
+    /// `FILE_ATTRIBUTE_*` bits.
+    pub attributes: u32,
+    pub name: String,
+}
+
+impl UsnRecord {
+    fn flags(&self) -> FileFlags {
+        let mut flags = attribute_flags(self.attributes);
+        flags.set(
+            FileFlags::IS_DIR,
+            self.attributes & FILE_ATTRIBUTE_DIRECTORY != 0,
+        );
+        flags.set(
+            FileFlags::HIDDEN,
+            self.attributes & FILE_ATTRIBUTE_HIDDEN != 0,
+        );
+        flags.set(
+            FileFlags::SYSTEM,
+            self.attributes & FILE_ATTRIBUTE_SYSTEM != 0,
+        );
+        flags
+    }
+}
+
+/// Records decoded from one journal read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodedRecords {
+    pub records: Vec<UsnRecord>,
+    /// USN of the first record left undecoded because `max` was reached; reading resumes
+    /// there. `None` when the whole buffer was consumed.
+    pub resume_usn: Option<Usn>,
+}
+
+fn u16_at(buf: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(buf.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(buf: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(buf.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(buf: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(buf.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Decode up to `max` records from `buf`, the record area of a `FSCTL_READ_USN_JOURNAL`
+/// result (i.e. without its leading 8-byte next-USN). Records of other major versions are
+/// skipped.
+pub fn decode_records(buf: &[u8], max: usize) -> Result<DecodedRecords, NtfsError> {
+    let malformed = |at: usize| NtfsError::Journal(format!("malformed USN record at offset {at}"));
+    let mut out = DecodedRecords::default();
+    let mut at = 0;
+    while at < buf.len() {
+        let rec = &buf[at..];
+        let len = u32_at(rec, 0).ok_or_else(|| malformed(at))? as usize;
+        if len < 8 || len > rec.len() {
+            return Err(malformed(at));
+        }
+        let rec = &rec[..len];
+        // (file id, parent id, usn, timestamp, reason, attributes, name len, name offset)
+        let offsets = match u16_at(rec, 4) {
+            Some(2) => Some((8, 16, 24, 32, 40, 52, 56, 58)),
+            Some(3) => Some((8, 24, 40, 48, 56, 68, 72, 74)),
+            _ => None,
+        };
+        if let Some((frn, parent, usn, time, reason, attrs, name_len, name_off)) = offsets {
+            let usn = u64_at(rec, usn).ok_or_else(|| malformed(at))?;
+            if out.records.len() >= max {
+                out.resume_usn = Some(usn);
+                break;
+            }
+            let name_len = u16_at(rec, name_len).ok_or_else(|| malformed(at))? as usize;
+            let name_off = u16_at(rec, name_off).ok_or_else(|| malformed(at))? as usize;
+            let name: Vec<u16> = rec
+                .get(name_off..name_off + name_len)
+                .ok_or_else(|| malformed(at))?
+                .as_chunks::<2>()
+                .0
+                .iter()
+                .map(|c| u16::from_le_bytes(*c))
+                .collect();
+            out.records.push(UsnRecord {
+                usn,
+                frn: u64_at(rec, frn).ok_or_else(|| malformed(at))?,
+                parent_frn: u64_at(rec, parent).ok_or_else(|| malformed(at))?,
+                timestamp: filetime_to_unix(u64_at(rec, time).ok_or_else(|| malformed(at))?),
+                reason: u32_at(rec, reason).ok_or_else(|| malformed(at))?,
+                attributes: u32_at(rec, attrs).ok_or_else(|| malformed(at))?,
+                name: String::from_utf16_lossy(&name),
+            });
+        }
+        // Records are 8-byte aligned.
+        at += len.next_multiple_of(8);
+    }
+    Ok(out)
+}
+
+/// Map records to events, strongest reason first: delete, create, rename (new name), data
+/// change, attribute change. Old-name-only rename records and records carrying no reason
+/// of interest produce nothing. `resolve_path` supplies the full path for created and
+/// renamed entries (`None` when it can't be resolved).
+pub fn events_from_records(
+    volume: VolumeId,
+    records: &[UsnRecord],
+    mut resolve_path: impl FnMut(&UsnRecord) -> Option<String>,
+) -> Vec<FileEvent> {
+    let mut events = Vec::with_capacity(records.len());
+    for record in records {
+        let doc = DocKey::from_parts(volume, record.frn);
+        let meta = |path| {
+            FileMeta::new(
+                doc,
+                volume,
+                Some(DocKey::from_parts(volume, record.parent_frn)),
+                record.name.clone(),
+                path,
+                0,
+                0,
+                record.timestamp,
+                record.flags(),
+            )
+        };
+        let reason = record.reason;
+        let event = if reason & USN_REASON_FILE_DELETE != 0 {
+            FileEvent::Deleted(doc)
+        } else if reason & USN_REASON_FILE_CREATE != 0 {
+            FileEvent::Created(meta(resolve_path(record)))
+        } else if reason & USN_REASON_RENAME_NEW_NAME != 0 {
+            FileEvent::Renamed {
+                from: doc,
+                to: meta(resolve_path(record)),
+            }
+        } else if reason & DATA_REASONS != 0 {
+            FileEvent::Modified { doc }
+        } else if reason & ATTRIBUTE_REASONS != 0 {
+            FileEvent::AttributesChanged { doc }
+        } else {
+            continue;
+        };
+        events.push(event);
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::FILETIME_UNIX_OFFSET;
+
+    /// Encode a record as `USN_RECORD_V2` (`v3 = false`) or `USN_RECORD_V3`.
+    fn encode(v3: bool, frn: u64, parent: u64, usn: u64, reason: u32, name: &str) -> Vec<u8> {
+        let name: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let (ids, header) = if v3 { (16, 76) } else { (8, 60) };
+        let len = (header + name.len()).next_multiple_of(8);
+        let mut rec = vec![0u8; len];
+        rec[0..4].copy_from_slice(&(len as u32).to_le_bytes());
+        rec[4..6].copy_from_slice(&(if v3 { 3u16 } else { 2 }).to_le_bytes());
+        rec[8..16].copy_from_slice(&frn.to_le_bytes());
+        rec[8 + ids..16 + ids].copy_from_slice(&parent.to_le_bytes());
+        let at = 8 + 2 * ids;
+        rec[at..at + 8].copy_from_slice(&usn.to_le_bytes());
+        // 2024-01-01T00:00:00Z as FILETIME.
+        let filetime = (1_704_067_200 + FILETIME_UNIX_OFFSET as u64) * 10_000_000;
+        rec[at + 8..at + 16].copy_from_slice(&filetime.to_le_bytes());
+        rec[at + 16..at + 20].copy_from_slice(&reason.to_le_bytes());
+        rec[at + 28..at + 32].copy_from_slice(&0x20u32.to_le_bytes());
+        rec[at + 32..at + 34].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        rec[at + 34..at + 36].copy_from_slice(&(header as u16).to_le_bytes());
+        rec[header..header + name.len()].copy_from_slice(&name);
+        rec
+    }
+
+    #[test]
+    fn v2_and_v3_records_decode_into_events() {
+        let rename = USN_REASON_RENAME_OLD_NAME | USN_REASON_RENAME_NEW_NAME;
+        let records = [
+            (false, 10, 100, USN_REASON_FILE_CREATE, "new.txt"),
+            (true, 11, 200, USN_REASON_DATA_EXTEND, "data.bin"),
+            (false, 12, 300, rename, "renamed.txt"),
+            (true, 13, 400, USN_REASON_FILE_DELETE, "gone"),
+            (false, 14, 500, USN_REASON_SECURITY_CHANGE, "acl"),
+        ];
+        let buf: Vec<u8> = records
+            .iter()
+            .flat_map(|&(v3, frn, usn, reason, name)| {
+                encode(v3, frn, 5, usn, reason | USN_REASON_CLOSE, name)
+            })
+            .collect();
+
+        let decoded = decode_records(&buf, usize::MAX).unwrap();
+        assert_eq!(decoded.resume_usn, None);
+        assert_eq!(decoded.records.len(), 5);
+        assert_eq!(decoded.records[1].name, "data.bin");
+        assert_eq!(decoded.records[1].usn, 200);
+        assert_eq!(decoded.records[0].timestamp, 1_704_067_200);
+
+        let events = events_from_records(7, &decoded.records, |r| Some(format!(r"C:\{}", r.name)));
+        let key = |frn| DocKey::from_parts(7, frn);
+        match &events[0] {
+            FileEvent::Created(meta) => {
+                assert_eq!(meta.key, key(10));
+                assert_eq!(meta.parent, Some(key(5)));
+                assert_eq!(meta.path.as_deref(), Some(r"C:\new.txt"));
+                assert_eq!(meta.modified, 1_704_067_200);
+            }
+            other => panic!("expected Created, got {other:?}"),
+        }
+        assert_eq!(events[1], FileEvent::Modified { doc: key(11) });
+        assert!(matches!(&events[2], FileEvent::Renamed { from, to }
+            if *from == key(12) && to.name == "renamed.txt"));
+        assert_eq!(events[3], FileEvent::Deleted(key(13)));
+        assert_eq!(events[4], FileEvent::AttributesChanged { doc: key(14) });
+
+        // The cap leaves the rest for the next read, starting at the first undecoded USN.
+        let capped = decode_records(&buf, 2).unwrap();
+        assert_eq!(capped.records.len(), 2);
+        assert_eq!(capped.resume_usn, Some(300));
+
+        assert!(decode_records(&buf[..20], usize::MAX).is_err());
+    }
+}
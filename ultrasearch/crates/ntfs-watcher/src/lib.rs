@@ -6,11 +6,61 @@
 //! testable scaffold that mirrors the implementation plan without yet wiring
 //! Win32 calls.
 
-use core_types::{DocKey, FileMeta, VolumeId};
+use core_types::{DocKey, FileFlags, FileMeta, Timestamp, VolumeId};
 use thiserror::Error;
 
+pub mod coalesce;
+pub mod disk;
+pub mod filter;
+pub mod journal;
+pub mod object_id;
+pub mod owner;
+pub mod polling;
+pub mod record;
+pub use coalesce::coalesce_renames;
+pub use disk::physical_disk_number;
+pub use filter::EventFilter;
+pub use object_id::file_object_id;
+pub use owner::file_owner;
+pub use polling::PollingWatcher;
+pub use record::filetime_to_unix;
+
 pub type Usn = u64;
 
+/// Reparse tag shared by volume mount points and directory junctions.
+pub const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xA000_0003;
+
+const FILE_ATTRIBUTE_SPARSE_FILE: u32 = 0x0200;
+const FILE_ATTRIBUTE_COMPRESSED: u32 = 0x0800;
+const FILE_ATTRIBUTE_ENCRYPTED: u32 = 0x4000;
+
+/// Compression, encryption and sparseness flags from an entry's `FILE_ATTRIBUTE_*` bits.
+pub fn attribute_flags(attributes: u32) -> FileFlags {
+    let mut flags = FileFlags::empty();
+    flags.set(
+        FileFlags::COMPRESSED,
+        attributes & FILE_ATTRIBUTE_COMPRESSED != 0,
+    );
+    flags.set(
+        FileFlags::ENCRYPTED,
+        attributes & FILE_ATTRIBUTE_ENCRYPTED != 0,
+    );
+    flags.set(
+        FileFlags::SPARSE,
+        attributes & FILE_ATTRIBUTE_SPARSE_FILE != 0,
+    );
+    flags
+}
+
+/// Flags implied by an entry's reparse tag (`None` = not a reparse point).
+pub fn reparse_flags(tag: Option<u32>) -> FileFlags {
+    match tag {
+        Some(IO_REPARSE_TAG_MOUNT_POINT) => FileFlags::REPARSE | FileFlags::MOUNT_POINT,
+        Some(_) => FileFlags::REPARSE,
+        None => FileFlags::empty(),
+    }
+}
+
 /// Static information about a mounted NTFS volume.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct VolumeInfo {
@@ -34,9 +84,10 @@ pub enum FileEvent {
 
 /// Configuration knobs for NTFS/USN access.
 #[derive(Debug, Clone)]
-#[allow(dead_code)] // will be wired when Win32 integrations land
 pub struct ReaderConfig {
+    /// Bytes per `FSCTL_READ_USN_JOURNAL` read.
     pub chunk_size: usize,
+    /// Records decoded per [`tail_usn`] call; the rest wait for the next call.
     pub max_records_per_tick: usize,
 }
 
@@ -49,13 +100,116 @@ impl Default for ReaderConfig {
     }
 }
 
+/// Result of an MFT enumeration that may have stopped at the per-volume entry cap.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MftEnumeration {
+    pub entries: Vec<FileMeta>,
+    /// Entries seen after the cap was reached and not emitted.
+    pub skipped: u64,
+    /// FRN of the first skipped entry; a later run can continue from here.
+    pub resume_frn: Option<u64>,
+    /// Files dropped because they were last modified before the `only_modified_since`
+    /// baseline. They don't count toward the cap.
+    pub unchanged: u64,
+}
+
+/// Accumulates enumerated entries up to `max_entries`, counting (but not keeping) the rest.
+///
+/// Platform walkers check [`has_room`](Self::has_room) before doing per-entry work such as
+/// path resolution, so entries past the cap cost only the walk itself.
+#[derive(Debug)]
+pub struct CappedCollector {
+    max_entries: Option<u64>,
+    only_modified_since: Option<Timestamp>,
+    out: MftEnumeration,
+}
+
+impl CappedCollector {
+    /// `None` means unlimited.
+    pub fn new(max_entries: Option<u64>) -> Self {
+        Self {
+            max_entries,
+            only_modified_since: None,
+            out: MftEnumeration::default(),
+        }
+    }
+
+    /// Drop files last modified before `since` (Unix seconds; `None` keeps everything).
+    /// Directories are always kept so the surviving files still have parents, and entries
+    /// whose modification time is unknown (0) are kept rather than guessed at.
+    pub fn with_modified_since(mut self, since: Option<Timestamp>) -> Self {
+        self.only_modified_since = since;
+        self
+    }
+
+    fn is_unchanged(&self, meta: &FileMeta) -> bool {
+        self.only_modified_since.is_some_and(|since| {
+            !meta.flags.is_dir() && meta.modified != 0 && meta.modified < since
+        })
+    }
+
+    pub fn has_room(&self) -> bool {
+        self.max_entries
+            .is_none_or(|max| (self.out.entries.len() as u64) < max)
+    }
+
+    /// Record an entry; past the cap it is counted as skipped instead. Files older than the
+    /// modified-since baseline are counted as unchanged.
+    pub fn push(&mut self, meta: FileMeta) {
+        if self.is_unchanged(&meta) {
+            self.out.unchanged += 1;
+        } else if self.has_room() {
+            self.out.entries.push(meta);
+        } else {
+            self.skip(meta.key.into_parts().1);
+        }
+    }
+
+    /// Record an entry past the cap without materializing it.
+    pub fn skip(&mut self, frn: u64) {
+        self.out.resume_frn.get_or_insert(frn);
+        self.out.skipped += 1;
+    }
+
+    pub fn finish(self, volume: &VolumeInfo) -> MftEnumeration {
+        if self.out.skipped > 0 {
+            tracing::warn!(
+                volume = volume.id,
+                guid = %volume.guid_path,
+                emitted = self.out.entries.len(),
+                skipped = self.out.skipped,
+                resume_frn = ?self.out.resume_frn,
+                "MFT enumeration reached max_entries_per_volume; remaining entries were not indexed"
+            );
+        }
+        if self.out.unchanged > 0 {
+            tracing::info!(
+                volume = volume.id,
+                unchanged = self.out.unchanged,
+                since = ?self.only_modified_since,
+                "skipped files not modified since the baseline"
+            );
+        }
+        self.out
+    }
+}
+
 /// Cursor for resuming USN processing.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct JournalCursor {
     pub last_usn: Usn,
     pub journal_id: u64,
 }
 
+impl JournalCursor {
+    /// Whether the journal, whose oldest retained record is `first_usn`, has wrapped past
+    /// this cursor: records after `last_usn` were purged unread, so the index may have
+    /// missed changes and the volume must be re-enumerated.
+    pub fn is_stale_against(&self, first_usn: Usn) -> bool {
+        self.last_usn < first_usn
+    }
+}
+
 /// Errors that can surface while interacting with NTFS / USN APIs.
 #[derive(Debug, Error)]
 pub enum NtfsError {
@@ -63,8 +217,10 @@ pub enum NtfsError {
     Discovery(String),
     #[error("usn journal error: {0}")]
     Journal(String),
+    /// The journal was recreated or wrapped past the cursor. Re-enumerate the volume, then
+    /// continue tailing from `restart` (the journal's end when the gap was found).
     #[error("usn gap detected")]
-    GapDetected,
+    GapDetected { restart: JournalCursor },
     #[error("mft enumeration failed: {0}")]
     Mft(String),
     #[error("operation not supported on this platform")]
@@ -81,6 +237,37 @@ pub trait NtfsWatcher {
     /// Enumerate the MFT and stream file metadata snapshots.
     fn enumerate_mft(&self, volume: &VolumeInfo) -> Result<Vec<FileMeta>, NtfsError>;
 
+    /// Enumerate at most `max_entries` entries (`None` = unlimited), skipping files last
+    /// modified before `only_modified_since`.
+    fn enumerate_mft_capped(
+        &self,
+        volume: &VolumeInfo,
+        max_entries: Option<u64>,
+        only_modified_since: Option<Timestamp>,
+    ) -> Result<MftEnumeration, NtfsError> {
+        let mut collector =
+            CappedCollector::new(max_entries).with_modified_since(only_modified_since);
+        for meta in self.enumerate_mft(volume)? {
+            collector.push(meta);
+        }
+        Ok(collector.finish(volume))
+    }
+
+    /// Enumerate in batches of `batch_size`, handing each to `sink` as it fills; see
+    /// [`enumerate_mft_streaming`](crate::enumerate_mft_streaming).
+    fn enumerate_mft_streaming(
+        &self,
+        volume: &VolumeInfo,
+        batch_size: usize,
+        sink: &mut dyn FnMut(Vec<FileMeta>) -> Result<(), NtfsError>,
+    ) -> Result<(), NtfsError> {
+        let mut batcher = Batcher::new(batch_size, sink);
+        for meta in self.enumerate_mft(volume)? {
+            batcher.push(meta)?;
+        }
+        batcher.finish()
+    }
+
     /// Tail the USN journal starting at the given cursor.
     fn tail_usn(
         &self,
@@ -224,14 +411,115 @@ pub fn open_volume_handle(
 
 /// Enumerate the MFT for a given volume and emit file metadata snapshots.
 ///
-/// On Windows this uses usn-journal-rs to iterate the MFT and resolve paths.
-#[cfg(windows)]
+/// Holds the whole volume in memory; prefer [`enumerate_mft_streaming`] for large volumes.
 pub fn enumerate_mft(volume: &VolumeInfo) -> Result<Vec<FileMeta>, NtfsError> {
-    use core_types::FileFlags;
+    let mut all = Vec::new();
+    enumerate_mft_streaming(volume, 64 * 1024, |batch| {
+        all.extend(batch);
+        Ok(())
+    })?;
+    Ok(all)
+}
+
+/// Hands entries to a sink in batches of `batch_size`.
+struct Batcher<F> {
+    batch_size: usize,
+    batch: Vec<FileMeta>,
+    sink: F,
+}
+
+impl<F> Batcher<F>
+where
+    F: FnMut(Vec<FileMeta>) -> Result<(), NtfsError>,
+{
+    fn new(batch_size: usize, sink: F) -> Self {
+        let batch_size = batch_size.max(1);
+        Self {
+            batch_size,
+            batch: Vec::with_capacity(batch_size),
+            sink,
+        }
+    }
+
+    fn push(&mut self, meta: FileMeta) -> Result<(), NtfsError> {
+        self.batch.push(meta);
+        if self.batch.len() >= self.batch_size {
+            let full = std::mem::replace(&mut self.batch, Vec::with_capacity(self.batch_size));
+            (self.sink)(full)?;
+        }
+        Ok(())
+    }
+
+    /// Deliver the remainder, if any.
+    fn finish(mut self) -> Result<(), NtfsError> {
+        if self.batch.is_empty() {
+            return Ok(());
+        }
+        (self.sink)(self.batch)
+    }
+}
+
+/// Enumerate the MFT, calling `sink` with every `batch_size` entries (at least 1) and once
+/// more with the remainder. Only one batch is held at a time, so memory stays bounded on
+/// volumes with millions of files and callers can index and commit as batches arrive. An
+/// error from `sink` stops the walk and is returned.
+#[cfg(windows)]
+pub fn enumerate_mft_streaming(
+    volume: &VolumeInfo,
+    batch_size: usize,
+    sink: impl FnMut(Vec<FileMeta>) -> Result<(), NtfsError>,
+) -> Result<(), NtfsError> {
+    let mut batcher = Batcher::new(batch_size, sink);
+    walk_mft(volume, |_, build| batcher.push(build()))?;
+    batcher.finish()
+}
+
+#[cfg(not(windows))]
+pub fn enumerate_mft_streaming(
+    _volume: &VolumeInfo,
+    _batch_size: usize,
+    _sink: impl FnMut(Vec<FileMeta>) -> Result<(), NtfsError>,
+) -> Result<(), NtfsError> {
+    Err(NtfsError::NotSupported)
+}
+
+/// Enumerate the MFT, emitting at most `max_entries` entries (`None` = unlimited) and skipping
+/// files last modified before `only_modified_since`.
+///
+/// On Windows this uses usn-journal-rs to iterate the MFT and resolve paths. Entries past the
+/// cap are counted but never resolved, so a pathological volume cannot exhaust memory.
+#[cfg(windows)]
+pub fn enumerate_mft_capped(
+    volume: &VolumeInfo,
+    max_entries: Option<u64>,
+    only_modified_since: Option<Timestamp>,
+) -> Result<MftEnumeration, NtfsError> {
+    let mut out = CappedCollector::new(max_entries).with_modified_since(only_modified_since);
+    walk_mft(volume, |frn, build| {
+        if out.has_room() {
+            out.push(build());
+        } else {
+            out.skip(frn);
+        }
+        Ok(())
+    })?;
+    Ok(out.finish(volume))
+}
+
+/// Walk the MFT with usn-journal-rs. `visit` gets each entry's FRN and a closure that builds
+/// its [`FileMeta`]; path resolution and the file record lookup only happen when it is
+/// called. Timestamps come from each file's `$STANDARD_INFORMATION` (see [`record`]).
+#[cfg(windows)]
+fn walk_mft(
+    volume: &VolumeInfo,
+    mut visit: impl FnMut(u64, &mut dyn FnMut() -> FileMeta) -> Result<(), NtfsError>,
+) -> Result<(), NtfsError> {
+    use std::os::windows::io::AsRawHandle;
     use std::path::Path;
     use usn_journal_rs::mft::Mft;
     use usn_journal_rs::path::PathResolver;
     use usn_journal_rs::volume::Volume;
+    use windows::Win32::Foundation::HANDLE;
 
     let drive = volume
         .drive_letters
@@ -243,70 +531,352 @@ pub fn enumerate_mft(volume: &VolumeInfo) -> Result<Vec<FileMeta>, NtfsError> {
         .map_err(|e| NtfsError::Mft(format!("open volume {drive}: {e}")))?;
     let mut resolver = PathResolver::new(&vol);
     let mft = Mft::new(&vol).iter();
+    let records = open_volume_handle(volume)?;
+    let records = HANDLE(records.as_raw_handle() as isize);
+    let mut record_buf = vec![0u64; FILE_RECORD_BUF_WORDS];
 
-    let mut out = Vec::new();
     for entry in mft {
         let entry = entry.map_err(|e| NtfsError::Mft(format!("mft read: {e}")))?;
         let frn = entry.fid;
-        let parent_frn = entry.parent_fid;
-        let is_dir = entry.is_dir();
-
-        let path = resolver
-            .resolve_path(&entry)
-            .and_then(|p| p.to_str().map(|s| s.to_string()));
-        let name = path
-            .as_deref()
-            .and_then(|p| Path::new(p).file_name())
-            .and_then(|s| s.to_str())
-            .unwrap_or("")
-            .to_string();
+        let mut build = || {
+            let parent_frn = entry.parent_fid;
+            let is_dir = entry.is_dir();
 
-        let size = path
-            .as_deref()
-            .and_then(|p| std::fs::metadata(p).ok())
-            .map(|m| m.len())
-            .unwrap_or(0);
+            let path = resolver
+                .resolve_path(&entry)
+                .and_then(|p| p.to_str().map(|s| s.to_string()));
+            let name = path
+                .as_deref()
+                .and_then(|p| Path::new(p).file_name())
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_string();
 
-        let key = DocKey::from_parts(volume.id, frn);
-        let parent = Some(DocKey::from_parts(volume.id, parent_frn));
-        let flags = if is_dir {
-            FileFlags::IS_DIR
-        } else {
-            FileFlags::empty()
+            let fs_meta = path.as_deref().and_then(|p| std::fs::metadata(p).ok());
+            let size = fs_meta.as_ref().map_or(0, |m| m.len());
+            let (created, modified) = file_record_times(records, frn, &mut record_buf)
+                .map_or((0, 0), |t| (t.created, t.modified));
+
+            let key = DocKey::from_parts(volume.id, frn);
+            let parent = Some(DocKey::from_parts(volume.id, parent_frn));
+            // Only directories can be mount points; skip the extra lookup for files.
+            let flags = attribute_flags(entry.file_attributes)
+                | if is_dir {
+                    FileFlags::IS_DIR | reparse_flags(path.as_deref().and_then(reparse_tag))
+                } else {
+                    FileFlags::empty()
+                };
+
+            FileMeta::new(
+                key, volume.id, parent, name, path, size, created, modified, flags,
+            )
         };
+        visit(frn, &mut build)?;
+    }
+    Ok(())
+}
+
+/// Room for `NTFS_FILE_RECORD_OUTPUT_BUFFER` holding a file record of up to 4 KiB.
+#[cfg(windows)]
+const FILE_RECORD_BUF_WORDS: usize = (16 + 4096) / 8;
 
-        out.push(FileMeta::new(
-            key, volume.id, parent, name, path, size, 0, 0, flags,
-        ));
+/// `$STANDARD_INFORMATION` times of file `frn`, read with `FSCTL_GET_NTFS_FILE_RECORD`.
+/// `None` when the record can't be read or lacks the attribute.
+#[cfg(windows)]
+fn file_record_times(
+    volume: windows::Win32::Foundation::HANDLE,
+    frn: u64,
+    buf: &mut [u64],
+) -> Option<record::StandardTimes> {
+    use windows::Win32::System::IO::DeviceIoControl;
+    use windows::Win32::System::Ioctl::{
+        FSCTL_GET_NTFS_FILE_RECORD, NTFS_FILE_RECORD_INPUT_BUFFER,
+    };
+
+    let input = NTFS_FILE_RECORD_INPUT_BUFFER {
+        FileReferenceNumber: frn as i64,
+    };
+    let mut returned = 0u32;
+    // SAFETY: `volume` is an open volume handle; the input is an
+    // NTFS_FILE_RECORD_INPUT_BUFFER and the output buffer is 8-byte aligned and
+    // `buf.len() * 8` bytes long.
+    unsafe {
+        DeviceIoControl(
+            volume,
+            FSCTL_GET_NTFS_FILE_RECORD,
+            Some(&input as *const _ as *const _),
+            std::mem::size_of::<NTFS_FILE_RECORD_INPUT_BUFFER>() as u32,
+            Some(buf.as_mut_ptr().cast()),
+            (buf.len() * 8) as u32,
+            Some(&mut returned),
+            None,
+        )
+    }
+    .ok()?;
+    // SAFETY: `buf` is initialized and `returned` never exceeds its byte length.
+    let out = unsafe { std::slice::from_raw_parts(buf.as_ptr().cast::<u8>(), returned as usize) };
+    // The output is the FRN actually returned (the nearest in-use record at or below the
+    // requested one), the record length, then the record itself.
+    let got = u64::from_le_bytes(out.get(..8)?.try_into().ok()?);
+    if got & 0x0000_FFFF_FFFF_FFFF != frn & 0x0000_FFFF_FFFF_FFFF {
+        return None;
     }
+    let len = u32::from_le_bytes(out.get(8..12)?.try_into().ok()?) as usize;
+    record::standard_times(out.get(12..12 + len)?)
+}
+
+/// Reparse tag of `path`, or `None` when it is not a reparse point.
+#[cfg(windows)]
+fn reparse_tag(path: &str) -> Option<u32> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::fs::MetadataExt;
+    use windows::Win32::Storage::FileSystem::{
+        FILE_ATTRIBUTE_REPARSE_POINT, FindClose, FindFirstFileW, WIN32_FIND_DATAW,
+    };
+    use windows::core::PCWSTR;
 
-    Ok(out)
+    let attrs = std::fs::symlink_metadata(path).ok()?.file_attributes();
+    if attrs & FILE_ATTRIBUTE_REPARSE_POINT.0 == 0 {
+        return None;
+    }
+    let wide: Vec<u16> = OsStr::new(path)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut data = WIN32_FIND_DATAW::default();
+    // SAFETY: `wide` is NUL-terminated and `data` outlives the call.
+    let handle = unsafe { FindFirstFileW(PCWSTR(wide.as_ptr()), &mut data) }.ok()?;
+    // SAFETY: `handle` came from a successful FindFirstFileW.
+    let _ = unsafe { FindClose(handle) };
+    // For reparse points, dwReserved0 holds the reparse tag.
+    Some(data.dwReserved0)
 }
 
 #[cfg(not(windows))]
-pub fn enumerate_mft(_volume: &VolumeInfo) -> Result<Vec<FileMeta>, NtfsError> {
+pub fn enumerate_mft_capped(
+    _volume: &VolumeInfo,
+    _max_entries: Option<u64>,
+    _only_modified_since: Option<Timestamp>,
+) -> Result<MftEnumeration, NtfsError> {
     Err(NtfsError::NotSupported)
 }
 
 /// Tail the USN journal for a volume and emit file events from the given cursor.
+///
+/// A cursor with `journal_id` 0 has not read this journal yet: it is pointed at the journal's
+/// current end and no events are returned. When the journal was recreated (different id) or
+/// has purged records past the cursor, [`NtfsError::GapDetected`] tells the caller to rescan
+/// and carries the cursor to resume from. Reads `config.chunk_size` bytes at a time and
+/// decodes at most `config.max_records_per_tick` records; the returned cursor resumes after
+/// the last one.
+#[cfg(windows)]
+pub fn tail_usn(
+    volume: &VolumeInfo,
+    cursor: JournalCursor,
+    config: &ReaderConfig,
+) -> Result<(Vec<FileEvent>, JournalCursor), NtfsError> {
+    use std::collections::HashMap;
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::{
+        ERROR_JOURNAL_ENTRY_DELETED, ERROR_JOURNAL_NOT_ACTIVE, HANDLE,
+    };
+    use windows::Win32::System::IO::DeviceIoControl;
+    use windows::Win32::System::Ioctl::{
+        FSCTL_QUERY_USN_JOURNAL, FSCTL_READ_USN_JOURNAL, READ_USN_JOURNAL_DATA_V1,
+        USN_JOURNAL_DATA_V0,
+    };
+
+    let owned = open_volume_handle(volume)?;
+    let handle = HANDLE(owned.as_raw_handle() as isize);
+
+    let mut journal = USN_JOURNAL_DATA_V0::default();
+    let mut returned = 0u32;
+    // SAFETY: `handle` is open for the duration of the call; the output is a properly
+    // sized USN_JOURNAL_DATA_V0.
+    unsafe {
+        DeviceIoControl(
+            handle,
+            FSCTL_QUERY_USN_JOURNAL,
+            None,
+            0,
+            Some(&mut journal as *mut _ as *mut _),
+            std::mem::size_of::<USN_JOURNAL_DATA_V0>() as u32,
+            Some(&mut returned),
+            None,
+        )
+    }
+    .map_err(|e| {
+        if e.code() == ERROR_JOURNAL_NOT_ACTIVE.to_hresult() {
+            NtfsError::Journal(format!("no USN journal on {}", volume.guid_path))
+        } else {
+            NtfsError::Journal(format!("FSCTL_QUERY_USN_JOURNAL: {e}"))
+        }
+    })?;
+
+    let restart = JournalCursor {
+        last_usn: journal.NextUsn as Usn,
+        journal_id: journal.UsnJournalID,
+    };
+    if cursor.journal_id == 0 {
+        return Ok((Vec::new(), restart));
+    }
+    if cursor.journal_id != journal.UsnJournalID || cursor.is_stale_against(journal.FirstUsn as Usn)
+    {
+        return Err(NtfsError::GapDetected { restart });
+    }
+
+    let mut buf = vec![0u64; config.chunk_size.max(4096) / 8];
+    let mut records = Vec::new();
+    let mut next = cursor;
+    while records.len() < config.max_records_per_tick {
+        let request = READ_USN_JOURNAL_DATA_V1 {
+            StartUsn: next.last_usn as i64,
+            ReasonMask: u32::MAX,
+            // One record per handle close, carrying every reason accumulated while open.
+            ReturnOnlyOnClose: 1,
+            Timeout: 0,
+            BytesToWaitFor: 0,
+            UsnJournalID: journal.UsnJournalID,
+            MinMajorVersion: 2,
+            MaxMajorVersion: 3,
+        };
+        // SAFETY: the input is a READ_USN_JOURNAL_DATA_V1 and the output buffer is 8-byte
+        // aligned and `buf.len() * 8` bytes long.
+        unsafe {
+            DeviceIoControl(
+                handle,
+                FSCTL_READ_USN_JOURNAL,
+                Some(&request as *const _ as *const _),
+                std::mem::size_of::<READ_USN_JOURNAL_DATA_V1>() as u32,
+                Some(buf.as_mut_ptr().cast()),
+                (buf.len() * 8) as u32,
+                Some(&mut returned),
+                None,
+            )
+        }
+        .map_err(|e| {
+            if e.code() == ERROR_JOURNAL_ENTRY_DELETED.to_hresult() {
+                NtfsError::GapDetected { restart }
+            } else {
+                NtfsError::Journal(format!("FSCTL_READ_USN_JOURNAL: {e}"))
+            }
+        })?;
+
+        // SAFETY: the kernel wrote `returned` bytes into `buf`.
+        let bytes =
+            unsafe { std::slice::from_raw_parts(buf.as_ptr().cast::<u8>(), returned as usize) };
+        if bytes.len() < 8 {
+            break;
+        }
+        let next_usn = u64::from_le_bytes(bytes[..8].try_into().unwrap_or_default());
+        let decoded =
+            journal::decode_records(&bytes[8..], config.max_records_per_tick - records.len())?;
+        let done = decoded.records.is_empty();
+        records.extend(decoded.records);
+        if let Some(resume) = decoded.resume_usn {
+            next.last_usn = resume;
+            break;
+        }
+        next.last_usn = next_usn;
+        if done {
+            break;
+        }
+    }
+
+    // Created and renamed entries get a path: their parent directory's, plus their name.
+    let mut parents: HashMap<u64, Option<String>> = HashMap::new();
+    let events = journal::events_from_records(volume.id, &records, |record| {
+        let parent = parents
+            .entry(record.parent_frn)
+            .or_insert_with(|| path_by_file_id(handle, record.parent_frn))
+            .as_deref()?;
+        Some(format!(
+            r"{}\{}",
+            parent.trim_end_matches('\\'),
+            record.name
+        ))
+    });
+    Ok((events, next))
+}
+
+/// Full DOS path of the file or directory with reference number `frn` on the volume open
+/// as `volume`, e.g. `C:\Users\me`.
+#[cfg(windows)]
+fn path_by_file_id(volume: windows::Win32::Foundation::HANDLE, frn: u64) -> Option<String> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::Storage::FileSystem::{
+        FILE_FLAG_BACKUP_SEMANTICS, FILE_ID_DESCRIPTOR, FILE_ID_DESCRIPTOR_0, FILE_NAME_NORMALIZED,
+        FILE_READ_ATTRIBUTES, FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, FileIdType,
+        GETFINALPATHNAMEBYHANDLE_FLAGS, GetFinalPathNameByHandleW, OpenFileById, VOLUME_NAME_DOS,
+    };
+
+    let id = FILE_ID_DESCRIPTOR {
+        dwSize: std::mem::size_of::<FILE_ID_DESCRIPTOR>() as u32,
+        Type: FileIdType,
+        Anonymous: FILE_ID_DESCRIPTOR_0 { FileId: frn as i64 },
+    };
+    // SAFETY: `volume` is an open volume handle and `id` outlives the call.
+    let file = unsafe {
+        OpenFileById(
+            volume,
+            &id,
+            FILE_READ_ATTRIBUTES.0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            None,
+            FILE_FLAG_BACKUP_SEMANTICS,
+        )
+    }
+    .ok()?;
+    let mut buf = vec![0u16; 1024];
+    // SAFETY: `file` is open and `buf` is writable; the handle is closed right after.
+    let len = unsafe {
+        let flags = GETFINALPATHNAMEBYHANDLE_FLAGS(FILE_NAME_NORMALIZED.0 | VOLUME_NAME_DOS.0);
+        let len = GetFinalPathNameByHandleW(file, &mut buf, flags);
+        let _ = CloseHandle(file);
+        len as usize
+    };
+    if len == 0 || len > buf.len() {
+        return None;
+    }
+    let path = String::from_utf16_lossy(&buf[..len]);
+    Some(path.strip_prefix(r"\\?\").unwrap_or(&path).to_string())
+}
+
+#[cfg(not(windows))]
 pub fn tail_usn(
     _volume: &VolumeInfo,
     _cursor: JournalCursor,
+    _config: &ReaderConfig,
 ) -> Result<(Vec<FileEvent>, JournalCursor), NtfsError> {
-    // TODO: connect to USN journal, read deltas, and return next cursor.
-    Ok((Vec::new(), _cursor))
+    Err(NtfsError::NotSupported)
 }
 
 /// Simple in-memory watcher useful for tests and higher-level components.
+///
+/// Events carry the USN they were journaled at; `tail_usn` returns those past the cursor and
+/// advances it, like a real journal read.
 pub struct InMemoryWatcher {
     vols: Vec<VolumeInfo>,
     mft: Vec<FileMeta>,
-    events: Vec<FileEvent>,
+    events: Vec<(Usn, FileEvent)>,
+    journal_id: u64,
 }
 
 impl InMemoryWatcher {
-    pub fn new(vols: Vec<VolumeInfo>, mft: Vec<FileMeta>, events: Vec<FileEvent>) -> Self {
-        Self { vols, mft, events }
+    pub fn new(vols: Vec<VolumeInfo>, mft: Vec<FileMeta>, events: Vec<(Usn, FileEvent)>) -> Self {
+        Self {
+            vols,
+            mft,
+            events,
+            journal_id: 0,
+        }
+    }
+
+    /// Journal id reported in returned cursors (default 0).
+    pub fn with_journal_id(mut self, journal_id: u64) -> Self {
+        self.journal_id = journal_id;
+        self
     }
 }
 
@@ -324,14 +894,29 @@ impl NtfsWatcher for InMemoryWatcher {
         _volume: &VolumeInfo,
         cursor: JournalCursor,
     ) -> Result<(Vec<FileEvent>, JournalCursor), NtfsError> {
-        Ok((self.events.clone(), cursor))
+        let mut last_usn = cursor.last_usn;
+        let events = self
+            .events
+            .iter()
+            .filter(|(usn, _)| *usn > cursor.last_usn)
+            .map(|(usn, event)| {
+                last_usn = last_usn.max(*usn);
+                event.clone()
+            })
+            .collect();
+        Ok((
+            events,
+            JournalCursor {
+                last_usn,
+                journal_id: self.journal_id,
+            },
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use core_types::FileFlags;
 
     #[test]
     fn doc_key_round_trip() {
@@ -341,6 +926,17 @@ mod tests {
         assert_eq!(frn, 1_234_567_890);
     }
 
+    #[test]
+    fn cursor_is_stale_once_the_journal_wraps_past_it() {
+        let cursor = JournalCursor {
+            last_usn: 4096,
+            journal_id: 7,
+        };
+        assert!(!cursor.is_stale_against(4096), "oldest record still unread");
+        assert!(!cursor.is_stale_against(1024));
+        assert!(cursor.is_stale_against(4097));
+    }
+
     #[test]
     fn reader_config_defaults_are_sane() {
         let cfg = ReaderConfig::default();
@@ -366,7 +962,7 @@ mod tests {
             0,
             FileFlags::empty(),
         )];
-        let events = vec![FileEvent::Deleted(DocKey::from_parts(1, 10))];
+        let events = vec![(8, FileEvent::Deleted(DocKey::from_parts(1, 10)))];
 
         let watcher = InMemoryWatcher::new(vols.clone(), mft.clone(), events.clone());
         assert_eq!(watcher.discover_volumes().unwrap().len(), vols.len());
@@ -385,6 +981,204 @@ mod tests {
             )
             .unwrap();
         assert_eq!(evs.len(), events.len());
-        assert_eq!(cur.last_usn, 0);
+        assert_eq!(cur.last_usn, 8);
+    }
+
+    #[test]
+    fn in_memory_watcher_drains_events_past_the_cursor() {
+        let vol = VolumeInfo {
+            id: 1,
+            guid_path: r"\\?\Volume{abc}\".to_string(),
+            drive_letters: vec!['C'],
+        };
+        let events = vec![
+            (100, FileEvent::Deleted(DocKey::from_parts(1, 10))),
+            (250, FileEvent::Deleted(DocKey::from_parts(1, 11))),
+            (300, FileEvent::Deleted(DocKey::from_parts(1, 12))),
+        ];
+        let watcher =
+            InMemoryWatcher::new(vec![vol.clone()], Vec::new(), events).with_journal_id(7);
+
+        let start = JournalCursor {
+            last_usn: 100,
+            journal_id: 7,
+        };
+        let (evs, cur) = watcher.tail_usn(&vol, start).unwrap();
+        assert_eq!(
+            evs,
+            vec![
+                FileEvent::Deleted(DocKey::from_parts(1, 11)),
+                FileEvent::Deleted(DocKey::from_parts(1, 12)),
+            ]
+        );
+        assert_eq!(
+            cur,
+            JournalCursor {
+                last_usn: 300,
+                journal_id: 7,
+            }
+        );
+
+        // Caught up: nothing new, and the cursor stays put.
+        let (evs, again) = watcher.tail_usn(&vol, cur).unwrap();
+        assert!(evs.is_empty());
+        assert_eq!(again, cur);
+    }
+
+    #[test]
+    fn storage_attributes_map_to_flags() {
+        // FILE_ATTRIBUTE_ARCHIVE | COMPRESSED | SPARSE_FILE
+        let flags = attribute_flags(0x0020 | 0x0800 | 0x0200);
+        assert_eq!(flags, FileFlags::COMPRESSED | FileFlags::SPARSE);
+        assert_eq!(attribute_flags(0x4000), FileFlags::ENCRYPTED);
+        assert!(attribute_flags(0x0080).is_empty());
+    }
+
+    #[test]
+    fn mount_point_tag_sets_mount_point_flag() {
+        let flags = reparse_flags(Some(IO_REPARSE_TAG_MOUNT_POINT));
+        assert!(flags.contains(FileFlags::REPARSE | FileFlags::MOUNT_POINT));
+
+        // IO_REPARSE_TAG_SYMLINK is a reparse point but not a mount point.
+        let symlink = reparse_flags(Some(0xA000_000C));
+        assert!(symlink.contains(FileFlags::REPARSE));
+        assert!(!symlink.contains(FileFlags::MOUNT_POINT));
+
+        assert!(reparse_flags(None).is_empty());
+    }
+
+    #[test]
+    fn capped_enumeration_stops_at_cap_and_reports_skipped() {
+        let vol = VolumeInfo {
+            id: 1,
+            guid_path: r"\\?\Volume{abc}\".to_string(),
+            drive_letters: vec!['C'],
+        };
+        let mft: Vec<FileMeta> = (0..10u64)
+            .map(|frn| {
+                FileMeta::new(
+                    DocKey::from_parts(1, 100 + frn),
+                    1,
+                    None,
+                    format!("f{frn}.txt"),
+                    None,
+                    1,
+                    0,
+                    0,
+                    FileFlags::empty(),
+                )
+            })
+            .collect();
+        let watcher = InMemoryWatcher::new(vec![vol.clone()], mft, Vec::new());
+
+        let capped = watcher.enumerate_mft_capped(&vol, Some(4), None).unwrap();
+        assert_eq!(capped.entries.len(), 4);
+        assert_eq!(capped.skipped, 6);
+        assert_eq!(capped.resume_frn, Some(104));
+
+        let all = watcher.enumerate_mft_capped(&vol, None, None).unwrap();
+        assert_eq!(all.entries.len(), 10);
+        assert_eq!(all.skipped, 0);
+        assert_eq!(all.resume_frn, None);
+    }
+
+    #[test]
+    fn streaming_enumeration_delivers_full_batches_then_the_remainder() {
+        let vol = VolumeInfo {
+            id: 1,
+            guid_path: r"\\?\Volume{abc}\".to_string(),
+            drive_letters: vec!['C'],
+        };
+        let mft: Vec<FileMeta> = (0..5u64)
+            .map(|frn| {
+                FileMeta::new(
+                    DocKey::from_parts(1, frn),
+                    1,
+                    None,
+                    format!("f{frn}.txt"),
+                    None,
+                    1,
+                    0,
+                    0,
+                    FileFlags::empty(),
+                )
+            })
+            .collect();
+        let watcher = InMemoryWatcher::new(vec![vol.clone()], mft, Vec::new());
+
+        let mut batches = Vec::new();
+        watcher
+            .enumerate_mft_streaming(&vol, 2, &mut |batch| {
+                batches.push(
+                    batch
+                        .iter()
+                        .map(|m| m.key.into_parts().1)
+                        .collect::<Vec<_>>(),
+                );
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(batches, vec![vec![0, 1], vec![2, 3], vec![4]]);
+
+        // A failing sink stops the walk; a zero batch size means one entry per batch.
+        let mut calls = 0;
+        let err = watcher.enumerate_mft_streaming(&vol, 0, &mut |_| {
+            calls += 1;
+            if calls == 2 {
+                return Err(NtfsError::Mft("index full".into()));
+            }
+            Ok(())
+        });
+        assert!(matches!(err, Err(NtfsError::Mft(_))));
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn modified_since_baseline_skips_older_files() {
+        let vol = VolumeInfo {
+            id: 1,
+            guid_path: r"\\?\Volume{abc}\".to_string(),
+            drive_letters: vec!['C'],
+        };
+        let entry = |frn: u64, modified: i64, flags: FileFlags| {
+            FileMeta::new(
+                DocKey::from_parts(1, frn),
+                1,
+                None,
+                format!("e{frn}"),
+                None,
+                1,
+                0,
+                modified,
+                flags,
+            )
+        };
+        let mft = vec![
+            entry(1, 1_000, FileFlags::empty()),
+            entry(2, 2_000, FileFlags::empty()),
+            entry(3, 3_000, FileFlags::empty()),
+            entry(4, 1_000, FileFlags::IS_DIR),
+        ];
+        let watcher = InMemoryWatcher::new(vec![vol.clone()], mft, Vec::new());
+        let frns = |e: &MftEnumeration| -> Vec<u64> {
+            e.entries.iter().map(|m| m.key.into_parts().1).collect()
+        };
+
+        let since = watcher
+            .enumerate_mft_capped(&vol, None, Some(2_000))
+            .unwrap();
+        assert_eq!(frns(&since), vec![2, 3, 4], "old directory is kept");
+        assert_eq!(since.unchanged, 1);
+
+        // Skipped files don't use up the cap.
+        let capped = watcher
+            .enumerate_mft_capped(&vol, Some(1), Some(3_000))
+            .unwrap();
+        assert_eq!(frns(&capped), vec![3]);
+        assert_eq!((capped.unchanged, capped.skipped), (2, 1));
+
+        let all = watcher.enumerate_mft_capped(&vol, None, None).unwrap();
+        assert_eq!(frns(&all), vec![1, 2, 3, 4]);
+        assert_eq!(all.unchanged, 0);
     }
 }
@@ -0,0 +1,69 @@
+//! NTFS object ID lookup for `indexing.index_object_ids`.
+
+use std::io;
+
+/// Object ID (GUID bytes) of `path` via `FSCTL_GET_OBJECT_ID`: `Ok(None)` if the file has
+/// none, `Err` if it can't be opened or queried. Never assigns an ID to a file lacking one.
+#[cfg(windows)]
+pub fn file_object_id(path: &str) -> io::Result<Option<[u8; 16]>> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::Foundation::{CloseHandle, ERROR_FILE_NOT_FOUND, ERROR_OBJECT_NOT_FOUND};
+    use windows::Win32::Storage::FileSystem::{
+        CreateFileW, FILE_FLAG_BACKUP_SEMANTICS, FILE_SHARE_DELETE, FILE_SHARE_READ,
+        FILE_SHARE_WRITE, OPEN_EXISTING,
+    };
+    use windows::Win32::System::IO::DeviceIoControl;
+    use windows::Win32::System::Ioctl::{FILE_OBJECTID_BUFFER, FSCTL_GET_OBJECT_ID};
+    use windows::core::PCWSTR;
+
+    let wide: Vec<u16> = OsStr::new(path).encode_wide().chain(Some(0)).collect();
+    // SAFETY: `wide` is NUL-terminated; zero access rights suffice for this FSCTL, and backup
+    // semantics let directories be opened too.
+    let handle = unsafe {
+        CreateFileW(
+            PCWSTR(wide.as_ptr()),
+            0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE | FILE_SHARE_DELETE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAG_BACKUP_SEMANTICS,
+            None,
+        )
+    }
+    .map_err(io::Error::from)?;
+
+    let mut buffer = FILE_OBJECTID_BUFFER::default();
+    let mut returned = 0u32;
+    // SAFETY: the output buffer is a properly sized FILE_OBJECTID_BUFFER.
+    let result = unsafe {
+        DeviceIoControl(
+            handle,
+            FSCTL_GET_OBJECT_ID,
+            None,
+            0,
+            Some(&mut buffer as *mut _ as *mut _),
+            std::mem::size_of::<FILE_OBJECTID_BUFFER>() as u32,
+            Some(&mut returned),
+            None,
+        )
+    };
+    unsafe {
+        let _ = CloseHandle(handle);
+    }
+    match result.map_err(io::Error::from) {
+        Ok(()) => Ok(Some(buffer.ObjectId)),
+        Err(e)
+            if e.raw_os_error() == Some(ERROR_FILE_NOT_FOUND.0 as i32)
+                || e.raw_os_error() == Some(ERROR_OBJECT_NOT_FOUND.0 as i32) =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(windows))]
+pub fn file_object_id(_path: &str) -> io::Result<Option<[u8; 16]>> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
@@ -0,0 +1,50 @@
+//! File owner lookup for `indexing.index_owner`.
+
+/// Owner SID of `path` in string form (`S-1-5-21-...`), or `None` if the security
+/// descriptor can't be read.
+#[cfg(windows)]
+pub fn file_owner(path: &str) -> Option<String> {
+    use std::ffi::OsStr;
+    use std::os::windows::ffi::OsStrExt;
+    use windows::Win32::Foundation::{HLOCAL, LocalFree, PSID};
+    use windows::Win32::Security::Authorization::{
+        ConvertSidToStringSidW, GetNamedSecurityInfoW, SE_FILE_OBJECT,
+    };
+    use windows::Win32::Security::{OWNER_SECURITY_INFORMATION, PSECURITY_DESCRIPTOR};
+    use windows::core::{PCWSTR, PWSTR};
+
+    let wide: Vec<u16> = OsStr::new(path).encode_wide().chain(Some(0)).collect();
+    let mut owner = PSID::default();
+    let mut sd = PSECURITY_DESCRIPTOR::default();
+    // SAFETY: `wide` is NUL-terminated; `owner` points into `sd`, which is freed below.
+    unsafe {
+        GetNamedSecurityInfoW(
+            PCWSTR(wide.as_ptr()),
+            SE_FILE_OBJECT,
+            OWNER_SECURITY_INFORMATION,
+            Some(&mut owner),
+            None,
+            None,
+            None,
+            &mut sd,
+        )
+    }
+    .ok()?;
+
+    let mut sid = PWSTR::null();
+    let result = unsafe { ConvertSidToStringSidW(owner, &mut sid) }
+        .ok()
+        .and_then(|_| unsafe { sid.to_string() }.ok());
+    unsafe {
+        if !sid.is_null() {
+            let _ = LocalFree(HLOCAL(sid.0 as _));
+        }
+        let _ = LocalFree(HLOCAL(sd.0));
+    }
+    result
+}
+
+#[cfg(not(windows))]
+pub fn file_owner(_path: &str) -> Option<String> {
+    None
+}
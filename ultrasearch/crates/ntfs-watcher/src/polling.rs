@@ -0,0 +1,289 @@
+//! Polling fallback for volumes where the USN journal cannot be read.
+//!
+//! When the journal is disabled, the volume is not NTFS, or the process lacks privilege,
+//! [`PollingWatcher`] periodically re-scans a configured set of directories and turns the
+//! difference between successive snapshots into [`FileEvent`]s.
+
+use crate::{FileEvent, JournalCursor, NtfsError, NtfsWatcher, VolumeInfo};
+use core_types::{DocKey, FileFlags, FileMeta, Timestamp, VolumeId};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+/// Volume id reserved for documents discovered by polling.
+pub const POLL_VOLUME_ID: VolumeId = VolumeId::MAX;
+
+/// What a scan records about one path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollEntry {
+    pub size: u64,
+    pub modified: Timestamp,
+    pub is_dir: bool,
+}
+
+/// Full paths under the watched roots, in sorted order.
+pub type Snapshot = BTreeMap<String, PollEntry>;
+
+/// Stable key for a polled path. There is no FRN to use, so the path itself is hashed
+/// (FNV-1a); the same path maps to the same document across scans and restarts.
+pub fn path_key(volume: VolumeId, path: &str) -> DocKey {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in path.as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    DocKey::from_parts(volume, hash)
+}
+
+/// Events that turn `old` into `new`: deletions, then creations, then modifications.
+/// Directories only produce Created/Deleted; their mtime moves with every child change.
+pub fn diff_snapshots(volume: VolumeId, old: &Snapshot, new: &Snapshot) -> Vec<FileEvent> {
+    let mut deleted = Vec::new();
+    let mut created = Vec::new();
+    let mut modified = Vec::new();
+
+    for path in old.keys() {
+        if !new.contains_key(path) {
+            deleted.push(FileEvent::Deleted(path_key(volume, path)));
+        }
+    }
+    for (path, entry) in new {
+        match old.get(path) {
+            None => created.push(FileEvent::Created(entry_meta(volume, path, entry))),
+            Some(prev) if prev.is_dir != entry.is_dir => {
+                deleted.push(FileEvent::Deleted(path_key(volume, path)));
+                created.push(FileEvent::Created(entry_meta(volume, path, entry)));
+            }
+            Some(prev) if !entry.is_dir && prev != entry => {
+                modified.push(FileEvent::Modified {
+                    doc: path_key(volume, path),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+
+    deleted.extend(created);
+    deleted.extend(modified);
+    deleted
+}
+
+fn entry_meta(volume: VolumeId, path: &str, entry: &PollEntry) -> FileMeta {
+    let p = Path::new(path);
+    let name = p
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string());
+    let parent = p
+        .parent()
+        .map(|parent| path_key(volume, &parent.to_string_lossy()));
+    let flags = if entry.is_dir {
+        FileFlags::IS_DIR
+    } else {
+        FileFlags::empty()
+    };
+    FileMeta::new(
+        path_key(volume, path),
+        volume,
+        parent,
+        name,
+        Some(path.to_string()),
+        entry.size,
+        0,
+        entry.modified,
+        flags,
+    )
+}
+
+/// Walk `roots` recursively without following symlinks. Unreadable entries are skipped.
+pub fn scan_dirs(roots: &[PathBuf]) -> Snapshot {
+    let mut snapshot = Snapshot::new();
+    let mut stack: Vec<PathBuf> = roots.to_vec();
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(meta) = entry.path().symlink_metadata() else {
+                continue;
+            };
+            let path = entry.path();
+            let is_dir = meta.is_dir();
+            let modified = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs() as Timestamp)
+                .unwrap_or(0);
+            snapshot.insert(
+                path.to_string_lossy().into_owned(),
+                PollEntry {
+                    size: if is_dir { 0 } else { meta.len() },
+                    modified,
+                    is_dir,
+                },
+            );
+            if is_dir {
+                stack.push(path);
+            }
+        }
+    }
+    snapshot
+}
+
+/// [`NtfsWatcher`] that synthesizes events by re-scanning directories.
+///
+/// `enumerate_mft` takes the baseline snapshot; each `tail_usn` call re-scans and returns
+/// the diff against the previous scan. The cursor's `last_usn` counts scans.
+pub struct PollingWatcher {
+    volume: VolumeInfo,
+    roots: Vec<PathBuf>,
+    snapshot: Mutex<Snapshot>,
+}
+
+impl PollingWatcher {
+    pub fn new(roots: Vec<PathBuf>) -> Self {
+        Self {
+            volume: VolumeInfo {
+                id: POLL_VOLUME_ID,
+                guid_path: "poll".to_string(),
+                drive_letters: Vec::new(),
+            },
+            roots,
+            snapshot: Mutex::new(Snapshot::new()),
+        }
+    }
+
+    pub fn volume(&self) -> &VolumeInfo {
+        &self.volume
+    }
+
+    /// Replace the stored snapshot with `next` and return the events between them.
+    pub fn advance(&self, next: Snapshot) -> Vec<FileEvent> {
+        let mut current = self.snapshot.lock().unwrap_or_else(|e| e.into_inner());
+        let events = diff_snapshots(self.volume.id, &current, &next);
+        *current = next;
+        events
+    }
+
+    /// Metadata for `doc` as of the latest scan, e.g. to act on a `Modified` event.
+    pub fn current_meta(&self, doc: DocKey) -> Option<FileMeta> {
+        let current = self.snapshot.lock().unwrap_or_else(|e| e.into_inner());
+        current
+            .iter()
+            .find(|(path, _)| path_key(self.volume.id, path) == doc)
+            .map(|(path, entry)| entry_meta(self.volume.id, path, entry))
+    }
+}
+
+impl NtfsWatcher for PollingWatcher {
+    fn discover_volumes(&self) -> Result<Vec<VolumeInfo>, NtfsError> {
+        Ok(vec![self.volume.clone()])
+    }
+
+    fn enumerate_mft(&self, volume: &VolumeInfo) -> Result<Vec<FileMeta>, NtfsError> {
+        let snapshot = scan_dirs(&self.roots);
+        let metas = snapshot
+            .iter()
+            .map(|(path, entry)| entry_meta(volume.id, path, entry))
+            .collect();
+        *self.snapshot.lock().unwrap_or_else(|e| e.into_inner()) = snapshot;
+        Ok(metas)
+    }
+
+    fn tail_usn(
+        &self,
+        _volume: &VolumeInfo,
+        cursor: JournalCursor,
+    ) -> Result<(Vec<FileEvent>, JournalCursor), NtfsError> {
+        let events = self.advance(scan_dirs(&self.roots));
+        let next = JournalCursor {
+            last_usn: cursor.last_usn + 1,
+            journal_id: cursor.journal_id,
+        };
+        Ok((events, next))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(size: u64, modified: Timestamp) -> PollEntry {
+        PollEntry {
+            size,
+            modified,
+            is_dir: false,
+        }
+    }
+
+    #[test]
+    fn successive_snapshots_yield_created_deleted_modified() {
+        let dir = PollEntry {
+            size: 0,
+            modified: 1,
+            is_dir: true,
+        };
+        let first: Snapshot = [
+            ("/w/docs".to_string(), dir),
+            ("/w/docs/a.txt".to_string(), file(10, 100)),
+            ("/w/docs/b.txt".to_string(), file(20, 100)),
+            ("/w/docs/c.txt".to_string(), file(30, 100)),
+        ]
+        .into_iter()
+        .collect();
+        let second: Snapshot = [
+            ("/w/docs".to_string(), PollEntry { modified: 2, ..dir }),
+            ("/w/docs/a.txt".to_string(), file(10, 100)),
+            ("/w/docs/b.txt".to_string(), file(25, 200)),
+            ("/w/docs/d.txt".to_string(), file(40, 200)),
+        ]
+        .into_iter()
+        .collect();
+
+        let watcher = PollingWatcher::new(Vec::new());
+        assert_eq!(watcher.advance(first).len(), 4);
+        let events = watcher.advance(second);
+
+        assert_eq!(events.len(), 3, "{events:?}");
+        assert_eq!(
+            events[0],
+            FileEvent::Deleted(path_key(POLL_VOLUME_ID, "/w/docs/c.txt"))
+        );
+        match &events[1] {
+            FileEvent::Created(meta) => {
+                assert_eq!(meta.key, path_key(POLL_VOLUME_ID, "/w/docs/d.txt"));
+                assert_eq!(meta.name, "d.txt");
+                assert_eq!(meta.path.as_deref(), Some("/w/docs/d.txt"));
+                assert_eq!(meta.parent, Some(path_key(POLL_VOLUME_ID, "/w/docs")));
+                assert_eq!(meta.size, 40);
+            }
+            other => panic!("expected Created, got {other:?}"),
+        }
+        assert_eq!(
+            events[2],
+            FileEvent::Modified {
+                doc: path_key(POLL_VOLUME_ID, "/w/docs/b.txt")
+            }
+        );
+        let b = watcher
+            .current_meta(path_key(POLL_VOLUME_ID, "/w/docs/b.txt"))
+            .unwrap();
+        assert_eq!((b.size, b.modified), (25, 200));
+    }
+
+    #[test]
+    fn scan_dirs_walks_nested_directories() {
+        let root = std::env::temp_dir().join(format!("us-poll-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+        std::fs::write(root.join("sub").join("x.txt"), b"hello").unwrap();
+
+        let snapshot = scan_dirs(std::slice::from_ref(&root));
+        let file = root.join("sub").join("x.txt");
+        assert_eq!(snapshot[&*file.to_string_lossy()].size, 5);
+        assert!(snapshot[&*root.join("sub").to_string_lossy()].is_dir);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,151 @@
+//! MFT file record parsing.
+//!
+//! USN enumeration (`FSCTL_ENUM_USN_DATA`) reports names and attributes but no timestamps.
+//! Those live in each file's `$STANDARD_INFORMATION` attribute, read from the raw `FILE`
+//! record that `FSCTL_GET_NTFS_FILE_RECORD` returns. [`standard_times`] pulls the creation
+//! and modification times out of such a record.
+
+use core_types::Timestamp;
+
+/// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01.
+pub const FILETIME_UNIX_OFFSET: i64 = 11_644_473_600;
+
+const ATTR_STANDARD_INFORMATION: u32 = 0x10;
+const ATTR_END: u32 = 0xFFFF_FFFF;
+const SECTOR_SIZE: usize = 512;
+
+/// Convert a Windows FILETIME (100 ns ticks since 1601-01-01 UTC) to Unix seconds.
+pub fn filetime_to_unix(ft: u64) -> Timestamp {
+    (ft / 10_000_000) as i64 - FILETIME_UNIX_OFFSET
+}
+
+/// Timestamps from a file's `$STANDARD_INFORMATION`, as Unix seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StandardTimes {
+    pub created: Timestamp,
+    pub modified: Timestamp,
+}
+
+fn u16_at(buf: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(buf.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(buf: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(buf.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(buf: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(buf.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Creation and modification time of the `FILE` record `record`. `None` when the record is
+/// malformed, fails its update-sequence check, or has no resident `$STANDARD_INFORMATION`.
+pub fn standard_times(record: &[u8]) -> Option<StandardTimes> {
+    if record.get(..4)? != b"FILE" {
+        return None;
+    }
+    let record = apply_fixups(record)?;
+    let mut at = usize::from(u16_at(&record, 0x14)?);
+    loop {
+        let kind = u32_at(&record, at)?;
+        if kind == ATTR_END {
+            return None;
+        }
+        let len = u32_at(&record, at + 4)? as usize;
+        if len == 0 {
+            return None;
+        }
+        // Resident attributes only; $STANDARD_INFORMATION always is.
+        if kind == ATTR_STANDARD_INFORMATION && *record.get(at + 8)? == 0 {
+            let content = at + usize::from(u16_at(&record, at + 0x14)?);
+            return Some(StandardTimes {
+                created: filetime_to_unix(u64_at(&record, content)?),
+                modified: filetime_to_unix(u64_at(&record, content + 8)?),
+            });
+        }
+        at += len;
+    }
+}
+
+/// `record` with the last two bytes of each sector restored from the update sequence array.
+fn apply_fixups(record: &[u8]) -> Option<Vec<u8>> {
+    let offset = usize::from(u16_at(record, 4)?);
+    let count = usize::from(u16_at(record, 6)?);
+    let mut fixed = record.to_vec();
+    let check = record.get(offset..offset + 2)?;
+    for i in 1..count {
+        let end = i * SECTOR_SIZE - 2;
+        if end + 2 > record.len() {
+            break;
+        }
+        if &record[end..end + 2] != check {
+            return None;
+        }
+        fixed[end..end + 2].copy_from_slice(record.get(offset + 2 * i..offset + 2 * i + 2)?);
+    }
+    Some(fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filetime_converts_from_the_1601_epoch() {
+        assert_eq!(
+            filetime_to_unix(FILETIME_UNIX_OFFSET as u64 * 10_000_000),
+            0
+        );
+        assert_eq!(filetime_to_unix(0), -FILETIME_UNIX_OFFSET);
+        // 2024-01-01T00:00:00Z, plus sub-second ticks that are truncated.
+        assert_eq!(
+            filetime_to_unix(133_485_408_000_000_000 + 9_999_999),
+            1_704_067_200
+        );
+    }
+
+    #[test]
+    fn standard_information_times_come_from_the_file_record() {
+        let created = 133_485_408_000_000_000u64; // 2024-01-01T00:00:00Z
+        let modified = created + 86_400 * 10_000_000;
+        let mut rec = vec![0u8; 1024];
+        rec[..4].copy_from_slice(b"FILE");
+        rec[4..6].copy_from_slice(&0x30u16.to_le_bytes()); // update sequence offset
+        rec[6..8].copy_from_slice(&3u16.to_le_bytes()); // 1 + one entry per sector
+        rec[0x14..0x16].copy_from_slice(&0x38u16.to_le_bytes()); // first attribute
+        // Update sequence number 0x0007; the sectors' real tail bytes are 0xAAAA/0xBBBB.
+        rec[0x30..0x36].copy_from_slice(&[0x07, 0x00, 0xAA, 0xAA, 0xBB, 0xBB]);
+        rec[510..512].copy_from_slice(&[0x07, 0x00]);
+        rec[1022..1024].copy_from_slice(&[0x07, 0x00]);
+
+        // A $FILE_NAME-sized filler attribute first, then $STANDARD_INFORMATION.
+        let si = 0x38 + 0x20;
+        rec[0x38..0x3C].copy_from_slice(&0x30u32.to_le_bytes());
+        rec[0x3C..0x40].copy_from_slice(&0x20u32.to_le_bytes());
+        rec[si..si + 4].copy_from_slice(&ATTR_STANDARD_INFORMATION.to_le_bytes());
+        rec[si + 4..si + 8].copy_from_slice(&0x60u32.to_le_bytes());
+        rec[si + 0x14..si + 0x16].copy_from_slice(&0x18u16.to_le_bytes());
+        rec[si + 0x18..si + 0x20].copy_from_slice(&created.to_le_bytes());
+        rec[si + 0x20..si + 0x28].copy_from_slice(&modified.to_le_bytes());
+        rec[si + 0x60..si + 0x64].copy_from_slice(&ATTR_END.to_le_bytes());
+
+        assert_eq!(
+            standard_times(&rec),
+            Some(StandardTimes {
+                created: 1_704_067_200,
+                modified: 1_704_153_600,
+            })
+        );
+
+        // A torn write leaves a sector tail that doesn't match the update sequence number.
+        let mut torn = rec.clone();
+        torn[1022] = 0x08;
+        assert_eq!(standard_times(&torn), None);
+
+        // Without $STANDARD_INFORMATION there is nothing to report.
+        let mut missing = rec.clone();
+        missing[si..si + 4].copy_from_slice(&0x80u32.to_le_bytes());
+        assert_eq!(standard_times(&missing), None);
+        assert_eq!(standard_times(b"BAAD"), None);
+    }
+}
@@ -0,0 +1,159 @@
+//! Foreground-application detection for `scheduler.follow_foreground`.
+//!
+//! Files the user is working on right now should be indexed first. A [`ForegroundSource`]
+//! reports the working directory of the application owning the foreground window; the
+//! service moves pending jobs under that directory to the front of its queue.
+
+use std::path::{Path, PathBuf};
+
+/// Reports the directory the foreground application is working in.
+pub trait ForegroundSource: Send + Sync {
+    /// Working directory of the foreground window's process, or `None` when there is no
+    /// foreground window or it can't be inspected.
+    fn foreground_dir(&mut self) -> Option<PathBuf>;
+}
+
+/// Closures work as sources, which keeps tests free of OS state.
+impl<F> ForegroundSource for F
+where
+    F: FnMut() -> Option<PathBuf> + Send + Sync,
+{
+    fn foreground_dir(&mut self) -> Option<PathBuf> {
+        self()
+    }
+}
+
+/// Reads the foreground window's process from the OS (Windows only; `None` elsewhere, and
+/// for a service without access to the interactive desktop).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsForeground;
+
+impl ForegroundSource for OsForeground {
+    fn foreground_dir(&mut self) -> Option<PathBuf> {
+        foreground_cwd()
+    }
+}
+
+/// Whether `path` lies in `dir` or below it. Case-insensitive, accepting `\` or `/`.
+pub fn is_within(path: &Path, dir: &Path) -> bool {
+    fn components(p: &Path) -> Vec<String> {
+        p.to_string_lossy()
+            .split(['\\', '/'])
+            .filter(|c| !c.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    }
+    let dir = components(dir);
+    !dir.is_empty() && components(path).starts_with(&dir)
+}
+
+#[cfg(target_os = "windows")]
+fn foreground_cwd() -> Option<PathBuf> {
+    use std::ffi::c_void;
+    use std::os::windows::ffi::OsStringExt;
+    use windows::Wdk::System::Threading::{NtQueryInformationProcess, ProcessBasicInformation};
+    use windows::Win32::Foundation::{CloseHandle, HANDLE, UNICODE_STRING};
+    use windows::Win32::System::Diagnostics::Debug::ReadProcessMemory;
+    use windows::Win32::System::Threading::{
+        OpenProcess, PEB, PROCESS_BASIC_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION,
+        PROCESS_VM_READ,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{GetForegroundWindow, GetWindowThreadProcessId};
+
+    // `RTL_USER_PROCESS_PARAMETERS.CurrentDirectory.DosPath`; the public struct omits it.
+    #[cfg(target_pointer_width = "64")]
+    const CURRENT_DIRECTORY_OFFSET: usize = 0x38;
+    #[cfg(target_pointer_width = "32")]
+    const CURRENT_DIRECTORY_OFFSET: usize = 0x24;
+
+    unsafe fn read<T>(process: HANDLE, addr: *const c_void, out: *mut T) -> Option<()> {
+        // SAFETY: the caller passes a valid handle with PROCESS_VM_READ and a writable `out`.
+        unsafe { ReadProcessMemory(process, addr, out.cast(), std::mem::size_of::<T>(), None) }.ok()
+    }
+
+    // SAFETY: plain Win32 calls; every out-parameter is a live local of the expected size,
+    // and the process handle is closed before returning.
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.0 == 0 {
+            return None;
+        }
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        if pid == 0 {
+            return None;
+        }
+        let process = OpenProcess(
+            PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_VM_READ,
+            false,
+            pid,
+        )
+        .ok()?;
+
+        let cwd = (|| {
+            let mut info = PROCESS_BASIC_INFORMATION::default();
+            NtQueryInformationProcess(
+                process,
+                ProcessBasicInformation,
+                (&mut info as *mut PROCESS_BASIC_INFORMATION).cast(),
+                std::mem::size_of::<PROCESS_BASIC_INFORMATION>() as u32,
+                std::ptr::null_mut(),
+            )
+            .ok()
+            .ok()?;
+            let mut peb = std::mem::zeroed::<PEB>();
+            read(process, info.PebBaseAddress.cast(), &mut peb)?;
+            let mut dos_path = UNICODE_STRING::default();
+            let params = peb.ProcessParameters.cast::<u8>();
+            read(
+                process,
+                params.add(CURRENT_DIRECTORY_OFFSET).cast(),
+                &mut dos_path,
+            )?;
+            let mut buf = vec![0u16; usize::from(dos_path.Length) / 2];
+            if buf.is_empty() {
+                return None;
+            }
+            ReadProcessMemory(
+                process,
+                dos_path.Buffer.0.cast(),
+                buf.as_mut_ptr().cast(),
+                buf.len() * 2,
+                None,
+            )
+            .ok()?;
+            Some(PathBuf::from(std::ffi::OsString::from_wide(&buf)))
+        })();
+        let _ = CloseHandle(process);
+        cwd
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn foreground_cwd() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn within_matches_the_subtree_case_insensitively() {
+        let dir = Path::new(r"C:\Users\Me\Projects\app\");
+        assert!(is_within(
+            Path::new(r"c:\users\me\projects\APP\src\main.rs"),
+            dir
+        ));
+        assert!(is_within(Path::new("C:/Users/Me/Projects/app/a.txt"), dir));
+        assert!(!is_within(
+            Path::new(r"C:\Users\Me\Projects\app2\a.txt"),
+            dir
+        ));
+        assert!(!is_within(Path::new(r"C:\Users\Me\a.txt"), dir));
+        assert!(!is_within(Path::new(r"C:\a.txt"), Path::new("")));
+
+        let mut source = || Some(PathBuf::from(r"D:\work"));
+        assert_eq!(source.foreground_dir(), Some(PathBuf::from(r"D:\work")));
+    }
+}
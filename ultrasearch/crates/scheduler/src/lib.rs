@@ -2,15 +2,20 @@
 //! small policy helpers for background work. The service crate orchestrates
 //! execution; this crate keeps the decision logic testable and self-contained.
 
+pub mod foreground;
 pub mod idle;
 pub mod metrics;
 pub mod policy;
+pub mod quiet_hours;
 
+pub use foreground::{ForegroundSource, OsForeground};
 pub use idle::{IdleSample, IdleState, IdleTracker};
 pub use metrics::{SystemLoad, SystemLoadSampler};
 pub use policy::adaptive::AdaptivePolicy;
+pub use quiet_hours::{QuietHours, is_within_allowed_window};
 
 use core_types::DocKey;
+pub use core_types::config::PowerProfile;
 use std::collections::VecDeque;
 use std::time::{Duration, Instant};
 
@@ -138,24 +143,164 @@ pub fn select_jobs(
     selected
 }
 
+/// Why a category of work is held back on a tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockReason {
+    /// The user is not idle long enough (metadata needs warm idle, content deep idle).
+    NotIdle(IdleState),
+    CpuHigh {
+        percent: f32,
+        max: f32,
+    },
+    DiskBusy {
+        bytes_per_sec: u64,
+    },
+    /// A full-screen app is active and `power_save_mode` is on.
+    GameMode,
+    /// On battery and the power profile doesn't allow this work.
+    OnBattery {
+        percent: Option<u8>,
+    },
+    /// Outside `scheduler.quiet_hours`.
+    QuietHours,
+}
+
+impl BlockReason {
+    /// Stable snake_case identifier for structured output.
+    pub fn code(&self) -> &'static str {
+        match self {
+            BlockReason::NotIdle(_) => "not_idle",
+            BlockReason::CpuHigh { .. } => "cpu_high",
+            BlockReason::DiskBusy { .. } => "disk_busy",
+            BlockReason::GameMode => "game_mode",
+            BlockReason::OnBattery { .. } => "on_battery",
+            BlockReason::QuietHours => "quiet_hours",
+        }
+    }
+}
+
+impl std::fmt::Display for BlockReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockReason::NotIdle(state) => write!(f, "user idle state is {state:?}"),
+            BlockReason::CpuHigh { percent, max } => {
+                write!(f, "CPU at {percent:.1}% (limit {max:.1}%)")
+            }
+            BlockReason::DiskBusy { bytes_per_sec } => {
+                write!(f, "disk busy ({bytes_per_sec} bytes/s)")
+            }
+            BlockReason::GameMode => f.write_str("full-screen app active"),
+            BlockReason::OnBattery { percent: Some(pct) } => write!(f, "on battery ({pct}%)"),
+            BlockReason::OnBattery { percent: None } => f.write_str("on battery"),
+            BlockReason::QuietHours => f.write_str("outside allowed indexing hours"),
+        }
+    }
+}
+
 /// Basic policy for running metadata jobs.
 pub fn allow_metadata_jobs(idle: IdleState, load: SystemLoad, config: &SchedulerConfig) -> bool {
-    if config.power_save_mode && (load.on_battery || load.game_mode) {
-        return false;
-    }
-    matches!(idle, IdleState::WarmIdle | IdleState::DeepIdle)
-        && load.cpu_percent < config.cpu_metadata_max
-        && !load.disk_busy
+    metadata_blockers(idle, load, config).is_empty()
 }
 
 /// Basic policy for running content jobs (heavier work).
 pub fn allow_content_jobs(idle: IdleState, load: SystemLoad, config: &SchedulerConfig) -> bool {
-    if config.power_save_mode && (load.on_battery || load.game_mode) {
+    content_blockers(idle, load, config).is_empty()
+}
+
+/// Every reason metadata jobs can't run right now (empty = allowed).
+pub fn metadata_blockers(
+    idle: IdleState,
+    load: SystemLoad,
+    config: &SchedulerConfig,
+) -> Vec<BlockReason> {
+    let idle_ok = matches!(idle, IdleState::WarmIdle | IdleState::DeepIdle);
+    blockers(
+        JobCategory::Metadata,
+        idle,
+        idle_ok,
+        config.cpu_metadata_max,
+        load,
+        config,
+    )
+}
+
+/// Every reason content jobs can't run right now (empty = allowed).
+pub fn content_blockers(
+    idle: IdleState,
+    load: SystemLoad,
+    config: &SchedulerConfig,
+) -> Vec<BlockReason> {
+    let idle_ok = matches!(idle, IdleState::DeepIdle);
+    blockers(
+        JobCategory::Content,
+        idle,
+        idle_ok,
+        config.cpu_content_max,
+        load,
+        config,
+    )
+}
+
+fn blockers(
+    category: JobCategory,
+    idle: IdleState,
+    idle_ok: bool,
+    cpu_max: f32,
+    load: SystemLoad,
+    config: &SchedulerConfig,
+) -> Vec<BlockReason> {
+    let mut out = Vec::new();
+    if !power_allows(category, load, config) {
+        out.push(if config.power_save_mode && load.game_mode {
+            BlockReason::GameMode
+        } else {
+            BlockReason::OnBattery {
+                percent: load.battery_percent,
+            }
+        });
+    }
+    if !config.quiet_hours.allows_now() {
+        out.push(BlockReason::QuietHours);
+    }
+    if !idle_ok {
+        out.push(BlockReason::NotIdle(idle));
+    }
+    if load.cpu_percent >= cpu_max {
+        out.push(BlockReason::CpuHigh {
+            percent: load.cpu_percent,
+            max: cpu_max,
+        });
+    }
+    if load.disk_busy {
+        out.push(BlockReason::DiskBusy {
+            bytes_per_sec: load.disk_bytes_per_sec,
+        });
+    }
+    out
+}
+
+/// Power/game-mode gate for non-critical work.
+///
+/// `AcOnly` and `Always` decide battery behavior on their own; `Balanced` keeps the
+/// `power_save_mode` behavior, except that metadata may run on battery above the floor.
+fn power_allows(category: JobCategory, load: SystemLoad, config: &SchedulerConfig) -> bool {
+    if config.power_save_mode && load.game_mode {
         return false;
     }
-    matches!(idle, IdleState::DeepIdle)
-        && load.cpu_percent < config.cpu_content_max
-        && !load.disk_busy
+    if !load.on_battery {
+        return true;
+    }
+    match config.power_profile {
+        PowerProfile::AcOnly => false,
+        PowerProfile::Always => true,
+        PowerProfile::Balanced => {
+            !config.power_save_mode
+                || (category == JobCategory::Metadata
+                    && load
+                        .battery_percent
+                        .is_some_and(|pct| pct >= config.battery_floor_pct))
+        }
+    }
 }
 
 /// Static policy inputs used across scheduler beads.
@@ -172,6 +317,11 @@ pub struct SchedulerConfig {
     pub content_spawn_cooldown: Duration,
     pub content_batch_size: usize,
     pub power_save_mode: bool,
+    pub power_profile: PowerProfile,
+    /// Minimum battery percentage for metadata work on battery under `Balanced`.
+    pub battery_floor_pct: u8,
+    /// Outside these windows metadata/content jobs are paused (critical jobs still run).
+    pub quiet_hours: QuietHours,
 }
 
 impl Default for SchedulerConfig {
@@ -194,6 +344,9 @@ impl Default for SchedulerConfig {
             content_spawn_cooldown: Duration::from_secs(30),
             content_batch_size: 500,
             power_save_mode: true,
+            power_profile: PowerProfile::Balanced,
+            battery_floor_pct: 50,
+            quiet_hours: QuietHours::default(),
         }
     }
 }
@@ -216,12 +369,15 @@ pub fn should_spawn_content_worker(
     config: &SchedulerConfig,
     last_spawn: Option<Instant>,
 ) -> bool {
-    if config.power_save_mode && (load.on_battery || load.game_mode) {
+    if !power_allows(JobCategory::Content, load, config) {
         return false;
     }
     if backlog == 0 || load.disk_busy || load.cpu_percent >= config.cpu_content_max {
         return false;
     }
+    if !config.quiet_hours.allows_now() {
+        return false;
+    }
     if !matches!(idle, IdleState::DeepIdle) {
         return false;
     }
@@ -248,6 +404,7 @@ mod tests {
             disk_bytes_per_sec: 0,
             sample_duration: Duration::from_secs(1),
             on_battery: false,
+            battery_percent: None,
             game_mode: false,
         }
     }
@@ -259,6 +416,30 @@ mod tests {
         assert!(allow_content_jobs(IdleState::DeepIdle, load_ok(), &cfg));
     }
 
+    #[test]
+    fn blockers_name_every_failed_gate() {
+        let cfg = SchedulerConfig::default();
+        assert!(content_blockers(IdleState::DeepIdle, load_ok(), &cfg).is_empty());
+
+        let busy = SystemLoad {
+            cpu_percent: 95.0,
+            disk_busy: true,
+            disk_bytes_per_sec: 50_000_000,
+            ..load_ok()
+        };
+        let codes: Vec<_> = content_blockers(IdleState::WarmIdle, busy, &cfg)
+            .iter()
+            .map(BlockReason::code)
+            .collect();
+        assert_eq!(codes, vec!["not_idle", "cpu_high", "disk_busy"]);
+        assert_eq!(
+            metadata_blockers(IdleState::WarmIdle, busy, &cfg)
+                .first()
+                .map(ToString::to_string),
+            Some("CPU at 95.0% (limit 60.0%)".to_string())
+        );
+    }
+
     #[test]
     fn metadata_jobs_respect_cpu_and_disk() {
         let cfg = SchedulerConfig::default();
@@ -303,6 +484,68 @@ mod tests {
         assert!(allow_metadata_jobs(IdleState::DeepIdle, load, &cfg));
     }
 
+    #[test]
+    fn power_profiles_gate_battery_work() {
+        let on_battery = SystemLoad {
+            on_battery: true,
+            battery_percent: Some(80),
+            ..load_ok()
+        };
+        let low = SystemLoad {
+            battery_percent: Some(20),
+            ..on_battery
+        };
+        let profile = |power_profile| SchedulerConfig {
+            power_profile,
+            ..SchedulerConfig::default()
+        };
+
+        let balanced = profile(PowerProfile::Balanced);
+        assert!(allow_metadata_jobs(
+            IdleState::DeepIdle,
+            on_battery,
+            &balanced
+        ));
+        assert!(!allow_metadata_jobs(IdleState::DeepIdle, low, &balanced));
+        assert!(!allow_content_jobs(
+            IdleState::DeepIdle,
+            on_battery,
+            &balanced
+        ));
+
+        let ac_only = profile(PowerProfile::AcOnly);
+        assert!(!allow_metadata_jobs(
+            IdleState::DeepIdle,
+            on_battery,
+            &ac_only
+        ));
+        assert!(!allow_content_jobs(
+            IdleState::DeepIdle,
+            on_battery,
+            &ac_only
+        ));
+        let ac_only_no_save = SchedulerConfig {
+            power_save_mode: false,
+            ..ac_only
+        };
+        assert!(!allow_metadata_jobs(
+            IdleState::DeepIdle,
+            on_battery,
+            &ac_only_no_save
+        ));
+        assert!(allow_metadata_jobs(
+            IdleState::DeepIdle,
+            load_ok(),
+            &ac_only_no_save
+        ));
+
+        let always = profile(PowerProfile::Always);
+        for load in [on_battery, low, load_ok()] {
+            assert!(allow_metadata_jobs(IdleState::DeepIdle, load, &always));
+            assert!(allow_content_jobs(IdleState::DeepIdle, load, &always));
+        }
+    }
+
     #[test]
     fn budgets_respected_files_and_bytes() {
         let mut queues = JobQueues::default();
@@ -353,6 +596,42 @@ mod tests {
         assert!(selected.iter().any(|j| matches!(j, Job::Delete(_))));
     }
 
+    #[test]
+    fn outside_quiet_hours_only_critical_runs() {
+        let mut queues = JobQueues::default();
+        queues.push(
+            JobCategory::Critical,
+            Job::Delete(DocKey::from_parts(1, 1)),
+            0,
+        );
+        queues.push(
+            JobCategory::Metadata,
+            Job::MetadataUpdate(DocKey::from_parts(1, 2)),
+            0,
+        );
+        queues.push(
+            JobCategory::Content,
+            Job::ContentIndex(DocKey::from_parts(1, 3)),
+            0,
+        );
+        // A window on no days never allows background work.
+        let cfg = SchedulerConfig {
+            quiet_hours: QuietHours {
+                windows: vec![quiet_hours::AllowedWindow {
+                    days: 0,
+                    start_min: 0,
+                    end_min: 0,
+                }],
+            },
+            ..SchedulerConfig::default()
+        };
+
+        let selected = select_jobs(&mut queues, IdleState::DeepIdle, load_ok(), &cfg);
+        assert_eq!(selected.len(), 1);
+        assert!(matches!(selected[0], Job::Delete(_)));
+        assert_eq!(queues.counts(), (0, 1, 1));
+    }
+
     #[test]
     fn spawn_content_worker_honors_backlog_and_cooldown() {
         let cfg = SchedulerConfig {
@@ -23,6 +23,8 @@ pub struct SystemLoad {
     pub sample_duration: Duration,
     /// True if the system is running on battery power.
     pub on_battery: bool,
+    /// Remaining battery charge (0-100), if known.
+    pub battery_percent: Option<u8>,
     /// True if a full-screen application (game/presentation) is active.
     pub game_mode: bool,
 }
@@ -105,7 +107,7 @@ impl SystemLoadSampler {
         let mem_used_percent = (self.system.used_memory() as f32 / total_mem as f32) * 100.0;
 
         let (disk_bytes_per_sec, disk_busy) = self.sample_disk(elapsed);
-        let on_battery = self.sample_power();
+        let (on_battery, battery_percent) = self.sample_power();
         let game_mode = self.sample_game_mode();
 
         self.last_sample = now;
@@ -117,6 +119,7 @@ impl SystemLoadSampler {
             disk_busy,
             sample_duration: elapsed,
             on_battery,
+            battery_percent,
             game_mode,
         }
     }
@@ -169,7 +172,7 @@ impl SystemLoadSampler {
             return (bytes_per_sec, busy);
         }
     }
-    fn sample_power(&self) -> bool {
+    fn sample_power(&self) -> (bool, Option<u8>) {
         #[cfg(target_os = "windows")]
         {
             use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
@@ -177,10 +180,13 @@ impl SystemLoadSampler {
             if unsafe { GetSystemPowerStatus(&mut status) }.is_ok() {
                 // ACLineStatus: 0 = Offline (Battery), 1 = Online, 255 = Unknown.
                 // We assume on battery if AC is offline (0).
-                return status.ACLineStatus == 0;
+                // BatteryLifePercent: 0-100, or 255 when unknown.
+                let percent =
+                    (status.BatteryLifePercent <= 100).then_some(status.BatteryLifePercent);
+                return (status.ACLineStatus == 0, percent);
             }
         }
-        false
+        (false, None)
     }
 
     fn sample_game_mode(&self) -> bool {
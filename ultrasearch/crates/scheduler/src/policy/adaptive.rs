@@ -6,12 +6,24 @@ const BATCH_SIZE_MIN: usize = 10;
 const BATCH_SIZE_MAX: usize = 2000;
 const CPU_THRESHOLD_MIN: f32 = 15.0;
 const CPU_THRESHOLD_MAX: f32 = 60.0;
+const THROUGHPUT_SMOOTHING: f64 = 0.3;
+/// Batches finishing faster than this are grown; slower than twice this count as a stall.
+const TARGET_BATCH_SECS: f64 = 10.0;
+const BATCH_GROW: f64 = 1.25;
+const BATCH_SHRINK: f64 = 0.5;
 
 /// Dynamically adjusts scheduler config based on recent system load.
+///
+/// Besides the open-loop CPU heuristic in [`AdaptivePolicy::update`], the policy closes the loop
+/// on measured content throughput: completed batches feed an EMA of files/sec, enqueues feed an
+/// EMA of arrivals/sec, and the batch size grows while batches finish quickly and completions
+/// keep up, and shrinks on stalls or when completions lag enqueues.
 pub struct AdaptivePolicy {
     config: SchedulerConfig,
     smoothed_cpu: f32,
     last_adjustment: std::time::Instant,
+    completion_rate: Option<f64>,
+    enqueue_rate: Option<f64>,
 }
 
 impl AdaptivePolicy {
@@ -19,6 +31,8 @@ impl AdaptivePolicy {
         Self {
             smoothed_cpu: 0.0,
             last_adjustment: std::time::Instant::now(),
+            completion_rate: None,
+            enqueue_rate: None,
             config,
         }
     }
@@ -27,6 +41,39 @@ impl AdaptivePolicy {
         &self.config
     }
 
+    /// Smoothed content completion rate (files/sec), once a batch has been recorded.
+    pub fn completion_rate(&self) -> Option<f64> {
+        self.completion_rate
+    }
+
+    /// Record `files` content jobs enqueued over `interval`.
+    pub fn record_enqueued(&mut self, files: usize, interval: Duration) {
+        let secs = interval.as_secs_f64().max(1e-3);
+        self.enqueue_rate = Some(ema(self.enqueue_rate, files as f64 / secs));
+    }
+
+    /// Record a finished content batch and resize the next batch from the measured throughput.
+    pub fn record_completion(&mut self, files: usize, elapsed: Duration) {
+        if files == 0 {
+            return;
+        }
+        let secs = elapsed.as_secs_f64().max(1e-3);
+        let rate = ema(self.completion_rate, files as f64 / secs);
+        self.completion_rate = Some(rate);
+
+        let stalled = secs > TARGET_BATCH_SECS * 2.0;
+        let lagging = self.enqueue_rate.is_some_and(|enq| rate < enq);
+        let current = self.config.content_batch_size as f64;
+        let next = if stalled || lagging {
+            current * BATCH_SHRINK
+        } else if secs < TARGET_BATCH_SECS {
+            (current * BATCH_GROW).ceil()
+        } else {
+            current
+        };
+        self.config.content_batch_size = (next as usize).clamp(BATCH_SIZE_MIN, BATCH_SIZE_MAX);
+    }
+
     /// Update internal state and adjust config if needed.
     pub fn update(&mut self, load: &SystemLoad) {
         // Smooth CPU load to avoid jerky reactions
@@ -64,6 +111,13 @@ impl AdaptivePolicy {
     }
 }
 
+fn ema(prev: Option<f64>, sample: f64) -> f64 {
+    match prev {
+        Some(p) => p * (1.0 - THROUGHPUT_SMOOTHING) + sample * THROUGHPUT_SMOOTHING,
+        None => sample,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -76,6 +130,7 @@ mod tests {
             disk_busy: false,
             game_mode: false,
             on_battery: false,
+            battery_percent: None,
             sample_duration: Duration::from_secs(1),
         }
     }
@@ -91,4 +146,36 @@ mod tests {
 
         assert!(policy.config().content_batch_size < initial_batch);
     }
+
+    #[test]
+    fn throughput_grows_batch_and_stalls_shrink_it() {
+        let mut policy = AdaptivePolicy::new(SchedulerConfig::default());
+        let initial_batch = policy.config().content_batch_size;
+
+        // Fast batches with completions outpacing arrivals: the batch grows.
+        for _ in 0..5 {
+            policy.record_enqueued(10, Duration::from_secs(1));
+            let batch = policy.config().content_batch_size;
+            policy.record_completion(batch, Duration::from_secs(1));
+        }
+        let grown = policy.config().content_batch_size;
+        assert!(grown > initial_batch, "{grown} <= {initial_batch}");
+        assert!(policy.completion_rate().unwrap() > 10.0);
+
+        // Stalled batches: the batch shrinks.
+        for _ in 0..3 {
+            let batch = policy.config().content_batch_size;
+            policy.record_completion(batch / 10 + 1, Duration::from_secs(60));
+        }
+        assert!(policy.config().content_batch_size < grown);
+    }
+
+    #[test]
+    fn lagging_completions_shrink_batch() {
+        let mut policy = AdaptivePolicy::new(SchedulerConfig::default());
+        let initial_batch = policy.config().content_batch_size;
+        policy.record_enqueued(5_000, Duration::from_secs(1));
+        policy.record_completion(50, Duration::from_secs(1));
+        assert!(policy.config().content_batch_size < initial_batch);
+    }
 }
@@ -0,0 +1,162 @@
+//! Quiet-hours schedule: local-time windows in which metadata/content indexing may run.
+//!
+//! Outside every window the scheduler treats background work as paused regardless of idle
+//! state; critical jobs (deletes/renames) still run. An empty schedule allows all times.
+
+use anyhow::{Context, Result, bail};
+use chrono::{Datelike, Local, NaiveDateTime, Timelike, Weekday};
+use core_types::config::IndexWindow;
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// One parsed window; `days` is a bitmask with bit 0 = Monday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllowedWindow {
+    pub days: u8,
+    pub start_min: u16,
+    pub end_min: u16,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QuietHours {
+    pub windows: Vec<AllowedWindow>,
+}
+
+impl QuietHours {
+    /// Parse config windows; fails on malformed days or `HH:MM` times.
+    pub fn from_config(windows: &[IndexWindow]) -> Result<Self> {
+        let windows = windows
+            .iter()
+            .map(|w| {
+                let days = if w.days.is_empty() {
+                    0x7f
+                } else {
+                    w.days.iter().try_fold(0u8, |mask, d| {
+                        let day: Weekday = d
+                            .parse()
+                            .map_err(|_| anyhow::anyhow!("invalid quiet_hours day `{d}`"))?;
+                        Ok::<_, anyhow::Error>(mask | 1 << day.num_days_from_monday())
+                    })?
+                };
+                Ok(AllowedWindow {
+                    days,
+                    start_min: parse_hhmm(&w.start)?,
+                    end_min: parse_hhmm(&w.end)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { windows })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// Evaluate against the current local time.
+    pub fn allows_now(&self) -> bool {
+        self.is_empty() || is_within_allowed_window(Local::now().naive_local(), self)
+    }
+}
+
+fn parse_hhmm(s: &str) -> Result<u16> {
+    let (h, m) = s
+        .trim()
+        .split_once(':')
+        .with_context(|| format!("invalid quiet_hours time `{s}` (expected HH:MM)"))?;
+    let (h, m): (u16, u16) = (
+        h.parse()
+            .with_context(|| format!("invalid hour in `{s}`"))?,
+        m.parse()
+            .with_context(|| format!("invalid minute in `{s}`"))?,
+    );
+    if h > 24 || m > 59 || (h == 24 && m != 0) {
+        bail!("quiet_hours time `{s}` out of range");
+    }
+    Ok(h * 60 + m)
+}
+
+/// Whether `now` falls inside any window of `schedule`. An empty schedule always allows.
+///
+/// A window whose end is before its start spans midnight: it covers `[start, 24:00)` on its
+/// listed days and `[00:00, end)` on the following day. `start == end` covers the whole day.
+pub fn is_within_allowed_window(now: NaiveDateTime, schedule: &QuietHours) -> bool {
+    if schedule.is_empty() {
+        return true;
+    }
+    let minute = (now.hour() * 60 + now.minute()) as u16;
+    let today = now.weekday().num_days_from_monday();
+    let yesterday = (today + 6) % 7;
+    let on = |mask: u8, day: u32| mask & (1 << day) != 0;
+
+    schedule.windows.iter().any(|w| {
+        let start = w.start_min.min(MINUTES_PER_DAY);
+        let end = w.end_min.min(MINUTES_PER_DAY);
+        if start == end {
+            on(w.days, today)
+        } else if start < end {
+            on(w.days, today) && (start..end).contains(&minute)
+        } else {
+            (on(w.days, today) && minute >= start) || (on(w.days, yesterday) && minute < end)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn window(days: &[&str], start: &str, end: &str) -> QuietHours {
+        QuietHours::from_config(&[IndexWindow {
+            days: days.iter().map(|d| d.to_string()).collect(),
+            start: start.into(),
+            end: end.into(),
+        }])
+        .unwrap()
+    }
+
+    // 2024-01-01 was a Monday.
+    fn at(day: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(h, m, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn inside_and_outside_same_day_window() {
+        let s = window(&[], "09:00", "17:00");
+        assert!(is_within_allowed_window(at(1, 9, 0), &s));
+        assert!(is_within_allowed_window(at(1, 16, 59), &s));
+        assert!(!is_within_allowed_window(at(1, 17, 0), &s));
+        assert!(!is_within_allowed_window(at(1, 8, 59), &s));
+    }
+
+    #[test]
+    fn window_spanning_midnight_covers_next_morning() {
+        let s = window(&["mon"], "22:00", "06:00");
+        assert!(is_within_allowed_window(at(1, 23, 30), &s)); // Monday night
+        assert!(is_within_allowed_window(at(2, 5, 59), &s)); // Tuesday early morning
+        assert!(!is_within_allowed_window(at(2, 6, 0), &s));
+        assert!(!is_within_allowed_window(at(2, 23, 0), &s)); // Tuesday night not listed
+        assert!(!is_within_allowed_window(at(1, 3, 0), &s)); // Sunday's night not listed
+    }
+
+    #[test]
+    fn empty_schedule_always_allows_and_bad_input_is_rejected() {
+        assert!(is_within_allowed_window(
+            at(3, 12, 0),
+            &QuietHours::default()
+        ));
+        let bad = |start: &str, days: &[&str]| {
+            QuietHours::from_config(&[IndexWindow {
+                days: days.iter().map(|d| d.to_string()).collect(),
+                start: start.into(),
+                end: "06:00".into(),
+            }])
+        };
+        assert!(bad("25:00", &[]).is_err());
+        assert!(bad("noon", &[]).is_err());
+        assert!(bad("22:00", &["funday"]).is_err());
+    }
+}
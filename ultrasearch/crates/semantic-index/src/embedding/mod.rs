@@ -1 +1,231 @@
+//! Embedding stage of the semantic pipeline.
+//!
+//! Extraction and embedding have different costs (I/O and parsing vs. a GPU/ONNX model), so
+//! they are decoupled by a bounded queue of extracted chunks. `max_concurrent_embeddings`
+//! workers drain it, each handing the model up to `embedding_batch_size` chunks per
+//! [`Embedder::embed`] call. A worker sends a partial batch once the queue stays empty for
+//! the flush delay, or when the stage is finished. A full queue blocks [`EmbeddingStage::submit`],
+//! which throttles extraction to the model's pace.
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, SyncSender, sync_channel};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+use core_types::DocKey;
+use core_types::config::SemanticSection;
+
+use crate::VectorEmbedding;
+
+/// How long a worker waits for more chunks before embedding a partial batch.
+pub const DEFAULT_FLUSH_AFTER: Duration = Duration::from_millis(50);
+
+/// A model turning text into vectors.
+pub trait Embedder: Send + Sync {
+    /// One vector per text, in order.
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// A piece of extracted text to embed.
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub key: DocKey,
+    pub text: String,
+}
+
+/// Receives each embedded chunk, e.g. to insert it into the semantic index.
+pub type EmbeddingSink = Arc<dyn Fn(DocKey, VectorEmbedding) + Send + Sync>;
+
+/// Totals reported by [`EmbeddingStage::finish`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EmbeddingStats {
+    pub batches: usize,
+    pub embedded: usize,
+    /// Chunks whose batch the model rejected or answered with the wrong number of vectors.
+    pub failed: usize,
+}
+
+#[derive(Default)]
+struct Counters {
+    batches: AtomicUsize,
+    embedded: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+pub struct EmbeddingStage {
+    tx: Option<SyncSender<Chunk>>,
+    workers: Vec<JoinHandle<()>>,
+    counters: Arc<Counters>,
+}
+
+impl EmbeddingStage {
+    /// Start the workers configured by `cfg`.
+    pub fn spawn(embedder: Arc<dyn Embedder>, cfg: &SemanticSection, sink: EmbeddingSink) -> Self {
+        Self::spawn_with_flush(embedder, cfg, sink, DEFAULT_FLUSH_AFTER)
+    }
+
+    /// Like [`Self::spawn`] with a custom partial-batch delay.
+    pub fn spawn_with_flush(
+        embedder: Arc<dyn Embedder>,
+        cfg: &SemanticSection,
+        sink: EmbeddingSink,
+        flush_after: Duration,
+    ) -> Self {
+        let workers = cfg.max_concurrent_embeddings.max(1);
+        let batch_size = cfg.embedding_batch_size.max(1);
+        let (tx, rx) = sync_channel(workers * batch_size * 2);
+        let rx = Arc::new(Mutex::new(rx));
+        let counters = Arc::new(Counters::default());
+        let workers = (0..workers)
+            .map(|_| {
+                let (rx, embedder, sink, counters) =
+                    (rx.clone(), embedder.clone(), sink.clone(), counters.clone());
+                std::thread::spawn(move || {
+                    while let Some(batch) = next_batch(&rx, batch_size, flush_after) {
+                        embed_batch(embedder.as_ref(), &batch, &sink, &counters);
+                    }
+                })
+            })
+            .collect();
+        Self {
+            tx: Some(tx),
+            workers,
+            counters,
+        }
+    }
+
+    /// Queue `chunk`, blocking while the queue is full.
+    pub fn submit(&self, chunk: Chunk) -> Result<()> {
+        let tx = self
+            .tx
+            .as_ref()
+            .ok_or_else(|| anyhow!("embedding stage finished"))?;
+        tx.send(chunk)
+            .map_err(|_| anyhow!("embedding workers exited"))
+    }
+
+    /// Embed everything queued, stop the workers and report totals.
+    pub fn finish(mut self) -> EmbeddingStats {
+        self.shutdown();
+        EmbeddingStats {
+            batches: self.counters.batches.load(Ordering::Relaxed),
+            embedded: self.counters.embedded.load(Ordering::Relaxed),
+            failed: self.counters.failed.load(Ordering::Relaxed),
+        }
+    }
+
+    fn shutdown(&mut self) {
+        self.tx = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for EmbeddingStage {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Up to `batch_size` chunks: waits for the first, then takes more until the batch is full or
+/// none arrive within `flush_after`. `None` once the queue is closed and drained. The
+/// receiver stays locked while a batch is assembled so batches aren't interleaved.
+fn next_batch(
+    rx: &Mutex<Receiver<Chunk>>,
+    batch_size: usize,
+    flush_after: Duration,
+) -> Option<Vec<Chunk>> {
+    let rx = rx.lock().unwrap_or_else(|e| e.into_inner());
+    let mut batch = vec![rx.recv().ok()?];
+    while batch.len() < batch_size {
+        match rx.recv_timeout(flush_after) {
+            Ok(chunk) => batch.push(chunk),
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    Some(batch)
+}
+
+fn embed_batch(
+    embedder: &dyn Embedder,
+    batch: &[Chunk],
+    sink: &EmbeddingSink,
+    counters: &Counters,
+) {
+    counters.batches.fetch_add(1, Ordering::Relaxed);
+    let texts: Vec<&str> = batch.iter().map(|c| c.text.as_str()).collect();
+    match embedder.embed(&texts) {
+        Ok(vectors) if vectors.len() == batch.len() => {
+            for (chunk, vector) in batch.iter().zip(vectors) {
+                sink(chunk.key, VectorEmbedding(vector));
+            }
+            counters.embedded.fetch_add(batch.len(), Ordering::Relaxed);
+        }
+        _ => {
+            counters.failed.fetch_add(batch.len(), Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records batch sizes and returns each text's length as a one-dimensional vector.
+    #[derive(Default)]
+    struct StubEmbedder {
+        batches: Mutex<Vec<usize>>,
+    }
+
+    impl Embedder for StubEmbedder {
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+            self.batches.lock().unwrap().push(texts.len());
+            Ok(texts.iter().map(|t| vec![t.len() as f32]).collect())
+        }
+    }
+
+    #[test]
+    fn chunks_are_batched_to_the_configured_size() {
+        let embedder = Arc::new(StubEmbedder::default());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink_out = received.clone();
+        let sink: EmbeddingSink = Arc::new(move |key, embedding: VectorEmbedding| {
+            sink_out.lock().unwrap().push((key, embedding.0[0]));
+        });
+        let cfg = SemanticSection {
+            embedding_batch_size: 4,
+            max_concurrent_embeddings: 1,
+            ..SemanticSection::default()
+        };
+        // A long flush delay: only a full batch or finishing sends one.
+        let stage =
+            EmbeddingStage::spawn_with_flush(embedder.clone(), &cfg, sink, Duration::from_secs(30));
+        for i in 0..10u64 {
+            let text = "x".repeat(i as usize + 1);
+            stage
+                .submit(Chunk {
+                    key: DocKey(i),
+                    text,
+                })
+                .unwrap();
+        }
+        let stats = stage.finish();
+
+        assert_eq!(*embedder.batches.lock().unwrap(), vec![4, 4, 2]);
+        assert_eq!(
+            stats,
+            EmbeddingStats {
+                batches: 3,
+                embedded: 10,
+                failed: 0
+            }
+        );
+        let received = received.lock().unwrap();
+        let expected: Vec<(DocKey, f32)> =
+            (0..10u64).map(|i| (DocKey(i), (i + 1) as f32)).collect();
+        assert_eq!(*received, expected);
+    }
+}
@@ -0,0 +1,117 @@
+//! Merging keyword and semantic result lists into one ranking.
+//!
+//! Keyword scores (BM25/FST) and cosine similarities live on unrelated scales, so they are
+//! either min-max normalized per list before blending ([`FusionMethod::WeightedSum`]) or
+//! replaced by rank positions ([`FusionMethod::Rrf`]).
+
+use core_types::DocKey;
+use core_types::config::{FusionMethod, SemanticSection};
+use std::collections::HashMap;
+
+/// Fuse two ranked lists (best first) according to `cfg`. The result is sorted by fused
+/// score, descending; ties are broken by `DocKey` so the order is deterministic.
+pub fn fuse(
+    keyword: &[(DocKey, f32)],
+    semantic: &[(DocKey, f32)],
+    cfg: &SemanticSection,
+) -> Vec<(DocKey, f32)> {
+    match cfg.fusion {
+        FusionMethod::WeightedSum => weighted_sum(keyword, semantic, cfg.semantic_weight),
+        FusionMethod::Rrf => reciprocal_rank_fusion(&[keyword, semantic], cfg.rrf_k),
+    }
+}
+
+/// Reciprocal Rank Fusion: each list contributes `1 / (k + rank)` (rank is 1-based) for
+/// every document it contains. Only the order of each list matters.
+pub fn reciprocal_rank_fusion(lists: &[&[(DocKey, f32)]], k: u32) -> Vec<(DocKey, f32)> {
+    let mut fused: HashMap<DocKey, f32> = HashMap::new();
+    for list in lists {
+        for (rank, (key, _)) in list.iter().enumerate() {
+            *fused.entry(*key).or_default() += 1.0 / (k as f32 + rank as f32 + 1.0);
+        }
+    }
+    sorted(fused)
+}
+
+/// Blend min-max normalized scores: `(1 - w) * keyword + w * semantic`, where a document
+/// missing from a list scores 0 there.
+pub fn weighted_sum(
+    keyword: &[(DocKey, f32)],
+    semantic: &[(DocKey, f32)],
+    semantic_weight: f32,
+) -> Vec<(DocKey, f32)> {
+    let w = semantic_weight.clamp(0.0, 1.0);
+    let mut fused: HashMap<DocKey, f32> = HashMap::new();
+    for (list, weight) in [(keyword, 1.0 - w), (semantic, w)] {
+        for (key, score) in normalize(list) {
+            *fused.entry(key).or_default() += weight * score;
+        }
+    }
+    sorted(fused)
+}
+
+fn normalize(list: &[(DocKey, f32)]) -> impl Iterator<Item = (DocKey, f32)> + '_ {
+    let (min, max) = list
+        .iter()
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), (_, s)| {
+            (lo.min(*s), hi.max(*s))
+        });
+    let span = max - min;
+    list.iter().map(move |(key, score)| {
+        let norm = if span > f32::EPSILON {
+            (score - min) / span
+        } else {
+            1.0
+        };
+        (*key, norm)
+    })
+}
+
+fn sorted(fused: HashMap<DocKey, f32>) -> Vec<(DocKey, f32)> {
+    let mut out: Vec<_> = fused.into_iter().collect();
+    out.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(entries: &[(u64, f32)]) -> Vec<(DocKey, f32)> {
+        entries.iter().map(|&(k, s)| (DocKey(k), s)).collect()
+    }
+
+    #[test]
+    fn rrf_ranks_doc_in_both_lists_above_single_list_docs() {
+        // Keyword scores dwarf cosine similarities; RRF only looks at positions.
+        let keyword = list(&[(1, 42.0), (2, 17.5), (3, 9.0)]);
+        let semantic = list(&[(4, 0.91), (5, 0.88), (2, 0.80)]);
+        let cfg = SemanticSection {
+            fusion: FusionMethod::Rrf,
+            rrf_k: 60,
+            ..Default::default()
+        };
+
+        let fused = fuse(&keyword, &semantic, &cfg);
+
+        assert_eq!(fused.len(), 5);
+        assert_eq!(fused[0].0, DocKey(2));
+        let expected = 1.0 / 62.0 + 1.0 / 63.0;
+        assert!((fused[0].1 - expected).abs() < 1e-6);
+        // Rank-1 entries of each list tie; the lower key wins the tie.
+        assert_eq!(fused[1].0, DocKey(1));
+        assert_eq!(fused[2].0, DocKey(4));
+    }
+
+    #[test]
+    fn weighted_sum_normalizes_each_list() {
+        let keyword = list(&[(1, 40.0), (2, 20.0)]);
+        let semantic = list(&[(2, 0.9), (3, 0.5)]);
+
+        let fused = weighted_sum(&keyword, &semantic, 0.75);
+
+        assert_eq!(fused[0], (DocKey(2), 0.75));
+        assert_eq!(fused[1], (DocKey(1), 0.25));
+        assert_eq!(fused[2], (DocKey(3), 0.0));
+    }
+}
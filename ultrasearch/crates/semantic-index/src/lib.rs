@@ -2,6 +2,7 @@
 
 pub mod ann;
 pub mod embedding;
+pub mod fusion;
 
 use core_types::DocKey;
 
@@ -0,0 +1,17 @@
+// Generates the gRPC messages and service for the optional `grpc` feature from
+// `proto/ultrasearch.proto`. protoc comes from `protoc-bin-vendored`, so no system install is
+// needed; the default build does nothing here.
+
+#[cfg(feature = "grpc")]
+fn main() {
+    println!("cargo:rerun-if-changed=proto/ultrasearch.proto");
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc");
+    let mut config = tonic_prost_build::Config::new();
+    config.protoc_executable(protoc);
+    tonic_prost_build::configure()
+        .compile_with_config(config, &["proto/ultrasearch.proto"], &["proto"])
+        .expect("compile proto/ultrasearch.proto");
+}
+
+#[cfg(not(feature = "grpc"))]
+fn main() {}
@@ -0,0 +1,147 @@
+//! Search audit log.
+//!
+//! When `[audit] enabled = true`, every `SearchRequest` served over IPC is appended to
+//! `audit.file` as one JSON line: timestamp, request id, client identity, query, mode and
+//! result counts. The file is separate from the tracing log, rotates by size like it, and
+//! nothing is redacted since it never leaves the machine.
+
+use crate::logging::{SizeRotatingWriter, split_dir_file};
+use anyhow::{Context, Result};
+use core_types::config::AuditSection;
+use ipc::{QueryExpr, SearchMode, SearchRequest, SearchResponse};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::io::Write;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+static AUDIT_LOG: OnceLock<AuditLog> = OnceLock::new();
+
+/// Open the configured audit log and install it globally; a no-op when auditing is off.
+pub fn init_audit_log(cfg: &AuditSection) -> Result<()> {
+    if !cfg.enabled {
+        return Ok(());
+    }
+    let log = AuditLog::open(cfg)?;
+    tracing::info!("search audit log enabled at {}", cfg.file);
+    let _ = AUDIT_LOG.set(log);
+    Ok(())
+}
+
+/// The installed audit log, if auditing is enabled.
+pub fn audit_log() -> Option<&'static AuditLog> {
+    AUDIT_LOG.get()
+}
+
+/// One line of the audit log.
+#[derive(Debug, Serialize)]
+struct AuditRecord<'a> {
+    timestamp_ms: u64,
+    request_id: Uuid,
+    /// Who sent the request (e.g. `pid:1234` for a pipe client); `None` when unknown.
+    client: Option<&'a str>,
+    mode: SearchMode,
+    query: &'a QueryExpr,
+    limit: u32,
+    offset: u32,
+    total: u64,
+    returned: usize,
+    took_ms: u32,
+}
+
+/// Appends audit records to a size-rotated JSON-lines file.
+pub struct AuditLog {
+    writer: Mutex<SizeRotatingWriter>,
+}
+
+impl AuditLog {
+    pub fn open(cfg: &AuditSection) -> Result<Self> {
+        let (dir, file) = split_dir_file(Path::new(&cfg.file))?;
+        let max_bytes = cfg.max_size_mb.saturating_mul(1024 * 1024);
+        let writer = SizeRotatingWriter::new(dir.to_path_buf(), file.to_string(), max_bytes)
+            .context("open audit log")?;
+        Ok(Self {
+            writer: Mutex::new(writer),
+        })
+    }
+
+    /// Record one served search.
+    pub fn record_search(
+        &self,
+        req: &SearchRequest,
+        resp: &SearchResponse,
+        client: Option<&str>,
+    ) -> Result<()> {
+        let record = AuditRecord {
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            request_id: req.id,
+            client,
+            mode: req.mode,
+            query: &req.query,
+            limit: req.limit,
+            offset: req.offset,
+            total: resp.total,
+            returned: resp.hits.len(),
+            took_ms: resp.took_ms,
+        };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        let mut writer = self.writer.lock();
+        writer.write_all(&line)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search_handler::search;
+
+    #[test]
+    fn enabled_audit_writes_one_record_per_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = AuditSection {
+            enabled: true,
+            file: dir
+                .path()
+                .join("audit.jsonl")
+                .to_string_lossy()
+                .into_owned(),
+            max_size_mb: 1,
+        };
+        let log = AuditLog::open(&cfg).unwrap();
+
+        let req = SearchRequest {
+            id: Uuid::new_v4(),
+            query: QueryExpr::Term(ipc::TermExpr {
+                field: None,
+                value: "report".into(),
+                modifier: ipc::TermModifier::Term,
+            }),
+            limit: 20,
+            mode: SearchMode::NameOnly,
+            ..Default::default()
+        };
+        let resp = search(req.clone());
+        log.record_search(&req, &resp, Some("pid:4242")).unwrap();
+
+        let text = std::fs::read_to_string(&cfg.file).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 1, "{text}");
+        let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record["request_id"], req.id.to_string());
+        assert_eq!(record["client"], "pid:4242");
+        assert_eq!(record["mode"], "NameOnly");
+        assert_eq!(record["query"]["Term"]["value"], "report");
+        assert_eq!(record["limit"], 20);
+        assert_eq!(record["total"], resp.total);
+        assert_eq!(record["returned"], resp.hits.len());
+        assert!(record["timestamp_ms"].as_u64().unwrap() > 0);
+    }
+}
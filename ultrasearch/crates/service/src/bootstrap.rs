@@ -6,6 +6,7 @@ use std::{
 
 use anyhow::Result;
 use core_types::config::AppConfig;
+use core_types::dates::DateZone;
 use ipc::VolumeStatus;
 use tokio::sync::mpsc;
 
@@ -22,6 +23,7 @@ pub struct BootstrapOptions {
 }
 
 use crate::{
+    audit::init_audit_log,
     init_tracing_with_config,
     meta_ingest::ingest_with_paths,
     metrics::{init_metrics_from_config, set_global_metrics},
@@ -47,6 +49,9 @@ pub fn run_app_with_options(
     apply_background_priorities();
 
     let _guard = init_tracing_with_config(&cfg.logging)?;
+    if let Err(e) = init_audit_log(&cfg.audit) {
+        tracing::warn!("search audit log disabled: {e:#}");
+    }
 
     // Initialize Tokio runtime
     let rt = tokio::runtime::Builder::new_multi_thread()
@@ -55,7 +60,7 @@ pub fn run_app_with_options(
     let _rt_guard = rt.enter();
 
     // Install status provider so IPC/status can respond.
-    init_basic_status_provider();
+    init_basic_status_provider().set_eta_window(cfg.scheduler.eta_window_secs);
 
     if cfg.metrics.enabled {
         let metrics = Arc::new(init_metrics_from_config(&cfg.metrics)?);
@@ -67,6 +72,16 @@ pub fn run_app_with_options(
     let mut cfg_owned = cfg.clone();
     super::ensure_default_volumes(&mut cfg_owned)?;
     ensure_data_paths_exist(&cfg_owned)?;
+    // Create the meta index up front so its stored-field layout follows `meta_store`.
+    if let Err(e) = meta_index::open_or_create_index_with(
+        Path::new(&cfg_owned.paths.meta_index),
+        &cfg_owned.meta_store,
+    ) {
+        tracing::warn!("failed to open meta index: {e:#}");
+    }
+    if cfg_owned.integrity.verify_on_startup {
+        log_integrity_report(&cfg_owned);
+    }
 
     match opts.initial_metas {
         Some(metas) => ingest_seed_metadata(&cfg_owned, metas, &mut pending_jobs)?,
@@ -107,11 +122,91 @@ pub fn run_app_with_options(
     // We pass both meta and content index paths.
     let meta_path = Path::new(&cfg_owned.paths.meta_index);
     let content_path = Path::new(&cfg_owned.paths.content_index);
+    let analysis = &cfg_owned.content_analysis;
+    let analyzer = content_index::AnalyzerOptions {
+        stop_words_enabled: analysis.stop_words_enabled,
+        language: analysis.stop_words_language.clone(),
+        stop_words: analysis.stop_words.clone(),
+    };
 
     let mut attempts = 0;
     loop {
-        match crate::search_handler::UnifiedSearchHandler::try_new(meta_path, content_path) {
+        match crate::search_handler::UnifiedSearchHandler::try_new_with_analyzer(
+            meta_path,
+            content_path,
+            &analyzer,
+        ) {
             Ok(handler) => {
+                let search = &cfg_owned.search;
+                let mut handler = handler
+                    .with_fuzzy_transpositions(search.fuzzy_transpositions)
+                    .with_highlight_style(search.snippet_highlight)
+                    .with_snippet_dedup(search.snippet_dedup_similarity)
+                    .with_sort_locale(&search.sort_locale)
+                    .with_content_degradation(search.degrade_without_content)
+                    .with_name_match_ranking(search.name_match_ranking)
+                    .with_content_score_cap(search.content_max_docs_scored)
+                    .with_total_bytes_directories(search.total_bytes_directories)
+                    .with_context_weight(search.context_path_weight)
+                    .with_date_zone(DateZone::parse(&search.date_time_zone).unwrap_or_else(|| {
+                        tracing::warn!(
+                            zone = %search.date_time_zone,
+                            "unrecognized search.date_time_zone; reading dates as local time"
+                        );
+                        DateZone::Local
+                    }))
+                    .with_streaming(search.stream_threshold, search.stream_page_size)
+                    .with_trailing_prefix(search.prefix_trailing_term)
+                    .with_dead_letters(
+                        Path::new(&cfg_owned.paths.jobs_dir)
+                            .join(crate::extract_status::DEAD_LETTER_FILE),
+                    )
+                    .with_snippet_cache(crate::snippet_cache::SnippetCache::new(
+                        std::time::Duration::from_secs(search.snippet_cache_ttl_secs),
+                        search.snippet_cache_entries,
+                    ));
+                match crate::tags::init_tag_store(Path::new(&cfg_owned.paths.tags_file)) {
+                    Ok(store) => handler = handler.with_tags(store),
+                    Err(e) => tracing::warn!("failed to open tag store: {e:#}"),
+                }
+                if search.result_set_max_keys > 0 {
+                    handler = handler.with_result_sets(crate::result_sets::init_result_sets(
+                        std::time::Duration::from_secs(search.result_set_ttl_secs),
+                        search.result_set_max_keys,
+                    ));
+                }
+                if search.match_former_names && cfg_owned.indexing.former_names > 0 {
+                    match crate::former_names::init_former_name_store(
+                        &crate::former_names::former_names_path(&cfg_owned.paths),
+                        cfg_owned.indexing.former_names,
+                    ) {
+                        Ok(store) => handler = handler.with_former_names(store),
+                        Err(e) => tracing::warn!("failed to open former-name store: {e:#}"),
+                    }
+                }
+                if search.prefetch_queries > 0
+                    && let Err(e) = crate::query_prefetch::init_query_frequency(
+                        &crate::query_prefetch::query_frequency_path(&cfg_owned.paths),
+                        search.prefetch_queries,
+                    )
+                {
+                    tracing::warn!("failed to open query frequency sketch: {e:#}");
+                }
+                if search.query_history_len > 0
+                    && let Err(e) = crate::query_history::init_query_history(
+                        &crate::query_history::query_history_path(&cfg_owned.paths),
+                        search.query_history_len,
+                    )
+                {
+                    tracing::warn!("failed to open query history: {e:#}");
+                }
+                if search.rehydrate_on_miss {
+                    let extract = cfg_owned.extract.clone();
+                    handler = handler.with_rehydration(Arc::new(move |hit, flags| {
+                        crate::scheduler_runtime::content_job_from_hit(hit, flags, &extract)
+                            .is_some_and(crate::scheduler_runtime::enqueue_content_job)
+                    }));
+                }
                 set_search_handler(Box::new(handler));
                 break;
             }
@@ -153,11 +248,26 @@ pub fn run_app_with_options(
     {
         // Start IPC server
         // We use the runtime we just created.
-        if let Err(e) = rt.block_on(crate::ipc::start_pipe_server(opts.pipe_name.as_deref())) {
+        if let Err(e) = rt.block_on(crate::ipc::start_pipe_server(
+            opts.pipe_name.as_deref(),
+            &cfg.ipc,
+        )) {
             tracing::error!("failed to start IPC server: {}", e);
         }
     }
 
+    if !cfg.ipc.grpc_addr.is_empty() {
+        #[cfg(feature = "grpc")]
+        if let Err(e) = rt.block_on(crate::grpc::start_grpc_server(
+            &cfg.ipc.grpc_addr,
+            Arc::new(crate::search_handler::InstalledSearchHandler),
+        )) {
+            tracing::error!("failed to start gRPC server: {e:#}");
+        }
+        #[cfg(not(feature = "grpc"))]
+        tracing::warn!("ipc.grpc_addr is set but this build lacks the `grpc` feature");
+    }
+
     tracing::info!("UltraSearch service started. Waiting for shutdown signal...");
 
     // Block until shutdown signal
@@ -169,6 +279,7 @@ pub fn run_app_with_options(
     let _ = shutdown_rx.blocking_recv();
 
     tracing::info!("Shutdown signal received. Exiting.");
+    crate::shutdown_telemetry();
     Ok(())
 }
 
@@ -193,6 +304,35 @@ fn ensure_data_paths_exist(cfg: &AppConfig) -> Result<()> {
     Ok(())
 }
 
+/// Run the integrity self-check and log its findings; the service starts either way.
+fn log_integrity_report(cfg: &AppConfig) {
+    let opts = crate::integrity::VerifyOptions {
+        sample_keys: cfg.integrity.sample_keys,
+        checksums: cfg.integrity.verify_checksums,
+    };
+    let report = crate::integrity::verify_index(&cfg.paths, &opts);
+    for check in &report.components {
+        match check.status {
+            crate::integrity::CheckStatus::Failed => tracing::error!(
+                component = check.component,
+                "integrity check failed: {}",
+                check.detail
+            ),
+            status => tracing::info!(
+                component = check.component,
+                ?status,
+                "integrity check: {}",
+                check.detail
+            ),
+        }
+    }
+    if report.rebuild_recommended {
+        tracing::error!(
+            "index integrity check failed; rebuild recommended (search-cli reset-index)"
+        );
+    }
+}
+
 fn ingest_seed_metadata(
     cfg: &AppConfig,
     metas: Vec<core_types::FileMeta>,
@@ -0,0 +1,179 @@
+//! Sizes of every document a query matches, for `SearchResponse::total_bytes_matched`.
+//!
+//! Paging only ever loads `limit` hits, so the total is gathered by a separate pass over the
+//! whole match set that reads the `size` (and, in the metadata index, `flags`) fast fields
+//! without scoring or loading stored documents. Directories are left out of the sum unless
+//! their contents are requested (`search.total_bytes_directories = "contents"`).
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use core_types::dir_components;
+use meta_index::MetaFields;
+use tantivy::collector::{Collector, DocSetCollector, FilterCollector, SegmentCollector};
+use tantivy::columnar::Column;
+use tantivy::query::{BooleanQuery, Occur, Query, TermQuery};
+use tantivy::schema::{IndexRecordOption, TantivyDocument, Value};
+use tantivy::{DocAddress, DocId, Score, Searcher, SegmentOrdinal, SegmentReader, Term};
+
+/// `FileFlags::IS_DIR`, as stored in the metadata index's `flags` field.
+const IS_DIR: u64 = core_types::FileFlags::IS_DIR.bits() as u64;
+
+/// Matched documents split into files (by key, so overlapping passes are counted once) and
+/// directories.
+#[derive(Debug, Default)]
+pub struct MatchedSizes {
+    /// `doc_key` -> size of each matched file.
+    pub files: HashMap<u64, u64>,
+    /// Matched directories, for callers that sum their contents.
+    pub dirs: Vec<DocAddress>,
+}
+
+impl MatchedSizes {
+    pub fn total(&self) -> u64 {
+        self.files.values().sum()
+    }
+
+    /// Union with `other`; a file in both keeps one entry.
+    pub fn merge(&mut self, other: MatchedSizes) {
+        self.files.extend(other.files);
+        self.dirs.extend(other.dirs);
+    }
+}
+
+/// Collects [`MatchedSizes`]. Indexes without a `flags` field (the content index, which only
+/// holds files) treat every match as a file.
+struct SizeCollector;
+
+struct SizeSegment {
+    segment: SegmentOrdinal,
+    key: Column<u64>,
+    size: Column<u64>,
+    flags: Option<Column<u64>>,
+    out: MatchedSizes,
+}
+
+impl SegmentCollector for SizeSegment {
+    type Fruit = MatchedSizes;
+
+    fn collect(&mut self, doc: DocId, _score: Score) {
+        let is_dir = self
+            .flags
+            .as_ref()
+            .and_then(|flags| flags.first(doc))
+            .is_some_and(|flags| flags & IS_DIR != 0);
+        if is_dir {
+            self.out.dirs.push(DocAddress::new(self.segment, doc));
+        } else if let Some(key) = self.key.first(doc) {
+            self.out
+                .files
+                .insert(key, self.size.first(doc).unwrap_or(0));
+        }
+    }
+
+    fn harvest(self) -> MatchedSizes {
+        self.out
+    }
+}
+
+impl Collector for SizeCollector {
+    type Fruit = MatchedSizes;
+    type Child = SizeSegment;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tantivy::Result<SizeSegment> {
+        let fast = segment.fast_fields();
+        Ok(SizeSegment {
+            segment: segment_local_id,
+            key: fast.u64("doc_key")?,
+            size: fast.u64("size")?,
+            flags: fast.u64("flags").ok(),
+            out: MatchedSizes::default(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(&self, segment_fruits: Vec<MatchedSizes>) -> tantivy::Result<MatchedSizes> {
+        let mut merged = MatchedSizes::default();
+        for fruit in segment_fruits {
+            merged.merge(fruit);
+        }
+        Ok(merged)
+    }
+}
+
+/// Add the files beneath each directory in `sizes.dirs` (metadata-index addresses) to
+/// `sizes.files`, at any depth, counting files that already matched only once. Candidates
+/// come from the `dir_component` terms of the directory's path and are confirmed against
+/// their stored path.
+pub fn add_dir_contents(
+    searcher: &Searcher,
+    fields: &MetaFields,
+    sizes: &mut MatchedSizes,
+) -> tantivy::Result<()> {
+    let dirs = std::mem::take(&mut sizes.dirs);
+    for addr in &dirs {
+        let dir: TantivyDocument = searcher.doc(*addr)?;
+        let Some(path) = dir.get_first(fields.path).and_then(|v| v.as_str()) else {
+            continue;
+        };
+        let prefix = format!("{}\\", path.trim_end_matches(['\\', '/']).to_lowercase());
+        // `dir_components` drops the last element, so a placeholder keeps the dir's own name.
+        let clauses: Vec<(Occur, Box<dyn Query>)> = dir_components(&format!("{prefix}*"))
+            .into_iter()
+            .map(|component| {
+                let term = Term::from_field_text(fields.dir_component, &component);
+                let query = TermQuery::new(term, IndexRecordOption::Basic);
+                (Occur::Must, Box::new(query) as Box<dyn Query>)
+            })
+            .collect();
+        if clauses.is_empty() {
+            continue;
+        }
+        let candidates = searcher.search(&BooleanQuery::new(clauses), &DocSetCollector)?;
+        for candidate in candidates {
+            let doc: TantivyDocument = searcher.doc(candidate)?;
+            let field_u64 = |field| doc.get_first(field).and_then(|v| v.as_u64());
+            let under = doc
+                .get_first(fields.path)
+                .and_then(|v| v.as_str())
+                .is_some_and(|p| p.to_lowercase().starts_with(&prefix));
+            let is_dir = field_u64(fields.flags).is_some_and(|f| f & IS_DIR != 0);
+            if let (true, false, Some(key)) = (under, is_dir, field_u64(fields.doc_key)) {
+                sizes
+                    .files
+                    .entry(key)
+                    .or_insert(field_u64(fields.size).unwrap_or(0));
+            }
+        }
+    }
+    sizes.dirs = dirs;
+    Ok(())
+}
+
+/// Sizes of all documents matching `query`, restricted to `refine` keys when given.
+pub fn matched_sizes(
+    searcher: &Searcher,
+    query: &dyn Query,
+    refine: Option<&[core_types::DocKey]>,
+) -> tantivy::Result<MatchedSizes> {
+    match refine {
+        None => searcher.search(query, &SizeCollector),
+        Some(keys) => {
+            let keys: Arc<std::collections::HashSet<u64>> =
+                Arc::new(keys.iter().map(|k| k.0).collect());
+            let filtered = FilterCollector::new(
+                "doc_key".to_string(),
+                move |key: u64| keys.contains(&key),
+                SizeCollector,
+            );
+            searcher.search(query, &filtered)
+        }
+    }
+}
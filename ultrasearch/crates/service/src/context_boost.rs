@@ -0,0 +1,60 @@
+//! Ranking boost for hits near the directory a search is made from
+//! (`SearchRequest::context_path`, weighted by `search.context_path_weight`).
+//!
+//! Proximity is measured in folder hops through the tree: a file in the context folder is as
+//! close as it gets, one in a subfolder or sibling folder a little less, and one on another
+//! drive not close at all.
+
+/// How close the file at `path` is to the folder `context`, in `[0, 1]`: the folders they
+/// share from the root, divided by that plus the hops between the file's folder and `context`.
+/// 1 means the file sits directly in `context`; 0 means nothing in common. Case-insensitive,
+/// accepting `\` or `/` separators.
+pub fn context_proximity(path: &str, context: &str) -> f32 {
+    let mut folder = components(path);
+    folder.pop();
+    let context = components(context);
+    let shared = folder
+        .iter()
+        .zip(&context)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if shared == 0 {
+        return 0.0;
+    }
+    let hops = (folder.len() - shared) + (context.len() - shared);
+    shared as f32 / (shared + hops) as f32
+}
+
+/// Multiply `score` by up to `1 + weight` for a hit at `path` near `context`.
+pub fn boosted_score(score: f32, path: Option<&str>, context: &str, weight: f32) -> f32 {
+    let proximity = path.map_or(0.0, |p| context_proximity(p, context));
+    score * (1.0 + weight * proximity)
+}
+
+fn components(path: &str) -> Vec<String> {
+    path.split(['\\', '/'])
+        .filter(|p| !p.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closer_folders_score_higher() {
+        let ctx = r"C:\src\app";
+        let inside = context_proximity(r"C:\src\app\main.rs", ctx);
+        let below = context_proximity(r"C:\src\app\ui\view.rs", ctx);
+        let sibling = context_proximity(r"C:\src\lib\util.rs", ctx);
+        let far = context_proximity(r"C:\Users\me\Downloads\main.rs", ctx);
+        assert_eq!(inside, 1.0);
+        assert!(
+            inside > below && below > sibling && sibling > far,
+            "{below} {sibling} {far}"
+        );
+        assert_eq!(context_proximity(r"D:\src\app\main.rs", ctx), 0.0);
+        assert_eq!(context_proximity("c:/SRC/App/main.rs", r"C:\src\app\"), 1.0);
+    }
+}
@@ -0,0 +1,262 @@
+//! Diagnostics bundle: a single JSON document support can ask users to share.
+//!
+//! Gathering is split in two: [`gather_inputs`] reads live state (config, index directories,
+//! log file, status provider) and [`build_bundle`] turns an injected [`DiagnosticsInputs`]
+//! into JSON so the shape and redaction can be tested without a running service.
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use core_types::config::AppConfig;
+use ipc::{MetricsSnapshot, VolumeStatus};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::status_provider::status_snapshot;
+
+const REDACTED: &str = "<redacted>";
+
+/// Config keys (matched case-insensitively as substrings) whose values are never exported.
+const SENSITIVE_KEYS: &[&str] = &[
+    "product_uid",
+    "password",
+    "secret",
+    "token",
+    "api_key",
+    "credential",
+];
+
+/// Size and segment statistics for one on-disk index.
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexDiagnostics {
+    pub name: String,
+    pub path: String,
+    pub exists: bool,
+    pub size_bytes: u64,
+    pub segment_count: Option<usize>,
+    pub num_docs: Option<u64>,
+}
+
+/// Everything the bundle is built from; populated by [`gather_inputs`] or directly in tests.
+#[derive(Debug, Clone)]
+pub struct DiagnosticsInputs {
+    pub config: AppConfig,
+    pub indexes: Vec<IndexDiagnostics>,
+    pub log_tail: Vec<String>,
+    pub scheduler_state: String,
+    pub metrics: Option<MetricsSnapshot>,
+    pub volumes: Vec<VolumeStatus>,
+    pub version: String,
+}
+
+/// Collect diagnostics inputs from the running process and the filesystem.
+pub fn gather_inputs(cfg: &AppConfig, log_tail_lines: usize) -> DiagnosticsInputs {
+    let snap = status_snapshot();
+    DiagnosticsInputs {
+        config: cfg.clone(),
+        indexes: vec![
+            index_diagnostics("meta", Path::new(&cfg.paths.meta_index)),
+            index_diagnostics("content", Path::new(&cfg.paths.content_index)),
+        ],
+        log_tail: read_log_tail(Path::new(&cfg.logging.file), log_tail_lines),
+        scheduler_state: snap.scheduler_state,
+        metrics: snap.metrics,
+        volumes: snap.volumes,
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+/// Build the diagnostics JSON document with secrets redacted from the config section.
+pub fn build_bundle(inputs: &DiagnosticsInputs) -> Value {
+    let mut config = serde_json::to_value(&inputs.config).unwrap_or(Value::Null);
+    redact(&mut config);
+
+    json!({
+        "version": {
+            "service": inputs.version,
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+        },
+        "config": config,
+        "indexes": inputs.indexes,
+        "scheduler": {
+            "state": inputs.scheduler_state,
+            "metrics": inputs.metrics,
+        },
+        "volumes": inputs.volumes,
+        "log_tail": inputs.log_tail,
+    })
+}
+
+/// Replace values of sensitive keys with a placeholder, recursively.
+pub fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if is_sensitive_key(key) {
+                    *v = Value::String(REDACTED.to_string());
+                } else {
+                    redact(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+fn is_sensitive_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SENSITIVE_KEYS.iter().any(|s| key.contains(s))
+}
+
+/// Best-effort size/segment stats; missing or unreadable indexes are reported, not errors.
+pub fn index_diagnostics(name: &str, path: &Path) -> IndexDiagnostics {
+    let exists = path.exists();
+    let (segment_count, num_docs) = if exists {
+        match tantivy::Index::open_in_dir(path) {
+            Ok(index) => {
+                let metas = index.searchable_segment_metas().ok();
+                (
+                    metas.as_ref().map(Vec::len),
+                    metas.map(|m| m.iter().map(|s| u64::from(s.num_docs())).sum()),
+                )
+            }
+            Err(_) => (None, None),
+        }
+    } else {
+        (None, None)
+    };
+
+    IndexDiagnostics {
+        name: name.to_string(),
+        path: path.display().to_string(),
+        exists,
+        size_bytes: dir_size(path),
+        segment_count,
+        num_docs,
+    }
+}
+
+pub(crate) fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|e| match e.metadata() {
+            Ok(m) if m.is_dir() => dir_size(&e.path()),
+            Ok(m) => m.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// Return the last `max_lines` WARN/ERROR lines from the service log file.
+pub fn read_log_tail(path: &Path, max_lines: usize) -> Vec<String> {
+    let Ok(file) = fs::File::open(path) else {
+        return Vec::new();
+    };
+    let mut tail: std::collections::VecDeque<String> =
+        std::collections::VecDeque::with_capacity(max_lines.min(1024));
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        if !(line.contains("ERROR") || line.contains("WARN")) {
+            continue;
+        }
+        if tail.len() == max_lines {
+            tail.pop_front();
+        }
+        if max_lines > 0 {
+            tail.push_back(line);
+        }
+    }
+    tail.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn sample_inputs() -> DiagnosticsInputs {
+        let mut config = AppConfig::default();
+        config.app.product_uid = "machine-secret-uid".into();
+        DiagnosticsInputs {
+            config,
+            indexes: vec![IndexDiagnostics {
+                name: "meta".into(),
+                path: "/tmp/meta".into(),
+                exists: true,
+                size_bytes: 4096,
+                segment_count: Some(3),
+                num_docs: Some(42),
+            }],
+            log_tail: vec![r#"{"level":"ERROR","message":"boom"}"#.into()],
+            scheduler_state: "idle".into(),
+            metrics: None,
+            volumes: vec![],
+            version: "0.1.0".into(),
+        }
+    }
+
+    #[test]
+    fn bundle_has_expected_sections() {
+        let bundle = build_bundle(&sample_inputs());
+        for section in [
+            "version",
+            "config",
+            "indexes",
+            "scheduler",
+            "volumes",
+            "log_tail",
+        ] {
+            assert!(bundle.get(section).is_some(), "missing section {section}");
+        }
+        assert_eq!(bundle["indexes"][0]["segment_count"], 3);
+        assert_eq!(bundle["scheduler"]["state"], "idle");
+    }
+
+    #[test]
+    fn redaction_removes_sensitive_fields() {
+        let bundle = build_bundle(&sample_inputs());
+        let text = bundle.to_string();
+        assert!(!text.contains("machine-secret-uid"));
+        assert_eq!(bundle["config"]["app"]["product_uid"], REDACTED);
+
+        let mut nested = json!({
+            "outer": { "api_key": "k", "items": [{ "Password": "p", "keep": 1 }] }
+        });
+        redact(&mut nested);
+        assert_eq!(nested["outer"]["api_key"], REDACTED);
+        assert_eq!(nested["outer"]["items"][0]["Password"], REDACTED);
+        assert_eq!(nested["outer"]["items"][0]["keep"], 1);
+    }
+
+    #[test]
+    fn log_tail_keeps_last_warn_error_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("service.log");
+        let mut f = fs::File::create(&path).unwrap();
+        for i in 0..5 {
+            writeln!(f, r#"{{"level":"INFO","n":{i}}}"#).unwrap();
+            writeln!(f, r#"{{"level":"ERROR","n":{i}}}"#).unwrap();
+        }
+        drop(f);
+
+        let tail = read_log_tail(&path, 2);
+        assert_eq!(tail.len(), 2);
+        assert!(tail[0].contains(r#""n":3"#));
+        assert!(tail[1].contains(r#""n":4"#));
+        assert!(read_log_tail(&dir.path().join("missing.log"), 10).is_empty());
+    }
+
+    #[test]
+    fn missing_index_reports_absent() {
+        let dir = tempfile::tempdir().unwrap();
+        let d = index_diagnostics("content", &dir.path().join("nope"));
+        assert!(!d.exists);
+        assert_eq!(d.size_bytes, 0);
+        assert_eq!(d.segment_count, None);
+    }
+}
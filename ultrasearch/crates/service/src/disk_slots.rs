@@ -0,0 +1,129 @@
+//! Per-physical-disk concurrency limit for volume indexing
+//! (`indexing.max_jobs_per_physical_disk`).
+//!
+//! Two partitions on one spinning drive enumerated at once make the heads seek between them
+//! and finish later than one after the other; separate drives don't interfere and should run
+//! in parallel. Each job holds a [`DiskPermit`] for its disk while it runs.
+
+use core_types::VolumeId;
+use parking_lot::{Condvar, Mutex};
+use std::collections::HashMap;
+
+/// What a volume's job contends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiskId {
+    /// Physical disk number from `IOCTL_STORAGE_GET_DEVICE_NUMBER`.
+    Physical(u32),
+    /// Disk unknown: the volume only contends with itself.
+    Volume(VolumeId),
+}
+
+impl DiskId {
+    pub fn for_volume(volume: VolumeId, disk_number: Option<u32>) -> Self {
+        disk_number.map_or(DiskId::Volume(volume), DiskId::Physical)
+    }
+}
+
+/// Counting semaphore keyed by disk.
+pub struct DiskSlots {
+    max_per_disk: usize,
+    busy: Mutex<HashMap<DiskId, usize>>,
+    freed: Condvar,
+}
+
+impl DiskSlots {
+    /// `max_per_disk` = 0 disables the limit.
+    pub fn new(max_per_disk: usize) -> Self {
+        Self {
+            max_per_disk,
+            busy: Mutex::new(HashMap::new()),
+            freed: Condvar::new(),
+        }
+    }
+
+    /// Block until `disk` has a free slot and take it until the permit is dropped.
+    pub fn acquire(&self, disk: DiskId) -> DiskPermit<'_> {
+        let mut busy = self.busy.lock();
+        while self.max_per_disk > 0 && busy.get(&disk).copied().unwrap_or(0) >= self.max_per_disk {
+            self.freed.wait(&mut busy);
+        }
+        *busy.entry(disk).or_default() += 1;
+        DiskPermit { slots: self, disk }
+    }
+}
+
+/// A held slot on one disk; released on drop.
+pub struct DiskPermit<'a> {
+    slots: &'a DiskSlots,
+    disk: DiskId,
+}
+
+impl Drop for DiskPermit<'_> {
+    fn drop(&mut self) {
+        let mut busy = self.slots.busy.lock();
+        if let Some(n) = busy.get_mut(&self.disk) {
+            *n -= 1;
+            if *n == 0 {
+                busy.remove(&self.disk);
+            }
+        }
+        self.slots.freed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Run one job per entry concurrently and return the peak number running at once.
+    fn peak_concurrency(slots: &DiskSlots, disks: &[DiskId]) -> usize {
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        std::thread::scope(|s| {
+            for &disk in disks {
+                let (running, peak) = (&running, &peak);
+                s.spawn(move || {
+                    let _permit = slots.acquire(disk);
+                    let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                    peak.fetch_max(now, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(100));
+                    running.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+        peak.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn same_disk_serializes_and_different_disks_run_concurrently() {
+        let slots = DiskSlots::new(1);
+        let same = [
+            DiskId::for_volume(1, Some(0)),
+            DiskId::for_volume(2, Some(0)),
+        ];
+        assert_eq!(peak_concurrency(&slots, &same), 1);
+
+        let different = [
+            DiskId::for_volume(1, Some(0)),
+            DiskId::for_volume(2, Some(1)),
+        ];
+        assert_eq!(peak_concurrency(&slots, &different), 2);
+
+        // Unknown disks never wait on each other.
+        let unknown = [DiskId::for_volume(1, None), DiskId::for_volume(2, None)];
+        assert_eq!(peak_concurrency(&slots, &unknown), 2);
+    }
+
+    #[test]
+    fn zero_means_unlimited() {
+        let slots = DiskSlots::new(0);
+        let same = [
+            DiskId::Physical(3),
+            DiskId::Physical(3),
+            DiskId::Physical(3),
+        ];
+        assert_eq!(peak_concurrency(&slots, &same), 3);
+    }
+}
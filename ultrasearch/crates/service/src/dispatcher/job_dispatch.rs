@@ -1,5 +1,6 @@
 use anyhow::{Context, Result};
 use core_types::config::AppConfig;
+use core_types::filenames::ContentKind;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tokio::task;
@@ -16,6 +17,12 @@ pub struct JobSpec {
     pub max_chars: Option<usize>,
     #[serde(default)]
     pub file_size: u64,
+    /// Set for recognized extensionless files (e.g. `Makefile`) so the worker can route them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_kind: Option<ContentKind>,
+    /// Retries already made because the file was locked (see [`crate::locked_retry`]).
+    #[serde(default)]
+    pub attempt: u32,
 }
 
 #[derive(Debug, Serialize)]
@@ -28,6 +35,26 @@ pub struct JobDispatcher {
     worker_path: PathBuf,
     jobs_dir: PathBuf,
     index_dir: PathBuf,
+    /// `[content_merge]` as JSON, forwarded to the worker's index writer.
+    merge_policy: String,
+    /// `extract.detect_mime`, forwarded as `--detect-mime`.
+    detect_mime: bool,
+    /// `extract.index_shortcuts`, forwarded as `--index-shortcuts`.
+    index_shortcuts: bool,
+    /// `extract.dedup_content`, forwarded as `--dedup-content`.
+    dedup_content: bool,
+    /// `extract.index_email`, forwarded as `--index-email`.
+    index_email: bool,
+    /// `extract.index_titles`, forwarded as `--index-titles`.
+    index_titles: bool,
+    /// `extract.max_ifilter_threads`, forwarded as `--max-ifilter-threads`.
+    max_ifilter_threads: usize,
+    /// `extract.disabled_extractors`, forwarded comma-separated as `--disabled-extractors`.
+    disabled_extractors: Vec<String>,
+    /// `extract.locked_retry_attempts`, forwarded as `--locked-retry-attempts`.
+    locked_retry_attempts: u32,
+    /// `extract.locked_retry_backoff_secs`, forwarded as `--locked-retry-backoff-secs`.
+    locked_retry_backoff_secs: u64,
 }
 
 impl JobDispatcher {
@@ -50,6 +77,17 @@ impl JobDispatcher {
             worker_path,
             jobs_dir: PathBuf::from(&cfg.paths.jobs_dir),
             index_dir: PathBuf::from(&cfg.paths.content_index),
+            merge_policy: serde_json::to_string(&cfg.content_merge)
+                .expect("merge section serializes"),
+            detect_mime: cfg.extract.detect_mime,
+            index_shortcuts: cfg.extract.index_shortcuts,
+            dedup_content: cfg.extract.dedup_content,
+            index_email: cfg.extract.index_email,
+            index_titles: cfg.extract.index_titles,
+            max_ifilter_threads: cfg.extract.max_ifilter_threads,
+            disabled_extractors: cfg.extract.disabled_extractors.clone(),
+            locked_retry_attempts: cfg.extract.locked_retry_attempts,
+            locked_retry_backoff_secs: cfg.extract.locked_retry_backoff_secs,
         }
     }
 
@@ -84,6 +122,21 @@ impl JobDispatcher {
         let job_file_for_spawn = job_file_path.clone();
         let index_dir_for_spawn = self.index_dir.clone();
         let index_dir_for_log = index_dir_for_spawn.clone();
+        let merge_policy = self.merge_policy.clone();
+        let detect_mime = self.detect_mime.then_some("--detect-mime");
+        let index_shortcuts = self.index_shortcuts.then_some("--index-shortcuts");
+        let dedup_content = self.dedup_content.then_some("--dedup-content");
+        let index_email = self.index_email.then_some("--index-email");
+        let index_titles = self.index_titles.then_some("--index-titles");
+        let max_ifilter_threads = self.max_ifilter_threads.to_string();
+        let disabled_extractors = (!self.disabled_extractors.is_empty()).then(|| {
+            [
+                "--disabled-extractors".to_string(),
+                self.disabled_extractors.join(","),
+            ]
+        });
+        let locked_retry_attempts = self.locked_retry_attempts.to_string();
+        let locked_retry_backoff_secs = self.locked_retry_backoff_secs.to_string();
 
         let status = task::spawn_blocking(move || -> anyhow::Result<std::process::ExitStatus> {
             if !worker_path.exists() {
@@ -104,6 +157,20 @@ impl JobDispatcher {
                     .arg(&job_file_for_spawn)
                     .arg("--index-dir")
                     .arg(&index_dir_for_spawn)
+                    .arg("--merge-policy")
+                    .arg(&merge_policy)
+                    .args(detect_mime)
+                    .args(index_shortcuts)
+                    .args(dedup_content)
+                    .args(index_email)
+                    .args(index_titles)
+                    .arg("--max-ifilter-threads")
+                    .arg(&max_ifilter_threads)
+                    .args(disabled_extractors.iter().flatten())
+                    .arg("--locked-retry-attempts")
+                    .arg(&locked_retry_attempts)
+                    .arg("--locked-retry-backoff-secs")
+                    .arg(&locked_retry_backoff_secs)
                     .creation_flags(CREATE_NO_WINDOW)
                     .spawn()
                     .context("failed to spawn worker process")?;
@@ -125,6 +192,20 @@ impl JobDispatcher {
                     .arg(&job_file_for_spawn)
                     .arg("--index-dir")
                     .arg(&index_dir)
+                    .arg("--merge-policy")
+                    .arg(&merge_policy)
+                    .args(detect_mime)
+                    .args(index_shortcuts)
+                    .args(dedup_content)
+                    .args(index_email)
+                    .args(index_titles)
+                    .arg("--max-ifilter-threads")
+                    .arg(&max_ifilter_threads)
+                    .args(disabled_extractors.iter().flatten())
+                    .arg("--locked-retry-attempts")
+                    .arg(&locked_retry_attempts)
+                    .arg("--locked-retry-backoff-secs")
+                    .arg(&locked_retry_backoff_secs)
                     .spawn()
                     .context("failed to spawn worker process")?
                     .wait()?;
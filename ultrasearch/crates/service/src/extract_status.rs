@@ -0,0 +1,110 @@
+//! `SearchHit::extract_status`: why a hit does or doesn't have indexed content.
+//!
+//! The index worker appends every file it couldn't extract to [`DEAD_LETTER_FILE`] in
+//! `paths.jobs_dir`, marking unsupported or oversized files as skipped. A hit is `Ok` when the
+//! content index holds it, whatever older failures were recorded; otherwise its latest
+//! dead-letter record decides, then whether a re-extraction is queued for it.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use core_types::DocKey;
+use ipc::ExtractStatus;
+use serde::Deserialize;
+
+/// Dead-letter log written by the index worker beside its job files.
+pub const DEAD_LETTER_FILE: &str = "dead_letter.jsonl";
+
+#[derive(Deserialize)]
+struct DeadLetterRecord {
+    volume_id: u16,
+    file_id: u64,
+    reason: String,
+    #[serde(default)]
+    skipped: bool,
+}
+
+/// Latest dead-letter outcome per document (`Failed` or `Skipped`). A missing file or
+/// unparsable lines yield no entries.
+pub fn load_dead_letters(path: &Path) -> HashMap<DocKey, ExtractStatus> {
+    let Ok(text) = std::fs::read_to_string(path) else {
+        return HashMap::new();
+    };
+    text.lines()
+        .filter_map(|line| serde_json::from_str::<DeadLetterRecord>(line).ok())
+        .map(|rec| {
+            let status = if rec.skipped {
+                ExtractStatus::Skipped
+            } else {
+                ExtractStatus::Failed(rec.reason)
+            };
+            (DocKey::from_parts(rec.volume_id, rec.file_id), status)
+        })
+        .collect()
+}
+
+/// What is known about one hit's content.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ContentState<'a> {
+    /// The content index holds a document for it.
+    pub indexed: bool,
+    pub is_dir: bool,
+    /// A re-extraction has been requested and not yet seen in the index.
+    pub requeued: bool,
+    pub dead_letter: Option<&'a ExtractStatus>,
+}
+
+pub fn extract_status(state: ContentState<'_>) -> ExtractStatus {
+    if state.indexed {
+        ExtractStatus::Ok
+    } else if state.is_dir {
+        ExtractStatus::Skipped
+    } else if state.requeued {
+        ExtractStatus::Pending
+    } else {
+        state.dead_letter.cloned().unwrap_or(ExtractStatus::Pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latest_dead_letter_wins_unless_content_is_indexed() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DEAD_LETTER_FILE);
+        std::fs::write(
+            &path,
+            concat!(
+                r#"{"volume_id":1,"file_id":7,"path":"a.pdf","reason":"old","ts":1}"#,
+                "\n",
+                r#"{"volume_id":1,"file_id":7,"path":"a.pdf","reason":"bad xref","ts":2}"#,
+                "\nnot json\n",
+                r#"{"volume_id":1,"file_id":8,"path":"b.iso","reason":"too large","ts":3,"skipped":true}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+        let dead = load_dead_letters(&path);
+        let failed = &dead[&DocKey::from_parts(1, 7)];
+        assert_eq!(failed, &ExtractStatus::Failed("bad xref".into()));
+        assert_eq!(dead[&DocKey::from_parts(1, 8)], ExtractStatus::Skipped);
+        assert!(load_dead_letters(&dir.path().join("missing.jsonl")).is_empty());
+
+        let state = ContentState {
+            dead_letter: Some(failed),
+            ..ContentState::default()
+        };
+        assert_eq!(extract_status(state), *failed);
+        let reextracted = ContentState {
+            indexed: true,
+            ..state
+        };
+        assert_eq!(extract_status(reextracted), ExtractStatus::Ok);
+        assert_eq!(
+            extract_status(ContentState::default()),
+            ExtractStatus::Pending
+        );
+    }
+}
@@ -0,0 +1,55 @@
+//! Process-wide former-name store (`indexing.former_names`), fed by the change watcher's
+//! rename events and read by the search handler (`search.match_former_names`).
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use anyhow::Result;
+use core_types::config::PathsSection;
+use meta_index::former_names::FormerNameStore;
+use meta_index::{lookup_file_meta, open_or_create_index, open_reader};
+use ntfs_watcher::FileEvent;
+
+static FORMER_NAME_STORE: OnceLock<Arc<FormerNameStore>> = OnceLock::new();
+
+/// Where the store lives: `former_names.rkyv` under `paths.state_dir`.
+pub fn former_names_path(paths: &PathsSection) -> PathBuf {
+    Path::new(&paths.state_dir).join("former_names.rkyv")
+}
+
+/// Open the store at `path` and install it globally; later calls return the installed one.
+pub fn init_former_name_store(path: &Path, max: usize) -> Result<Arc<FormerNameStore>> {
+    if let Some(store) = FORMER_NAME_STORE.get() {
+        return Ok(store.clone());
+    }
+    let store = Arc::new(FormerNameStore::open(path, max)?);
+    Ok(FORMER_NAME_STORE.get_or_init(|| store).clone())
+}
+
+/// Record the renames among `events`. The old name is read from the metadata index at
+/// `meta_index`, which still holds the file under its `from` key; files it doesn't know are
+/// skipped.
+pub fn record_renames(
+    store: &FormerNameStore,
+    meta_index: &Path,
+    events: &[FileEvent],
+) -> Result<()> {
+    let mut renames = events
+        .iter()
+        .filter_map(|ev| match ev {
+            FileEvent::Renamed { from, to } => Some((*from, to)),
+            _ => None,
+        })
+        .peekable();
+    if renames.peek().is_none() || !meta_index.join("meta.json").exists() {
+        return Ok(());
+    }
+    let meta = open_or_create_index(meta_index)?;
+    let searcher = open_reader(&meta)?.searcher();
+    for (from, to) in renames {
+        if let Some(old) = lookup_file_meta(&searcher, &meta.fields, from)? {
+            store.record_rename(from, to.key, &old.name, &to.name)?;
+        }
+    }
+    Ok(())
+}
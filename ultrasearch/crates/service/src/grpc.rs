@@ -0,0 +1,599 @@
+//! gRPC API (`ipc.grpc_addr`, `grpc` feature).
+//!
+//! An opt-in transport for integrations beyond the CLI and UI, served next to the named
+//! pipe. The `Search` and `Status` RPCs answer through the same paths as the pipe's
+//! `SearchRequest` and `StatusRequest` ([`answer_search`], [`current_status`]); their
+//! messages, generated from `proto/ultrasearch.proto`, mirror the `ipc` structs and are
+//! converted here. `anytime` searches are pipe-only.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::{Context, Result};
+use core_types::DocKey;
+use tokio::task::JoinHandle;
+use tonic::transport::Server;
+use tonic::transport::server::TcpIncoming;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use crate::metrics::record_ipc_request;
+use crate::search_handler::{SearchHandler, answer_search};
+use crate::status::current_status;
+
+/// Messages and service stubs generated from `proto/ultrasearch.proto`.
+pub mod proto {
+    tonic::include_proto!("ultrasearch.v1");
+}
+
+use proto::ultra_search_server::{UltraSearch, UltraSearchServer};
+
+/// Bind `addr` and serve the gRPC API on the current runtime, answering searches with
+/// `handler`. Returns the bound address (useful with port 0) and the server task.
+pub async fn start_grpc_server(
+    addr: &str,
+    handler: Arc<dyn SearchHandler>,
+) -> Result<(SocketAddr, JoinHandle<()>)> {
+    let addr: SocketAddr = addr
+        .parse()
+        .with_context(|| format!("invalid ipc.grpc_addr {addr:?}"))?;
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("bind gRPC server to {addr}"))?;
+    let local = listener.local_addr()?;
+    let service = UltraSearchServer::new(GrpcApi { handler });
+    let handle = tokio::spawn(async move {
+        if let Err(e) = Server::builder()
+            .add_service(service)
+            .serve_with_incoming(TcpIncoming::from(listener))
+            .await
+        {
+            tracing::error!("gRPC server stopped: {e}");
+        }
+    });
+    tracing::info!(%local, "gRPC server listening");
+    Ok((local, handle))
+}
+
+struct GrpcApi {
+    handler: Arc<dyn SearchHandler>,
+}
+
+#[tonic::async_trait]
+impl UltraSearch for GrpcApi {
+    async fn search(
+        &self,
+        request: Request<proto::SearchRequest>,
+    ) -> Result<Response<proto::SearchResponse>, Status> {
+        let client = request.remote_addr().map(|addr| format!("grpc:{addr}"));
+        let req = ipc::SearchRequest::try_from(request.into_inner())?;
+        let handler = self.handler.clone();
+        let resp = tokio::task::spawn_blocking(move || {
+            answer_search(handler.as_ref(), req, client.as_deref())
+        })
+        .await
+        .map_err(|e| Status::internal(format!("search task failed: {e}")))?;
+        Ok(Response::new(resp.into()))
+    }
+
+    async fn status(
+        &self,
+        request: Request<proto::StatusRequest>,
+    ) -> Result<Response<proto::StatusResponse>, Status> {
+        let started = Instant::now();
+        let id = parse_id(&request.into_inner().id)?;
+        let resp = current_status(id);
+        record_ipc_request(started.elapsed());
+        Ok(Response::new(resp.into()))
+    }
+}
+
+fn parse_id(id: &str) -> Result<Uuid, Status> {
+    if id.is_empty() {
+        return Ok(Uuid::nil());
+    }
+    Uuid::parse_str(id).map_err(|e| Status::invalid_argument(format!("invalid id {id:?}: {e}")))
+}
+
+fn id_string(id: Uuid) -> String {
+    if id.is_nil() {
+        String::new()
+    } else {
+        id.to_string()
+    }
+}
+
+fn enum_value<E: TryFrom<i32>>(value: i32, what: &str) -> Result<E, Status> {
+    E::try_from(value).map_err(|_| Status::invalid_argument(format!("unknown {what} {value}")))
+}
+
+fn field_from_proto(field: proto::FieldKind) -> Option<ipc::FieldKind> {
+    use proto::FieldKind as P;
+    Some(match field {
+        P::Unspecified => return None,
+        P::Name => ipc::FieldKind::Name,
+        P::Path => ipc::FieldKind::Path,
+        P::Ext => ipc::FieldKind::Ext,
+        P::Content => ipc::FieldKind::Content,
+        P::Size => ipc::FieldKind::Size,
+        P::Modified => ipc::FieldKind::Modified,
+        P::Created => ipc::FieldKind::Created,
+        P::Flags => ipc::FieldKind::Flags,
+        P::Volume => ipc::FieldKind::Volume,
+        P::Kind => ipc::FieldKind::Kind,
+        P::Owner => ipc::FieldKind::Owner,
+        P::Tag => ipc::FieldKind::Tag,
+        P::Mime => ipc::FieldKind::Mime,
+        P::Subject => ipc::FieldKind::Subject,
+        P::From => ipc::FieldKind::From,
+        P::To => ipc::FieldKind::To,
+        P::Sent => ipc::FieldKind::Sent,
+        P::Title => ipc::FieldKind::Title,
+    })
+}
+
+fn field_to_proto(field: Option<ipc::FieldKind>) -> proto::FieldKind {
+    use ipc::FieldKind as F;
+    let Some(field) = field else {
+        return proto::FieldKind::Unspecified;
+    };
+    match field {
+        F::Name => proto::FieldKind::Name,
+        F::Path => proto::FieldKind::Path,
+        F::Ext => proto::FieldKind::Ext,
+        F::Content => proto::FieldKind::Content,
+        F::Size => proto::FieldKind::Size,
+        F::Modified => proto::FieldKind::Modified,
+        F::Created => proto::FieldKind::Created,
+        F::Flags => proto::FieldKind::Flags,
+        F::Volume => proto::FieldKind::Volume,
+        F::Kind => proto::FieldKind::Kind,
+        F::Owner => proto::FieldKind::Owner,
+        F::Tag => proto::FieldKind::Tag,
+        F::Mime => proto::FieldKind::Mime,
+        F::Subject => proto::FieldKind::Subject,
+        F::From => proto::FieldKind::From,
+        F::To => proto::FieldKind::To,
+        F::Sent => proto::FieldKind::Sent,
+        F::Title => proto::FieldKind::Title,
+    }
+}
+
+fn mode_from_proto(mode: proto::SearchMode) -> ipc::SearchMode {
+    match mode {
+        proto::SearchMode::Auto => ipc::SearchMode::Auto,
+        proto::SearchMode::NameOnly => ipc::SearchMode::NameOnly,
+        proto::SearchMode::Content => ipc::SearchMode::Content,
+        proto::SearchMode::Hybrid => ipc::SearchMode::Hybrid,
+    }
+}
+
+fn mode_to_proto(mode: ipc::SearchMode) -> proto::SearchMode {
+    match mode {
+        ipc::SearchMode::Auto => proto::SearchMode::Auto,
+        ipc::SearchMode::NameOnly => proto::SearchMode::NameOnly,
+        ipc::SearchMode::Content => proto::SearchMode::Content,
+        ipc::SearchMode::Hybrid => proto::SearchMode::Hybrid,
+    }
+}
+
+impl TryFrom<proto::QueryExpr> for ipc::QueryExpr {
+    type Error = Status;
+
+    fn try_from(expr: proto::QueryExpr) -> Result<Self, Status> {
+        use proto::query_expr::Expr;
+        let list = |list: proto::QueryList| {
+            list.items
+                .into_iter()
+                .map(ipc::QueryExpr::try_from)
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(match expr.expr {
+            None => ipc::QueryExpr::default(),
+            Some(Expr::Term(term)) => {
+                use proto::TermModifier as M;
+                let modifier = match enum_value(term.modifier, "term modifier")? {
+                    M::Term => ipc::TermModifier::Term,
+                    M::Phrase => ipc::TermModifier::Phrase,
+                    M::Prefix => ipc::TermModifier::Prefix,
+                    M::Fuzzy => ipc::TermModifier::Fuzzy(term.fuzzy_distance.min(255) as u8),
+                    M::Component => ipc::TermModifier::Component,
+                    M::Phonetic => ipc::TermModifier::Phonetic,
+                    M::Glob => ipc::TermModifier::Glob,
+                    M::Regex => ipc::TermModifier::Regex,
+                };
+                ipc::QueryExpr::Term(ipc::TermExpr {
+                    field: field_from_proto(enum_value(term.field, "field")?),
+                    value: term.value,
+                    modifier,
+                })
+            }
+            Some(Expr::Range(range)) => {
+                use proto::RangeOp as O;
+                use proto::range_expr::Value;
+                let field = field_from_proto(enum_value(range.field, "field")?)
+                    .ok_or_else(|| Status::invalid_argument("range query needs a field"))?;
+                let op = match enum_value(range.op, "range op")? {
+                    O::Gt => ipc::RangeOp::Gt,
+                    O::Ge => ipc::RangeOp::Ge,
+                    O::Lt => ipc::RangeOp::Lt,
+                    O::Le => ipc::RangeOp::Le,
+                    O::Between => ipc::RangeOp::Between,
+                };
+                let value = match range.value {
+                    Some(Value::I64(r)) => ipc::RangeValue::I64 { lo: r.lo, hi: r.hi },
+                    Some(Value::U64(r)) => ipc::RangeValue::U64 { lo: r.lo, hi: r.hi },
+                    None => return Err(Status::invalid_argument("range query needs a value")),
+                };
+                ipc::QueryExpr::Range(ipc::RangeExpr { field, op, value })
+            }
+            Some(Expr::Not(inner)) => {
+                ipc::QueryExpr::Not(Box::new(ipc::QueryExpr::try_from(*inner)?))
+            }
+            Some(Expr::And(items)) => ipc::QueryExpr::And(list(items)?),
+            Some(Expr::Or(items)) => ipc::QueryExpr::Or(list(items)?),
+            Some(Expr::Near(near)) => ipc::QueryExpr::Near {
+                terms: near.terms,
+                slop: near.slop,
+            },
+        })
+    }
+}
+
+impl From<ipc::QueryExpr> for proto::QueryExpr {
+    fn from(expr: ipc::QueryExpr) -> Self {
+        use proto::query_expr::Expr;
+        let list = |items: Vec<ipc::QueryExpr>| proto::QueryList {
+            items: items.into_iter().map(Into::into).collect(),
+        };
+        let expr = match expr {
+            ipc::QueryExpr::Term(term) => {
+                use ipc::TermModifier as M;
+                let (modifier, fuzzy_distance) = match term.modifier {
+                    M::Term => (proto::TermModifier::Term, 0),
+                    M::Phrase => (proto::TermModifier::Phrase, 0),
+                    M::Prefix => (proto::TermModifier::Prefix, 0),
+                    M::Fuzzy(distance) => (proto::TermModifier::Fuzzy, u32::from(distance)),
+                    M::Component => (proto::TermModifier::Component, 0),
+                    M::Phonetic => (proto::TermModifier::Phonetic, 0),
+                    M::Glob => (proto::TermModifier::Glob, 0),
+                    M::Regex => (proto::TermModifier::Regex, 0),
+                };
+                Expr::Term(proto::TermExpr {
+                    field: field_to_proto(term.field).into(),
+                    value: term.value,
+                    modifier: modifier.into(),
+                    fuzzy_distance,
+                })
+            }
+            ipc::QueryExpr::Range(range) => {
+                use proto::range_expr::Value;
+                let op = match range.op {
+                    ipc::RangeOp::Gt => proto::RangeOp::Gt,
+                    ipc::RangeOp::Ge => proto::RangeOp::Ge,
+                    ipc::RangeOp::Lt => proto::RangeOp::Lt,
+                    ipc::RangeOp::Le => proto::RangeOp::Le,
+                    ipc::RangeOp::Between => proto::RangeOp::Between,
+                };
+                let value = match range.value {
+                    ipc::RangeValue::I64 { lo, hi } => Value::I64(proto::I64Range { lo, hi }),
+                    ipc::RangeValue::U64 { lo, hi } => Value::U64(proto::U64Range { lo, hi }),
+                };
+                Expr::Range(proto::RangeExpr {
+                    field: field_to_proto(Some(range.field)).into(),
+                    op: op.into(),
+                    value: Some(value),
+                })
+            }
+            ipc::QueryExpr::Not(inner) => Expr::Not(Box::new((*inner).into())),
+            ipc::QueryExpr::And(items) => Expr::And(list(items)),
+            ipc::QueryExpr::Or(items) => Expr::Or(list(items)),
+            ipc::QueryExpr::Near { terms, slop } => Expr::Near(proto::NearExpr { terms, slop }),
+        };
+        Self { expr: Some(expr) }
+    }
+}
+
+impl TryFrom<proto::SearchRequest> for ipc::SearchRequest {
+    type Error = Status;
+
+    fn try_from(req: proto::SearchRequest) -> Result<Self, Status> {
+        let group_by = match enum_value(req.group_by, "group_by")? {
+            proto::GroupBy::None => None,
+            proto::GroupBy::Folder => Some(ipc::GroupBy::Folder),
+        };
+        let fields = req
+            .fields
+            .map(|f| {
+                f.fields
+                    .into_iter()
+                    .map(|field| {
+                        Ok(match enum_value(field, "hit field")? {
+                            proto::HitField::Name => ipc::HitField::Name,
+                            proto::HitField::Path => ipc::HitField::Path,
+                            proto::HitField::Ext => ipc::HitField::Ext,
+                            proto::HitField::Size => ipc::HitField::Size,
+                            proto::HitField::Modified => ipc::HitField::Modified,
+                            proto::HitField::Snippet => ipc::HitField::Snippet,
+                            proto::HitField::Tags => ipc::HitField::Tags,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, Status>>()
+            })
+            .transpose()?;
+        let sort = req
+            .sort
+            .map(|sort| {
+                let field = match enum_value(sort.field, "sort field")? {
+                    proto::SortField::Name => ipc::SortField::Name,
+                    proto::SortField::Path => ipc::SortField::Path,
+                    proto::SortField::Size => ipc::SortField::Size,
+                    proto::SortField::Modified => ipc::SortField::Modified,
+                };
+                Ok::<_, Status>(ipc::SortSpec {
+                    field,
+                    descending: sort.descending,
+                })
+            })
+            .transpose()?;
+        let defaults = ipc::SearchRequest::default();
+        Ok(ipc::SearchRequest {
+            id: parse_id(&req.id)?,
+            query: req
+                .query
+                .map(ipc::QueryExpr::try_from)
+                .transpose()?
+                .unwrap_or_default(),
+            limit: req.limit.unwrap_or(defaults.limit),
+            mode: mode_from_proto(enum_value(req.mode, "search mode")?),
+            timeout: req.timeout_ms.map(std::time::Duration::from_millis),
+            offset: req.offset,
+            group_by,
+            fields,
+            refine: req.refine.map(|r| r.keys.into_iter().map(DocKey).collect()),
+            sort,
+            snippets_per_hit: req
+                .snippets_per_hit
+                .map_or(defaults.snippets_per_hit, |n| n.min(255) as u8),
+            cursor: req.cursor,
+            total_bytes: req.total_bytes,
+            context_path: req.context_path,
+            extract_status: req.extract_status,
+            anytime: false,
+            explain: req.explain,
+            deterministic_order: req.deterministic_order,
+            max_hits_per_dir: req.max_hits_per_dir,
+            result_set: req.result_set,
+            refine_set: req.refine_set.as_deref().map(parse_id).transpose()?,
+            cancel_token: None,
+            query_text: None,
+        })
+    }
+}
+
+impl From<ipc::SearchHit> for proto::SearchHit {
+    fn from(hit: ipc::SearchHit) -> Self {
+        use proto::extract_status::State;
+        let extract_status = hit.extract_status.map(|status| {
+            let (state, reason) = match status {
+                ipc::ExtractStatus::Ok => (State::Ok, String::new()),
+                ipc::ExtractStatus::Skipped => (State::Skipped, String::new()),
+                ipc::ExtractStatus::Failed(reason) => (State::Failed, reason),
+                ipc::ExtractStatus::Pending => (State::Pending, String::new()),
+            };
+            proto::ExtractStatus {
+                state: state.into(),
+                reason,
+            }
+        });
+        Self {
+            key: hit.key.0,
+            score: hit.score,
+            name: hit.name,
+            path: hit.path,
+            ext: hit.ext,
+            size: hit.size,
+            modified: hit.modified,
+            snippet: hit.snippet,
+            snippets: hit.snippets,
+            tags: hit.tags,
+            extract_status,
+            archive: hit.archive.map(|key| key.0),
+            matched_former_name: hit.matched_former_name,
+        }
+    }
+}
+
+impl From<ipc::SearchResponse> for proto::SearchResponse {
+    fn from(resp: ipc::SearchResponse) -> Self {
+        Self {
+            id: id_string(resp.id),
+            hits: resp.hits.into_iter().map(Into::into).collect(),
+            total: resp.total,
+            truncated: resp.truncated,
+            took_ms: resp.took_ms,
+            served_by: resp.served_by,
+            grouped: resp.grouped.map(|groups| proto::FolderGroups {
+                groups: groups
+                    .into_iter()
+                    .map(|g| proto::FolderGroup {
+                        folder_path: g.folder_path,
+                        hits: g.hits.into_iter().map(Into::into).collect(),
+                    })
+                    .collect(),
+            }),
+            degraded_to: resp.degraded_to.map(|mode| mode_to_proto(mode).into()),
+            next_cursor: resp.next_cursor,
+            approximate: resp.approximate,
+            total_bytes_matched: resp.total_bytes_matched,
+            refining: resp.refining,
+            executed_query: resp.executed_query.map(Into::into),
+            error: resp.error,
+            result_set_id: resp.result_set_id.map(id_string),
+        }
+    }
+}
+
+impl From<ipc::StatusResponse> for proto::StatusResponse {
+    fn from(resp: ipc::StatusResponse) -> Self {
+        Self {
+            id: id_string(resp.id),
+            volumes: resp
+                .volumes
+                .into_iter()
+                .map(|v| proto::VolumeStatus {
+                    volume: u32::from(v.volume),
+                    indexed_files: v.indexed_files,
+                    indexed_bytes: v.indexed_bytes,
+                    pending_files: v.pending_files,
+                    pending_bytes: v.pending_bytes,
+                    last_usn: v.last_usn,
+                    journal_id: v.journal_id,
+                })
+                .collect(),
+            last_index_commit_ts: resp.last_index_commit_ts,
+            scheduler_state: resp.scheduler_state,
+            content_jobs_total: resp.content_jobs_total,
+            content_jobs_remaining: resp.content_jobs_remaining,
+            content_bytes_total: resp.content_bytes_total,
+            content_bytes_remaining: resp.content_bytes_remaining,
+            eta_seconds: resp.eta_seconds,
+            metrics: resp.metrics.map(|m| proto::MetricsSnapshot {
+                search_latency_ms_p50: m.search_latency_ms_p50,
+                search_latency_ms_p95: m.search_latency_ms_p95,
+                worker_cpu_pct: m.worker_cpu_pct,
+                worker_mem_bytes: m.worker_mem_bytes,
+                queue_depth: m.queue_depth,
+                active_workers: m.active_workers,
+                content_enqueued: m.content_enqueued,
+                content_dropped: m.content_dropped,
+                pipe_accept_failures: m.pipe_accept_failures,
+                trimmed_content: m.trimmed_content,
+                trimmed_meta: m.trimmed_meta,
+            }),
+            served_by: resp.served_by,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipc::{QueryExpr, SearchHit, SearchMode, SearchRequest, SearchResponse, TermExpr};
+    use proto::ultra_search_client::UltraSearchClient;
+
+    /// Answers every query with the same two hits, echoing the query back.
+    struct FixedHits;
+
+    impl SearchHandler for FixedHits {
+        fn search(&self, req: SearchRequest) -> SearchResponse {
+            let hit = |id: u64, name: &str| SearchHit {
+                key: DocKey::from_parts(1, id),
+                score: 2.0 / id as f32,
+                name: Some(name.into()),
+                path: Some(format!(r"C:\docs\{name}")),
+                ext: Some("txt".into()),
+                size: Some(id * 100),
+                modified: Some(1_700_000_000),
+                snippet: None,
+                snippets: Vec::new(),
+                tags: vec!["q3".into()],
+                extract_status: Some(ipc::ExtractStatus::Failed("encrypted".into())),
+                archive: None,
+                matched_former_name: false,
+            };
+            SearchResponse {
+                id: req.id,
+                hits: vec![hit(1, "report.txt"), hit(2, "report-old.txt")],
+                total: 2,
+                truncated: false,
+                took_ms: 3,
+                served_by: Some("fixed".into()),
+                grouped: None,
+                degraded_to: Some(SearchMode::NameOnly),
+                next_cursor: None,
+                approximate: false,
+                total_bytes_matched: None,
+                refining: false,
+                executed_query: Some(req.query),
+                error: None,
+                result_set_id: None,
+                cancelled: false,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn grpc_search_matches_the_pipe_answer() {
+        let handler: Arc<dyn SearchHandler> = Arc::new(FixedHits);
+        let (addr, server) = start_grpc_server("127.0.0.1:0", handler.clone())
+            .await
+            .unwrap();
+
+        let id = Uuid::new_v4();
+        let query = QueryExpr::And(vec![
+            QueryExpr::Term(TermExpr {
+                field: None,
+                value: "report".into(),
+                modifier: ipc::TermModifier::Fuzzy(1),
+            }),
+            QueryExpr::Not(Box::new(QueryExpr::Range(ipc::RangeExpr {
+                field: ipc::FieldKind::Size,
+                op: ipc::RangeOp::Gt,
+                value: ipc::RangeValue::U64 { lo: 1024, hi: None },
+            }))),
+        ]);
+        let pipe_req = SearchRequest {
+            id,
+            ..SearchRequest::with_query(query.clone())
+                .with_limit(10)
+                .with_mode(SearchMode::Hybrid)
+        };
+        // The pipe carries the request as bincode and answers it through `answer_search`.
+        let pipe_req: SearchRequest =
+            bincode::deserialize(&bincode::serialize(&pipe_req).unwrap()).unwrap();
+        let pipe_resp = answer_search(handler.as_ref(), pipe_req, None);
+
+        let mut client = UltraSearchClient::connect(format!("http://{addr}"))
+            .await
+            .unwrap();
+        let grpc_resp = client
+            .search(proto::SearchRequest {
+                id: id.to_string(),
+                query: Some(query.into()),
+                limit: Some(10),
+                mode: proto::SearchMode::Hybrid.into(),
+                ..Default::default()
+            })
+            .await
+            .unwrap()
+            .into_inner();
+
+        assert_eq!(grpc_resp, proto::SearchResponse::from(pipe_resp));
+        assert_eq!(grpc_resp.id, id.to_string());
+        assert_eq!(grpc_resp.hits.len(), 2);
+        assert_eq!(
+            grpc_resp.degraded_to,
+            Some(proto::SearchMode::NameOnly.into())
+        );
+
+        let status = client
+            .status(proto::StatusRequest { id: id.to_string() })
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(status.id, id.to_string());
+        assert!(status.served_by.is_some());
+
+        let err = client
+            .search(proto::SearchRequest {
+                mode: 42,
+                ..Default::default()
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+        server.abort();
+    }
+}
@@ -0,0 +1,241 @@
+//! `.gitignore` / `.ignore` rules for folder-watch mode (`indexing.respect_gitignore`).
+//!
+//! Follows git's semantics: a pattern without a slash matches at any depth, a leading or
+//! inner slash anchors it to the ignore file's folder, a trailing slash matches only
+//! folders, `**` spans folders, and `!` re-includes. Rules in deeper ignore files override
+//! those above them, later lines override earlier ones, and nothing below an ignored folder
+//! can be re-included. Matching is case-insensitive, as git's is on Windows. `.git` folders
+//! are always ignored.
+
+use std::path::{Path, PathBuf};
+
+/// File names whose patterns are honoured.
+pub const IGNORE_FILE_NAMES: &[&str] = &[".gitignore", ".ignore"];
+
+/// Whether `name` is an ignore file.
+pub fn is_ignore_file(name: &str) -> bool {
+    IGNORE_FILE_NAMES
+        .iter()
+        .any(|n| name.eq_ignore_ascii_case(n))
+}
+
+#[derive(Debug, Default)]
+pub struct IgnoreRules {
+    /// Ordered from shallowest folder to deepest, so later sets win.
+    sets: Vec<RuleSet>,
+}
+
+#[derive(Debug)]
+struct RuleSet {
+    file: PathBuf,
+    /// Normalized folder holding the ignore file.
+    base: String,
+    patterns: Vec<Pattern>,
+}
+
+#[derive(Debug)]
+struct Pattern {
+    glob: Vec<char>,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl IgnoreRules {
+    /// Rules from the given ignore files; unreadable files are skipped.
+    pub fn from_files(files: impl IntoIterator<Item = PathBuf>) -> Self {
+        let mut sets: Vec<RuleSet> = files
+            .into_iter()
+            .filter_map(|file| {
+                let text = std::fs::read_to_string(&file).ok()?;
+                let base = normalize(&file.parent()?.to_string_lossy());
+                Some(RuleSet {
+                    patterns: text.lines().filter_map(Pattern::parse).collect(),
+                    file,
+                    base,
+                })
+            })
+            .collect();
+        sets.sort_by_key(|set| set.base.matches('/').count());
+        Self { sets }
+    }
+
+    /// The ignore files the rules came from.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.sets.iter().map(|set| set.file.as_path())
+    }
+
+    /// Whether `path` is ignored, by its own rules or because a folder above it is.
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let path = normalize(path);
+        let mut end = 0;
+        while let Some(slash) = path[end + 1..].find('/') {
+            end += 1 + slash;
+            if self.matches(&path[..end], true) {
+                return true;
+            }
+        }
+        self.matches(&path, is_dir)
+    }
+
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if is_dir && path.rsplit('/').next() == Some(".git") {
+            return true;
+        }
+        let mut ignored = false;
+        for set in &self.sets {
+            let Some(rel) = path
+                .strip_prefix(set.base.as_str())
+                .and_then(|rest| rest.strip_prefix('/'))
+            else {
+                continue;
+            };
+            let rel: Vec<char> = rel.chars().collect();
+            for pattern in &set.patterns {
+                if (is_dir || !pattern.dir_only) && glob(&pattern.glob, &rel) {
+                    ignored = !pattern.negated;
+                }
+            }
+        }
+        ignored
+    }
+}
+
+impl Pattern {
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        // `\#` and `\!` escape a leading special character.
+        let line = line.strip_prefix('\\').unwrap_or(line);
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        if line.is_empty() {
+            return None;
+        }
+        let glob = if line.contains('/') {
+            line.trim_start_matches('/').to_lowercase()
+        } else {
+            format!("**/{}", line.to_lowercase())
+        };
+        Some(Self {
+            glob: glob.chars().collect(),
+            negated,
+            dir_only,
+        })
+    }
+}
+
+/// Lowercased, `/`-separated, without a trailing separator.
+fn normalize(path: &str) -> String {
+    path.replace('\\', "/").trim_end_matches('/').to_lowercase()
+}
+
+/// Gitignore-style glob match of the whole of `s`.
+fn glob(p: &[char], s: &[char]) -> bool {
+    match p {
+        [] => s.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            glob(rest, s) || (0..s.len()).any(|i| s[i] == '/' && glob(rest, &s[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=s.len()).any(|i| glob(rest, &s[i..])),
+        ['*', rest @ ..] => {
+            let segment = s.iter().position(|&c| c == '/').unwrap_or(s.len());
+            (0..=segment).any(|i| glob(rest, &s[i..]))
+        }
+        ['?', rest @ ..] => s.first().is_some_and(|&c| c != '/') && glob(rest, &s[1..]),
+        ['[', class @ ..] => match class.iter().position(|&c| c == ']') {
+            Some(close) if close > 0 => {
+                let Some(&c) = s.first().filter(|&&c| c != '/') else {
+                    return false;
+                };
+                in_class(&class[..close], c) && glob(&class[close + 1..], &s[1..])
+            }
+            _ => s.first() == Some(&'[') && glob(class, &s[1..]),
+        },
+        [c, rest @ ..] => s.first() == Some(c) && glob(rest, &s[1..]),
+    }
+}
+
+/// `c` against a bracket expression's contents, e.g. `a-z0-9` or `!ab`.
+fn in_class(class: &[char], c: char) -> bool {
+    let (negated, class) = match class {
+        ['!' | '^', rest @ ..] => (true, rest),
+        _ => (false, class),
+    };
+    let mut found = false;
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            found |= (class[i]..=class[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            found |= class[i] == c;
+            i += 1;
+        }
+    }
+    found != negated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(dir: &Path, files: &[(&str, &str)]) -> IgnoreRules {
+        let paths = files.iter().map(|(rel, text)| {
+            let path = dir.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, text).unwrap();
+            path
+        });
+        IgnoreRules::from_files(paths.collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn follows_gitignore_semantics() {
+        let dir = tempfile::tempdir().unwrap();
+        let rules = rules(
+            dir.path(),
+            &[
+                (
+                    ".gitignore",
+                    "# build output\ntarget/\n*.log\n!keep.log\n/dist\ndocs/**/*.tmp\n",
+                ),
+                ("sub/.ignore", "*.bak\nkeep.log\n"),
+            ],
+        );
+        let root = dir.path().to_string_lossy().into_owned();
+        let ignored = |rel: &str, is_dir: bool| rules.is_ignored(&format!("{root}/{rel}"), is_dir);
+
+        assert!(ignored("target", true));
+        assert!(!ignored("target", false), "dir-only pattern");
+        assert!(
+            ignored("crates/a/target/debug/app.exe", false),
+            "below an ignored dir"
+        );
+        assert!(ignored("Build.LOG", false));
+        assert!(!ignored("keep.log", false), "negated");
+        assert!(ignored("sub/keep.log", false), "deeper file overrides");
+        assert!(ignored("dist", true));
+        assert!(!ignored("src/dist", true), "anchored to its folder");
+        assert!(ignored("docs/a/b/x.tmp", false));
+        assert!(!ignored("x.tmp", false));
+        assert!(ignored("sub/old.bak", false));
+        assert!(
+            !ignored("old.bak", false),
+            "rules apply below their folder only"
+        );
+        assert!(ignored(".git/HEAD", false));
+        assert!(!ignored("src/main.rs", false));
+        assert!(glob(
+            &"[a-c]?.rs".chars().collect::<Vec<_>>(),
+            &['b', '1', '.', 'r', 's']
+        ));
+    }
+}
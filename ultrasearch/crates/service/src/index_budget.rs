@@ -0,0 +1,443 @@
+//! `indexing.max_index_bytes`: keep the metadata and content indexes within a disk budget.
+//!
+//! After a content batch commits, both index directories are measured. When together they
+//! exceed the budget, [`plan_trim`] picks entries to drop, least valuable first: content
+//! bodies whose files were accessed longest ago, then metadata for files modified longest
+//! ago. Each entry is costed at its index's average document size; with
+//! `extract.dedup_content`, bodies other files still share are kept and references cost
+//! almost nothing. The chosen documents are deleted and their segments rewritten so the
+//! space is actually released. Trimmed counts are reported in status metrics; a trimmed
+//! body returns if its file is re-extracted, and the most recently trimmed content keys are
+//! remembered so `search.rehydrate_on_miss` only re-extracts files this process trimmed.
+
+use std::collections::{HashSet, VecDeque};
+use std::ops::Bound;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::UNIX_EPOCH;
+
+use anyhow::Result;
+use core_types::DocKey;
+use tantivy::query::{BooleanQuery, Occur, Query, RangeQuery};
+use tantivy::schema::{Field, Value};
+use tantivy::{DocAddress, Index, IndexWriter, TantivyDocument, Term};
+
+use crate::diagnostics::dir_size;
+use crate::index_lock::with_index_write_lock;
+
+/// Deletions per query; keeps each boolean query to a modest number of clauses.
+const DELETE_CHUNK: usize = 1024;
+
+/// Estimated size of a dedup reference, a metadata-only content document.
+const REFERENCE_BYTES: u64 = 256;
+
+/// Most trimmed content keys remembered for rehydration; the oldest are forgotten first.
+const TRIMMED_KEYS_CAP: usize = 65_536;
+
+static TRIMMED_CONTENT: AtomicU64 = AtomicU64::new(0);
+static TRIMMED_META: AtomicU64 = AtomicU64::new(0);
+static TRIMMED_KEYS: LazyLock<Mutex<TrimmedKeys>> = LazyLock::new(Default::default);
+
+/// Content keys trimmed by this process, oldest first.
+#[derive(Default)]
+struct TrimmedKeys {
+    order: VecDeque<DocKey>,
+    keys: HashSet<DocKey>,
+}
+
+/// Which index an entry lives in. Content bodies are trimmed before any metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EntryKind {
+    Content,
+    Meta,
+}
+
+/// One index document considered for trimming.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrimEntry {
+    pub key: DocKey,
+    pub kind: EntryKind,
+    /// Estimated bytes it occupies on disk.
+    pub bytes: u64,
+    /// Unix seconds the file was last accessed (content) or modified (metadata).
+    pub last_used: i64,
+}
+
+/// Documents removed by one [`enforce_budget`] run.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TrimReport {
+    pub content: u64,
+    pub meta: u64,
+}
+
+/// Entries to drop so `used` bytes fit in `budget`, lowest value first: content before
+/// metadata, then oldest `last_used`, ties broken by key. Empty when already within budget.
+pub fn plan_trim(mut entries: Vec<TrimEntry>, used: u64, budget: u64) -> Vec<TrimEntry> {
+    if used <= budget {
+        return Vec::new();
+    }
+    entries.sort_by_key(|e| (e.kind, e.last_used, e.key.0));
+    let mut remaining = used;
+    entries
+        .into_iter()
+        .take_while(|e| {
+            if remaining <= budget {
+                return false;
+            }
+            remaining = remaining.saturating_sub(e.bytes);
+            true
+        })
+        .collect()
+}
+
+/// Trim the indexes at `meta_dir` and `content_dir` until they fit in `budget` bytes
+/// (0 = unlimited). Each index is modified under its write lock.
+pub fn enforce_budget(meta_dir: &Path, content_dir: &Path, budget: u64) -> Result<TrimReport> {
+    let mut report = TrimReport::default();
+    if budget == 0 {
+        return Ok(report);
+    }
+    let used = dir_size(meta_dir) + dir_size(content_dir);
+    if used <= budget {
+        return Ok(report);
+    }
+
+    let content = open_existing(content_dir, |p| Ok(content_index::open_or_create(p)?.index))?;
+    let meta = open_existing(meta_dir, |p| Ok(meta_index::open_or_create_index(p)?.index))?;
+    let mut entries = Vec::new();
+    if let Some(index) = &content {
+        entries.extend(content_entries(index, dir_size(content_dir))?);
+    }
+    if let Some(index) = &meta {
+        entries.extend(meta_entries(index, dir_size(meta_dir))?);
+    }
+    let plan = plan_trim(entries, used, budget);
+
+    let keys_of = |kind| -> Vec<DocKey> {
+        plan.iter()
+            .filter(|e| e.kind == kind)
+            .map(|e| e.key)
+            .collect()
+    };
+    if let Some(index) = &content {
+        let keys = keys_of(EntryKind::Content);
+        with_index_write_lock(content_dir, || delete_keys(index, &keys))?;
+        record_trimmed(&keys);
+        report.content = keys.len() as u64;
+    }
+    if let Some(index) = &meta {
+        let keys = keys_of(EntryKind::Meta);
+        with_index_write_lock(meta_dir, || delete_keys(index, &keys))?;
+        report.meta = keys.len() as u64;
+    }
+    TRIMMED_CONTENT.fetch_add(report.content, Ordering::Relaxed);
+    TRIMMED_META.fetch_add(report.meta, Ordering::Relaxed);
+    Ok(report)
+}
+
+/// Delete the documents with `keys` from the indexes at `meta_dir` and `content_dir`, each
+/// under its write lock; an index not created yet is skipped.
+pub(crate) fn delete_from_indexes(
+    meta_dir: &Path,
+    content_dir: &Path,
+    keys: &[DocKey],
+) -> Result<()> {
+    if let Some(index) =
+        open_existing(content_dir, |p| Ok(content_index::open_or_create(p)?.index))?
+    {
+        with_index_write_lock(content_dir, || delete_keys(&index, keys))?;
+    }
+    if let Some(index) =
+        open_existing(meta_dir, |p| Ok(meta_index::open_or_create_index(p)?.index))?
+    {
+        with_index_write_lock(meta_dir, || delete_keys(&index, keys))?;
+    }
+    Ok(())
+}
+
+/// Content bodies and metadata documents trimmed since the service started.
+pub fn trimmed_totals() -> (u64, u64) {
+    (
+        TRIMMED_CONTENT.load(Ordering::Relaxed),
+        TRIMMED_META.load(Ordering::Relaxed),
+    )
+}
+
+/// Remember `keys` as trimmed from the content index, forgetting the oldest past
+/// [`TRIMMED_KEYS_CAP`].
+pub(crate) fn record_trimmed(keys: &[DocKey]) {
+    let mut trimmed = TRIMMED_KEYS.lock().unwrap_or_else(|e| e.into_inner());
+    for &key in keys {
+        if trimmed.keys.insert(key) {
+            trimmed.order.push_back(key);
+        }
+    }
+    while trimmed.order.len() > TRIMMED_KEYS_CAP {
+        if let Some(old) = trimmed.order.pop_front() {
+            trimmed.keys.remove(&old);
+        }
+    }
+}
+
+/// Whether `key`'s body was trimmed from the content index and not seen back since.
+pub(crate) fn was_trimmed(key: DocKey) -> bool {
+    TRIMMED_KEYS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .keys
+        .contains(&key)
+}
+
+/// Stop treating `key` as trimmed, once its body is back in the content index.
+pub(crate) fn forget_trimmed(key: DocKey) {
+    let mut trimmed = TRIMMED_KEYS.lock().unwrap_or_else(|e| e.into_inner());
+    if trimmed.keys.remove(&key) {
+        trimmed.order.retain(|&k| k != key);
+    }
+}
+
+fn open_existing(dir: &Path, open: impl FnOnce(&Path) -> Result<Index>) -> Result<Option<Index>> {
+    if dir.join("meta.json").exists() {
+        open(dir).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Every content document that can go, valued by its file's last access time (falling back
+/// to the indexed modification time when the file can't be read). With
+/// `extract.dedup_content`, a body still referenced by another document is kept, since the
+/// references would be left pointing at nothing, and references are costed at
+/// [`REFERENCE_BYTES`]; the rest of the directory is spread over the documents storing a body.
+fn content_entries(index: &Index, dir_bytes: u64) -> Result<Vec<TrimEntry>> {
+    let schema = index.schema();
+    let path_field = schema.get_field("path")?;
+    let body_hash = schema.get_field("body_hash").ok();
+    let body_of = schema.get_field("body_of").ok();
+    let u64_of = |doc: &TantivyDocument, field: Option<Field>| {
+        field
+            .and_then(|f| doc.get_first(f))
+            .and_then(|v| v.as_u64())
+    };
+    // (entry, whether it is a reference, the hash of the body it stores)
+    let mut docs = Vec::new();
+    let mut referenced = HashSet::new();
+    for_each_doc(
+        index,
+        dir_bytes,
+        EntryKind::Content,
+        |addr, searcher, mut entry| {
+            let doc: TantivyDocument = searcher.doc(addr)?;
+            let accessed = doc
+                .get_first(path_field)
+                .and_then(|v| v.as_str())
+                .and_then(|p| std::fs::metadata(p).ok())
+                .and_then(|m| m.accessed().ok())
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok());
+            if let Some(accessed) = accessed {
+                entry.last_used = accessed.as_secs() as i64;
+            }
+            if let Some(hash) = u64_of(&doc, body_of) {
+                referenced.insert(hash);
+                entry.bytes = REFERENCE_BYTES;
+                docs.push((entry, true, None));
+            } else {
+                docs.push((entry, false, u64_of(&doc, body_hash)));
+            }
+            Ok(())
+        },
+    )?;
+
+    let references = docs.iter().filter(|(_, reference, _)| *reference).count() as u64;
+    let bodies = docs.len() as u64 - references;
+    let body_bytes = dir_bytes.saturating_sub(references * REFERENCE_BYTES) / bodies.max(1);
+    Ok(docs
+        .into_iter()
+        .filter_map(|(mut entry, reference, hash)| {
+            if reference {
+                return Some(entry);
+            }
+            if hash.is_some_and(|h| referenced.contains(&h)) {
+                return None;
+            }
+            entry.bytes = body_bytes;
+            Some(entry)
+        })
+        .collect())
+}
+
+/// Every metadata document, valued by its file's modification time.
+fn meta_entries(index: &Index, dir_bytes: u64) -> Result<Vec<TrimEntry>> {
+    let mut entries = Vec::new();
+    for_each_doc(index, dir_bytes, EntryKind::Meta, |_, _, entry| {
+        entries.push(entry);
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// Call `f` with an entry (key, `modified` as `last_used`, average size) per live document.
+fn for_each_doc(
+    index: &Index,
+    dir_bytes: u64,
+    kind: EntryKind,
+    mut f: impl FnMut(DocAddress, &tantivy::Searcher, TrimEntry) -> Result<()>,
+) -> Result<()> {
+    let searcher = index.reader()?.searcher();
+    let num_docs = searcher.num_docs();
+    if num_docs == 0 {
+        return Ok(());
+    }
+    let bytes = dir_bytes / num_docs;
+    for (ord, segment) in searcher.segment_readers().iter().enumerate() {
+        let fast = segment.fast_fields();
+        let key = fast.u64("doc_key")?;
+        let modified = fast.i64("modified")?;
+        for doc in segment.doc_ids_alive() {
+            let Some(k) = key.first(doc) else {
+                continue;
+            };
+            let entry = TrimEntry {
+                key: DocKey(k),
+                kind,
+                bytes,
+                last_used: modified.first(doc).unwrap_or(0),
+            };
+            f(DocAddress::new(ord as u32, doc), &searcher, entry)?;
+        }
+    }
+    Ok(())
+}
+
+/// Delete the documents with `keys`, commit, and rewrite the segments holding them so their
+/// space is released.
+fn delete_keys(index: &Index, keys: &[DocKey]) -> Result<()> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    let doc_key = index.schema().get_field("doc_key")?;
+    let mut writer: IndexWriter = index.writer_with_num_threads(1, 64 * 1024 * 1024)?;
+    writer.set_merge_policy(Box::new(tantivy::indexer::NoMergePolicy));
+    for chunk in keys.chunks(DELETE_CHUNK) {
+        writer.delete_query(keys_query(doc_key, chunk))?;
+    }
+    writer.commit()?;
+    let ids: Vec<_> = index
+        .searchable_segment_metas()?
+        .iter()
+        .filter(|m| m.has_deletes())
+        .map(|m| m.id())
+        .collect();
+    if !ids.is_empty() {
+        writer.merge(&ids).wait()?;
+    }
+    writer.garbage_collect_files().wait()?;
+    writer.wait_merging_threads()?;
+    Ok(())
+}
+
+/// Documents whose `doc_key` fast field is one of `keys`.
+fn keys_query(doc_key: Field, keys: &[DocKey]) -> Box<dyn Query> {
+    Box::new(BooleanQuery::new(
+        keys.iter()
+            .map(|key| {
+                let t = Term::from_field_u64(doc_key, key.0);
+                let exact = RangeQuery::new(Bound::Included(t.clone()), Bound::Included(t));
+                (Occur::Should, Box::new(exact) as Box<dyn Query>)
+            })
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: u64, kind: EntryKind, last_used: i64) -> TrimEntry {
+        TrimEntry {
+            key: DocKey(key),
+            kind,
+            bytes: 100,
+            last_used,
+        }
+    }
+
+    #[test]
+    fn over_budget_trims_oldest_content_then_oldest_meta() {
+        let entries = vec![
+            entry(1, EntryKind::Meta, 10),
+            entry(2, EntryKind::Content, 500),
+            entry(3, EntryKind::Content, 100),
+            entry(4, EntryKind::Meta, 5),
+            entry(5, EntryKind::Content, 300),
+            entry(6, EntryKind::Meta, 50),
+        ];
+        // 600 bytes used, 250 allowed: four 100-byte entries must go.
+        let trimmed: Vec<u64> = plan_trim(entries.clone(), 600, 250)
+            .iter()
+            .map(|e| e.key.0)
+            .collect();
+        assert_eq!(trimmed, vec![3, 5, 2, 4]);
+
+        // One entry is enough to get under budget.
+        let trimmed = plan_trim(entries.clone(), 600, 550);
+        assert_eq!(trimmed, vec![entry(3, EntryKind::Content, 100)]);
+        assert!(plan_trim(entries, 600, 600).is_empty());
+    }
+
+    #[test]
+    fn shared_bodies_are_kept_and_references_cost_little() {
+        use content_index::dedup::{BodyDedup, add_content_doc_dedup};
+        use content_index::{ContentDoc, WriterConfig, create_writer};
+        use core_types::{FileFlags, FileMeta};
+
+        let dir = tempfile::tempdir().unwrap();
+        let idx = content_index::open_or_create(dir.path()).unwrap();
+        let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+        let mut dedup = BodyDedup::new(&idx).unwrap();
+        // 1 stores the body 2 references; 3 has a body of its own.
+        for (id, text) in [
+            (1, "shared license"),
+            (2, "shared license"),
+            (3, "own notes"),
+        ] {
+            let meta = FileMeta::new(
+                DocKey::from_parts(1, id),
+                1,
+                None,
+                format!("f{id}.txt"),
+                None,
+                10,
+                0,
+                0,
+                FileFlags::empty(),
+            );
+            let doc = ContentDoc::from_meta(&meta, text.into(), None);
+            add_content_doc_dedup(&mut writer, &idx.fields, &doc, &mut dedup).unwrap();
+        }
+        writer.commit().unwrap();
+
+        let mut entries = content_entries(&idx.index, 10_256).unwrap();
+        entries.sort_by_key(|e| e.key.0);
+        let costs: Vec<(u64, u64)> = entries.iter().map(|e| (e.key.0, e.bytes)).collect();
+        let (reference, own) = (DocKey::from_parts(1, 2).0, DocKey::from_parts(1, 3).0);
+        assert_eq!(
+            costs,
+            vec![
+                (reference, REFERENCE_BYTES),
+                (own, (10_256 - REFERENCE_BYTES) / 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn trimmed_keys_are_remembered_until_forgotten() {
+        let (a, b) = (DocKey::from_parts(9, 1), DocKey::from_parts(9, 2));
+        record_trimmed(&[a, b, a]);
+        assert!(was_trimmed(a) && was_trimmed(b));
+        forget_trimmed(a);
+        assert!(!was_trimmed(a) && was_trimmed(b));
+        forget_trimmed(b);
+    }
+}
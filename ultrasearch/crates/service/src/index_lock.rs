@@ -0,0 +1,104 @@
+//! Process-wide write locks keyed by index directory.
+//!
+//! The service's index writers (metadata ingest, content compaction, budget trimming, removal
+//! of reused record numbers, and reset) run under [`with_index_write_lock`] so two of them
+//! never write the same directory concurrently. Searches only use readers and never take this
+//! lock. Cross-process writers (index-worker) are additionally fenced by tantivy's own writer
+//! lockfile.
+//!
+//! The index epoch is a process-wide generation number bumped whenever an index is replaced
+//! wholesale (e.g. reset); caches derived from index contents compare against it.
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static LOCKS: Lazy<Mutex<HashMap<PathBuf, Arc<Mutex<()>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static EPOCH: AtomicU64 = AtomicU64::new(0);
+
+/// Return the shared lock for an index directory.
+pub fn index_write_lock(path: &Path) -> Arc<Mutex<()>> {
+    let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    LOCKS.lock().entry(key).or_default().clone()
+}
+
+/// Run `f` while holding the write lock for `path`; concurrent callers for the same index wait.
+pub fn with_index_write_lock<R>(path: &Path, f: impl FnOnce() -> R) -> R {
+    let lock = index_write_lock(path);
+    let _guard = lock.lock();
+    f()
+}
+
+/// Current index epoch.
+pub fn index_epoch() -> u64 {
+    EPOCH.load(Ordering::Acquire)
+}
+
+/// Advance the index epoch, returning the new value.
+pub fn bump_index_epoch() -> u64 {
+    EPOCH.fetch_add(1, Ordering::AcqRel) + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn concurrent_commits_on_same_index_serialize() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let barrier = Arc::new(Barrier::new(2));
+
+        let handles: Vec<_> = ["a", "b"]
+            .into_iter()
+            .map(|name| {
+                let path = path.clone();
+                let events = events.clone();
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    with_index_write_lock(&path, || {
+                        events.lock().push(format!("{name}:start"));
+                        thread::sleep(Duration::from_millis(50));
+                        events.lock().push(format!("{name}:end"));
+                    });
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+
+        let events = events.lock();
+        assert_eq!(events.len(), 4);
+        // Each critical section must finish before the next begins.
+        for pair in events.chunks(2) {
+            let owner = pair[0].trim_end_matches(":start");
+            assert_eq!(pair[1], format!("{owner}:end"), "interleaved: {events:?}");
+        }
+    }
+
+    #[test]
+    fn different_indexes_use_different_locks() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = index_write_lock(&dir.path().join("meta"));
+        let content = index_write_lock(&dir.path().join("content"));
+        assert!(!Arc::ptr_eq(&meta, &content));
+
+        let _held = meta.lock();
+        assert!(content.try_lock().is_some());
+        assert!(
+            index_write_lock(&dir.path().join("meta"))
+                .try_lock()
+                .is_none()
+        );
+    }
+}
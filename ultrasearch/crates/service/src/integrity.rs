@@ -0,0 +1,369 @@
+//! Index integrity self-check (`integrity.verify_on_startup`, `search-cli verify`).
+//!
+//! [`verify_index`] only reads: it opens each on-disk component, checks that its structure is
+//! consistent, and reports per-component status plus whether a rebuild (`reset-index`) is
+//! recommended. Nothing is created or repaired, so a missing component is `Skipped`.
+
+use std::collections::HashSet;
+use std::ops::Bound;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+
+use core_types::DocKey;
+use core_types::config::PathsSection;
+use meta_index::fst::{FST_DIR, LayeredFstIndex};
+use meta_index::tags::TagStore;
+use serde::Serialize;
+use tantivy::collector::Count;
+use tantivy::query::RangeQuery;
+use tantivy::schema::{Field, Schema};
+use tantivy::{Index, Searcher, Term};
+
+/// What to check beyond opening and structural consistency.
+#[derive(Debug, Clone, Copy)]
+pub struct VerifyOptions {
+    /// DocKeys sampled from the name FST and looked up in the meta index.
+    pub sample_keys: usize,
+    /// Verify the checksum footer of every segment file (reads the whole index).
+    pub checksums: bool,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            sample_keys: 1000,
+            checksums: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    /// Component not present (e.g. nothing indexed yet).
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentCheck {
+    pub component: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IntegrityReport {
+    pub components: Vec<ComponentCheck>,
+    /// An index component failed; `reset-index` and a rescan rebuild it.
+    pub rebuild_recommended: bool,
+}
+
+impl IntegrityReport {
+    pub fn is_healthy(&self) -> bool {
+        self.components
+            .iter()
+            .all(|c| c.status != CheckStatus::Failed)
+    }
+
+    pub fn status_of(&self, component: &str) -> Option<CheckStatus> {
+        self.components
+            .iter()
+            .find(|c| c.component == component)
+            .map(|c| c.status)
+    }
+}
+
+/// Check the meta index, content index, name FST (cross-referenced against the meta index)
+/// and the tag store under `paths`.
+pub fn verify_index(paths: &PathsSection, opts: &VerifyOptions) -> IntegrityReport {
+    let meta_path = Path::new(&paths.meta_index);
+    let (meta, meta_searcher) = check_tantivy("meta_index", meta_path, opts, |schema| {
+        meta_index::MetaFields::from_schema(schema).map(|f| f.doc_key)
+    });
+    let (content, _) = check_tantivy(
+        "content_index",
+        Path::new(&paths.content_index),
+        opts,
+        |schema| content_index::ContentFields::from_schema(schema).map(|f| f.doc_key),
+    );
+    let fst = check_fst(&meta_path.join(FST_DIR), meta_searcher.as_ref(), opts);
+    let rebuild_recommended = [&meta, &content, &fst]
+        .iter()
+        .any(|c| c.status == CheckStatus::Failed);
+    let tags = check_tag_store(Path::new(&paths.tags_file));
+
+    IntegrityReport {
+        components: vec![meta, content, fst, tags],
+        rebuild_recommended,
+    }
+}
+
+fn ok(component: &'static str, detail: String) -> ComponentCheck {
+    ComponentCheck {
+        component,
+        status: CheckStatus::Ok,
+        detail,
+    }
+}
+
+fn failed(component: &'static str, detail: String) -> ComponentCheck {
+    ComponentCheck {
+        component,
+        status: CheckStatus::Failed,
+        detail,
+    }
+}
+
+fn skipped(component: &'static str, detail: String) -> ComponentCheck {
+    ComponentCheck {
+        component,
+        status: CheckStatus::Skipped,
+        detail,
+    }
+}
+
+/// Open a Tantivy index, validate its schema, segment files and deletes, and optionally its
+/// checksums. On success also returns a searcher and the `doc_key` field for lookups.
+fn check_tantivy(
+    component: &'static str,
+    path: &Path,
+    opts: &VerifyOptions,
+    schema_check: impl Fn(&Schema) -> anyhow::Result<Field>,
+) -> (ComponentCheck, Option<(Searcher, Field)>) {
+    if !path.join("meta.json").exists() {
+        return (
+            skipped(component, format!("no index at {}", path.display())),
+            None,
+        );
+    }
+    // Tantivy asserts on some malformed files instead of returning an error.
+    let inspected = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        inspect_tantivy(path, opts, schema_check)
+    }));
+    match inspected {
+        Ok(Ok((detail, lookup))) => (ok(component, detail), Some(lookup)),
+        Ok(Err(e)) => (failed(component, format!("{e:#}")), None),
+        Err(_) => (
+            failed(
+                component,
+                "index files are malformed (reader panicked)".into(),
+            ),
+            None,
+        ),
+    }
+}
+
+fn inspect_tantivy(
+    path: &Path,
+    opts: &VerifyOptions,
+    schema_check: impl Fn(&Schema) -> anyhow::Result<Field>,
+) -> anyhow::Result<(String, (Searcher, Field))> {
+    let index = Index::open_in_dir(path)?;
+    let doc_key = schema_check(&index.schema())?;
+
+    let segments = index.searchable_segment_metas()?;
+    let mut expected_docs = 0u64;
+    for segment in &segments {
+        if segment.num_deleted_docs() > segment.max_doc() {
+            anyhow::bail!(
+                "segment {} has {} deletes for {} docs",
+                segment.id().short_uuid_string(),
+                segment.num_deleted_docs(),
+                segment.max_doc()
+            );
+        }
+        let missing: Vec<String> = segment
+            .list_files()
+            .into_iter()
+            // `list_files` names a delete bitset even for segments without deletes.
+            .filter(|f| segment.has_deletes() || f.extension().is_none_or(|e| e != "del"))
+            .filter(|f| !path.join(f).exists())
+            .map(|f| f.display().to_string())
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!("missing segment files: {}", missing.join(", "));
+        }
+        expected_docs += u64::from(segment.num_docs());
+    }
+
+    // Checksums first: opening a reader on a damaged segment may fail less descriptively.
+    if opts.checksums {
+        let damaged = index.validate_checksum()?;
+        if !damaged.is_empty() {
+            let mut files: Vec<String> = damaged.iter().map(|f| f.display().to_string()).collect();
+            files.sort();
+            anyhow::bail!("checksum mismatch in {}", files.join(", "));
+        }
+    }
+
+    let searcher = index.reader()?.searcher();
+    let live_docs = searcher.num_docs();
+    if live_docs != expected_docs {
+        anyhow::bail!("segments hold {live_docs} live docs but metadata records {expected_docs}");
+    }
+
+    Ok((
+        format!("{} segments, {live_docs} docs", segments.len()),
+        (searcher, doc_key),
+    ))
+}
+
+/// Open the name FST segments and confirm a sample of their keys exist in the meta index.
+fn check_fst(dir: &Path, meta: Option<&(Searcher, Field)>, opts: &VerifyOptions) -> ComponentCheck {
+    const COMPONENT: &str = "name_fst";
+    if !dir.is_dir() {
+        return skipped(COMPONENT, format!("no FST at {}", dir.display()));
+    }
+    let fst = match LayeredFstIndex::open(dir, 0) {
+        Ok(fst) => fst,
+        Err(e) => return failed(COMPONENT, format!("{e:#}")),
+    };
+    let sample: HashSet<DocKey> = fst.search("", opts.sample_keys).into_iter().collect();
+    let Some((searcher, doc_key)) = meta else {
+        return ok(
+            COMPONENT,
+            format!(
+                "{} segments; meta index unavailable, cross-check skipped",
+                fst.segment_count()
+            ),
+        );
+    };
+
+    let mut dangling = 0usize;
+    for key in &sample {
+        let term = Term::from_field_u64(*doc_key, key.0);
+        let query = RangeQuery::new(Bound::Included(term.clone()), Bound::Included(term));
+        match searcher.search(&query, &Count) {
+            Ok(0) => dangling += 1,
+            Ok(_) => {}
+            Err(e) => return failed(COMPONENT, format!("meta lookup failed: {e}")),
+        }
+    }
+    if dangling > 0 {
+        return failed(
+            COMPONENT,
+            format!(
+                "{dangling} of {} sampled keys missing from the meta index",
+                sample.len()
+            ),
+        );
+    }
+    ok(
+        COMPONENT,
+        format!(
+            "{} segments, {} sampled keys found in the meta index",
+            fst.segment_count(),
+            sample.len()
+        ),
+    )
+}
+
+/// Tags are user data: a damaged store is reported but a rebuild would not restore it.
+fn check_tag_store(path: &Path) -> ComponentCheck {
+    const COMPONENT: &str = "tag_store";
+    if !path.exists() {
+        return skipped(COMPONENT, format!("no tag store at {}", path.display()));
+    }
+    match TagStore::open(path) {
+        Ok(_) => ok(COMPONENT, "readable".into()),
+        Err(e) => failed(COMPONENT, format!("{e:#}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::meta_ingest::ingest_file_meta_batch;
+    use content_index::ContentDoc;
+    use core_types::{FileFlags, FileMeta};
+    use std::fs;
+
+    fn paths_in(root: &Path) -> PathsSection {
+        PathsSection {
+            meta_index: root.join("meta").to_string_lossy().into_owned(),
+            content_index: root.join("content").to_string_lossy().into_owned(),
+            state_dir: root.join("state").to_string_lossy().into_owned(),
+            jobs_dir: root.join("jobs").to_string_lossy().into_owned(),
+            tags_file: root.join("tags.rkyv").to_string_lossy().into_owned(),
+        }
+    }
+
+    fn populate(paths: &PathsSection) {
+        let meta = FileMeta::new(
+            DocKey::from_parts(1, 7),
+            1,
+            None,
+            "notes.txt".into(),
+            Some(r"C:\notes.txt".into()),
+            5,
+            0,
+            0,
+            FileFlags::empty(),
+        );
+        fs::create_dir_all(&paths.meta_index).unwrap();
+        ingest_file_meta_batch(Path::new(&paths.meta_index), vec![meta.clone()], None).unwrap();
+
+        let mut fst =
+            LayeredFstIndex::open(&Path::new(&paths.meta_index).join(FST_DIR), 1 << 20).unwrap();
+        fst.insert("notes.txt", meta.key).unwrap();
+        fst.commit().unwrap();
+
+        fs::create_dir_all(&paths.content_index).unwrap();
+        let content = content_index::open_or_create(Path::new(&paths.content_index)).unwrap();
+        let mut writer = content_index::create_writer(
+            &content,
+            &content_index::WriterConfig {
+                heap_size_bytes: 15_000_000,
+                num_threads: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let doc = ContentDoc::from_meta(&meta, "hello".into(), None);
+        content_index::add_content_doc(&mut writer, &content.fields, &doc).unwrap();
+        writer.commit().unwrap();
+    }
+
+    #[test]
+    fn healthy_index_passes_and_corrupted_segment_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = paths_in(dir.path());
+        let opts = VerifyOptions {
+            checksums: true,
+            ..VerifyOptions::default()
+        };
+
+        let empty = verify_index(&paths, &opts);
+        assert!(empty.is_healthy());
+        assert_eq!(empty.status_of("meta_index"), Some(CheckStatus::Skipped));
+
+        populate(&paths);
+        let healthy = verify_index(&paths, &opts);
+        assert!(healthy.is_healthy(), "{healthy:?}");
+        assert!(!healthy.rebuild_recommended);
+        assert_eq!(healthy.status_of("meta_index"), Some(CheckStatus::Ok));
+        assert_eq!(healthy.status_of("content_index"), Some(CheckStatus::Ok));
+        assert_eq!(healthy.status_of("name_fst"), Some(CheckStatus::Ok));
+
+        // Flip bytes in the middle of a content segment's doc store.
+        let store = fs::read_dir(&paths.content_index)
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .find(|p| p.extension().is_some_and(|e| e == "store"))
+            .unwrap();
+        let mut bytes = fs::read(&store).unwrap();
+        let mid = bytes.len() / 2;
+        bytes[mid] ^= 0xFF;
+        fs::write(&store, bytes).unwrap();
+
+        let corrupted = verify_index(&paths, &opts);
+        assert_eq!(
+            corrupted.status_of("content_index"),
+            Some(CheckStatus::Failed)
+        );
+        assert_eq!(corrupted.status_of("meta_index"), Some(CheckStatus::Ok));
+        assert!(corrupted.rebuild_recommended);
+    }
+}
@@ -1,19 +1,27 @@
 #![cfg(target_os = "windows")]
 
-use std::env;
 use std::time::Instant;
 
-use crate::metrics::{global_metrics_snapshot, record_ipc_request};
-use crate::search_handler::search;
-use crate::status::make_status_response;
-use crate::status_provider::status_snapshot;
-use anyhow::Result;
-use ipc::{
-    MetricsSnapshot, ReloadConfigRequest, ReloadConfigResponse, RescanRequest, RescanResponse,
-    SearchRequest, StatusRequest, framing,
+use crate::audit::audit_log;
+use crate::diagnostics;
+use crate::metrics::record_ipc_request;
+use crate::pipe_accept::{AcceptBackoff, PipeAcceptor, create_with_backoff};
+use crate::search_handler::{
+    InstalledSearchHandler, answer_search, cancel_search, finish_search_response, search_anytime,
+    stream_search,
 };
+use crate::status::current_status;
+use anyhow::Result;
+use core_types::config::IpcSection;
 #[cfg(test)]
 use ipc::{SearchResponse, StatusResponse};
+use ipc::{
+    CancelRequest, CancelResponse, ClearQueryHistoryRequest, DiagnosticsRequest,
+    DiagnosticsResponse, ExportResultsRequest, ReloadConfigRequest, ReloadConfigResponse,
+    RescanRequest, RescanResponse, ResetIndexRequest, ResetIndexResponse, SchedulerTraceRequest,
+    SchedulerTraceResponse, SearchRequest, SearchStreamFrame, SearchStreamRequest, StatusRequest,
+    SuggestRequest, TagRequest, VerifyRequest, VerifyResponse, framing,
+};
 use std::io::Cursor;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::windows::named_pipe::NamedPipeServer;
@@ -23,25 +31,39 @@ use uuid::Uuid;
 const DEFAULT_PIPE_NAME: &str = r#"\\.\pipe\ultrasearch"#;
 const MAX_MESSAGE_BYTES: usize = 256 * 1024;
 
+/// Creates pipe instances with the service's security descriptor.
+struct SecurePipeAcceptor {
+    name: String,
+}
+
+impl PipeAcceptor for SecurePipeAcceptor {
+    type Server = NamedPipeServer;
+
+    fn create(&mut self, first: bool) -> Result<NamedPipeServer> {
+        // Use raw Win32 API to create pipe with Security Descriptor
+        // SDDL: D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;GRGW;;;AU)
+        // SY=System, BA=Admins, AU=Authenticated Users (Read/Write)
+        unsafe { create_secure_pipe(&self.name, first) }
+    }
+}
+
 /// Start a Tokio named-pipe server that spawns a task per connection.
-pub async fn start_pipe_server(pipe_name: Option<&str>) -> Result<JoinHandle<()>> {
-    let name = pipe_name.unwrap_or(DEFAULT_PIPE_NAME).to_string();
+pub async fn start_pipe_server(
+    pipe_name: Option<&str>,
+    ipc_cfg: &IpcSection,
+) -> Result<JoinHandle<()>> {
+    let mut acceptor = SecurePipeAcceptor {
+        name: pipe_name.unwrap_or(DEFAULT_PIPE_NAME).to_string(),
+    };
+    let mut backoff = AcceptBackoff::new(ipc_cfg);
 
     let handle = tokio::spawn(async move {
         let mut first = true;
         loop {
-            // Use raw Win32 API to create pipe with Security Descriptor
-            // SDDL: D:(A;;GA;;;SY)(A;;GA;;;BA)(A;;GRGW;;;AU)
-            // SY=System, BA=Admins, AU=Authenticated Users (Read/Write)
-            let server = match unsafe { create_secure_pipe(&name, first) } {
-                Ok(s) => s,
-                Err(e) => {
-                    // Likely another instance owns the pipe. Back off and retry, but escalate to warning after a few tries.
-                    tracing::warn!("named pipe create failed ({}); retrying in 1s", e);
-                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
-                    continue;
-                }
-            };
+            // Create failures (e.g. another instance owns the pipe) back off exponentially and
+            // escalate to an error once the server is effectively down.
+            let server =
+                create_with_backoff(&mut acceptor, first, &mut backoff, tokio::time::sleep).await;
 
             first = false;
 
@@ -50,8 +72,9 @@ pub async fn start_pipe_server(pipe_name: Option<&str>) -> Result<JoinHandle<()>
                 continue;
             }
 
+            let client = pipe_client_label(&server);
             tokio::spawn(async move {
-                if let Err(e) = handle_connection(server).await {
+                if let Err(e) = handle_connection(server, client).await {
                     tracing::warn!("pipe connection error: {e:?}");
                 }
             });
@@ -61,6 +84,18 @@ pub async fn start_pipe_server(pipe_name: Option<&str>) -> Result<JoinHandle<()>
     Ok(handle)
 }
 
+/// Identify the connected client by process id for the audit log.
+fn pipe_client_label(server: &NamedPipeServer) -> Option<String> {
+    use std::os::windows::io::AsRawHandle;
+    use windows::Win32::Foundation::HANDLE;
+    use windows::Win32::System::Pipes::GetNamedPipeClientProcessId;
+
+    let mut pid = 0u32;
+    unsafe { GetNamedPipeClientProcessId(HANDLE(server.as_raw_handle() as isize), &mut pid) }
+        .ok()?;
+    Some(format!("pid:{pid}"))
+}
+
 unsafe fn create_secure_pipe(name: &str, first: bool) -> Result<NamedPipeServer> {
     use std::ffi::OsStr;
     use std::os::windows::ffi::OsStrExt;
@@ -147,7 +182,7 @@ unsafe fn create_secure_pipe(name: &str, first: bool) -> Result<NamedPipeServer>
     Ok(server)
 }
 
-async fn handle_connection(mut conn: NamedPipeServer) -> Result<()> {
+async fn handle_connection(mut conn: NamedPipeServer, client: Option<String>) -> Result<()> {
     loop {
         // decode frame
         let mut len_prefix = [0u8; 4];
@@ -171,23 +206,87 @@ async fn handle_connection(mut conn: NamedPipeServer) -> Result<()> {
         // But wait, `buf` IS the payload.
         // framing::decode_frame also checks length.
 
-        let response = dispatch(&buf);
-        let framed = framing::encode_frame(&response).unwrap_or_default();
-        // framed includes length prefix.
+        if let Some(req) = deserialize_exact::<SearchStreamRequest>(&buf) {
+            stream_search_frames(&mut conn, req, client.clone()).await?;
+            continue;
+        }
+        if let Some(req) = deserialize_exact::<SearchRequest>(&buf).filter(|r| r.anytime) {
+            stream_anytime(&mut conn, req, client.clone()).await?;
+            continue;
+        }
+
+        let response = dispatch(&buf, client.as_deref());
+        // Responses over one frame (large previews/extractions) go out as sequenced chunks.
+        let framed = framing::encode_chunked(&response, framing::MAX_FRAME).unwrap_or_default();
         conn.write_all(&framed).await?;
     }
     Ok(())
 }
 
-fn dispatch(payload: &[u8]) -> Vec<u8> {
-    fn deserialize_exact<T: serde::de::DeserializeOwned>(payload: &[u8]) -> Option<T> {
-        let mut cursor = Cursor::new(payload);
-        match bincode::deserialize_from::<_, T>(&mut cursor) {
-            Ok(v) if cursor.position() as usize == payload.len() => Some(v),
-            _ => None,
-        }
+/// Write each stage of an `anytime` search as its own framed response while later stages are
+/// still being scored. A failed write drops the receiver, which stops the search.
+async fn stream_anytime(
+    conn: &mut NamedPipeServer,
+    req: SearchRequest,
+    client: Option<String>,
+) -> Result<()> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Vec<u8>>();
+    let worker = tokio::task::spawn_blocking(move || {
+        let start = Instant::now();
+        let audited = req.clone();
+        search_anytime(req, &mut |mut resp| {
+            finish_search_response(&audited, &mut resp, start);
+            if !resp.refining
+                && let Some(log) = audit_log()
+                && let Err(e) = log.record_search(&audited, &resp, client.as_deref())
+            {
+                tracing::warn!("failed to write search audit record: {e:#}");
+            }
+            tx.send(bincode::serialize(&resp).unwrap_or_default()).is_ok()
+        });
+        record_ipc_request(start.elapsed());
+    });
+    while let Some(response) = rx.recv().await {
+        let framed = framing::encode_chunked(&response, framing::MAX_FRAME).unwrap_or_default();
+        conn.write_all(&framed).await?;
     }
+    worker.await?;
+    Ok(())
+}
 
+/// Write a streamed search's frames as they are produced. A failed write drops the receiver,
+/// which stops the search before its next batch.
+async fn stream_search_frames(
+    conn: &mut NamedPipeServer,
+    req: SearchStreamRequest,
+    client: Option<String>,
+) -> Result<()> {
+    // Bounded, so a slow reader holds back paging instead of buffering every batch.
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<u8>>(4);
+    let worker = tokio::task::spawn_blocking(move || {
+        let mut send = |frame: SearchStreamFrame| {
+            tx.blocking_send(bincode::serialize(&frame).unwrap_or_default())
+                .is_ok()
+        };
+        stream_search(&InstalledSearchHandler, req, client.as_deref(), &mut send);
+    });
+    while let Some(frame) = rx.recv().await {
+        let framed = framing::encode_chunked(&frame, framing::MAX_FRAME).unwrap_or_default();
+        conn.write_all(&framed).await?;
+    }
+    worker.await?;
+    Ok(())
+}
+
+fn deserialize_exact<T: serde::de::DeserializeOwned>(payload: &[u8]) -> Option<T> {
+    let mut cursor = Cursor::new(payload);
+    match bincode::deserialize_from::<_, T>(&mut cursor) {
+        Ok(v) if cursor.position() as usize == payload.len() => Some(v),
+        _ => None,
+    }
+}
+
+fn dispatch(payload: &[u8], client: Option<&str>) -> Vec<u8> {
     // Fast-path: ping echo when payload is prefixed with "PING" + UUID.
     if payload.len() >= 20
         && payload.starts_with(b"PING")
@@ -199,33 +298,7 @@ fn dispatch(payload: &[u8]) -> Vec<u8> {
     // Try StatusRequest first.
     if let Some(req) = deserialize_exact::<StatusRequest>(payload) {
         let started = Instant::now();
-        let snap = status_snapshot();
-        let empty_metrics =
-            snap.metrics.or(
-                global_metrics_snapshot(Some(0), Some(0), Some(0), Some(0)).or(Some(
-                    MetricsSnapshot {
-                        search_latency_ms_p50: None,
-                        search_latency_ms_p95: None,
-                        worker_cpu_pct: None,
-                        worker_mem_bytes: None,
-                        queue_depth: Some(0),
-                        active_workers: Some(0),
-                        content_enqueued: Some(0),
-                        content_dropped: Some(0),
-                    },
-                )),
-            );
-        let resp = make_status_response(
-            req.id,
-            snap.volumes,
-            snap.scheduler_state,
-            empty_metrics,
-            snap.last_index_commit_ts,
-            snap.content_jobs_total,
-            snap.content_jobs_remaining,
-            snap.content_bytes_total,
-            snap.content_bytes_remaining,
-        );
+        let resp = current_status(req.id);
         let encoded = bincode::serialize(&resp).unwrap_or_default();
         record_ipc_request(started.elapsed());
         return encoded;
@@ -278,39 +351,158 @@ fn dispatch(payload: &[u8]) -> Vec<u8> {
         return encoded;
     }
 
-    // Fallback: dispatch SearchRequest.
-    if let Some(req) = deserialize_exact::<SearchRequest>(payload) {
-        let start = Instant::now();
-        let req_clone = req.clone();
-        let mut resp = search(req);
-        // Ensure the echoed id always matches the request for protocol stability.
-        // search(req) should propagate id, but we enforce it defensively.
-        // Use the id already in resp if set, otherwise fallback to request id.
-        if resp.id.is_nil() {
-            resp.id = req_clone.id;
-        }
-        let elapsed = start.elapsed();
-        let took = elapsed.as_millis().min(u32::MAX as u128) as u32;
-        if resp.took_ms == 0 {
-            resp.took_ms = took;
-        }
-        if resp.served_by.is_none() {
-            resp.served_by = Some(host_label());
-        }
+    // Handle DiagnosticsRequest (distinct shape from the id-only requests above).
+    if let Some(req) = deserialize_exact::<DiagnosticsRequest>(payload) {
+        let started = Instant::now();
+        let cfg = core_types::config::get_current_config();
+        let inputs = diagnostics::gather_inputs(&cfg, req.log_tail_lines as usize);
+        let bundle = diagnostics::build_bundle(&inputs);
+        let resp = match serde_json::to_string_pretty(&bundle) {
+            Ok(bundle_json) => DiagnosticsResponse {
+                id: req.id,
+                success: true,
+                bundle_json,
+                message: None,
+            },
+            Err(e) => DiagnosticsResponse {
+                id: req.id,
+                success: false,
+                bundle_json: String::new(),
+                message: Some(e.to_string()),
+            },
+        };
         let encoded = bincode::serialize(&resp).unwrap_or_default();
-        record_ipc_request(elapsed);
+        record_ipc_request(started.elapsed());
         return encoded;
     }
+
+    // Handle ClearQueryHistoryRequest (uuid + u64 + string). A reset token would have to
+    // start with its own length minus eight as a little-endian u64 to decode as one.
+    if let Some(req) = deserialize_exact::<ClearQueryHistoryRequest>(payload) {
+        let started = Instant::now();
+        let history = crate::query_history::query_history();
+        let resp = crate::query_history::clear_history(history.as_deref(), &req);
+        let encoded = bincode::serialize(&resp).unwrap_or_default();
+        record_ipc_request(started.elapsed());
+        return encoded;
+    }
+
+    // Handle SuggestRequest (uuid + string + u16). Never a CancelRequest: a cancel's second
+    // uuid leaves no bytes for the u16, and a suggestion's 16-byte prefix leaves them unread.
+    if let Some(req) = deserialize_exact::<SuggestRequest>(payload) {
+        let started = Instant::now();
+        let history = crate::query_history::query_history();
+        let resp =
+            crate::query_history::suggest(&InstalledSearchHandler, history.as_deref(), &req);
+        let encoded = bincode::serialize(&resp).unwrap_or_default();
+        record_ipc_request(started.elapsed());
+        return encoded;
+    }
+
+    // Handle CancelRequest (two uuids). Tried before ResetIndexRequest, which would read the
+    // second uuid as a 16-byte token whenever its bytes happen to be valid UTF-8; issued
+    // reset tokens are 12 characters, so a real reset never takes this shape.
+    if let Some(req) = deserialize_exact::<CancelRequest>(payload) {
+        let started = Instant::now();
+        let resp = CancelResponse {
+            id: req.id,
+            cancelled: cancel_search(req.token),
+        };
+        let encoded = bincode::serialize(&resp).unwrap_or_default();
+        record_ipc_request(started.elapsed());
+        return encoded;
+    }
+
+    // Handle ResetIndexRequest (uuid + token string; never decodes as a SearchRequest).
+    if let Some(req) = deserialize_exact::<ResetIndexRequest>(payload) {
+        let started = Instant::now();
+        let cfg = core_types::config::get_current_config();
+        let reset = crate::reset::reset_indexes(&cfg.paths, &cfg.meta_store, &req.confirm_token);
+        let (success, message) = match reset {
+            Ok(summary) => (
+                true,
+                Some(format!(
+                    "Removed {} meta docs and {} content docs (epoch {})",
+                    summary.meta_docs_removed, summary.content_docs_removed, summary.epoch
+                )),
+            ),
+            Err(e) => (false, Some(e.to_string())),
+        };
+        let resp = ResetIndexResponse {
+            id: req.id,
+            success,
+            message,
+        };
+        let encoded = bincode::serialize(&resp).unwrap_or_default();
+        record_ipc_request(started.elapsed());
+        return encoded;
+    }
+
+    // Handle SchedulerTraceRequest (uuid + u16).
+    if let Some(req) = deserialize_exact::<SchedulerTraceRequest>(payload) {
+        let started = Instant::now();
+        let log = crate::scheduler_trace::decision_log().lock();
+        let resp = SchedulerTraceResponse {
+            id: req.id,
+            enabled: log.capacity() > 0,
+            decisions_json: serde_json::to_string_pretty(&log.recent(req.limit as usize))
+                .unwrap_or_default(),
+        };
+        drop(log);
+        let encoded = bincode::serialize(&resp).unwrap_or_default();
+        record_ipc_request(started.elapsed());
+        return encoded;
+    }
+
+    // Handle VerifyRequest (uuid + u32 + bool; shorter than any ResetIndexRequest).
+    if let Some(req) = deserialize_exact::<VerifyRequest>(payload) {
+        let started = Instant::now();
+        let cfg = core_types::config::get_current_config();
+        let opts = crate::integrity::VerifyOptions {
+            sample_keys: req.sample_keys as usize,
+            checksums: req.checksums,
+        };
+        let report = crate::integrity::verify_index(&cfg.paths, &opts);
+        let resp = VerifyResponse {
+            id: req.id,
+            healthy: report.is_healthy(),
+            rebuild_recommended: report.rebuild_recommended,
+            report_json: serde_json::to_string_pretty(&report).unwrap_or_default(),
+        };
+        let encoded = bincode::serialize(&resp).unwrap_or_default();
+        record_ipc_request(started.elapsed());
+        return encoded;
+    }
+
+    // Handle ExportResultsRequest (two uuids + two u32s).
+    if let Some(req) = deserialize_exact::<ExportResultsRequest>(payload) {
+        let started = Instant::now();
+        let cache = crate::result_sets::result_sets();
+        let resp = crate::result_sets::export_results(cache.as_deref(), &req);
+        let encoded = bincode::serialize(&resp).unwrap_or_default();
+        record_ipc_request(started.elapsed());
+        return encoded;
+    }
+
+    // Handle TagRequest (uuid + doc key + two string lists).
+    if let Some(req) = deserialize_exact::<TagRequest>(payload) {
+        let started = Instant::now();
+        let store = crate::tags::tag_store();
+        let resp = crate::tags::apply_tag_request(store.as_deref(), &req);
+        let encoded = bincode::serialize(&resp).unwrap_or_default();
+        record_ipc_request(started.elapsed());
+        return encoded;
+    }
+
+    // Fallback: dispatch SearchRequest.
+    if let Some(req) = deserialize_exact::<SearchRequest>(payload) {
+        let resp = answer_search(&InstalledSearchHandler, req, client);
+        return bincode::serialize(&resp).unwrap_or_default();
+    }
     // If payload decodes as a UUID prefix, echo it back.
     Vec::new()
 }
 
-fn host_label() -> String {
-    env::var("COMPUTERNAME")
-        .or_else(|_| env::var("HOSTNAME"))
-        .unwrap_or_else(|_| "service".into())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -320,14 +512,14 @@ mod tests {
         let id = Uuid::new_v4();
         let mut payload = b"PING".to_vec();
         payload.extend_from_slice(id.as_bytes());
-        let resp = dispatch(&payload);
+        let resp = dispatch(&payload, None);
         assert_eq!(resp, id.as_bytes());
     }
 
     #[test]
     fn status_request_roundtrip() {
         let req = StatusRequest { id: Uuid::new_v4() };
-        let resp_bytes = dispatch(&bincode::serialize(&req).unwrap());
+        let resp_bytes = dispatch(&bincode::serialize(&req).unwrap(), None);
         let resp: StatusResponse = bincode::deserialize(&resp_bytes).unwrap();
         assert_eq!(resp.id, req.id);
         assert!(resp.volumes.is_empty());
@@ -335,6 +527,20 @@ mod tests {
         assert!(resp.served_by.is_some());
     }
 
+    #[test]
+    fn diagnostics_request_returns_bundle() {
+        let req = DiagnosticsRequest {
+            id: Uuid::new_v4(),
+            log_tail_lines: 10,
+        };
+        let resp_bytes = dispatch(&bincode::serialize(&req).unwrap(), None);
+        let resp: DiagnosticsResponse = bincode::deserialize(&resp_bytes).unwrap();
+        assert_eq!(resp.id, req.id);
+        assert!(resp.success);
+        let bundle: serde_json::Value = serde_json::from_str(&resp.bundle_json).unwrap();
+        assert!(bundle.get("config").is_some());
+    }
+
     #[test]
     fn search_request_echoes_id() {
         let req = SearchRequest {
@@ -348,8 +554,25 @@ mod tests {
             mode: ipc::SearchMode::Auto,
             timeout: None,
             offset: 0,
+            group_by: None,
+            fields: None,
+            refine: None,
+            sort: None,
+            snippets_per_hit: 1,
+            cursor: None,
+            total_bytes: false,
+            context_path: None,
+            extract_status: false,
+            anytime: false,
+            explain: false,
+            deterministic_order: false,
+            max_hits_per_dir: None,
+            result_set: false,
+            refine_set: None,
+            cancel_token: None,
+            query_text: None,
         };
-        let resp_bytes = dispatch(&bincode::serialize(&req).unwrap());
+        let resp_bytes = dispatch(&bincode::serialize(&req).unwrap(), None);
         let resp: SearchResponse = bincode::deserialize(&resp_bytes).unwrap();
         assert_eq!(resp.id, req.id);
         assert!(resp.hits.is_empty());
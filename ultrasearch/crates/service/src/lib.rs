@@ -1,18 +1,44 @@
 //! Service support library: tracing/logging bootstrap and metrics helpers.
 
+pub mod audit;
 pub mod bootstrap;
+pub mod byte_total;
+pub mod context_boost;
+pub mod diagnostics;
+pub mod disk_slots;
 pub mod dispatcher;
+pub mod extract_status;
+pub mod former_names;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod ignore_files;
+pub mod index_budget;
+pub mod index_lock;
+pub mod integrity;
+pub mod locked_retry;
 mod logging;
 pub mod memory;
 pub mod meta_ingest;
 pub mod metrics;
+pub mod name_rank;
+pub mod object_ids;
+pub mod pipe_accept;
 pub mod planner;
 pub mod priority;
+pub mod query_history;
+pub mod query_prefetch;
+pub mod reset;
+pub mod result_sets;
 pub mod scanner;
+pub mod score_cap;
 pub mod scheduler_runtime;
+pub mod scheduler_trace;
 pub mod search_handler;
+pub mod snippet_cache;
 pub mod status;
 pub mod status_provider;
+pub mod tags;
+mod telemetry;
 
 #[cfg(windows)]
 pub mod windows;
@@ -32,6 +58,7 @@ pub use search_handler::{
 pub use status_provider::{
     BasicStatusProvider, init_basic_status_provider, set_status_provider, status_snapshot,
 };
+pub use telemetry::shutdown_telemetry;
 
 use core_types::config::AppConfig;
 use ntfs_watcher::discover_volumes;
@@ -227,6 +254,23 @@ mod e2e_windows_tests {
             mode: SearchMode::NameOnly,
             timeout: Some(Duration::from_secs(2)),
             offset: 0,
+            group_by: None,
+            fields: None,
+            refine: None,
+            sort: None,
+            snippets_per_hit: 1,
+            cursor: None,
+            total_bytes: false,
+            context_path: None,
+            extract_status: false,
+            anytime: false,
+            explain: false,
+            deterministic_order: false,
+            max_hits_per_dir: None,
+            result_set: false,
+            refine_set: None,
+            cancel_token: None,
+            query_text: None,
         };
         let resp = client.search(search_req).await?;
         assert!(
@@ -251,6 +295,23 @@ mod e2e_windows_tests {
                     mode: SearchMode::Content,
                     timeout: Some(Duration::from_secs(2)),
                     offset: 0,
+                    group_by: None,
+                    fields: None,
+                    refine: None,
+                    sort: None,
+                    snippets_per_hit: 1,
+                    cursor: None,
+                    total_bytes: false,
+                    context_path: None,
+                    extract_status: false,
+                    anytime: false,
+                    explain: false,
+                    deterministic_order: false,
+                    max_hits_per_dir: None,
+                    result_set: false,
+                    refine_set: None,
+                    cancel_token: None,
+                    query_text: None,
                 };
                 let resp = client.search(content_req).await?;
                 if resp.total > 0 {
@@ -302,8 +363,12 @@ mod e2e_windows_tests {
             ext: Some("txt".into()),
             size: 20,
             modified: now_ts(),
+            created: now_ts(),
             content_lang: Some("en".into()),
             content: "lorem ipsum ultrasearch content".into(),
+            mime: None,
+            email: None,
+            title: None,
         };
         add_content_doc(&mut writer, &content_idx.fields, &doc)?;
         writer.commit()?;
@@ -368,6 +433,23 @@ mod e2e_windows_tests {
             mode: SearchMode::Content,
             timeout: Some(Duration::from_secs(2)),
             offset: 0,
+            group_by: None,
+            fields: None,
+            refine: None,
+            sort: None,
+            snippets_per_hit: 1,
+            cursor: None,
+            total_bytes: false,
+            context_path: None,
+            extract_status: false,
+            anytime: false,
+            explain: false,
+            deterministic_order: false,
+            max_hits_per_dir: None,
+            result_set: false,
+            refine_set: None,
+            cancel_token: None,
+            query_text: None,
         };
         let resp = client.search(search_req).await?;
         assert!(
@@ -475,6 +557,23 @@ mod e2e_windows_tests {
             mode: SearchMode::NameOnly,
             timeout: Some(Duration::from_secs(2)),
             offset: 0,
+            group_by: None,
+            fields: None,
+            refine: None,
+            sort: None,
+            snippets_per_hit: 1,
+            cursor: None,
+            total_bytes: false,
+            context_path: None,
+            extract_status: false,
+            anytime: false,
+            explain: false,
+            deterministic_order: false,
+            max_hits_per_dir: None,
+            result_set: false,
+            refine_set: None,
+            cancel_token: None,
+            query_text: None,
         };
         let resp = client.search(search_req).await?;
         assert!(
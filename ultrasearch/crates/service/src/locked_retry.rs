@@ -0,0 +1,101 @@
+//! Content jobs deferred because another process held the file locked.
+//!
+//! When a file can't be opened because of a sharing or lock violation, the index worker appends
+//! its job to [`LOCKED_RETRY_FILE`] in `paths.jobs_dir` instead of dead-lettering it, with the
+//! time of the next attempt (`extract.locked_retry_backoff_secs`, doubling per attempt). The
+//! scheduler takes due jobs back into its content queue; once `extract.locked_retry_attempts`
+//! run out the worker dead-letters the file as failed.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::dispatcher::job_dispatch::JobSpec;
+
+/// Retry queue written by the index worker beside its job files.
+pub const LOCKED_RETRY_FILE: &str = "locked_retry.jsonl";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LockedRetry {
+    job: JobSpec,
+    /// Unix seconds before which the file is not retried.
+    not_before: i64,
+}
+
+/// Remove the jobs due at `now` (unix seconds) from the queue at `path` and return them.
+/// Jobs not yet due are written back; unparsable lines are dropped with them. A missing file
+/// is an empty queue.
+pub fn take_due(path: &Path, now: i64) -> Result<Vec<JobSpec>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).context("read locked-file retry queue"),
+    };
+    let (due, later): (Vec<_>, Vec<_>) = text
+        .lines()
+        .filter_map(|line| serde_json::from_str::<LockedRetry>(line).ok())
+        .partition(|r| r.not_before <= now);
+    if due.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut rest = String::new();
+    for record in &later {
+        rest.push_str(&serde_json::to_string(record)?);
+        rest.push('\n');
+    }
+    // Atomic write: write to tmp, rename.
+    let tmp_path = path.with_extension("tmp");
+    fs::write(&tmp_path, rest).context("write tmp locked-file retry queue")?;
+    fs::rename(&tmp_path, path).context("rename locked-file retry queue")?;
+    Ok(due.into_iter().map(|r| r.job).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn record(file_id: u64, attempt: u32, not_before: i64) -> String {
+        // The shape the index worker appends.
+        serde_json::json!({
+            "job": {
+                "volume_id": 1,
+                "file_id": file_id,
+                "path": format!(r"C:\data\{file_id}.db"),
+                "max_bytes": null,
+                "max_chars": null,
+                "file_size": 10,
+                "content_kind": null,
+                "attempt": attempt,
+            },
+            "not_before": not_before,
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn due_jobs_are_taken_and_later_ones_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LOCKED_RETRY_FILE);
+        assert!(take_due(&path, 1000).unwrap().is_empty());
+
+        let queue = [record(1, 1, 900), record(2, 3, 1500), record(3, 2, 1000)].join("\n");
+        fs::write(&path, queue + "\nnot json\n").unwrap();
+
+        let due = take_due(&path, 1000).unwrap();
+        let ids: Vec<_> = due.iter().map(|j| (j.file_id, j.attempt)).collect();
+        assert_eq!(ids, vec![(1, 1), (3, 2)]);
+        assert_eq!(due[0].path, PathBuf::from(r"C:\data\1.db"));
+
+        // Not due yet: left in the queue until its time comes.
+        assert!(take_due(&path, 1200).unwrap().is_empty());
+        let later = take_due(&path, 1500).unwrap();
+        assert_eq!(later.len(), 1);
+        assert_eq!(later[0].file_id, 2);
+        assert!(take_due(&path, i64::MAX).unwrap().is_empty());
+    }
+}
@@ -21,6 +21,7 @@ use tracing_subscriber::{
 ///
 /// - Honors `logging.level` from config, falling back to `RUST_LOG` then `info`.
 /// - Writes JSON logs to the configured rolling file (daily) and stdout (json or text per cfg).
+/// - Exports spans over OTLP when `logging.otlp_endpoint` is set (`otel` feature).
 pub fn init_tracing_with_config(
     cfg: &LoggingSection,
 ) -> Result<tracing_appender::non_blocking::WorkerGuard> {
@@ -60,7 +61,10 @@ pub fn init_tracing_with_config(
     // But if it's already set, we can't change it.
     // We'll log a warning if we can't init.
 
-    let registry = tracing_subscriber::registry().with(filter).with(file_layer);
+    let registry = tracing_subscriber::registry()
+        .with(crate::telemetry::otlp_layer(cfg)?)
+        .with(filter)
+        .with(file_layer);
 
     let result = if cfg.format.as_str() == "json" {
         registry
@@ -104,7 +108,7 @@ pub fn init_tracing() -> Result<tracing_appender::non_blocking::WorkerGuard> {
     init_tracing_with_config(&default)
 }
 
-fn split_dir_file(path: &Path) -> Result<(&Path, &str)> {
+pub(crate) fn split_dir_file(path: &Path) -> Result<(&Path, &str)> {
     let dir = path.parent().context("log file missing parent directory")?;
     let file = path
         .file_name()
@@ -116,7 +120,7 @@ fn split_dir_file(path: &Path) -> Result<(&Path, &str)> {
 /// A simple size-rotating writer: when the active log exceeds `max_bytes`,
 /// the file is renamed with a timestamp suffix and a new file is opened.
 #[derive(Clone)]
-struct SizeRotatingWriter {
+pub(crate) struct SizeRotatingWriter {
     inner: Arc<Inner>,
 }
 
@@ -133,7 +137,7 @@ struct WriterState {
 }
 
 impl SizeRotatingWriter {
-    fn new(dir: PathBuf, file_name: String, max_bytes: u64) -> io::Result<Self> {
+    pub(crate) fn new(dir: PathBuf, file_name: String, max_bytes: u64) -> io::Result<Self> {
         if !dir.exists() {
             fs::create_dir_all(&dir)?;
         }
@@ -191,7 +195,7 @@ impl<'a> tracing_subscriber::fmt::writer::MakeWriter<'a> for SizeRotatingWriter
 }
 
 #[derive(Clone)]
-struct SizeRotatingWriterHandle {
+pub(crate) struct SizeRotatingWriterHandle {
     inner: Arc<Inner>,
 }
 
@@ -1,7 +1,8 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
-use core_types::config::load_or_create_config;
-use service::bootstrap;
+use core_types::config::{AppConfig, load_or_create_config};
+use service::bootstrap::{self, BootstrapOptions};
+use std::path::PathBuf;
 use tokio::sync::mpsc;
 
 #[derive(Parser, Debug)]
@@ -13,6 +14,39 @@ struct Args {
     /// Run in console mode (skip Service Control Manager hooks).
     #[arg(long, global = true)]
     console: bool,
+
+    /// Console mode with debug-level, human-readable logging, for local development.
+    #[arg(long, global = true)]
+    foreground: bool,
+
+    /// Serve IPC on this pipe instead of the default (console runs only).
+    #[arg(long, global = true, value_name = "NAME")]
+    pipe_name: Option<String>,
+
+    /// Keep indexes, state, jobs and logs under this directory instead of the configured one.
+    #[arg(long, global = true, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+}
+
+impl Args {
+    fn console(&self) -> bool {
+        self.console || self.foreground
+    }
+
+    /// Apply the command-line overrides to `cfg` and build the matching bootstrap options.
+    fn overrides(&self, cfg: &mut AppConfig) -> BootstrapOptions {
+        if let Some(dir) = &self.data_dir {
+            cfg.override_data_dir(&dir.to_string_lossy());
+        }
+        if self.foreground {
+            cfg.logging.level = "debug".into();
+            cfg.logging.format = "text".into();
+        }
+        BootstrapOptions {
+            pipe_name: self.pipe_name.clone(),
+            ..BootstrapOptions::default()
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -42,16 +76,18 @@ fn main() -> Result<()> {
     }
 
     // Load config early to ensure it exists, though bootstrap will reload or use passed cfg.
-    let cfg = load_or_create_config(None)?;
+    let mut cfg = load_or_create_config(None)?;
     // Ensure config file is writable by standard users for CLI/UI updates.
     let cfg_path = core_types::config::default_config_path();
     service::ensure_config_acl_writable(&cfg_path);
 
-    tracing::info!("Starting service (console: {})", args.console);
+    let opts = args.overrides(&mut cfg);
+
+    tracing::info!("Starting service (console: {})", args.console());
 
     #[cfg(windows)]
     {
-        if !args.console {
+        if !args.console() {
             // Attempt to start as a Windows Service.
             // This will block until the service stops.
             // We pass a dummy closure because our current skeleton hardcodes the bootstrap call inside service_main
@@ -80,5 +116,45 @@ fn main() -> Result<()> {
         }
     });
 
-    bootstrap::run_app(&cfg, rx)
+    bootstrap::run_app_with_options(&cfg, rx, opts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn foreground_flags_map_to_overrides() {
+        let args = Args::try_parse_from([
+            "ultrasearch-service",
+            "--foreground",
+            "--pipe-name",
+            r"\\.\pipe\ultrasearch-dev",
+            "--data-dir",
+            "/tmp/us-dev",
+        ])
+        .unwrap();
+        assert!(args.console());
+
+        let mut cfg = AppConfig::default();
+        let opts = args.overrides(&mut cfg);
+        assert_eq!(opts.pipe_name.as_deref(), Some(r"\\.\pipe\ultrasearch-dev"));
+        assert!(!opts.skip_initial_ingest && opts.initial_metas.is_none());
+        assert_eq!(cfg.app.data_dir, "/tmp/us-dev");
+        assert_eq!(cfg.paths.meta_index, "/tmp/us-dev/index/meta");
+        assert_eq!(cfg.paths.jobs_dir, "/tmp/us-dev/jobs");
+        assert_eq!(cfg.logging.file, "/tmp/us-dev/log/searchd.log");
+        assert_eq!(
+            (cfg.logging.level.as_str(), cfg.logging.format.as_str()),
+            ("debug", "text")
+        );
+
+        // Plain runs keep the configuration as loaded.
+        let args = Args::try_parse_from(["ultrasearch-service"]).unwrap();
+        let mut cfg = AppConfig::default();
+        let before = format!("{:?}", cfg.paths);
+        let opts = args.overrides(&mut cfg);
+        assert!(!args.console() && opts.pipe_name.is_none());
+        assert_eq!(format!("{:?}", cfg.paths), before);
+    }
 }
@@ -1,3 +1,4 @@
+use crate::index_lock::with_index_write_lock;
 use anyhow::Result;
 use core_types::FileMeta;
 use core_types::config::PathsSection;
@@ -10,11 +11,13 @@ pub fn ingest_file_meta_batch(
     metas: impl IntoIterator<Item = FileMeta>,
     writer_cfg: Option<WriterConfig>,
 ) -> Result<()> {
-    let meta = open_or_create_index(index_path)?;
-    let mut writer = create_writer(&meta, &writer_cfg.unwrap_or_default())?;
-    add_file_meta_batch(&mut writer, &meta.fields, metas)?;
-    writer.commit()?;
-    Ok(())
+    with_index_write_lock(index_path, || {
+        let meta = open_or_create_index(index_path)?;
+        let mut writer = create_writer(&meta, &writer_cfg.unwrap_or_default())?;
+        add_file_meta_batch(&mut writer, &meta.fields, metas)?;
+        writer.commit()?;
+        Ok(())
+    })
 }
 
 /// Convenience for ingesting using configured paths.
@@ -4,7 +4,9 @@ use anyhow::Result;
 use core_types::config::MetricsSection;
 use ipc::MetricsSnapshot;
 use once_cell::sync::Lazy;
-use prometheus::{Encoder, Histogram, HistogramOpts, IntCounter, Registry, TextEncoder, opts};
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, IntCounter, IntGauge, Registry, TextEncoder, opts,
+};
 use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 use tracing::warn;
@@ -16,6 +18,8 @@ pub struct ServiceMetrics {
     pub request_latency: Histogram,
     pub worker_failures: IntCounter,
     pub worker_failure_threshold: u64,
+    pub pipe_accept_failures: IntCounter,
+    pub pipe_accept_consecutive_failures: IntGauge,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -46,7 +50,18 @@ impl ServiceMetrics {
 
         registry.register(Box::new(requests_total.clone()))?;
         registry.register(Box::new(request_latency.clone()))?;
+        let pipe_accept_failures = IntCounter::with_opts(opts!(
+            "pipe_accept_failures_total",
+            "Named pipe server instances that failed to create"
+        ))?;
+        let pipe_accept_consecutive_failures = IntGauge::with_opts(opts!(
+            "pipe_accept_consecutive_failures",
+            "Current run of named pipe create failures (0 = healthy)"
+        ))?;
+
         registry.register(Box::new(worker_failures.clone()))?;
+        registry.register(Box::new(pipe_accept_failures.clone()))?;
+        registry.register(Box::new(pipe_accept_consecutive_failures.clone()))?;
 
         Ok(Self {
             registry,
@@ -54,6 +69,8 @@ impl ServiceMetrics {
             request_latency,
             worker_failures,
             worker_failure_threshold: cfg.worker_failure_threshold,
+            pipe_accept_failures,
+            pipe_accept_consecutive_failures,
         })
     }
 
@@ -82,6 +99,15 @@ impl ServiceMetrics {
         tripped
     }
 
+    /// Track the pipe server's current run of create failures; 0 marks a recovery.
+    pub fn record_pipe_accept_failure(&self, consecutive: u64) {
+        if consecutive > 0 {
+            self.pipe_accept_failures.inc();
+        }
+        self.pipe_accept_consecutive_failures
+            .set(consecutive.min(i64::MAX as u64) as i64);
+    }
+
     /// Reset the worker failure counter (used after a healthy run).
     pub fn reset_worker_failures(&self) {
         self.worker_failures.reset();
@@ -159,6 +185,7 @@ pub fn global_metrics_snapshot(
             content_enqueued,
             content_dropped,
         );
+        let (trimmed_content, trimmed_meta) = crate::index_budget::trimmed_totals();
         MetricsSnapshot {
             search_latency_ms_p50: snap.search_latency_ms_p50,
             search_latency_ms_p95: snap.search_latency_ms_p95,
@@ -168,6 +195,9 @@ pub fn global_metrics_snapshot(
             active_workers: snap.active_workers,
             content_enqueued: snap.content_enqueued,
             content_dropped: snap.content_dropped,
+            pipe_accept_failures: Some(crate::pipe_accept::consecutive_accept_failures()),
+            trimmed_content: Some(trimmed_content),
+            trimmed_meta: Some(trimmed_meta),
         }
     })
 }
@@ -0,0 +1,77 @@
+//! Match-quality scoring for name-only results (`search.name_match_ranking`).
+//!
+//! BM25 says little about short file names: every match of `report` scores about the same, so
+//! results come back in index order. This ranks by how well the name matches instead: an
+//! exact name (or name without extension) beats a match at the start of a word, which beats a
+//! match inside a word. Within a tier, shorter (more specific) names score higher.
+
+const EXACT: f32 = 3.0;
+const WORD_START: f32 = 2.0;
+const MID_WORD: f32 = 1.0;
+
+/// Score of `name` for the whitespace-separated `query` terms, averaged over terms; a term the
+/// name doesn't contain contributes 0. Deterministic and in `[0, 3.5]`.
+pub fn name_match_score(name: &str, query: &[String]) -> f32 {
+    if query.is_empty() {
+        return 0.0;
+    }
+    let name = name.to_lowercase();
+    let total: f32 = query.iter().map(|term| term_score(&name, term)).sum();
+    total / query.len() as f32
+}
+
+fn term_score(name: &str, term: &str) -> f32 {
+    let term = term.to_lowercase();
+    if term.is_empty() {
+        return 0.0;
+    }
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    let tier = if name == term || stem == term {
+        EXACT
+    } else if name
+        .match_indices(&term)
+        .any(|(at, _)| is_word_start(name, at))
+    {
+        WORD_START
+    } else if name.contains(&term) {
+        MID_WORD
+    } else {
+        return 0.0;
+    };
+    // In (0, 0.5], so it orders names within a tier without crossing into the next.
+    tier + 1.0 / (2.0 + name.chars().count() as f32)
+}
+
+/// `at` begins the name or follows a separator such as `_`, `-`, `.` or a space.
+fn is_word_start(name: &str, at: usize) -> bool {
+    name[..at]
+        .chars()
+        .next_back()
+        .is_none_or(|c| !c.is_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(name: &str, query: &str) -> f32 {
+        let terms: Vec<String> = query.split_whitespace().map(str::to_string).collect();
+        name_match_score(name, &terms)
+    }
+
+    #[test]
+    fn exact_beats_word_start_beats_mid_word() {
+        let exact = score("Report.txt", "report");
+        let word_start = score("q3-report.txt", "report");
+        let mid_word = score("myreport.txt", "report");
+        assert!(exact > word_start, "{exact} vs {word_start}");
+        assert!(word_start > mid_word, "{word_start} vs {mid_word}");
+        assert_eq!(score("notes.txt", "report"), 0.0);
+
+        // Shorter names win within a tier, never across tiers.
+        assert!(score("report_a.txt", "report") > score("report_final_v2.txt", "report"));
+        assert!(
+            score("myreport.txt", "report") < score("report_with_a_very_long_name.txt", "report")
+        );
+    }
+}
@@ -0,0 +1,54 @@
+//! Process-wide object-ID store (`indexing.index_object_ids`), fed by enumeration to spot
+//! record numbers NTFS handed to a new file since the last scan.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use anyhow::Result;
+use core_types::DocKey;
+use core_types::config::AppConfig;
+use meta_index::object_ids::ObjectIdStore;
+
+static OBJECT_ID_STORE: OnceLock<Arc<ObjectIdStore>> = OnceLock::new();
+
+/// Where the store lives: `object_ids.rkyv` under `paths.state_dir`.
+pub fn object_ids_path(cfg: &AppConfig) -> PathBuf {
+    Path::new(&cfg.paths.state_dir).join("object_ids.rkyv")
+}
+
+/// Open the store at `path` and install it globally; later calls return the installed one.
+pub fn init_object_id_store(path: &Path) -> Result<Arc<ObjectIdStore>> {
+    if let Some(store) = OBJECT_ID_STORE.get() {
+        return Ok(store.clone());
+    }
+    let store = Arc::new(ObjectIdStore::open(path)?);
+    Ok(OBJECT_ID_STORE.get_or_init(|| store).clone())
+}
+
+/// Drop what is held for `keys`, whose record numbers now name other files: their metadata
+/// and content documents, tags, and former names. The caller then ingests them as new files.
+pub fn retire_reused(cfg: &AppConfig, keys: &[DocKey]) -> Result<()> {
+    if keys.is_empty() {
+        return Ok(());
+    }
+    crate::index_budget::delete_from_indexes(
+        Path::new(&cfg.paths.meta_index),
+        Path::new(&cfg.paths.content_index),
+        keys,
+    )?;
+    let tags = crate::tags::init_tag_store(Path::new(&cfg.paths.tags_file))?;
+    for &key in keys {
+        let old = tags.tags(key);
+        if !old.is_empty() {
+            tags.update(key, &[], &old)?;
+        }
+    }
+    if cfg.indexing.former_names > 0 {
+        crate::former_names::init_former_name_store(
+            &crate::former_names::former_names_path(&cfg.paths),
+            cfg.indexing.former_names,
+        )?
+        .forget(keys)?;
+    }
+    Ok(())
+}
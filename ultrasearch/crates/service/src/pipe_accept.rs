@@ -0,0 +1,193 @@
+//! Retry policy for creating named-pipe server instances.
+//!
+//! Failures back off exponentially up to `ipc.accept_backoff_max_ms`. The consecutive
+//! failure count is published to metrics/status, and once it reaches
+//! `ipc.accept_failure_threshold` the server is reported as down at error level.
+
+use crate::metrics::with_global_metrics;
+use core_types::config::IpcSection;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+static CONSECUTIVE_FAILURES: AtomicU64 = AtomicU64::new(0);
+static TOTAL_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// Current run of pipe-creation failures (0 while the server is healthy).
+pub fn consecutive_accept_failures() -> u64 {
+    CONSECUTIVE_FAILURES.load(Ordering::Relaxed)
+}
+
+/// Pipe-creation failures since startup.
+pub fn total_accept_failures() -> u64 {
+    TOTAL_FAILURES.load(Ordering::Relaxed)
+}
+
+/// Creates one pipe server instance; abstracted so the retry loop can be tested.
+pub trait PipeAcceptor {
+    type Server;
+
+    /// `first` requests `FILE_FLAG_FIRST_PIPE_INSTANCE`.
+    fn create(&mut self, first: bool) -> anyhow::Result<Self::Server>;
+}
+
+/// Exponential backoff with a consecutive-failure counter.
+#[derive(Debug, Clone)]
+pub struct AcceptBackoff {
+    initial: Duration,
+    max: Duration,
+    threshold: u32,
+    consecutive: u32,
+}
+
+impl AcceptBackoff {
+    pub fn new(cfg: &IpcSection) -> Self {
+        Self {
+            initial: Duration::from_millis(cfg.accept_backoff_initial_ms.max(1)),
+            max: Duration::from_millis(
+                cfg.accept_backoff_max_ms.max(cfg.accept_backoff_initial_ms),
+            ),
+            threshold: cfg.accept_failure_threshold.max(1),
+            consecutive: 0,
+        }
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive
+    }
+
+    /// Record a failure and return how long to wait before retrying.
+    pub fn record_failure(&mut self, err: &anyhow::Error) -> Duration {
+        self.consecutive = self.consecutive.saturating_add(1);
+        CONSECUTIVE_FAILURES.store(self.consecutive as u64, Ordering::Relaxed);
+        TOTAL_FAILURES.fetch_add(1, Ordering::Relaxed);
+        let consecutive = self.consecutive as u64;
+        let _ = with_global_metrics(|m| m.record_pipe_accept_failure(consecutive));
+
+        let delay = self.delay();
+        if self.consecutive.is_multiple_of(self.threshold) {
+            tracing::error!(
+                failures = self.consecutive,
+                retry_ms = delay.as_millis() as u64,
+                "IPC server is effectively down: named pipe create failed {} times in a row ({})",
+                self.consecutive,
+                err
+            );
+        } else {
+            tracing::warn!(
+                failures = self.consecutive,
+                "named pipe create failed ({}); retrying in {:?}",
+                err,
+                delay
+            );
+        }
+        delay
+    }
+
+    /// Reset after a pipe instance was created.
+    pub fn record_success(&mut self) {
+        if self.consecutive > 0 {
+            tracing::info!(
+                failures = self.consecutive,
+                "named pipe server recovered after {} failures",
+                self.consecutive
+            );
+            self.consecutive = 0;
+            CONSECUTIVE_FAILURES.store(0, Ordering::Relaxed);
+            let _ = with_global_metrics(|m| m.record_pipe_accept_failure(0));
+        }
+    }
+
+    fn delay(&self) -> Duration {
+        let doublings = self.consecutive.saturating_sub(1).min(31);
+        self.initial.saturating_mul(1u32 << doublings).min(self.max)
+    }
+}
+
+/// Create a server instance, retrying with backoff until one succeeds.
+pub async fn create_with_backoff<A, S, F>(
+    acceptor: &mut A,
+    first: bool,
+    backoff: &mut AcceptBackoff,
+    mut sleep: S,
+) -> A::Server
+where
+    A: PipeAcceptor,
+    S: FnMut(Duration) -> F,
+    F: Future<Output = ()>,
+{
+    loop {
+        match acceptor.create(first) {
+            Ok(server) => {
+                backoff.record_success();
+                return server;
+            }
+            Err(err) => {
+                let delay = backoff.record_failure(&err);
+                sleep(delay).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FlakyAcceptor {
+        failures_left: u32,
+        calls: u32,
+    }
+
+    impl PipeAcceptor for FlakyAcceptor {
+        type Server = u32;
+
+        fn create(&mut self, _first: bool) -> anyhow::Result<u32> {
+            self.calls += 1;
+            if self.failures_left > 0 {
+                self.failures_left -= 1;
+                anyhow::bail!("access denied");
+            }
+            Ok(self.calls)
+        }
+    }
+
+    #[tokio::test]
+    async fn repeated_failures_back_off_and_count() {
+        let cfg = IpcSection {
+            accept_backoff_initial_ms: 100,
+            accept_backoff_max_ms: 1_000,
+            accept_failure_threshold: 3,
+            grpc_addr: String::new(),
+        };
+        let mut backoff = AcceptBackoff::new(&cfg);
+        let mut acceptor = FlakyAcceptor {
+            failures_left: 6,
+            calls: 0,
+        };
+        let mut slept = Vec::new();
+        let mut counts = Vec::new();
+
+        let server = create_with_backoff(&mut acceptor, true, &mut backoff, |d| {
+            slept.push(d);
+            counts.push(consecutive_accept_failures());
+            async {}
+        })
+        .await;
+
+        assert_eq!(server, 7);
+        assert_eq!(
+            slept,
+            [100, 200, 400, 800, 1_000, 1_000].map(Duration::from_millis)
+        );
+        assert_eq!(counts, vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(total_accept_failures(), 6);
+        // Success resets the run and the delay.
+        assert_eq!(backoff.consecutive_failures(), 0);
+        assert_eq!(consecutive_accept_failures(), 0);
+        assert_eq!(
+            backoff.record_failure(&anyhow::anyhow!("boom")),
+            Duration::from_millis(100)
+        );
+    }
+}
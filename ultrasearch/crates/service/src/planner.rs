@@ -1,4 +1,5 @@
-use ipc::QueryExpr;
+use anyhow::{Result, bail};
+use ipc::{FieldKind, QueryExpr, SearchMode, TermModifier};
 
 /// Optimizes a raw query AST for execution.
 pub struct QueryPlanner;
@@ -10,6 +11,50 @@ impl QueryPlanner {
         Self::flatten(Self::push_down_not(expr))
     }
 
+    /// Match the trailing term as a prefix, as when the user is still typing it. Applies to
+    /// a query that is a single term or an AND ending in one, when that term is a plain match
+    /// on names (or on no particular field).
+    pub fn prefix_trailing_term(expr: QueryExpr) -> QueryExpr {
+        match expr {
+            QueryExpr::Term(mut term) => {
+                Self::upgrade_to_prefix(&mut term);
+                QueryExpr::Term(term)
+            }
+            QueryExpr::And(mut subs) => {
+                if let Some(QueryExpr::Term(term)) = subs.last_mut() {
+                    Self::upgrade_to_prefix(term);
+                }
+                QueryExpr::And(subs)
+            }
+            other => other,
+        }
+    }
+
+    /// Reject queries `mode` can't run: `Near` needs word positions, which only the content
+    /// index has, so it can't be dispatched `NameOnly`.
+    pub fn check_mode(expr: &QueryExpr, mode: SearchMode) -> Result<()> {
+        if mode == SearchMode::NameOnly && Self::has_near(expr) {
+            bail!("NEAR queries search content and can't run in NameOnly mode");
+        }
+        Ok(())
+    }
+
+    fn has_near(expr: &QueryExpr) -> bool {
+        match expr {
+            QueryExpr::Near { .. } => true,
+            QueryExpr::Not(inner) => Self::has_near(inner),
+            QueryExpr::And(subs) | QueryExpr::Or(subs) => subs.iter().any(Self::has_near),
+            QueryExpr::Term(_) | QueryExpr::Range(_) => false,
+        }
+    }
+
+    fn upgrade_to_prefix(term: &mut ipc::TermExpr) {
+        if term.modifier == TermModifier::Term && matches!(term.field, None | Some(FieldKind::Name))
+        {
+            term.modifier = TermModifier::Prefix;
+        }
+    }
+
     /// Distribute NOTs: `Not(And([A, B]))` -> `Or([Not(A), Not(B)])` (De Morgan's).
     /// This canonicalizes negations to be closer to leaves.
     fn push_down_not(expr: QueryExpr) -> QueryExpr {
@@ -128,4 +173,17 @@ mod tests {
             assert!(matches!(subs[1], QueryExpr::Not(_)));
         }
     }
+
+    #[test]
+    fn near_is_rejected_in_name_only_mode() {
+        let near = QueryExpr::Near {
+            terms: vec!["quarterly".into(), "revenue".into()],
+            slop: 2,
+        };
+        let q = QueryExpr::And(vec![term("A"), QueryExpr::Not(Box::new(near))]);
+        assert!(QueryPlanner::check_mode(&q, SearchMode::NameOnly).is_err());
+        assert!(QueryPlanner::check_mode(&q, SearchMode::Content).is_ok());
+        assert!(QueryPlanner::check_mode(&q, SearchMode::Hybrid).is_ok());
+        assert!(QueryPlanner::check_mode(&term("A"), SearchMode::NameOnly).is_ok());
+    }
 }
@@ -0,0 +1,302 @@
+//! Local query history behind `SuggestRequest` (`search.query_history_len`).
+//!
+//! Searches that carry `SearchRequest::query_text` are recorded here, deduplicated, with the
+//! time of their last use and how often they ran; past the configured length the least
+//! recently used query is dropped. A `SuggestRequest` returns the recorded queries starting
+//! with its prefix, most recent first, next to matching file names, and a
+//! `ClearQueryHistoryRequest` forgets them. The history never leaves the machine: it persists
+//! as `query_history.json` under `paths.state_dir`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use core_types::config::PathsSection;
+use ipc::{
+    ClearQueryHistoryRequest, ClearQueryHistoryResponse, FieldKind, HitField, QueryExpr,
+    QuerySuggestion, SearchMode, SearchRequest, SuggestRequest, SuggestResponse, TermExpr,
+    TermModifier,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::search_handler::SearchHandler;
+
+/// Name hits fetched per requested suggestion, since files sharing a name collapse into one.
+const NAME_HITS_PER_SUGGESTION: u32 = 4;
+
+static QUERY_HISTORY: OnceLock<Arc<QueryHistory>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    query: String,
+    /// Unix seconds.
+    last_used: i64,
+    uses: u32,
+}
+
+/// Recently used queries, written through to `path` on every change.
+#[derive(Debug)]
+pub struct QueryHistory {
+    path: PathBuf,
+    capacity: usize,
+    entries: Mutex<Vec<Entry>>,
+}
+
+impl QueryHistory {
+    /// Load the history at `path`, keeping its `capacity` most recent queries; a missing file
+    /// is an empty history.
+    pub fn open(path: &Path, capacity: usize) -> Result<Self> {
+        let mut entries = if path.exists() {
+            let bytes = fs::read(path).context("read query history")?;
+            serde_json::from_slice::<Vec<Entry>>(&bytes).context("deserialize query history")?
+        } else {
+            Vec::new()
+        };
+        entries.sort_by_key(|e| std::cmp::Reverse(e.last_used));
+        entries.truncate(capacity);
+        Ok(Self {
+            path: path.to_path_buf(),
+            capacity,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Record a search for `query` (trimmed; blank queries are ignored) at `now`.
+    pub fn record(&self, query: &str, now: i64) -> Result<()> {
+        let query = query.trim();
+        if query.is_empty() || self.capacity == 0 {
+            return Ok(());
+        }
+        let mut entries = self.entries.lock();
+        if let Some(entry) = entries.iter_mut().find(|e| e.query == query) {
+            entry.last_used = entry.last_used.max(now);
+            entry.uses = entry.uses.saturating_add(1);
+        } else {
+            if entries.len() >= self.capacity
+                && let Some(oldest) = (0..entries.len()).min_by_key(|&i| entries[i].last_used)
+            {
+                entries.swap_remove(oldest);
+            }
+            entries.push(Entry {
+                query: query.to_string(),
+                last_used: now,
+                uses: 1,
+            });
+        }
+        self.save(&entries)
+    }
+
+    /// Up to `limit` recorded queries starting with `prefix` (case-insensitively), most
+    /// recently used first; ties go to the more frequent query.
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<QuerySuggestion> {
+        let prefix = prefix.trim().to_lowercase();
+        let mut matches: Vec<Entry> = self
+            .entries
+            .lock()
+            .iter()
+            .filter(|e| e.query.to_lowercase().starts_with(&prefix))
+            .cloned()
+            .collect();
+        matches.sort_by(|a, b| {
+            (b.last_used, b.uses)
+                .cmp(&(a.last_used, a.uses))
+                .then_with(|| a.query.cmp(&b.query))
+        });
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|e| QuerySuggestion {
+                query: e.query,
+                last_used: e.last_used,
+                uses: e.uses,
+            })
+            .collect()
+    }
+
+    /// Forget the queries starting with `prefix` (case-insensitively; empty = all) not used in
+    /// the `older_than_secs` seconds before `now` (0 = all). Returns how many were removed.
+    pub fn clear(&self, prefix: &str, older_than_secs: u64, now: i64) -> Result<usize> {
+        let prefix = prefix.trim().to_lowercase();
+        let cutoff = match older_than_secs {
+            0 => i64::MAX,
+            secs => now.saturating_sub(i64::try_from(secs).unwrap_or(i64::MAX)),
+        };
+        let mut entries = self.entries.lock();
+        let before = entries.len();
+        entries.retain(|e| e.last_used > cutoff || !e.query.to_lowercase().starts_with(&prefix));
+        let removed = before - entries.len();
+        if removed > 0 {
+            self.save(&entries)?;
+        }
+        Ok(removed)
+    }
+
+    fn save(&self, entries: &[Entry]) -> Result<()> {
+        let bytes = serde_json::to_vec(entries).context("serialize query history")?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("create query history dir")?;
+        }
+
+        // Atomic write: write to tmp, rename.
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, &bytes).context("write tmp query history")?;
+        fs::rename(&tmp_path, &self.path).context("rename query history")?;
+        Ok(())
+    }
+}
+
+/// Where the history lives: `query_history.json` under `paths.state_dir`.
+pub fn query_history_path(paths: &PathsSection) -> PathBuf {
+    Path::new(&paths.state_dir).join("query_history.json")
+}
+
+/// Open the history at `path` and install it globally; later calls return the installed one.
+pub fn init_query_history(path: &Path, capacity: usize) -> Result<Arc<QueryHistory>> {
+    if let Some(history) = QUERY_HISTORY.get() {
+        return Ok(history.clone());
+    }
+    let history = Arc::new(QueryHistory::open(path, capacity)?);
+    Ok(QUERY_HISTORY.get_or_init(|| history).clone())
+}
+
+pub fn query_history() -> Option<Arc<QueryHistory>> {
+    QUERY_HISTORY.get().cloned()
+}
+
+pub(crate) fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_default()
+}
+
+/// Answer `req`: matching queries from `history` and the distinct names of files whose name
+/// starts with the prefix, found through `handler`. A blank prefix lists recent queries only.
+pub fn suggest(
+    handler: &dyn SearchHandler,
+    history: Option<&QueryHistory>,
+    req: &SuggestRequest,
+) -> SuggestResponse {
+    let limit = usize::from(req.limit);
+    let queries = history.map_or_else(Vec::new, |h| h.suggest(&req.prefix, limit));
+
+    let mut names: Vec<String> = Vec::new();
+    let prefix = req.prefix.trim();
+    if !prefix.is_empty() && limit > 0 {
+        let search = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Name),
+            value: prefix.to_string(),
+            modifier: TermModifier::Prefix,
+        }))
+        .with_mode(SearchMode::NameOnly)
+        .with_limit(u32::from(req.limit) * NAME_HITS_PER_SUGGESTION)
+        .with_fields([HitField::Name]);
+        let hits = handler.search(search).hits;
+        for name in hits.into_iter().filter_map(|h| h.name) {
+            if names.len() >= limit {
+                break;
+            }
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+
+    SuggestResponse {
+        id: req.id,
+        queries,
+        names,
+    }
+}
+
+/// Answer `req` against `history`; nothing to clear when the history is off.
+pub fn clear_history(
+    history: Option<&QueryHistory>,
+    req: &ClearQueryHistoryRequest,
+) -> ClearQueryHistoryResponse {
+    let removed = match history.map(|h| h.clear(&req.prefix, req.older_than_secs, unix_now())) {
+        Some(Ok(removed)) => removed,
+        Some(Err(e)) => {
+            tracing::warn!("failed to clear query history: {e:#}");
+            0
+        }
+        None => 0,
+    };
+    ClearQueryHistoryResponse {
+        id: req.id,
+        removed: u32::try_from(removed).unwrap_or(u32::MAX),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ipc::{SearchHit, SearchResponse};
+    use uuid::Uuid;
+
+    struct Names(&'static [&'static str]);
+
+    impl SearchHandler for Names {
+        fn search(&self, req: SearchRequest) -> SearchResponse {
+            let mut resp = crate::search_handler::StubSearchHandler.search(req);
+            resp.hits = self
+                .0
+                .iter()
+                .enumerate()
+                .map(|(i, name)| SearchHit {
+                    key: core_types::DocKey::from_parts(1, i as u64),
+                    score: 1.0,
+                    name: Some(name.to_string()),
+                    path: None,
+                    ext: None,
+                    size: None,
+                    modified: None,
+                    snippet: None,
+                    snippets: Vec::new(),
+                    tags: Vec::new(),
+                    extract_status: None,
+                    archive: None,
+                    matched_former_name: false,
+                })
+                .collect();
+            resp
+        }
+    }
+
+    #[test]
+    fn prefix_suggest_returns_matching_queries_most_recent_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("query_history.json");
+        let history = QueryHistory::open(&path, 3).unwrap();
+        history.record("report 2023", 100).unwrap();
+        history.record("Reports ext:pdf", 200).unwrap();
+        history.record("budget", 300).unwrap();
+        history.record("  report 2023 ", 400).unwrap();
+        history.record("", 500).unwrap();
+
+        let history = QueryHistory::open(&path, 3).unwrap();
+        let req = SuggestRequest {
+            id: Uuid::new_v4(),
+            prefix: "rep".into(),
+            limit: 5,
+        };
+        let names = Names(&["report.docx", "report.docx", "repo"]);
+        let resp = suggest(&names, Some(&history), &req);
+        let queries: Vec<_> = resp.queries.iter().map(|q| (&*q.query, q.uses)).collect();
+        assert_eq!(queries, vec![("report 2023", 2), ("Reports ext:pdf", 1)]);
+        assert_eq!(resp.names, vec!["report.docx", "repo"]);
+
+        // Over capacity the least recently used query goes.
+        history.record("taxes", 600).unwrap();
+        assert_eq!(history.suggest("rep", 5).len(), 1);
+        assert_eq!(history.clear("", 250, 600).unwrap(), 1);
+        assert_eq!(history.suggest("", 5)[0].query, "taxes");
+        assert_eq!(history.clear("TAX", 0, 600).unwrap(), 1);
+        assert_eq!(history.suggest("", 5)[0].query, "report 2023");
+        assert_eq!(history.clear("", 0, 600).unwrap(), 1);
+        assert!(history.suggest("", 5).is_empty());
+    }
+}
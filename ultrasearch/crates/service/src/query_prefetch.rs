@@ -0,0 +1,272 @@
+//! Frequent-query tracking and idle posting-list prefetch (`search.prefetch_queries`).
+//!
+//! Every content search adds its terms to a small Space-Saving sketch: a fixed number of
+//! counters, where an unseen query evicts the least-counted one and inherits its count as
+//! possible over-estimate (`error`). During deep idle the scheduler reads the posting lists of
+//! the most frequent queries' terms so the OS page cache holds them when the user searches
+//! again. The sketch persists as `query_frequency.json` under `paths.state_dir`.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use anyhow::{Context, Result};
+use core_types::config::PathsSection;
+use ipc::{FieldKind, QueryExpr, SearchMode, SearchRequest, TermModifier};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tantivy::DocSet;
+use tantivy::schema::IndexRecordOption;
+use tantivy::tokenizer::TokenStream;
+
+/// Counters kept per prefetched query, so queries just outside the top N can still climb in.
+const COUNTERS_PER_QUERY: usize = 4;
+/// A query must have been seen at least this often (beyond any over-estimate) to be prefetched.
+const MIN_PREFETCH_COUNT: u64 = 2;
+
+static QUERY_FREQUENCY: OnceLock<Arc<QueryFrequency>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Counter {
+    query: String,
+    count: u64,
+    /// Count inherited from the evicted counter; `count - error` was certainly seen.
+    error: u64,
+}
+
+/// Approximate counts of the most frequent content queries.
+#[derive(Debug)]
+pub struct QueryFrequency {
+    path: PathBuf,
+    capacity: usize,
+    counters: Mutex<Vec<Counter>>,
+    dirty: AtomicBool,
+}
+
+impl QueryFrequency {
+    /// Load the sketch at `path`, sized to track the top `top_n` queries; a missing file is an
+    /// empty sketch.
+    pub fn open(path: &Path, top_n: usize) -> Result<Self> {
+        let capacity = top_n.max(1) * COUNTERS_PER_QUERY;
+        let mut counters = if path.exists() {
+            let bytes = fs::read(path).context("read query frequency sketch")?;
+            serde_json::from_slice::<Vec<Counter>>(&bytes)
+                .context("deserialize query frequency sketch")?
+        } else {
+            Vec::new()
+        };
+        counters.sort_by_key(|c| std::cmp::Reverse(c.count));
+        counters.truncate(capacity);
+        Ok(Self {
+            path: path.to_path_buf(),
+            capacity,
+            counters: Mutex::new(counters),
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    /// Count one occurrence of `query` (see [`query_key`]).
+    pub fn record(&self, query: &str) {
+        let mut counters = self.counters.lock();
+        if let Some(c) = counters.iter_mut().find(|c| c.query == query) {
+            c.count += 1;
+        } else if counters.len() < self.capacity {
+            counters.push(Counter {
+                query: query.to_string(),
+                count: 1,
+                error: 0,
+            });
+        } else if let Some(min) = counters.iter_mut().min_by_key(|c| c.count) {
+            *min = Counter {
+                query: query.to_string(),
+                count: min.count + 1,
+                error: min.count,
+            };
+        }
+        self.dirty.store(true, Ordering::Relaxed);
+    }
+
+    /// Up to `n` queries to prefetch, most frequent first. Queries seen fewer than
+    /// [`MIN_PREFETCH_COUNT`] times are left out.
+    pub fn top(&self, n: usize) -> Vec<String> {
+        let mut frequent: Vec<Counter> = self
+            .counters
+            .lock()
+            .iter()
+            .filter(|c| c.count - c.error >= MIN_PREFETCH_COUNT)
+            .cloned()
+            .collect();
+        frequent.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.query.cmp(&b.query)));
+        frequent.into_iter().take(n).map(|c| c.query).collect()
+    }
+
+    /// Persist the sketch if anything was recorded since the last save.
+    pub fn save(&self) -> Result<()> {
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+        let bytes = serde_json::to_vec(&*self.counters.lock())
+            .context("serialize query frequency sketch")?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("create query frequency dir")?;
+        }
+
+        // Atomic write: write to tmp, rename.
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, &bytes).context("write tmp query frequency sketch")?;
+        fs::rename(&tmp_path, &self.path).context("rename query frequency sketch")?;
+        Ok(())
+    }
+}
+
+/// Where the sketch lives: `query_frequency.json` under `paths.state_dir`.
+pub fn query_frequency_path(paths: &PathsSection) -> PathBuf {
+    Path::new(&paths.state_dir).join("query_frequency.json")
+}
+
+/// Open the sketch at `path` and install it globally; later calls return the installed one.
+pub fn init_query_frequency(path: &Path, top_n: usize) -> Result<Arc<QueryFrequency>> {
+    if let Some(freq) = QUERY_FREQUENCY.get() {
+        return Ok(freq.clone());
+    }
+    let freq = Arc::new(QueryFrequency::open(path, top_n)?);
+    Ok(QUERY_FREQUENCY.get_or_init(|| freq).clone())
+}
+
+pub fn query_frequency() -> Option<Arc<QueryFrequency>> {
+    QUERY_FREQUENCY.get().cloned()
+}
+
+/// The content terms of `req` as one lowercased string, or `None` when it doesn't search
+/// content: plain and phrase terms on content or on no particular field, and `Near` terms,
+/// outside any `Not`.
+pub fn query_key(req: &SearchRequest) -> Option<String> {
+    fn collect<'a>(expr: &'a QueryExpr, out: &mut Vec<&'a str>) {
+        match expr {
+            QueryExpr::Term(t)
+                if matches!(t.field, None | Some(FieldKind::Content))
+                    && matches!(t.modifier, TermModifier::Term | TermModifier::Phrase) =>
+            {
+                out.push(t.value.trim());
+            }
+            QueryExpr::Near { terms, .. } => out.extend(terms.iter().map(|t| t.trim())),
+            QueryExpr::And(items) | QueryExpr::Or(items) => {
+                items.iter().for_each(|e| collect(e, out))
+            }
+            _ => {}
+        }
+    }
+    if req.mode == SearchMode::NameOnly {
+        return None;
+    }
+    let mut values = Vec::new();
+    collect(&req.query, &mut values);
+    values.retain(|v| !v.is_empty());
+    (!values.is_empty()).then(|| values.join(" ").to_lowercase())
+}
+
+/// Read the content-index posting lists of every term in `queries`, in every segment, so they
+/// are paged in. Returns how many posting lists were read.
+pub fn prefetch_postings(content_dir: &Path, queries: &[String]) -> Result<usize> {
+    if queries.is_empty() || !content_dir.join("meta.json").exists() {
+        return Ok(0);
+    }
+    let idx = content_index::open_or_create(content_dir)?;
+    let field = idx.fields.content;
+    let mut analyzer = idx.index.tokenizer_for_field(field)?;
+    let mut terms = BTreeSet::new();
+    for query in queries {
+        let mut stream = analyzer.token_stream(query);
+        while let Some(token) = stream.next() {
+            terms.insert(tantivy::Term::from_field_text(field, &token.text));
+        }
+    }
+
+    let searcher = content_index::open_reader(&idx)?.searcher();
+    let mut read = 0;
+    for segment in searcher.segment_readers() {
+        let inverted = segment.inverted_index(field)?;
+        for term in &terms {
+            if let Some(mut postings) =
+                inverted.read_postings(term, IndexRecordOption::WithFreqsAndPositions)?
+            {
+                while postings.advance() != tantivy::TERMINATED {}
+                read += 1;
+            }
+        }
+    }
+    Ok(read)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use content_index::{ContentDoc, WriterConfig, add_content_doc, create_writer};
+    use core_types::{DocKey, FileFlags, FileMeta};
+    use ipc::TermExpr;
+
+    fn content_request(value: &str) -> SearchRequest {
+        SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: None,
+            value: value.into(),
+            modifier: TermModifier::Term,
+        }))
+    }
+
+    #[test]
+    fn frequent_queries_are_selected_and_survive_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("query_frequency.json");
+        let freq = QueryFrequency::open(&path, 1).unwrap();
+        for _ in 0..5 {
+            freq.record(&query_key(&content_request("Quarterly Revenue")).unwrap());
+        }
+        freq.record("budget");
+        freq.record("invoice");
+        assert_eq!(freq.top(3), vec!["quarterly revenue"]);
+
+        // More one-off queries than counters: each evicts another, none reaches the minimum.
+        for i in 0..10 {
+            freq.record(&format!("rare {i}"));
+        }
+        assert_eq!(freq.top(3), vec!["quarterly revenue"]);
+
+        freq.save().unwrap();
+        let reopened = QueryFrequency::open(&path, 1).unwrap();
+        assert_eq!(reopened.top(1), vec!["quarterly revenue"]);
+
+        let name_only = content_request("revenue").with_mode(SearchMode::NameOnly);
+        assert_eq!(query_key(&name_only), None);
+    }
+
+    #[test]
+    fn prefetch_reads_postings_of_indexed_terms() {
+        let dir = tempfile::tempdir().unwrap();
+        let idx = content_index::open_or_create(dir.path()).unwrap();
+        let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+        let meta = FileMeta::new(
+            DocKey::from_parts(1, 7),
+            1,
+            None,
+            "report.txt".into(),
+            Some(r"C:\docs\report.txt".into()),
+            10,
+            0,
+            0,
+            FileFlags::empty(),
+        );
+        let doc = ContentDoc::from_meta(&meta, "quarterly revenue summary".into(), None);
+        add_content_doc(&mut writer, &idx.fields, &doc).unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+
+        let queries = vec!["quarterly revenue".to_string(), "missing".to_string()];
+        assert_eq!(prefetch_postings(dir.path(), &queries).unwrap(), 2);
+        assert_eq!(
+            prefetch_postings(&dir.path().join("none"), &queries).unwrap(),
+            0
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Full index reset guarded by a logged confirmation token.
+//!
+//! The service writes a one-time token to its log; a reset only proceeds when the caller echoes
+//! that token back, so a stray client call cannot wipe the indexes. A reset deletes the meta and
+//! content index directories and recreates them empty with the current schema, so it also
+//! replaces an index with an outdated schema (open readers see zero docs after their next
+//! reload).
+//! It removes the per-volume cursor files under `state_dir` so the next scan starts from
+//! scratch, rotates the token and bumps the index epoch. Configuration is never touched.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use core_types::config::{MetaStoreSection, PathsSection};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use tantivy::{Index, IndexWriter};
+use uuid::Uuid;
+
+use crate::index_lock::{bump_index_epoch, with_index_write_lock};
+
+static RESET_TOKEN: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
+
+/// Small writer budget: a reset only takes the writer lock.
+const RESET_WRITER_HEAP_BYTES: usize = 32 * 1024 * 1024;
+
+/// What a successful reset removed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResetSummary {
+    pub meta_docs_removed: u64,
+    pub content_docs_removed: u64,
+    pub state_files_removed: usize,
+    pub epoch: u64,
+}
+
+/// Token required by the next reset; issues and logs a fresh one if none is pending.
+pub fn reset_token() -> String {
+    RESET_TOKEN
+        .lock()
+        .get_or_insert_with(|| {
+            let token = Uuid::new_v4().simple().to_string()[..12].to_string();
+            tracing::warn!(
+                %token,
+                "index reset token issued; pass it back to confirm wiping all indexes"
+            );
+            token
+        })
+        .clone()
+}
+
+/// Wipe every index under `paths` if `confirm_token` matches the logged token. The meta index
+/// is recreated with the stored-field layout of `store`.
+pub fn reset_indexes(
+    paths: &PathsSection,
+    store: &MetaStoreSection,
+    confirm_token: &str,
+) -> Result<ResetSummary> {
+    {
+        let mut pending = RESET_TOKEN.lock();
+        match pending.as_deref() {
+            Some(expected) if !confirm_token.is_empty() && expected == confirm_token => {
+                *pending = None;
+            }
+            _ => {
+                drop(pending);
+                reset_token();
+                bail!(
+                    "reset rejected: confirmation token mismatch (see service log for the token)"
+                );
+            }
+        }
+    }
+
+    tracing::warn!("resetting all indexes");
+    let meta_docs_removed = clear_meta_index(Path::new(&paths.meta_index), store)?;
+    let content_docs_removed = clear_content_index(Path::new(&paths.content_index))?;
+    let state_files_removed = clear_state_files(Path::new(&paths.state_dir))?;
+    let epoch = bump_index_epoch();
+    tracing::info!(
+        meta_docs_removed,
+        content_docs_removed,
+        state_files_removed,
+        epoch,
+        "index reset complete"
+    );
+
+    Ok(ResetSummary {
+        meta_docs_removed,
+        content_docs_removed,
+        state_files_removed,
+        epoch,
+    })
+}
+
+fn clear_meta_index(path: &Path, store: &MetaStoreSection) -> Result<u64> {
+    with_index_write_lock(path, || {
+        let removed = remove_index_dir(path)?;
+        meta_index::open_or_create_index_with(path, store)?;
+        Ok(removed)
+    })
+}
+
+fn clear_content_index(path: &Path) -> Result<u64> {
+    with_index_write_lock(path, || {
+        let removed = remove_index_dir(path)?;
+        content_index::open_or_create(path)?;
+        Ok(removed)
+    })
+}
+
+/// Delete the index directory at `path`, leaving it empty, and return how many documents it
+/// held. Tantivy's writer lock is held meanwhile, so a reset fails rather than pulling the
+/// directory from under another process's writer. The index is opened without resolving its
+/// fields, so an index with an outdated schema goes too.
+fn remove_index_dir(path: &Path) -> Result<u64> {
+    let mut removed = 0;
+    if path.join("meta.json").exists() {
+        let index = Index::open_in_dir(path).context("open index to reset")?;
+        let _writer: IndexWriter = index.writer_with_num_threads(1, RESET_WRITER_HEAP_BYTES)?;
+        removed = index.reader()?.searcher().num_docs();
+        fs::remove_dir_all(path).context("remove index directory")?;
+    } else if path.exists() {
+        fs::remove_dir_all(path).context("remove index directory")?;
+    }
+    fs::create_dir_all(path)?;
+    Ok(removed)
+}
+
+/// Remove per-volume cursor/state files; subdirectories are left alone.
+fn clear_state_files(dir: &Path) -> Result<usize> {
+    if !dir.exists() {
+        return Ok(0);
+    }
+    let mut removed = 0;
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            fs::remove_file(entry.path())?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index_lock::index_epoch;
+    use crate::meta_ingest::ingest_file_meta_batch;
+    use content_index::ContentDoc;
+    use core_types::{DocKey, FileFlags, FileMeta};
+
+    fn paths_in(root: &Path) -> PathsSection {
+        let paths = PathsSection {
+            meta_index: root.join("meta").to_string_lossy().into_owned(),
+            content_index: root.join("content").to_string_lossy().into_owned(),
+            state_dir: root.join("state").to_string_lossy().into_owned(),
+            jobs_dir: root.join("jobs").to_string_lossy().into_owned(),
+            tags_file: root.join("tags.rkyv").to_string_lossy().into_owned(),
+        };
+        for p in [&paths.meta_index, &paths.content_index, &paths.state_dir] {
+            fs::create_dir_all(p).unwrap();
+        }
+        paths
+    }
+
+    fn populate(paths: &PathsSection) {
+        let meta = FileMeta::new(
+            DocKey::from_parts(1, 7),
+            1,
+            None,
+            "notes.txt".into(),
+            Some(r"C:\notes.txt".into()),
+            5,
+            0,
+            0,
+            FileFlags::empty(),
+        );
+        ingest_file_meta_batch(Path::new(&paths.meta_index), vec![meta.clone()], None).unwrap();
+
+        let content = content_index::open_or_create(Path::new(&paths.content_index)).unwrap();
+        let mut writer = content_index::create_writer(
+            &content,
+            &content_index::WriterConfig {
+                heap_size_bytes: RESET_WRITER_HEAP_BYTES,
+                num_threads: 1,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let doc = ContentDoc::from_meta(&meta, "hello".into(), None);
+        content_index::add_content_doc(&mut writer, &content.fields, &doc).unwrap();
+        writer.commit().unwrap();
+
+        fs::write(Path::new(&paths.state_dir).join("volume_1.json"), "{}").unwrap();
+    }
+
+    fn doc_counts(paths: &PathsSection) -> (u64, u64) {
+        let meta = meta_index::open_or_create_index(Path::new(&paths.meta_index)).unwrap();
+        let content = content_index::open_or_create(Path::new(&paths.content_index)).unwrap();
+        (
+            meta_index::open_reader(&meta)
+                .unwrap()
+                .searcher()
+                .num_docs(),
+            content_index::open_reader(&content)
+                .unwrap()
+                .searcher()
+                .num_docs(),
+        )
+    }
+
+    #[test]
+    fn wrong_token_rejected_and_correct_token_clears() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths = paths_in(dir.path());
+        populate(&paths);
+        assert_eq!(doc_counts(&paths), (1, 1));
+
+        let store = MetaStoreSection {
+            compress: true,
+            ..MetaStoreSection::default()
+        };
+        let token = reset_token();
+        assert!(reset_indexes(&paths, &store, "not-the-token").is_err());
+        assert!(reset_indexes(&paths, &store, "").is_err());
+        assert_eq!(doc_counts(&paths), (1, 1));
+
+        let epoch_before = index_epoch();
+        let summary = reset_indexes(&paths, &store, &token).unwrap();
+        assert_eq!(summary.meta_docs_removed, 1);
+        assert_eq!(summary.content_docs_removed, 1);
+        assert_eq!(summary.state_files_removed, 1);
+        assert!(summary.epoch > epoch_before);
+        assert_eq!(doc_counts(&paths), (0, 0));
+        // The meta index was recreated, in the configured layout.
+        let meta = meta_index::open_or_create_index(Path::new(&paths.meta_index)).unwrap();
+        assert!(matches!(
+            meta.index.settings().docstore_compression,
+            tantivy::store::Compressor::Zstd(_)
+        ));
+
+        // The token is single-use.
+        assert!(reset_indexes(&paths, &store, &token).is_err());
+        assert_ne!(reset_token(), token);
+    }
+}
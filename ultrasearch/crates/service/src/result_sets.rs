@@ -0,0 +1,178 @@
+//! Matched key sets cached behind `SearchResponse::result_set_id`.
+//!
+//! A request that sets `result_set` has every `DocKey` it matched (not only the returned page)
+//! stored here under a fresh id, so a client can read the set back with an
+//! `ExportResultsRequest` or search within it through `refine_set`. Sets expire after
+//! `search.result_set_ttl_secs`; `search.result_set_max_keys` bounds the keys held across all
+//! sets, dropping the oldest first.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use core_types::DocKey;
+use ipc::{ExportResultsRequest, ExportResultsResponse};
+use parking_lot::Mutex;
+use uuid::Uuid;
+
+struct Entry {
+    id: Uuid,
+    inserted: Instant,
+    keys: Arc<Vec<DocKey>>,
+}
+
+#[derive(Default)]
+struct State {
+    /// Oldest first.
+    sets: VecDeque<Entry>,
+    keys: usize,
+}
+
+impl State {
+    fn pop_oldest(&mut self) {
+        if let Some(entry) = self.sets.pop_front() {
+            self.keys -= entry.keys.len();
+        }
+    }
+}
+
+pub struct ResultSetCache {
+    ttl: Duration,
+    max_keys: usize,
+    state: Mutex<State>,
+}
+
+impl ResultSetCache {
+    pub fn new(ttl: Duration, max_keys: usize) -> Self {
+        Self {
+            ttl,
+            max_keys,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Cache `keys` (sorted, duplicates removed) and return their id, or `None` when the set
+    /// alone exceeds the key budget.
+    pub fn insert(&self, mut keys: Vec<DocKey>) -> Option<Uuid> {
+        keys.sort_unstable();
+        keys.dedup();
+        if keys.len() > self.max_keys {
+            return None;
+        }
+        let now = Instant::now();
+        let mut state = self.state.lock();
+        while state
+            .sets
+            .front()
+            .is_some_and(|e| now.duration_since(e.inserted) >= self.ttl)
+        {
+            state.pop_oldest();
+        }
+        while state.keys + keys.len() > self.max_keys {
+            state.pop_oldest();
+        }
+        let id = Uuid::new_v4();
+        state.keys += keys.len();
+        state.sets.push_back(Entry {
+            id,
+            inserted: now,
+            keys: Arc::new(keys),
+        });
+        Some(id)
+    }
+
+    /// Keys of the set `id`, in ascending order, unless it expired or was evicted.
+    pub fn get(&self, id: Uuid) -> Option<Arc<Vec<DocKey>>> {
+        let state = self.state.lock();
+        state
+            .sets
+            .iter()
+            .find(|e| e.id == id)
+            .filter(|e| e.inserted.elapsed() < self.ttl)
+            .map(|e| e.keys.clone())
+    }
+}
+
+static RESULT_SETS: OnceLock<Arc<ResultSetCache>> = OnceLock::new();
+
+/// Install the process-wide cache; later calls return the installed one.
+pub fn init_result_sets(ttl: Duration, max_keys: usize) -> Arc<ResultSetCache> {
+    RESULT_SETS
+        .get_or_init(|| Arc::new(ResultSetCache::new(ttl, max_keys)))
+        .clone()
+}
+
+pub fn result_sets() -> Option<Arc<ResultSetCache>> {
+    RESULT_SETS.get().cloned()
+}
+
+/// Answer `req` from `cache`: up to `limit` keys of the set from `offset` on.
+pub fn export_results(
+    cache: Option<&ResultSetCache>,
+    req: &ExportResultsRequest,
+) -> ExportResultsResponse {
+    match cache.and_then(|c| c.get(req.result_set_id)) {
+        Some(keys) => ExportResultsResponse {
+            id: req.id,
+            found: true,
+            keys: keys
+                .iter()
+                .skip(req.offset as usize)
+                .take(req.limit as usize)
+                .copied()
+                .collect(),
+            total: keys.len() as u64,
+        },
+        None => ExportResultsResponse {
+            id: req.id,
+            found: false,
+            keys: Vec::new(),
+            total: 0,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(ids: &[u64]) -> Vec<DocKey> {
+        ids.iter().map(|&i| DocKey::from_parts(1, i)).collect()
+    }
+
+    #[test]
+    fn oldest_sets_are_evicted_past_the_key_budget() {
+        let cache = ResultSetCache::new(Duration::from_secs(60), 5);
+        let first = cache.insert(keys(&[3, 1, 2, 1])).unwrap();
+        assert_eq!(*cache.get(first).unwrap(), keys(&[1, 2, 3]));
+        assert!(cache.insert(keys(&[1, 2, 3, 4, 5, 6])).is_none());
+
+        let second = cache.insert(keys(&[7, 8])).unwrap();
+        assert!(cache.get(first).is_some());
+        let third = cache.insert(keys(&[9])).unwrap();
+        assert!(cache.get(first).is_none());
+        assert!(cache.get(second).is_some() && cache.get(third).is_some());
+
+        let req = ExportResultsRequest {
+            id: Uuid::new_v4(),
+            result_set_id: second,
+            offset: 1,
+            limit: 10,
+        };
+        let resp = export_results(Some(&cache), &req);
+        assert!(resp.found);
+        assert_eq!((resp.keys, resp.total), (keys(&[8]), 2));
+        let gone = ExportResultsRequest {
+            result_set_id: first,
+            ..req
+        };
+        assert!(!export_results(Some(&cache), &gone).found);
+    }
+
+    #[test]
+    fn expired_sets_are_not_returned() {
+        let cache = ResultSetCache::new(Duration::ZERO, 10);
+        let id = cache.insert(keys(&[1])).unwrap();
+        assert!(cache.get(id).is_none());
+    }
+}
@@ -1,28 +1,35 @@
+use crate::disk_slots::{DiskId, DiskSlots};
 use crate::dispatcher::job_dispatch::JobSpec;
+use crate::ignore_files::{IgnoreRules, is_ignore_file};
 use crate::meta_ingest::ingest_with_paths;
 use crate::scheduler_runtime::{content_job_from_meta, enqueue_content_job};
 use crate::status_provider::{update_status_last_commit, update_status_volumes};
 use anyhow::Result;
-use core_types::FileMeta;
-use core_types::config::AppConfig;
+use content_extractor::archive::ArchiveExtractor;
+use core_types::config::{AppConfig, ExtractSection, IndexingSection};
+use core_types::{DocKey, FileFlags, FileMeta};
 use ipc::VolumeStatus;
 #[cfg(any())]
 use meta_index::{open_or_create_index, open_reader};
 use ntfs_watcher::{
-    FileEvent, JournalCursor, NtfsError, VolumeInfo, discover_volumes, enumerate_mft, tail_usn,
+    EventFilter, FileEvent, JournalCursor, NtfsError, NtfsWatcher, PollingWatcher, ReaderConfig,
+    VolumeInfo, discover_volumes, enumerate_mft_capped, file_object_id, file_owner,
+    physical_disk_number, tail_usn,
 };
 #[cfg(any())]
 use std::collections::HashMap;
 #[cfg(any())]
 use std::fs;
-#[cfg(any())]
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 #[cfg(any())]
 use tantivy::DocAddress;
 use tokio::time::{Duration, interval};
 
 pub fn scan_volumes(cfg: &AppConfig) -> Result<Vec<JobSpec>> {
+    if !cfg.indexing.watch_folders.is_empty() {
+        return Ok(scan_folders(cfg));
+    }
     tracing::info!("Starting volume scan...");
     let all_volumes = match discover_volumes() {
         Ok(v) if v.is_empty() => {
@@ -65,83 +72,222 @@ pub fn scan_volumes(cfg: &AppConfig) -> Result<Vec<JobSpec>> {
         return Ok(Vec::new());
     }
 
-    let mut jobs: Vec<JobSpec> = Vec::new();
-    let mut status = Vec::with_capacity(volumes.len());
-
-    for volume in volumes {
-        tracing::info!(guid = %volume.guid_path, letters = ?volume.drive_letters, "enumerating MFT for volume");
-        match enumerate_mft(&volume) {
-            Ok(metas) => {
-                if metas.is_empty() {
-                    tracing::info!(guid = %volume.guid_path, "no entries found during MFT enumeration");
-                    continue;
-                }
-
-                let (content_jobs, content_bytes) = build_content_jobs(&metas, cfg);
+    // Volumes are enumerated in parallel, limited per physical disk so partitions of one
+    // drive don't compete for its heads.
+    let slots = DiskSlots::new(cfg.indexing.max_jobs_per_physical_disk);
+    let results: Vec<(Vec<JobSpec>, VolumeStatus)> = std::thread::scope(|s| {
+        let handles: Vec<_> = volumes
+            .iter()
+            .map(|volume| {
+                let slots = &slots;
+                s.spawn(move || {
+                    let disk = DiskId::for_volume(volume.id, physical_disk_number(volume));
+                    let _permit = slots.acquire(disk);
+                    scan_volume(volume, cfg)
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .filter_map(|h| h.join().ok().flatten())
+            .collect()
+    });
 
-                let count = metas.len() as u64;
-                let total_bytes: u64 = metas.iter().map(|m| m.size).sum();
-                tracing::info!(guid = %volume.guid_path, files = count, "ingesting metadata batch into meta-index");
-                match ingest_with_paths(&cfg.paths, metas, None) {
-                    Ok(_) => tracing::info!("Successfully ingested {} files.", count),
-                    Err(e) => tracing::error!("Failed to ingest files: {}", e),
-                }
+    let mut jobs: Vec<JobSpec> = Vec::new();
+    let mut status = Vec::with_capacity(results.len());
+    for (volume_jobs, volume_status) in results {
+        jobs.extend(volume_jobs);
+        status.push(volume_status);
+    }
 
-                let pending_files = content_jobs.len() as u64;
-                jobs.extend(content_jobs);
+    if !status.is_empty() {
+        update_status_volumes(status);
+    }
 
-                status.push(VolumeStatus {
-                    volume: volume.id,
-                    indexed_files: count,
-                    indexed_bytes: total_bytes,
-                    pending_files,
-                    pending_bytes: content_bytes,
-                    last_usn: None,
-                    journal_id: None,
-                });
+    Ok(jobs)
+}
 
-                update_status_last_commit(Some(unix_timestamp_secs()));
+/// Enumerate and ingest one volume; returns its content jobs and status, or `None` if the
+/// volume yielded nothing.
+fn scan_volume(volume: &VolumeInfo, cfg: &AppConfig) -> Option<(Vec<JobSpec>, VolumeStatus)> {
+    let max_entries = Some(cfg.indexing.max_entries_per_volume).filter(|&n| n > 0);
+    tracing::info!(guid = %volume.guid_path, letters = ?volume.drive_letters, "enumerating MFT for volume");
+    match enumerate_mft_capped(volume, max_entries, cfg.indexing.only_modified_since) {
+        Ok(enumeration) => {
+            let mut metas = enumeration.entries;
+            metas.retain(|m| should_index_meta(m, &cfg.indexing));
+            if metas.is_empty() {
+                tracing::info!(guid = %volume.guid_path, "no entries found during MFT enumeration");
+                return None;
             }
-            Err(err) => {
-                let msg = err.to_string();
-                if msg.contains("Access is denied") || msg.contains("privilege") {
-                    tracing::error!(
-                        guid = %volume.guid_path,
-                        "CRITICAL: Failed to enumerate MFT due to permissions. Please run the application as Administrator."
-                    );
-                } else {
-                    tracing::warn!(
-                        guid = %volume.guid_path,
-                        error = %err,
-                        "failed to enumerate MFT; skipping volume"
-                    );
-                }
+            Some(ingest_entries(volume, metas, cfg))
+        }
+        Err(err) => {
+            let msg = err.to_string();
+            if msg.contains("Access is denied") || msg.contains("privilege") {
+                tracing::error!(
+                    guid = %volume.guid_path,
+                    "CRITICAL: Failed to enumerate MFT due to permissions. Please run the application as Administrator."
+                );
+            } else {
+                tracing::warn!(
+                    guid = %volume.guid_path,
+                    error = %err,
+                    "failed to enumerate MFT; skipping volume"
+                );
             }
+            None
         }
     }
+}
 
-    if !status.is_empty() {
-        update_status_volumes(status);
+/// Ingest enumerated entries into the meta index; returns their content jobs and the
+/// volume's status.
+fn ingest_entries(
+    volume: &VolumeInfo,
+    mut metas: Vec<FileMeta>,
+    cfg: &AppConfig,
+) -> (Vec<JobSpec>, VolumeStatus) {
+    if cfg.indexing.index_owner {
+        resolve_owners(&mut metas);
+    }
+    if cfg.indexing.index_object_ids {
+        retire_reused_keys(&metas, cfg);
+    }
+    if cfg.extract.index_archives {
+        let entries = archive_children(&metas, &cfg.extract);
+        metas.extend(entries);
     }
 
-    Ok(jobs)
+    let (content_jobs, content_bytes) = build_content_jobs(&metas, cfg);
+
+    let count = metas.len() as u64;
+    let total_bytes: u64 = metas.iter().map(|m| m.size).sum();
+    tracing::info!(guid = %volume.guid_path, files = count, "ingesting metadata batch into meta-index");
+    match ingest_with_paths(&cfg.paths, metas, None) {
+        Ok(_) => tracing::info!("Successfully ingested {} files.", count),
+        Err(e) => tracing::error!("Failed to ingest files: {}", e),
+    }
+
+    let status = VolumeStatus {
+        volume: volume.id,
+        indexed_files: count,
+        indexed_bytes: total_bytes,
+        pending_files: content_jobs.len() as u64,
+        pending_bytes: content_bytes,
+        last_usn: None,
+        journal_id: None,
+    };
+
+    update_status_last_commit(Some(unix_timestamp_secs()));
+    (content_jobs, status)
+}
+
+/// Folder-watch mode: index only `indexing.watch_folders` instead of whole volumes.
+fn scan_folders(cfg: &AppConfig) -> Vec<JobSpec> {
+    tracing::info!(
+        folders = cfg.indexing.watch_folders.len(),
+        "folder-watch mode: walking configured folders instead of volumes"
+    );
+    let watcher = PollingWatcher::new(folder_roots(&cfg.indexing.watch_folders));
+    let metas = folder_entries(&watcher, &cfg.indexing);
+    if metas.is_empty() {
+        tracing::info!("folder-watch mode: no entries found under the configured folders");
+        update_status_volumes(Vec::new());
+        return Vec::new();
+    }
+    let (jobs, status) = ingest_entries(watcher.volume(), metas, cfg);
+    update_status_volumes(vec![status]);
+    jobs
+}
+
+fn folder_roots(folders: &[String]) -> Vec<PathBuf> {
+    folders.iter().map(PathBuf::from).collect()
+}
+
+/// Everything under the watcher's roots that passes the indexing filters. Entries are keyed
+/// by path ([`ntfs_watcher::polling::path_key`]), matching what the folder watcher emits.
+fn folder_entries(watcher: &PollingWatcher, indexing: &IndexingSection) -> Vec<FileMeta> {
+    let max_entries = Some(indexing.max_entries_per_volume).filter(|&n| n > 0);
+    let enumeration = match watcher.enumerate_mft_capped(
+        watcher.volume(),
+        max_entries,
+        indexing.only_modified_since,
+    ) {
+        Ok(enumeration) => enumeration,
+        Err(err) => {
+            tracing::warn!(error = %err, "folder-watch mode: walking folders failed");
+            return Vec::new();
+        }
+    };
+    let mut metas = enumeration.entries;
+    let ignores = indexing.respect_gitignore.then(|| ignore_rules(&metas));
+    metas.retain(|m| should_index_meta(m, indexing));
+    if let Some(rules) = &ignores {
+        metas.retain(|m| !is_ignored_meta(rules, m));
+    }
+    metas
+}
+
+/// Rules from the `.gitignore` / `.ignore` files among `metas` (`indexing.respect_gitignore`).
+fn ignore_rules(metas: &[FileMeta]) -> IgnoreRules {
+    IgnoreRules::from_files(
+        metas
+            .iter()
+            .filter(|m| is_ignore_file(&m.name))
+            .filter_map(|m| m.path.as_deref().map(PathBuf::from))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn is_ignored_meta(rules: &IgnoreRules, meta: &FileMeta) -> bool {
+    meta.path
+        .as_deref()
+        .is_some_and(|p| rules.is_ignored(p, meta.flags.contains(FileFlags::IS_DIR)))
+}
+
+/// Re-read `rules` if `events` create, rename or modify an ignore file.
+fn reload_ignore_rules(rules: &mut IgnoreRules, events: &[FileEvent], watcher: &PollingWatcher) {
+    let changed: Vec<PathBuf> = events
+        .iter()
+        .filter_map(|ev| match ev {
+            FileEvent::Created(meta) | FileEvent::Renamed { to: meta, .. } => Some(meta.clone()),
+            FileEvent::Modified { doc } => watcher.current_meta(*doc),
+            _ => None,
+        })
+        .filter(|m| is_ignore_file(&m.name))
+        .filter_map(|m| m.path.map(PathBuf::from))
+        .collect();
+    if changed.is_empty() {
+        return;
+    }
+    let mut files: Vec<PathBuf> = rules.files().map(std::path::Path::to_path_buf).collect();
+    files.extend(changed);
+    files.sort();
+    files.dedup();
+    *rules = IgnoreRules::from_files(files);
 }
 
 /// Spawn a background task that tails the USN journal (where available) and enqueues content jobs.
 pub async fn watch_changes(cfg: AppConfig) -> Result<()> {
+    if !cfg.indexing.watch_folders.is_empty() {
+        let roots = folder_roots(&cfg.indexing.watch_folders);
+        let honor_ignores = cfg.indexing.respect_gitignore;
+        return watch_dirs(cfg, roots, honor_ignores).await;
+    }
     let volumes = match discover_volumes() {
         Ok(v) if v.is_empty() => {
             tracing::info!("change watcher: no NTFS volumes discovered");
-            return Ok(());
+            return watch_poll_dirs(cfg).await;
         }
         Ok(v) => filter_volumes(cfg.clone(), v),
         Err(NtfsError::NotSupported) => {
             tracing::info!("change watcher: USN not supported; falling back to polling.");
-            return Ok(());
+            return watch_poll_dirs(cfg).await;
         }
         Err(err) => {
             tracing::warn!(error = %err, "change watcher: failed to discover volumes");
-            return Ok(());
+            return watch_poll_dirs(cfg).await;
         }
     };
 
@@ -150,8 +296,9 @@ pub async fn watch_changes(cfg: AppConfig) -> Result<()> {
         update_status_volumes(Vec::new());
         return Ok(());
     }
+    let mut volumes = volumes;
 
-    // Initialize cursors per volume (start at 0).
+    // Initialize cursors per volume; the first tail moves them to the journal's end.
     let mut cursors = volumes
         .iter()
         .map(|v| {
@@ -165,18 +312,24 @@ pub async fn watch_changes(cfg: AppConfig) -> Result<()> {
         })
         .collect::<std::collections::HashMap<_, _>>();
 
+    let reader = ReaderConfig::default();
+    let mut filter = EventFilter::new(cfg.indexing.watch_structure_only)
+        .with_rename_coalescing(cfg.indexing.coalesce_renames);
     let mut ticker = interval(Duration::from_secs(5));
     loop {
         ticker.tick().await;
+        let mut failed = Vec::new();
         for vol in volumes.iter() {
             let cursor = *cursors.get(&vol.id).unwrap_or(&JournalCursor {
                 last_usn: 0,
                 journal_id: 0,
             });
 
-            match tail_usn(vol, cursor) {
+            match tail_usn(vol, cursor, &reader) {
                 Ok((events, next)) => {
+                    let events = filter.apply(events);
                     if !events.is_empty() {
+                        remember_renames(&events, &cfg);
                         let jobs = events_to_jobs(&events, &cfg);
                         let mut dropped = 0;
                         for job in jobs {
@@ -195,14 +348,134 @@ pub async fn watch_changes(cfg: AppConfig) -> Result<()> {
                     }
                     cursors.insert(vol.id, next);
                 }
-                Err(NtfsError::GapDetected) => {
-                    tracing::warn!("USN gap detected on volume {}; consider rescan", vol.id);
+                Err(NtfsError::GapDetected { restart }) => {
+                    tracing::warn!(
+                        volume = vol.id,
+                        "USN journal wrapped or was recreated; rescanning volumes"
+                    );
+                    cursors.insert(vol.id, restart);
+                    let cfg = cfg.clone();
+                    tokio::task::spawn_blocking(move || match scan_volumes(&cfg) {
+                        Ok(jobs) => {
+                            for job in jobs {
+                                enqueue_content_job(job);
+                            }
+                        }
+                        Err(e) => tracing::error!("Rescan after USN gap failed: {}", e),
+                    });
                 }
                 Err(err) => {
                     tracing::warn!(volume = vol.id, error = %err, "tail_usn failed");
+                    failed.push(vol.id);
+                }
+            }
+        }
+        if !failed.is_empty() {
+            volumes.retain(|v| !failed.contains(&v.id));
+            if volumes.is_empty() {
+                tracing::warn!("change watcher: USN unavailable on all volumes");
+                return watch_poll_dirs(cfg).await;
+            }
+        }
+    }
+}
+
+/// Fallback when the USN journal can't be used: re-scan `indexing.poll_dirs` on an interval
+/// and enqueue content jobs for created, renamed, and modified files.
+async fn watch_poll_dirs(cfg: AppConfig) -> Result<()> {
+    if cfg.indexing.poll_dirs.is_empty() {
+        tracing::info!("change watcher: no indexing.poll_dirs configured; not watching");
+        return Ok(());
+    }
+    let roots = folder_roots(&cfg.indexing.poll_dirs);
+    watch_dirs(cfg, roots, false).await
+}
+
+/// Re-scan `roots` every `indexing.poll_interval_secs` and enqueue content jobs for the
+/// differences. Used for `poll_dirs` and folder-watch mode; `honor_ignores` drops jobs for
+/// files matched by ignore files under `roots`.
+async fn watch_dirs(cfg: AppConfig, roots: Vec<PathBuf>, honor_ignores: bool) -> Result<()> {
+    let dirs = roots.len();
+    let watcher = std::sync::Arc::new(PollingWatcher::new(roots));
+    let volume = watcher.volume().clone();
+    tracing::info!(
+        dirs,
+        interval_secs = cfg.indexing.poll_interval_secs,
+        "change watcher: polling directories"
+    );
+
+    // Baseline scan; only later differences produce jobs.
+    let baseline = watcher.clone();
+    let vol = volume.clone();
+    let snapshot = tokio::task::spawn_blocking(move || baseline.enumerate_mft(&vol)).await??;
+    let mut filter = EventFilter::new(cfg.indexing.watch_structure_only)
+        .with_rename_coalescing(cfg.indexing.coalesce_renames);
+    filter.observe(&snapshot);
+    let mut ignores = honor_ignores.then(|| ignore_rules(&snapshot));
+
+    let mut cursor = JournalCursor {
+        last_usn: 0,
+        journal_id: 0,
+    };
+    let mut ticker = interval(Duration::from_secs(cfg.indexing.poll_interval_secs.max(1)));
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        let w = watcher.clone();
+        let vol = volume.clone();
+        let (events, next) =
+            match tokio::task::spawn_blocking(move || w.tail_usn(&vol, cursor)).await? {
+                Ok(res) => res,
+                Err(err) => {
+                    tracing::warn!(error = %err, "polling scan failed");
+                    continue;
                 }
+            };
+        cursor = next;
+        let events = filter.apply(events);
+        if events.is_empty() {
+            continue;
+        }
+        remember_renames(&events, &cfg);
+        let mut jobs = events_to_jobs(&events, &cfg);
+        for ev in &events {
+            if let FileEvent::Modified { doc } = ev
+                && let Some(meta) = watcher.current_meta(*doc)
+                && should_index_meta(&meta, &cfg.indexing)
+                && let Some(job) = content_job_from_meta(&meta, &cfg.extract)
+            {
+                jobs.push(job);
             }
         }
+        if let Some(rules) = ignores.as_mut() {
+            reload_ignore_rules(rules, &events, &watcher);
+            jobs.retain(|job| !rules.is_ignored(&job.path.to_string_lossy(), false));
+        }
+        let dropped = jobs
+            .into_iter()
+            .filter(|job| !enqueue_content_job(job.clone()))
+            .count();
+        tracing::debug!(
+            events = events.len(),
+            dropped,
+            "polling watcher enqueued jobs for {} events",
+            events.len()
+        );
+    }
+}
+
+/// Record the old names of renamed files when `indexing.former_names` is set.
+fn remember_renames(events: &[FileEvent], cfg: &AppConfig) {
+    if cfg.indexing.former_names == 0 {
+        return;
+    }
+    let path = crate::former_names::former_names_path(&cfg.paths);
+    let result = crate::former_names::init_former_name_store(&path, cfg.indexing.former_names)
+        .and_then(|store| {
+            crate::former_names::record_renames(&store, Path::new(&cfg.paths.meta_index), events)
+        });
+    if let Err(e) = result {
+        tracing::warn!("failed to record former names: {e:#}");
     }
 }
 
@@ -213,6 +486,119 @@ fn unix_timestamp_secs() -> i64 {
         .unwrap_or(0)
 }
 
+/// Fill in owner SIDs for entries with a resolved path; unreadable ones stay `None`.
+fn resolve_owners(metas: &mut [FileMeta]) {
+    let mut missing = 0usize;
+    for meta in metas.iter_mut() {
+        meta.owner = meta.path.as_deref().and_then(file_owner);
+        if meta.owner.is_none() {
+            missing += 1;
+        }
+    }
+    if missing > 0 {
+        tracing::debug!(missing, "owner SID unavailable for {} entries", missing);
+    }
+}
+
+/// Read object IDs for entries with a resolved path and retire the keys whose file changed
+/// since the last scan, so they are ingested as new files rather than updates of the old
+/// ones. Unreadable entries are left as they were.
+fn retire_reused_keys(metas: &[FileMeta], cfg: &AppConfig) {
+    let observed: Vec<_> = metas
+        .iter()
+        .filter_map(|m| Some((m.key, file_object_id(m.path.as_deref()?).ok()?)))
+        .collect();
+    let result = crate::object_ids::init_object_id_store(&crate::object_ids::object_ids_path(cfg))
+        .and_then(|store| store.observe(&observed))
+        .and_then(|reused| {
+            if !reused.is_empty() {
+                tracing::info!(
+                    count = reused.len(),
+                    "file record numbers reused by new files"
+                );
+            }
+            crate::object_ids::retire_reused(cfg, &reused)
+        });
+    if let Err(e) = result {
+        tracing::warn!("failed to check object ids: {e:#}");
+    }
+}
+
+/// System folders hidden from results unless `indexing.include_recycle_bin` is set.
+const SYSTEM_EXCLUDED_DIRS: &[&str] = &["$Recycle.Bin", "System Volume Information"];
+
+/// Whether a file should be ingested given the indexing filters.
+pub(crate) fn should_index_path(path: Option<&str>, indexing: &IndexingSection) -> bool {
+    let Some(path) = path else {
+        return true;
+    };
+    if indexing.max_path_depth > 0 && path_depth(path) > indexing.max_path_depth {
+        return false;
+    }
+    if indexing.include_recycle_bin {
+        return true;
+    }
+    !path.split(['\\', '/']).any(|component| {
+        SYSTEM_EXCLUDED_DIRS
+            .iter()
+            .any(|dir| component.eq_ignore_ascii_case(dir))
+    })
+}
+
+/// Components below the volume root: `C:\a\b.txt` is at depth 2. Drive and `\\?\`
+/// prefixes don't count.
+fn path_depth(path: &str) -> usize {
+    path.split(['\\', '/'])
+        .filter(|p| !p.is_empty() && !p.ends_with(':') && !matches!(*p, "?" | "."))
+        .count()
+}
+
+/// [`should_index_path`] plus entry-level filters such as skipping mount points.
+pub(crate) fn should_index_meta(meta: &FileMeta, indexing: &IndexingSection) -> bool {
+    if meta.flags.contains(FileFlags::MOUNT_POINT) && !indexing.follow_mount_points {
+        return false;
+    }
+    should_index_path(meta.path.as_deref(), indexing)
+}
+
+/// Virtual entries for the files inside each archive among `metas` (`extract.index_archives`),
+/// keyed by [`DocKey::archive_entry`] with the archive as `parent`. An entry's path continues
+/// the archive's, e.g. `C:\backup.zip\docs\report.pdf`. Unreadable archives are skipped.
+fn archive_children(metas: &[FileMeta], extract: &ExtractSection) -> Vec<FileMeta> {
+    let lister = ArchiveExtractor::new(extract.archive_max_bytes, extract.archive_max_entries);
+    let mut children = Vec::new();
+    for archive in metas {
+        let Some(path) = archive.path.as_deref() else {
+            continue;
+        };
+        if archive.flags.is_dir() || !ArchiveExtractor::supports(Path::new(path)) {
+            continue;
+        }
+        let entries = match lister.entries(Path::new(path)) {
+            Ok(entries) => entries,
+            Err(err) => {
+                tracing::debug!(path, error = %err, "skipping unreadable archive");
+                continue;
+            }
+        };
+        children.extend(entries.into_iter().map(|entry| {
+            let modified = entry.modified.unwrap_or(archive.modified);
+            FileMeta::new(
+                DocKey::archive_entry(archive.key, &entry.path),
+                archive.volume,
+                Some(archive.key),
+                entry.name().to_string(),
+                Some(format!("{path}\\{}", entry.path.replace('/', "\\"))),
+                entry.size,
+                modified,
+                modified,
+                FileFlags::ARCHIVE_ENTRY,
+            )
+        }));
+    }
+    children
+}
+
 fn build_content_jobs(metas: &[FileMeta], cfg: &AppConfig) -> (Vec<JobSpec>, u64) {
     let mut total_bytes = 0u64;
     let jobs = metas
@@ -251,11 +637,17 @@ fn events_to_jobs(events: &[FileEvent], cfg: &AppConfig) -> Vec<JobSpec> {
     for ev in events {
         match ev {
             FileEvent::Created(meta) => {
+                if !should_index_meta(meta, &cfg.indexing) {
+                    continue;
+                }
                 if let Some(job) = content_job_from_meta(meta, &cfg.extract) {
                     out.push(job);
                 }
             }
             FileEvent::Renamed { to, .. } => {
+                if !should_index_meta(to, &cfg.indexing) {
+                    continue;
+                }
                 if let Some(job) = content_job_from_meta(to, &cfg.extract) {
                     out.push(job);
                 }
@@ -360,6 +752,7 @@ fn detect_changed_files(
                             created: meta_doc.created,
                             modified: current_mtime,
                             flags: core_types::FileFlags::empty(),
+                            owner: meta_doc.owner.clone(),
                         },
                         &cfg.extract,
                     )
@@ -374,3 +767,223 @@ fn detect_changed_files(
 
     Ok(changed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recycle_bin_excluded_by_default_and_included_with_toggle() {
+        let recycle = Some(r"C:\$Recycle.Bin\S-1-5-21-1000\$RABC123.txt");
+        let svi = Some(r"C:\System Volume Information\tracking.log");
+        let user = Some(r"C:\Users\alice\Documents\notes.txt");
+
+        let default = IndexingSection::default();
+        assert!(!should_index_path(recycle, &default));
+        assert!(!should_index_path(svi, &default));
+        assert!(should_index_path(user, &default));
+
+        let include = IndexingSection {
+            include_recycle_bin: true,
+            ..IndexingSection::default()
+        };
+        assert!(should_index_path(recycle, &include));
+        assert!(should_index_path(svi, &include));
+        assert!(should_index_path(user, &include));
+    }
+
+    #[test]
+    fn recycle_bin_match_is_case_insensitive_and_component_based() {
+        let default = IndexingSection::default();
+        assert!(!should_index_path(Some(r"D:\$RECYCLE.BIN\x"), &default));
+        assert!(should_index_path(
+            Some(r"D:\notes\$Recycle.Bin.txt"),
+            &default
+        ));
+        assert!(should_index_path(None, &default));
+    }
+
+    #[test]
+    fn entries_below_max_path_depth_are_skipped() {
+        let path = |depth: usize| {
+            let dirs: Vec<String> = (1..depth).map(|i| format!("d{i}")).collect();
+            format!(r"C:\{}\file.txt", dirs.join("\\"))
+        };
+        assert_eq!(path_depth(&path(5)), 5);
+        assert_eq!(path_depth(r"\\?\C:\a\b.txt"), 2);
+
+        let limited = IndexingSection {
+            max_path_depth: 8,
+            ..IndexingSection::default()
+        };
+        assert!(!should_index_path(Some(&path(12)), &limited));
+        assert!(should_index_path(Some(&path(5)), &limited));
+        assert!(should_index_path(Some(&path(8)), &limited));
+        // The directory at the limit is kept; its children are not.
+        assert!(should_index_path(
+            Some(r"C:\1\2\3\4\5\6\7\node_modules"),
+            &limited
+        ));
+        assert!(!should_index_path(
+            Some(r"C:\1\2\3\4\5\6\7\node_modules\pkg"),
+            &limited
+        ));
+
+        // Unlimited by default; system folders stay excluded under a limit.
+        assert!(should_index_path(
+            Some(&path(12)),
+            &IndexingSection::default()
+        ));
+        assert!(!should_index_path(Some(r"C:\$Recycle.Bin\x"), &limited));
+    }
+
+    #[test]
+    fn mount_points_skipped_by_default_and_kept_when_following() {
+        let dir = |frn: u64, name: &str, flags: FileFlags| {
+            FileMeta::new(
+                core_types::DocKey::from_parts(1, frn),
+                1,
+                None,
+                name.into(),
+                Some(format!(r"C:\{name}")),
+                0,
+                0,
+                0,
+                FileFlags::IS_DIR | flags,
+            )
+        };
+        let mount = dir(
+            10,
+            "Data",
+            ntfs_watcher::reparse_flags(Some(ntfs_watcher::IO_REPARSE_TAG_MOUNT_POINT)),
+        );
+        let plain = dir(11, "Projects", FileFlags::empty());
+        assert!(mount.flags.contains(FileFlags::MOUNT_POINT));
+
+        let default = IndexingSection::default();
+        assert!(!should_index_meta(&mount, &default));
+        assert!(should_index_meta(&plain, &default));
+
+        let follow = IndexingSection {
+            follow_mount_points: true,
+            ..IndexingSection::default()
+        };
+        assert!(should_index_meta(&mount, &follow));
+    }
+
+    #[test]
+    fn folder_watch_indexes_configured_roots_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let docs = dir.path().join("Documents");
+        let sibling = dir.path().join("Music");
+        std::fs::create_dir_all(docs.join("taxes")).unwrap();
+        std::fs::create_dir_all(&sibling).unwrap();
+        std::fs::write(docs.join("notes.txt"), b"notes").unwrap();
+        std::fs::write(docs.join("taxes").join("2025.pdf"), b"%PDF-").unwrap();
+        std::fs::write(sibling.join("song.mp3"), b"ID3").unwrap();
+
+        let folders = vec![docs.to_string_lossy().into_owned()];
+        let watcher = PollingWatcher::new(folder_roots(&folders));
+        let metas = folder_entries(&watcher, &IndexingSection::default());
+
+        let mut names: Vec<&str> = metas.iter().map(|m| m.name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, vec!["2025.pdf", "notes.txt", "taxes"]);
+        // Keyed the same way the folder watcher keys its events.
+        let notes_path = docs.join("notes.txt").to_string_lossy().into_owned();
+        let notes = metas.iter().find(|m| m.name == "notes.txt").unwrap();
+        assert_eq!(
+            notes.key,
+            ntfs_watcher::polling::path_key(watcher.volume().id, &notes_path)
+        );
+    }
+
+    #[test]
+    fn folder_watch_skips_gitignored_files_when_enabled() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(repo.join("src")).unwrap();
+        std::fs::create_dir_all(repo.join("target").join("debug")).unwrap();
+        std::fs::write(repo.join(".gitignore"), "target/\n*.log\n").unwrap();
+        std::fs::write(repo.join("src").join("main.rs"), b"fn main() {}").unwrap();
+        std::fs::write(repo.join("build.log"), b"ok").unwrap();
+        std::fs::write(repo.join("target").join("debug").join("app"), b"ELF").unwrap();
+
+        let folders = vec![repo.to_string_lossy().into_owned()];
+        let watcher = PollingWatcher::new(folder_roots(&folders));
+        let names = |indexing: &IndexingSection| {
+            let mut names: Vec<String> = folder_entries(&watcher, indexing)
+                .into_iter()
+                .map(|m| m.name)
+                .collect();
+            names.sort_unstable();
+            names
+        };
+
+        let respect = IndexingSection {
+            respect_gitignore: true,
+            ..IndexingSection::default()
+        };
+        assert_eq!(names(&respect), vec![".gitignore", "main.rs", "src"]);
+        // Off by default: everything is indexed.
+        assert!(names(&IndexingSection::default()).contains(&"build.log".to_string()));
+    }
+
+    #[test]
+    fn zip_entries_are_searchable_and_point_at_their_archive() {
+        use ipc::{FieldKind, QueryExpr, SearchMode, SearchRequest, TermExpr, TermModifier};
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let folder = dir.path().join("files");
+        std::fs::create_dir_all(&folder).unwrap();
+        let zip_path = folder.join("backup.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        zip.start_file("docs/report.pdf", zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(b"%PDF-1.7").unwrap();
+        zip.finish().unwrap();
+
+        let watcher = PollingWatcher::new(vec![folder.clone()]);
+        let mut metas = folder_entries(&watcher, &IndexingSection::default());
+        let extract = ExtractSection {
+            index_archives: true,
+            ..ExtractSection::default()
+        };
+        let children = archive_children(&metas, &extract);
+        assert_eq!(children.len(), 1);
+        // Archive entries never become content jobs.
+        assert!(content_job_from_meta(&children[0], &extract).is_none());
+        metas.extend(children);
+
+        let meta_dir = dir.path().join("meta");
+        std::fs::create_dir_all(&meta_dir).unwrap();
+        crate::meta_ingest::ingest_file_meta_batch(&meta_dir, metas, None).unwrap();
+        let handler = crate::search_handler::UnifiedSearchHandler::try_new(
+            &meta_dir,
+            &dir.path().join("content"),
+        )
+        .unwrap();
+        let resp = crate::search_handler::SearchHandler::search(
+            &handler,
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: "report".into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::NameOnly),
+        );
+
+        assert_eq!(resp.hits.len(), 1);
+        let hit = &resp.hits[0];
+        let zip_str = zip_path.to_string_lossy().into_owned();
+        let archive_key = ntfs_watcher::polling::path_key(watcher.volume().id, &zip_str);
+        assert_eq!(hit.name.as_deref(), Some("report.pdf"));
+        assert_eq!(hit.archive, Some(archive_key));
+        assert_eq!(
+            hit.key,
+            DocKey::archive_entry(archive_key, "docs/report.pdf")
+        );
+        assert_eq!(hit.path, Some(format!(r"{zip_str}\docs\report.pdf")));
+    }
+}
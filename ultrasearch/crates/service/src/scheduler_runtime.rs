@@ -1,19 +1,25 @@
 use crate::dispatcher::job_dispatch::{JobDispatcher, JobSpec};
+use crate::index_budget::{self, TrimReport};
 use crate::scanner;
+use crate::scheduler_trace::{SchedulerDecision, decision_log, record_decision};
 use crate::status_provider::{
     increment_content_plan, update_content_remaining, update_status_metrics,
     update_status_queue_state, update_status_scheduler_state,
 };
 use core_types::FileMeta;
-use core_types::config::{AppConfig, ExtractSection};
+use core_types::config::IndexWindow;
+use core_types::config::{AppConfig, ContentMergeSection, ExtractSection};
+use core_types::filenames::classify_filename_with;
 use scheduler::{
-    SchedulerConfig, allow_content_jobs, idle::IdleTracker, metrics::SystemLoadSampler,
+    AdaptivePolicy, ForegroundSource, IdleState, OsForeground, QuietHours, SchedulerConfig,
+    SystemLoad, content_blockers, foreground::is_within, idle::IdleTracker,
+    metrics::SystemLoadSampler,
 };
 use std::collections::VecDeque;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
 use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::task;
 
@@ -32,6 +38,11 @@ static JOB_SENDER: OnceLock<mpsc::UnboundedSender<JobSpec>> = OnceLock::new();
 static RUNTIME_ACTIVE: AtomicBool = AtomicBool::new(false);
 
 const MAX_CONTENT_QUEUE: usize = 100_000;
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+/// Minimum time between idle prefetches of frequent queries' postings.
+const PREFETCH_INTERVAL: Duration = Duration::from_secs(600);
+/// How often the locked-file retry queue is checked for due jobs.
+const LOCKED_RETRY_INTERVAL: Duration = Duration::from_secs(30);
 
 /// Runtime wrapper that drives a simple scheduling loop and dispatches content batches.
 pub struct SchedulerRuntime {
@@ -44,6 +55,29 @@ pub struct SchedulerRuntime {
     live: &'static SchedulerLiveState,
     current_volumes: Vec<String>,
     force_allow_content: bool,
+    /// Throughput-driven batch sizing; present when `features.adaptive_scheduler` is on.
+    adaptive: Option<AdaptivePolicy>,
+    /// Raw `scheduler.quiet_hours` last applied, so config reloads only re-parse on change.
+    quiet_hours_src: Vec<IndexWindow>,
+    content_index_dir: PathBuf,
+    meta_index_dir: PathBuf,
+    merge: ContentMergeSection,
+    /// `indexing.max_index_bytes`, enforced after each committed batch (0 = unlimited).
+    max_index_bytes: u64,
+    /// When deep-idle compaction last ran; `None` until the first run.
+    last_compaction: Option<Instant>,
+    /// `search.prefetch_queries` (0 = off).
+    prefetch_queries: usize,
+    /// When the idle query prefetch last ran; `None` until the first run.
+    last_prefetch: Option<Instant>,
+    /// `locked_retry.jsonl` under `paths.jobs_dir`, written by the index worker.
+    locked_retry_path: PathBuf,
+    /// When the locked-file retry queue was last checked.
+    last_locked_retry: Option<Instant>,
+    /// Present when `scheduler.follow_foreground` is on.
+    foreground: Option<Box<dyn ForegroundSource>>,
+    /// Foreground directory the queue was last reordered for.
+    foreground_dir: Option<PathBuf>,
 }
 
 impl SchedulerRuntime {
@@ -56,6 +90,9 @@ impl SchedulerRuntime {
             disk_busy_threshold_bps: app_cfg.scheduler.disk_busy_bytes_per_s,
             content_batch_size: app_cfg.scheduler.content_batch_size as usize,
             power_save_mode: app_cfg.scheduler.power_save_mode,
+            power_profile: app_cfg.scheduler.power_profile,
+            battery_floor_pct: app_cfg.scheduler.battery_floor_pct,
+            quiet_hours: parse_quiet_hours(&app_cfg.scheduler.quiet_hours),
             ..SchedulerConfig::default()
         };
 
@@ -72,10 +109,30 @@ impl SchedulerRuntime {
             content_jobs: VecDeque::new(),
             job_rx: rx,
             dispatcher: JobDispatcher::new(app_cfg),
+            adaptive: app_cfg
+                .features
+                .adaptive_scheduler
+                .then(|| AdaptivePolicy::new(config.clone())),
             config,
             live,
             current_volumes: app_cfg.volumes.clone(),
             force_allow_content: false,
+            quiet_hours_src: app_cfg.scheduler.quiet_hours.clone(),
+            content_index_dir: PathBuf::from(&app_cfg.paths.content_index),
+            meta_index_dir: PathBuf::from(&app_cfg.paths.meta_index),
+            merge: app_cfg.content_merge.clone(),
+            max_index_bytes: app_cfg.indexing.max_index_bytes,
+            last_compaction: None,
+            prefetch_queries: app_cfg.search.prefetch_queries,
+            last_prefetch: None,
+            locked_retry_path: Path::new(&app_cfg.paths.jobs_dir)
+                .join(crate::locked_retry::LOCKED_RETRY_FILE),
+            last_locked_retry: None,
+            foreground: app_cfg
+                .scheduler
+                .follow_foreground
+                .then(|| Box::new(OsForeground) as Box<dyn ForegroundSource>),
+            foreground_dir: None,
         }
     }
 
@@ -104,6 +161,28 @@ impl SchedulerRuntime {
         self.config.disk_busy_threshold_bps = app_cfg.scheduler.disk_busy_bytes_per_s;
         self.config.content_batch_size = app_cfg.scheduler.content_batch_size as usize;
         self.config.power_save_mode = app_cfg.scheduler.power_save_mode;
+        self.config.power_profile = app_cfg.scheduler.power_profile;
+        self.config.battery_floor_pct = app_cfg.scheduler.battery_floor_pct;
+        if self.quiet_hours_src != app_cfg.scheduler.quiet_hours {
+            self.quiet_hours_src = app_cfg.scheduler.quiet_hours.clone();
+            self.config.quiet_hours = parse_quiet_hours(&self.quiet_hours_src);
+        }
+        self.merge = app_cfg.content_merge.clone();
+        self.max_index_bytes = app_cfg.indexing.max_index_bytes;
+        self.prefetch_queries = app_cfg.search.prefetch_queries;
+        if app_cfg.scheduler.follow_foreground != self.foreground.is_some() {
+            self.set_foreground_source(
+                app_cfg
+                    .scheduler
+                    .follow_foreground
+                    .then(|| Box::new(OsForeground) as Box<dyn ForegroundSource>),
+            );
+        }
+
+        let mut decisions = decision_log().lock();
+        if decisions.capacity() != app_cfg.scheduler.decision_trace_len {
+            decisions.set_capacity(app_cfg.scheduler.decision_trace_len);
+        }
     }
 
     /// Submit a content indexing job (path + doc ids).
@@ -121,6 +200,12 @@ impl SchedulerRuntime {
         }
     }
 
+    /// Replace the foreground detector (`None` turns following off).
+    pub fn set_foreground_source(&mut self, source: Option<Box<dyn ForegroundSource>>) {
+        self.foreground = source;
+        self.foreground_dir = None;
+    }
+
     /// Force content jobs to run regardless of idle/load (useful for tests).
     pub fn force_allow_content(&mut self) {
         self.force_allow_content = true;
@@ -136,7 +221,7 @@ impl SchedulerRuntime {
     }
 
     pub async fn run_loop(mut self) {
-        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
         loop {
             interval.tick().await;
             self.tick().await;
@@ -149,13 +234,21 @@ impl SchedulerRuntime {
         self.update_config(&app_cfg);
 
         // Drain any newly submitted content jobs.
+        let mut received = 0usize;
         while let Ok(job) = self.job_rx.try_recv() {
             self.push_job(job);
+            received += 1;
         }
+        received += self.requeue_locked_files();
+        self.promote_foreground(received > 0);
         self.update_live_counts();
 
         let idle_sample = self.idle.sample();
         let load = self.load.sample();
+        if let Some(policy) = self.adaptive.as_mut() {
+            policy.record_enqueued(received, TICK_INTERVAL);
+            policy.update(&load);
+        }
 
         // Update status snapshot counts + active workers.
         let ct = self.content_jobs.len();
@@ -176,24 +269,19 @@ impl SchedulerRuntime {
         update_status_metrics(None);
 
         // Gate metadata/content on policies; we only have content jobs for now.
-        let mut allow_content =
-            self.force_allow_content || allow_content_jobs(idle_sample.state, load, &self.config);
-
-        // If backlog is large, override load/idle gates to prevent permanent stalls.
-        let backlog = self.content_jobs.len();
-        if backlog >= (MAX_CONTENT_QUEUE / 2) {
-            allow_content = true;
+        let mut decision = self.plan_tick(idle_sample.state, &load);
+        let deep_idle_allowed = decision.blocked.is_empty();
+        if self.backlog_override_applies() {
             tracing::warn!(
                 "Backlog high ({} jobs, max {}); overriding load gates to drain queue",
-                backlog,
+                self.content_jobs.len(),
                 MAX_CONTENT_QUEUE
             );
         }
 
-        if allow_content && !self.content_jobs.is_empty() {
+        if decision.content_allowed && !self.content_jobs.is_empty() {
             let batch_size = self
-                .config
-                .content_batch_size
+                .current_batch_size()
                 .min(self.content_jobs.len())
                 .max(1);
 
@@ -206,13 +294,182 @@ impl SchedulerRuntime {
 
             self.update_live_counts();
             self.live.active_workers.fetch_add(1, Ordering::Relaxed);
-
-            if let Err(e) = self.dispatcher.spawn_batch(batch).await {
-                tracing::error!("failed to dispatch batch: {e:?}");
+            decision.jobs_dispatched = batch.len();
+            decision.workers_spawned = 1;
+
+            let started = Instant::now();
+            match self.dispatcher.spawn_batch(batch).await {
+                Ok(()) => {
+                    if let Some(policy) = self.adaptive.as_mut() {
+                        policy.record_completion(batch_size, started.elapsed());
+                    }
+                    self.enforce_index_budget().await;
+                }
+                Err(e) => tracing::error!("failed to dispatch batch: {e:?}"),
             }
 
             self.live.active_workers.fetch_sub(1, Ordering::Relaxed);
+        } else if deep_idle_allowed && self.content_jobs.is_empty() && self.compaction_due() {
+            self.last_compaction = Some(Instant::now());
+            decision.compaction = true;
+            let dir = self.content_index_dir.clone();
+            let max_segments = self.merge.compact_max_segments;
+            match task::spawn_blocking(move || compact_content_index(&dir, max_segments)).await {
+                Ok(Ok(Some((before, after)))) => {
+                    tracing::info!(
+                        "idle compaction merged content index: {before} -> {after} segments"
+                    )
+                }
+                Ok(Ok(None)) => {}
+                Ok(Err(e)) => tracing::warn!("idle compaction failed: {e:?}"),
+                Err(e) => tracing::warn!("idle compaction task failed: {e}"),
+            }
+        } else if deep_idle_allowed && self.content_jobs.is_empty() && self.prefetch_due() {
+            self.last_prefetch = Some(Instant::now());
+            decision.prefetch = true;
+            let dir = self.content_index_dir.clone();
+            let top_n = self.prefetch_queries;
+            match task::spawn_blocking(move || prefetch_frequent_queries(&dir, top_n)).await {
+                Ok(Ok(0)) => {}
+                Ok(Ok(read)) => {
+                    tracing::debug!("idle prefetch read {read} posting lists of frequent queries")
+                }
+                Ok(Err(e)) => tracing::warn!("idle query prefetch failed: {e:?}"),
+                Err(e) => tracing::warn!("idle query prefetch task failed: {e}"),
+            }
+        }
+        record_decision(decision);
+    }
+
+    /// Evaluate this tick's content gates. `content_allowed` includes the overrides: forced
+    /// content (tests) and a near-full backlog, which bypasses load/idle gates to prevent
+    /// permanent stalls.
+    fn plan_tick(&self, idle: IdleState, load: &SystemLoad) -> SchedulerDecision {
+        let blocked = content_blockers(idle, *load, &self.config);
+        let mut decision = SchedulerDecision::new(idle, load, self.content_jobs.len(), blocked);
+        if !decision.content_allowed {
+            if self.force_allow_content {
+                decision.content_allowed = true;
+                decision.override_reason = Some("forced");
+            } else if self.backlog_override_applies() {
+                decision.content_allowed = true;
+                decision.override_reason = Some("backlog");
+            }
+        }
+        decision
+    }
+
+    /// Quiet hours are a user schedule, not a load gate, so they are never overridden.
+    fn backlog_override_applies(&self) -> bool {
+        self.content_jobs.len() >= (MAX_CONTENT_QUEUE / 2) && self.config.quiet_hours.allows_now()
+    }
+
+    /// Trim the indexes back under `indexing.max_index_bytes` after a batch committed.
+    async fn enforce_index_budget(&mut self) {
+        if self.max_index_bytes == 0 {
+            return;
+        }
+        let (meta, content) = (self.meta_index_dir.clone(), self.content_index_dir.clone());
+        let budget = self.max_index_bytes;
+        match task::spawn_blocking(move || index_budget::enforce_budget(&meta, &content, budget))
+            .await
+        {
+            Ok(Ok(report)) if report != TrimReport::default() => tracing::info!(
+                "index over {budget} bytes: trimmed {} content bodies, {} metadata entries",
+                report.content,
+                report.meta
+            ),
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => tracing::warn!("index budget enforcement failed: {e:?}"),
+            Err(e) => tracing::warn!("index budget task failed: {e}"),
+        }
+    }
+
+    /// Move queued jobs under the foreground application's directory to the front, keeping
+    /// relative order on both sides. Only reorders when that directory changed or new jobs
+    /// arrived since the last pass.
+    fn promote_foreground(&mut self, new_jobs: bool) {
+        let Some(source) = self.foreground.as_mut() else {
+            return;
+        };
+        let dir = source.foreground_dir();
+        if dir == self.foreground_dir && !new_jobs {
+            return;
+        }
+        self.foreground_dir = dir;
+        let Some(dir) = &self.foreground_dir else {
+            return;
+        };
+        let (mut front, rest): (VecDeque<_>, VecDeque<_>) = self
+            .content_jobs
+            .drain(..)
+            .partition(|job| is_within(&job.path, dir));
+        if !front.is_empty() {
+            tracing::debug!(
+                "promoted {} queued jobs under foreground dir {}",
+                front.len(),
+                dir.display()
+            );
+        }
+        front.extend(rest);
+        self.content_jobs = front;
+    }
+
+    /// Idle compaction is enabled and its cooldown has elapsed.
+    fn compaction_due(&self) -> bool {
+        if self.merge.compact_max_segments == 0 {
+            return false;
+        }
+        let interval = Duration::from_secs(self.merge.compact_interval_secs);
+        self.last_compaction
+            .is_none_or(|last| last.elapsed() >= interval)
+    }
+
+    /// Query prefetch is enabled, the sketch is installed, and its cooldown has elapsed.
+    fn prefetch_due(&self) -> bool {
+        self.prefetch_queries > 0
+            && crate::query_prefetch::query_frequency().is_some()
+            && self
+                .last_prefetch
+                .is_none_or(|last| last.elapsed() >= PREFETCH_INTERVAL)
+    }
+
+    /// Take locked files whose retry is due back into the content queue; returns how many.
+    fn requeue_locked_files(&mut self) -> usize {
+        if self
+            .last_locked_retry
+            .is_some_and(|last| last.elapsed() < LOCKED_RETRY_INTERVAL)
+        {
+            return 0;
         }
+        self.last_locked_retry = Some(Instant::now());
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        match crate::locked_retry::take_due(&self.locked_retry_path, now) {
+            Ok(jobs) => {
+                let count = jobs.len();
+                if count > 0 {
+                    tracing::info!("retrying {count} previously locked files");
+                }
+                jobs.into_iter().for_each(|job| self.push_job(job));
+                count
+            }
+            Err(e) => {
+                tracing::warn!("locked-file retry queue unreadable: {e:?}");
+                0
+            }
+        }
+    }
+
+    /// Batch size for the next dispatch: the adaptive policy's when enabled, else the config's.
+    fn current_batch_size(&self) -> usize {
+        self.adaptive
+            .as_ref()
+            .map_or(self.config.content_batch_size, |p| {
+                p.config().content_batch_size
+            })
     }
 
     fn push_job(&mut self, job: JobSpec) {
@@ -234,6 +491,43 @@ impl SchedulerRuntime {
     }
 }
 
+/// Merge the content index down to `max_segments` under the index write lock. Returns the
+/// segment counts before and after, or `None` when there was nothing to merge.
+fn compact_content_index(
+    dir: &Path,
+    max_segments: usize,
+) -> anyhow::Result<Option<(usize, usize)>> {
+    if !dir.join("meta.json").exists() {
+        return Ok(None);
+    }
+    crate::index_lock::with_index_write_lock(dir, || {
+        let idx = content_index::open_or_create(dir)?;
+        let before = content_index::segment_count(&idx)?;
+        if before <= max_segments {
+            return Ok(None);
+        }
+        let after = content_index::force_merge(&idx, max_segments)?;
+        Ok(Some((before, after)))
+    })
+}
+
+/// Persist the query sketch and read the postings of its `top_n` most frequent queries.
+fn prefetch_frequent_queries(dir: &Path, top_n: usize) -> anyhow::Result<usize> {
+    let Some(freq) = crate::query_prefetch::query_frequency() else {
+        return Ok(0);
+    };
+    freq.save()?;
+    crate::query_prefetch::prefetch_postings(dir, &freq.top(top_n))
+}
+
+/// Parse `scheduler.quiet_hours`; an invalid schedule is logged and ignored (no restriction).
+fn parse_quiet_hours(windows: &[IndexWindow]) -> QuietHours {
+    QuietHours::from_config(windows).unwrap_or_else(|e| {
+        tracing::warn!("ignoring invalid scheduler.quiet_hours: {e:#}");
+        QuietHours::default()
+    })
+}
+
 /// Enqueue a content indexing job for the scheduler loop.
 /// Returns `false` if the scheduler has not been initialized yet.
 pub fn enqueue_content_job(job: JobSpec) -> bool {
@@ -285,12 +579,21 @@ impl Drop for SchedulerRuntime {
 
 /// Convert a `FileMeta` into a `JobSpec` if it looks indexable.
 pub fn content_job_from_meta(meta: &FileMeta, extract: &ExtractSection) -> Option<JobSpec> {
-    if meta.flags.is_dir() {
+    // Archive entries have no file of their own to extract.
+    if meta.flags.is_dir() || meta.flags.contains(core_types::FileFlags::ARCHIVE_ENTRY) {
         return None;
     }
     let path_str = meta.path.as_ref()?;
     let path = PathBuf::from(path_str);
     let file_id = meta.key.file_id();
+    let content_kind = match meta.extension() {
+        Some(_) => None,
+        None => classify_filename_with(
+            &meta.name,
+            &extract.extra_code_filenames,
+            &extract.extra_text_filenames,
+        ),
+    };
 
     let to_usize = |v: u64| -> usize {
         if v > usize::MAX as u64 {
@@ -307,9 +610,40 @@ pub fn content_job_from_meta(meta: &FileMeta, extract: &ExtractSection) -> Optio
         max_bytes: Some(to_usize(extract.max_bytes_per_file)),
         max_chars: Some(to_usize(extract.max_chars_per_file)),
         file_size: meta.size,
+        content_kind,
+        attempt: 0,
     })
 }
 
+/// Re-extraction job for a search hit whose body is missing from the content index, given the
+/// file's indexed `flags`. None for directories, archive entries and extensionless names that
+/// aren't known text or code files, none of which have a body to extract.
+pub fn content_job_from_hit(
+    hit: &ipc::SearchHit,
+    flags: core_types::FileFlags,
+    extract: &ExtractSection,
+) -> Option<JobSpec> {
+    if hit.archive.is_some() {
+        return None;
+    }
+    let meta = FileMeta::new(
+        hit.key,
+        hit.key.volume(),
+        None,
+        hit.name.clone()?,
+        hit.path.clone(),
+        hit.size.unwrap_or(0),
+        0,
+        hit.modified.unwrap_or(0),
+        flags,
+    );
+    let job = content_job_from_meta(&meta, extract)?;
+    if meta.extension().is_none() && job.content_kind.is_none() {
+        return None;
+    }
+    Some(job)
+}
+
 #[cfg(test)]
 pub fn live_counters() -> (usize, usize) {
     let live = LIVE_STATE.get_or_init(SchedulerLiveState::default);
@@ -325,13 +659,19 @@ mod tests {
     use crate::status_provider::init_basic_status_provider;
 
     fn dummy_job() -> JobSpec {
+        job_at("C:\\dummy")
+    }
+
+    fn job_at(path: &str) -> JobSpec {
         JobSpec {
             volume_id: 1,
             file_id: 1,
-            path: PathBuf::from("C:\\dummy"),
+            path: PathBuf::from(path),
             max_bytes: None,
             max_chars: None,
             file_size: 0,
+            content_kind: None,
+            attempt: 0,
         }
     }
 
@@ -349,6 +689,71 @@ mod tests {
         assert!(after > before, "dropped counter should increase");
     }
 
+    #[test]
+    fn cpu_blocked_tick_records_the_cpu_reason() {
+        let _ = init_basic_status_provider();
+        let mut rt = SchedulerRuntime::new(&AppConfig::default());
+        rt.submit_content_job(dummy_job());
+        let busy = SystemLoad {
+            cpu_percent: 97.0,
+            mem_used_percent: 30.0,
+            disk_bytes_per_sec: 0,
+            disk_busy: false,
+            sample_duration: Duration::from_secs(1),
+            on_battery: false,
+            battery_percent: None,
+            game_mode: false,
+        };
+
+        let mut log = crate::scheduler_trace::DecisionLog::new(8);
+        log.record(rt.plan_tick(IdleState::DeepIdle, &busy));
+        let recorded = log.recent(1);
+        let decision = &recorded[0];
+        assert!(!decision.content_allowed);
+        assert_eq!(decision.content_queue, 1);
+        let codes: Vec<_> = decision.blocked.iter().map(|b| b.code).collect();
+        assert_eq!(codes, vec!["cpu_high"]);
+        assert!(decision.blocked[0].detail.contains("97.0%"));
+
+        rt.force_allow_content();
+        let forced = rt.plan_tick(IdleState::DeepIdle, &busy);
+        assert!(forced.content_allowed);
+        assert_eq!(forced.override_reason, Some("forced"));
+    }
+
+    #[test]
+    fn foreground_directory_jobs_move_to_the_front() {
+        let _ = init_basic_status_provider();
+        let mut rt = SchedulerRuntime::new(&AppConfig::default());
+        rt.set_foreground_source(Some(Box::new(|| {
+            Some(PathBuf::from(r"C:\Users\me\Projects\app"))
+        })));
+        for path in [
+            r"C:\Users\me\Downloads\a.pdf",
+            r"C:\Users\me\Projects\app\src\main.rs",
+            r"C:\Users\me\Projects\other\b.txt",
+            r"c:\users\me\projects\APP\README.md",
+        ] {
+            rt.submit_content_job(job_at(path));
+        }
+
+        rt.promote_foreground(true);
+        let order: Vec<_> = rt
+            .content_jobs
+            .iter()
+            .map(|j| j.path.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                r"C:\Users\me\Projects\app\src\main.rs",
+                r"c:\users\me\projects\APP\README.md",
+                r"C:\Users\me\Downloads\a.pdf",
+                r"C:\Users\me\Projects\other\b.txt",
+            ]
+        );
+    }
+
     #[test]
     fn submit_content_job_increments_enqueued_counter() {
         // Initialize status provider once for metric updates (harmless if already set).
@@ -362,4 +767,30 @@ mod tests {
         let after = live_counters().0;
         assert_eq!(after, before + 1, "enqueued counter should increase");
     }
+
+    #[test]
+    fn rehydration_jobs_skip_directories_and_unknown_extensionless_files() {
+        let extract = ExtractSection::default();
+        let hit = |name: &str| ipc::SearchHit {
+            key: core_types::DocKey::from_parts(1, 7),
+            score: 1.0,
+            name: Some(name.into()),
+            path: Some(format!(r"C:\docs\{name}")),
+            ext: None,
+            size: Some(10),
+            modified: None,
+            snippet: None,
+            snippets: Vec::new(),
+            tags: Vec::new(),
+            extract_status: None,
+            archive: None,
+            matched_former_name: false,
+        };
+        let job = |name: &str, flags| content_job_from_hit(&hit(name), flags, &extract);
+
+        assert!(job("notes.txt", core_types::FileFlags::empty()).is_some());
+        assert!(job("Makefile", core_types::FileFlags::empty()).is_some());
+        assert!(job("notes.txt", core_types::FileFlags::IS_DIR).is_none());
+        assert!(job("blob", core_types::FileFlags::empty()).is_none());
+    }
 }
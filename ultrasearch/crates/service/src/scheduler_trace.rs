@@ -0,0 +1,163 @@
+//! Recent scheduler decisions (`scheduler.decision_trace_len`), for answering "why isn't it
+//! indexing?".
+//!
+//! Each tick records its inputs (idle state, CPU, disk, battery) and what it decided (whether
+//! content work was allowed and why not, jobs dispatched, workers spawned) in a ring buffer
+//! that `SchedulerTraceRequest` reads back. Every decision is also emitted as a `debug` event
+//! on the `scheduler` tracing target.
+
+use std::collections::VecDeque;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use parking_lot::Mutex;
+use scheduler::{BlockReason, IdleState, SystemLoad};
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockedBy {
+    /// Stable identifier, e.g. `cpu_high`.
+    pub code: &'static str,
+    pub detail: String,
+}
+
+impl From<BlockReason> for BlockedBy {
+    fn from(reason: BlockReason) -> Self {
+        Self {
+            code: reason.code(),
+            detail: reason.to_string(),
+        }
+    }
+}
+
+/// One scheduler tick: inputs and outcome.
+#[derive(Debug, Clone, Serialize)]
+pub struct SchedulerDecision {
+    /// Increases by one per recorded decision.
+    pub seq: u64,
+    /// Unix seconds.
+    pub at: i64,
+    pub idle: String,
+    pub cpu_percent: f32,
+    pub mem_used_percent: f32,
+    pub disk_bytes_per_sec: u64,
+    pub disk_busy: bool,
+    pub on_battery: bool,
+    pub battery_percent: Option<u8>,
+    pub game_mode: bool,
+    pub content_queue: usize,
+    pub content_allowed: bool,
+    /// Why content ran despite `blocked` (`forced` or `backlog`).
+    pub override_reason: Option<&'static str>,
+    /// Policy gates that failed this tick; may be non-empty when an override applied.
+    pub blocked: Vec<BlockedBy>,
+    pub jobs_dispatched: usize,
+    pub workers_spawned: u32,
+    pub compaction: bool,
+    /// Frequent queries' postings were prefetched (`search.prefetch_queries`).
+    pub prefetch: bool,
+}
+
+impl SchedulerDecision {
+    pub fn new(
+        idle: IdleState,
+        load: &SystemLoad,
+        content_queue: usize,
+        blocked: Vec<BlockReason>,
+    ) -> Self {
+        Self {
+            seq: 0,
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs() as i64),
+            idle: format!("{idle:?}"),
+            cpu_percent: load.cpu_percent,
+            mem_used_percent: load.mem_used_percent,
+            disk_bytes_per_sec: load.disk_bytes_per_sec,
+            disk_busy: load.disk_busy,
+            on_battery: load.on_battery,
+            battery_percent: load.battery_percent,
+            game_mode: load.game_mode,
+            content_queue,
+            content_allowed: blocked.is_empty(),
+            override_reason: None,
+            blocked: blocked.into_iter().map(BlockedBy::from).collect(),
+            jobs_dispatched: 0,
+            workers_spawned: 0,
+            compaction: false,
+            prefetch: false,
+        }
+    }
+}
+
+/// Ring buffer of the most recent decisions.
+#[derive(Debug)]
+pub struct DecisionLog {
+    capacity: usize,
+    next_seq: u64,
+    entries: VecDeque<SchedulerDecision>,
+}
+
+impl DecisionLog {
+    /// `capacity` = 0 records nothing.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            next_seq: 1,
+            entries: VecDeque::with_capacity(capacity.min(1024)),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn record(&mut self, mut decision: SchedulerDecision) {
+        if self.capacity == 0 {
+            return;
+        }
+        decision.seq = self.next_seq;
+        self.next_seq += 1;
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(decision);
+    }
+
+    /// Up to `limit` most recent decisions, oldest first.
+    pub fn recent(&self, limit: usize) -> Vec<SchedulerDecision> {
+        let skip = self.entries.len().saturating_sub(limit);
+        self.entries.iter().skip(skip).cloned().collect()
+    }
+}
+
+static DECISIONS: OnceLock<Mutex<DecisionLog>> = OnceLock::new();
+
+/// Process-wide log written by the scheduler runtime.
+pub fn decision_log() -> &'static Mutex<DecisionLog> {
+    DECISIONS.get_or_init(|| Mutex::new(DecisionLog::new(0)))
+}
+
+/// Emit `decision` as a tracing event and keep it in the global log.
+pub fn record_decision(decision: SchedulerDecision) {
+    tracing::debug!(
+        target: "scheduler",
+        idle = %decision.idle,
+        cpu = decision.cpu_percent,
+        disk_busy = decision.disk_busy,
+        on_battery = decision.on_battery,
+        queue = decision.content_queue,
+        allowed = decision.content_allowed,
+        blocked = ?decision.blocked.iter().map(|b| b.code).collect::<Vec<_>>(),
+        dispatched = decision.jobs_dispatched,
+        "scheduler decision"
+    );
+    decision_log().lock().record(decision);
+}
@@ -0,0 +1,116 @@
+//! Bound how many documents a query scores (`search.content_max_docs_scored`).
+//!
+//! Tantivy's `TopDocs` scores every match before keeping the best, so a broad content query
+//! over millions of documents costs millions of BM25 evaluations. [`ScoreCapped`] drives the
+//! scorer itself and stops once the budget (shared across segments) is spent; the results
+//! are then the best of the documents scored rather than of all matches, and are reported as
+//! approximate.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use tantivy::collector::{Collector, SegmentCollector};
+use tantivy::query::{Query, Weight};
+use tantivy::{DocId, DocSet, Score, Searcher, SegmentOrdinal, SegmentReader, TERMINATED};
+
+/// Wraps `inner`, feeding it at most `max_scored` documents across all segments.
+pub struct ScoreCapped<C> {
+    inner: C,
+    max_scored: usize,
+    scored: AtomicUsize,
+}
+
+impl<C> ScoreCapped<C> {
+    pub fn new(inner: C, max_scored: usize) -> Self {
+        Self {
+            inner,
+            max_scored,
+            scored: AtomicUsize::new(0),
+        }
+    }
+}
+
+pub struct ScoreCappedSegment<S> {
+    inner: S,
+    hit_cap: bool,
+}
+
+impl<S: SegmentCollector> SegmentCollector for ScoreCappedSegment<S> {
+    type Fruit = (S::Fruit, bool);
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        self.inner.collect(doc, score);
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        (self.inner.harvest(), self.hit_cap)
+    }
+}
+
+impl<C: Collector> Collector for ScoreCapped<C> {
+    /// The inner fruit, and whether the cap cut collection short.
+    type Fruit = (C::Fruit, bool);
+    type Child = ScoreCappedSegment<C::Child>;
+
+    fn for_segment(
+        &self,
+        segment_local_id: SegmentOrdinal,
+        segment: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        Ok(ScoreCappedSegment {
+            inner: self.inner.for_segment(segment_local_id, segment)?,
+            hit_cap: false,
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        self.inner.requires_scoring()
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<<Self::Child as SegmentCollector>::Fruit>,
+    ) -> tantivy::Result<Self::Fruit> {
+        let hit_cap = segment_fruits.iter().any(|(_, hit)| *hit);
+        let inner = self
+            .inner
+            .merge_fruits(segment_fruits.into_iter().map(|(f, _)| f).collect())?;
+        Ok((inner, hit_cap))
+    }
+
+    fn collect_segment(
+        &self,
+        weight: &dyn Weight,
+        segment_ord: u32,
+        reader: &SegmentReader,
+    ) -> tantivy::Result<<Self::Child as SegmentCollector>::Fruit> {
+        let mut child = self.for_segment(segment_ord, reader)?;
+        let alive = reader.alive_bitset();
+        let mut scorer = weight.scorer(reader, 1.0)?;
+        let mut doc = scorer.doc();
+        while doc != TERMINATED {
+            if alive.is_none_or(|bits| bits.is_alive(doc)) {
+                if self.scored.fetch_add(1, Ordering::Relaxed) >= self.max_scored {
+                    child.hit_cap = true;
+                    break;
+                }
+                child.collect(doc, scorer.score());
+            }
+            doc = scorer.advance();
+        }
+        Ok(child.harvest())
+    }
+}
+
+/// Run `collector` over `query`, scoring at most `max_scored` documents (0 = no cap).
+/// Returns the fruit and whether the cap was hit.
+pub fn search_capped<C: Collector>(
+    searcher: &Searcher,
+    query: &dyn Query,
+    collector: C,
+    max_scored: usize,
+) -> tantivy::Result<(C::Fruit, bool)> {
+    if max_scored == 0 {
+        return Ok((searcher.search(query, &collector)?, false));
+    }
+    searcher.search(query, &ScoreCapped::new(collector, max_scored))
+}
@@ -1,22 +1,64 @@
+use crate::byte_total::{MatchedSizes, add_dir_contents, matched_sizes};
+use crate::context_boost::boosted_score;
+use crate::extract_status::{ContentState, extract_status, load_dead_letters};
+use crate::name_rank::name_match_score;
+use crate::planner::QueryPlanner;
+use crate::result_sets::ResultSetCache;
+use crate::score_cap::search_capped;
+use crate::snippet_cache::SnippetCache;
 use anyhow::Result;
-use content_index::{ContentIndex, open_or_create as open_content};
+use content_index::dedup::{BodyScores, attach_shared_preview, has_references};
+use content_index::snippet::{HighlightStyle, SnippetOptions};
+use content_index::{AnalyzerOptions, ContentIndex, open_or_create as open_content};
+use core_types::collation::Collator;
+use core_types::config::DirectoryBytes;
+use core_types::dates::{DateZone, day_range, parse_date, parse_relative_time};
 use ipc::{
-    FieldKind, QueryExpr, SearchHit, SearchMode, SearchRequest, SearchResponse, TermExpr,
-    TermModifier,
+    FieldKind, FolderGroup, GroupBy, HitField, PageCursor, QueryExpr, RangeExpr, RangeOp,
+    RangeValue, SearchHit, SearchHitBatch, SearchMode, SearchRequest, SearchResponse,
+    SearchStreamFrame, SearchStreamRequest, SortField, SortSpec, TermExpr, TermModifier,
 };
+use meta_index::former_names::{FormerNameStore, name_matches};
+use meta_index::tags::TagStore;
 use meta_index::{MetaFields, MetaIndex, open_or_create_index, open_reader};
-use std::path::Path;
-use std::sync::OnceLock;
-use std::time::Instant;
-use tantivy::collector::{Count, TopDocs};
-use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
-use tantivy::schema::{Document, IndexRecordOption, TantivyDocument, Value};
-use tantivy::{IndexReader, Score, Term};
+use regex::{Regex, RegexBuilder};
+use std::cell::{Cell, RefCell};
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::ops::Bound;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+use tantivy::collector::{Collector, Count, FilterCollector, TopDocs};
+use tantivy::query::{
+    BooleanQuery, ConstScoreQuery, ExistsQuery, FuzzyTermQuery, Occur, PhraseQuery, Query,
+    QueryParser, RangeQuery, RegexQuery, TermQuery, TermSetQuery,
+};
+use tantivy::schema::{Document, Field, IndexRecordOption, TantivyDocument, Value};
+use tantivy::tokenizer::TokenStream;
+use tantivy::{DocAddress, DocId, IndexReader, Score, Searcher, SegmentReader, Term};
 use tracing::warn;
+use uuid::Uuid;
 
 /// Trait for handling search requests.
 pub trait SearchHandler: Send + Sync {
     fn search(&self, req: SearchRequest) -> SearchResponse;
+
+    /// Answer an `anytime` request in stages, passing each response to `emit`; all but the
+    /// last are flagged `refining`. Stops early once `emit` returns false (the client left).
+    /// Handlers without a cheaper first stage answer once.
+    fn search_anytime(&self, req: SearchRequest, emit: &mut dyn FnMut(SearchResponse) -> bool) {
+        emit(self.search(req));
+    }
+
+    /// Like `search`, but stops early once `cancel` is set (see [`cancel_search`]). The flag
+    /// is visible to the handler through [`search_cancelled`] while it runs; a handler that
+    /// checks it answers with `cancelled` and `truncated` set, others run to completion.
+    fn search_cancellable(&self, req: SearchRequest, cancel: Arc<AtomicBool>) -> SearchResponse {
+        let _scope = CancelScope::enter(cancel);
+        self.search(req)
+    }
 }
 
 /// Simple placeholder handler that returns an empty response.
@@ -32,19 +74,78 @@ impl SearchHandler for StubSearchHandler {
             truncated: false,
             took_ms: 0,
             served_by: Some("service-stub".into()),
+            grouped: None,
+            degraded_to: None,
+            next_cursor: None,
+            approximate: false,
+            total_bytes_matched: None,
+            refining: false,
+            executed_query: None,
+            error: None,
+            result_set_id: None,
+            cancelled: false,
         }
     }
 }
 
+/// Requests re-extraction of a hit whose body was trimmed from the content index, given the
+/// file's indexed flags; returns whether a job was enqueued.
+pub type RehydrateFn = Arc<dyn Fn(&SearchHit, core_types::FileFlags) -> bool + Send + Sync>;
+
 /// Handler backed by metadata and optional content index.
 pub struct UnifiedSearchHandler {
     meta: MetaIndex,
     meta_reader: IndexReader,
     content: Option<(ContentIndex, IndexReader)>,
+    fuzzy_transpositions: bool,
+    snippet_cache: SnippetCache,
+    /// Base snippet options: highlight markup and near-duplicate collapsing.
+    snippet_opts: SnippetOptions,
+    collator: Collator,
+    degrade_without_content: bool,
+    name_match_ranking: bool,
+    content_max_scored: usize,
+    directory_bytes: DirectoryBytes,
+    context_weight: f32,
+    date_zone: DateZone,
+    prefix_trailing_term: bool,
+    tags: Option<Arc<TagStore>>,
+    former_names: Option<Arc<FormerNameStore>>,
+    stream_threshold: u32,
+    stream_page_size: u32,
+    rehydrate: Option<RehydrateFn>,
+    /// Docs with a re-extraction in flight and when it was requested, so repeated searches
+    /// don't enqueue duplicates; a request older than [`REHYDRATE_RETRY_AFTER`] is dropped.
+    rehydrate_pending: Mutex<HashMap<core_types::DocKey, Instant>>,
+    /// The index worker's dead-letter log, read for `SearchHit::extract_status`.
+    dead_letters: Option<PathBuf>,
+    /// Compiled `Regex` term patterns, so each is compiled once rather than per name tested.
+    regex_cache: Mutex<HashMap<String, Arc<Regex>>>,
+    /// Where `result_set` requests store their matched keys and `refine_set` reads them.
+    result_sets: Option<Arc<ResultSetCache>>,
+    /// Most candidates the last hybrid merge held at once.
+    merge_peak: AtomicUsize,
 }
 
 impl UnifiedSearchHandler {
     pub fn try_new(meta_path: &Path, content_path: &Path) -> Result<Self> {
+        Self::open(meta_path, content_path, None)
+    }
+
+    /// Like [`Self::try_new`], applying `analyzer` to a new (or still empty) content index.
+    pub fn try_new_with_analyzer(
+        meta_path: &Path,
+        content_path: &Path,
+        analyzer: &AnalyzerOptions,
+    ) -> Result<Self> {
+        Self::open(meta_path, content_path, Some(analyzer))
+    }
+
+    fn open(
+        meta_path: &Path,
+        content_path: &Path,
+        analyzer: Option<&AnalyzerOptions>,
+    ) -> Result<Self> {
         tracing::info!(
             "UnifiedSearchHandler: opening meta index at {:?}",
             meta_path
@@ -52,7 +153,11 @@ impl UnifiedSearchHandler {
         let meta = open_or_create_index(meta_path)?;
         let meta_reader = open_reader(&meta)?;
 
-        let content = match open_content(content_path) {
+        let opened = match analyzer {
+            Some(opts) => content_index::open_or_create_with(content_path, opts),
+            None => open_content(content_path),
+        };
+        let content = match opened {
             Ok(idx) => {
                 tracing::info!(
                     "UnifiedSearchHandler: opened content index at {:?}",
@@ -71,11 +176,412 @@ impl UnifiedSearchHandler {
             meta,
             meta_reader,
             content,
+            fuzzy_transpositions: false,
+            snippet_cache: SnippetCache::default(),
+            snippet_opts: SnippetOptions::default(),
+            collator: Collator::default(),
+            degrade_without_content: true,
+            name_match_ranking: false,
+            content_max_scored: 0,
+            directory_bytes: DirectoryBytes::Exclude,
+            context_weight: 1.0,
+            date_zone: DateZone::Local,
+            prefix_trailing_term: false,
+            tags: None,
+            former_names: None,
+            stream_threshold: 1000,
+            stream_page_size: 250,
+            rehydrate: None,
+            rehydrate_pending: Mutex::new(HashMap::new()),
+            dead_letters: None,
+            regex_cache: Mutex::new(HashMap::new()),
+            result_sets: None,
+            merge_peak: AtomicUsize::new(0),
         })
     }
 
-    fn build_meta_query(&self, expr: &QueryExpr) -> Result<Box<dyn Query>> {
-        self.build_query(expr, &self.meta.fields, &self.meta.index)
+    /// Count adjacent transpositions as a single edit in fuzzy term queries.
+    pub fn with_fuzzy_transpositions(mut self, enabled: bool) -> Self {
+        self.fuzzy_transpositions = enabled;
+        self
+    }
+
+    /// Replace the snippet cache (e.g. with configured TTL/capacity).
+    pub fn with_snippet_cache(mut self, cache: SnippetCache) -> Self {
+        self.snippet_cache = cache;
+        self
+    }
+
+    /// Markup for term matches in snippets (`search.snippet_highlight`).
+    pub fn with_highlight_style(mut self, style: HighlightStyle) -> Self {
+        self.snippet_opts.highlight = style;
+        self
+    }
+
+    /// Collapse snippet fragments at least this similar (`search.snippet_dedup_similarity`).
+    pub fn with_snippet_dedup(mut self, similarity: f32) -> Self {
+        self.snippet_opts.dedup_similarity = similarity;
+        self
+    }
+
+    /// Locale for name/path sorting (`search.sort_locale`).
+    pub fn with_sort_locale(mut self, locale: &str) -> Self {
+        self.collator = Collator::new(locale);
+        self
+    }
+
+    /// Serve content/hybrid queries from what exists while the content index is missing or
+    /// empty (`search.degrade_without_content`).
+    pub fn with_content_degradation(mut self, enabled: bool) -> Self {
+        self.degrade_without_content = enabled;
+        self
+    }
+
+    /// Order name-only results by match quality instead of BM25 (`search.name_match_ranking`).
+    pub fn with_name_match_ranking(mut self, enabled: bool) -> Self {
+        self.name_match_ranking = enabled;
+        self
+    }
+
+    /// Score at most `max` documents per content query (`search.content_max_docs_scored`;
+    /// 0 = no cap). Capped results are flagged `approximate`.
+    pub fn with_content_score_cap(mut self, max: usize) -> Self {
+        self.content_max_scored = max;
+        self
+    }
+
+    /// How matched directories count toward `total_bytes_matched`
+    /// (`search.total_bytes_directories`).
+    pub fn with_total_bytes_directories(mut self, mode: DirectoryBytes) -> Self {
+        self.directory_bytes = mode;
+        self
+    }
+
+    /// Strength of the `context_path` boost (`search.context_path_weight`): a hit in the
+    /// context folder scores up to `1 + weight` times its relevance. 0 ignores `context_path`.
+    pub fn with_context_weight(mut self, weight: f32) -> Self {
+        self.context_weight = weight;
+        self
+    }
+
+    /// Zone in which bare dates in `modified`/`created` terms are read
+    /// (`search.date_time_zone`).
+    pub fn with_date_zone(mut self, zone: DateZone) -> Self {
+        self.date_zone = zone;
+        self
+    }
+
+    /// Run a query's trailing name term as a prefix (`search.prefix_trailing_term`).
+    pub fn with_trailing_prefix(mut self, enabled: bool) -> Self {
+        self.prefix_trailing_term = enabled;
+        self
+    }
+
+    /// Page responses with more than `threshold` hits into `page_size` chunks linked by
+    /// `next_cursor` (`search.stream_threshold`/`stream_page_size`; 0 = never page).
+    pub fn with_streaming(mut self, threshold: u32, page_size: u32) -> Self {
+        self.stream_threshold = threshold;
+        self.stream_page_size = page_size;
+        self
+    }
+
+    /// Cut a response over the streaming threshold down to its first page and point
+    /// `next_cursor` at the rest of the requested window.
+    fn page(&self, req: &SearchRequest, resp: &mut SearchResponse) {
+        let hits = resp.hits.len();
+        if self.stream_threshold == 0 || hits <= self.stream_threshold as usize {
+            return;
+        }
+        let page = (self.stream_page_size.max(1) as usize).min(hits);
+        resp.hits.truncate(page);
+        resp.next_cursor = Some(
+            PageCursor {
+                offset: req.offset.saturating_add(page as u32),
+                end: req.offset.saturating_add(hits as u32),
+            }
+            .encode(),
+        );
+    }
+
+    /// Resolve `tag:` queries and fill `SearchHit::tags` from `store`.
+    pub fn with_tags(mut self, store: Arc<TagStore>) -> Self {
+        self.tags = Some(store);
+        self
+    }
+
+    /// Let name terms match the former names in `store` (`search.match_former_names`) and
+    /// flag such hits with `matched_former_name`.
+    pub fn with_former_names(mut self, store: Arc<FormerNameStore>) -> Self {
+        self.former_names = Some(store);
+        self
+    }
+
+    /// Cache the matched keys of `result_set` requests in `cache` and resolve `refine_set`
+    /// ids against it.
+    pub fn with_result_sets(mut self, cache: Arc<ResultSetCache>) -> Self {
+        self.result_sets = Some(cache);
+        self
+    }
+
+    /// Read failed and skipped extractions from the worker's dead-letter log at `path`, for
+    /// requests that set `extract_status`.
+    pub fn with_dead_letters(mut self, path: PathBuf) -> Self {
+        self.dead_letters = Some(path);
+        self
+    }
+
+    /// Fill `SearchHit::extract_status` on `hits`.
+    fn fill_extract_status(&self, hits: &mut [SearchHit]) {
+        let dead = self
+            .dead_letters
+            .as_deref()
+            .map(load_dead_letters)
+            .unwrap_or_default();
+        let content = self.content.as_ref().map(|(idx, r)| (idx, r.searcher()));
+        let meta = self.meta_reader.searcher();
+        let pending = self.live_rehydrations();
+        for hit in hits {
+            let indexed = content.as_ref().is_some_and(|(idx, searcher)| {
+                searcher
+                    .search(&key_query(idx.fields.doc_key, hit.key), &Count)
+                    .is_ok_and(|n| n > 0)
+            });
+            let is_dir = !indexed
+                && self
+                    .indexed_flags(&meta, hit.key)
+                    .is_some_and(|flags| flags.is_dir());
+            hit.extract_status = Some(extract_status(ContentState {
+                indexed,
+                is_dir,
+                requeued: pending.contains_key(&hit.key),
+                dead_letter: dead.get(&hit.key),
+            }));
+        }
+    }
+
+    /// Documents tagged `tag`, matched on the `doc_key` fast field (both indexes have one).
+    /// Without a tag store nothing matches.
+    fn tag_query(&self, doc_key: Field, tag: &str) -> Box<dyn Query> {
+        let keys = self
+            .tags
+            .as_ref()
+            .map(|store| store.keys_with(tag))
+            .unwrap_or_default();
+        keys_query(doc_key, keys)
+    }
+
+    /// Documents with a former name matching the name term `value`; `None` unless former
+    /// names are matched.
+    fn former_name_query(&self, doc_key: Field, term: &TermExpr) -> Option<Box<dyn Query>> {
+        if term.modifier != TermModifier::Term {
+            return None;
+        }
+        let store = self.former_names.as_ref()?;
+        Some(keys_query(doc_key, store.keys_matching(&term.value)))
+    }
+
+    /// Set `matched_former_name` on hits whose current name misses a name word of `query`
+    /// that one of their former names has.
+    fn flag_former_names(&self, query: &QueryExpr, hits: &mut [SearchHit]) {
+        let Some(store) = &self.former_names else {
+            return;
+        };
+        let mut words = Vec::new();
+        name_terms(query, &mut words);
+        for hit in hits {
+            let name = hit.name.as_deref().unwrap_or_default();
+            let former = store.names(hit.key);
+            hit.matched_former_name = words
+                .iter()
+                .any(|w| !name_matches(name, w) && former.iter().any(|f| name_matches(f, w)));
+        }
+    }
+
+    /// `pattern` compiled case-insensitively, from the cache when a request used it before.
+    fn regex(&self, pattern: &str) -> Result<Arc<Regex>, regex::Error> {
+        let mut cache = self.regex_cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(re) = cache.get(pattern) {
+            return Ok(re.clone());
+        }
+        let re = Arc::new(RegexBuilder::new(pattern).case_insensitive(true).build()?);
+        if cache.len() >= REGEX_CACHE_ENTRIES {
+            cache.clear();
+        }
+        cache.insert(pattern.to_string(), re.clone());
+        Ok(re)
+    }
+
+    /// Compile every `Regex` term of `query`, failing on the first invalid pattern.
+    fn check_regexes(&self, query: &QueryExpr) -> Result<()> {
+        let mut patterns = Vec::new();
+        regex_terms(query, &mut patterns);
+        for pattern in patterns {
+            self.regex(pattern)
+                .map_err(|e| anyhow::anyhow!("invalid regex `{pattern}`: {e}"))?;
+        }
+        Ok(())
+    }
+
+    /// Narrow `req.refine` to the cached set named by `req.refine_set`, failing if that set
+    /// is unknown or expired.
+    fn resolve_refine_set(&self, req: &mut SearchRequest) -> Result<()> {
+        let Some(id) = req.refine_set else {
+            return Ok(());
+        };
+        let keys = self
+            .result_sets
+            .as_ref()
+            .and_then(|cache| cache.get(id))
+            .ok_or_else(|| anyhow::anyhow!("unknown or expired result set {id}"))?;
+        req.refine = Some(match req.refine.take() {
+            Some(refine) => refine
+                .into_iter()
+                .filter(|key| keys.binary_search(key).is_ok())
+                .collect(),
+            None => keys.to_vec(),
+        });
+        Ok(())
+    }
+
+    /// Names matching the `Regex` term `pattern`. The lowercased whole names in `field` are
+    /// tested one by one, from the literal prefix of an anchored pattern (`^report`) when it
+    /// has one, else all of them, until `scan` says to stop.
+    fn regex_name_query(
+        &self,
+        field: Field,
+        pattern: &str,
+        scan: &RegexScan,
+    ) -> Result<Box<dyn Query>> {
+        let re = self.regex(pattern)?;
+        let prefix = regex_literal_prefix(pattern);
+        let searcher = self.meta_reader.searcher();
+        let mut names = BTreeSet::new();
+        'segments: for segment in searcher.segment_readers() {
+            let inverted = segment.inverted_index(field)?;
+            let dict = inverted.terms();
+            let mut stream = match &prefix {
+                Some(p) => dict.range().ge(p.as_bytes()).into_stream()?,
+                None => dict.stream()?,
+            };
+            let mut scanned = 0usize;
+            while stream.advance() {
+                let key = stream.key();
+                if prefix
+                    .as_ref()
+                    .is_some_and(|p| !key.starts_with(p.as_bytes()))
+                {
+                    break;
+                }
+                scanned += 1;
+                if scanned > REGEX_SCAN_CEILING {
+                    scan.capped.set(true);
+                    break;
+                }
+                if scanned.is_multiple_of(CANCEL_CHECK_INTERVAL) && search_cancelled() {
+                    scan.capped.set(true);
+                    break 'segments;
+                }
+                let Ok(name) = std::str::from_utf8(key) else {
+                    continue;
+                };
+                if re.is_match(name) && !names.contains(name) {
+                    if names.len() >= scan.max_names {
+                        scan.capped.set(true);
+                        break 'segments;
+                    }
+                    names.insert(name.to_string());
+                }
+            }
+        }
+        Ok(Box::new(TermSetQuery::new(
+            names.iter().map(|n| Term::from_field_text(field, n)),
+        )))
+    }
+
+    /// Re-extract hybrid hits whose content was trimmed (`search.rehydrate_on_miss`).
+    pub fn with_rehydration(mut self, hook: RehydrateFn) -> Self {
+        self.rehydrate = Some(hook);
+        self
+    }
+
+    /// The file flags in `key`'s metadata document.
+    fn indexed_flags(
+        &self,
+        meta: &Searcher,
+        key: core_types::DocKey,
+    ) -> Option<core_types::FileFlags> {
+        let top = meta
+            .search(
+                &key_query(self.meta.fields.doc_key, key),
+                &TopDocs::with_limit(1),
+            )
+            .ok()?;
+        let doc = meta.doc::<TantivyDocument>(top.first()?.1).ok()?;
+        let bits = doc.get_first(self.meta.fields.flags)?.as_u64()?;
+        Some(core_types::FileFlags::from_bits_truncate(bits as u32))
+    }
+
+    /// Pending rehydrations, with requests older than [`REHYDRATE_RETRY_AFTER`] dropped: their
+    /// job failed or was lost, and a later search may request it again.
+    fn live_rehydrations(&self) -> MutexGuard<'_, HashMap<core_types::DocKey, Instant>> {
+        let mut pending = self
+            .rehydrate_pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        pending.retain(|_, at| at.elapsed() < REHYDRATE_RETRY_AFTER);
+        pending
+    }
+
+    /// Hand returned hits whose body the index budget trimmed to the rehydrate hook, unless
+    /// the worker recorded the file as skipped (unsupported or too large). Other hits without
+    /// a content document were never extracted and are left alone. The hits themselves are
+    /// returned unchanged (without a snippet).
+    fn rehydrate_missing(&self, hits: &[SearchHit]) {
+        let (Some(hook), Some((content_idx, reader))) = (&self.rehydrate, &self.content) else {
+            return;
+        };
+        let trimmed: Vec<&SearchHit> = hits
+            .iter()
+            .filter(|h| h.snippet.is_none() && crate::index_budget::was_trimmed(h.key))
+            .collect();
+        if trimmed.is_empty() {
+            return;
+        }
+        let searcher = reader.searcher();
+        let meta = self.meta_reader.searcher();
+        let dead = self
+            .dead_letters
+            .as_deref()
+            .map(load_dead_letters)
+            .unwrap_or_default();
+        let mut pending = self.live_rehydrations();
+        for hit in trimmed {
+            let query = key_query(content_idx.fields.doc_key, hit.key);
+            match searcher.search(&query, &Count) {
+                Ok(0) => {
+                    if pending.contains_key(&hit.key)
+                        || dead.get(&hit.key) == Some(&ipc::ExtractStatus::Skipped)
+                    {
+                        continue;
+                    }
+                    let Some(flags) = self.indexed_flags(&meta, hit.key) else {
+                        continue;
+                    };
+                    if hook(hit, flags) {
+                        pending.insert(hit.key, Instant::now());
+                    }
+                }
+                Ok(_) => {
+                    pending.remove(&hit.key);
+                    crate::index_budget::forget_trimmed(hit.key);
+                }
+                Err(err) => warn!(error = %err, "content presence check failed"),
+            }
+        }
+    }
+
+    fn build_meta_query(&self, expr: &QueryExpr, scan: &RegexScan) -> Result<Box<dyn Query>> {
+        self.build_query(expr, &self.meta.fields, &self.meta.index, scan)
     }
 
     fn build_query(
@@ -83,24 +589,26 @@ impl UnifiedSearchHandler {
         expr: &QueryExpr,
         fields: &MetaFields,
         index: &tantivy::Index,
+        scan: &RegexScan,
     ) -> Result<Box<dyn Query>> {
         Ok(match expr {
-            QueryExpr::Term(t) => self.term_query(t, fields, index)?,
-            QueryExpr::Range(_) => Box::new(BooleanQuery::new(vec![])),
+            QueryExpr::Term(t) => self.term_query(t, fields, index, scan)?,
+            // Names have no word positions; `Near` only matches content.
+            QueryExpr::Range(_) | QueryExpr::Near { .. } => Box::new(BooleanQuery::new(vec![])),
             QueryExpr::Not(inner) => Box::new(BooleanQuery::new(vec![(
                 Occur::MustNot,
-                self.build_query(inner, fields, index)?,
+                self.build_query(inner, fields, index, scan)?,
             )])),
             QueryExpr::And(items) => Box::new(BooleanQuery::new(
                 items
                     .iter()
-                    .map(|q| Ok((Occur::Must, self.build_query(q, fields, index)?)))
+                    .map(|q| Ok((Occur::Must, self.build_query(q, fields, index, scan)?)))
                     .collect::<Result<Vec<_>>>()?,
             )),
             QueryExpr::Or(items) => Box::new(BooleanQuery::new(
                 items
                     .iter()
-                    .map(|q| Ok((Occur::Should, self.build_query(q, fields, index)?)))
+                    .map(|q| Ok((Occur::Should, self.build_query(q, fields, index, scan)?)))
                     .collect::<Result<Vec<_>>>()?,
             )),
         })
@@ -111,6 +619,7 @@ impl UnifiedSearchHandler {
         term: &TermExpr,
         fields: &MetaFields,
         index: &tantivy::Index,
+        scan: &RegexScan,
     ) -> Result<Box<dyn Query>> {
         let value = term.value.trim();
         if value.is_empty() {
@@ -119,6 +628,13 @@ impl UnifiedSearchHandler {
 
         let target_fields: Vec<FieldKind> = match term.field {
             Some(f) => vec![f],
+            None if matches!(
+                term.modifier,
+                TermModifier::Phonetic | TermModifier::Glob | TermModifier::Regex
+            ) =>
+            {
+                vec![FieldKind::Name]
+            }
             None => vec![FieldKind::Name, FieldKind::Path],
         };
 
@@ -132,6 +648,38 @@ impl UnifiedSearchHandler {
                         Box::new(TermQuery::new(t, IndexRecordOption::WithFreqs)) as Box<dyn Query>,
                     ));
                 }
+                FieldKind::Owner => {
+                    // SIDs are indexed uppercase; `s-1-5-18` and `S-1-5-18` are the same owner.
+                    let t = Term::from_field_text(fields.owner, &value.to_ascii_uppercase());
+                    clauses.push((
+                        Occur::Should,
+                        Box::new(TermQuery::new(t, IndexRecordOption::Basic)) as Box<dyn Query>,
+                    ));
+                }
+                FieldKind::Tag => {
+                    clauses.push((Occur::Should, self.tag_query(fields.doc_key, value)));
+                }
+                FieldKind::Path if term.modifier == TermModifier::Component => {
+                    clauses.push((Occur::Should, component_query(fields.dir_component, value)));
+                }
+                FieldKind::Name if term.modifier == TermModifier::Phonetic => {
+                    clauses.push((Occur::Should, phonetic_query(fields.name_phonetic, value)));
+                }
+                FieldKind::Name if term.modifier == TermModifier::Glob => {
+                    match RegexQuery::from_pattern(&glob_regex(value), fields.name_lower) {
+                        Ok(q) => clauses.push((Occur::Should, Box::new(q) as Box<dyn Query>)),
+                        Err(e) => warn!(glob = value, error = %e, "unusable glob pattern"),
+                    }
+                }
+                FieldKind::Name if term.modifier == TermModifier::Regex => {
+                    clauses.push((
+                        Occur::Should,
+                        self.regex_name_query(fields.name_lower, value, scan)?,
+                    ));
+                }
+                // Globs and regexes match names only.
+                FieldKind::Path
+                    if matches!(term.modifier, TermModifier::Glob | TermModifier::Regex) => {}
                 FieldKind::Name | FieldKind::Path => match term.modifier {
                     TermModifier::Prefix => {
                         let pf = if matches!(field, FieldKind::Name) {
@@ -139,12 +687,17 @@ impl UnifiedSearchHandler {
                         } else {
                             fields.path
                         };
-                        // Fallback for PrefixQuery removal
-                        let t = Term::from_field_text(pf, value);
+                        clauses.push((Occur::Should, prefix_query(pf, value)));
+                    }
+                    TermModifier::Fuzzy(distance) => {
+                        let pf = if matches!(field, FieldKind::Name) {
+                            fields.name
+                        } else {
+                            fields.path
+                        };
                         clauses.push((
                             Occur::Should,
-                            Box::new(TermQuery::new(t, IndexRecordOption::WithFreqs))
-                                as Box<dyn Query>,
+                            fuzzy_query(pf, value, distance, self.fuzzy_transpositions),
                         ));
                     }
                     _ => {
@@ -160,6 +713,11 @@ impl UnifiedSearchHandler {
                         if let Ok(q) = parser.parse_query(value) {
                             clauses.push((Occur::Should, q));
                         }
+                        if matches!(field, FieldKind::Name)
+                            && let Some(q) = self.former_name_query(fields.doc_key, term)
+                        {
+                            clauses.push((Occur::Should, q));
+                        }
                     }
                 },
                 _ => {}
@@ -180,7 +738,10 @@ impl UnifiedSearchHandler {
 
             Ok(match expr {
                 QueryExpr::Term(t) => self.term_query_content(t, &idx.fields, &idx.index)?,
-                QueryExpr::Range(_) => Box::new(BooleanQuery::new(vec![])),
+                QueryExpr::Range(r) => content_range_query(r, &idx.fields),
+                QueryExpr::Near { terms, slop } => {
+                    near_query(&idx.index, idx.fields.content, terms, *slop)?
+                }
                 QueryExpr::Not(inner) => Box::new(BooleanQuery::new(vec![(
                     Occur::MustNot,
                     self.build_content_query(inner)?,
@@ -203,6 +764,76 @@ impl UnifiedSearchHandler {
         }
     }
 
+    /// `expr` as it applies to reference documents (`extract.dedup_content`), which carry
+    /// their own metadata but no body. Each clause on the body text (`content:`, `Near`, and
+    /// the content half of an unfielded term) becomes a `body_of` match on the bodies it
+    /// matches; every other clause, like `ext:` or a size range, is matched against the
+    /// reference itself. Records each matched body's best score per body clause, summed, in
+    /// `body_scores`.
+    fn build_reference_query(
+        &self,
+        expr: &QueryExpr,
+        searcher: &Searcher,
+        body_scores: &mut HashMap<u64, Score>,
+    ) -> Result<Box<dyn Query>> {
+        let Some((idx, _)) = &self.content else {
+            return Err(anyhow::anyhow!("content index not available"));
+        };
+        let fields = &idx.fields;
+        let mut on_body = |query: Box<dyn Query>| -> Result<Box<dyn Query>> {
+            let matched = searcher.search(&*query, &BodyScores)?;
+            for (&hash, &score) in &matched {
+                *body_scores.entry(hash).or_default() += score;
+            }
+            let terms = matched
+                .into_keys()
+                .map(|hash| Term::from_field_u64(fields.body_of, hash));
+            Ok(Box::new(TermSetQuery::new(terms)))
+        };
+        Ok(match expr {
+            QueryExpr::Term(t) => match t.field {
+                Some(FieldKind::Content) => {
+                    on_body(self.term_query_content(t, fields, &idx.index)?)?
+                }
+                None => {
+                    let on = |field| TermExpr {
+                        field: Some(field),
+                        ..t.clone()
+                    };
+                    let name = self.term_query_content(&on(FieldKind::Name), fields, &idx.index)?;
+                    let content =
+                        self.term_query_content(&on(FieldKind::Content), fields, &idx.index)?;
+                    Box::new(BooleanQuery::new(vec![
+                        (Occur::Should, name),
+                        (Occur::Should, on_body(content)?),
+                    ]))
+                }
+                Some(_) => self.term_query_content(t, fields, &idx.index)?,
+            },
+            QueryExpr::Range(r) => content_range_query(r, fields),
+            QueryExpr::Near { terms, slop } => {
+                on_body(near_query(&idx.index, fields.content, terms, *slop)?)?
+            }
+            QueryExpr::Not(inner) => Box::new(BooleanQuery::new(vec![(
+                Occur::MustNot,
+                self.build_reference_query(inner, searcher, body_scores)?,
+            )])),
+            QueryExpr::And(items) | QueryExpr::Or(items) => {
+                let occur = if matches!(expr, QueryExpr::And(_)) {
+                    Occur::Must
+                } else {
+                    Occur::Should
+                };
+                Box::new(BooleanQuery::new(
+                    items
+                        .iter()
+                        .map(|q| Ok((occur, self.build_reference_query(q, searcher, body_scores)?)))
+                        .collect::<Result<Vec<_>>>()?,
+                ))
+            }
+        })
+    }
+
     fn term_query_content(
         &self,
         term: &TermExpr,
@@ -221,26 +852,54 @@ impl UnifiedSearchHandler {
 
         let mut clauses = Vec::new();
         for field in target_fields {
+            if field == FieldKind::Tag {
+                clauses.push((Occur::Should, self.tag_query(fields.doc_key, value)));
+                continue;
+            }
+            if field == FieldKind::Mime {
+                let t = Term::from_field_text(fields.mime, &value.to_ascii_lowercase());
+                clauses.push((
+                    Occur::Should,
+                    Box::new(TermQuery::new(t, IndexRecordOption::Basic)) as Box<dyn Query>,
+                ));
+                continue;
+            }
+            if field == FieldKind::Name
+                && let Some(q) = self.former_name_query(fields.doc_key, term)
+            {
+                clauses.push((Occur::Should, q));
+            }
             // Map FieldKind to tantivy::schema::Field in ContentFields
             let t_field = match field {
                 FieldKind::Name => Some(fields.name),
                 FieldKind::Path => Some(fields.path),
                 FieldKind::Ext => Some(fields.ext),
                 FieldKind::Content => Some(fields.content),
+                FieldKind::Subject => Some(fields.email_subject),
+                FieldKind::From => Some(fields.email_from),
+                FieldKind::To => Some(fields.email_to),
+                FieldKind::Title => Some(fields.title),
                 // Other fields like size/modified handled in ranges or ignored for text search
                 _ => None,
             };
 
             if let Some(tf) = t_field {
                 match term.modifier {
-                    TermModifier::Prefix => {
-                        let t = Term::from_field_text(tf, value);
+                    TermModifier::Component if tf == fields.path => {
+                        clauses.push((Occur::Should, component_query(fields.dir_component, value)));
+                    }
+                    TermModifier::Prefix if tf != fields.ext => {
+                        clauses.push((Occur::Should, prefix_query(tf, value)));
+                    }
+                    TermModifier::Fuzzy(distance) if tf != fields.ext => {
                         clauses.push((
                             Occur::Should,
-                            Box::new(TermQuery::new(t, IndexRecordOption::WithFreqs))
-                                as Box<dyn Query>,
+                            fuzzy_query(tf, value, distance, self.fuzzy_transpositions),
                         ));
                     }
+                    // Phonetic codes and whole lowercased names only live in the metadata
+                    // index.
+                    TermModifier::Phonetic | TermModifier::Glob | TermModifier::Regex => {}
                     _ => {
                         let mut parser = QueryParser::for_index(index, vec![tf]);
                         parser.set_conjunction_by_default();
@@ -260,7 +919,8 @@ impl UnifiedSearchHandler {
         let offset = req.offset as usize;
 
         let searcher = self.meta_reader.searcher();
-        let query = match self.build_meta_query(&req.query) {
+        let scan = RegexScan::for_request(req);
+        let query = match self.build_meta_query(&req.query, &scan) {
             Ok(q) => q,
             Err(err) => {
                 warn!(error = %err, "failed to build meta query");
@@ -270,14 +930,25 @@ impl UnifiedSearchHandler {
 
         tracing::info!("executing meta query: {:?}", query);
 
+        let mut terms = Vec::new();
+        if self.name_match_ranking {
+            name_terms(&req.query, &mut terms);
+        }
         let top_k = limit.saturating_add(offset);
-        let (hits, total) = match searcher.search(&query, &(TopDocs::with_limit(top_k), Count)) {
-            Ok(r) => r,
-            Err(err) => {
-                warn!(error = %err, "meta search execution failed");
-                return StubSearchHandler.search(req.clone());
-            }
+        // Tantivy picks the top candidates by BM25; rescoring by name needs a wider pool.
+        let candidates = if terms.is_empty() {
+            top_k
+        } else {
+            top_k.saturating_mul(NAME_RANK_POOL)
         };
+        let (hits, total, _) =
+            match search_top(&searcher, &*query, candidates, req.refine.as_deref(), 0) {
+                Ok(r) => r,
+                Err(err) => {
+                    warn!(error = %err, "meta search execution failed");
+                    return StubSearchHandler.search(req.clone());
+                }
+            };
 
         tracing::info!(
             "meta search found {} total hits (returned {})",
@@ -285,14 +956,20 @@ impl UnifiedSearchHandler {
             hits.len()
         );
 
-        let out = hits
+        let mut out: Vec<SearchHit> = hits
             .into_iter()
-            .skip(offset)
             .filter_map(|(score, addr)| {
                 let retrieved = searcher.doc::<TantivyDocument>(addr).ok()?;
                 to_hit(&retrieved, &self.meta.fields, score)
             })
             .collect();
+        if !terms.is_empty() {
+            for hit in &mut out {
+                hit.score = name_match_score(hit.name.as_deref().unwrap_or_default(), &terms);
+            }
+            out.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.key.0.cmp(&b.key.0)));
+        }
+        let out = out.into_iter().skip(offset).take(limit).collect();
 
         SearchResponse {
             id: req.id,
@@ -301,6 +978,16 @@ impl UnifiedSearchHandler {
             truncated: false, // MVP
             took_ms: start.elapsed().as_millis().min(u32::MAX as u128) as u32,
             served_by: None,
+            grouped: None,
+            degraded_to: None,
+            next_cursor: None,
+            approximate: scan.capped.get(),
+            total_bytes_matched: None,
+            refining: false,
+            executed_query: None,
+            error: None,
+            result_set_id: None,
+            cancelled: false,
         }
     }
 
@@ -323,7 +1010,49 @@ impl UnifiedSearchHandler {
         };
 
         let top_k = limit.saturating_add(offset);
-        let (hits, total) = match searcher.search(&query, &(TopDocs::with_limit(top_k), Count)) {
+        // Where the scoring cap cuts off depends on segment order.
+        let max_scored = if req.deterministic_order {
+            0
+        } else {
+            self.content_max_scored
+        };
+        // References to shared bodies are matched separately, through their bodies.
+        let mut body_scores = HashMap::new();
+        let (query, references) = if has_references(&searcher, &content_idx.fields) {
+            let references =
+                match self.build_reference_query(&req.query, &searcher, &mut body_scores) {
+                    Ok(q) => q,
+                    Err(err) => {
+                        warn!(error = %err, "failed to build reference query");
+                        return StubSearchHandler.search(req.clone());
+                    }
+                };
+            let stored: Box<dyn Query> = Box::new(BooleanQuery::new(vec![
+                (Occur::Must, query),
+                (Occur::Must, stores_body()),
+            ]));
+            (stored, Some(references))
+        } else {
+            (query, None)
+        };
+        let searched = search_top(&searcher, &*query, top_k, req.refine.as_deref(), max_scored)
+            .and_then(|(hits, total, approximate)| {
+                let Some(references) = &references else {
+                    return Ok((hits, total, approximate));
+                };
+                let (mut hits, total, approximate) = expand_shared_bodies(
+                    &searcher,
+                    &**references,
+                    Arc::new(body_scores),
+                    top_k,
+                    req.refine.as_deref(),
+                    max_scored,
+                    (hits, total, approximate),
+                )?;
+                sort_by_score_then_key(&searcher, &mut hits)?;
+                Ok((hits, total, approximate))
+            });
+        let (hits, total, approximate) = match searched {
             Ok(r) => r,
             Err(err) => {
                 warn!(error = %err, "content search execution failed");
@@ -331,13 +1060,29 @@ impl UnifiedSearchHandler {
             }
         };
 
+        // Skip snippet rendering entirely when the request projects it away.
+        let mut terms = Vec::new();
+        snippet_terms(&req.query, &mut terms);
+        let terms = req
+            .wants_field(HitField::Snippet)
+            .then_some(terms.as_slice());
         let out = hits
             .into_iter()
             .skip(offset)
+            .take(limit)
             .filter_map(|(score, addr)| {
-                let retrieved = searcher.doc::<TantivyDocument>(addr).ok()?;
+                let mut retrieved = searcher.doc::<TantivyDocument>(addr).ok()?;
+                attach_shared_preview(&searcher, &content_idx.fields, &mut retrieved).ok()?;
                 // We need to_hit equivalent for content fields
-                to_hit_content(&retrieved, &content_idx.fields, score)
+                to_hit_content(
+                    &retrieved,
+                    &content_idx.fields,
+                    score,
+                    terms,
+                    req.snippets_per_hit.max(1) as usize,
+                    &self.snippet_cache,
+                    &self.snippet_opts,
+                )
             })
             .collect();
 
@@ -348,6 +1093,16 @@ impl UnifiedSearchHandler {
             truncated: false,
             took_ms: start.elapsed().as_millis().min(u32::MAX as u128) as u32,
             served_by: None,
+            grouped: None,
+            degraded_to: None,
+            next_cursor: None,
+            approximate,
+            total_bytes_matched: None,
+            refining: false,
+            executed_query: None,
+            error: None,
+            result_set_id: None,
+            cancelled: false,
         }
     }
 
@@ -359,142 +1114,1098 @@ impl UnifiedSearchHandler {
 
         let start = Instant::now();
         let limit = req.limit.max(1) as usize;
+        let offset = req.offset as usize;
 
-        // Fetch more to allow merging
-        let fetch_limit = limit * 2;
+        // Any hit in the merged top (offset + limit) is also in the top (offset + limit) of the
+        // side that produced its max score, so each side only needs that many candidates.
+        let top_k = limit.saturating_add(offset);
 
         // Create sub-requests
         let mut meta_req = req.clone();
-        meta_req.limit = fetch_limit as u32;
-        meta_req.offset = 0; // We handle paging after merge? Or simple approach: no deep paging in hybrid for now.
+        meta_req.limit = top_k.min(u32::MAX as usize) as u32;
+        meta_req.offset = 0;
 
         let meta_resp = self.search_meta(&meta_req);
+        let mut approximate = meta_resp.approximate;
+        let (meta_total, mut content_total) = (meta_resp.total, 0);
 
-        let mut hits_map: std::collections::HashMap<core_types::DocKey, SearchHit> =
-            std::collections::HashMap::new();
-
-        for hit in meta_resp.hits {
-            hits_map.insert(hit.key, hit);
-        }
+        // Name hits are kept by key so content hits for the same file merge into them; content
+        // hits for other files go straight into the bounded top. Neither holds more than
+        // `top_k`, however many documents matched.
+        let mut by_key: HashMap<core_types::DocKey, SearchHit> = meta_resp
+            .hits
+            .into_iter()
+            .map(|hit| (hit.key, hit))
+            .collect();
+        let mut candidates = by_key.len();
+        let mut top = TopKHits::new(top_k);
 
         if self.content.is_some() {
             let mut content_req = req.clone();
-            content_req.limit = fetch_limit as u32;
+            content_req.limit = meta_req.limit;
             content_req.offset = 0;
             let content_resp = self.search_content(&content_req);
+            approximate |= content_resp.approximate;
+            content_total = content_resp.total;
 
             for hit in content_resp.hits {
-                hits_map
-                    .entry(hit.key)
-                    .and_modify(|e| {
+                match by_key.get_mut(&hit.key) {
+                    Some(e) => {
                         e.score = e.score.max(hit.score); // Max score strategy? Or sum? Max is safer for boolean queries.
                         if e.snippet.is_none() {
-                            e.snippet = hit.snippet.clone();
+                            e.snippet = hit.snippet;
+                            e.snippets = hit.snippets;
                         }
-                    })
-                    .or_insert(hit);
+                    }
+                    None => {
+                        candidates += 1;
+                        top.push(hit);
+                    }
+                }
             }
         }
 
-        let mut merged: Vec<SearchHit> = hits_map.into_values().collect();
-        merged.sort_by(|a, b| {
-            b.score
-                .partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
-        });
-
-        let offset = req.offset as usize;
-        let total = merged.len();
-        let hits = merged.into_iter().skip(offset).take(limit).collect();
+        // When either side matched more than it returned, the merged candidates are only part
+        // of the result set; count the union of both sides' matches instead.
+        let fetched = u64::from(meta_req.limit);
+        let total = if meta_total <= fetched && content_total <= fetched {
+            candidates as u64
+        } else {
+            self.hybrid_total(req)
+                .unwrap_or(meta_total.max(content_total))
+        };
+        let name_hits = by_key.len();
+        top.extend(by_key.into_values());
+        let buffered = name_hits.max(top.peak());
+        self.merge_peak.store(buffered, Ordering::Relaxed);
+        tracing::debug!(candidates, buffered, "hybrid merge");
+        let hits: Vec<SearchHit> = top.into_sorted_vec().into_iter().skip(offset).collect();
+        self.rehydrate_missing(&hits);
 
         SearchResponse {
             id: req.id,
             hits,
-            total: total as u64, // Approx
+            total,
             truncated: false,
             took_ms: start.elapsed().as_millis().min(u32::MAX as u128) as u32,
             served_by: None,
+            grouped: None,
+            degraded_to: None,
+            next_cursor: None,
+            approximate,
+            total_bytes_matched: None,
+            refining: false,
+            executed_query: None,
+            error: None,
+            result_set_id: None,
+            cancelled: false,
         }
     }
-}
 
-impl SearchHandler for UnifiedSearchHandler {
-    fn search(&self, req: SearchRequest) -> SearchResponse {
-        match req.mode {
-            SearchMode::NameOnly => self.search_meta(&req),
-            SearchMode::Content => self.search_content(&req),
-            SearchMode::Hybrid | SearchMode::Auto => self.search_hybrid(&req),
-        }
+    /// Documents a hybrid `req` matches by name or content, each counted once. `None` if a
+    /// query fails to build or run.
+    fn hybrid_total(&self, req: &SearchRequest) -> Option<u64> {
+        self.matched_keys(req, false).map(|keys| keys.len() as u64)
     }
-}
 
-// Helper to map content doc to SearchHit
-fn to_hit_content<D: Document>(
-    doc: &D,
-    fields: &content_index::ContentFields,
-    score: Score,
-) -> Option<SearchHit> {
-    let mut key = None;
-    let mut name = None;
-    let mut path = None;
-    let mut ext = None;
-    let mut size = None;
-    let mut modified = None;
-    let snippet = None; // TODO: snippet generation
+    /// Every document `req` matches in the indexes its mode searches (only the metadata index
+    /// once `degraded`), each key once. `None` if a query fails to build or run.
+    fn matched_keys(
+        &self,
+        req: &SearchRequest,
+        degraded: bool,
+    ) -> Option<HashSet<core_types::DocKey>> {
+        let mut keys = HashSet::new();
+        if req.mode != SearchMode::Content || degraded {
+            let searcher = self.meta_reader.searcher();
+            let scan = RegexScan::new(REGEX_MAX_NAMES);
+            let query = self.build_meta_query(&req.query, &scan).ok()?;
+            let result =
+                matched_sizes(&searcher, &*query, req.refine.as_deref()).and_then(|meta| {
+                    for addr in &meta.dirs {
+                        let column = searcher
+                            .segment_reader(addr.segment_ord)
+                            .fast_fields()
+                            .u64("doc_key")?;
+                        keys.extend(column.first(addr.doc_id).map(core_types::DocKey));
+                    }
+                    keys.extend(meta.files.into_keys().map(core_types::DocKey));
+                    Ok(())
+                });
+            if let Err(err) = result {
+                warn!(error = %err, "meta match keys failed");
+                return None;
+            }
+        }
+        if let Some((_, reader)) = self
+            .content
+            .as_ref()
+            .filter(|_| !degraded && req.mode != SearchMode::NameOnly)
+        {
+            let query = self.build_content_query(&req.query).ok()?;
+            match matched_sizes(&reader.searcher(), &*query, req.refine.as_deref()) {
+                Ok(content) => keys.extend(content.files.into_keys().map(core_types::DocKey)),
+                Err(err) => {
+                    warn!(error = %err, "content match keys failed");
+                    return None;
+                }
+            }
+        }
+        Some(keys)
+    }
 
-    for (field, value) in doc.iter_fields_and_values() {
-        match field {
-            f if f == fields.doc_key => {
-                if let Some(v) = value.as_u64() {
-                    key = Some(core_types::DocKey(v));
+    /// Summed size of every match of `req` in the indexes its mode searches (only the
+    /// metadata index once `degraded`). `None` if a query fails to build or run.
+    fn total_bytes(&self, req: &SearchRequest, degraded: bool) -> Option<u64> {
+        let mut sizes = MatchedSizes::default();
+        if req.mode != SearchMode::Content || degraded {
+            let searcher = self.meta_reader.searcher();
+            let scan = RegexScan::new(REGEX_MAX_NAMES);
+            let query = self.build_meta_query(&req.query, &scan).ok()?;
+            let result =
+                matched_sizes(&searcher, &*query, req.refine.as_deref()).and_then(|mut meta| {
+                    if self.directory_bytes == DirectoryBytes::Contents {
+                        add_dir_contents(&searcher, &self.meta.fields, &mut meta)?;
+                    }
+                    Ok(meta)
+                });
+            match result {
+                Ok(meta) => sizes.merge(meta),
+                Err(err) => {
+                    warn!(error = %err, "meta byte total failed");
+                    return None;
+                }
+            }
+        }
+        if let Some((_, reader)) = self
+            .content
+            .as_ref()
+            .filter(|_| !degraded && req.mode != SearchMode::NameOnly)
+        {
+            let query = self.build_content_query(&req.query).ok()?;
+            match matched_sizes(&reader.searcher(), &*query, req.refine.as_deref()) {
+                Ok(content) => sizes.merge(content),
+                Err(err) => {
+                    warn!(error = %err, "content byte total failed");
+                    return None;
                 }
             }
-            f if f == fields.name => name = value.as_str().map(|s| s.to_string()),
-            f if f == fields.path => path = value.as_str().map(|s| s.to_string()),
-            f if f == fields.ext => ext = value.as_str().map(|s| s.to_string()),
-            f if f == fields.size => size = value.as_u64(),
-            f if f == fields.modified => modified = value.as_i64(),
-            // TODO: snippet from content field
-            _ => {}
         }
+        Some(sizes.total())
     }
 
-    key.map(|doc_key| SearchHit {
-        key: doc_key,
-        score,
-        name,
-        path,
-        ext,
-        size,
-        modified,
-        snippet,
-    })
-}
+    /// Whether the content index is open and holds at least one document.
+    fn has_content(&self) -> bool {
+        self.content
+            .as_ref()
+            .is_some_and(|(_, reader)| reader.searcher().num_docs() > 0)
+    }
 
-static HANDLER: OnceLock<Box<dyn SearchHandler>> = OnceLock::new();
+    fn search_mode(&self, req: &SearchRequest) -> SearchResponse {
+        if self.degrade_without_content && req.mode != SearchMode::NameOnly && !self.has_content() {
+            if req.mode == SearchMode::Content {
+                return SearchResponse {
+                    id: req.id,
+                    hits: Vec::new(),
+                    total: 0,
+                    truncated: false,
+                    took_ms: 0,
+                    served_by: None,
+                    grouped: None,
+                    degraded_to: None,
+                    next_cursor: None,
+                    approximate: false,
+                    total_bytes_matched: None,
+                    refining: false,
+                    executed_query: None,
+                    error: None,
+                    result_set_id: None,
+                    cancelled: false,
+                };
+            }
+            let mut resp = self.search_meta(req);
+            resp.degraded_to = Some(SearchMode::NameOnly);
+            return resp;
+        }
+        match req.mode {
+            SearchMode::NameOnly => self.search_meta(req),
+            SearchMode::Content => self.search_content(req),
+            SearchMode::Hybrid | SearchMode::Auto => self.search_hybrid(req),
+        }
+    }
 
-pub fn set_search_handler(handler: Box<dyn SearchHandler>) {
-    tracing::info!("Global search handler installed.");
-    let _ = HANDLER.set(handler);
-}
+    /// Hits in the order the request asks for: by a sort field, boosted toward a context
+    /// path, or by relevance.
+    fn search_ordered(&self, req: &SearchRequest) -> SearchResponse {
+        match (req.sort, req.context_path.as_deref()) {
+            (Some(spec), _) => self.search_sorted(req, spec),
+            (None, Some(context)) if self.context_weight > 0.0 => self.search_near(req, context),
+            (None, _) => self.search_mode(req),
+        }
+    }
 
-pub fn search(req: SearchRequest) -> SearchResponse {
-    tracing::info!("Received search request id={} mode={:?}", req.id, req.mode);
-    if let Some(h) = HANDLER.get() {
-        h.search(req)
-    } else {
-        tracing::warn!("No search handler installed, using StubSearchHandler.");
-        StubSearchHandler.search(req)
+    /// At most `cap` hits per folder. Dropping hits from crowded folders lets lower-ranked
+    /// ones in, so `DIR_CAP_POOL` times as many candidates are fetched before the cap and
+    /// paging apply.
+    fn search_diverse(&self, req: &SearchRequest, cap: usize) -> SearchResponse {
+        let limit = req.limit.max(1) as usize;
+        let offset = req.offset as usize;
+        let mut candidates = req.clone();
+        candidates.offset = 0;
+        candidates.limit = offset
+            .saturating_add(limit)
+            .saturating_mul(DIR_CAP_POOL)
+            .min(u32::MAX as usize) as u32;
+
+        let mut resp = self.search_ordered(&candidates);
+        let hits = cap_per_folder(std::mem::take(&mut resp.hits), cap);
+        resp.hits = hits.into_iter().skip(offset).take(limit).collect();
+        resp
+    }
+
+    /// Relevance boosted toward `context`. A boost can lift a hit from below the requested
+    /// window, so `CONTEXT_POOL` times as many candidates are fetched and re-ranked.
+    fn search_near(&self, req: &SearchRequest, context: &str) -> SearchResponse {
+        let limit = req.limit.max(1) as usize;
+        let offset = req.offset as usize;
+        let mut candidates = req.clone();
+        candidates.offset = 0;
+        candidates.limit = offset
+            .saturating_add(limit)
+            .saturating_mul(CONTEXT_POOL)
+            .min(u32::MAX as usize) as u32;
+
+        let mut resp = self.search_mode(&candidates);
+        let mut hits = std::mem::take(&mut resp.hits);
+        for hit in &mut hits {
+            hit.score = boosted_score(hit.score, hit.path.as_deref(), context, self.context_weight);
+        }
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.key.cmp(&b.key)));
+        resp.hits = hits.into_iter().skip(offset).take(limit).collect();
+        resp
+    }
+
+    /// Order by `spec` instead of score. Sorting needs the matches up front, so up to
+    /// `SORT_CANDIDATES` (or `offset + limit`, if larger) best-scoring hits are gathered,
+    /// sorted, then paged; `truncated` is set when more matches existed.
+    fn search_sorted(&self, req: &SearchRequest, spec: SortSpec) -> SearchResponse {
+        let limit = req.limit.max(1) as usize;
+        let offset = req.offset as usize;
+        let mut candidates = req.clone();
+        candidates.offset = 0;
+        candidates.limit = SORT_CANDIDATES
+            .max(offset.saturating_add(limit))
+            .min(u32::MAX as usize) as u32;
+
+        let mut resp = self.search_mode(&candidates);
+        resp.truncated |= resp.total > resp.hits.len() as u64;
+        let mut hits = std::mem::take(&mut resp.hits);
+        match spec.field {
+            SortField::Name => sort_hits_by(&mut hits, spec.descending, |h| {
+                h.name.as_deref().map(|n| self.collator.sort_key(n))
+            }),
+            SortField::Path => sort_hits_by(&mut hits, spec.descending, |h| {
+                h.path.as_deref().map(|p| self.collator.sort_key(p))
+            }),
+            SortField::Size => sort_hits_by(&mut hits, spec.descending, |h| h.size),
+            SortField::Modified => sort_hits_by(&mut hits, spec.descending, |h| h.modified),
+        }
+        resp.hits = hits.into_iter().skip(offset).take(limit).collect();
+        resp
     }
 }
 
-fn to_hit<D: Document>(doc: &D, fields: &MetaFields, score: Score) -> Option<SearchHit> {
+/// Candidates per requested hit when results are boosted toward a context path.
+const CONTEXT_POOL: usize = 4;
+
+/// Candidates per requested hit when hits per folder are capped.
+const DIR_CAP_POOL: usize = 4;
+
+/// Most matches a sorted request orders before paging.
+const SORT_CANDIDATES: usize = 10_000;
+
+/// Candidates per requested hit when name-only results are re-ranked by match quality.
+const NAME_RANK_POOL: usize = 4;
+
+/// Names a `Regex` term tests between checks for cancellation.
+const CANCEL_CHECK_INTERVAL: usize = 4096;
+
+/// Compiled `Regex` patterns kept before the cache starts over.
+const REGEX_CACHE_ENTRIES: usize = 64;
+
+/// Matching names a `Regex` term collects per requested hit, leaving room for the rest of the
+/// query to filter some out.
+const REGEX_NAME_POOL: usize = 16;
+
+/// Most matching names a `Regex` term collects, whatever the request's limit.
+const REGEX_MAX_NAMES: usize = 100_000;
+
+/// Names a `Regex` term tests per segment before giving up.
+const REGEX_SCAN_CEILING: usize = 5_000_000;
+
+/// How long a requested rehydration counts as in flight before a search may request it again.
+const REHYDRATE_RETRY_AFTER: Duration = Duration::from_secs(600);
+
+/// Per-request bounds on `Regex` terms, which test names one by one instead of looking
+/// them up.
+struct RegexScan {
+    /// Matching names each term collects before stopping.
+    max_names: usize,
+    /// Set when a term stopped early, so its matches may be incomplete.
+    capped: Cell<bool>,
+}
+
+impl RegexScan {
+    fn new(max_names: usize) -> Self {
+        Self {
+            max_names,
+            capped: Cell::new(false),
+        }
+    }
+
+    /// Enough names for the request's window (`offset + limit`), up to [`REGEX_MAX_NAMES`].
+    fn for_request(req: &SearchRequest) -> Self {
+        let top_k = (req.limit.max(1) as usize).saturating_add(req.offset as usize);
+        Self::new(top_k.saturating_mul(REGEX_NAME_POOL).min(REGEX_MAX_NAMES))
+    }
+}
+
+/// Stable field ordering for hits; hits without the field go last in either direction and
+/// equal keys fall back to `DocKey` order.
+fn sort_hits_by<K: Ord>(
+    hits: &mut Vec<SearchHit>,
+    descending: bool,
+    key: impl Fn(&SearchHit) -> Option<K>,
+) {
+    let mut keyed: Vec<(Option<K>, SearchHit)> = hits.drain(..).map(|h| (key(&h), h)).collect();
+    keyed.sort_by(|(ka, a), (kb, b)| {
+        let ord = match (ka, kb) {
+            (Some(x), Some(y)) if descending => y.cmp(x),
+            (Some(x), Some(y)) => x.cmp(y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+        ord.then(a.key.cmp(&b.key))
+    });
+    hits.extend(keyed.into_iter().map(|(_, h)| h));
+}
+
+impl SearchHandler for UnifiedSearchHandler {
+    fn search(&self, mut req: SearchRequest) -> SearchResponse {
+        if let Some(cursor) = req.cursor.as_deref().and_then(PageCursor::decode) {
+            req.offset = cursor.offset;
+            req.limit = cursor.end - cursor.offset;
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        req.query = QueryPlanner::optimize(localize_dates(&req.query, self.date_zone, now));
+        if self.prefix_trailing_term {
+            req.query = QueryPlanner::prefix_trailing_term(req.query);
+        }
+        if let Err(err) = QueryPlanner::check_mode(&req.query, req.mode)
+            .and_then(|()| self.check_regexes(&req.query))
+            .and_then(|()| self.resolve_refine_set(&mut req))
+        {
+            let mut resp = StubSearchHandler.search(req);
+            resp.served_by = None;
+            resp.error = Some(format!("{err:#}"));
+            return resp;
+        }
+        if search_cancelled() {
+            return cancelled_response(req);
+        }
+        let mut resp = match req.max_hits_per_dir {
+            Some(cap) => self.search_diverse(&req, cap as usize),
+            None => self.search_ordered(&req),
+        };
+        // Whatever was scored is dropped; the stages below only dress up the hits.
+        if search_cancelled() {
+            return cancelled_response(req);
+        }
+        if let Some(cache) = self.result_sets.as_ref().filter(|_| req.result_set) {
+            let keys = self.matched_keys(&req, resp.degraded_to.is_some());
+            resp.result_set_id = keys.and_then(|keys| cache.insert(keys.into_iter().collect()));
+        }
+        if req.total_bytes {
+            resp.total_bytes_matched = self.total_bytes(&req, resp.degraded_to.is_some());
+        }
+        self.page(&req, &mut resp);
+        if req.extract_status {
+            self.fill_extract_status(&mut resp.hits);
+        }
+        if let Some(store) = self
+            .tags
+            .as_ref()
+            .filter(|_| req.wants_field(HitField::Tags))
+        {
+            for hit in &mut resp.hits {
+                hit.tags = store.tags(hit.key);
+            }
+        }
+        self.flag_former_names(&req.query, &mut resp.hits);
+        if let Some(GroupBy::Folder) = req.group_by {
+            resp.grouped = Some(group_by_folder(&resp.hits));
+        }
+        // Project after grouping, which needs each hit's path.
+        if let Some(fields) = &req.fields {
+            let grouped = resp.grouped.iter_mut().flatten().flat_map(|g| &mut g.hits);
+            for hit in resp.hits.iter_mut().chain(grouped) {
+                hit.project(fields);
+            }
+        }
+        if req.explain {
+            resp.executed_query = Some(req.query);
+        }
+        resp
+    }
+
+    /// Hybrid and auto requests first get their name matches, which come from the metadata
+    /// index alone and skip content scoring, then the full result merged with content hits.
+    fn search_anytime(&self, req: SearchRequest, emit: &mut dyn FnMut(SearchResponse) -> bool) {
+        let staged =
+            matches!(req.mode, SearchMode::Hybrid | SearchMode::Auto) && self.has_content();
+        if staged {
+            let mut names = req.clone();
+            names.mode = SearchMode::NameOnly;
+            names.total_bytes = false;
+            let mut first = self.search(names);
+            first.approximate = true;
+            first.refining = true;
+            // Paging follows the final ranking.
+            first.next_cursor = None;
+            if !emit(first) {
+                return;
+            }
+        }
+        emit(self.search(req));
+    }
+}
+
+/// The document with `key`, matched on a `doc_key` fast field.
+fn key_query(doc_key: Field, key: core_types::DocKey) -> RangeQuery {
+    let term = Term::from_field_u64(doc_key, key.0);
+    RangeQuery::new(Bound::Included(term.clone()), Bound::Included(term))
+}
+
+/// Documents whose `doc_key` is one of `keys`.
+fn keys_query(doc_key: Field, keys: Vec<core_types::DocKey>) -> Box<dyn Query> {
+    Box::new(BooleanQuery::new(
+        keys.into_iter()
+            .map(|key| {
+                (
+                    Occur::Should,
+                    Box::new(key_query(doc_key, key)) as Box<dyn Query>,
+                )
+            })
+            .collect(),
+    ))
+}
+
+/// Matches in descending score order, equal scores in ascending `doc_key` order.
+type ScoredDocs = Vec<(Score, DocAddress)>;
+
+/// Top `top_k` matches of `query` plus the total match count. With `refine`, only documents
+/// whose `doc_key` is in that set are collected (both indexes keep `doc_key` as a fast field).
+/// With `max_scored` > 0, at most that many documents are scored; the last value reports
+/// whether that cut the search short, in which case the count covers only scored documents.
+/// Equal scores are ranked by `doc_key` instead of index position, so which documents make
+/// the cut doesn't depend on segment layout and consecutive pages neither overlap nor skip.
+fn search_top(
+    searcher: &Searcher,
+    query: &dyn Query,
+    top_k: usize,
+    refine: Option<&[core_types::DocKey]>,
+    max_scored: usize,
+) -> tantivy::Result<(ScoredDocs, usize, bool)> {
+    let top = TopDocs::with_limit(top_k).tweak_score(|segment: &SegmentReader| {
+        let keys = segment
+            .fast_fields()
+            .u64("doc_key")
+            .ok()
+            .map(|column| column.first_or_default_col(0));
+        move |doc: DocId, score: Score| {
+            (score, Reverse(keys.as_ref().map_or(0, |k| k.get_val(doc))))
+        }
+    });
+    let ((hits, total), approximate) =
+        collect_refined(searcher, query, (top, Count), refine, max_scored)?;
+    let hits = hits
+        .into_iter()
+        .map(|((score, _), addr)| (score, addr))
+        .collect();
+    Ok((hits, total, approximate))
+}
+
+/// Run `collector` (scoring at most `max_scored` documents) over documents whose `doc_key`
+/// is in `refine`, or over every match without it.
+fn collect_refined<C: Collector>(
+    searcher: &Searcher,
+    query: &dyn Query,
+    collector: C,
+    refine: Option<&[core_types::DocKey]>,
+    max_scored: usize,
+) -> tantivy::Result<(C::Fruit, bool)> {
+    match refine {
+        None => search_capped(searcher, query, collector, max_scored),
+        Some(keys) => {
+            let keys: Arc<HashSet<u64>> = Arc::new(keys.iter().map(|k| k.0).collect());
+            let filtered = FilterCollector::new(
+                "doc_key".to_string(),
+                move |key: u64| keys.contains(&key),
+                collector,
+            );
+            search_capped(searcher, query, filtered, max_scored)
+        }
+    }
+}
+
+/// Order `hits` by score, then `doc_key`.
+fn sort_by_score_then_key(searcher: &Searcher, hits: &mut ScoredDocs) -> tantivy::Result<()> {
+    let mut keyed = Vec::with_capacity(hits.len());
+    for (score, addr) in hits.drain(..) {
+        let key = searcher
+            .segment_reader(addr.segment_ord)
+            .fast_fields()
+            .u64("doc_key")?
+            .first(addr.doc_id);
+        keyed.push((score, key, addr));
+    }
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    hits.extend(keyed.into_iter().map(|(score, _, addr)| (score, addr)));
+    Ok(())
+}
+
+/// Documents storing a body of their own, i.e. everything but dedup references. Scores 0 so
+/// it only filters.
+fn stores_body() -> Box<dyn Query> {
+    let exists = Box::new(ExistsQuery::new("body_hash".to_string(), false));
+    Box::new(ConstScoreQuery::new(exists, 0.0))
+}
+
+/// Add the best `top_k` reference documents (`extract.dedup_content`) matching `references`,
+/// built by `build_reference_query`, to `hits`. Each takes the score its body has in
+/// `body_scores` (0 when it matched on its own metadata alone), read through the `body_of`
+/// fast field. `refine` and the `max_scored` cap apply to them like to any other document, and
+/// `total` grows by every one of them, not only those on this page.
+fn expand_shared_bodies(
+    searcher: &Searcher,
+    references: &dyn Query,
+    body_scores: Arc<HashMap<u64, Score>>,
+    top_k: usize,
+    refine: Option<&[core_types::DocKey]>,
+    max_scored: usize,
+    (mut hits, total, approximate): (ScoredDocs, usize, bool),
+) -> tantivy::Result<(ScoredDocs, usize, bool)> {
+    let only_references = BooleanQuery::new(vec![
+        (Occur::Must, references.box_clone()),
+        (Occur::MustNot, stores_body()),
+    ]);
+    let top = TopDocs::with_limit(top_k).tweak_score(move |segment: &SegmentReader| {
+        let fast = segment.fast_fields();
+        let column = |name| fast.u64(name).ok().map(|c| c.first_or_default_col(0));
+        let (bodies, keys) = (column("body_of"), column("doc_key"));
+        let body_scores = body_scores.clone();
+        move |doc: DocId, _: Score| {
+            let body = bodies.as_ref().map_or(0, |b| b.get_val(doc));
+            let key = keys.as_ref().map_or(0, |k| k.get_val(doc));
+            (body_scores.get(&body).copied().unwrap_or(0.0), Reverse(key))
+        }
+    });
+    let ((references, count), capped) =
+        collect_refined(searcher, &only_references, (top, Count), refine, max_scored)?;
+    hits.extend(
+        references
+            .into_iter()
+            .map(|((score, _), addr)| (score, addr)),
+    );
+    Ok((hits, total + count, approximate || capped))
+}
+
+/// Rewrite date terms into UTC ranges covering that day in `zone`: a `Modified`/`Created`/`Sent`
+/// term whose value is a `YYYY-MM-DD` date, or an unfielded `modified:YYYY-MM-DD` /
+/// `created:YYYY-MM-DD` as typed into a search box. Relative ages in the same places
+/// (`modified:today`, `modified:<7d`, `created:>1y`) are measured back from `now`. Everything
+/// else is left as is.
+fn localize_dates(expr: &QueryExpr, zone: DateZone, now: i64) -> QueryExpr {
+    let each = |items: &[QueryExpr]| items.iter().map(|q| localize_dates(q, zone, now)).collect();
+    match expr {
+        QueryExpr::Term(term) => {
+            let dated = match (term.field, term.value.split_once(':')) {
+                (Some(field @ (FieldKind::Modified | FieldKind::Created | FieldKind::Sent)), _) => {
+                    Some((field, term.value.as_str()))
+                }
+                (None, Some((prefix, date))) if prefix.eq_ignore_ascii_case("modified") => {
+                    Some((FieldKind::Modified, date))
+                }
+                (None, Some((prefix, date))) if prefix.eq_ignore_ascii_case("created") => {
+                    Some((FieldKind::Created, date))
+                }
+                _ => None,
+            };
+            let bounds = |value: &str| match parse_date(value) {
+                Some(date) => {
+                    let (start, end) = day_range(date, zone);
+                    Some((start, Some(end - 1)))
+                }
+                None => parse_relative_time(value, now, zone),
+            };
+            match dated.and_then(|(field, value)| Some((field, bounds(value)?))) {
+                Some((field, (lo, hi))) => QueryExpr::Range(RangeExpr {
+                    field,
+                    op: RangeOp::Between,
+                    value: RangeValue::I64 { lo, hi },
+                }),
+                None => expr.clone(),
+            }
+        }
+        QueryExpr::Range(_) | QueryExpr::Near { .. } => expr.clone(),
+        QueryExpr::Not(inner) => QueryExpr::Not(Box::new(localize_dates(inner, zone, now))),
+        QueryExpr::And(items) => QueryExpr::And(each(items)),
+        QueryExpr::Or(items) => QueryExpr::Or(each(items)),
+    }
+}
+
+/// Group hits by containing folder. Each group is ranked by score and groups are ordered by
+/// their best hit; hits without a path land in a group with an empty `folder_path`.
+fn group_by_folder(hits: &[SearchHit]) -> Vec<FolderGroup> {
+    let mut groups: Vec<FolderGroup> = Vec::new();
+    let mut index: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    for hit in hits {
+        let folder = hit.path.as_deref().map(parent_folder).unwrap_or_default();
+        let slot = *index.entry(folder.to_string()).or_insert_with(|| {
+            groups.push(FolderGroup {
+                folder_path: folder.to_string(),
+                hits: Vec::new(),
+            });
+            groups.len() - 1
+        });
+        groups[slot].hits.push(hit.clone());
+    }
+
+    let best = |g: &FolderGroup| g.hits.first().map_or(f32::MIN, |h| h.score);
+    for group in &mut groups {
+        group.hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    }
+    // Stable sort keeps first-seen order between groups whose best scores tie.
+    groups.sort_by(|a, b| best(b).total_cmp(&best(a)));
+    groups
+}
+
+/// Keep at most `cap` hits per containing folder, in order. Hits without a path are kept.
+fn cap_per_folder(hits: Vec<SearchHit>, cap: usize) -> Vec<SearchHit> {
+    let mut seen: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+    hits.into_iter()
+        .filter(|hit| {
+            let Some(path) = hit.path.as_deref() else {
+                return true;
+            };
+            let count = seen.entry(parent_folder(path).to_string()).or_default();
+            *count += 1;
+            *count <= cap
+        })
+        .collect()
+}
+
+/// Folder portion of a full path (`C:\a\b.txt` -> `C:\a`); a bare name has no folder.
+fn parent_folder(path: &str) -> &str {
+    path.rfind(['\\', '/']).map_or("", |i| &path[..i])
+}
+
+/// Fuzzy match on a tokenized text field; every whitespace-separated token must match.
+fn fuzzy_query(field: Field, value: &str, distance: u8, transpositions: bool) -> Box<dyn Query> {
+    // Tantivy only builds Levenshtein automata up to distance 2.
+    let distance = distance.min(2);
+    let clauses: Vec<(Occur, Box<dyn Query>)> = value
+        .split_whitespace()
+        .map(|token| {
+            let term = Term::from_field_text(field, &token.to_lowercase());
+            (
+                Occur::Must,
+                Box::new(FuzzyTermQuery::new(term, distance, transpositions)) as Box<dyn Query>,
+            )
+        })
+        .collect();
+    Box::new(BooleanQuery::new(clauses))
+}
+
+/// Every word of `value` must start a token of `field` (tokens are lowercased).
+fn prefix_query(field: Field, value: &str) -> Box<dyn Query> {
+    let clauses: Vec<(Occur, Box<dyn Query>)> = value
+        .split_whitespace()
+        .map(|token| {
+            let term = Term::from_field_text(field, &token.to_lowercase());
+            (
+                Occur::Must,
+                Box::new(FuzzyTermQuery::new_prefix(term, 0, false)) as Box<dyn Query>,
+            )
+        })
+        .collect();
+    Box::new(BooleanQuery::new(clauses))
+}
+
+/// Hit ordered by score (ties broken by key) so it can live in a `BinaryHeap`.
+struct RankedHit(SearchHit);
+
+impl RankedHit {
+    fn cmp_key(&self, other: &Self) -> std::cmp::Ordering {
+        self.0
+            .score
+            .total_cmp(&other.0.score)
+            .then_with(|| other.0.key.0.cmp(&self.0.key.0))
+    }
+}
+
+impl PartialEq for RankedHit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp_key(other).is_eq()
+    }
+}
+
+impl Eq for RankedHit {}
+
+impl PartialOrd for RankedHit {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RankedHit {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.cmp_key(other)
+    }
+}
+
+/// Bounded min-heap that keeps the best `capacity` hits, so merging a broad candidate stream
+/// costs O(offset + limit) memory instead of materializing and sorting every match.
+pub(crate) struct TopKHits {
+    heap: BinaryHeap<Reverse<RankedHit>>,
+    capacity: usize,
+    peak: usize,
+}
+
+impl TopKHits {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            heap: BinaryHeap::with_capacity(capacity.min(4096)),
+            capacity,
+            peak: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, hit: SearchHit) {
+        if self.capacity == 0 {
+            return;
+        }
+        let ranked = RankedHit(hit);
+        if self.heap.len() < self.capacity {
+            self.heap.push(Reverse(ranked));
+        } else if let Some(mut worst) = self.heap.peek_mut()
+            && ranked > worst.0
+        {
+            *worst = Reverse(ranked);
+        }
+        self.peak = self.peak.max(self.heap.len());
+    }
+
+    pub(crate) fn extend(&mut self, hits: impl IntoIterator<Item = SearchHit>) {
+        for hit in hits {
+            self.push(hit);
+        }
+    }
+
+    /// Largest number of hits held at once.
+    pub(crate) fn peak(&self) -> usize {
+        self.peak
+    }
+
+    /// Hits ordered best-first.
+    pub(crate) fn into_sorted_vec(self) -> Vec<SearchHit> {
+        // `Reverse` flips the order, so ascending here means best score first.
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(r)| r.0)
+            .collect()
+    }
+}
+
+/// Characters with a meaning in Tantivy's regex syntax, escaped when a glob uses them
+/// literally.
+const REGEX_META: &str = r"\.+*?()|[]{}^$#&-~";
+
+/// Anchored, lowercased regex for a `Glob` term (see [`TermModifier::Glob`]). An unclosed
+/// `[` is literal.
+fn glob_regex(glob: &str) -> String {
+    let glob: Vec<char> = glob.to_lowercase().chars().collect();
+    let literal = |out: &mut String, c: char| {
+        if REGEX_META.contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    };
+    let mut out = String::new();
+    let mut i = 0;
+    while i < glob.len() {
+        match glob[i] {
+            '*' => out.push_str(".*"),
+            '?' => out.push('.'),
+            '\\' if i + 1 < glob.len() => {
+                i += 1;
+                literal(&mut out, glob[i]);
+            }
+            '[' if let Some(len) = glob[i + 1..]
+                .iter()
+                .enumerate()
+                .skip(usize::from(matches!(glob.get(i + 1), Some('!' | '^'))) + 1)
+                .find(|&(_, &c)| c == ']')
+                .map(|(len, _)| len) =>
+            {
+                let mut class = &glob[i + 1..i + 1 + len];
+                out.push('[');
+                if let Some(rest) = class.strip_prefix(&['!']).or(class.strip_prefix(&['^'])) {
+                    out.push('^');
+                    class = rest;
+                }
+                for (j, &c) in class.iter().enumerate() {
+                    let range = c == '-' && j > 0 && j + 1 < class.len();
+                    if range {
+                        out.push('-');
+                    } else {
+                        literal(&mut out, c);
+                    }
+                }
+                out.push(']');
+                i += len + 1;
+            }
+            c => literal(&mut out, c),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// The lowercased literal text every match of a `^`-anchored `pattern` starts with, so a
+/// name scan can seek to it. `None` when the pattern isn't anchored, has an alternation, or
+/// starts with anything but plain characters.
+fn regex_literal_prefix(pattern: &str) -> Option<String> {
+    let rest = pattern.strip_prefix('^')?;
+    if pattern.contains('|') {
+        return None;
+    }
+    let mut prefix = String::new();
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        // A quantified character may be absent or repeated.
+        if REGEX_META.contains(c) || matches!(chars.peek(), Some('*' | '?' | '{')) {
+            break;
+        }
+        prefix.extend(c.to_lowercase());
+    }
+    (!prefix.is_empty()).then_some(prefix)
+}
+
+/// The non-empty `Regex` term patterns of `expr`.
+fn regex_terms<'a>(expr: &'a QueryExpr, out: &mut Vec<&'a str>) {
+    match expr {
+        QueryExpr::Term(t) if t.modifier == TermModifier::Regex && !t.value.trim().is_empty() => {
+            out.push(t.value.trim());
+        }
+        QueryExpr::Not(inner) => regex_terms(inner, out),
+        QueryExpr::And(items) | QueryExpr::Or(items) => {
+            items.iter().for_each(|q| regex_terms(q, out));
+        }
+        QueryExpr::Term(_) | QueryExpr::Range(_) | QueryExpr::Near { .. } => {}
+    }
+}
+
+/// `Near` as a phrase query with `slop` over the content tokens of `terms`. Two tokens match
+/// in either order; a single token is a plain term query.
+fn near_query(
+    index: &tantivy::Index,
+    field: Field,
+    terms: &[String],
+    slop: u32,
+) -> Result<Box<dyn Query>> {
+    let mut analyzer = index.tokenizer_for_field(field)?;
+    let mut tokens = Vec::new();
+    for text in terms {
+        let mut stream = analyzer.token_stream(text);
+        while let Some(token) = stream.next() {
+            tokens.push(Term::from_field_text(field, &token.text));
+        }
+    }
+    let phrase = |terms: Vec<Term>| {
+        let mut q = PhraseQuery::new(terms);
+        q.set_slop(slop);
+        Box::new(q) as Box<dyn Query>
+    };
+    Ok(match tokens.len() {
+        0 => Box::new(BooleanQuery::new(vec![])),
+        1 => Box::new(TermQuery::new(
+            tokens.remove(0),
+            IndexRecordOption::WithFreqs,
+        )),
+        2 => {
+            let reversed = vec![tokens[1].clone(), tokens[0].clone()];
+            Box::new(BooleanQuery::new(vec![
+                (Occur::Should, phrase(tokens)),
+                (Occur::Should, phrase(reversed)),
+            ]))
+        }
+        _ => phrase(tokens),
+    })
+}
+
+/// Match files with an ancestor directory named `value` (case-insensitive, whole name).
+/// A multi-segment value such as `clients/invoices` requires every segment to be an ancestor.
+fn component_query(field: Field, value: &str) -> Box<dyn Query> {
+    let clauses: Vec<(Occur, Box<dyn Query>)> = value
+        .split(['\\', '/'])
+        .filter(|c| !c.is_empty())
+        .map(|c| {
+            let term = Term::from_field_text(field, &c.to_lowercase());
+            (
+                Occur::Must,
+                Box::new(TermQuery::new(term, IndexRecordOption::Basic)) as Box<dyn Query>,
+            )
+        })
+        .collect();
+    Box::new(BooleanQuery::new(clauses))
+}
+
+/// Range filter evaluated natively on the content index's size/modified/created fields.
+fn content_range_query(range: &RangeExpr, fields: &content_index::ContentFields) -> Box<dyn Query> {
+    let (lo, hi) = match range.value {
+        RangeValue::I64 { lo, hi } => (lo as i128, hi.map(i128::from)),
+        RangeValue::U64 { lo, hi } => (lo as i128, hi.map(i128::from)),
+    };
+    let (lower, upper) = match range.op {
+        RangeOp::Gt => (Bound::Excluded(lo), Bound::Unbounded),
+        RangeOp::Ge => (Bound::Included(lo), Bound::Unbounded),
+        RangeOp::Lt => (Bound::Unbounded, Bound::Excluded(lo)),
+        RangeOp::Le => (Bound::Unbounded, Bound::Included(lo)),
+        RangeOp::Between => (
+            Bound::Included(lo),
+            hi.map_or(Bound::Unbounded, Bound::Included),
+        ),
+    };
+
+    let to_term: Box<dyn Fn(i128) -> Term> = match range.field {
+        FieldKind::Size => {
+            let f = fields.size;
+            Box::new(move |v| Term::from_field_u64(f, v.clamp(0, u64::MAX as i128) as u64))
+        }
+        FieldKind::Modified | FieldKind::Created | FieldKind::Sent => {
+            let f = match range.field {
+                FieldKind::Modified => fields.modified,
+                FieldKind::Created => fields.created,
+                _ => fields.email_sent,
+            };
+            Box::new(move |v| {
+                Term::from_field_i64(f, v.clamp(i64::MIN as i128, i64::MAX as i128) as i64)
+            })
+        }
+        // No range-capable field in the content index; match nothing.
+        _ => return Box::new(BooleanQuery::new(vec![])),
+    };
+
+    Box::new(RangeQuery::new(lower.map(&to_term), upper.map(&to_term)))
+}
+
+/// Every word of `value` must share a phonetic code (primary or alternate) with a name word.
+fn phonetic_query(field: Field, value: &str) -> Box<dyn Query> {
+    let words = value
+        .split(|c: char| !c.is_ascii_alphabetic())
+        .filter(|w| w.len() >= 2)
+        .map(|word| {
+            let (primary, alternate) = core_types::phonetic::double_metaphone(word);
+            let mut codes = vec![primary];
+            if alternate != codes[0] {
+                codes.push(alternate);
+            }
+            let any_code = codes
+                .into_iter()
+                .map(|code| {
+                    let t = Term::from_field_text(field, &code);
+                    (
+                        Occur::Should,
+                        Box::new(TermQuery::new(t, IndexRecordOption::Basic)) as Box<dyn Query>,
+                    )
+                })
+                .collect();
+            (
+                Occur::Must,
+                Box::new(BooleanQuery::new(any_code)) as Box<dyn Query>,
+            )
+        })
+        .collect();
+    Box::new(BooleanQuery::new(words))
+}
+
+/// Positive text terms (not under `Not`) that can match content; these drive snippet highlighting.
+fn snippet_terms(expr: &QueryExpr, out: &mut Vec<String>) {
+    match expr {
+        QueryExpr::Term(t)
+            if matches!(t.field, None | Some(FieldKind::Content))
+                && !matches!(
+                    t.modifier,
+                    TermModifier::Phonetic | TermModifier::Glob | TermModifier::Regex
+                ) =>
+        {
+            out.extend(t.value.split_whitespace().map(str::to_lowercase));
+        }
+        QueryExpr::Near { terms, .. } => {
+            out.extend(
+                terms
+                    .iter()
+                    .flat_map(|t| t.split_whitespace().map(str::to_lowercase)),
+            );
+        }
+        QueryExpr::And(items) | QueryExpr::Or(items) => {
+            items.iter().for_each(|q| snippet_terms(q, out));
+        }
+        QueryExpr::Term(_) | QueryExpr::Range(_) | QueryExpr::Not(_) => {}
+    }
+}
+
+/// Terms that can match a file name, for [`name_match_score`].
+fn name_terms(expr: &QueryExpr, out: &mut Vec<String>) {
+    match expr {
+        QueryExpr::Term(t)
+            if matches!(t.field, None | Some(FieldKind::Name))
+                && !matches!(
+                    t.modifier,
+                    TermModifier::Phonetic | TermModifier::Glob | TermModifier::Regex
+                ) =>
+        {
+            out.extend(t.value.split_whitespace().map(str::to_lowercase));
+        }
+        QueryExpr::And(items) | QueryExpr::Or(items) => {
+            items.iter().for_each(|q| name_terms(q, out));
+        }
+        QueryExpr::Term(_) | QueryExpr::Range(_) | QueryExpr::Not(_) | QueryExpr::Near { .. } => {}
+    }
+}
+
+// Helper to map content doc to SearchHit
+fn to_hit_content<D: Document>(
+    doc: &D,
+    fields: &content_index::ContentFields,
+    score: Score,
+    terms: Option<&[String]>,
+    snippet_count: usize,
+    snippet_cache: &SnippetCache,
+    snippet_opts: &SnippetOptions,
+) -> Option<SearchHit> {
     let mut key = None;
     let mut name = None;
     let mut path = None;
     let mut ext = None;
     let mut size = None;
     let mut modified = None;
+    let mut preview = None;
 
     for (field, value) in doc.iter_fields_and_values() {
         match field {
@@ -503,36 +2214,33 @@ fn to_hit<D: Document>(doc: &D, fields: &MetaFields, score: Score) -> Option<Sea
                     key = Some(core_types::DocKey(v));
                 }
             }
-            f if f == fields.name => {
-                if let Some(s) = value.as_str() {
-                    name = Some(s.to_string());
-                }
-            }
-            f if f == fields.path => {
-                if let Some(s) = value.as_str() {
-                    path = Some(s.to_string());
-                }
-            }
-            f if f == fields.ext => {
-                if let Some(s) = value.as_str() {
-                    ext = Some(s.to_string());
-                }
-            }
-            f if f == fields.size => {
-                if let Some(v) = value.as_u64() {
-                    size = Some(v);
-                }
-            }
-            f if f == fields.modified => {
-                if let Some(v) = value.as_i64() {
-                    modified = Some(v);
-                }
-            }
+            f if f == fields.name => name = value.as_str().map(|s| s.to_string()),
+            f if f == fields.path => path = value.as_str().map(|s| s.to_string()),
+            f if f == fields.ext => ext = value.as_str().map(|s| s.to_string()),
+            f if f == fields.size => size = value.as_u64(),
+            f if f == fields.modified => modified = value.as_i64(),
+            f if f == fields.content_preview => preview = value.as_str(),
             _ => {}
         }
     }
 
-    key.map(|doc_key| SearchHit {
+    let doc_key = key?;
+    let snippets = preview
+        .zip(terms)
+        .map(|(text, terms)| {
+            snippet_cache.get_or_compute(doc_key, terms, snippet_count, || {
+                content_index::snippet::best_snippets(
+                    text,
+                    terms,
+                    &SnippetOptions {
+                        max_fragments: snippet_count,
+                        ..snippet_opts.clone()
+                    },
+                )
+            })
+        })
+        .unwrap_or_default();
+    Some(SearchHit {
         key: doc_key,
         score,
         name,
@@ -540,6 +2248,2116 @@ fn to_hit<D: Document>(doc: &D, fields: &MetaFields, score: Score) -> Option<Sea
         ext,
         size,
         modified,
-        snippet: None,
+        snippet: snippets.first().cloned(),
+        snippets,
+        tags: Vec::new(),
+        extract_status: None,
+        archive: None,
+        matched_former_name: false,
     })
 }
+
+static HANDLER: OnceLock<Box<dyn SearchHandler>> = OnceLock::new();
+
+pub fn set_search_handler(handler: Box<dyn SearchHandler>) {
+    tracing::info!("Global search handler installed.");
+    let _ = HANDLER.set(handler);
+}
+
+pub fn search(req: SearchRequest) -> SearchResponse {
+    tracing::info!("Received search request id={} mode={:?}", req.id, req.mode);
+    if let Some(h) = HANDLER.get() {
+        h.search(req)
+    } else {
+        tracing::warn!("No search handler installed, using StubSearchHandler.");
+        StubSearchHandler.search(req)
+    }
+}
+
+/// Cancel flags of the searches running under a `cancel_token`, set by [`cancel_search`].
+static IN_FLIGHT: LazyLock<Mutex<HashMap<Uuid, Arc<AtomicBool>>>> = LazyLock::new(Default::default);
+
+fn in_flight() -> MutexGuard<'static, HashMap<Uuid, Arc<AtomicBool>>> {
+    IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// One search's cancel flag, listed in [`IN_FLIGHT`] under its token (if it has one) until
+/// dropped.
+struct InFlight {
+    token: Option<Uuid>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl InFlight {
+    fn register(token: Option<Uuid>) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        if let Some(token) = token {
+            in_flight().insert(token, cancel.clone());
+        }
+        Self { token, cancel }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Some(token) = self.token {
+            let mut searches = in_flight();
+            // Leave the entry alone if a newer search reused the token.
+            if searches
+                .get(&token)
+                .is_some_and(|c| Arc::ptr_eq(c, &self.cancel))
+            {
+                searches.remove(&token);
+            }
+        }
+    }
+}
+
+/// Stop the search running under `cancel_token` `token`; false if none is.
+pub fn cancel_search(token: Uuid) -> bool {
+    match in_flight().get(&token) {
+        Some(cancel) => {
+            cancel.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
+
+thread_local! {
+    /// Cancel flag of the search running on this thread; see [`search_cancelled`].
+    static CANCEL: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+/// Makes `cancel` the flag of searches run on this thread until dropped.
+struct CancelScope(Option<Arc<AtomicBool>>);
+
+impl CancelScope {
+    fn enter(cancel: Arc<AtomicBool>) -> Self {
+        Self(CANCEL.replace(Some(cancel)))
+    }
+}
+
+impl Drop for CancelScope {
+    fn drop(&mut self) {
+        CANCEL.set(self.0.take());
+    }
+}
+
+/// Whether the search running on this thread was cancelled. Handlers check it between
+/// stages and inside long scans.
+pub fn search_cancelled() -> bool {
+    CANCEL.with_borrow(|c| c.as_ref().is_some_and(|f| f.load(Ordering::Relaxed)))
+}
+
+/// The empty answer to a cancelled search.
+fn cancelled_response(req: SearchRequest) -> SearchResponse {
+    let mut resp = StubSearchHandler.search(req);
+    resp.served_by = None;
+    resp.truncated = true;
+    resp.cancelled = true;
+    resp
+}
+
+/// Echo the request id and fill in timing and host when the handler left them unset.
+pub(crate) fn finish_search_response(
+    req: &SearchRequest,
+    resp: &mut SearchResponse,
+    start: Instant,
+) {
+    // Ensure the echoed id always matches the request for protocol stability.
+    // search(req) should propagate id, but we enforce it defensively.
+    // Use the id already in resp if set, otherwise fallback to request id.
+    if resp.id.is_nil() {
+        resp.id = req.id;
+    }
+    let took = start.elapsed().as_millis().min(u32::MAX as u128) as u32;
+    if resp.took_ms == 0 {
+        resp.took_ms = took;
+    }
+    if resp.served_by.is_none() {
+        resp.served_by = Some(crate::status::host_label());
+    }
+}
+
+/// Answer a (non-`anytime`) search from a client, the same way on every transport: run it on
+/// `handler`, finish the response, audit it under `client`, and record the latency and (for
+/// `search.prefetch_queries`) the query.
+pub fn answer_search(
+    handler: &dyn SearchHandler,
+    req: SearchRequest,
+    client: Option<&str>,
+) -> SearchResponse {
+    let search = InFlight::register(req.cancel_token);
+    answer_cancellable(handler, req, client, &search.cancel)
+}
+
+fn answer_cancellable(
+    handler: &dyn SearchHandler,
+    req: SearchRequest,
+    client: Option<&str>,
+    cancel: &Arc<AtomicBool>,
+) -> SearchResponse {
+    let start = Instant::now();
+    let audited = req.clone();
+    let mut resp = handler.search_cancellable(req, cancel.clone());
+    finish_search_response(&audited, &mut resp, start);
+    if let Some(freq) = crate::query_prefetch::query_frequency()
+        && let Some(key) = crate::query_prefetch::query_key(&audited)
+    {
+        freq.record(&key);
+    }
+    if let Some(history) = crate::query_history::query_history()
+        && let Some(text) = audited.query_text.as_deref()
+        && resp.error.is_none()
+        && let Err(e) = history.record(text, crate::query_history::unix_now())
+    {
+        tracing::warn!("failed to record query history: {e:#}");
+    }
+    if let Some(log) = crate::audit::audit_log()
+        && let Err(e) = log.record_search(&audited, &resp, client)
+    {
+        tracing::warn!("failed to write search audit record: {e:#}");
+    }
+    crate::metrics::record_ipc_request(start.elapsed());
+    resp
+}
+
+/// Answer a [`SearchStreamRequest`] on `handler`: the first page as [`answer_search`] does,
+/// then each page behind the server's `next_cursor`, handing every page's hits to `emit` in
+/// batches and finishing with an `End` frame. Stops as soon as `emit` returns false; a
+/// cancelled search ends with a `cancelled` response after the pages already sent.
+pub fn stream_search(
+    handler: &dyn SearchHandler,
+    req: SearchStreamRequest,
+    client: Option<&str>,
+    emit: &mut dyn FnMut(SearchStreamFrame) -> bool,
+) {
+    let batch_size = match req.batch_size {
+        0 => SearchStreamRequest::DEFAULT_BATCH_SIZE,
+        n => n,
+    } as usize;
+    let mut page = req.request;
+    page.anytime = false;
+    page.group_by = None;
+    let search = InFlight::register(page.cancel_token);
+    let mut resp = answer_cancellable(handler, page.clone(), client, &search.cancel);
+    let mut seq = 0;
+    loop {
+        let mut hits = std::mem::take(&mut resp.hits).into_iter().peekable();
+        while hits.peek().is_some() {
+            let batch = SearchHitBatch {
+                id: page.id,
+                seq,
+                hits: hits.by_ref().take(batch_size).collect(),
+            };
+            if !emit(SearchStreamFrame::Hits(batch)) {
+                return;
+            }
+            seq += 1;
+        }
+        let Some(cursor) = resp.next_cursor.take() else {
+            break;
+        };
+        page.cursor = Some(cursor);
+        let start = Instant::now();
+        resp = handler.search_cancellable(page.clone(), search.cancel.clone());
+        finish_search_response(&page, &mut resp, start);
+    }
+    emit(SearchStreamFrame::End(resp));
+}
+
+/// The handler installed with [`set_search_handler`], or the stub until there is one.
+#[derive(Debug, Default)]
+pub struct InstalledSearchHandler;
+
+impl SearchHandler for InstalledSearchHandler {
+    fn search(&self, req: SearchRequest) -> SearchResponse {
+        search(req)
+    }
+
+    fn search_anytime(&self, req: SearchRequest, emit: &mut dyn FnMut(SearchResponse) -> bool) {
+        search_anytime(req, emit)
+    }
+}
+
+/// Run an `anytime` request on the installed handler; see [`SearchHandler::search_anytime`].
+pub fn search_anytime(req: SearchRequest, emit: &mut dyn FnMut(SearchResponse) -> bool) {
+    tracing::info!(
+        "Received anytime search request id={} mode={:?}",
+        req.id,
+        req.mode
+    );
+    let search = InFlight::register(req.cancel_token);
+    let _scope = CancelScope::enter(search.cancel.clone());
+    if let Some(h) = HANDLER.get() {
+        h.search_anytime(req, emit)
+    } else {
+        StubSearchHandler.search_anytime(req, emit)
+    }
+}
+
+fn to_hit<D: Document>(doc: &D, fields: &MetaFields, score: Score) -> Option<SearchHit> {
+    let mut key = None;
+    let mut name = None;
+    let mut path = None;
+    let mut ext = None;
+    let mut size = None;
+    let mut modified = None;
+    let mut archive = None;
+
+    for (field, value) in doc.iter_fields_and_values() {
+        match field {
+            f if f == fields.doc_key => {
+                if let Some(v) = value.as_u64() {
+                    key = Some(core_types::DocKey(v));
+                }
+            }
+            f if f == fields.archive => archive = value.as_u64().map(core_types::DocKey),
+            f if f == fields.name => {
+                if let Some(s) = value.as_str() {
+                    name = Some(s.to_string());
+                }
+            }
+            f if f == fields.path => {
+                if let Some(s) = value.as_str() {
+                    path = Some(s.to_string());
+                }
+            }
+            f if f == fields.ext => {
+                if let Some(s) = value.as_str() {
+                    ext = Some(s.to_string());
+                }
+            }
+            f if f == fields.size => {
+                if let Some(v) = value.as_u64() {
+                    size = Some(v);
+                }
+            }
+            f if f == fields.modified => {
+                if let Some(v) = value.as_i64() {
+                    modified = Some(v);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    key.map(|doc_key| SearchHit {
+        key: doc_key,
+        score,
+        name,
+        path,
+        ext,
+        size,
+        modified,
+        snippet: None,
+        snippets: Vec::new(),
+        tags: Vec::new(),
+        extract_status: None,
+        archive,
+        matched_former_name: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use content_index::{ContentDoc, WriterConfig, add_content_doc, create_writer};
+    use core_types::{DocKey, FileFlags, FileMeta};
+    use ipc::ExtractStatus;
+
+    fn content_doc(file_id: u64, modified: i64) -> ContentDoc {
+        let meta = FileMeta::new(
+            DocKey::from_parts(1, file_id),
+            1,
+            None,
+            format!("report{file_id}.txt"),
+            Some(format!(r"C:\docs\report{file_id}.txt")),
+            100,
+            modified - 10,
+            modified,
+            FileFlags::empty(),
+        );
+        ContentDoc::from_meta(&meta, "quarterly revenue summary".into(), None)
+    }
+
+    fn handler_with_docs(docs: &[ContentDoc]) -> (tempfile::TempDir, UnifiedSearchHandler) {
+        handler_with(Vec::new(), docs)
+    }
+
+    fn handler_with(
+        metas: Vec<FileMeta>,
+        docs: &[ContentDoc],
+    ) -> (tempfile::TempDir, UnifiedSearchHandler) {
+        let dir = tempfile::tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        let content_path = dir.path().join("content");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        std::fs::create_dir_all(&content_path).unwrap();
+        crate::meta_ingest::ingest_file_meta_batch(&meta_path, metas, None).unwrap();
+
+        let idx = open_content(&content_path).unwrap();
+        let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+        for doc in docs {
+            add_content_doc(&mut writer, &idx.fields, doc).unwrap();
+        }
+        writer.commit().unwrap();
+        drop(writer);
+
+        let handler = UnifiedSearchHandler::try_new(&meta_path, &content_path).unwrap();
+        (dir, handler)
+    }
+
+    fn stub_hit(file_id: u64, score: f32) -> SearchHit {
+        SearchHit {
+            key: DocKey::from_parts(1, file_id),
+            score,
+            name: Some(format!("file{file_id}.txt")),
+            path: None,
+            ext: None,
+            size: None,
+            modified: None,
+            snippet: None,
+            snippets: Vec::new(),
+            tags: Vec::new(),
+            extract_status: None,
+            archive: None,
+            matched_former_name: false,
+        }
+    }
+
+    #[test]
+    fn top_k_buffer_stays_within_offset_plus_limit() {
+        let (offset, limit) = (5usize, 10usize);
+        // Deterministic pseudo-random scores over a large candidate stream.
+        let candidates =
+            (0..200_000u64).map(|i| stub_hit(i, ((i.wrapping_mul(2_654_435_761)) % 10_007) as f32));
+
+        let mut top = TopKHits::new(offset + limit);
+        top.extend(candidates.clone());
+        assert!(top.peak() <= offset + limit);
+
+        let got: Vec<_> = top
+            .into_sorted_vec()
+            .into_iter()
+            .skip(offset)
+            .map(|h| h.key)
+            .collect();
+
+        let mut all: Vec<_> = candidates.collect();
+        all.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.key.0.cmp(&b.key.0)));
+        let expected: Vec<_> = all
+            .into_iter()
+            .skip(offset)
+            .take(limit)
+            .map(|h| h.key)
+            .collect();
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn top_k_with_zero_capacity_keeps_nothing() {
+        let mut top = TopKHits::new(0);
+        top.extend((0..10).map(|i| stub_hit(i, 1.0)));
+        assert_eq!(top.peak(), 0);
+        assert!(top.into_sorted_vec().is_empty());
+    }
+
+    #[test]
+    fn hybrid_merge_buffer_stays_within_offset_plus_limit() {
+        // 1500 name matches and 1500 content matches, overlapping on 500 files.
+        let metas = (0..1_500)
+            .map(|i| name_meta(i, "quarterly notes.txt"))
+            .collect();
+        let docs: Vec<_> = (1_000..2_500).map(|i| content_doc(i, 1_000)).collect();
+        let (_dir, handler) = handler_with(metas, &docs);
+
+        let mut req = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: None,
+            value: "quarterly".into(),
+            modifier: TermModifier::Term,
+        }))
+        .with_mode(SearchMode::Hybrid);
+        req.offset = 5;
+        req.limit = 10;
+        let resp = handler.search(req);
+
+        assert_eq!(resp.hits.len(), 10);
+        assert_eq!(resp.total, 2_500);
+        assert!(handler.merge_peak.load(Ordering::Relaxed) <= 15);
+    }
+
+    #[test]
+    fn content_query_applies_modified_range_in_index() {
+        let (_dir, handler) = handler_with_docs(&[content_doc(1, 1_000), content_doc(2, 2_000)]);
+        let query = QueryExpr::And(vec![
+            QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Content),
+                value: "quarterly".into(),
+                modifier: TermModifier::Term,
+            }),
+            QueryExpr::Range(RangeExpr {
+                field: FieldKind::Modified,
+                op: RangeOp::Ge,
+                value: RangeValue::I64 {
+                    lo: 1_500,
+                    hi: None,
+                },
+            }),
+        ]);
+        let resp = handler.search(SearchRequest::with_query(query).with_mode(SearchMode::Content));
+
+        assert_eq!(resp.total, 1);
+        assert_eq!(resp.hits.len(), 1);
+        assert_eq!(resp.hits[0].key, DocKey::from_parts(1, 2));
+        assert_eq!(resp.hits[0].modified, Some(2_000));
+        assert_eq!(
+            resp.hits[0].snippet.as_deref(),
+            Some("quarterly revenue summary")
+        );
+    }
+
+    #[test]
+    fn near_matches_terms_within_slop_in_either_order() {
+        let doc = |file_id: u64, text: &str| {
+            let meta = FileMeta::new(
+                DocKey::from_parts(1, file_id),
+                1,
+                None,
+                format!("doc{file_id}.txt"),
+                Some(format!(r"C:\docs\doc{file_id}.txt")),
+                100,
+                0,
+                0,
+                FileFlags::empty(),
+            );
+            ContentDoc::from_meta(&meta, text.into(), None)
+        };
+        let (_dir, handler) = handler_with_docs(&[
+            doc(1, "the quarterly report shows revenue growth"),
+            doc(
+                2,
+                "revenue fell sharply this year and the board met to review the quarterly plan",
+            ),
+        ]);
+        let near = |terms: [&str; 2], slop: u32, mode: SearchMode| {
+            handler.search(
+                SearchRequest::with_query(QueryExpr::Near {
+                    terms: terms.iter().map(|t| t.to_string()).collect(),
+                    slop,
+                })
+                .with_mode(mode),
+            )
+        };
+        let keys = |resp: SearchResponse| {
+            assert_eq!(resp.error, None);
+            let mut keys: Vec<u64> = resp.hits.iter().map(|h| h.key.file_id()).collect();
+            keys.sort();
+            keys
+        };
+
+        assert_eq!(
+            keys(near(["quarterly", "revenue"], 2, SearchMode::Content)),
+            vec![1]
+        );
+        assert_eq!(
+            keys(near(["revenue", "quarterly"], 2, SearchMode::Content)),
+            vec![1]
+        );
+        assert!(keys(near(["quarterly", "revenue"], 1, SearchMode::Content)).is_empty());
+        assert_eq!(
+            keys(near(["Quarterly", "Revenue"], 20, SearchMode::Hybrid)),
+            vec![1, 2]
+        );
+
+        let rejected = near(["quarterly", "revenue"], 2, SearchMode::NameOnly);
+        assert!(rejected.hits.is_empty());
+        assert!(rejected.error.unwrap().contains("NameOnly"));
+    }
+
+    #[test]
+    fn mime_query_matches_renamed_pdf_that_ext_misses() {
+        let mut renamed = content_doc(1, 1_000);
+        renamed.name = Some("report1.dat".into());
+        renamed.ext = Some("dat".into());
+        renamed.mime = Some("application/pdf".into());
+        let (_dir, handler) = handler_with_docs(&[renamed, content_doc(2, 2_000)]);
+        let request = |field, value: &str| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(field),
+                value: value.into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::Content)
+        };
+
+        let by_mime = handler.search(request(FieldKind::Mime, "Application/PDF"));
+        let keys: Vec<_> = by_mime.hits.iter().map(|h| h.key).collect();
+        assert_eq!(keys, vec![DocKey::from_parts(1, 1)]);
+        assert!(
+            handler
+                .search(request(FieldKind::Ext, "pdf"))
+                .hits
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn email_header_fields_are_searchable() {
+        let mut invoice = content_doc(1, 1_000);
+        invoice.content = "Invoice 4711\n\nPlease find the quarterly invoice attached.".into();
+        invoice.email = Some(core_types::EmailHeaders {
+            subject: vec!["Invoice 4711".into()],
+            from: vec!["\"Alice Example\" <alice@example.com>".into()],
+            to: vec!["bob@example.com".into()],
+            sent: vec![1_685_604_600],
+        });
+        let (_dir, handler) = handler_with_docs(&[invoice, content_doc(2, 2_000)]);
+        let term = |field, value: &str| {
+            QueryExpr::Term(TermExpr {
+                field: Some(field),
+                value: value.into(),
+                modifier: TermModifier::Term,
+            })
+        };
+        let keys = |query| {
+            let resp =
+                handler.search(SearchRequest::with_query(query).with_mode(SearchMode::Content));
+            resp.hits.iter().map(|h| h.key).collect::<Vec<_>>()
+        };
+        let invoice_key = vec![DocKey::from_parts(1, 1)];
+
+        assert_eq!(
+            keys(QueryExpr::And(vec![
+                term(FieldKind::From, "alice"),
+                term(FieldKind::Subject, "invoice"),
+            ])),
+            invoice_key
+        );
+        assert_eq!(
+            keys(term(FieldKind::From, "alice@example.com")),
+            invoice_key
+        );
+        assert_eq!(keys(term(FieldKind::Content, "attached")), invoice_key);
+        assert!(keys(term(FieldKind::To, "alice")).is_empty());
+        assert_eq!(keys(term(FieldKind::Sent, "2023-06-01")), invoice_key);
+    }
+
+    #[test]
+    fn title_query_matches_the_document_title_not_the_filename() {
+        let mut report = content_doc(1, 1_000);
+        report.name = Some("doc1.pdf".into());
+        report.title = Some("Annual Report".into());
+        let (_dir, handler) = handler_with_docs(&[report, content_doc(2, 2_000)]);
+        let keys = |field| {
+            let resp = handler.search(
+                SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                    field: Some(field),
+                    value: "Annual Report".into(),
+                    modifier: TermModifier::Term,
+                }))
+                .with_mode(SearchMode::Content),
+            );
+            resp.hits.iter().map(|h| h.key).collect::<Vec<_>>()
+        };
+
+        assert_eq!(keys(FieldKind::Title), vec![DocKey::from_parts(1, 1)]);
+        assert!(keys(FieldKind::Name).is_empty());
+    }
+
+    #[test]
+    fn broad_content_query_is_approximate_past_the_scoring_cap() {
+        let docs: Vec<ContentDoc> = (1..=20).map(|i| content_doc(i, 1_000)).collect();
+        let (_dir, handler) = handler_with_docs(&docs);
+        let request = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Content),
+            value: "revenue".into(),
+            modifier: TermModifier::Term,
+        }))
+        .with_mode(SearchMode::Content)
+        .with_limit(5);
+
+        let exact = handler.search(request.clone());
+        assert!(!exact.approximate);
+        assert_eq!((exact.hits.len(), exact.total), (5, 20));
+
+        let handler = handler.with_content_score_cap(8);
+        let capped = handler.search(request.clone());
+        assert!(capped.approximate);
+        assert_eq!(capped.hits.len(), 5);
+        assert_eq!(capped.total, 8, "only scored documents are counted");
+
+        let roomy = handler.with_content_score_cap(20);
+        assert!(!roomy.search(request).approximate, "cap not exceeded");
+    }
+
+    #[test]
+    fn identical_bodies_are_stored_once_but_every_file_is_a_hit() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        let content_path = dir.path().join("content");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        std::fs::create_dir_all(&content_path).unwrap();
+        crate::meta_ingest::ingest_file_meta_batch(&meta_path, Vec::new(), None).unwrap();
+        let idx = open_content(&content_path).unwrap();
+        let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+        let mut dedup = content_index::dedup::BodyDedup::new(&idx).unwrap();
+        let mut docs: Vec<ContentDoc> = (1..=3).map(|i| content_doc(i, 1_000)).collect();
+        docs[1].content = "Quarterly  REVENUE summary".into();
+        docs[2].content = "annual headcount plan".into();
+        for doc in &docs {
+            content_index::dedup::add_content_doc_dedup(&mut writer, &idx.fields, doc, &mut dedup)
+                .unwrap();
+        }
+        writer.commit().unwrap();
+        drop(writer);
+        let handler = UnifiedSearchHandler::try_new(&meta_path, &content_path).unwrap();
+
+        let stored = content_index::open_reader(&idx)
+            .unwrap()
+            .searcher()
+            .search(
+                &TermQuery::new(
+                    Term::from_field_text(idx.fields.content, "revenue"),
+                    IndexRecordOption::Basic,
+                ),
+                &Count,
+            )
+            .unwrap();
+        assert_eq!(stored, 1, "the shared body is indexed once");
+
+        let resp = handler.search(
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Content),
+                value: "revenue".into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::Content),
+        );
+        let keys: Vec<DocKey> = resp.hits.iter().map(|h| h.key).collect();
+        assert_eq!(
+            keys,
+            vec![DocKey::from_parts(1, 1), DocKey::from_parts(1, 2)]
+        );
+        assert_eq!(resp.total, 2);
+        assert_eq!(resp.hits[0].score, resp.hits[1].score);
+        assert!(resp.hits[1].snippet.as_deref().unwrap().contains("revenue"));
+    }
+
+    #[test]
+    fn references_match_metadata_clauses_on_their_own_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta_path = dir.path().join("meta");
+        let content_path = dir.path().join("content");
+        std::fs::create_dir_all(&meta_path).unwrap();
+        std::fs::create_dir_all(&content_path).unwrap();
+        crate::meta_ingest::ingest_file_meta_batch(&meta_path, Vec::new(), None).unwrap();
+        let idx = open_content(&content_path).unwrap();
+        let mut writer = create_writer(&idx, &WriterConfig::default()).unwrap();
+        let mut dedup = content_index::dedup::BodyDedup::new(&idx).unwrap();
+        // LICENSE.md stores the body; LICENSE.txt references it.
+        let (md, txt) = (DocKey::from_parts(1, 1), DocKey::from_parts(1, 2));
+        for (i, ext) in [(1, "md"), (2, "txt")] {
+            let mut doc = content_doc(i, 1_000);
+            doc.content = "MIT license, permission is granted".into();
+            doc.name = Some(format!("LICENSE.{ext}"));
+            doc.ext = Some(ext.into());
+            content_index::dedup::add_content_doc_dedup(&mut writer, &idx.fields, &doc, &mut dedup)
+                .unwrap();
+        }
+        writer.commit().unwrap();
+        drop(writer);
+        let handler = UnifiedSearchHandler::try_new(&meta_path, &content_path).unwrap();
+
+        let term = |field, value: &str| {
+            QueryExpr::Term(TermExpr {
+                field,
+                value: value.into(),
+                modifier: TermModifier::Term,
+            })
+        };
+        let keys = |query: QueryExpr, refine: Option<Vec<DocKey>>| {
+            let mut req = SearchRequest::with_query(query).with_mode(SearchMode::Content);
+            req.refine = refine;
+            let resp = handler.search(req);
+            assert_eq!(resp.total as usize, resp.hits.len());
+            resp.hits.iter().map(|h| h.key).collect::<Vec<_>>()
+        };
+        let with_ext = |ext| {
+            let body = term(Some(FieldKind::Content), "permission");
+            QueryExpr::And(vec![body, term(Some(FieldKind::Ext), ext)])
+        };
+
+        assert_eq!(keys(with_ext("txt"), None), vec![txt]);
+        assert_eq!(keys(with_ext("md"), None), vec![md]);
+        let license = term(None, "license");
+        assert_eq!(keys(license.clone(), None), vec![md, txt]);
+        assert_eq!(keys(license.clone(), Some(vec![txt])), vec![txt]);
+        // Only a page's worth of references is collected, but all are counted.
+        let resp = handler.search(
+            SearchRequest::with_query(license)
+                .with_mode(SearchMode::Content)
+                .with_limit(1)
+                .with_offset(1),
+        );
+        assert_eq!(resp.total, 2);
+        assert_eq!(
+            resp.hits.iter().map(|h| h.key).collect::<Vec<_>>(),
+            vec![txt]
+        );
+    }
+
+    #[test]
+    fn bare_dates_cover_the_configured_zones_day() {
+        // 2023-05-31T15:00Z is June 1st in +10:00; 2023-06-01T15:00Z is June 1st in UTC.
+        let docs = [content_doc(1, 1_685_545_200), content_doc(2, 1_685_631_600)];
+        let (_dir, handler) = handler_with_docs(&docs);
+        let on_june_1st = |date: TermExpr| {
+            SearchRequest::with_query(QueryExpr::And(vec![
+                QueryExpr::Term(TermExpr {
+                    field: Some(FieldKind::Content),
+                    value: "revenue".into(),
+                    modifier: TermModifier::Term,
+                }),
+                QueryExpr::Term(date),
+            ]))
+            .with_mode(SearchMode::Content)
+        };
+        let fielded = TermExpr {
+            field: Some(FieldKind::Modified),
+            value: "2023-06-01".into(),
+            modifier: TermModifier::Term,
+        };
+        let typed = TermExpr {
+            field: None,
+            value: "modified:2023-06-01".into(),
+            modifier: TermModifier::Term,
+        };
+        let keys = |handler: &UnifiedSearchHandler, date: &TermExpr| -> Vec<DocKey> {
+            let resp = handler.search(on_june_1st(date.clone()));
+            resp.hits.iter().map(|h| h.key).collect()
+        };
+
+        let handler = handler.with_date_zone(DateZone::parse("+10:00").unwrap());
+        assert_eq!(keys(&handler, &fielded), vec![DocKey::from_parts(1, 1)]);
+        assert_eq!(keys(&handler, &typed), vec![DocKey::from_parts(1, 1)]);
+
+        let handler = handler.with_date_zone(DateZone::parse("UTC").unwrap());
+        assert_eq!(keys(&handler, &fielded), vec![DocKey::from_parts(1, 2)]);
+    }
+
+    #[test]
+    fn relative_ages_become_ranges_from_now() {
+        let utc = DateZone::parse("UTC").unwrap();
+        let now = 1_686_823_200;
+        let typed = |value: &str| {
+            QueryExpr::Term(TermExpr {
+                field: None,
+                value: value.into(),
+                modifier: TermModifier::Term,
+            })
+        };
+        let range = |lo, hi| {
+            QueryExpr::Range(RangeExpr {
+                field: FieldKind::Modified,
+                op: RangeOp::Between,
+                value: RangeValue::I64 { lo, hi },
+            })
+        };
+
+        assert_eq!(
+            localize_dates(&typed("modified:today"), utc, now),
+            range(1_686_787_200, Some(1_686_873_599))
+        );
+        assert_eq!(
+            localize_dates(&typed("modified:>30d"), utc, now),
+            range(i64::MIN, Some(now - 30 * 86_400 - 1))
+        );
+        assert_eq!(
+            localize_dates(&typed("modified:soon"), utc, now),
+            typed("modified:soon")
+        );
+    }
+
+    #[test]
+    fn repeated_searches_reuse_cached_snippets() {
+        let (_dir, handler) = handler_with_docs(&[content_doc(1, 1_000), content_doc(2, 2_000)]);
+        let handler = handler.with_snippet_cache(
+            SnippetCache::new(std::time::Duration::from_secs(60), 16).with_epoch_source(|| 0),
+        );
+        let request = |limit| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Content),
+                value: "revenue".into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::Content)
+            .with_limit(limit)
+        };
+
+        let first = handler.search(request(1));
+        assert_eq!(handler.snippet_cache.generation(), 1);
+
+        // Same query, larger page: only the newly visible hit renders a snippet.
+        let second = handler.search(request(2));
+        assert_eq!(second.hits.len(), 2);
+        assert_eq!(handler.snippet_cache.generation(), 2);
+        assert_eq!(first.hits[0].snippet, second.hits[0].snippet);
+
+        handler.search(request(2));
+        assert_eq!(handler.snippet_cache.generation(), 2);
+    }
+
+    #[test]
+    fn snippets_per_hit_returns_best_fragments_first() {
+        let filler = "Nothing of note happens in this part of the report. ".repeat(5);
+        let text = [
+            "Alpha revenue rose.",
+            "Beta revenue and more revenue grew.",
+            "Gamma revenue held.",
+            "Delta revenue fell.",
+        ]
+        .join(&format!(" {filler}"));
+        let mut doc = content_doc(1, 1_000);
+        doc.content = text;
+        let (_dir, handler) = handler_with_docs(&[doc]);
+        let request = |n| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Content),
+                value: "revenue".into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::Content)
+            .with_snippets_per_hit(n)
+        };
+
+        let hit = &handler.search(request(3)).hits[0];
+        let leads: Vec<&str> = hit
+            .snippets
+            .iter()
+            .map(|s| s.split_whitespace().next().unwrap())
+            .collect();
+        assert_eq!(leads, vec!["Beta", "Alpha", "Gamma"]);
+        assert_eq!(hit.snippet.as_ref(), hit.snippets.first());
+
+        let hit = &handler.search(request(1)).hits[0];
+        assert_eq!(hit.snippets.len(), 1);
+        assert!(hit.snippet.as_deref().unwrap().starts_with("Beta revenue"));
+    }
+
+    #[test]
+    fn projection_leaves_unrequested_fields_unset() {
+        let (_dir, handler) = handler_with_docs(&[content_doc(1, 1_000)]);
+        let query = QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Content),
+            value: "revenue".into(),
+            modifier: TermModifier::Term,
+        });
+
+        let full =
+            handler.search(SearchRequest::with_query(query.clone()).with_mode(SearchMode::Content));
+        assert!(full.hits[0].snippet.is_some());
+        assert!(full.hits[0].modified.is_some());
+
+        let projected = handler.search(
+            SearchRequest::with_query(query)
+                .with_mode(SearchMode::Content)
+                .with_fields([HitField::Name, HitField::Path]),
+        );
+        let hit = &projected.hits[0];
+        assert_eq!(hit.key, full.hits[0].key);
+        assert_eq!(hit.name, full.hits[0].name);
+        assert_eq!(hit.path, full.hits[0].path);
+        assert!(hit.name.is_some() && hit.path.is_some());
+        assert_eq!(
+            (hit.size, hit.modified, hit.ext.as_deref()),
+            (None, None, None)
+        );
+        assert!(hit.snippet.is_none());
+    }
+
+    #[test]
+    fn trimmed_doc_is_rehydrated_once_and_returned_without_snippet() {
+        // Doc 1 has a body in the content index; the others only exist in the metadata index.
+        // Doc 2's body was trimmed, doc 3 was never extracted, doc 4 was trimmed and then
+        // skipped by the worker, and doc 5 is a trimmed directory entry.
+        let mut dir_meta = name_meta(5, "report5.txt");
+        dir_meta.flags = FileFlags::IS_DIR;
+        let (dir, handler) = handler_with(
+            vec![
+                name_meta(1, "report1.txt"),
+                name_meta(2, "report2.txt"),
+                name_meta(3, "report3.txt"),
+                name_meta(4, "report4.txt"),
+                dir_meta,
+            ],
+            &[content_doc(1, 1_000)],
+        );
+        let key = |file_id| DocKey::from_parts(1, file_id);
+        crate::index_budget::record_trimmed(&[key(2), key(4), key(5)]);
+        let dead_letters = dir.path().join(crate::extract_status::DEAD_LETTER_FILE);
+        std::fs::write(
+            &dead_letters,
+            r#"{"volume_id":1,"file_id":4,"path":"C:/docs/report4.txt","reason":"too large","ts":5,"skipped":true}"#,
+        )
+        .unwrap();
+        let enqueued = Arc::new(Mutex::new(Vec::new()));
+        let sink = enqueued.clone();
+        let handler = handler
+            .with_dead_letters(dead_letters)
+            .with_rehydration(Arc::new(move |hit: &SearchHit, flags: FileFlags| {
+                sink.lock().unwrap().push((hit.key, flags));
+                true
+            }));
+        let request = || {
+            SearchRequest::with_query(QueryExpr::Or(
+                (1..=5)
+                    .map(|i| {
+                        QueryExpr::Term(TermExpr {
+                            field: Some(FieldKind::Name),
+                            value: format!("report{i}.txt"),
+                            modifier: TermModifier::Term,
+                        })
+                    })
+                    .collect(),
+            ))
+            .with_mode(SearchMode::Hybrid)
+        };
+
+        let resp = handler.search(request());
+        let hit = resp.hits.iter().find(|h| h.key == key(2)).unwrap();
+        assert!(hit.snippet.is_none());
+        assert_eq!(resp.hits.len(), 5);
+        let mut calls = enqueued.lock().unwrap().clone();
+        calls.sort_by_key(|(k, _)| k.0);
+        // The hook gets the indexed flags, so it can refuse the directory.
+        assert_eq!(
+            calls,
+            vec![(key(2), FileFlags::empty()), (key(5), FileFlags::IS_DIR)]
+        );
+
+        // Still pending: a repeat search doesn't enqueue again.
+        handler.search(request());
+        assert_eq!(enqueued.lock().unwrap().len(), 2);
+
+        // A request that has been pending too long is dropped and may be made again.
+        for at in handler.rehydrate_pending.lock().unwrap().values_mut() {
+            *at -= REHYDRATE_RETRY_AFTER;
+        }
+        handler.search(request());
+        assert_eq!(enqueued.lock().unwrap().len(), 4);
+        for file_id in [2, 4, 5] {
+            crate::index_budget::forget_trimmed(key(file_id));
+        }
+    }
+
+    #[test]
+    fn hits_report_recorded_extraction_failures() {
+        let (dir, handler) = handler_with(
+            vec![
+                name_meta(1, "report1.txt"),
+                name_meta(2, "report2.pdf"),
+                name_meta(3, "report3.docx"),
+            ],
+            &[content_doc(1, 1_000)],
+        );
+        let dead_letters = dir.path().join(crate::extract_status::DEAD_LETTER_FILE);
+        std::fs::write(
+            &dead_letters,
+            r#"{"volume_id":1,"file_id":2,"path":"C:/docs/report2.pdf","reason":"extraction failed: bad xref table","ts":5}"#,
+        )
+        .unwrap();
+        let handler = handler.with_dead_letters(dead_letters);
+        let name = |value: &str| {
+            QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: value.into(),
+                modifier: TermModifier::Term,
+            })
+        };
+        let request = SearchRequest::with_query(QueryExpr::Or(vec![
+            name("report1.txt"),
+            name("report2.pdf"),
+            name("report3.docx"),
+        ]))
+        .with_mode(SearchMode::NameOnly);
+
+        let resp = handler.search(request.clone().with_extract_status());
+        let status = |file_id| {
+            let key = DocKey::from_parts(1, file_id);
+            let hit = resp.hits.iter().find(|h| h.key == key).unwrap();
+            hit.extract_status.clone()
+        };
+        assert_eq!(status(1), Some(ExtractStatus::Ok));
+        assert_eq!(
+            status(2),
+            Some(ExtractStatus::Failed(
+                "extraction failed: bad xref table".into()
+            ))
+        );
+        assert_eq!(status(3), Some(ExtractStatus::Pending));
+
+        // Not asked for: left empty.
+        let plain = handler.search(request);
+        assert!(plain.hits.iter().all(|h| h.extract_status.is_none()));
+    }
+
+    fn name_meta(file_id: u64, name: &str) -> FileMeta {
+        FileMeta::new(
+            DocKey::from_parts(1, file_id),
+            1,
+            None,
+            name.into(),
+            Some(format!(r"C:\docs\{name}")),
+            1,
+            0,
+            0,
+            FileFlags::empty(),
+        )
+    }
+
+    fn fuzzy_name_request(value: &str, distance: u8) -> SearchRequest {
+        SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Name),
+            value: value.into(),
+            modifier: TermModifier::Fuzzy(distance),
+        }))
+        .with_mode(SearchMode::NameOnly)
+    }
+
+    #[test]
+    fn phonetic_modifier_matches_sound_alike_names() {
+        let (_dir, handler) = handler_with(
+            vec![
+                name_meta(1, "Stephan Resume.pdf"),
+                name_meta(2, "Simon Resume.pdf"),
+            ],
+            &[],
+        );
+        let request = |modifier| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: None,
+                value: "Stefan".into(),
+                modifier,
+            }))
+            .with_mode(SearchMode::NameOnly)
+        };
+
+        let phonetic = handler.search(request(TermModifier::Phonetic));
+        let keys: Vec<_> = phonetic.hits.iter().map(|h| h.key).collect();
+        assert_eq!(keys, vec![DocKey::from_parts(1, 1)]);
+        assert!(handler.search(request(TermModifier::Term)).hits.is_empty());
+    }
+
+    #[test]
+    fn glob_modifier_matches_whole_names() {
+        let names = [
+            "main.rs",
+            "Report_01.PDF",
+            "report_123.pdf",
+            "notes.rs.bak",
+            "a*b.txt",
+            "axb.txt",
+            "budget.xlsx",
+        ];
+        let metas = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| name_meta(i as u64 + 1, name))
+            .collect();
+        let (_dir, handler) = handler_with(metas, &[]);
+        let glob = |value: &str| {
+            let resp = handler.search(
+                SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                    field: None,
+                    value: value.into(),
+                    modifier: TermModifier::Glob,
+                }))
+                .with_mode(SearchMode::NameOnly),
+            );
+            let mut hits: Vec<String> = resp.hits.into_iter().filter_map(|h| h.name).collect();
+            hits.sort();
+            hits
+        };
+
+        // Anchored to the whole name: `notes.rs.bak` doesn't end in `.rs`.
+        assert_eq!(glob("*.rs"), vec!["main.rs"]);
+        assert_eq!(glob("report_??.pdf"), vec!["Report_01.PDF"]);
+        assert_eq!(glob("[a-c]*.TXT"), vec!["a*b.txt", "axb.txt"]);
+        assert_eq!(
+            glob("[!a-c]*"),
+            vec!["Report_01.PDF", "main.rs", "notes.rs.bak", "report_123.pdf"]
+        );
+        assert_eq!(glob(r"a\*b.txt"), vec!["a*b.txt"]);
+        assert_eq!(glob("a?b.txt"), vec!["a*b.txt", "axb.txt"]);
+        assert_eq!(glob("report_1[0-9][0-9].pdf"), vec!["report_123.pdf"]);
+    }
+
+    #[test]
+    fn regex_modifier_filters_names_and_reports_bad_patterns() {
+        let names = [
+            "report_01.pdf",
+            "Report_2024.PDF",
+            "old_report_01.pdf",
+            "notes.txt",
+        ];
+        let metas = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| name_meta(i as u64 + 1, name))
+            .collect();
+        let (_dir, handler) = handler_with(metas, &[]);
+        let regex = |value: &str| {
+            handler.search(
+                SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                    field: None,
+                    value: value.into(),
+                    modifier: TermModifier::Regex,
+                }))
+                .with_mode(SearchMode::NameOnly),
+            )
+        };
+        let names = |resp: SearchResponse| {
+            assert_eq!(resp.error, None);
+            let mut hits: Vec<String> = resp.hits.into_iter().filter_map(|h| h.name).collect();
+            hits.sort();
+            hits
+        };
+
+        assert_eq!(
+            names(regex(r"^report_\d+\.pdf$")),
+            vec!["Report_2024.PDF", "report_01.pdf"]
+        );
+        assert_eq!(
+            names(regex(r"report_\d{2}\.")),
+            vec!["old_report_01.pdf", "report_01.pdf"]
+        );
+        assert!(names(regex("^zzz")).is_empty());
+
+        let bad = regex("report_(");
+        assert!(bad.hits.is_empty());
+        assert!(bad.error.unwrap().contains("report_("));
+
+        assert_eq!(regex_literal_prefix(r"^Report_\d"), Some("report_".into()));
+        assert_eq!(regex_literal_prefix("^ab*c"), Some("a".into()));
+        assert_eq!(regex_literal_prefix("^a|b"), None);
+        assert_eq!(regex_literal_prefix("report"), None);
+
+        let scan = RegexScan::new(1);
+        handler
+            .regex_name_query(handler.meta.fields.name_lower, "report", &scan)
+            .unwrap();
+        assert!(scan.capped.get());
+    }
+
+    #[test]
+    fn names_match_case_insensitively_but_display_as_written() {
+        let (_dir, handler) = handler_with(vec![name_meta(1, "README.md")], &[]);
+        let resp = handler.search(
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: "readme".into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::NameOnly),
+        );
+        assert_eq!(resp.hits.len(), 1);
+        assert_eq!(resp.hits[0].name.as_deref(), Some("README.md"));
+        assert_eq!(resp.hits[0].path.as_deref(), Some(r"C:\docs\README.md"));
+    }
+
+    #[test]
+    fn pages_are_disjoint_and_contiguous_and_total_counts_every_match() {
+        // Twelve equally scored names, plus content matches for one of them and two others.
+        let metas: Vec<_> = (1..=12)
+            .rev()
+            .map(|id| name_meta(id, "report.txt"))
+            .collect();
+        let docs: Vec<_> = [3, 20, 21]
+            .into_iter()
+            .map(|id| {
+                let meta = name_meta(id, &format!("notes{id}.txt"));
+                ContentDoc::from_meta(&meta, "annual report draft".into(), None)
+            })
+            .collect();
+        let (_dir, handler) = handler_with(metas, &docs);
+        let request = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: None,
+            value: "report".into(),
+            modifier: TermModifier::Term,
+        }))
+        .with_mode(SearchMode::Hybrid);
+        let page = |offset: u32, limit: u32| {
+            let mut req = request.clone().with_limit(limit);
+            req.offset = offset;
+            let resp = handler.search(req);
+            let keys: Vec<DocKey> = resp.hits.iter().map(|h| h.key).collect();
+            (keys, resp.total)
+        };
+
+        let (first, first_total) = page(0, 5);
+        let (second, second_total) = page(5, 5);
+        let (both, _) = page(0, 10);
+        assert_eq!(first.len(), 5);
+        assert_eq!(second.len(), 5);
+        assert!(first.iter().all(|k| !second.contains(k)));
+        assert_eq!([first, second].concat(), both);
+        // 12 names and 3 content matches, one of them the same file.
+        assert_eq!((first_total, second_total), (14, 14));
+    }
+
+    #[test]
+    fn result_set_id_resolves_every_matched_key_for_export_and_refine() {
+        let mut metas: Vec<_> = (1..=12).map(|id| name_meta(id, "report.txt")).collect();
+        metas.push(FileMeta::new(
+            DocKey::from_parts(1, 30),
+            1,
+            None,
+            "report".into(),
+            Some(r"C:\docs\report".into()),
+            0,
+            0,
+            0,
+            FileFlags::IS_DIR,
+        ));
+        let docs: Vec<_> = [3, 20]
+            .into_iter()
+            .map(|id| {
+                let meta = name_meta(id, &format!("notes{id}.txt"));
+                ContentDoc::from_meta(&meta, "annual budget".into(), None)
+            })
+            .collect();
+        let (_dir, handler) = handler_with(metas, &docs);
+        let cache = Arc::new(ResultSetCache::new(std::time::Duration::from_secs(60), 100));
+        let handler = handler.with_result_sets(cache.clone());
+        let term = |value: &str| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: None,
+                value: value.into(),
+                modifier: TermModifier::Term,
+            }))
+        };
+
+        let resp = handler.search(
+            term("report")
+                .with_mode(SearchMode::NameOnly)
+                .with_limit(3)
+                .with_result_set(),
+        );
+        assert_eq!(resp.hits.len(), 3);
+        let set = resp.result_set_id.expect("result set id");
+        let export = crate::result_sets::export_results(
+            Some(&cache),
+            &ipc::ExportResultsRequest {
+                id: uuid::Uuid::new_v4(),
+                result_set_id: set,
+                offset: 0,
+                limit: 100,
+            },
+        );
+        let mut expected: Vec<_> = (1..=12).map(|id| DocKey::from_parts(1, id)).collect();
+        expected.push(DocKey::from_parts(1, 30));
+        assert!(export.found);
+        assert_eq!((export.keys, export.total), (expected, 13));
+
+        // Content matches outside the set (doc 20) are left out.
+        let refined = handler.search(
+            term("budget")
+                .with_mode(SearchMode::Content)
+                .with_refine_set(set),
+        );
+        let keys: Vec<DocKey> = refined.hits.iter().map(|h| h.key).collect();
+        assert_eq!(keys, vec![DocKey::from_parts(1, 3)]);
+        assert!(refined.result_set_id.is_none());
+
+        let unknown = handler.search(term("budget").with_refine_set(uuid::Uuid::new_v4()));
+        assert!(unknown.hits.is_empty());
+        assert!(
+            unknown
+                .error
+                .unwrap()
+                .contains("unknown or expired result set")
+        );
+    }
+
+    #[test]
+    fn deterministic_order_breaks_score_ties_by_key_regardless_of_layout() {
+        // Twenty equally scored names, indexed in opposite orders.
+        let metas: Vec<_> = (1..=20).map(|id| name_meta(id, "report.txt")).collect();
+        let (_a, forward) = handler_with(metas.clone(), &[]);
+        let (_b, backward) = handler_with(metas.into_iter().rev().collect(), &[]);
+        let request = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Name),
+            value: "report".into(),
+            modifier: TermModifier::Term,
+        }))
+        .with_mode(SearchMode::NameOnly)
+        .with_limit(3);
+        let keys = |handler: &UnifiedSearchHandler, req: &SearchRequest| -> Vec<DocKey> {
+            handler
+                .search(req.clone())
+                .hits
+                .iter()
+                .map(|h| h.key)
+                .collect()
+        };
+
+        // Ties go by key whether or not the request asks for deterministic order.
+        let expected: Vec<_> = (1..=3).map(|id| DocKey::from_parts(1, id)).collect();
+        assert_eq!(keys(&forward, &request), expected);
+        assert_eq!(keys(&backward, &request), expected);
+
+        let request = request.with_deterministic_order();
+        assert_eq!(keys(&forward, &request), expected);
+        assert_eq!(keys(&backward, &request), expected);
+        assert_eq!(keys(&forward, &request), keys(&forward, &request));
+    }
+
+    #[test]
+    fn explain_reports_the_trailing_prefix_upgrade() {
+        let (_dir, handler) = handler_with(
+            vec![
+                name_meta(1, "quarterly_report_draft.txt"),
+                name_meta(2, "quarterly_summary.txt"),
+            ],
+            &[],
+        );
+        let handler = handler.with_trailing_prefix(true);
+        let term = |value: &str, modifier| {
+            QueryExpr::Term(TermExpr {
+                field: None,
+                value: value.into(),
+                modifier,
+            })
+        };
+        let typed = SearchRequest::with_query(QueryExpr::And(vec![
+            term("quarterly", TermModifier::Term),
+            QueryExpr::And(vec![term("rep", TermModifier::Term)]),
+        ]))
+        .with_mode(SearchMode::NameOnly);
+
+        let resp = handler.search(typed.clone().with_explain());
+        let keys: Vec<_> = resp.hits.iter().map(|h| h.key).collect();
+        assert_eq!(keys, vec![DocKey::from_parts(1, 1)]);
+        assert_eq!(
+            resp.executed_query,
+            Some(QueryExpr::And(vec![
+                term("quarterly", TermModifier::Term),
+                term("rep", TermModifier::Prefix),
+            ]))
+        );
+        assert_eq!(handler.search(typed).executed_query, None);
+    }
+
+    #[test]
+    fn name_match_ranking_puts_exact_names_first() {
+        let (_dir, handler) = handler_with(
+            vec![
+                name_meta(1, "quarterly_report_draft.txt"),
+                name_meta(2, "report.txt"),
+                name_meta(3, "unrelated.txt"),
+            ],
+            &[],
+        );
+        let handler = handler.with_name_match_ranking(true);
+        let resp = handler.search(
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: None,
+                value: "report".into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::NameOnly),
+        );
+        let names: Vec<_> = resp.hits.iter().filter_map(|h| h.name.as_deref()).collect();
+        assert_eq!(names, vec!["report.txt", "quarterly_report_draft.txt"]);
+        assert!(resp.hits[0].score > resp.hits[1].score);
+    }
+
+    #[test]
+    fn name_sort_uses_collation_not_bytes() {
+        let (_dir, handler) = handler_with(
+            vec![
+                name_meta(1, "Zebra.txt"),
+                name_meta(2, "apple.txt"),
+                name_meta(3, "Äpfel.txt"),
+            ],
+            &[],
+        );
+        let request = |descending| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Ext),
+                value: "txt".into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::NameOnly)
+            .with_sort(ipc::SortField::Name, descending)
+        };
+        let names = |resp: SearchResponse| -> Vec<String> {
+            resp.hits.into_iter().filter_map(|h| h.name).collect()
+        };
+
+        assert_eq!(
+            names(handler.search(request(false))),
+            vec!["Äpfel.txt", "apple.txt", "Zebra.txt"]
+        );
+        assert_eq!(
+            names(handler.search(request(true).with_offset(1).with_limit(1))),
+            vec!["apple.txt"]
+        );
+
+        let swedish = handler.with_sort_locale("sv");
+        assert_eq!(
+            names(swedish.search(request(false))),
+            vec!["apple.txt", "Zebra.txt", "Äpfel.txt"]
+        );
+    }
+
+    #[test]
+    fn large_results_are_paged_with_a_cursor() {
+        let metas = (1..=30)
+            .map(|i| name_meta(i, &format!("report-{i}.txt")))
+            .collect();
+        let (_dir, handler) = handler_with(metas, &[]);
+        let handler = handler.with_streaming(10, 4);
+        let request = |limit| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: "report".into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::NameOnly)
+            .with_limit(limit)
+        };
+
+        // At or under the threshold: everything inline.
+        let small = handler.search(request(10));
+        assert_eq!(small.hits.len(), 10);
+        assert!(small.next_cursor.is_none());
+
+        // Over it: pages of 4 until what remains fits under the threshold.
+        let mut resp = handler.search(request(25));
+        assert_eq!(resp.hits.len(), 4);
+        let mut keys: Vec<DocKey> = resp.hits.iter().map(|h| h.key).collect();
+        let mut pages = 1;
+        while let Some(cursor) = resp.next_cursor.take() {
+            resp = handler.search(request(25).with_cursor(cursor));
+            keys.extend(resp.hits.iter().map(|h| h.key));
+            pages += 1;
+        }
+        assert_eq!(pages, 5);
+        assert_eq!(keys.len(), 25);
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), 25);
+    }
+
+    #[test]
+    fn streamed_search_sends_every_page_in_batches_then_an_end_frame() {
+        let metas = (1..=30)
+            .map(|i| name_meta(i, &format!("report-{i}.txt")))
+            .collect();
+        let (_dir, handler) = handler_with(metas, &[]);
+        let request = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Name),
+            value: "report".into(),
+            modifier: TermModifier::Term,
+        }))
+        .with_mode(SearchMode::NameOnly)
+        .with_limit(25);
+        let expected: Vec<DocKey> = handler
+            .search(request.clone())
+            .hits
+            .iter()
+            .map(|h| h.key)
+            .collect();
+        // Paged by the server in fours, batched in threes.
+        let handler = handler.with_streaming(10, 4);
+        let stream = SearchStreamRequest {
+            request,
+            batch_size: 3,
+        };
+
+        let mut frames = Vec::new();
+        stream_search(&handler, stream.clone(), None, &mut |frame| {
+            frames.push(frame);
+            true
+        });
+        let Some(SearchStreamFrame::End(end)) = frames.pop() else {
+            panic!("stream must finish with an end frame");
+        };
+        assert!(end.hits.is_empty() && end.next_cursor.is_none());
+        assert_eq!(end.total, 30);
+        let mut keys = Vec::new();
+        for (i, frame) in frames.iter().enumerate() {
+            let SearchStreamFrame::Hits(batch) = frame else {
+                panic!("only the last frame ends the stream");
+            };
+            assert_eq!(batch.seq, i as u32);
+            assert!(!batch.hits.is_empty() && batch.hits.len() <= 3);
+            keys.extend(batch.hits.iter().map(|h| h.key));
+        }
+        assert_eq!(keys, expected);
+
+        // A receiver that goes away stops the stream.
+        let mut sent = 0;
+        stream_search(&handler, stream, None, &mut |_| {
+            sent += 1;
+            false
+        });
+        assert_eq!(sent, 1);
+    }
+
+    /// Waits for its search to be cancelled, noting that it saw the flag.
+    #[derive(Default)]
+    struct SlowSearch {
+        observed: AtomicBool,
+    }
+
+    impl SearchHandler for SlowSearch {
+        fn search(&self, req: SearchRequest) -> SearchResponse {
+            let deadline = Instant::now() + std::time::Duration::from_secs(10);
+            while !search_cancelled() && Instant::now() < deadline {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            if !search_cancelled() {
+                return StubSearchHandler.search(req);
+            }
+            self.observed.store(true, Ordering::Relaxed);
+            cancelled_response(req)
+        }
+    }
+
+    #[test]
+    fn cancel_request_stops_a_running_search() {
+        let query = QueryExpr::Term(TermExpr {
+            field: None,
+            value: "report".into(),
+            modifier: TermModifier::Term,
+        });
+        let req = SearchRequest {
+            id: Uuid::new_v4(),
+            ..SearchRequest::with_query(query.clone())
+        }
+        .with_cancel_token();
+        let token = req.id;
+        assert!(!cancel_search(token), "not started yet");
+
+        let slow = SlowSearch::default();
+        let resp = std::thread::scope(|s| {
+            let search = s.spawn(|| answer_search(&slow, req, None));
+            while !cancel_search(token) {
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            search.join().unwrap()
+        });
+        assert!(slow.observed.load(Ordering::Relaxed));
+        assert!(resp.cancelled && resp.truncated && resp.hits.is_empty());
+        assert_eq!(resp.id, token);
+        assert!(!cancel_search(token), "finished searches are dropped");
+
+        // The real handler checks the flag before scoring anything.
+        let (_dir, handler) = handler_with(vec![name_meta(1, "report.txt")], &[]);
+        let req = SearchRequest::with_query(query).with_mode(SearchMode::NameOnly);
+        let running = handler.search_cancellable(req.clone(), Arc::default());
+        assert!(!running.cancelled && running.hits.len() == 1);
+        let stopped = handler.search_cancellable(req, Arc::new(AtomicBool::new(true)));
+        assert!(stopped.cancelled && stopped.hits.is_empty());
+    }
+
+    #[test]
+    fn tag_query_returns_only_tagged_files() {
+        let (dir, handler) = handler_with(
+            vec![
+                name_meta(1, "plan.txt"),
+                name_meta(2, "budget.xlsx"),
+                name_meta(3, "notes.txt"),
+            ],
+            &[],
+        );
+        let store = Arc::new(TagStore::open(&dir.path().join("tags.rkyv")).unwrap());
+        store
+            .update(DocKey::from_parts(1, 1), &["Important".into()], &[])
+            .unwrap();
+        store
+            .update(
+                DocKey::from_parts(1, 2),
+                &["important".into(), "q3".into()],
+                &[],
+            )
+            .unwrap();
+        store
+            .update(DocKey::from_parts(1, 3), &["archive".into()], &[])
+            .unwrap();
+        let handler = handler.with_tags(store);
+        let tag = |value: &str| {
+            QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Tag),
+                value: value.into(),
+                modifier: TermModifier::Term,
+            })
+        };
+
+        let resp = handler
+            .search(SearchRequest::with_query(tag("important")).with_mode(SearchMode::NameOnly));
+        let mut hits: Vec<_> = resp.hits.iter().map(|h| (h.key, h.tags.clone())).collect();
+        hits.sort();
+        assert_eq!(
+            hits,
+            vec![
+                (DocKey::from_parts(1, 1), vec!["important".to_string()]),
+                (
+                    DocKey::from_parts(1, 2),
+                    vec!["important".to_string(), "q3".to_string()]
+                ),
+            ]
+        );
+
+        let resp = handler.search(
+            SearchRequest::with_query(QueryExpr::And(vec![tag("important"), tag("Q3")]))
+                .with_mode(SearchMode::NameOnly),
+        );
+        assert_eq!(resp.hits.len(), 1);
+        assert_eq!(resp.hits[0].key, DocKey::from_parts(1, 2));
+
+        let resp = handler
+            .search(SearchRequest::with_query(tag("missing")).with_mode(SearchMode::NameOnly));
+        assert!(resp.hits.is_empty());
+    }
+
+    #[test]
+    fn former_names_find_renamed_files_when_enabled() {
+        let (dir, handler) = handler_with(
+            vec![name_meta(1, "final.txt"), name_meta(2, "draft notes.txt")],
+            &[],
+        );
+        let store =
+            Arc::new(FormerNameStore::open(&dir.path().join("former_names.rkyv"), 4).unwrap());
+        let key = DocKey::from_parts(1, 1);
+        store
+            .record_rename(key, key, "draft.txt", "final.txt")
+            .unwrap();
+        let search = |handler: &UnifiedSearchHandler, value: &str| {
+            let mut hits: Vec<_> = handler
+                .search(
+                    SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                        field: None,
+                        value: value.into(),
+                        modifier: TermModifier::Term,
+                    }))
+                    .with_mode(SearchMode::NameOnly),
+                )
+                .hits
+                .into_iter()
+                .map(|h| (h.key, h.matched_former_name))
+                .collect();
+            hits.sort();
+            hits
+        };
+
+        assert_eq!(
+            search(&handler, "draft"),
+            vec![(DocKey::from_parts(1, 2), false)]
+        );
+
+        let handler = handler.with_former_names(store);
+        assert_eq!(
+            search(&handler, "draft"),
+            vec![(key, true), (DocKey::from_parts(1, 2), false)]
+        );
+        // The current name still matches without the flag.
+        assert_eq!(search(&handler, "final"), vec![(key, false)]);
+    }
+
+    #[test]
+    fn owner_field_filters_to_that_owners_files() {
+        let alice = "S-1-5-21-1004336348-1177238915-682003330-1001";
+        let owned = |file_id, name, owner: Option<&str>| FileMeta {
+            owner: owner.map(Into::into),
+            ..name_meta(file_id, name)
+        };
+        let (_dir, handler) = handler_with(
+            vec![
+                owned(1, "alice-notes.txt", Some(alice)),
+                owned(2, "system.log", Some("S-1-5-18")),
+                owned(3, "unknown.txt", None),
+                owned(4, "alice-plan.txt", Some(alice)),
+            ],
+            &[],
+        );
+
+        let resp = handler.search(
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Owner),
+                value: alice.to_ascii_lowercase(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::NameOnly),
+        );
+
+        let mut keys: Vec<_> = resp.hits.iter().map(|h| h.key).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![DocKey::from_parts(1, 1), DocKey::from_parts(1, 4)]
+        );
+    }
+
+    #[test]
+    fn hybrid_without_content_index_degrades_to_name_only() {
+        let (_dir, handler) = handler_with(vec![name_meta(1, "report.txt")], &[]);
+        let request = |mode| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: None,
+                value: "report".into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(mode)
+        };
+
+        let hybrid = handler.search(request(SearchMode::Hybrid));
+        assert_eq!(hybrid.degraded_to, Some(SearchMode::NameOnly));
+        assert_eq!(hybrid.hits.len(), 1);
+        assert_eq!(hybrid.hits[0].key, DocKey::from_parts(1, 1));
+
+        let content = handler.search(request(SearchMode::Content));
+        assert!(content.hits.is_empty());
+        assert_eq!(content.total, 0);
+        assert_eq!(content.served_by, None);
+
+        let name_only = handler.search(request(SearchMode::NameOnly));
+        assert_eq!(name_only.degraded_to, None);
+
+        // Once the content index has documents, hybrid runs as requested.
+        let (_dir, handler) = handler_with(vec![name_meta(1, "report.txt")], &[content_doc(2, 0)]);
+        assert_eq!(
+            handler.search(request(SearchMode::Hybrid)).degraded_to,
+            None
+        );
+    }
+
+    #[test]
+    fn anytime_sends_name_matches_before_the_scored_result() {
+        let (_dir, handler) =
+            handler_with(vec![name_meta(11, "revenue.txt")], &[content_doc(2, 0)]);
+        // Name hits whose content was trimmed are handed to the hook once hybrid scoring has
+        // finished.
+        crate::index_budget::record_trimmed(&[DocKey::from_parts(1, 11)]);
+        let enqueued = Arc::new(Mutex::new(Vec::new()));
+        let sink = enqueued.clone();
+        let handler = handler.with_rehydration(Arc::new(move |hit: &SearchHit, _| {
+            sink.lock().unwrap().push(hit.key);
+            true
+        }));
+        let request = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: None,
+            value: "revenue".into(),
+            modifier: TermModifier::Term,
+        }))
+        .with_mode(SearchMode::Hybrid)
+        .with_anytime();
+        let keys = |resp: &SearchResponse| resp.hits.iter().map(|h| h.key).collect::<Vec<_>>();
+
+        let mut batches = Vec::new();
+        handler.search_anytime(request.clone(), &mut |resp| {
+            batches.push((resp, enqueued.lock().unwrap().len()));
+            true
+        });
+        assert_eq!(batches.len(), 2);
+        let (first, scored_before_first) = &batches[0];
+        assert_eq!(*scored_before_first, 0, "sent before content scoring");
+        assert!(first.refining && first.approximate);
+        assert_eq!(keys(first), vec![DocKey::from_parts(1, 11)]);
+        let (last, enqueued_by_last) = &batches[1];
+        assert_eq!(*enqueued_by_last, 1);
+        assert!(!last.refining);
+        assert!(keys(last).contains(&DocKey::from_parts(1, 2)));
+        assert_eq!(keys(last), keys(&handler.search(request.clone())));
+        crate::index_budget::forget_trimmed(DocKey::from_parts(1, 11));
+
+        // A client that leaves after the first batch doesn't wait for scoring.
+        let (_dir, handler) = handler_with(vec![name_meta(1, "revenue.txt")], &[content_doc(2, 0)]);
+        let mut sent = 0;
+        handler.search_anytime(request, &mut |_| {
+            sent += 1;
+            false
+        });
+        assert_eq!(sent, 1);
+    }
+
+    #[test]
+    fn refine_intersects_with_previous_result_set() {
+        let (_dir, handler) = handler_with(
+            vec![
+                name_meta(1, "report 2023 q1.txt"),
+                name_meta(2, "report 2024 q1.txt"),
+                name_meta(3, "budget 2023 q1.txt"),
+            ],
+            &[],
+        );
+        let term = |value: &str| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: None,
+                value: value.into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::NameOnly)
+        };
+        let sorted_keys = |resp: SearchResponse| {
+            let mut keys: Vec<_> = resp.hits.iter().map(|h| h.key).collect();
+            keys.sort();
+            keys
+        };
+
+        let first = sorted_keys(handler.search(term("report")));
+        assert_eq!(
+            first,
+            vec![DocKey::from_parts(1, 1), DocKey::from_parts(1, 2)]
+        );
+
+        let refined = handler.search(term("2023").within(first));
+        assert_eq!(refined.total, 1);
+        assert_eq!(sorted_keys(refined), vec![DocKey::from_parts(1, 1)]);
+        // Unrefined, the second term also matches the budget file.
+        assert_eq!(sorted_keys(handler.search(term("2023"))).len(), 2);
+    }
+
+    #[test]
+    fn total_bytes_cover_every_match_regardless_of_limit() {
+        let sized = |file_id: u64, path: &str, size: u64, flags: FileFlags| {
+            let name = path.rsplit('\\').next().unwrap();
+            FileMeta::new(
+                DocKey::from_parts(1, file_id),
+                1,
+                None,
+                name.into(),
+                Some(path.into()),
+                size,
+                0,
+                0,
+                flags,
+            )
+        };
+        let (_dir, handler) = handler_with(
+            vec![
+                sized(1, r"C:\a\report.txt", 100, FileFlags::empty()),
+                sized(2, r"C:\b\report.pdf", 250, FileFlags::empty()),
+                sized(3, r"C:\c\report.md", 4_000, FileFlags::empty()),
+                sized(4, r"C:\d\report", 4_096, FileFlags::IS_DIR),
+                sized(5, r"C:\d\report\q1.csv", 900, FileFlags::empty()),
+                sized(6, r"C:\d\report\deep\q2.csv", 50, FileFlags::empty()),
+                sized(7, r"C:\d\report\report.txt", 10, FileFlags::empty()),
+                sized(8, r"C:\e\notes.txt", 7, FileFlags::empty()),
+            ],
+            &[],
+        );
+        let request = |limit: u32| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: "report".into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::NameOnly)
+            .with_limit(limit)
+            .with_total_bytes()
+        };
+
+        // Matching files only; the directory's own size is left out.
+        let files = 100 + 250 + 4_000 + 10;
+        for limit in [1, 2, 10] {
+            let resp = handler.search(request(limit));
+            assert_eq!(resp.total, 5);
+            assert_eq!(resp.total_bytes_matched, Some(files), "limit {limit}");
+        }
+        assert_eq!(
+            handler
+                .search(request(10).within(vec![DocKey::from_parts(1, 1)]))
+                .total_bytes_matched,
+            Some(100)
+        );
+        let mut unasked = request(10);
+        unasked.total_bytes = false;
+        assert_eq!(handler.search(unasked).total_bytes_matched, None);
+
+        // Directory contents at any depth, with its matching `report.txt` counted once.
+        let handler = handler.with_total_bytes_directories(DirectoryBytes::Contents);
+        for limit in [1, 10] {
+            assert_eq!(
+                handler.search(request(limit)).total_bytes_matched,
+                Some(files + 900 + 50)
+            );
+        }
+    }
+
+    #[test]
+    fn context_path_ranks_project_files_first() {
+        let at = |file_id: u64, path: &str| {
+            let mut meta = name_meta(file_id, "main.rs");
+            meta.path = Some(path.into());
+            meta
+        };
+        let (_dir, handler) = handler_with(
+            vec![
+                at(1, r"C:\Users\me\Downloads\main.rs"),
+                at(2, r"C:\src\app\main.rs"),
+                at(3, r"C:\src\tool\main.rs"),
+            ],
+            &[],
+        );
+        let request = |context: &str| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Name),
+                value: "main".into(),
+                modifier: TermModifier::Term,
+            }))
+            .with_mode(SearchMode::NameOnly)
+            .with_limit(1)
+            .with_context_path(context)
+        };
+
+        let top = |context: &str| handler.search(request(context)).hits[0].key;
+        assert_eq!(top(r"C:\src\app"), DocKey::from_parts(1, 2));
+        assert_eq!(top(r"C:\src\tool\tests"), DocKey::from_parts(1, 3));
+        assert_eq!(top(r"C:\Users\me"), DocKey::from_parts(1, 1));
+    }
+
+    #[test]
+    fn fuzzy_transposition_costs_one_only_in_damerau_mode() {
+        let (_dir, handler) = handler_with(vec![name_meta(1, "the")], &[]);
+
+        // Plain Levenshtein: "teh" -> "the" is two substitutions.
+        assert_eq!(handler.search(fuzzy_name_request("teh", 1)).total, 0);
+        assert_eq!(handler.search(fuzzy_name_request("teh", 2)).total, 1);
+
+        // Damerau-Levenshtein: the swap is a single edit.
+        let handler = handler.with_fuzzy_transpositions(true);
+        assert_eq!(handler.search(fuzzy_name_request("teh", 1)).total, 1);
+    }
+
+    fn path_meta(file_id: u64, path: &str) -> FileMeta {
+        let name = path.rsplit('\\').next().unwrap().to_string();
+        FileMeta::new(
+            DocKey::from_parts(1, file_id),
+            1,
+            None,
+            name,
+            Some(path.into()),
+            1,
+            0,
+            0,
+            FileFlags::empty(),
+        )
+    }
+
+    #[test]
+    fn component_query_matches_ancestor_directory_at_any_depth() {
+        let metas = vec![
+            path_meta(1, r"C:\invoices\a.pdf"),
+            path_meta(2, r"C:\clients\acme\Invoices\2024\q1\b.pdf"),
+            path_meta(3, r"C:\docs\invoices.pdf"),
+            path_meta(4, r"C:\my invoices\c.pdf"),
+            path_meta(5, r"C:\other\d.pdf"),
+        ];
+        let (_dir, handler) = handler_with(metas, &[]);
+        let component = |value: &str| {
+            SearchRequest::with_query(QueryExpr::Term(TermExpr {
+                field: Some(FieldKind::Path),
+                value: value.into(),
+                modifier: TermModifier::Component,
+            }))
+            .with_mode(SearchMode::NameOnly)
+        };
+
+        let resp = handler.search(component("invoices"));
+        let mut keys: Vec<_> = resp.hits.iter().map(|h| h.key).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![DocKey::from_parts(1, 1), DocKey::from_parts(1, 2)]
+        );
+
+        let resp = handler.search(component("acme/invoices"));
+        assert_eq!(resp.total, 1);
+        assert_eq!(resp.hits[0].key, DocKey::from_parts(1, 2));
+    }
+
+    #[test]
+    fn group_by_folder_orders_groups_by_best_hit() {
+        let hit = |file_id: u64, path: &str, score: f32| SearchHit {
+            path: Some(path.into()),
+            ..stub_hit(file_id, score)
+        };
+        let hits = vec![
+            hit(1, r"C:\a\one.txt", 9.0),
+            hit(2, r"C:\b\two.txt", 7.0),
+            hit(3, r"C:\a\three.txt", 5.0),
+            hit(4, r"C:\c\four.txt", 8.0),
+            hit(5, r"C:\b\five.txt", 7.5),
+        ];
+
+        let groups = group_by_folder(&hits);
+        let folders: Vec<&str> = groups.iter().map(|g| g.folder_path.as_str()).collect();
+        assert_eq!(folders, vec![r"C:\a", r"C:\c", r"C:\b"]);
+        let scores: Vec<Vec<f32>> = groups
+            .iter()
+            .map(|g| g.hits.iter().map(|h| h.score).collect())
+            .collect();
+        assert_eq!(scores, vec![vec![9.0, 5.0], vec![8.0], vec![7.5, 7.0]]);
+    }
+
+    #[test]
+    fn max_hits_per_dir_caps_a_crowded_folder() {
+        let mut metas: Vec<FileMeta> = (1..=10)
+            .map(|i| path_meta(i, &format!(r"C:\photos\img{i}.jpg")))
+            .collect();
+        metas.push(path_meta(11, r"C:\docs\img.jpg"));
+        metas.push(path_meta(12, r"C:\work\img.jpg"));
+        let (_dir, handler) = handler_with(metas, &[]);
+        let req = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Ext),
+            value: "jpg".into(),
+            modifier: TermModifier::Term,
+        }))
+        .with_mode(SearchMode::NameOnly)
+        .with_limit(5);
+
+        let folders = |resp: SearchResponse| -> Vec<String> {
+            resp.hits
+                .iter()
+                .map(|h| parent_folder(h.path.as_deref().unwrap()).to_string())
+                .collect()
+        };
+        let uncapped = folders(handler.search(req.clone()));
+        assert_eq!(uncapped.len(), 5);
+
+        let mut capped = folders(handler.search(req.with_max_hits_per_dir(2)));
+        assert_eq!(capped.len(), 4);
+        capped.sort();
+        assert_eq!(
+            capped,
+            vec![r"C:\docs", r"C:\photos", r"C:\photos", r"C:\work"]
+        );
+    }
+
+    #[test]
+    fn grouped_field_only_set_when_requested() {
+        let metas = vec![
+            path_meta(1, r"C:\x\report.txt"),
+            path_meta(2, r"C:\y\report.txt"),
+        ];
+        let (_dir, handler) = handler_with(metas, &[]);
+        let req = SearchRequest::with_query(QueryExpr::Term(TermExpr {
+            field: Some(FieldKind::Name),
+            value: "report".into(),
+            modifier: TermModifier::Term,
+        }))
+        .with_mode(SearchMode::NameOnly);
+
+        assert!(handler.search(req.clone()).grouped.is_none());
+        let grouped = handler
+            .search(req.with_group_by(GroupBy::Folder))
+            .grouped
+            .unwrap();
+        assert_eq!(grouped.len(), 2);
+    }
+}
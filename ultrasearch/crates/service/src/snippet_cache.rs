@@ -0,0 +1,188 @@
+//! Short-lived cache of rendered snippets keyed by `(DocKey, term_hash, fragment count)`.
+//!
+//! Paging through the same query re-renders the same snippets; this cache lets those requests
+//! skip the snippet generator. Entries expire after a short TTL (so re-extracted content shows
+//! up quickly) and the whole cache is dropped when the index epoch changes.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use core_types::DocKey;
+use parking_lot::Mutex;
+
+use crate::index_lock::index_epoch;
+
+pub const DEFAULT_SNIPPET_CACHE_TTL: Duration = Duration::from_secs(60);
+pub const DEFAULT_SNIPPET_CACHE_CAPACITY: usize = 4096;
+
+struct Entry {
+    snippets: Vec<String>,
+    inserted: Instant,
+}
+
+struct State {
+    epoch: u64,
+    entries: HashMap<(DocKey, u64, usize), Entry>,
+}
+
+pub struct SnippetCache {
+    ttl: Duration,
+    capacity: usize,
+    state: Mutex<State>,
+    epoch_source: fn() -> u64,
+    /// Number of snippets actually computed (cache misses).
+    generation: AtomicU64,
+}
+
+impl Default for SnippetCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SNIPPET_CACHE_TTL, DEFAULT_SNIPPET_CACHE_CAPACITY)
+    }
+}
+
+impl SnippetCache {
+    /// A zero `ttl` or `capacity` disables caching.
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            state: Mutex::new(State {
+                epoch: index_epoch(),
+                entries: HashMap::new(),
+            }),
+            epoch_source: index_epoch,
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Track a different epoch counter (tests use a local one instead of the global epoch).
+    pub fn with_epoch_source(mut self, source: fn() -> u64) -> Self {
+        self.state.get_mut().epoch = source();
+        self.epoch_source = source;
+        self
+    }
+
+    fn enabled(&self) -> bool {
+        !self.ttl.is_zero() && self.capacity > 0
+    }
+
+    /// Cached snippets (up to `count` fragments) for `key` under `terms`, or the result of
+    /// `compute` (which is cached).
+    pub fn get_or_compute(
+        &self,
+        key: DocKey,
+        terms: &[String],
+        count: usize,
+        compute: impl FnOnce() -> Vec<String>,
+    ) -> Vec<String> {
+        if !self.enabled() {
+            self.generation.fetch_add(1, Ordering::Relaxed);
+            return compute();
+        }
+        let cache_key = (key, term_hash(terms), count);
+        {
+            let state = self.lock_current();
+            if let Some(entry) = state.entries.get(&cache_key)
+                && entry.inserted.elapsed() < self.ttl
+            {
+                return entry.snippets.clone();
+            }
+        }
+
+        // Render outside the lock; a concurrent miss for the same key just computes twice.
+        self.generation.fetch_add(1, Ordering::Relaxed);
+        let snippets = compute();
+        let mut state = self.lock_current();
+        if state.entries.len() >= self.capacity {
+            self.evict(&mut state);
+        }
+        state.entries.insert(
+            cache_key,
+            Entry {
+                snippets: snippets.clone(),
+                inserted: Instant::now(),
+            },
+        );
+        snippets
+    }
+
+    /// Number of snippets computed so far; unchanged across cache hits.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    /// Lock the state, dropping every entry if the index epoch moved since it was filled.
+    fn lock_current(&self) -> parking_lot::MutexGuard<'_, State> {
+        let mut state = self.state.lock();
+        let epoch = (self.epoch_source)();
+        if state.epoch != epoch {
+            state.entries.clear();
+            state.epoch = epoch;
+        }
+        state
+    }
+
+    /// Drop expired entries; if still full, drop the oldest one.
+    fn evict(&self, state: &mut State) {
+        state.entries.retain(|_, e| e.inserted.elapsed() < self.ttl);
+        if state.entries.len() >= self.capacity
+            && let Some(oldest) = state
+                .entries
+                .iter()
+                .min_by_key(|(_, e)| e.inserted)
+                .map(|(k, _)| *k)
+        {
+            state.entries.remove(&oldest);
+        }
+    }
+}
+
+/// Hash of the query terms, independent of order and duplicates.
+pub fn term_hash(terms: &[String]) -> u64 {
+    let mut sorted: Vec<&str> = terms.iter().map(String::as_str).collect();
+    sorted.sort_unstable();
+    sorted.dedup();
+    let mut hasher = DefaultHasher::new();
+    sorted.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static TEST_EPOCH: AtomicU64 = AtomicU64::new(0);
+
+    fn terms(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn hits_until_ttl_or_epoch_change() {
+        let cache = SnippetCache::new(Duration::from_millis(50), 8)
+            .with_epoch_source(|| TEST_EPOCH.load(Ordering::Relaxed));
+        let key = DocKey::from_parts(1, 1);
+        let render = || vec!["rendered".to_string()];
+
+        cache.get_or_compute(key, &terms(&["a", "b"]), 1, render);
+        cache.get_or_compute(key, &terms(&["b", "a", "a"]), 1, render);
+        assert_eq!(cache.generation(), 1);
+
+        cache.get_or_compute(key, &terms(&["c"]), 1, render);
+        assert_eq!(cache.generation(), 2);
+
+        // A different fragment count is a different rendering.
+        cache.get_or_compute(key, &terms(&["c"]), 3, render);
+        assert_eq!(cache.generation(), 3);
+
+        TEST_EPOCH.fetch_add(1, Ordering::Relaxed);
+        cache.get_or_compute(key, &terms(&["a", "b"]), 1, render);
+        assert_eq!(cache.generation(), 4);
+
+        std::thread::sleep(Duration::from_millis(60));
+        cache.get_or_compute(key, &terms(&["a", "b"]), 1, render);
+        assert_eq!(cache.generation(), 5);
+    }
+}
@@ -1,6 +1,48 @@
+use crate::metrics::global_metrics_snapshot;
+use crate::pipe_accept::consecutive_accept_failures;
+use crate::status_provider::status_snapshot;
 use ipc::{MetricsSnapshot, StatusResponse, VolumeStatus};
 use std::{env, time::SystemTime};
 
+/// The service's current status, as answered to a `StatusRequest` on any transport.
+pub fn current_status(id: uuid::Uuid) -> StatusResponse {
+    let snap = status_snapshot();
+    let metrics = snap.metrics.or(
+        global_metrics_snapshot(Some(0), Some(0), Some(0), Some(0)).or(Some(MetricsSnapshot {
+            search_latency_ms_p50: None,
+            search_latency_ms_p95: None,
+            worker_cpu_pct: None,
+            worker_mem_bytes: None,
+            queue_depth: Some(0),
+            active_workers: Some(0),
+            content_enqueued: Some(0),
+            content_dropped: Some(0),
+            pipe_accept_failures: None,
+            trimmed_content: None,
+            trimmed_meta: None,
+        })),
+    );
+    let metrics = metrics.map(|mut m| {
+        m.pipe_accept_failures = Some(consecutive_accept_failures());
+        let (content, meta) = crate::index_budget::trimmed_totals();
+        m.trimmed_content = Some(content);
+        m.trimmed_meta = Some(meta);
+        m
+    });
+    make_status_response(
+        id,
+        snap.volumes,
+        snap.scheduler_state,
+        metrics,
+        snap.last_index_commit_ts,
+        snap.content_jobs_total,
+        snap.content_jobs_remaining,
+        snap.content_bytes_total,
+        snap.content_bytes_remaining,
+        snap.eta_seconds,
+    )
+}
+
 /// Build a StatusResponse from provided fragments.
 ///
 /// This keeps server wiring centralized and ensures new fields are populated consistently.
@@ -15,6 +57,7 @@ pub fn make_status_response(
     content_jobs_remaining: Option<u64>,
     content_bytes_total: Option<u64>,
     content_bytes_remaining: Option<u64>,
+    eta_seconds: Option<u64>,
 ) -> StatusResponse {
     StatusResponse {
         id,
@@ -25,6 +68,7 @@ pub fn make_status_response(
         content_jobs_remaining,
         content_bytes_total,
         content_bytes_remaining,
+        eta_seconds,
         metrics,
         served_by: Some(host_label()),
     }
@@ -37,7 +81,7 @@ fn now_ts() -> Option<i64> {
         .map(|d| d.as_secs() as i64)
 }
 
-fn host_label() -> String {
+pub(crate) fn host_label() -> String {
     env::var("COMPUTERNAME")
         .or_else(|_| env::var("HOSTNAME"))
         .unwrap_or_else(|_| "service".into())
@@ -60,6 +104,7 @@ mod tests {
             None,
             None,
             None,
+            None,
         );
         assert!(resp.last_index_commit_ts.is_some());
         assert!(resp.served_by.is_some());
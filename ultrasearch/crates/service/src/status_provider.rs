@@ -1,6 +1,7 @@
 use crate::metrics::global_metrics_snapshot;
 use ipc::{MetricsSnapshot, VolumeStatus};
 use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Instant;
 
 /// Snapshot of service status used by IPC responses.
 #[derive(Debug, Clone, Default)]
@@ -13,6 +14,8 @@ pub struct StatusSnapshot {
     pub content_jobs_remaining: Option<u64>,
     pub content_bytes_total: Option<u64>,
     pub content_bytes_remaining: Option<u64>,
+    /// Estimated seconds until the content queue drains; `None` until throughput is known.
+    pub eta_seconds: Option<u64>,
 }
 
 pub trait StatusProvider: Send + Sync {
@@ -50,6 +53,7 @@ pub fn status_snapshot() -> StatusSnapshot {
         content_jobs_remaining: None,
         content_bytes_total: None,
         content_bytes_remaining: None,
+        eta_seconds: None,
     }
 }
 
@@ -119,6 +123,77 @@ pub fn update_content_remaining(queue_depth: u64, active_workers: u32) {
 pub struct BasicStatusProvider {
     state: RwLock<StatusSnapshot>,
     avg_content_job_bytes: RwLock<Option<u64>>,
+    eta: RwLock<EtaEstimator>,
+}
+
+/// Default `scheduler.eta_window_secs`.
+const DEFAULT_ETA_WINDOW_SECS: u64 = 300;
+
+/// Content-job completion rate, smoothed exponentially over `window_secs`, for
+/// [`StatusSnapshot::eta_seconds`].
+///
+/// Completions are inferred between samples: the previous remaining count plus the jobs
+/// enqueued since, minus what remains now.
+#[derive(Debug)]
+struct EtaEstimator {
+    window_secs: u64,
+    last: Option<(Instant, u64)>,
+    added: u64,
+    /// Jobs per second; `None` until two samples have been taken.
+    rate: Option<f64>,
+}
+
+impl Default for EtaEstimator {
+    fn default() -> Self {
+        Self::new(DEFAULT_ETA_WINDOW_SECS)
+    }
+}
+
+impl EtaEstimator {
+    fn new(window_secs: u64) -> Self {
+        Self {
+            window_secs,
+            last: None,
+            added: 0,
+            rate: None,
+        }
+    }
+
+    fn record_added(&mut self, jobs: u64) {
+        self.added = self.added.saturating_add(jobs);
+    }
+
+    /// Take a sample of `remaining` jobs at `now` and return the resulting ETA.
+    fn observe(&mut self, now: Instant, remaining: u64) -> Option<u64> {
+        if self.window_secs == 0 {
+            return None;
+        }
+        if let Some((at, previous)) = self.last {
+            let dt = now.saturating_duration_since(at).as_secs_f64();
+            if dt <= 0.0 {
+                return eta_seconds(remaining, self.rate);
+            }
+            let done = previous
+                .saturating_add(self.added)
+                .saturating_sub(remaining);
+            let sample = done as f64 / dt;
+            let alpha = 1.0 - (-dt / self.window_secs as f64).exp();
+            self.rate = Some(match self.rate {
+                Some(rate) => rate + alpha * (sample - rate),
+                None => sample,
+            });
+        }
+        self.last = Some((now, remaining));
+        self.added = 0;
+        eta_seconds(remaining, self.rate)
+    }
+}
+
+/// Seconds to finish `remaining` jobs at `rate` jobs per second, rounded up. `None` when the
+/// rate is unknown or zero.
+fn eta_seconds(remaining: u64, rate: Option<f64>) -> Option<u64> {
+    let rate = rate.filter(|r| r.is_finite() && *r > 0.0)?;
+    Some((remaining as f64 / rate).ceil() as u64)
 }
 
 impl BasicStatusProvider {
@@ -133,8 +208,18 @@ impl BasicStatusProvider {
                 content_jobs_remaining: None,
                 content_bytes_total: None,
                 content_bytes_remaining: None,
+                eta_seconds: None,
             }),
             avg_content_job_bytes: RwLock::new(None),
+            eta: RwLock::new(EtaEstimator::default()),
+        }
+    }
+
+    /// Smooth the completion rate over about `window_secs` (`scheduler.eta_window_secs`);
+    /// 0 stops reporting an ETA.
+    pub fn set_eta_window(&self, window_secs: u64) {
+        if let Ok(mut eta) = self.eta.write() {
+            *eta = EtaEstimator::new(window_secs);
         }
     }
 
@@ -173,6 +258,9 @@ impl BasicStatusProvider {
                 active_workers: None,
                 content_enqueued: None,
                 content_dropped: None,
+                pipe_accept_failures: None,
+                trimmed_content: None,
+                trimmed_meta: None,
             });
             snap.queue_depth = queue_depth;
             snap.active_workers = active_workers;
@@ -226,14 +314,23 @@ impl BasicStatusProvider {
         {
             *avg = Some(bytes / jobs.max(1));
         }
+        if let Ok(mut eta) = self.eta.write() {
+            eta.record_added(new_jobs);
+        }
     }
 
     pub fn update_content_remaining(&self, queue_depth: u64, active_workers: u32) {
         let remaining_jobs = queue_depth + active_workers as u64;
         let avg_bytes = self.avg_content_job_bytes.read().ok().and_then(|v| *v);
+        let eta = self
+            .eta
+            .write()
+            .ok()
+            .and_then(|mut eta| eta.observe(Instant::now(), remaining_jobs));
 
         if let Ok(mut guard) = self.state.write() {
             guard.content_jobs_remaining = Some(remaining_jobs);
+            guard.eta_seconds = eta;
             if let Some(avg) = avg_bytes {
                 guard.content_bytes_remaining = Some(remaining_jobs.saturating_mul(avg));
             }
@@ -263,6 +360,7 @@ impl StatusProvider for BasicStatusProvider {
                 content_jobs_remaining: None,
                 content_bytes_total: None,
                 content_bytes_remaining: None,
+                eta_seconds: None,
             })
     }
 }
@@ -293,4 +391,25 @@ mod tests {
         assert_eq!(metrics.queue_depth, Some(3));
         assert_eq!(metrics.active_workers, Some(1));
     }
+
+    #[test]
+    fn eta_follows_the_smoothed_completion_rate() {
+        assert_eq!(eta_seconds(120, Some(2.0)), Some(60));
+        assert_eq!(eta_seconds(5, Some(2.0)), Some(3));
+        assert_eq!(eta_seconds(120, Some(0.0)), None);
+        assert_eq!(eta_seconds(120, None), None);
+
+        let start = Instant::now();
+        let secs = |s| start + std::time::Duration::from_secs(s);
+        let mut eta = EtaEstimator::new(300);
+        assert_eq!(eta.observe(start, 100), None, "throughput unknown at first");
+        // 30 done in 10s while 10 more arrived: 3 jobs/s, 80 left.
+        eta.record_added(10);
+        assert_eq!(eta.observe(secs(10), 80), Some(27));
+
+        let mut stalled = EtaEstimator::new(300);
+        stalled.observe(start, 100);
+        assert_eq!(stalled.observe(secs(10), 100), None);
+        assert_eq!(EtaEstimator::new(0).observe(start, 100), None);
+    }
 }
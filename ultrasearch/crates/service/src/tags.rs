@@ -0,0 +1,45 @@
+//! Process-wide user tag store (`paths.tags_file`), shared by the IPC `TagRequest` handler
+//! and the search handler's `tag:` queries.
+
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use ipc::{TagRequest, TagResponse};
+use meta_index::tags::TagStore;
+
+static TAG_STORE: OnceLock<Arc<TagStore>> = OnceLock::new();
+
+/// Open the store at `path` and install it globally; later calls return the installed one.
+pub fn init_tag_store(path: &Path) -> anyhow::Result<Arc<TagStore>> {
+    if let Some(store) = TAG_STORE.get() {
+        return Ok(store.clone());
+    }
+    let store = Arc::new(TagStore::open(path)?);
+    Ok(TAG_STORE.get_or_init(|| store).clone())
+}
+
+pub fn tag_store() -> Option<Arc<TagStore>> {
+    TAG_STORE.get().cloned()
+}
+
+/// Apply `req` to `store` and report the document's resulting tags.
+pub fn apply_tag_request(store: Option<&TagStore>, req: &TagRequest) -> TagResponse {
+    let result = match store {
+        Some(store) => store.update(req.key, &req.add, &req.remove),
+        None => Err(anyhow::anyhow!("tag store not initialized")),
+    };
+    match result {
+        Ok(tags) => TagResponse {
+            id: req.id,
+            success: true,
+            tags,
+            message: None,
+        },
+        Err(e) => TagResponse {
+            id: req.id,
+            success: false,
+            tags: Vec::new(),
+            message: Some(format!("{e:#}")),
+        },
+    }
+}
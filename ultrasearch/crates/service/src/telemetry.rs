@@ -0,0 +1,144 @@
+//! OpenTelemetry export (`logging.otlp_endpoint`, `otel` feature).
+//!
+//! When an endpoint is configured, [`otlp_layer`] adds a `tracing` layer that turns spans
+//! (search, ingest, extraction, ...) into OpenTelemetry spans and exports them over OTLP/HTTP
+//! (JSON encoding) in batches. `logging.otlp_sample_ratio` bounds the overhead: only that
+//! fraction of root traces is recorded, and child spans follow their root's decision.
+//! Builds without the feature ignore the endpoint with a warning.
+
+use anyhow::Result;
+use core_types::config::LoggingSection;
+use tracing_subscriber::{Layer, Registry};
+
+pub(crate) type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+#[cfg(feature = "otel")]
+static PROVIDER: std::sync::OnceLock<opentelemetry_sdk::trace::SdkTracerProvider> =
+    std::sync::OnceLock::new();
+
+/// Layer exporting spans to `cfg.otlp_endpoint`; `None` when no endpoint is set.
+#[cfg(feature = "otel")]
+pub(crate) fn otlp_layer(cfg: &LoggingSection) -> Result<Option<BoxedLayer>> {
+    use opentelemetry::trace::TracerProvider as _;
+
+    let Some(endpoint) = cfg.otlp_endpoint.as_deref().filter(|e| !e.is_empty()) else {
+        return Ok(None);
+    };
+    let provider = tracer_provider(endpoint, cfg.otlp_sample_ratio)?;
+    let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("ultrasearch"));
+    // A second init (tests) keeps exporting through the first provider.
+    let _ = PROVIDER.set(provider);
+    Ok(Some(Box::new(layer)))
+}
+
+#[cfg(not(feature = "otel"))]
+pub(crate) fn otlp_layer(cfg: &LoggingSection) -> Result<Option<BoxedLayer>> {
+    if cfg.otlp_endpoint.as_deref().is_some_and(|e| !e.is_empty()) {
+        eprintln!("logging.otlp_endpoint is set but this build lacks the `otel` feature");
+    }
+    Ok(None)
+}
+
+/// Batch-exporting provider for `endpoint`, sampling `ratio` of root traces.
+#[cfg(feature = "otel")]
+pub(crate) fn tracer_provider(
+    endpoint: &str,
+    ratio: f64,
+) -> Result<opentelemetry_sdk::trace::SdkTracerProvider> {
+    use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_protocol(Protocol::HttpJson)
+        .with_endpoint(endpoint)
+        .build()?;
+    let sampler = Sampler::TraceIdRatioBased(ratio.clamp(0.0, 1.0));
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(sampler)))
+        .with_resource(Resource::builder().with_service_name("ultrasearch").build())
+        .build())
+}
+
+/// Export any spans still buffered; call before the process exits.
+pub fn shutdown_telemetry() {
+    #[cfg(feature = "otel")]
+    if let Some(provider) = PROVIDER.get()
+        && let Err(e) = provider.shutdown()
+    {
+        eprintln!("OpenTelemetry shutdown failed: {e}");
+    }
+}
+
+#[cfg(all(test, feature = "otel"))]
+mod tests {
+    use super::*;
+    use opentelemetry::trace::TracerProvider as _;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    /// Accepts one OTLP/HTTP request, answers 200, and sends its JSON body back.
+    fn mock_collector() -> (String, mpsc::Receiver<serde_json::Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/v1/traces", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut len = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    len = value.trim().parse().unwrap();
+                }
+            }
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+            let mut stream = stream;
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+            tx.send(serde_json::from_slice(&body).unwrap()).unwrap();
+        });
+        (endpoint, rx)
+    }
+
+    #[test]
+    fn spans_are_exported_to_the_collector() {
+        let (endpoint, rx) = mock_collector();
+        let provider = tracer_provider(&endpoint, 1.0).unwrap();
+        let layer = tracing_opentelemetry::layer().with_tracer(provider.tracer("test"));
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("search", query = "report", hits = 3u64);
+            span.in_scope(|| {});
+        });
+        provider.force_flush().unwrap();
+
+        let body = rx.recv_timeout(std::time::Duration::from_secs(10)).unwrap();
+        let span = &body["resourceSpans"][0]["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["name"], "search");
+        let attribute = |key: &str| {
+            span["attributes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|a| a["key"] == key)
+                .map(|a| a["value"].clone())
+        };
+        assert_eq!(attribute("query").unwrap()["stringValue"], "report");
+        assert!(attribute("hits").is_some());
+        provider.shutdown().unwrap();
+    }
+}
@@ -5,6 +5,10 @@ use ipc::{
 };
 #[cfg(windows)]
 use std::sync::Arc;
+use uuid::Uuid;
+
+#[cfg(windows)]
+use ipc::CancelRequest;
 
 #[cfg(windows)]
 use ipc::client::PipeClient;
@@ -48,10 +52,37 @@ impl IpcClient {
                 truncated: false,
                 took_ms: 0,
                 served_by: Some("ui-stub".into()),
+                grouped: None,
+                degraded_to: None,
+                next_cursor: None,
+                approximate: false,
+                total_bytes_matched: None,
+                refining: false,
+                executed_query: None,
+                error: None,
+                result_set_id: None,
+                cancelled: false,
             })
         }
     }
 
+    /// Stop the search sent with `cancel_token` `token`; whether it was still running.
+    pub async fn cancel(&self, token: Uuid) -> Result<bool> {
+        #[cfg(windows)]
+        {
+            let req = CancelRequest {
+                id: Uuid::new_v4(),
+                token,
+            };
+            Ok(self.inner.cancel(req).await?.cancelled)
+        }
+        #[cfg(not(windows))]
+        {
+            let _ = token;
+            Ok(false)
+        }
+    }
+
     pub async fn status(&self, req: StatusRequest) -> Result<StatusResponse> {
         #[cfg(windows)]
         {
@@ -68,6 +99,7 @@ impl IpcClient {
                 content_jobs_remaining: Some(0),
                 content_bytes_total: Some(0),
                 content_bytes_remaining: Some(0),
+                eta_seconds: None,
                 metrics: None,
                 served_by: Some("ui-stub".into()),
             })
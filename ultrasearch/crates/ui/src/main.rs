@@ -4,6 +4,7 @@
 //! search with deep content indexing, wrapped in a beautiful native UI.
 // Background: UI layer powered by GPUI and custom models.
 
+use core_types::config::keymap::KeyBindingEntry;
 use gpui::prelude::*;
 use gpui::{App, AppContext, AsyncApp, KeyBinding, *};
 use ui::actions::{CloseShortcuts, ToggleShortcuts};
@@ -34,7 +35,7 @@ struct UltraSearchWindow {
 }
 
 impl UltraSearchWindow {
-    fn new(cx: &mut Context<Self>, show_onboarding: bool) -> Self {
+    fn new(cx: &mut Context<Self>, show_onboarding: bool, keymap: Vec<KeyBindingEntry>) -> Self {
         let model = cx.new(SearchAppModel::new);
 
         // Update model with onboarding state
@@ -47,7 +48,7 @@ impl UltraSearchWindow {
         let preview_view = cx.new(|cx| PreviewView::new(model.clone(), cx));
         let onboarding_view = cx.new(|cx| OnboardingView::new(model.clone(), cx));
         let update_panel = cx.new(|cx| UpdatePanel::new(model.clone(), cx));
-        let help_panel = cx.new(HelpPanel::new);
+        let help_panel = cx.new(|cx| HelpPanel::new(keymap, cx));
 
         let focus_handle = cx.focus_handle();
 
@@ -492,6 +493,30 @@ impl Render for UltraSearchWindow {
     }
 }
 
+/// GPUI binding for a keymap action name; `None` for names this build does not know.
+fn key_binding(action: &str, keystroke: &str) -> Option<KeyBinding> {
+    let binding = match action {
+        "FocusSearch" => KeyBinding::new(keystroke, FocusSearch, None),
+        "SubmitSearch" => KeyBinding::new(keystroke, SubmitSearch, None),
+        "ClearSearch" => KeyBinding::new(keystroke, ClearSearch, None),
+        "SelectNext" => KeyBinding::new(keystroke, SelectNext, None),
+        "SelectPrev" => KeyBinding::new(keystroke, SelectPrev, None),
+        "ModeMetadata" => KeyBinding::new(keystroke, ModeMetadata, None),
+        "ModeMixed" => KeyBinding::new(keystroke, ModeMixed, None),
+        "ModeContent" => KeyBinding::new(keystroke, ModeContent, None),
+        "OpenSelected" => KeyBinding::new(keystroke, OpenSelected, None),
+        "CopySelectedPath" => KeyBinding::new(keystroke, CopySelectedPath, None),
+        "CopySelectedFile" => KeyBinding::new(keystroke, CopySelectedFile, None),
+        "OpenContainingFolder" => KeyBinding::new(keystroke, OpenContainingFolder, None),
+        "ShowProperties" => KeyBinding::new(keystroke, ShowProperties, None),
+        "ToggleShortcuts" => KeyBinding::new(keystroke, ToggleShortcuts, None),
+        "MinimizeToTray" => KeyBinding::new(keystroke, MinimizeToTray, None),
+        "QuitApp" => KeyBinding::new(keystroke, QuitApp, None),
+        _ => return None,
+    };
+    Some(binding)
+}
+
 fn main() {
     // Provide a Tokio runtime so async tasks in the UI (status/search polling) have a reactor.
     let runtime = tokio::runtime::Builder::new_multi_thread()
@@ -510,6 +535,10 @@ fn main() {
     if config.is_none() {
         eprintln!("Failed to load configuration, proceeding with defaults (and onboarding).");
     }
+    let keymap = config
+        .as_ref()
+        .map(|c| c.keymap.resolve())
+        .unwrap_or_else(|| core_types::config::KeymapConfig::default().resolve());
 
     // Start Background Tasks (Tray + Hotkeys)
     let bg_rx = match ui::background::spawn() {
@@ -673,33 +702,12 @@ fn main() {
             })
             .detach();
         }
-        cx.bind_keys([
-            KeyBinding::new("cmd-k", FocusSearch, None),
-            KeyBinding::new("ctrl-k", FocusSearch, None),
-            KeyBinding::new("f1", ToggleShortcuts, None),
-            KeyBinding::new("ctrl-/", ToggleShortcuts, None),
-            KeyBinding::new("cmd-/", ToggleShortcuts, None),
-            KeyBinding::new("escape", ClearSearch, None),
-            KeyBinding::new("enter", SubmitSearch, None),
-            KeyBinding::new("down", SelectNext, None),
-            KeyBinding::new("up", SelectPrev, None),
-            KeyBinding::new("cmd-1", ModeMetadata, None),
-            KeyBinding::new("ctrl-1", ModeMetadata, None),
-            KeyBinding::new("cmd-2", ModeMixed, None),
-            KeyBinding::new("ctrl-2", ModeMixed, None),
-            KeyBinding::new("cmd-3", ModeContent, None),
-            KeyBinding::new("ctrl-3", ModeContent, None),
-            KeyBinding::new("cmd-o", OpenSelected, None),
-            KeyBinding::new("ctrl-o", OpenSelected, None),
-            KeyBinding::new("cmd-c", CopySelectedPath, None),
-            KeyBinding::new("ctrl-c", CopySelectedPath, None),
-            KeyBinding::new("ctrl-shift-c", CopySelectedFile, None),
-            KeyBinding::new("alt-enter", ShowProperties, None),
-            KeyBinding::new("ctrl-shift-o", OpenContainingFolder, None),
-            KeyBinding::new("cmd-q", QuitApp, None),
-            KeyBinding::new("ctrl-q", QuitApp, None),
-            KeyBinding::new("ctrl-m", MinimizeToTray, None),
-        ]);
+        cx.bind_keys(keymap.iter().flat_map(|entry| {
+            entry
+                .keys
+                .iter()
+                .filter_map(|key| key_binding(entry.action, key))
+        }));
 
         // Open the main window
         cx.open_window(
@@ -727,7 +735,7 @@ fn main() {
                 app_id: Some("com.ultrasearch.desktop".to_string()),
                 ..WindowOptions::default()
             },
-            move |_, cx| cx.new(|cx| UltraSearchWindow::new(cx, show_onboarding)),
+            move |_, cx| cx.new(|cx| UltraSearchWindow::new(cx, show_onboarding, keymap)),
         )
         .expect("Failed to open window");
 
@@ -105,6 +105,8 @@ pub struct SearchAppModel {
     pub ipc_recent_reconnect: bool,
     pub client: IpcClient,
     pub search_debounce: Option<Task<()>>,
+    /// Cancel token of the search last sent, until it answers.
+    pub search_token: Option<Uuid>,
     pub status_task: Option<Task<()>>,
     pub last_search: Option<Instant>,
     pub show_onboarding: bool,
@@ -129,6 +131,7 @@ impl SearchAppModel {
             ipc_recent_reconnect: false,
             client,
             search_debounce: None,
+            search_token: None,
             status_task: None,
             last_search: None,
             show_onboarding: false,
@@ -278,8 +281,9 @@ impl SearchAppModel {
                         return;
                     }
 
+                    let id = Uuid::new_v4();
                     let req = SearchRequest {
-                        id: Uuid::new_v4(),
+                        id,
                         query: QueryExpr::Term(TermExpr {
                             field: None,
                             value: query_clone.clone(),
@@ -289,18 +293,44 @@ impl SearchAppModel {
                         mode: mode.into(),
                         timeout: Some(Duration::from_secs(5)),
                         offset: 0,
+                        group_by: None,
+                        fields: None,
+                        refine: None,
+                        sort: None,
+                        snippets_per_hit: 1,
+                        cursor: None,
+                        total_bytes: false,
+                        context_path: None,
+                        extract_status: false,
+                        anytime: false,
+                        explain: false,
+                        deterministic_order: false,
+                        max_hits_per_dir: None,
+                        result_set: false,
+                        refine_set: None,
+                        cancel_token: Some(id),
+                        query_text: None,
                     };
 
                     let start = Instant::now();
-                    let _ = async_app.update(|app| {
-                        this.update(
-                            app,
-                            |model: &mut SearchAppModel, cx: &mut Context<SearchAppModel>| {
-                                model.status.in_flight = true;
-                                cx.notify();
-                            },
-                        )
-                    });
+                    let superseded = async_app
+                        .update(|app| {
+                            this.update(
+                                app,
+                                |model: &mut SearchAppModel, cx: &mut Context<SearchAppModel>| {
+                                    model.status.in_flight = true;
+                                    cx.notify();
+                                    model.search_token.replace(id)
+                                },
+                            )
+                        })
+                        .ok()
+                        .and_then(|r| r.ok())
+                        .flatten();
+                    if let Some(token) = superseded {
+                        // Dropping the older task stopped waiting for it, not the service.
+                        let _ = client.cancel(token).await;
+                    }
                     match client.search(req).await {
                         Ok(resp) => {
                             let latency = start.elapsed().as_millis() as u32;
@@ -310,6 +340,9 @@ impl SearchAppModel {
                                     |model: &mut SearchAppModel,
                                      cx: &mut Context<SearchAppModel>| {
                                         model.status.in_flight = false;
+                                        if model.search_token == Some(id) {
+                                            model.search_token = None;
+                                        }
                                        model.results = resp.hits;
                                        model.status.total = resp.total;
                                         model.page = 0;
@@ -331,6 +364,9 @@ impl SearchAppModel {
                                     |model: &mut SearchAppModel,
                                      cx: &mut Context<SearchAppModel>| {
                                         model.status.in_flight = false;
+                                        if model.search_token == Some(id) {
+                                            model.search_token = None;
+                                        }
                                         model.status.connected = false;
                                         model.status.indexing_state =
                                             "Disconnected (search)".to_string();
@@ -423,6 +459,11 @@ impl SearchAppModel {
                 size: Some(12_345 + i as u64 * 10),
                 modified: Some(1_700_000_000 + i as i64 * 60),
                 snippet: Some("Lorem ipsum dolor sit amet, consectetur adipiscing elit.".into()),
+                snippets: Vec::new(),
+                tags: Vec::new(),
+                extract_status: None,
+                archive: None,
+                matched_former_name: false,
             });
         }
         self.page = 0;
@@ -1,6 +1,7 @@
 use crate::actions::CloseShortcuts;
 use crate::theme;
 use chrono::{DateTime, Local};
+use core_types::config::keymap::{KeyBindingEntry, KEYMAP_GROUPS};
 use gpui::prelude::FluentBuilder;
 use gpui::*;
 use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
@@ -12,10 +13,12 @@ pub struct HelpPanel {
     filter: String,
     docs: Option<String>,
     docs_updated: Option<String>,
+    /// Resolved keymap; the shortcut sections are rendered from it.
+    keymap: Vec<KeyBindingEntry>,
 }
 
 impl HelpPanel {
-    pub fn new(cx: &mut Context<Self>) -> Self {
+    pub fn new(keymap: Vec<KeyBindingEntry>, cx: &mut Context<Self>) -> Self {
         let docs_path = "docs/FEATURES.md";
         let docs = std::fs::read_to_string(docs_path).ok();
         let docs_updated = std::fs::metadata(docs_path)
@@ -33,12 +36,13 @@ impl HelpPanel {
             filter: String::new(),
             docs,
             docs_updated,
+            keymap,
         }
     }
 
-    fn key_badge(label: &'static str, cx: &mut Context<Self>) -> Div {
+    fn key_badge(label: String, cx: &mut Context<Self>) -> Div {
         let colors = theme::active_colors(cx);
-        let text = label.to_string();
+        let text = label.clone();
         div()
             .px_2()
             .py_0p5()
@@ -59,16 +63,25 @@ impl HelpPanel {
             )
     }
 
+    /// Shortcut rows for one keymap group, as `(keys, description)` pairs.
+    fn group_items(keymap: &[KeyBindingEntry], group: &str) -> Vec<(String, String)> {
+        keymap
+            .iter()
+            .filter(|entry| entry.group == group && !entry.keys.is_empty())
+            .map(|entry| (entry.display_keys(), entry.description.to_string()))
+            .collect()
+    }
+
     fn section(
         title: &'static str,
-        items: &'static [(&'static str, &'static str)],
+        items: Vec<(String, String)>,
         filter: &str,
         cx: &mut Context<Self>,
     ) -> Option<Div> {
         let colors = theme::active_colors(cx);
         let needle = filter.trim().to_lowercase();
         let filtered: Vec<_> = items
-            .iter()
+            .into_iter()
             .filter(|(k, v)| {
                 if needle.is_empty() {
                     true
@@ -101,7 +114,7 @@ impl HelpPanel {
                         .gap_2()
                         .text_size(px(12.))
                         .text_color(colors.text_secondary)
-                        .child(div().child(*v))
+                        .child(div().child(v))
                         .child(Self::key_badge(k, cx))
                 })),
         )
@@ -256,43 +269,13 @@ impl Render for HelpPanel {
             div().flex().flex_col().gap_1().children(nodes)
         };
 
-        let sections: Vec<Div> = [
-            Self::section(
-                "Navigation",
-                &[
-                    ("Ctrl/Cmd+K", "Focus search"),
-                    ("Up / Down", "Move selection"),
-                    ("Enter", "Open selected"),
-                    ("Ctrl+1/2/3", "Switch modes"),
-                ],
-                &self.filter,
-                cx,
-            ),
-            Self::section(
-                "Actions",
-                &[
-                    ("Ctrl/Cmd+C", "Copy path"),
-                    ("Ctrl+Shift+C", "Copy file"),
-                    ("Ctrl+Shift+O", "Open folder"),
-                    ("Alt+Enter", "Properties"),
-                ],
-                &self.filter,
-                cx,
-            ),
-            Self::section(
-                "System",
-                &[
-                    ("F1 / Ctrl+/", "Toggle help"),
-                    ("Alt+Space", "Quick Search palette"),
-                    ("Ctrl/Cmd+Q", "Quit"),
-                ],
-                &self.filter,
-                cx,
-            ),
-        ]
-        .into_iter()
-        .flatten()
-        .collect();
+        let sections: Vec<Div> = KEYMAP_GROUPS
+            .iter()
+            .filter_map(|&group| {
+                let items = Self::group_items(&self.keymap, group);
+                Self::section(group, items, &self.filter, cx)
+            })
+            .collect();
 
         let no_results = sections.is_empty() && !self.filter.trim().is_empty();
 